@@ -33,6 +33,7 @@ const ENDPOINT_ARGUMENTS: &[&str] = &[
 	"rate_limit",
 	"with_response",
 	"returns_bytes",
+	"returns_body",
 ];
 
 struct EndpointRouter {
@@ -199,7 +200,7 @@ impl EndpointRouter {
 					mut request: &mut Request<Body>,
 					response: &mut http::response::Builder,
 					router_config: &mut api_helper::macro_util::__RouterConfig,
-				) -> rivet_operation::prelude::GlobalResult<Option<Vec<u8>>> {
+				) -> rivet_operation::prelude::GlobalResult<Option<hyper::Body>> {
 					use std::str::FromStr;
 					use api_helper::macro_util::{self, __AsyncOption};
 
@@ -267,7 +268,7 @@ impl EndpointRouter {
 					// Convert to hyper response
 					match res {
 						Ok(body) => {
-							Ok(response.body(hyper::Body::from(body))?)
+							Ok(response.body(body)?)
 						},
 						Err(err) => api_helper::error::handle_rejection(&config, err, response, ray_id),
 					}
@@ -832,7 +833,7 @@ impl EndpointFunction {
 		};
 
 		// Returns the bytes directly instead of serializing them with serde_json
-		let response_body = if let Some(returns_bytes) =
+		let response_bytes = if let Some(returns_bytes) =
 			self.args.iter().find(|arg| arg.label == "returns_bytes")
 		{
 			let value = returns_bytes.value.expect_expr()?;
@@ -853,6 +854,27 @@ impl EndpointFunction {
 			quote! { serde_json::to_vec(&body)? }
 		};
 
+		// Returns the body directly, used for streaming responses
+		let response_body =
+			if let Some(returns_body) = self.args.iter().find(|arg| arg.label == "returns_body") {
+				let value = returns_body.value.expect_expr()?;
+				if let syn::Expr::Lit(syn::ExprLit {
+					lit: syn::Lit::Bool(syn::LitBool { value, .. }),
+					..
+				}) = value
+				{
+					if *value {
+						quote! { body }
+					} else {
+						quote! { hyper::Body::from(#response_bytes) }
+					}
+				} else {
+					return Err(syn::Error::new(value.span(), "Expected boolean"));
+				}
+			} else {
+				quote! { hyper::Body::from(#response_bytes) }
+			};
+
 		// Collect arg lines
 		// MARK: Simple argument parsing
 		let args = self
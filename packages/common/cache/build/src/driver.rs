@@ -1,23 +1,41 @@
 use std::{
 	fmt::Debug,
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Arc,
+	},
 	time::{Duration, Instant},
 };
 
-use moka::future::{Cache, CacheBuilder};
+use futures_util::StreamExt;
+use moka::{
+	future::{Cache, CacheBuilder},
+	ops::compute::Op,
+};
 use redis::AsyncCommands;
 use rivet_pools::prelude::*;
+use serde::{Deserialize, Serialize};
 use tracing::Instrument;
+use uuid::Uuid;
 
 use crate::{error::Error, metrics};
 
 /// Type alias for cache values stored as bytes
 pub type CacheValue = Vec<u8>;
 
+/// NATS subject that purged keys are published to so every process can drop them from its in-memory cache.
+const INVALIDATION_SUBJECT: &str = "rivet.cache.invalidate";
+
+/// Max time a value is kept in the in-memory layer of `LayeredDriver`. Bounds how long a stale value can be
+/// served if an invalidation message is missed.
+const LAYERED_L1_MAX_TTL_MS: i64 = 60_000;
+
 /// Enum wrapper for different cache driver implementations
 #[derive(Debug, Clone)]
 pub enum Driver {
 	Redis(RedisDriver),
 	InMemory(InMemoryDriver),
+	Layered(LayeredDriver),
 }
 
 impl Driver {
@@ -31,6 +49,7 @@ impl Driver {
 		match self {
 			Driver::Redis(d) => d.fetch_values(base_key, redis_keys).await,
 			Driver::InMemory(d) => d.fetch_values(base_key, redis_keys).await,
+			Driver::Layered(d) => d.fetch_values(base_key, redis_keys).await,
 		}
 	}
 
@@ -44,6 +63,7 @@ impl Driver {
 		match self {
 			Driver::Redis(d) => d.set_values(base_key, keys_values).await,
 			Driver::InMemory(d) => d.set_values(base_key, keys_values).await,
+			Driver::Layered(d) => d.set_values(base_key, keys_values).await,
 		}
 	}
 
@@ -57,6 +77,7 @@ impl Driver {
 		match self {
 			Driver::Redis(d) => d.delete_keys(base_key, redis_keys).await,
 			Driver::InMemory(d) => d.delete_keys(base_key, redis_keys).await,
+			Driver::Layered(d) => d.delete_keys(base_key, redis_keys).await,
 		}
 	}
 
@@ -65,10 +86,12 @@ impl Driver {
 	/// Different implementations use different key formats:
 	/// - Redis uses hash tags for key distribution
 	/// - In-memory uses simpler keys
+	/// - Layered uses the Redis format for both layers
 	pub fn process_key(&self, base_key: &str, key: &impl crate::CacheKey) -> String {
 		match self {
 			Driver::Redis(d) => d.process_key(base_key, key),
 			Driver::InMemory(d) => d.process_key(base_key, key),
+			Driver::Layered(d) => d.process_key(base_key, key),
 		}
 	}
 
@@ -87,6 +110,9 @@ impl Driver {
 			Driver::InMemory(d) => {
 				d.process_rate_limit_key(key, remote_address, bucket, bucket_duration_ms)
 			}
+			Driver::Layered(d) => {
+				d.process_rate_limit_key(key, remote_address, bucket, bucket_duration_ms)
+			}
 		}
 	}
 
@@ -95,6 +121,7 @@ impl Driver {
 		match self {
 			Driver::Redis(d) => Some(d.redis()),
 			Driver::InMemory(_) => None,
+			Driver::Layered(d) => Some(d.l2.redis()),
 		}
 	}
 
@@ -108,6 +135,7 @@ impl Driver {
 		match self {
 			Driver::Redis(d) => d.rate_limit_increment(key, ttl_ms).await,
 			Driver::InMemory(d) => d.rate_limit_increment(key, ttl_ms).await,
+			Driver::Layered(d) => d.rate_limit_increment(key, ttl_ms).await,
 		}
 	}
 
@@ -116,6 +144,7 @@ impl Driver {
 		match self {
 			Driver::Redis(d) => d.encode_value(value),
 			Driver::InMemory(d) => d.encode_value(value),
+			Driver::Layered(d) => d.encode_value(value),
 		}
 	}
 
@@ -124,6 +153,7 @@ impl Driver {
 		match self {
 			Driver::Redis(d) => d.decode_value(bytes),
 			Driver::InMemory(d) => d.decode_value(bytes),
+			Driver::Layered(d) => d.decode_value(bytes),
 		}
 	}
 }
@@ -133,6 +163,7 @@ impl std::fmt::Display for Driver {
 		match self {
 			Driver::Redis(_) => write!(f, "redis"),
 			Driver::InMemory(_) => write!(f, "in_memory"),
+			Driver::Layered(_) => write!(f, "layered"),
 		}
 	}
 }
@@ -211,6 +242,51 @@ impl RedisDriver {
 		}
 	}
 
+	/// Same as `fetch_values` but also returns the expiration time (epoch milliseconds) of each value.
+	pub async fn fetch_values_with_expiry<'a>(
+		&'a self,
+		base_key: &'a str,
+		redis_keys: &[String],
+	) -> Result<Vec<Option<(CacheValue, Option<i64>)>>, Error> {
+		let mut redis_conn = self.redis_conn.clone();
+
+		let mut pipe = redis::pipe();
+		for key in redis_keys {
+			pipe.get(key).pttl(key);
+		}
+
+		match pipe
+			.query_async::<_, Vec<(Option<CacheValue>, i64)>>(&mut redis_conn)
+			.instrument(tracing::info_span!("redis_query"))
+			.await
+		{
+			Ok(values) => {
+				let now = rivet_util::timestamp::now();
+
+				tracing::debug!(
+					cached_len = values.iter().filter(|(x, _)| x.is_some()).count(),
+					total_len = values.len(),
+					"read from cache"
+				);
+
+				// PTTL returns -1 if the key has no expiration and -2 if it does not exist
+				Ok(values
+					.into_iter()
+					.map(|(value, pttl)| {
+						value.map(|value| (value, (pttl >= 0).then(|| now + pttl)))
+					})
+					.collect())
+			}
+			Err(err) => {
+				tracing::error!(?err, "failed to read batch keys from cache");
+				metrics::CACHE_REQUEST_ERRORS
+					.with_label_values(&[base_key])
+					.inc();
+				Err(Error::ConnectRedis(err))
+			}
+		}
+	}
+
 	pub async fn set_values<'a>(
 		&'a self,
 		_base_key: &'a str,
@@ -344,6 +420,9 @@ struct ExpiringValue {
 	value: CacheValue,
 	/// The expiration time (epoch milliseconds)
 	expiry_time: i64,
+	/// Invalidation generation of the `LayeredDriver` when the value was read. Always 0 for the
+	/// `InMemoryDriver`.
+	generation: u64,
 }
 
 /// Cache expiry implementation for Moka
@@ -474,6 +553,7 @@ impl InMemoryDriver {
 				let entry = ExpiringValue {
 					value,
 					expiry_time: expire_at,
+					generation: 0,
 				};
 
 				// Store in cache - expiry will be handled by ValueExpiry
@@ -578,6 +658,7 @@ impl InMemoryDriver {
 		let entry = ExpiringValue {
 			value: encoded,
 			expiry_time: rivet_util::timestamp::now() + ttl_ms,
+			generation: 0,
 		};
 
 		// Update the rate limit cache
@@ -586,3 +667,307 @@ impl InMemoryDriver {
 		Ok(new_value)
 	}
 }
+
+#[derive(Serialize, Deserialize)]
+struct InvalidationMessage {
+	/// Id of the `LayeredDriver` that purged the keys. This driver already removed them from its own in-memory
+	/// cache.
+	origin: Uuid,
+	keys: Vec<String>,
+}
+
+/// In-memory cache (L1) in front of Redis (L2).
+///
+/// Values read from Redis are kept in memory until they expire in Redis or for at most
+/// `LAYERED_L1_MAX_TTL_MS`. Purged keys are published over NATS so every process drops them from its
+/// in-memory cache.
+///
+/// Every invalidation bumps the generation before removing keys. Values are written to the in-memory cache
+/// with the generation from before they were read and removed again if it changed in the meantime, so a
+/// read racing an invalidation can't bring back the purged value.
+#[derive(Clone)]
+pub struct LayeredDriver {
+	service_name: String,
+	id: Uuid,
+	l1: InMemoryDriver,
+	generation: Arc<AtomicU64>,
+	l2: RedisDriver,
+	nats: Option<NatsPool>,
+}
+
+impl Debug for LayeredDriver {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("LayeredDriver")
+			.field("service_name", &self.service_name)
+			.field("id", &self.id)
+			.finish()
+	}
+}
+
+impl LayeredDriver {
+	/// If `nats` is `None`, purges only invalidate the in-memory cache of this process.
+	pub fn new(
+		service_name: String,
+		redis_conn: RedisPool,
+		nats: Option<NatsPool>,
+		max_capacity: u64,
+	) -> Self {
+		let driver = Self {
+			service_name: service_name.clone(),
+			id: Uuid::new_v4(),
+			l1: InMemoryDriver::new(service_name.clone(), max_capacity),
+			generation: Arc::new(AtomicU64::new(0)),
+			l2: RedisDriver::new(service_name, redis_conn),
+			nats,
+		};
+
+		if let Some(nats) = &driver.nats {
+			let spawn_res = tokio::task::Builder::new()
+				.name("cache::invalidation")
+				.spawn(invalidation_loop(
+					driver.id,
+					nats.clone(),
+					driver.l1.cache.clone(),
+					driver.generation.clone(),
+				));
+			if let Err(err) = spawn_res {
+				tracing::error!(?err, "failed to spawn cache::invalidation task");
+			}
+		}
+
+		driver
+	}
+
+	pub async fn fetch_values<'a>(
+		&'a self,
+		base_key: &'a str,
+		keys: &[String],
+	) -> Result<Vec<Option<CacheValue>>, Error> {
+		let mut values = self.l1.fetch_values(base_key, keys).await?;
+
+		let missing_keys = keys
+			.iter()
+			.zip(values.iter())
+			.filter(|(_, value)| value.is_none())
+			.map(|(key, _)| key.clone())
+			.collect::<Vec<_>>();
+		if missing_keys.is_empty() {
+			return Ok(values);
+		}
+
+		let generation = self.generation.load(Ordering::SeqCst);
+		let l2_values = self
+			.l2
+			.fetch_values_with_expiry(base_key, &missing_keys)
+			.await?;
+
+		// Populate the in-memory cache with values from Redis
+		let max_expire_at = rivet_util::timestamp::now() + LAYERED_L1_MAX_TTL_MS;
+		let mut l1_keys_values = Vec::new();
+		let mut l2_values = missing_keys.into_iter().zip(l2_values);
+		for value in values.iter_mut().filter(|value| value.is_none()) {
+			let Some((key, Some((l2_value, expire_at)))) = l2_values.next() else {
+				continue;
+			};
+
+			l1_keys_values.push((
+				key,
+				l2_value.clone(),
+				expire_at.map_or(max_expire_at, |x| x.min(max_expire_at)),
+			));
+			*value = Some(l2_value);
+		}
+
+		self.set_l1_values(l1_keys_values, generation).await;
+
+		Ok(values)
+	}
+
+	pub async fn set_values<'a>(
+		&'a self,
+		base_key: &'a str,
+		keys_values: Vec<(String, CacheValue, i64)>,
+	) -> Result<(), Error> {
+		let generation = self.generation.load(Ordering::SeqCst);
+		let max_expire_at = rivet_util::timestamp::now() + LAYERED_L1_MAX_TTL_MS;
+		let l1_keys_values = keys_values
+			.iter()
+			.map(|(key, value, expire_at)| {
+				(key.clone(), value.clone(), (*expire_at).min(max_expire_at))
+			})
+			.collect();
+
+		let (_, l2_res) = tokio::join!(
+			self.set_l1_values(l1_keys_values, generation),
+			self.l2.set_values(base_key, keys_values),
+		);
+		l2_res?;
+
+		Ok(())
+	}
+
+	pub async fn delete_keys<'a>(
+		&'a self,
+		base_key: &'a str,
+		keys: Vec<String>,
+	) -> Result<(), Error> {
+		// Purge metrics are recorded by the Redis driver
+		invalidate_l1(&self.l1.cache, &self.generation, &keys)
+			.instrument(tracing::info_span!("remove"))
+			.await;
+
+		let l2_res = self.l2.delete_keys(base_key, keys.clone()).await;
+
+		// Invalidate other processes even if deleting from Redis failed since their in-memory cache may
+		// still be cleared
+		if let Some(nats) = &self.nats {
+			let message = InvalidationMessage {
+				origin: self.id,
+				keys,
+			};
+			let payload = serde_json::to_vec(&message).map_err(Error::SerdeEncode)?;
+
+			if let Err(err) = nats
+				.publish(INVALIDATION_SUBJECT, payload.into())
+				.instrument(tracing::info_span!("nats_publish"))
+				.await
+			{
+				tracing::error!(?err, "failed to publish cache invalidation");
+			}
+		}
+
+		l2_res
+	}
+
+	/// Writes values to the in-memory cache that were read (or written) at the given invalidation generation.
+	async fn set_l1_values(&self, keys_values: Vec<(String, CacheValue, i64)>, generation: u64) {
+		let cache = &self.l1.cache;
+
+		async {
+			for (key, value, expire_at) in keys_values {
+				// Skip values that are already known to be stale
+				if self.generation.load(Ordering::SeqCst) != generation {
+					break;
+				}
+
+				cache
+					.insert(
+						key.clone(),
+						ExpiringValue {
+							value,
+							expiry_time: expire_at,
+							generation,
+						},
+					)
+					.await;
+
+				// An invalidation that bumped the generation before the insert may have already removed the
+				// key, remove the value again unless it was replaced by a newer one
+				if self.generation.load(Ordering::SeqCst) != generation {
+					cache
+						.entry(key)
+						.and_compute_with(|entry| async move {
+							match entry {
+								Some(entry) if entry.value().generation <= generation => Op::Remove,
+								_ => Op::Nop,
+							}
+						})
+						.await;
+					break;
+				}
+			}
+		}
+		.instrument(tracing::info_span!("set"))
+		.await;
+	}
+
+	pub fn process_key(&self, base_key: &str, key: &impl crate::CacheKey) -> String {
+		self.l2.process_key(base_key, key)
+	}
+
+	pub fn process_rate_limit_key(
+		&self,
+		key: &impl crate::CacheKey,
+		remote_address: impl AsRef<str>,
+		bucket: i64,
+		bucket_duration_ms: i64,
+	) -> String {
+		self.l2
+			.process_rate_limit_key(key, remote_address, bucket, bucket_duration_ms)
+	}
+
+	pub fn encode_value<T: redis::ToRedisArgs>(&self, value: &T) -> CacheValue {
+		self.l2.encode_value(value)
+	}
+
+	pub fn decode_value<T: redis::FromRedisValue>(&self, bytes: &[u8]) -> Result<T, Error> {
+		self.l2.decode_value(bytes)
+	}
+
+	/// Rate limits are shared between processes so they are only stored in Redis.
+	pub async fn rate_limit_increment<'a>(
+		&'a self,
+		key: &'a str,
+		ttl_ms: i64,
+	) -> Result<i64, Error> {
+		self.l2.rate_limit_increment(key, ttl_ms).await
+	}
+}
+
+/// Removes keys from the in-memory cache of a `LayeredDriver`. Bumps the generation first so that values
+/// being read concurrently are not written back afterwards.
+async fn invalidate_l1(
+	cache: &Cache<String, ExpiringValue>,
+	generation: &AtomicU64,
+	keys: &[String],
+) {
+	generation.fetch_add(1, Ordering::SeqCst);
+
+	for key in keys {
+		cache.remove(key).await;
+	}
+}
+
+/// Removes keys purged by other processes from the in-memory cache.
+#[tracing::instrument(skip_all)]
+async fn invalidation_loop(
+	id: Uuid,
+	nats: NatsPool,
+	cache: Cache<String, ExpiringValue>,
+	generation: Arc<AtomicU64>,
+) {
+	loop {
+		let mut sub = match nats.subscribe(INVALIDATION_SUBJECT).await {
+			Ok(sub) => sub,
+			Err(err) => {
+				tracing::error!(?err, "failed to subscribe to cache invalidations, retrying");
+				tokio::time::sleep(Duration::from_secs(1)).await;
+				continue;
+			}
+		};
+
+		// Invalidations may have been missed while not subscribed
+		generation.fetch_add(1, Ordering::SeqCst);
+		cache.invalidate_all();
+
+		while let Some(msg) = sub.next().await {
+			let message = match serde_json::from_slice::<InvalidationMessage>(&msg.payload) {
+				Ok(message) => message,
+				Err(err) => {
+					tracing::warn!(?err, "failed to decode cache invalidation");
+					continue;
+				}
+			};
+
+			if message.origin == id {
+				continue;
+			}
+
+			tracing::trace!(keys = ?message.keys, "invalidating keys");
+
+			invalidate_l1(&cache, &generation, &message.keys).await;
+		}
+
+		tracing::warn!("cache invalidation subscription closed, resubscribing");
+	}
+}
@@ -0,0 +1,75 @@
+use crate::driver::CacheValue;
+
+/// Prefix of values written with metadata. Values without this prefix were written by requests without a soft
+/// TTL or negative caching and are read as-is.
+const ENTRY_MAGIC: &[u8] = b"\x00\xffrivet_cache_entry\x01";
+
+const FLAG_NEGATIVE: u8 = 1 << 0;
+const FLAG_REFRESH_AT: u8 = 1 << 1;
+
+/// Value read from the cache along with its metadata.
+#[derive(Debug)]
+pub(crate) struct Entry {
+	/// Encoded value. `None` if the getter did not resolve a value for this key (negative caching).
+	pub(crate) value: Option<CacheValue>,
+	/// Time (epoch milliseconds) after which the value is stale and should be refreshed.
+	pub(crate) refresh_at: Option<i64>,
+}
+
+impl Entry {
+	pub(crate) fn is_stale(&self, now: i64) -> bool {
+		self.refresh_at
+			.map_or(false, |refresh_at| refresh_at <= now)
+	}
+}
+
+/// Encodes a value with its metadata.
+///
+/// Layout: magic, flags (1 byte), refresh at (8 bytes, big endian), value.
+pub(crate) fn encode(value: Option<CacheValue>, refresh_at: Option<i64>) -> CacheValue {
+	let value_len = value.as_ref().map_or(0, |x| x.len());
+	let mut buf = Vec::with_capacity(ENTRY_MAGIC.len() + 1 + 8 + value_len);
+
+	let mut flags = 0;
+	if value.is_none() {
+		flags |= FLAG_NEGATIVE;
+	}
+	if refresh_at.is_some() {
+		flags |= FLAG_REFRESH_AT;
+	}
+
+	buf.extend_from_slice(ENTRY_MAGIC);
+	buf.push(flags);
+	buf.extend_from_slice(&refresh_at.unwrap_or_default().to_be_bytes());
+	if let Some(value) = value {
+		buf.extend(value);
+	}
+
+	buf
+}
+
+/// Decodes a value written by `encode`. Values written without metadata are returned as-is.
+pub(crate) fn decode(bytes: CacheValue) -> Entry {
+	let Some(rest) = bytes.strip_prefix(ENTRY_MAGIC) else {
+		return Entry {
+			value: Some(bytes),
+			refresh_at: None,
+		};
+	};
+
+	let (Some(&flags), Some(refresh_at)) = (rest.first(), rest.get(1..9)) else {
+		tracing::warn!("truncated cache entry, ignoring metadata");
+		return Entry {
+			value: Some(bytes),
+			refresh_at: None,
+		};
+	};
+
+	// Infallible since the slice is 8 bytes long
+	let refresh_at = i64::from_be_bytes(refresh_at.try_into().unwrap_or_default());
+
+	Entry {
+		value: (flags & FLAG_NEGATIVE == 0).then(|| rest[9..].to_vec()),
+		refresh_at: (flags & FLAG_REFRESH_AT != 0).then_some(refresh_at),
+	}
+}
@@ -13,7 +13,8 @@ pub(super) struct GetterCtxKey<K, V> {
 
 	/// If this value was read from the cache. If false and a value is present,
 	/// then this value was read from the getter and will be written to the
-	/// cache.
+	/// cache. If true and no value is present, the cache recorded that the
+	/// getter has no value for this key.
 	from_cache: bool,
 }
 
@@ -77,20 +78,25 @@ where
 		&self.keys[..]
 	}
 
-	/// If all keys have an associated value.
+	/// If all keys have an associated value or were cached as empty.
 	pub(super) fn all_keys_have_value(&self) -> bool {
-		self.keys.iter().all(|x| x.value.is_some())
+		self.keys.iter().all(|x| x.value.is_some() || x.from_cache)
 	}
 
-	/// Keys that do not have a value yet.
+	/// Keys that do not have a value yet and were not cached as empty.
 	pub(super) fn unresolved_keys(&self) -> Vec<K> {
 		self.keys
 			.iter()
-			.filter(|x| x.value.is_none())
+			.filter(|x| x.value.is_none() && !x.from_cache)
 			.map(|x| x.key.clone())
 			.collect()
 	}
 
+	/// Amount of keys without a value, including keys cached as empty.
+	pub(super) fn empty_keys_len(&self) -> usize {
+		self.keys.iter().filter(|x| x.value.is_none()).count()
+	}
+
 	/// Keys that have been resolved in a getter and need to be written to the
 	/// cache.
 	pub(super) fn values_needing_cache_write(&self) -> Vec<(&GetterCtxKey<K, V>, &V)> {
@@ -117,6 +123,15 @@ where
 		}
 	}
 
+	/// Marks a key as having no value according to the cache. The getter will not be called for this key.
+	pub(super) fn resolve_empty_from_cache(&mut self, idx: usize) {
+		if let Some(key) = self.keys.get_mut(idx) {
+			key.from_cache = true;
+		} else {
+			tracing::warn!(?idx, "resolving cache key index out of range");
+		}
+	}
+
 	/// Calls the callback with a mutable reference to a given key. Validates
 	/// that the key does not already have a value.
 	fn get_key_for_resolve(&mut self, key: &K, cb: impl FnOnce(&mut GetterCtxKey<K, V>)) {
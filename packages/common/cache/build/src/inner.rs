@@ -1,9 +1,14 @@
-use std::{fmt::Debug, sync::Arc};
+use std::{
+	collections::HashMap,
+	fmt::Debug,
+	sync::{Arc, Mutex},
+};
 
 use rivet_pools::prelude::*;
+use tokio::sync::watch;
 
 use super::*;
-use crate::driver::{Driver, InMemoryDriver, RedisDriver};
+use crate::driver::{CacheValue, Driver, InMemoryDriver, LayeredDriver, RedisDriver};
 
 pub type Cache = Arc<CacheInner>;
 
@@ -11,6 +16,8 @@ pub type Cache = Arc<CacheInner>;
 pub struct CacheInner {
 	service_name: String,
 	pub(crate) driver: Driver,
+	/// Getter calls in progress for a single cache key. See `CacheInner::in_flight`.
+	in_flight: Mutex<HashMap<String, InFlightReceiver>>,
 }
 
 impl Debug for CacheInner {
@@ -38,6 +45,17 @@ impl CacheInner {
 			rivet_config::config::CacheDriver::InMemory => {
 				Ok(Self::new_in_memory(service_name.to_string(), 1000))
 			}
+			rivet_config::config::CacheDriver::Layered => {
+				let redis_cache = pools.redis_cache().map_err(Error::Pools)?;
+				let nats = pools.nats().map_err(Error::Pools)?;
+
+				Ok(Self::new_layered(
+					service_name.to_string(),
+					redis_cache,
+					Some(nats),
+					1000,
+				))
+			}
 		}
 	}
 
@@ -47,6 +65,7 @@ impl CacheInner {
 		Arc::new(CacheInner {
 			service_name,
 			driver,
+			in_flight: Mutex::new(HashMap::new()),
 		})
 	}
 
@@ -56,10 +75,86 @@ impl CacheInner {
 		Arc::new(CacheInner {
 			service_name,
 			driver,
+			in_flight: Mutex::new(HashMap::new()),
+		})
+	}
+
+	/// If `nats` is `None`, purges are not broadcast to other processes.
+	#[tracing::instrument(skip(redis_conn, nats))]
+	pub fn new_layered(
+		service_name: String,
+		redis_conn: RedisPool,
+		nats: Option<NatsPool>,
+		max_capacity: u64,
+	) -> Cache {
+		let driver = Driver::Layered(LayeredDriver::new(
+			service_name.clone(),
+			redis_conn,
+			nats,
+			max_capacity,
+		));
+		Arc::new(CacheInner {
+			service_name,
+			driver,
+			in_flight: Mutex::new(HashMap::new()),
 		})
 	}
 }
 
+impl CacheInner {
+	/// Registers a getter call for the given cache key. Only the first caller becomes the leader and calls
+	/// the getter, concurrent callers wait for the leader's value instead.
+	pub(crate) fn in_flight(self: &Arc<Self>, cache_key: String) -> InFlight {
+		let mut in_flight = self.in_flight.lock().unwrap_or_else(|err| err.into_inner());
+
+		if let Some(rx) = in_flight.get(&cache_key) {
+			InFlight::Follower(rx.clone())
+		} else {
+			let (tx, rx) = watch::channel(None);
+			in_flight.insert(cache_key.clone(), rx);
+
+			InFlight::Leader(InFlightGuard {
+				cache: self.clone(),
+				cache_key,
+				tx,
+			})
+		}
+	}
+}
+
+/// Receives `Some` once the leader's getter completes. The inner value is the encoded value returned by the
+/// getter, if any.
+pub(crate) type InFlightReceiver = watch::Receiver<Option<Option<CacheValue>>>;
+
+pub(crate) enum InFlight {
+	Leader(InFlightGuard),
+	Follower(InFlightReceiver),
+}
+
+/// Removes the in-flight entry when dropped. If dropped without calling `resolve` (i.e. the getter failed),
+/// followers fall back to calling the getter themselves.
+pub(crate) struct InFlightGuard {
+	cache: Cache,
+	cache_key: String,
+	tx: watch::Sender<Option<Option<CacheValue>>>,
+}
+
+impl InFlightGuard {
+	pub(crate) fn resolve(self, value: Option<CacheValue>) {
+		self.tx.send_replace(Some(value));
+	}
+}
+
+impl Drop for InFlightGuard {
+	fn drop(&mut self) {
+		self.cache
+			.in_flight
+			.lock()
+			.unwrap_or_else(|err| err.into_inner())
+			.remove(&self.cache_key);
+	}
+}
+
 impl CacheInner {
 	/// Returns a new request config builder.
 	pub fn request(self: Arc<Self>) -> RequestConfig {
@@ -1,4 +1,5 @@
 mod driver;
+mod entry;
 mod error;
 mod getter_ctx;
 mod inner;
@@ -31,6 +31,18 @@ lazy_static::lazy_static! {
 		&["key"],
 		*REGISTRY,
 	).unwrap();
+	pub static ref CACHE_VALUE_COALESCED_TOTAL: IntCounterVec = register_int_counter_vec_with_registry!(
+		"cache_value_coalesced_total",
+		"Total number of cache value misses that waited for a concurrent getter call instead of calling the getter.",
+		&["key"],
+		*REGISTRY,
+	).unwrap();
+	pub static ref CACHE_VALUE_STALE_TOTAL: IntCounterVec = register_int_counter_vec_with_registry!(
+		"cache_value_stale_total",
+		"Total number of stale cache values that were refreshed.",
+		&["key"],
+		*REGISTRY,
+	).unwrap();
 	pub static ref CACHE_VALUE_EMPTY_TOTAL: IntCounterVec = register_int_counter_vec_with_registry!(
 		"cache_value_empty_total",
 		"Total number of cache values that were requested but not resolved by cache nor getter.",
@@ -1,19 +1,29 @@
 use std::{fmt::Debug, future::Future};
 
+use rand::Rng;
 use serde::{de::DeserializeOwned, Serialize};
 use tracing::Instrument;
 
 use super::*;
 use crate::{
+	driver::CacheValue,
+	entry::{self, Entry},
 	error::{Error, GetterResult},
+	inner::{InFlight, InFlightGuard},
 	metrics,
 };
 
+/// Refreshes stale keys. Called with the keys this request is responsible for refreshing.
+type RefreshFn<Key> = Box<dyn FnOnce(Vec<(Key, InFlightGuard)>) + Send>;
+
 /// Config specifying how cached values will behave.
 #[derive(Clone)]
 pub struct RequestConfig {
 	pub(super) cache: Cache,
 	ttl: i64,
+	soft_ttl: Option<i64>,
+	negative_ttl: Option<i64>,
+	ttl_jitter: f64,
 }
 
 impl Debug for RequestConfig {
@@ -21,6 +31,9 @@ impl Debug for RequestConfig {
 		f.debug_struct("RequestConfig")
 			.field("cache", &self.cache)
 			.field("ttl", &self.ttl)
+			.field("soft_ttl", &self.soft_ttl)
+			.field("negative_ttl", &self.negative_ttl)
+			.field("ttl_jitter", &self.ttl_jitter)
 			.finish()
 	}
 }
@@ -30,6 +43,9 @@ impl RequestConfig {
 		RequestConfig {
 			cache,
 			ttl: rivet_util::duration::hours(2),
+			soft_ttl: None,
+			negative_ttl: None,
+			ttl_jitter: 0.0,
 		}
 	}
 
@@ -40,6 +56,35 @@ impl RequestConfig {
 		self.ttl = ttl;
 		self
 	}
+
+	/// Sets the soft TTL for the keys in ms. Values older than the soft TTL are still served but are
+	/// refreshed with the getter in the background. Only one request per key refreshes a stale value at a
+	/// time, concurrent requests are served the stale value until it is refreshed.
+	///
+	/// The getter has to be `'static` since it outlives the request. Should be less than the TTL and set
+	/// after the other options. Disabled by default.
+	pub fn soft_ttl(mut self, soft_ttl: i64) -> SoftTtlRequestConfig {
+		self.soft_ttl = Some(soft_ttl);
+		SoftTtlRequestConfig { config: self }
+	}
+
+	/// Caches keys that the getter did not resolve a value for with the given TTL in ms, so missing values
+	/// do not call the getter on every request.
+	///
+	/// Purge the key when the value is created. Disabled by default.
+	pub fn negative_ttl(mut self, negative_ttl: i64) -> Self {
+		self.negative_ttl = Some(negative_ttl);
+		self
+	}
+
+	/// Randomly shortens the TTL, soft TTL, and negative TTL of each value by up to the given fraction
+	/// (between 0 and 1) so values written together do not expire together.
+	///
+	/// Defaults to 0.
+	pub fn ttl_jitter(mut self, ttl_jitter: f64) -> Self {
+		self.ttl_jitter = ttl_jitter.clamp(0.0, 1.0);
+		self
+	}
 }
 
 // MARK: Fetch
@@ -98,11 +143,13 @@ impl RequestConfig {
 			getter,
 			|x: &Value| Ok(x.clone()),
 			|x: &Value| Ok(x.clone()),
+			None,
 		)
 		.await
 	}
 
-	#[tracing::instrument(err, skip(keys, getter, encoder, decoder))]
+	/// Stale values are only refreshed if `refresh` is set.
+	#[tracing::instrument(err, skip(keys, getter, encoder, decoder, refresh))]
 	async fn fetch_all_convert<Key, Value, ValueRedis, Getter, Fut, Encoder, Decoder>(
 		self,
 		base_key: impl ToString + Debug,
@@ -110,6 +157,7 @@ impl RequestConfig {
 		getter: Getter,
 		encoder: Encoder,
 		decoder: Decoder,
+		refresh: Option<RefreshFn<Key>>,
 	) -> Result<Vec<(Key, Value)>, Error>
 	where
 		Key: CacheKey + Send + Sync,
@@ -158,22 +206,31 @@ impl RequestConfig {
 				);
 
 				// Create the getter ctx and resolve the cached values
+				let now = rivet_util::timestamp::now();
+				let mut stale_idxs = Vec::new();
 				for (i, value) in cached_values.into_iter().enumerate() {
-					if let Some(value_bytes) = value {
-						// Try to decode the value using the driver
-						match self.cache.driver.decode_value(&value_bytes) {
-							Ok(value_redis) => match decoder(&value_redis) {
-								Ok(value) => {
-									ctx.resolve_from_cache(i, value);
-								}
-								Err(err) => {
-									tracing::error!(?err, "Failed to decode value");
-								}
-							},
-							Err(err) => {
-								tracing::error!(?err, "Failed to decode value");
+					let Some(value_bytes) = value else {
+						continue;
+					};
+
+					let entry = entry::decode(value_bytes);
+					let Some(value_bytes) = &entry.value else {
+						ctx.resolve_empty_from_cache(i);
+						continue;
+					};
+
+					// Try to decode the value using the driver
+					match self.decode_value(value_bytes, &decoder) {
+						Ok(value) => {
+							ctx.resolve_from_cache(i, value);
+
+							if entry.is_stale(now) {
+								stale_idxs.push(i);
 							}
 						}
+						Err(err) => {
+							tracing::error!(?err, "Failed to decode value");
+						}
 					}
 				}
 
@@ -187,61 +244,96 @@ impl RequestConfig {
 						.with_label_values(&[&base_key])
 						.inc_by(unresolved_len as u64);
 
-					ctx = getter(ctx, remaining_keys).await.map_err(Error::Getter)?;
-
-					// Write the values to cache
-					let expire_at = rivet_util::timestamp::now() + self.ttl;
-					let values_needing_cache_write = ctx.values_needing_cache_write();
-
-					tracing::trace!(
-						unresolved_len,
-						fetched_len = values_needing_cache_write.len(),
-						"writing new values to cache"
-					);
-
-					// Convert values to cache bytes
-					let keys_values = values_needing_cache_write
-						.into_iter()
-						.filter_map(|(key, value)| {
-							// Process the key with the appropriate driver
-							let driver_key = self.cache.driver.process_key(&base_key, &key.key);
-							match encoder(value) {
-								Ok(value_redis) => {
-									// Encode the value with the driver
-									let value_bytes = self.cache.driver.encode_value(&value_redis);
-									Some((driver_key, value_bytes, expire_at))
+					// Coalesce concurrent misses for a single key so the getter is only called once
+					let in_flight = if let [cache_key] = cache_keys.as_slice() {
+						Some(self.cache.in_flight(cache_key.clone()))
+					} else {
+						None
+					};
+
+					let (leader, coalesced) = match in_flight {
+						Some(InFlight::Leader(leader)) => (Some(leader), false),
+						Some(InFlight::Follower(mut rx)) => {
+							metrics::CACHE_VALUE_COALESCED_TOTAL
+								.with_label_values(&[&base_key])
+								.inc();
+
+							match rx
+								.wait_for(Option::is_some)
+								.await
+								.map(|value| value.clone().flatten())
+							{
+								Ok(value) => {
+									match value.map(entry::decode) {
+										Some(Entry {
+											value: Some(value_bytes),
+											..
+										}) => match self.decode_value(&value_bytes, &decoder) {
+											Ok(value) => ctx.resolve_from_cache(0, value),
+											Err(err) => {
+												tracing::error!(?err, "Failed to decode value");
+											}
+										},
+										Some(Entry { value: None, .. }) => {
+											ctx.resolve_empty_from_cache(0);
+										}
+										None => {}
+									}
+
+									(None, true)
 								}
-								Err(err) => {
-									tracing::error!(?err, "Failed to encode value");
-									None
+								Err(_) => {
+									tracing::debug!("coalesced getter failed, calling getter");
+									(None, false)
 								}
 							}
-						})
-						.collect::<Vec<_>>();
+						}
+						None => (None, false),
+					};
 
-					if !keys_values.is_empty() {
-						let driver = self.cache.driver.clone();
-						let base_key_clone = base_key.clone();
+					if !coalesced {
+						ctx = getter(ctx, remaining_keys).await.map_err(Error::Getter)?;
 
-						let spawn_res = tokio::task::Builder::new().name("cache::write").spawn(
-							async move {
-								if let Err(err) =
-									driver.set_values(&base_key_clone, keys_values).await
-								{
-									tracing::error!(?err, "failed to write to cache");
-								}
-							}
-							.in_current_span(),
+						// Convert values to cache bytes
+						let keys_values = self.cache_entries(&base_key, &ctx, &encoder);
+
+						tracing::trace!(
+							unresolved_len,
+							fetched_len = keys_values.len(),
+							"writing new values to cache"
 						);
-						if let Err(err) = spawn_res {
-							tracing::error!(?err, "failed to spawn cache::write task");
+
+						if let Some(leader) = leader {
+							leader.resolve(keys_values.first().map(|(_, value, _)| value.clone()));
 						}
+
+						self.write_entries(&base_key, keys_values);
 					}
 				}
 
+				// Refresh stale values in the background. Only one request per key refreshes the value,
+				// concurrent requests keep serving the stale value.
+				if let Some(refresh) = refresh {
+					let refresh_leaders = stale_idxs
+						.into_iter()
+						.filter_map(|i| match self.cache.in_flight(cache_keys[i].clone()) {
+							InFlight::Leader(leader) => Some((ctx.keys()[i].key.clone(), leader)),
+							InFlight::Follower(_) => None,
+						})
+						.collect::<Vec<_>>();
+					if !refresh_leaders.is_empty() {
+						metrics::CACHE_VALUE_STALE_TOTAL
+							.with_label_values(&[&base_key])
+							.inc_by(refresh_leaders.len() as u64);
+
+						refresh(refresh_leaders);
+					}
+				}
+
+				// Includes keys cached as empty
 				metrics::CACHE_VALUE_EMPTY_TOTAL
 					.with_label_values(&[&base_key])
-					.inc_by(ctx.unresolved_keys().len() as u64);
+					.inc_by(ctx.empty_keys_len() as u64);
 
 				Ok(ctx.into_values())
 			}
@@ -265,6 +357,150 @@ impl RequestConfig {
 		}
 	}
 
+	/// Calls the getter for stale keys and writes the refreshed values to the cache.
+	async fn refresh_stale<Key, Value, ValueRedis, Getter, Fut, Encoder>(
+		self,
+		base_key: String,
+		refresh_leaders: Vec<(Key, InFlightGuard)>,
+		getter: Getter,
+		encoder: Encoder,
+	) where
+		Key: CacheKey,
+		ValueRedis: redis::ToRedisArgs,
+		Getter: Fn(GetterCtx<Key, Value>, Vec<Key>) -> Fut,
+		Fut: Future<Output = GetterResult<GetterCtx<Key, Value>>>,
+		Encoder: Fn(&Value) -> Result<ValueRedis, Error>,
+	{
+		let refresh_keys = refresh_leaders
+			.iter()
+			.map(|(key, _)| key.clone())
+			.collect::<Vec<_>>();
+		let refresh_ctx = GetterCtx::new(base_key.clone(), refresh_keys.clone());
+
+		match getter(refresh_ctx, refresh_keys).await {
+			Ok(refresh_ctx) => {
+				let keys_values = self.cache_entries(&base_key, &refresh_ctx, &encoder);
+
+				for (key, leader) in refresh_leaders {
+					let cache_key = self.cache.driver.process_key(&base_key, &key);
+					leader.resolve(
+						keys_values
+							.iter()
+							.find(|(k, _, _)| *k == cache_key)
+							.map(|(_, value, _)| value.clone()),
+					);
+				}
+
+				self.write_entries(&base_key, keys_values);
+			}
+			Err(err) => {
+				tracing::error!(?err, "failed to refresh stale values");
+			}
+		}
+	}
+
+	fn decode_value<Value, ValueRedis>(
+		&self,
+		value_bytes: &[u8],
+		decoder: &impl Fn(&ValueRedis) -> Result<Value, Error>,
+	) -> Result<Value, Error>
+	where
+		ValueRedis: redis::FromRedisValue,
+	{
+		let value_redis = self.cache.driver.decode_value(value_bytes)?;
+		decoder(&value_redis)
+	}
+
+	/// Builds the cache entries for the values resolved by the getter. With negative caching, keys that the
+	/// getter did not resolve are included too.
+	fn cache_entries<Key, Value, ValueRedis>(
+		&self,
+		base_key: &str,
+		ctx: &GetterCtx<Key, Value>,
+		encoder: &impl Fn(&Value) -> Result<ValueRedis, Error>,
+	) -> Vec<(String, CacheValue, i64)>
+	where
+		Key: CacheKey,
+		ValueRedis: redis::ToRedisArgs,
+	{
+		let now = rivet_util::timestamp::now();
+
+		// Values are only written with metadata if needed so they stay readable by older versions
+		let with_metadata = self.soft_ttl.is_some() || self.negative_ttl.is_some();
+
+		let mut keys_values = ctx
+			.values_needing_cache_write()
+			.into_iter()
+			.filter_map(|(key, value)| {
+				// Process the key with the appropriate driver
+				let driver_key = self.cache.driver.process_key(base_key, &key.key);
+				match encoder(value) {
+					Ok(value_redis) => {
+						// Encode the value with the driver
+						let value_bytes = self.cache.driver.encode_value(&value_redis);
+						let value_bytes = if with_metadata {
+							let refresh_at =
+								self.soft_ttl.map(|soft_ttl| now + self.jitter(soft_ttl));
+							entry::encode(Some(value_bytes), refresh_at)
+						} else {
+							value_bytes
+						};
+
+						Some((driver_key, value_bytes, now + self.jitter(self.ttl)))
+					}
+					Err(err) => {
+						tracing::error!(?err, "Failed to encode value");
+						None
+					}
+				}
+			})
+			.collect::<Vec<_>>();
+
+		if let Some(negative_ttl) = self.negative_ttl {
+			keys_values.extend(ctx.unresolved_keys().into_iter().map(|key| {
+				(
+					self.cache.driver.process_key(base_key, &key),
+					entry::encode(None, None),
+					now + self.jitter(negative_ttl),
+				)
+			}));
+		}
+
+		keys_values
+	}
+
+	/// Writes entries to the cache in the background.
+	fn write_entries(&self, base_key: &str, keys_values: Vec<(String, CacheValue, i64)>) {
+		if keys_values.is_empty() {
+			return;
+		}
+
+		let driver = self.cache.driver.clone();
+		let base_key = base_key.to_string();
+
+		let spawn_res = tokio::task::Builder::new().name("cache::write").spawn(
+			async move {
+				if let Err(err) = driver.set_values(&base_key, keys_values).await {
+					tracing::error!(?err, "failed to write to cache");
+				}
+			}
+			.in_current_span(),
+		);
+		if let Err(err) = spawn_res {
+			tracing::error!(?err, "failed to spawn cache::write task");
+		}
+	}
+
+	/// Randomly shortens the TTL by up to `ttl_jitter` of its length.
+	fn jitter(&self, ttl: i64) -> i64 {
+		let max_jitter = (ttl as f64 * self.ttl_jitter) as i64;
+		if max_jitter <= 0 {
+			return ttl;
+		}
+
+		ttl - rand::thread_rng().gen_range(0..=max_jitter)
+	}
+
 	#[tracing::instrument(err, skip(keys))]
 	pub async fn purge<Key>(
 		self,
@@ -375,6 +611,7 @@ impl RequestConfig {
 			|value: &Vec<u8>| -> Result<Value, Error> {
 				Value::decode(value.as_slice()).map_err(Error::ProtoDecode)
 			},
+			None,
 		)
 		.await
 	}
@@ -443,6 +680,131 @@ impl RequestConfig {
 		Value: Serialize + DeserializeOwned + Debug + Send + Sync,
 		Getter: Fn(GetterCtx<Key, Value>, Vec<Key>) -> Fut + Clone,
 		Fut: Future<Output = GetterResult<GetterCtx<Key, Value>>>,
+	{
+		self.fetch_all_convert(
+			base_key,
+			keys,
+			getter,
+			|value: &Value| -> Result<Vec<u8>, Error> {
+				serde_json::to_vec(value).map_err(Error::SerdeEncode)
+			},
+			|value: &Vec<u8>| -> Result<Value, Error> {
+				serde_json::from_slice(value.as_slice()).map_err(Error::SerdeDecode)
+			},
+			None,
+		)
+		.await
+	}
+}
+
+/// Request config with a soft TTL, see `RequestConfig::soft_ttl`. Stale values are refreshed in the
+/// background so the getter has to be `'static`.
+#[derive(Clone, Debug)]
+pub struct SoftTtlRequestConfig {
+	config: RequestConfig,
+}
+
+// MARK: Soft TTL fetch
+impl SoftTtlRequestConfig {
+	/// See `RequestConfig::fetch_one`.
+	#[tracing::instrument(err, skip(key, getter))]
+	pub async fn fetch_one<K, V, Getter, Fut>(
+		self,
+		base_key: impl ToString + Debug,
+		key: K,
+		getter: Getter,
+	) -> Result<Option<V>, Error>
+	where
+		K: CacheKey + Send + Sync + 'static,
+		V: redis::ToRedisArgs + redis::FromRedisValue + Clone + Debug + Send + Sync + 'static,
+		Getter: Fn(GetterCtx<K, V>, K) -> Fut + Clone + Send + Sync + 'static,
+		Fut: Future<Output = GetterResult<GetterCtx<K, V>>> + Send + 'static,
+	{
+		let values = self
+			.fetch_all(base_key, [key], move |cache, keys| {
+				let getter = getter.clone();
+				async move {
+					debug_assert_eq!(1, keys.len());
+					if let Some(key) = keys.into_iter().next() {
+						getter(cache, key).await
+					} else {
+						tracing::error!("no keys provided to fetch one");
+						Ok(cache)
+					}
+				}
+			})
+			.await?;
+		Ok(values.into_iter().next().map(|(_, v)| v))
+	}
+
+	/// See `RequestConfig::fetch_all`.
+	#[tracing::instrument(err, skip(keys, getter))]
+	pub async fn fetch_all<Key, Value, Getter, Fut>(
+		self,
+		base_key: impl ToString + Debug,
+		keys: impl IntoIterator<Item = Key>,
+		getter: Getter,
+	) -> Result<Vec<(Key, Value)>, Error>
+	where
+		Key: CacheKey + Send + Sync + 'static,
+		Value: redis::ToRedisArgs + redis::FromRedisValue + Clone + Debug + Send + Sync + 'static,
+		Getter: Fn(GetterCtx<Key, Value>, Vec<Key>) -> Fut + Clone + Send + Sync + 'static,
+		Fut: Future<Output = GetterResult<GetterCtx<Key, Value>>> + Send + 'static,
+	{
+		self.fetch_all_convert(
+			base_key,
+			keys,
+			getter,
+			|x: &Value| Ok(x.clone()),
+			|x: &Value| Ok(x.clone()),
+		)
+		.await
+	}
+
+	/// See `RequestConfig::fetch_one_json`.
+	#[tracing::instrument(err, skip(key, getter))]
+	pub async fn fetch_one_json<Key, Value, Getter, Fut>(
+		self,
+		base_key: impl ToString + Debug,
+		key: Key,
+		getter: Getter,
+	) -> Result<Option<Value>, Error>
+	where
+		Key: CacheKey + Send + Sync + 'static,
+		Value: Serialize + DeserializeOwned + Debug + Send + Sync + 'static,
+		Getter: Fn(GetterCtx<Key, Value>, Key) -> Fut + Clone + Send + Sync + 'static,
+		Fut: Future<Output = GetterResult<GetterCtx<Key, Value>>> + Send + 'static,
+	{
+		let values = self
+			.fetch_all_json_with_keys(base_key, [key], move |cache, keys| {
+				let getter = getter.clone();
+				async move {
+					debug_assert_eq!(1, keys.len());
+					if let Some(key) = keys.into_iter().next() {
+						getter(cache, key).await
+					} else {
+						tracing::error!("no keys provided to fetch one");
+						Ok(cache)
+					}
+				}
+			})
+			.await?;
+		Ok(values.into_iter().next().map(|(_, v)| v))
+	}
+
+	/// See `RequestConfig::fetch_all_json_with_keys`.
+	#[tracing::instrument(err, skip(keys, getter))]
+	pub async fn fetch_all_json_with_keys<Key, Value, Getter, Fut>(
+		self,
+		base_key: impl ToString + Debug,
+		keys: impl IntoIterator<Item = Key>,
+		getter: Getter,
+	) -> Result<Vec<(Key, Value)>, Error>
+	where
+		Key: CacheKey + Send + Sync + 'static,
+		Value: Serialize + DeserializeOwned + Debug + Send + Sync + 'static,
+		Getter: Fn(GetterCtx<Key, Value>, Vec<Key>) -> Fut + Clone + Send + Sync + 'static,
+		Fut: Future<Output = GetterResult<GetterCtx<Key, Value>>> + Send + 'static,
 	{
 		self.fetch_all_convert(
 			base_key,
@@ -457,4 +819,45 @@ impl RequestConfig {
 		)
 		.await
 	}
+
+	async fn fetch_all_convert<Key, Value, ValueRedis, Getter, Fut, Encoder, Decoder>(
+		self,
+		base_key: impl ToString + Debug,
+		keys: impl IntoIterator<Item = Key>,
+		getter: Getter,
+		encoder: Encoder,
+		decoder: Decoder,
+	) -> Result<Vec<(Key, Value)>, Error>
+	where
+		Key: CacheKey + Send + Sync + 'static,
+		Value: Debug + Send + Sync + 'static,
+		ValueRedis: redis::ToRedisArgs + redis::FromRedisValue + Debug + Send + Sync,
+		Getter: Fn(GetterCtx<Key, Value>, Vec<Key>) -> Fut + Clone + Send + Sync + 'static,
+		Fut: Future<Output = GetterResult<GetterCtx<Key, Value>>> + Send + 'static,
+		Encoder: Fn(&Value) -> Result<ValueRedis, Error> + Clone + Send + Sync + 'static,
+		Decoder: Fn(&ValueRedis) -> Result<Value, Error> + Clone,
+	{
+		let base_key = base_key.to_string();
+
+		let refresh: RefreshFn<Key> = {
+			let config = self.config.clone();
+			let base_key = base_key.clone();
+			let getter = getter.clone();
+			let encoder = encoder.clone();
+			Box::new(move |refresh_leaders| {
+				let spawn_res = tokio::task::Builder::new().name("cache::refresh").spawn(
+					config
+						.refresh_stale(base_key, refresh_leaders, getter, encoder)
+						.in_current_span(),
+				);
+				if let Err(err) = spawn_res {
+					tracing::error!(?err, "failed to spawn cache::refresh task");
+				}
+			})
+		};
+
+		self.config
+			.fetch_all_convert(base_key, keys, getter, encoder, decoder, Some(refresh))
+			.await
+	}
 }
@@ -1,6 +1,9 @@
 use std::{
 	collections::{HashMap, HashSet},
-	sync::Arc,
+	sync::{
+		atomic::{AtomicUsize, Ordering},
+		Arc,
+	},
 	time::Duration,
 };
 
@@ -18,6 +21,13 @@ async fn build_in_memory_cache() -> rivet_cache::Cache {
 	rivet_cache::CacheInner::new_in_memory("cache-test".to_owned(), 1000)
 }
 
+async fn build_layered_cache() -> rivet_cache::Cache {
+	let redis_client = redis::Client::open("redis://127.0.0.1/").unwrap();
+	let redis_conn = redis_client.get_tokio_connection_manager().await.unwrap();
+
+	rivet_cache::CacheInner::new_layered("cache-test".to_owned(), redis_conn, None, 1000)
+}
+
 async fn test_multiple_keys(cache: rivet_cache::Cache) {
 	let values = cache
 		.clone()
@@ -406,6 +416,290 @@ async fn test_multi_key_ttl(cache: rivet_cache::Cache) {
 	);
 }
 
+/// Tests that concurrent misses for the same key only call the getter once
+async fn test_coalesce(cache: rivet_cache::Cache) {
+	let test_key = format!("coalesce-{}", thread_rng().gen::<u64>());
+	let getter_calls = Arc::new(AtomicUsize::new(0));
+
+	let parallel_count = 16;
+	let barrier = Arc::new(tokio::sync::Barrier::new(parallel_count));
+	let mut handles = Vec::new();
+	for _ in 0..parallel_count {
+		let test_key = test_key.clone();
+		let getter_calls = getter_calls.clone();
+		let cache = cache.clone();
+		let barrier = barrier.clone();
+		let handle = tokio::spawn(async move {
+			barrier.wait().await;
+			cache
+				.request()
+				.fetch_one("coalesce_test", test_key, move |mut cache, key| {
+					let getter_calls = getter_calls.clone();
+					async move {
+						getter_calls.fetch_add(1, Ordering::SeqCst);
+						tokio::time::sleep(Duration::from_millis(200)).await;
+						cache.resolve(&key, "coalesced".to_string());
+						Ok(cache)
+					}
+				})
+				.await
+				.unwrap()
+		});
+		handles.push(handle);
+	}
+
+	let values = futures_util::future::try_join_all(handles).await.unwrap();
+	assert!(
+		values
+			.iter()
+			.all(|value| value.as_deref() == Some("coalesced")),
+		"all requests should receive the getter's value"
+	);
+	assert_eq!(
+		1,
+		getter_calls.load(Ordering::SeqCst),
+		"getter should only be called once"
+	);
+}
+
+/// Tests that values past the soft TTL are served stale and refreshed
+async fn test_soft_ttl(cache: rivet_cache::Cache) {
+	let test_key = format!("soft-ttl-{}", thread_rng().gen::<u64>());
+	let soft_ttl_ms = 200i64;
+	let getter_calls = Arc::new(AtomicUsize::new(0));
+
+	let fetch = |value: &'static str| {
+		let cache = cache.clone();
+		let test_key = test_key.clone();
+		let getter_calls = getter_calls.clone();
+		async move {
+			cache
+				.request()
+				.soft_ttl(soft_ttl_ms)
+				.fetch_one("soft_ttl_test", test_key, move |mut cache, key| {
+					let getter_calls = getter_calls.clone();
+					async move {
+						getter_calls.fetch_add(1, Ordering::SeqCst);
+						cache.resolve(&key, value.to_string());
+						Ok(cache)
+					}
+				})
+				.await
+				.unwrap()
+		}
+	};
+
+	assert_eq!(Some("first".to_string()), fetch("first").await);
+
+	// Wait for the cache write
+	tokio::time::sleep(Duration::from_millis(100)).await;
+
+	// Value is fresh, getter is not called
+	assert_eq!(Some("first".to_string()), fetch("second").await);
+	assert_eq!(1, getter_calls.load(Ordering::SeqCst));
+
+	// Wait for the value to become stale
+	tokio::time::sleep(Duration::from_millis((soft_ttl_ms * 2) as u64)).await;
+
+	// Stale value is served while the getter refreshes it
+	assert_eq!(
+		Some("first".to_string()),
+		fetch("second").await,
+		"Stale value should be served"
+	);
+
+	// Wait for the background refresh and the cache write
+	tokio::time::sleep(Duration::from_millis(100)).await;
+
+	assert_eq!(
+		2,
+		getter_calls.load(Ordering::SeqCst),
+		"Stale value should be refreshed"
+	);
+
+	assert_eq!(
+		Some("second".to_string()),
+		fetch("third").await,
+		"Refreshed value should be served"
+	);
+	assert_eq!(2, getter_calls.load(Ordering::SeqCst));
+}
+
+/// Tests that stale values are served without waiting for a slow getter to refresh them
+async fn test_soft_ttl_slow_getter(cache: rivet_cache::Cache) {
+	let test_key = format!("soft-ttl-slow-{}", thread_rng().gen::<u64>());
+	let soft_ttl_ms = 200i64;
+	let getter_delay = Duration::from_secs(1);
+
+	let fetch = |value: &'static str| {
+		let cache = cache.clone();
+		let test_key = test_key.clone();
+		async move {
+			cache
+				.request()
+				.soft_ttl(soft_ttl_ms)
+				.fetch_one(
+					"soft_ttl_slow_test",
+					test_key,
+					move |mut cache, key| async move {
+						tokio::time::sleep(getter_delay).await;
+						cache.resolve(&key, value.to_string());
+						Ok(cache)
+					},
+				)
+				.await
+				.unwrap()
+		}
+	};
+
+	assert_eq!(Some("first".to_string()), fetch("first").await);
+
+	// Wait for the cache write and for the value to become stale
+	tokio::time::sleep(Duration::from_millis((soft_ttl_ms * 2) as u64)).await;
+
+	// Stale value is served before the getter completes
+	let value = tokio::time::timeout(getter_delay / 2, fetch("second"))
+		.await
+		.expect("stale value should be served without waiting for the getter");
+	assert_eq!(Some("first".to_string()), value);
+
+	// Wait for the background refresh and the cache write
+	tokio::time::sleep(getter_delay + Duration::from_millis(100)).await;
+
+	assert_eq!(
+		Some("second".to_string()),
+		tokio::time::timeout(getter_delay / 2, fetch("third"))
+			.await
+			.unwrap(),
+		"Refreshed value should be served"
+	);
+}
+
+/// Tests that keys without a value are cached for the negative TTL
+async fn test_negative_ttl(cache: rivet_cache::Cache) {
+	let test_key = format!("negative-ttl-{}", thread_rng().gen::<u64>());
+	let negative_ttl_ms = 500i64;
+	let getter_calls = Arc::new(AtomicUsize::new(0));
+
+	let fetch = || {
+		let cache = cache.clone();
+		let test_key = test_key.clone();
+		let getter_calls = getter_calls.clone();
+		async move {
+			cache
+				.request()
+				.negative_ttl(negative_ttl_ms)
+				.fetch_one(
+					"negative_ttl_test",
+					test_key,
+					move |cache: rivet_cache::GetterCtx<String, String>, _| {
+						let getter_calls = getter_calls.clone();
+						async move {
+							getter_calls.fetch_add(1, Ordering::SeqCst);
+							Ok(cache)
+						}
+					},
+				)
+				.await
+				.unwrap()
+		}
+	};
+
+	assert_eq!(None, fetch().await);
+
+	// Wait for the cache write
+	tokio::time::sleep(Duration::from_millis(100)).await;
+
+	assert_eq!(None, fetch().await);
+	assert_eq!(
+		1,
+		getter_calls.load(Ordering::SeqCst),
+		"Missing value should be cached"
+	);
+
+	// Wait for the negative TTL to expire
+	tokio::time::sleep(Duration::from_millis((negative_ttl_ms * 2) as u64)).await;
+
+	assert_eq!(None, fetch().await);
+	assert_eq!(
+		2,
+		getter_calls.load(Ordering::SeqCst),
+		"Getter should be called after the negative TTL expires"
+	);
+
+	// Wait for the cache write
+	tokio::time::sleep(Duration::from_millis(100)).await;
+
+	// Purging removes the cached missing value
+	cache
+		.clone()
+		.request()
+		.purge("negative_ttl_test", [test_key.clone()])
+		.await
+		.unwrap();
+
+	let value = cache
+		.clone()
+		.request()
+		.negative_ttl(negative_ttl_ms)
+		.fetch_one("negative_ttl_test", test_key, |mut cache, key| async move {
+			cache.resolve(&key, "created".to_string());
+			Ok(cache)
+		})
+		.await
+		.unwrap();
+	assert_eq!(
+		Some("created".to_string()),
+		value,
+		"Value should be fetched after purging"
+	);
+}
+
+/// Tests that jittered values never outlive the TTL
+async fn test_ttl_jitter(cache: rivet_cache::Cache) {
+	let ttl_ms = 500i64;
+	let keys = (0..8)
+		.map(|_| format!("ttl-jitter-{}", thread_rng().gen::<u64>()))
+		.collect::<Vec<_>>();
+
+	let values = cache
+		.clone()
+		.request()
+		.ttl(ttl_ms)
+		.ttl_jitter(0.5)
+		.fetch_all(
+			"ttl_jitter_test",
+			keys.clone(),
+			|mut cache, keys| async move {
+				for key in &keys {
+					cache.resolve(key, "value".to_string());
+				}
+				Ok(cache)
+			},
+		)
+		.await
+		.unwrap();
+	assert_eq!(keys.len(), values.len());
+
+	// Wait for the TTL to expire
+	tokio::time::sleep(Duration::from_millis((ttl_ms * 2) as u64)).await;
+
+	let values = cache
+		.clone()
+		.request()
+		.fetch_all(
+			"ttl_jitter_test",
+			keys,
+			|cache: rivet_cache::GetterCtx<String, String>, _| async move { Ok(cache) },
+		)
+		.await
+		.unwrap();
+	assert!(
+		values.is_empty(),
+		"All values should expire within the TTL regardless of jitter"
+	);
+}
+
 /// Tests basic rate limiting functionality
 async fn test_rate_limit_basic(cache: rivet_cache::Cache) {
 	// Define a simple cache key for testing
@@ -585,6 +879,30 @@ async fn redis_multi_key_ttl() {
 	test_multi_key_ttl(cache).await;
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn redis_soft_ttl() {
+	let cache = build_redis_cache().await;
+	test_soft_ttl(cache).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn redis_soft_ttl_slow_getter() {
+	let cache = build_redis_cache().await;
+	test_soft_ttl_slow_getter(cache).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn redis_negative_ttl() {
+	let cache = build_redis_cache().await;
+	test_negative_ttl(cache).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn redis_ttl_jitter() {
+	let cache = build_redis_cache().await;
+	test_ttl_jitter(cache).await;
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn redis_rate_limit_basic() {
 	let cache = build_redis_cache().await;
@@ -597,6 +915,12 @@ async fn redis_rate_limit_ip_isolation() {
 	test_rate_limit_ip_isolation(cache).await;
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn redis_coalesce() {
+	let cache = build_redis_cache().await;
+	test_coalesce(cache).await;
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn in_memory_multiple_keys() {
 	let cache = build_in_memory_cache().await;
@@ -633,6 +957,30 @@ async fn in_memory_multi_key_ttl() {
 	test_multi_key_ttl(cache).await;
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn in_memory_soft_ttl() {
+	let cache = build_in_memory_cache().await;
+	test_soft_ttl(cache).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn in_memory_soft_ttl_slow_getter() {
+	let cache = build_in_memory_cache().await;
+	test_soft_ttl_slow_getter(cache).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn in_memory_negative_ttl() {
+	let cache = build_in_memory_cache().await;
+	test_negative_ttl(cache).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn in_memory_ttl_jitter() {
+	let cache = build_in_memory_cache().await;
+	test_ttl_jitter(cache).await;
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn in_memory_rate_limit_basic() {
 	let cache = build_in_memory_cache().await;
@@ -644,3 +992,155 @@ async fn in_memory_rate_limit_ip_isolation() {
 	let cache = build_in_memory_cache().await;
 	test_rate_limit_ip_isolation(cache).await;
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn in_memory_coalesce() {
+	let cache = build_in_memory_cache().await;
+	test_coalesce(cache).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn layered_multiple_keys() {
+	let cache = build_layered_cache().await;
+	test_multiple_keys(cache).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn layered_smoke_test() {
+	let cache = build_layered_cache().await;
+	test_smoke_test(cache).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn layered_custom_ttl() {
+	let cache = build_layered_cache().await;
+	test_custom_ttl(cache).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn layered_default_ttl() {
+	let cache = build_layered_cache().await;
+	test_default_ttl(cache).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn layered_purge_with_ttl() {
+	let cache = build_layered_cache().await;
+	test_purge_with_ttl(cache).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn layered_multi_key_ttl() {
+	let cache = build_layered_cache().await;
+	test_multi_key_ttl(cache).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn layered_coalesce() {
+	let cache = build_layered_cache().await;
+	test_coalesce(cache).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn layered_soft_ttl() {
+	let cache = build_layered_cache().await;
+	test_soft_ttl(cache).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn layered_soft_ttl_slow_getter() {
+	let cache = build_layered_cache().await;
+	test_soft_ttl_slow_getter(cache).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn layered_negative_ttl() {
+	let cache = build_layered_cache().await;
+	test_negative_ttl(cache).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn layered_ttl_jitter() {
+	let cache = build_layered_cache().await;
+	test_ttl_jitter(cache).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn layered_rate_limit_basic() {
+	let cache = build_layered_cache().await;
+	test_rate_limit_basic(cache).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn layered_rate_limit_ip_isolation() {
+	let cache = build_layered_cache().await;
+	test_rate_limit_ip_isolation(cache).await;
+}
+
+/// Tests that purging a key on one process removes it from the in-memory layer of every other process
+#[tokio::test(flavor = "multi_thread")]
+async fn layered_purge_invalidates_other_instances() {
+	let redis_client = redis::Client::open("redis://127.0.0.1/").unwrap();
+	let redis_conn = redis_client.get_tokio_connection_manager().await.unwrap();
+	let nats = nats::connect("nats://127.0.0.1:4222").await.unwrap();
+
+	let cache_a = rivet_cache::CacheInner::new_layered(
+		"cache-test".to_owned(),
+		redis_conn.clone(),
+		Some(nats.clone()),
+		1000,
+	);
+	let cache_b =
+		rivet_cache::CacheInner::new_layered("cache-test".to_owned(), redis_conn, Some(nats), 1000);
+
+	// Wait for invalidation subscriptions
+	tokio::time::sleep(Duration::from_millis(500)).await;
+
+	let test_key = format!("invalidate-{}", thread_rng().gen::<u64>());
+
+	// Populate the in-memory layer of both caches
+	for cache in [&cache_a, &cache_b] {
+		let value = cache
+			.clone()
+			.request()
+			.fetch_one(
+				"invalidate_test",
+				test_key.clone(),
+				|mut cache, key| async move {
+					cache.resolve(&key, "original".to_string());
+					Ok(cache)
+				},
+			)
+			.await
+			.unwrap();
+		assert_eq!(Some("original".to_string()), value);
+
+		// Wait for the cache write
+		tokio::time::sleep(Duration::from_millis(100)).await;
+	}
+
+	cache_a
+		.clone()
+		.request()
+		.purge("invalidate_test", [test_key.clone()])
+		.await
+		.unwrap();
+
+	// Wait for the invalidation to be delivered
+	tokio::time::sleep(Duration::from_millis(500)).await;
+
+	let value = cache_b
+		.clone()
+		.request()
+		.fetch_one(
+			"invalidate_test",
+			test_key,
+			|cache: rivet_cache::GetterCtx<String, String>, _| async move { Ok(cache) },
+		)
+		.await
+		.unwrap();
+	assert_eq!(
+		None, value,
+		"Value should not be served from the in-memory layer after a purge on another instance"
+	);
+}
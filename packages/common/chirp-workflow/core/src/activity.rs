@@ -1,10 +1,15 @@
-use std::{fmt::Debug, hash::Hash};
+use std::{
+	fmt::Debug,
+	hash::Hash,
+	time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use async_trait::async_trait;
-use global_error::GlobalResult;
-use serde::{de::DeserializeOwned, Serialize};
+use global_error::{GlobalError, GlobalResult};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tokio::time::Instant;
 
-use crate::ctx::ActivityCtx;
+use crate::ctx::{common::RETRY_TIMEOUT_MS, ActivityCtx};
 
 #[async_trait]
 pub trait Activity {
@@ -13,7 +18,14 @@ pub trait Activity {
 
 	const NAME: &'static str;
 	const MAX_RETRIES: usize;
-	const TIMEOUT: std::time::Duration;
+	const TIMEOUT: Duration;
+	const BACKOFF: ActivityBackoff = ActivityBackoff::DEFAULT;
+
+	/// Whether or not an error returned by this activity should be retried. Errors that are not retryable
+	/// fail the activity immediately regardless of `MAX_RETRIES`.
+	fn is_retryable(_err: &GlobalError) -> bool {
+		true
+	}
 
 	async fn run(ctx: &ActivityCtx, input: &Self::Input) -> GlobalResult<Self::Output>;
 }
@@ -21,3 +33,74 @@ pub trait Activity {
 pub trait ActivityInput: Serialize + DeserializeOwned + Debug + Hash + Send {
 	type Activity: Activity;
 }
+
+/// Exponential backoff between activity retries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActivityBackoff {
+	/// Base wait time in milliseconds.
+	pub wait_ms: usize,
+	/// Highest power of 2 the base wait time is multiplied by.
+	pub max_exponent: usize,
+	/// Random jitter in milliseconds added to each wait.
+	pub randomness_ms: usize,
+}
+
+impl ActivityBackoff {
+	pub const DEFAULT: ActivityBackoff = ActivityBackoff {
+		wait_ms: RETRY_TIMEOUT_MS,
+		max_exponent: 8,
+		randomness_ms: 500,
+	};
+
+	/// Returns the timestamp at which an activity that has failed `error_count` times should be retried.
+	pub(crate) fn deadline_ts(&self, error_count: usize) -> i64 {
+		// NOTE: Max retry is handled in `WorkflowCtx::activity`
+		let mut backoff = rivet_util::Backoff::new_at(
+			self.max_exponent,
+			None,
+			self.wait_ms,
+			self.randomness_ms,
+			error_count,
+		);
+		let next = backoff.step().expect("should not have max retry");
+
+		// Calculate timestamp based on the backoff
+		let duration_until = next.duration_since(Instant::now());
+		(SystemTime::now() + duration_until)
+			.duration_since(UNIX_EPOCH)
+			.unwrap_or_else(|err| unreachable!("time is broken: {}", err))
+			.as_millis()
+			.try_into()
+			.expect("doesn't fit in i64")
+	}
+}
+
+impl Default for ActivityBackoff {
+	fn default() -> Self {
+		ActivityBackoff::DEFAULT
+	}
+}
+
+/// Retry config of an activity. This is recorded in history the first time an activity runs so that changes
+/// to an activity's config don't affect replays of activities that are already in progress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+	pub max_retries: usize,
+	pub timeout_ms: u64,
+	pub backoff: ActivityBackoff,
+}
+
+impl RetryPolicy {
+	/// The current retry config of the given activity.
+	pub fn of<A: Activity>() -> Self {
+		RetryPolicy {
+			max_retries: A::MAX_RETRIES,
+			timeout_ms: A::TIMEOUT.as_millis().try_into().unwrap_or(u64::MAX),
+			backoff: A::BACKOFF,
+		}
+	}
+
+	pub fn timeout(&self) -> Duration {
+		Duration::from_millis(self.timeout_ms)
+	}
+}
@@ -1,14 +1,24 @@
-use std::{fmt::Display, time::Instant};
+use std::{
+	fmt::Display,
+	time::{Duration, Instant},
+};
 
 use global_error::{GlobalError, GlobalResult};
 use serde::Serialize;
 use uuid::Uuid;
 
 use crate::{
-	builder::BuilderError, db::DatabaseHandle, error::WorkflowError, metrics, signal::Signal,
+	builder::BuilderError,
+	db::{DatabaseHandle, IdempotencyKey},
+	error::WorkflowError,
+	metrics,
+	signal::Signal,
 	workflow::Workflow,
 };
 
+/// How long idempotency keys are kept by default.
+const DEFAULT_IDEMPOTENCY_WINDOW: Duration = Duration::from_secs(60 * 60);
+
 pub struct SignalBuilder<T: Signal + Serialize> {
 	db: DatabaseHandle,
 	ray_id: Uuid,
@@ -16,6 +26,8 @@ pub struct SignalBuilder<T: Signal + Serialize> {
 	to_workflow_name: Option<&'static str>,
 	to_workflow_id: Option<Uuid>,
 	tags: serde_json::Map<String, serde_json::Value>,
+	idempotency_key: Option<String>,
+	idempotency_window: Duration,
 	error: Option<BuilderError>,
 }
 
@@ -28,6 +40,8 @@ impl<T: Signal + Serialize> SignalBuilder<T> {
 			to_workflow_name: None,
 			to_workflow_id: None,
 			tags: serde_json::Map::new(),
+			idempotency_key: None,
+			idempotency_window: DEFAULT_IDEMPOTENCY_WINDOW,
 			error: from_workflow.then_some(BuilderError::CannotDispatchFromOpInWorkflow),
 		}
 	}
@@ -82,6 +96,30 @@ impl<T: Signal + Serialize> SignalBuilder<T> {
 		self
 	}
 
+	/// Deduplicates this signal against other signals of the same type sent to the same workflow (or tags)
+	/// with the same key within the idempotency window. Sending a duplicate does nothing and returns the ID
+	/// of the original signal.
+	pub fn idempotency_key(mut self, key: impl ToString) -> Self {
+		if self.error.is_some() {
+			return self;
+		}
+
+		self.idempotency_key = Some(key.to_string());
+
+		self
+	}
+
+	/// How long the idempotency key is kept after the signal is sent. Defaults to 1 hour.
+	pub fn idempotency_window(mut self, window: Duration) -> Self {
+		if self.error.is_some() {
+			return self;
+		}
+
+		self.idempotency_window = window;
+
+		self
+	}
+
 	#[tracing::instrument(skip_all, fields(signal_name=T::NAME, signal_id))]
 	pub async fn send(self) -> GlobalResult<Uuid> {
 		if let Some(err) = self.error {
@@ -90,15 +128,17 @@ impl<T: Signal + Serialize> SignalBuilder<T> {
 
 		let signal_id = Uuid::new_v4();
 		let start_instant = Instant::now();
-
-		tracing::Span::current().record("signal_id", signal_id.to_string());
+		let idempotency_key = self.idempotency_key.map(|key| IdempotencyKey {
+			key,
+			window_ms: self.idempotency_window.as_millis() as i64,
+		});
 
 		// Serialize input
 		let input_val = serde_json::value::to_raw_value(&self.body)
 			.map_err(WorkflowError::SerializeSignalBody)
 			.map_err(GlobalError::raw)?;
 
-		match (
+		let signal_id = match (
 			self.to_workflow_name,
 			self.to_workflow_id,
 			self.tags.is_empty(),
@@ -118,17 +158,31 @@ impl<T: Signal + Serialize> SignalBuilder<T> {
 					.map_err(GlobalError::raw)?;
 
 				self.db
-					.publish_signal(self.ray_id, workflow_id, signal_id, T::NAME, &input_val)
+					.publish_signal(
+						self.ray_id,
+						workflow_id,
+						signal_id,
+						T::NAME,
+						&input_val,
+						idempotency_key.as_ref(),
+					)
 					.await
-					.map_err(GlobalError::raw)?;
+					.map_err(GlobalError::raw)?
 			}
 			(None, Some(workflow_id), true) => {
 				tracing::info!(to_workflow_id=%workflow_id, "dispatching signal via workflow id");
 
 				self.db
-					.publish_signal(self.ray_id, workflow_id, signal_id, T::NAME, &input_val)
+					.publish_signal(
+						self.ray_id,
+						workflow_id,
+						signal_id,
+						T::NAME,
+						&input_val,
+						idempotency_key.as_ref(),
+					)
 					.await
-					.map_err(GlobalError::raw)?;
+					.map_err(GlobalError::raw)?
 			}
 			(None, None, false) => {
 				tracing::info!(tags=?self.tags, %signal_id, "dispatching tagged signal");
@@ -140,9 +194,10 @@ impl<T: Signal + Serialize> SignalBuilder<T> {
 						signal_id,
 						T::NAME,
 						&input_val,
+						idempotency_key.as_ref(),
 					)
 					.await
-					.map_err(GlobalError::raw)?;
+					.map_err(GlobalError::raw)?
 			}
 			(Some(_), Some(_), _) => {
 				return Err(BuilderError::InvalidSignalSend(
@@ -162,7 +217,9 @@ impl<T: Signal + Serialize> SignalBuilder<T> {
 				)
 				.into())
 			}
-		}
+		};
+
+		tracing::Span::current().record("signal_id", signal_id.to_string());
 
 		let dt = start_instant.elapsed().as_secs_f64();
 		metrics::SIGNAL_SEND_DURATION
@@ -20,6 +20,7 @@ pub struct WorkflowBuilder<T, I: WorkflowInput> {
 	repr: T,
 	tags: serde_json::Map<String, serde_json::Value>,
 	unique: bool,
+	propagate_failure: bool,
 	error: Option<BuilderError>,
 	_marker: PhantomData<I>,
 }
@@ -37,6 +38,7 @@ where
 			repr,
 			tags: serde_json::Map::new(),
 			unique: false,
+			propagate_failure: false,
 			error: from_workflow.then_some(BuilderError::CannotDispatchFromOpInWorkflow),
 			_marker: PhantomData,
 		}
@@ -84,6 +86,18 @@ where
 		self
 	}
 
+	/// Makes `output` return `WorkflowError::FailedWorkflowOutput` if the workflow failed instead of waiting
+	/// for it to be woken and complete.
+	pub fn propagate_failure(mut self) -> Self {
+		if self.error.is_some() {
+			return self;
+		}
+
+		self.propagate_failure = true;
+
+		self
+	}
+
 	#[tracing::instrument(skip_all, fields(workflow_name=I::Workflow::NAME, workflow_id, unique=self.unique))]
 	pub async fn dispatch(self) -> GlobalResult<Uuid> {
 		if let Some(err) = self.error {
@@ -157,6 +171,7 @@ where
 		}
 
 		let db = self.db.clone();
+		let propagate_failure = self.propagate_failure;
 
 		let workflow_id = if let Ok(workflow_id) = self.repr.as_workflow_id() {
 			workflow_id
@@ -164,7 +179,7 @@ where
 			self.dispatch().await?
 		};
 
-		common::wait_for_workflow_output::<I::Workflow>(&db, workflow_id)
+		common::wait_for_workflow_output::<I::Workflow>(&db, workflow_id, propagate_failure)
 			.in_current_span()
 			.await
 	}
@@ -176,4 +191,22 @@ where
 
 		db.get_workflow(workflow_id).await.map_err(GlobalError::raw)
 	}
+
+	/// Cancels the workflow. Its compensation handlers run the next time it is woken. Does nothing if the
+	/// workflow is already complete.
+	#[tracing::instrument(skip_all, fields(workflow_name=I::Workflow::NAME))]
+	pub async fn cancel(self) -> GlobalResult<()> {
+		if let Some(err) = self.error {
+			return Err(err.into());
+		}
+
+		let workflow_id = self.repr.as_workflow_id()?;
+
+		tracing::debug!(%workflow_id, "cancelling workflow");
+
+		self.db
+			.cancel_workflow(workflow_id)
+			.await
+			.map_err(GlobalError::raw)
+	}
 }
@@ -22,6 +22,7 @@ pub struct SubWorkflowBuilder<'a, T, I: WorkflowInput> {
 	repr: T,
 	tags: serde_json::Map<String, serde_json::Value>,
 	unique: bool,
+	propagate_failure: bool,
 	error: Option<BuilderError>,
 	_marker: PhantomData<I>,
 }
@@ -40,6 +41,7 @@ where
 			repr,
 			tags: serde_json::Map::new(),
 			unique: false,
+			propagate_failure: false,
 			error: None,
 			_marker: PhantomData,
 		}
@@ -87,6 +89,18 @@ where
 		self
 	}
 
+	/// Makes `output` return `WorkflowError::FailedWorkflowOutput` if the sub workflow failed. By default the
+	/// parent keeps waiting since a failed workflow can still be woken and complete.
+	pub fn propagate_failure(mut self) -> Self {
+		if self.error.is_some() {
+			return self;
+		}
+
+		self.propagate_failure = true;
+
+		self
+	}
+
 	#[tracing::instrument(skip_all)]
 	pub async fn dispatch(self) -> GlobalResult<Uuid> {
 		self.ctx.check_stop().map_err(GlobalError::raw)?;
@@ -198,6 +212,8 @@ where
 
 		tracing::Span::current().record("sub_workflow_id", id.to_string());
 
+		ctx.track_sub_workflow(id);
+
 		// Move to next event
 		ctx.cursor_mut().update(&location);
 
@@ -284,6 +300,18 @@ where
 				.ok_or(WorkflowError::WorkflowNotFound)
 				.map_err(GlobalError::raw)?;
 
+			// Cancelled workflows will never have an output
+			if workflow.is_cancelled() {
+				return Err(GlobalError::raw(WorkflowError::CancelledWorkflowOutput(
+					sub_workflow_id,
+				)));
+			} else if self.propagate_failure && workflow.is_dead() {
+				return Err(GlobalError::raw(WorkflowError::FailedWorkflowOutput(
+					sub_workflow_id,
+					workflow.error.unwrap_or_default(),
+				)));
+			}
+
 			if let Some(output) = workflow
 				.parse_output::<<I as WorkflowInput>::Workflow>()
 				.map_err(GlobalError::raw)?
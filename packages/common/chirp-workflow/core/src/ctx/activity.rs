@@ -13,6 +13,7 @@ use crate::{
 	message::{Message, NatsMessage},
 	operation::{Operation, OperationInput},
 	utils::tags::AsTags,
+	workflow::Workflow,
 };
 
 #[derive(Clone)]
@@ -109,6 +110,17 @@ impl ActivityCtx {
 			.map_err(GlobalError::raw)
 	}
 
+	/// Finds the first incomplete workflow with the given tags.
+	#[tracing::instrument(skip_all, ret(Debug), fields(workflow_name=W::NAME))]
+	pub async fn find_workflow<W: Workflow>(
+		&self,
+		tags: impl AsTags,
+	) -> GlobalResult<Option<Uuid>> {
+		common::find_workflow::<W>(&self.db, tags)
+			.in_current_span()
+			.await
+	}
+
 	/// IMPORTANT: This is intended for ephemeral realtime events and should be used carefully. Use
 	/// signals if you need this to be durable.
 	#[tracing::instrument(skip_all, fields(message=M::NAME))]
@@ -12,18 +12,22 @@ pub const RETRY_TIMEOUT_MS: usize = 1000;
 pub const WORKFLOW_TIMEOUT: Duration = Duration::from_secs(60);
 
 use crate::{
+	activity::ActivityBackoff,
 	ctx::OperationCtx,
 	db::DatabaseHandle,
 	error::WorkflowError,
 	operation::{Operation, OperationInput},
+	query::Query,
 	workflow::Workflow,
 };
 
-/// Polls the database for the workflow.
+/// Polls the database for the workflow. If `propagate_failure` is set, returns an error instead of waiting
+/// if the workflow failed.
 /// 60 second timeout.
 pub async fn wait_for_workflow_output<W: Workflow>(
 	db: &DatabaseHandle,
 	workflow_id: Uuid,
+	propagate_failure: bool,
 ) -> GlobalResult<W::Output> {
 	tracing::debug!("waiting for workflow");
 
@@ -42,6 +46,19 @@ pub async fn wait_for_workflow_output<W: Workflow>(
 				.map_err(GlobalError::raw)?
 				.ok_or(WorkflowError::WorkflowNotFound)
 				.map_err(GlobalError::raw)?;
+
+			// Cancelled workflows will never have an output
+			if workflow.is_cancelled() {
+				return Err(GlobalError::raw(WorkflowError::CancelledWorkflowOutput(
+					workflow_id,
+				)));
+			} else if propagate_failure && workflow.is_dead() {
+				return Err(GlobalError::raw(WorkflowError::FailedWorkflowOutput(
+					workflow_id,
+					workflow.error.unwrap_or_default(),
+				)));
+			}
+
 			if let Some(output) = workflow.parse_output::<W>().map_err(GlobalError::raw)? {
 				return Ok(output);
 			}
@@ -66,6 +83,32 @@ pub async fn find_workflow<W: Workflow>(
 		.map_err(GlobalError::raw)
 }
 
+/// Answers a query using the current loop state of the given workflow. Returns `None` if the workflow is
+/// not currently running a loop.
+pub async fn query<Q: Query>(
+	db: &DatabaseHandle,
+	workflow_id: Uuid,
+) -> GlobalResult<Option<Q::Output>> {
+	tracing::debug!(%workflow_id, "query call");
+
+	let Some(state) = db
+		.get_workflow_loop_state(workflow_id, <Q::Workflow as Workflow>::NAME)
+		.await
+		.map_err(GlobalError::raw)?
+	else {
+		return Ok(None);
+	};
+
+	let state = serde_json::from_str::<Q::State>(state.get())
+		.map_err(WorkflowError::DeserializeLoopState)
+		.map_err(GlobalError::raw)?;
+
+	Q::handle(&state)
+		.map(Some)
+		.map_err(WorkflowError::QueryFailure)
+		.map_err(GlobalError::raw)
+}
+
 pub async fn op<I>(
 	db: &DatabaseHandle,
 	config: &rivet_config::Config,
@@ -105,7 +148,8 @@ where
 
 	let res = tokio::time::timeout(I::Operation::TIMEOUT, I::Operation::run(&ctx, &input))
 		.await
-		.map_err(|_| WorkflowError::OperationTimeout(0))
+		// Replaced with the backoff of the activity the operation was called from, see `WorkflowCtx::activity`
+		.map_err(|_| WorkflowError::OperationTimeout(0, ActivityBackoff::DEFAULT))
 		.map(|res| {
 			res.map_err(WorkflowError::OperationFailure)
 				.map_err(GlobalError::raw)
@@ -15,6 +15,7 @@ use crate::{
 	error::WorkflowResult,
 	message::{Message, NatsMessage},
 	operation::{Operation, OperationInput},
+	query::Query,
 	signal::Signal,
 	utils::tags::AsTags,
 	workflow::{Workflow, WorkflowInput},
@@ -93,6 +94,14 @@ impl StandaloneCtx {
 		builder::workflow::WorkflowBuilder::new(self.db.clone(), self.ray_id, input, false)
 	}
 
+	/// Answers a query using the current state of the given workflow without modifying its history.
+	#[tracing::instrument(skip_all, ret(Debug), fields(query_name=Q::NAME))]
+	pub async fn query<Q: Query>(&self, workflow_id: Uuid) -> GlobalResult<Option<Q::Output>> {
+		common::query::<Q>(&self.db, workflow_id)
+			.in_current_span()
+			.await
+	}
+
 	/// Creates a signal builder.
 	pub fn signal<T: Signal + Serialize>(&self, body: T) -> builder::signal::SignalBuilder<T> {
 		builder::signal::SignalBuilder::new(self.db.clone(), self.ray_id, body, false)
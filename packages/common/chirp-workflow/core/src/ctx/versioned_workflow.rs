@@ -1,11 +1,15 @@
 use global_error::{GlobalError, GlobalResult};
 use serde::{de::DeserializeOwned, Serialize};
 use tracing::Instrument;
+use uuid::Uuid;
 
 use crate::{
 	activity::{Activity, ActivityInput},
 	builder::{workflow as builder, WorkflowRepr},
-	ctx::{workflow::Loop, WorkflowCtx},
+	ctx::{
+		workflow::{Loop, SubWorkflowResult},
+		WorkflowCtx,
+	},
 	executable::{AsyncResult, Executable},
 	listen::{CustomListener, Listen},
 	message::Message,
@@ -152,6 +156,46 @@ impl<'a> VersionedWorkflowCtx<'a> {
 		})
 	}
 
+	/// Runs workflow steps once and checkpoints their output. See `WorkflowCtx::snapshot`.
+	#[tracing::instrument(skip_all)]
+	pub async fn snapshot<F, T>(&mut self, cb: F) -> GlobalResult<T>
+	where
+		F: for<'b> FnOnce(&'b mut WorkflowCtx) -> AsyncResult<'b, T>,
+		T: Serialize + DeserializeOwned,
+	{
+		wrap!(self, "snapshot", {
+			self.inner.snapshot(cb).in_current_span().await
+		})
+	}
+
+	/// Waits for the first of the given sub workflows to finish. See `WorkflowCtx::select_sub_workflows`.
+	#[tracing::instrument(skip_all, fields(sub_workflow_name=W::NAME))]
+	pub async fn select_sub_workflows<W: Workflow>(
+		&mut self,
+		sub_workflow_ids: &[Uuid],
+	) -> GlobalResult<(Uuid, SubWorkflowResult<W::Output>)> {
+		wrap!(self, "select sub workflows", {
+			self.inner
+				.select_sub_workflows::<W>(sub_workflow_ids)
+				.in_current_span()
+				.await
+		})
+	}
+
+	/// Waits for all of the given sub workflows to finish. See `WorkflowCtx::join_all_sub_workflows`.
+	#[tracing::instrument(skip_all, fields(sub_workflow_name=W::NAME))]
+	pub async fn join_all_sub_workflows<W: Workflow>(
+		&mut self,
+		sub_workflow_ids: &[Uuid],
+	) -> GlobalResult<Vec<SubWorkflowResult<W::Output>>> {
+		wrap!(self, "join all sub workflows", {
+			self.inner
+				.join_all_sub_workflows::<W>(sub_workflow_ids)
+				.in_current_span()
+				.await
+		})
+	}
+
 	#[tracing::instrument(skip_all)]
 	pub async fn sleep(&mut self, duration: impl DurationToMillis) -> GlobalResult<()> {
 		wrap!(self, "sleep", {
@@ -1,19 +1,19 @@
 use std::{
-	sync::Arc,
+	sync::{Arc, Mutex},
 	time::{Duration, Instant},
 };
 
-use futures_util::StreamExt;
+use futures_util::{FutureExt, StreamExt};
 use global_error::{GlobalError, GlobalResult};
 use opentelemetry::trace::SpanContext;
-use serde::{de::DeserializeOwned, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use tokio::sync::watch;
 use tracing::Instrument;
 use tracing_opentelemetry::OpenTelemetrySpanExt;
 use uuid::Uuid;
 
 use crate::{
-	activity::{Activity, ActivityInput},
+	activity::{Activity, ActivityInput, RetryPolicy},
 	builder::{workflow as builder, WorkflowRepr},
 	ctx::{ActivityCtx, ListenCtx, MessageCtx, VersionedWorkflowCtx},
 	db::{DatabaseHandle, PulledWorkflowData},
@@ -43,6 +43,10 @@ const DB_ACTION_RETRY: Duration = Duration::from_millis(150);
 /// Most db action retries
 const MAX_DB_ACTION_RETRIES: usize = 5;
 
+/// A compensation handler registered with `WorkflowCtx::on_cancel`.
+type Compensation =
+	Box<dyn for<'a> FnOnce(&'a mut WorkflowCtx) -> AsyncResult<'a, ()> + Send + Sync>;
+
 // NOTE: Clonable because of inner arcs
 #[derive(Clone)]
 pub struct WorkflowCtx {
@@ -74,6 +78,13 @@ pub struct WorkflowCtx {
 	msg_ctx: MessageCtx,
 	/// Used to stop workflow execution by the worker.
 	stop: watch::Receiver<()>,
+
+	/// Whether or not this workflow was cancelled. Always false for the compensation branch.
+	cancelled: bool,
+	/// Compensation handlers registered with `on_cancel`. Shared between all branches of this workflow.
+	compensations: Arc<Mutex<Vec<Compensation>>>,
+	/// Sub workflows dispatched by this workflow (new or replayed). Cancelled along with this workflow.
+	sub_workflow_ids: Arc<Mutex<Vec<Uuid>>>,
 }
 
 impl WorkflowCtx {
@@ -112,6 +123,10 @@ impl WorkflowCtx {
 
 			msg_ctx,
 			stop,
+
+			cancelled: data.cancel_ts.is_some(),
+			compensations: Arc::new(Mutex::new(Vec::new())),
+			sub_workflow_ids: Arc::new(Mutex::new(Vec::new())),
 		})
 	}
 
@@ -144,8 +159,10 @@ impl WorkflowCtx {
 
 		tracing::debug!("running workflow");
 
-		// Check for stop before running
-		self.check_stop()?;
+		// Check for stop before running. Cancellation is handled by the first step of the workflow
+		if self.stop.has_changed().unwrap_or(true) {
+			return Err(WorkflowError::WorkflowStopped);
+		}
 
 		// Lookup workflow
 		let workflow = self.registry.get_workflow(&self.name)?;
@@ -153,6 +170,13 @@ impl WorkflowCtx {
 		// Run workflow
 		let mut res = (workflow.run)(&mut self).await;
 
+		// Run compensation handlers
+		if let Err(WorkflowError::WorkflowCancelled) = res {
+			if let Err(err) = self.compensate().await {
+				res = Err(err);
+			}
+		}
+
 		// Validate no leftover events
 		if res.is_ok() {
 			if let Err(err) = self.cursor().check_clear() {
@@ -201,11 +225,15 @@ impl WorkflowCtx {
 				// be retried when a signal is published
 				let wake_signals = err.signals();
 
-				// This sub workflow comes from a `wait_for_workflow` call on a workflow that did not
-				// finish. This workflow will be retried when the sub workflow completes
-				let wake_sub_workflow = err.sub_workflow();
+				// These sub workflows come from a `wait_for_workflow` or `join_all_sub_workflows` call on
+				// workflows that did not finish. This workflow will be retried when a sub workflow completes
+				let wake_sub_workflows = err.sub_workflows();
 
-				if err.is_recoverable() && !err.is_retryable() {
+				// Cancelled workflows are committed without a wake condition which is their terminal state.
+				// Workflows waiting on this one are woken and receive a `CancelledWorkflowOutput` error.
+				if let WorkflowError::WorkflowCancelled = err {
+					tracing::debug!("workflow cancelled");
+				} else if err.is_recoverable() && !err.is_retryable() {
 					tracing::debug!(?err, "workflow sleeping");
 				} else {
 					tracing::error!(?err, "workflow error");
@@ -234,7 +262,7 @@ impl WorkflowCtx {
 							wake_immediate,
 							wake_deadline_ts,
 							wake_signals,
-							wake_sub_workflow,
+							wake_sub_workflows,
 							&err_str,
 						)
 						.await;
@@ -262,6 +290,7 @@ impl WorkflowCtx {
 		event_id: &EventId,
 		location: &Location,
 		create_ts: i64,
+		retry_policy: &RetryPolicy,
 	) -> WorkflowResult<A::Output> {
 		tracing::debug!("running activity");
 
@@ -279,11 +308,16 @@ impl WorkflowCtx {
 
 		let start_instant = Instant::now();
 
-		let res = tokio::time::timeout(A::TIMEOUT, A::run(&ctx, input).in_current_span())
-			.await
-			.map_err(|_| WorkflowError::ActivityTimeout(0));
+		let res = tokio::time::timeout(
+			retry_policy.timeout(),
+			A::run(&ctx, input).in_current_span(),
+		)
+		.await
+		.map_err(|_| WorkflowError::ActivityTimeout(0, retry_policy.backoff));
 
 		let dt = start_instant.elapsed().as_secs_f64();
+		let retry_policy_val = serde_json::value::to_raw_value(retry_policy)
+			.map_err(WorkflowError::SerializeActivityRetryPolicy)?;
 
 		match res {
 			Ok(Ok(output)) => {
@@ -302,6 +336,7 @@ impl WorkflowCtx {
 						event_id,
 						create_ts,
 						&input_val,
+						&retry_policy_val,
 						Ok(&output_val),
 						self.loop_location(),
 					)
@@ -329,6 +364,7 @@ impl WorkflowCtx {
 						event_id,
 						create_ts,
 						&input_val,
+						&retry_policy_val,
 						Err(&err_str),
 						self.loop_location(),
 					)
@@ -343,7 +379,12 @@ impl WorkflowCtx {
 					.with_label_values(&[&self.name, A::NAME, &err_str])
 					.observe(dt);
 
-				Err(WorkflowError::ActivityFailure(err, 0))
+				// Fail immediately if the error is not retryable
+				if !err.is_workflow_recoverable() && !A::is_retryable(&err) {
+					Err(WorkflowError::ActivityNotRetryable(err))
+				} else {
+					Err(WorkflowError::ActivityFailure(err, 0, retry_policy.backoff))
+				}
 			}
 			Err(err) => {
 				tracing::debug!("activity timeout");
@@ -360,6 +401,7 @@ impl WorkflowCtx {
 						event_id,
 						create_ts,
 						&input_val,
+						&retry_policy_val,
 						Err(&err_str),
 						self.loop_location(),
 					)
@@ -438,6 +480,10 @@ impl WorkflowCtx {
 
 			msg_ctx: self.msg_ctx.clone(),
 			stop: self.stop.clone(),
+
+			cancelled: self.cancelled,
+			compensations: self.compensations.clone(),
+			sub_workflow_ids: self.sub_workflow_ids.clone(),
 		}
 	}
 
@@ -453,11 +499,79 @@ impl WorkflowCtx {
 	pub(crate) fn check_stop(&self) -> WorkflowResult<()> {
 		if self.stop.has_changed().unwrap_or(true) {
 			Err(WorkflowError::WorkflowStopped)
+		}
+		// Cancellation only takes effect once replay has caught up so that the point at which the workflow
+		// stops is deterministic
+		else if self.cancelled && self.cursor.current_event().is_none() {
+			Err(WorkflowError::WorkflowCancelled)
 		} else {
 			Ok(())
 		}
 	}
 
+	/// Runs all registered compensation handlers in reverse order of registration then cancels all sub
+	/// workflows dispatched by the workflow body. Sub workflows are cancelled last so that compensation
+	/// handlers can still signal and wait on them.
+	#[tracing::instrument(skip_all)]
+	async fn compensate(&mut self) -> WorkflowResult<()> {
+		// Sub workflows dispatched by compensation handlers are not cancelled
+		let sub_workflow_ids =
+			std::mem::take(&mut *self.sub_workflow_ids.lock().expect("poisoned"));
+		let compensations = std::mem::take(&mut *self.compensations.lock().expect("poisoned"));
+
+		if !compensations.is_empty() {
+			tracing::debug!(count=%compensations.len(), "running compensations");
+
+			// Compensations are run under coordinate 0 of the root which is never used by regular events. All
+			// compensation events are recorded (and replayed) like any other events
+			let mut branch = self.branch_inner(
+				self.input.clone(),
+				self.version,
+				Location::empty().join(Coordinate::simple(0)),
+			);
+			branch.cancelled = false;
+
+			for compensation in compensations.into_iter().rev() {
+				// Differentiate between WorkflowError and user error
+				if let Err(err) = compensation(&mut branch).await {
+					return match err {
+						GlobalError::Raw(inner_err) => {
+							match inner_err.downcast::<WorkflowError>() {
+								Ok(inner_err) => Err(*inner_err),
+								Err(err) => {
+									Err(WorkflowError::WorkflowFailure(GlobalError::Raw(err)))
+								}
+							}
+						}
+						_ => Err(WorkflowError::WorkflowFailure(err)),
+					};
+				}
+			}
+
+			branch.cursor().check_clear()?;
+		}
+
+		for sub_workflow_id in sub_workflow_ids {
+			tracing::debug!(%sub_workflow_id, "cancelling sub workflow");
+
+			match self.db.cancel_workflow(sub_workflow_id).await {
+				Ok(_) | Err(WorkflowError::WorkflowNotFound) => {}
+				Err(err) => return Err(err),
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Records a sub workflow so that it can be cancelled along with this workflow.
+	pub(crate) fn track_sub_workflow(&self, sub_workflow_id: Uuid) {
+		let mut sub_workflow_ids = self.sub_workflow_ids.lock().expect("poisoned");
+
+		if !sub_workflow_ids.contains(&sub_workflow_id) {
+			sub_workflow_ids.push(sub_workflow_id);
+		}
+	}
+
 	pub(crate) async fn wait_stop(&self) -> WorkflowResult<()> {
 		// We have to clone here because this function can't have a mutable reference to self. The state of
 		// the stop channel doesn't matter because it only ever receives one message
@@ -510,6 +624,11 @@ impl WorkflowCtx {
 			// Activity failed, retry
 			else {
 				let error_count = activity.error_count;
+				// Retry with the policy recorded when the activity first ran
+				let retry_policy = activity
+					.parse_retry_policy()
+					.map_err(GlobalError::raw)?
+					.unwrap_or_else(RetryPolicy::of::<I::Activity>);
 
 				// Backoff
 				if let Some(wake_deadline_ts) = self.wake_deadline_ts {
@@ -523,35 +642,44 @@ impl WorkflowCtx {
 				}
 
 				match self
-					.run_activity::<I::Activity>(&input, &event_id, &location, activity.create_ts)
+					.run_activity::<I::Activity>(
+						&input,
+						&event_id,
+						&location,
+						activity.create_ts,
+						&retry_policy,
+					)
 					.await
 				{
 					Err(err) => {
 						// Convert error in the case of max retries exceeded. This will only act on retryable
 						// errors
 						let err = match err {
-							WorkflowError::ActivityFailure(err, _) => {
-								if error_count + 1 >= I::Activity::MAX_RETRIES {
+							WorkflowError::ActivityFailure(err, _, backoff) => {
+								if error_count + 1 >= retry_policy.max_retries {
 									WorkflowError::ActivityMaxFailuresReached(err)
 								} else {
 									// Add error count to the error for backoff calculation
-									WorkflowError::ActivityFailure(err, error_count)
+									WorkflowError::ActivityFailure(err, error_count, backoff)
 								}
 							}
-							WorkflowError::ActivityTimeout(_) => {
-								if error_count + 1 >= I::Activity::MAX_RETRIES {
+							WorkflowError::ActivityTimeout(_, backoff) => {
+								if error_count + 1 >= retry_policy.max_retries {
 									WorkflowError::ActivityMaxFailuresReached(GlobalError::raw(err))
 								} else {
 									// Add error count to the error for backoff calculation
-									WorkflowError::ActivityTimeout(error_count)
+									WorkflowError::ActivityTimeout(error_count, backoff)
 								}
 							}
-							WorkflowError::OperationTimeout(_) => {
-								if error_count + 1 >= I::Activity::MAX_RETRIES {
+							WorkflowError::OperationTimeout(_, _) => {
+								if error_count + 1 >= retry_policy.max_retries {
 									WorkflowError::ActivityMaxFailuresReached(GlobalError::raw(err))
 								} else {
 									// Add error count to the error for backoff calculation
-									WorkflowError::OperationTimeout(error_count)
+									WorkflowError::OperationTimeout(
+										error_count,
+										retry_policy.backoff,
+									)
 								}
 							}
 							_ => err,
@@ -570,6 +698,7 @@ impl WorkflowCtx {
 				&event_id,
 				&location,
 				rivet_util::timestamp::now(),
+				&RetryPolicy::of::<I::Activity>(),
 			)
 			.await
 			.map_err(GlobalError::raw)?
@@ -590,6 +719,31 @@ impl WorkflowCtx {
 		exec.execute(self).await
 	}
 
+	/// Registers a compensation handler which runs if this workflow is cancelled. Handlers run in reverse
+	/// order of registration once the workflow reaches its cancellation point. Their steps are recorded in
+	/// history like any other step.
+	///
+	/// Handlers are re-registered on every replay so they must be registered deterministically. Handlers
+	/// registered in a loop iteration only apply while that iteration is running.
+	pub fn on_cancel<T>(&mut self, exec: T)
+	where
+		T: Executable + 'static,
+	{
+		fn wrap<F>(f: F) -> Compensation
+		where
+			F: for<'a> FnOnce(&'a mut WorkflowCtx) -> AsyncResult<'a, ()> + Send + Sync + 'static,
+		{
+			Box::new(f)
+		}
+
+		self.compensations
+			.lock()
+			.expect("poisoned")
+			.push(wrap(move |ctx| {
+				async move { exec.execute(ctx).await.map(|_| ()) }.boxed()
+			}));
+	}
+
 	/// Tests if the given error is unrecoverable. If it is, allows the user to run recovery code safely.
 	/// Should always be used when trying to handle activity errors manually.
 	#[tracing::instrument(skip_all)]
@@ -603,10 +757,14 @@ impl WorkflowCtx {
 					Ok(inner_err) => {
 						// Despite "history diverged" errors being unrecoverable, they should not have be returned
 						// by this function because the state of the history is already messed up and no new
-						// workflow items should be run.
+						// workflow items should be run. Cancellation must always unwind the workflow so that
+						// compensation handlers run.
 						if !inner_err.is_recoverable()
-							&& !matches!(*inner_err, WorkflowError::HistoryDiverged(_))
-						{
+							&& !matches!(
+								*inner_err,
+								WorkflowError::HistoryDiverged(_)
+									| WorkflowError::WorkflowCancelled
+							) {
 							self.cursor.inc();
 
 							Ok(Err(GlobalError::Raw(inner_err)))
@@ -881,9 +1039,18 @@ impl WorkflowCtx {
 					}
 
 					let start_instant2 = Instant::now();
+					let compensation_count = self.compensations.lock().expect("poisoned").len();
 
 					// Run loop
-					match cb(&mut iteration_branch, &mut state).await? {
+					let loop_res = cb(&mut iteration_branch, &mut state).await?;
+
+					// Compensation handlers registered in an iteration are scoped to that iteration
+					self.compensations
+						.lock()
+						.expect("poisoned")
+						.truncate(compensation_count);
+
+					match loop_res {
 						Loop::Continue => {
 							let dt2 = start_instant2.elapsed().as_secs_f64();
 							iteration += 1;
@@ -961,6 +1128,231 @@ impl WorkflowCtx {
 		Ok(output)
 	}
 
+	/// Runs workflow steps once and checkpoints their output. Once the steps complete, all history recorded
+	/// within the snapshot is deleted and replays return the checkpointed output without running the steps
+	/// again. Use this to keep the history of long lived workflows small.
+	///
+	/// NOTE: Compensation handlers and sub workflows registered within the snapshot are not restored on
+	/// replay. Events within nested loops are not deleted (they are handled by their loop).
+	#[tracing::instrument(skip_all)]
+	pub async fn snapshot<F, T>(&mut self, cb: F) -> GlobalResult<T>
+	where
+		F: for<'a> FnOnce(&'a mut WorkflowCtx) -> AsyncResult<'a, T>,
+		T: Serialize + DeserializeOwned,
+	{
+		self.check_stop().map_err(GlobalError::raw)?;
+
+		// Snapshots are stored as loop events
+		let history_res = self
+			.cursor
+			.compare_loop(self.version)
+			.map_err(GlobalError::raw)?;
+		let snapshot_location = self.cursor.current_location_for(&history_res);
+
+		// Snapshot existed before
+		let output = if let HistoryResult::Event(loop_event) = history_res {
+			loop_event.parse_output().map_err(GlobalError::raw)?
+		} else {
+			let state_val = serde_json::value::to_raw_value(&())
+				.map_err(WorkflowError::SerializeLoopOutput)
+				.map_err(GlobalError::raw)?;
+
+			// Insert event before snapshot is run so the history is consistent
+			self.db
+				.upsert_workflow_loop_event(
+					self.workflow_id,
+					&self.name,
+					&snapshot_location,
+					self.version,
+					0,
+					&state_val,
+					None,
+					self.loop_location(),
+				)
+				.await?;
+
+			None
+		};
+
+		// Snapshot complete
+		let output = if let Some(output) = output {
+			tracing::debug!("replaying snapshot output");
+
+			output
+		}
+		// Run snapshot
+		else {
+			tracing::debug!("running snapshot");
+
+			// Run steps at the same location as the first iteration of a loop. Setting the loop location
+			// allows all of the events in the branch to be deleted once the snapshot completes
+			let mut branch = self.branch_inner(
+				self.input.clone(),
+				self.version,
+				snapshot_location.join(Coordinate::simple(1)),
+			);
+			branch.loop_location = Some(snapshot_location.clone());
+
+			let output = cb(&mut branch).await?;
+
+			// Validate no leftover events
+			branch.cursor.check_clear().map_err(GlobalError::raw)?;
+
+			let output_val = serde_json::value::to_raw_value(&output)
+				.map_err(WorkflowError::SerializeLoopOutput)
+				.map_err(GlobalError::raw)?;
+
+			self.db
+				.commit_workflow_snapshot_event(
+					self.workflow_id,
+					&snapshot_location,
+					self.version,
+					&output_val,
+					self.loop_location(),
+				)
+				.await?;
+
+			output
+		};
+
+		// Move to next event
+		self.cursor.update(&snapshot_location);
+
+		Ok(output)
+	}
+
+	/// Waits for the first of the given sub workflows to finish and returns its ID and result. The result is
+	/// recorded in history so that replays always return the same sub workflow, even if others have since
+	/// finished. Sub workflows that failed or were cancelled are returned as `SubWorkflowResult::Failed` and
+	/// `SubWorkflowResult::Cancelled`.
+	#[tracing::instrument(skip_all, fields(sub_workflow_name=W::NAME))]
+	pub async fn select_sub_workflows<W: Workflow>(
+		&mut self,
+		sub_workflow_ids: &[Uuid],
+	) -> GlobalResult<(Uuid, SubWorkflowResult<W::Output>)> {
+		if sub_workflow_ids.is_empty() {
+			return Err(GlobalError::raw(WorkflowError::EmptySubWorkflowSelect));
+		}
+
+		let sub_workflow_ids = sub_workflow_ids.to_vec();
+
+		self.snapshot(move |ctx| {
+			async move {
+				let mut finished = ctx
+					.wait_for_sub_workflows::<W>(&sub_workflow_ids, false)
+					.await?;
+
+				// Sub workflows are returned in the order they were given
+				Ok(finished.remove(0))
+			}
+			.boxed()
+		})
+		.await
+	}
+
+	/// Waits for all of the given sub workflows to finish and returns their results in the same order as the
+	/// given IDs. Unlike joining `wait_for_workflow` calls, this does not fail if one of the sub workflows
+	/// failed or was cancelled, its result is `SubWorkflowResult::Failed` or `SubWorkflowResult::Cancelled`
+	/// instead.
+	#[tracing::instrument(skip_all, fields(sub_workflow_name=W::NAME))]
+	pub async fn join_all_sub_workflows<W: Workflow>(
+		&mut self,
+		sub_workflow_ids: &[Uuid],
+	) -> GlobalResult<Vec<SubWorkflowResult<W::Output>>> {
+		let sub_workflow_ids = sub_workflow_ids.to_vec();
+
+		self.snapshot(move |ctx| {
+			async move {
+				let finished = ctx
+					.wait_for_sub_workflows::<W>(&sub_workflow_ids, true)
+					.await?;
+
+				Ok(finished.into_iter().map(|(_, res)| res).collect())
+			}
+			.boxed()
+		})
+		.await
+	}
+
+	/// Polls the given sub workflows until any (or all, if `all` is set) of them have finished. If none
+	/// finish after polling the database, this workflow will go to sleep until one of the pending sub
+	/// workflows completes.
+	async fn wait_for_sub_workflows<W: Workflow>(
+		&self,
+		sub_workflow_ids: &[Uuid],
+		all: bool,
+	) -> GlobalResult<Vec<(Uuid, SubWorkflowResult<W::Output>)>> {
+		self.check_stop().map_err(GlobalError::raw)?;
+
+		tracing::debug!(?sub_workflow_ids, "waiting for sub workflows");
+
+		let mut wake_sub = self.db.wake_sub().await?;
+		let mut retries = self.db.max_sub_workflow_poll_retries();
+		let mut interval = tokio::time::interval(self.db.sub_workflow_poll_interval());
+		interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+		// Skip first tick, we wait after the db call instead of before
+		interval.tick().await;
+
+		loop {
+			let workflows = futures_util::future::try_join_all(sub_workflow_ids.iter().map(
+				|sub_workflow_id| {
+					async move {
+						let workflow = self
+							.db
+							.get_sub_workflow(self.workflow_id, &self.name, *sub_workflow_id)
+							.await?
+							.ok_or(WorkflowError::WorkflowNotFound)?;
+
+						// Dead workflows will never complete
+						let res = if workflow.is_cancelled() {
+							Some(SubWorkflowResult::Cancelled)
+						} else if workflow.is_dead() {
+							Some(SubWorkflowResult::Failed(
+								workflow.error.clone().unwrap_or_default(),
+							))
+						} else {
+							workflow
+								.parse_output::<W>()?
+								.map(SubWorkflowResult::Complete)
+						};
+
+						WorkflowResult::Ok((*sub_workflow_id, res))
+					}
+				},
+			))
+			.await
+			.map_err(GlobalError::raw)?;
+
+			let mut finished = Vec::new();
+			let mut pending = Vec::new();
+			for (sub_workflow_id, res) in workflows {
+				if let Some(res) = res {
+					finished.push((sub_workflow_id, res));
+				} else {
+					pending.push(sub_workflow_id);
+				}
+			}
+
+			if (all && pending.is_empty()) || (!all && !finished.is_empty()) {
+				return Ok(finished);
+			} else {
+				if retries == 0 {
+					return Err(GlobalError::raw(WorkflowError::SubWorkflowsIncomplete(
+						pending,
+					)));
+				}
+				retries -= 1;
+			}
+
+			// Poll and wait for a wake at the same time
+			tokio::select! {
+				_ = wake_sub.next() => {},
+				_ = interval.tick() => {},
+			}
+		}
+	}
+
 	#[tracing::instrument(skip_all)]
 	pub async fn sleep(&mut self, duration: impl DurationToMillis) -> GlobalResult<()> {
 		let ts = rivet_util::timestamp::now() as u64 + duration.to_millis()?;
@@ -1401,3 +1793,13 @@ pub enum Loop<T> {
 	Continue,
 	Break(T),
 }
+
+/// The result of a sub workflow awaited with `select_sub_workflows` or `join_all_sub_workflows`.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum SubWorkflowResult<T> {
+	Complete(T),
+	/// The sub workflow failed with the given error and will not be retried.
+	Failed(String),
+	/// The sub workflow was cancelled and has finished running its compensation handlers.
+	Cancelled,
+}
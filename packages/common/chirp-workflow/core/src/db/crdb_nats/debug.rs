@@ -8,10 +8,13 @@ use uuid::Uuid;
 
 use super::DatabaseCrdbNats;
 use crate::{
-	db::debug::{
-		ActivityError, ActivityEvent, DatabaseDebug, Event, EventData, HistoryData, LoopEvent,
-		MessageSendEvent, SignalData, SignalEvent, SignalSendEvent, SignalState, SubWorkflowEvent,
-		WorkflowData, WorkflowState,
+	db::{
+		debug::{
+			ActivityError, ActivityEvent, DatabaseDebug, Event, EventData, HistoryData,
+			HistorySize, LoopEvent, MessageSendEvent, SignalData, SignalEvent, SignalSendEvent,
+			SignalState, SubWorkflowEvent, WorkflowData, WorkflowState,
+		},
+		Database,
 	},
 	history::{
 		event::{EventType, RemovedEvent, SleepEvent, SleepState},
@@ -183,6 +186,15 @@ impl DatabaseDebug for DatabaseCrdbNats {
 		Ok(())
 	}
 
+	#[tracing::instrument(skip_all)]
+	async fn cancel_workflows(&self, workflow_ids: Vec<Uuid>) -> Result<()> {
+		for workflow_id in workflow_ids {
+			Database::cancel_workflow(self, workflow_id).await?;
+		}
+
+		Ok(())
+	}
+
 	#[tracing::instrument(skip_all)]
 	async fn get_workflow_history(
 		&self,
@@ -466,6 +478,101 @@ impl DatabaseDebug for DatabaseCrdbNats {
 		}))
 	}
 
+	#[tracing::instrument(skip_all)]
+	async fn get_workflow_history_sizes(
+		&self,
+		workflow_ids: Vec<Uuid>,
+	) -> Result<Vec<HistorySize>> {
+		let rows = sql_fetch_all!(
+			[self, (Uuid, bool, i64, i64)]
+			"
+			SELECT workflow_id, forgotten, COUNT(*), COALESCE(SUM(size), 0)::INT
+			FROM (
+				SELECT
+					workflow_id,
+					forgotten,
+					octet_length(input::TEXT) + COALESCE(octet_length(output::TEXT), 0) AS size
+				FROM db_workflow.workflow_activity_events
+				WHERE workflow_id = ANY($1)
+				UNION ALL
+				SELECT workflow_id, forgotten, octet_length(body::TEXT)
+				FROM db_workflow.workflow_signal_events
+				WHERE workflow_id = ANY($1)
+				UNION ALL
+				-- The sub workflow's input and tags are stored with the sub workflow instead of the event
+				SELECT
+					e.workflow_id,
+					e.forgotten,
+					octet_length(w.input::TEXT) + COALESCE(octet_length(w.tags::TEXT), 0)
+				FROM db_workflow.workflow_sub_workflow_events AS e
+				JOIN db_workflow.workflows AS w
+				ON w.workflow_id = e.sub_workflow_id
+				WHERE e.workflow_id = ANY($1)
+				UNION ALL
+				SELECT workflow_id, forgotten, octet_length(body::TEXT)
+				FROM db_workflow.workflow_signal_send_events
+				WHERE workflow_id = ANY($1)
+				UNION ALL
+				SELECT workflow_id, forgotten, octet_length(body::TEXT) + octet_length(tags::TEXT)
+				FROM db_workflow.workflow_message_send_events
+				WHERE workflow_id = ANY($1)
+				UNION ALL
+				SELECT
+					workflow_id,
+					forgotten,
+					octet_length(state::TEXT) + COALESCE(octet_length(output::TEXT), 0)
+				FROM db_workflow.workflow_loop_events
+				WHERE workflow_id = ANY($1)
+				UNION ALL
+				SELECT workflow_id, forgotten, 0
+				FROM db_workflow.workflow_sleep_events
+				WHERE workflow_id = ANY($1)
+				UNION ALL
+				SELECT workflow_id, forgotten, 0
+				FROM db_workflow.workflow_branch_events
+				WHERE workflow_id = ANY($1)
+				UNION ALL
+				SELECT workflow_id, forgotten, 0
+				FROM db_workflow.workflow_removed_events
+				WHERE workflow_id = ANY($1)
+				UNION ALL
+				SELECT workflow_id, forgotten, 0
+				FROM db_workflow.workflow_version_check_events
+				WHERE workflow_id = ANY($1)
+			)
+			GROUP BY workflow_id, forgotten
+			",
+			&workflow_ids,
+		)
+		.await?;
+
+		let mut sizes = workflow_ids
+			.into_iter()
+			.map(|workflow_id| HistorySize {
+				workflow_id,
+				events: 0,
+				forgotten_events: 0,
+				bytes: 0,
+			})
+			.collect::<Vec<_>>();
+
+		for (workflow_id, forgotten, count, bytes) in rows {
+			let Some(size) = sizes.iter_mut().find(|x| x.workflow_id == workflow_id) else {
+				continue;
+			};
+
+			if forgotten {
+				size.forgotten_events += usize::try_from(count)?;
+			} else {
+				size.events += usize::try_from(count)?;
+			}
+
+			size.bytes += usize::try_from(bytes)?;
+		}
+
+		Ok(sizes)
+	}
+
 	#[tracing::instrument(skip_all)]
 	async fn get_signals(&self, signal_ids: Vec<Uuid>) -> Result<Vec<SignalData>> {
 		let signals = sql_fetch_all!(
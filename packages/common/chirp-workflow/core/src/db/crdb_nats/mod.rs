@@ -3,14 +3,16 @@
 use std::{collections::HashSet, sync::Arc, time::Instant};
 
 use futures_util::{stream::BoxStream, StreamExt};
-use indoc::indoc;
+use indoc::{formatdoc, indoc};
 use rivet_pools::prelude::*;
 use sqlx::{pool::PoolConnection, Acquire, PgPool, Postgres};
 use tracing::Instrument;
 use types::*;
 use uuid::Uuid;
 
-use super::{Database, PulledWorkflowData, SignalData, WorkflowData};
+use super::{
+	Database, IdempotencyKey, PulledWorkflowData, ScheduleDispatch, SignalData, WorkflowData,
+};
 use crate::{
 	error::{WorkflowError, WorkflowResult},
 	history::{
@@ -38,6 +40,8 @@ const WORKER_INSTANCE_EXPIRED_THRESHOLD_MS: i64 = rivet_util::duration::seconds(
 const GC_LOCK_TIMEOUT_MS: i64 = rivet_util::duration::seconds(30);
 /// How long before overwriting an existing metrics lock.
 const METRICS_LOCK_TIMEOUT_MS: i64 = GC_LOCK_TIMEOUT_MS;
+/// Max amount of expired idempotency keys deleted in a single query.
+const IDEMPOTENCY_KEY_GC_BATCH: i64 = 1000;
 /// For SQL macros.
 const CONTEXT_NAME: &str = "chirp_workflow_crdb_nats_engine";
 /// For NATS wake mechanism.
@@ -201,7 +205,8 @@ impl Database for DatabaseCrdbNats {
 					wake_immediate = true,
 					wake_deadline_ts = NULL,
 					wake_signals = ARRAY[],
-					wake_sub_workflow_id = NULL
+					wake_sub_workflow_id = NULL,
+					wake_sub_workflow_ids = NULL
 				FROM db_workflow.worker_instances@worker_instances_ping_idx AS wi
 				WHERE
 					wi.last_ping_ts < $1 AND
@@ -254,6 +259,124 @@ impl Database for DatabaseCrdbNats {
 		Ok(())
 	}
 
+	#[tracing::instrument(skip_all)]
+	async fn clear_expired_idempotency_keys(&self) -> WorkflowResult<()> {
+		loop {
+			let res = sql_execute!(
+				[self]
+				"
+				DELETE FROM db_workflow.signal_idempotency_keys
+				WHERE expire_ts <= $1
+				LIMIT $2
+				",
+				rivet_util::timestamp::now(),
+				IDEMPOTENCY_KEY_GC_BATCH,
+			)
+			.await?;
+
+			if res.rows_affected() < IDEMPOTENCY_KEY_GC_BATCH as u64 {
+				break;
+			}
+		}
+
+		Ok(())
+	}
+
+	#[tracing::instrument(skip_all, fields(%schedule_name))]
+	async fn get_schedule_last_run_ts(&self, schedule_name: &str) -> WorkflowResult<Option<i64>> {
+		sql_fetch_optional!(
+			[self, (i64,)]
+			"
+			SELECT last_run_ts
+			FROM db_workflow.schedules
+			WHERE schedule_name = $1
+			",
+			schedule_name,
+		)
+		.await
+		.map(|row| row.map(|(last_run_ts,)| last_run_ts))
+	}
+
+	#[tracing::instrument(skip_all, fields(%schedule_name))]
+	async fn claim_schedule_run(
+		&self,
+		schedule_name: &str,
+		prev_run_ts: Option<i64>,
+		run_ts: i64,
+		dispatch: Option<ScheduleDispatch<'_>>,
+	) -> WorkflowResult<bool> {
+		let claim_query = if prev_run_ts.is_some() {
+			indoc!(
+				"
+				UPDATE db_workflow.schedules
+				SET last_run_ts = $3
+				WHERE
+					schedule_name = $1 AND
+					last_run_ts = $2
+				RETURNING 1
+				"
+			)
+		} else {
+			indoc!(
+				"
+				INSERT INTO db_workflow.schedules (schedule_name, last_run_ts)
+				VALUES ($1, $3)
+				ON CONFLICT DO NOTHING
+				RETURNING 1
+				"
+			)
+		};
+
+		let claimed = if let Some(dispatch) = dispatch {
+			// The workflow is only inserted if the run was claimed
+			let query = formatdoc!(
+				"
+				WITH
+					claim AS ({claim_query}),
+					insert_workflow AS (
+						INSERT INTO db_workflow.workflows (
+							workflow_id, workflow_name, create_ts, ray_id, tags, input, wake_immediate
+						)
+						SELECT $4, $5, $6, $7, $8, $9, true
+						WHERE EXISTS(SELECT 1 FROM claim)
+						RETURNING 1
+					)
+				SELECT 1 FROM claim
+				"
+			);
+
+			sql_fetch_optional!(
+				[self, (i64,)]
+				query.as_str(),
+				schedule_name,
+				prev_run_ts,
+				run_ts,
+				dispatch.workflow_id,
+				dispatch.workflow_name,
+				rivet_util::timestamp::now(),
+				dispatch.ray_id,
+				dispatch.tags,
+				sqlx::types::Json(dispatch.input),
+			)
+			.await?
+		} else {
+			sql_fetch_optional!(
+				[self, (i64,)]
+				claim_query,
+				schedule_name,
+				prev_run_ts,
+				run_ts,
+			)
+			.await?
+		};
+
+		if claimed.is_some() && dispatch.is_some() {
+			self.wake_worker();
+		}
+
+		Ok(claimed.is_some())
+	}
+
 	#[tracing::instrument(skip_all)]
 	async fn publish_metrics(&self, worker_instance_id: Uuid) -> WorkflowResult<()> {
 		let acquired_lock = sql_fetch_optional!(
@@ -500,7 +623,10 @@ impl Database for DatabaseCrdbNats {
 					wake_deadline_ts IS NOT NULL OR
 					cardinality(wake_signals) > 0 OR
 					wake_sub_workflow_id IS NOT NULL
-				) AS has_wake_condition
+				) AS has_wake_condition,
+				worker_instance_id IS NOT NULL AS is_active,
+				cancel_ts IS NOT NULL AS cancel_requested,
+				error
 			FROM db_workflow.workflows
 			WHERE workflow_id = $1
 			",
@@ -510,6 +636,53 @@ impl Database for DatabaseCrdbNats {
 		.map(|row| row.map(Into::into))
 	}
 
+	#[tracing::instrument(skip_all, fields(%workflow_id))]
+	async fn get_workflow_loop_state(
+		&self,
+		workflow_id: Uuid,
+		workflow_name: &str,
+	) -> WorkflowResult<Option<Box<serde_json::value::RawValue>>> {
+		let (actual_workflow_name,) = sql_fetch_optional!(
+			[self, (String,)]
+			"
+			SELECT workflow_name
+			FROM db_workflow.workflows
+			WHERE workflow_id = $1
+			",
+			workflow_id,
+		)
+		.await?
+		.ok_or(WorkflowError::WorkflowNotFound)?;
+
+		if actual_workflow_name != workflow_name {
+			return Err(WorkflowError::QueryWorkflowMismatch(
+				actual_workflow_name,
+				workflow_name.to_string(),
+			));
+		}
+
+		let loops = sql_fetch_all!(
+			[self, (Option<Location>, sqlx::types::Json<Box<serde_json::value::RawValue>>)]
+			"
+			SELECT location2, state
+			FROM db_workflow.workflow_loop_events
+			WHERE
+				workflow_id = $1 AND
+				output IS NULL AND
+				loop_location2 IS NULL AND
+				NOT forgotten
+			",
+			workflow_id,
+		)
+		.await?;
+
+		// Ordered in code instead of in SQL so both drivers pick the same loop
+		Ok(loops
+			.into_iter()
+			.max_by(|(a, _), (b, _)| a.cmp(b))
+			.map(|(_, state)| state.0))
+	}
+
 	#[tracing::instrument(skip_all, fields(%workflow_name))]
 	async fn find_workflow(
 		&self,
@@ -616,13 +789,27 @@ impl Database for DatabaseCrdbNats {
 						-- Not silenced
 						silence_ts IS NULL AND
 						wake_sub_workflow_id IS NOT NULL AND
-						-- Sub workflow completed
+						-- Sub workflow completed, failed, or was cancelled
 						(
 							SELECT true
 							FROM db_workflow.workflows@workflows_pred_sub_workflow_internal AS w2
 							WHERE
-								w2.workflow_id = w.wake_sub_workflow_id AND
-								output IS NOT NULL
+								(
+									w2.workflow_id = w.wake_sub_workflow_id OR
+									w2.workflow_id = ANY(w.wake_sub_workflow_ids)
+								) AND
+								(
+									w2.output IS NOT NULL OR
+									(
+										-- Dead
+										w2.error IS NOT NULL AND
+										w2.worker_instance_id IS NULL AND
+										NOT w2.wake_immediate AND
+										w2.wake_deadline_ts IS NULL AND
+										cardinality(w2.wake_signals) = 0 AND
+										w2.wake_sub_workflow_id IS NULL
+									)
+								)
 						)
 					LIMIT $5
 				)
@@ -633,7 +820,7 @@ impl Database for DatabaseCrdbNats {
 					last_pull_ts = $3
 				FROM select_pending_workflows AS pw
 				WHERE w.workflow_id = pw.workflow_id
-				RETURNING w.workflow_id, workflow_name, create_ts, ray_id, input, wake_deadline_ts
+				RETURNING w.workflow_id, workflow_name, create_ts, ray_id, input, wake_deadline_ts, cancel_ts
 				",
 			worker_instance_id,
 			filter,
@@ -679,7 +866,7 @@ impl Database for DatabaseCrdbNats {
 					activity_name AS name,
 					NULL AS auxiliary_id,
 					input_hash AS hash,
-					NULL AS input,
+					retry_policy AS input,
 					output AS output,
 					create_ts AS create_ts,
 					(
@@ -705,6 +892,7 @@ impl Database for DatabaseCrdbNats {
 					ev.activity_name,
 					ev.input_hash,
 					ev.output,
+					ev.retry_policy,
 					ev.create_ts
 				UNION ALL
 				-- Signal listen events
@@ -962,7 +1150,7 @@ impl Database for DatabaseCrdbNats {
 		immediate: bool,
 		wake_deadline_ts: Option<i64>,
 		wake_signals: &[&str],
-		wake_sub_workflow_id: Option<Uuid>,
+		wake_sub_workflows: &[Uuid],
 		error: &str,
 	) -> WorkflowResult<()> {
 		let start_instant = Instant::now();
@@ -973,10 +1161,14 @@ impl Database for DatabaseCrdbNats {
 			UPDATE db_workflow.workflows
 			SET
 				worker_instance_id = NULL,
-				wake_immediate = $2,
+				-- Keep immediate wake if the workflow was cancelled while running
+				wake_immediate = $2 OR COALESCE(cancel_ts >= last_pull_ts, false),
 				wake_deadline_ts = $3,
 				wake_signals = $4,
-				wake_sub_workflow_id = $5,
+				-- The first sub workflow is also written to the single column so that existing wake
+				-- condition checks still apply
+				wake_sub_workflow_id = $5[1],
+				wake_sub_workflow_ids = $5,
 				error = $6
 			WHERE workflow_id = $1
 			",
@@ -984,11 +1176,20 @@ impl Database for DatabaseCrdbNats {
 			immediate,
 			wake_deadline_ts,
 			wake_signals,
-			wake_sub_workflow_id,
+			wake_sub_workflows,
 			error,
 		)
 		.await?;
 
+		// Nothing will wake this workflow again (it failed or was cancelled), wake workflows waiting on it
+		if !immediate
+			&& wake_deadline_ts.is_none()
+			&& wake_signals.is_empty()
+			&& wake_sub_workflows.is_empty()
+		{
+			self.wake_worker();
+		}
+
 		// Wake worker again if the deadline is before the next tick
 		if let Some(deadline_ts) = wake_deadline_ts {
 			if deadline_ts
@@ -1006,6 +1207,62 @@ impl Database for DatabaseCrdbNats {
 		Ok(())
 	}
 
+	#[tracing::instrument(skip_all, fields(%workflow_id))]
+	async fn cancel_workflow(&self, workflow_id: Uuid) -> WorkflowResult<()> {
+		let (exists, dead, cancelled) = sql_fetch_one!(
+			[self, (bool, bool, bool)]
+			"
+			WITH
+				workflow AS (
+					SELECT
+						-- Nothing will wake a dead workflow, cancelling it would revive it
+						output IS NULL AND
+						cancel_ts IS NULL AND
+						worker_instance_id IS NULL AND
+						wake_immediate = FALSE AND
+						wake_deadline_ts IS NULL AND
+						cardinality(wake_signals) = 0 AND
+						wake_sub_workflow_id IS NULL AS dead
+					FROM db_workflow.workflows
+					WHERE workflow_id = $1
+				),
+				update_workflow AS (
+					UPDATE db_workflow.workflows
+					SET
+						cancel_ts = $2,
+						wake_immediate = true
+					WHERE
+						workflow_id = $1 AND
+						output IS NULL AND
+						cancel_ts IS NULL AND
+						NOT (SELECT dead FROM workflow)
+					RETURNING 1
+				)
+			SELECT
+				EXISTS(SELECT 1 FROM workflow),
+				COALESCE((SELECT dead FROM workflow), false),
+				EXISTS(SELECT 1 FROM update_workflow)
+			",
+			workflow_id,
+			rivet_util::timestamp::now(),
+		)
+		.await?;
+
+		if !exists {
+			return Err(WorkflowError::WorkflowNotFound);
+		}
+
+		if dead {
+			return Err(WorkflowError::WorkflowDead(workflow_id));
+		}
+
+		if cancelled {
+			self.wake_worker();
+		}
+
+		Ok(())
+	}
+
 	#[tracing::instrument(skip_all)]
 	async fn pull_next_signal(
 		&self,
@@ -1123,27 +1380,84 @@ impl Database for DatabaseCrdbNats {
 		signal_id: Uuid,
 		signal_name: &str,
 		body: &serde_json::value::RawValue,
-	) -> WorkflowResult<()> {
-		sql_execute!(
-			[self]
-			"
-			INSERT INTO db_workflow.signals (
-				signal_id, workflow_id, signal_name, body, ray_id, create_ts
-			)			
-			VALUES ($1, $2, $3, $4, $5, $6)
-			",
-			signal_id,
-			workflow_id,
-			signal_name,
-			sqlx::types::Json(body),
-			ray_id,
-			rivet_util::timestamp::now(),
-		)
-		.await?;
+		idempotency_key: Option<&IdempotencyKey>,
+	) -> WorkflowResult<Uuid> {
+		let signal_id = if let Some(idempotency_key) = idempotency_key {
+			// Only inserts the signal if the idempotency key does not exist or has expired. Returns the
+			// original signal id otherwise
+			let (signal_id,) = sql_fetch_one!(
+				[self, (Uuid,)]
+				"
+				WITH
+					claim AS (
+						INSERT INTO db_workflow.signal_idempotency_keys (
+							target, signal_name, idempotency_key, signal_id, create_ts, expire_ts
+						)
+						VALUES ($2::TEXT, $3, $7, $1, $6, $6 + $8)
+						ON CONFLICT (target, signal_name, idempotency_key) DO UPDATE
+						SET
+							signal_id = excluded.signal_id,
+							create_ts = excluded.create_ts,
+							expire_ts = excluded.expire_ts
+						WHERE signal_idempotency_keys.expire_ts <= excluded.create_ts
+						RETURNING signal_id
+					),
+					insert_signal AS (
+						INSERT INTO db_workflow.signals (
+							signal_id, workflow_id, signal_name, body, ray_id, create_ts
+						)
+						SELECT $1, $2, $3, $4, $5, $6
+						WHERE EXISTS(SELECT 1 FROM claim)
+						RETURNING 1
+					)
+				SELECT COALESCE(
+					(SELECT signal_id FROM claim),
+					(
+						SELECT signal_id
+						FROM db_workflow.signal_idempotency_keys
+						WHERE
+							target = $2::TEXT AND
+							signal_name = $3 AND
+							idempotency_key = $7
+					)
+				)
+				",
+				signal_id,
+				workflow_id,
+				signal_name,
+				sqlx::types::Json(body),
+				ray_id,
+				rivet_util::timestamp::now(),
+				&idempotency_key.key,
+				idempotency_key.window_ms,
+			)
+			.await?;
+
+			signal_id
+		} else {
+			sql_execute!(
+				[self]
+				"
+				INSERT INTO db_workflow.signals (
+					signal_id, workflow_id, signal_name, body, ray_id, create_ts
+				)			
+				VALUES ($1, $2, $3, $4, $5, $6)
+				",
+				signal_id,
+				workflow_id,
+				signal_name,
+				sqlx::types::Json(body),
+				ray_id,
+				rivet_util::timestamp::now(),
+			)
+			.await?;
+
+			signal_id
+		};
 
 		self.wake_worker();
 
-		Ok(())
+		Ok(signal_id)
 	}
 
 	#[tracing::instrument(skip_all)]
@@ -1154,27 +1468,84 @@ impl Database for DatabaseCrdbNats {
 		signal_id: Uuid,
 		signal_name: &str,
 		body: &serde_json::value::RawValue,
-	) -> WorkflowResult<()> {
-		sql_execute!(
-			[self]
-			"
-			INSERT INTO db_workflow.tagged_signals (
-				signal_id, tags, signal_name, body, ray_id, create_ts
-			)			
-			VALUES ($1, $2, $3, $4, $5, $6)
-			",
-			signal_id,
-			tags,
-			signal_name,
-			sqlx::types::Json(body),
-			ray_id,
-			rivet_util::timestamp::now(),
-		)
-		.await?;
+		idempotency_key: Option<&IdempotencyKey>,
+	) -> WorkflowResult<Uuid> {
+		let signal_id = if let Some(idempotency_key) = idempotency_key {
+			// Only inserts the signal if the idempotency key does not exist or has expired. Returns the
+			// original signal id otherwise
+			let (signal_id,) = sql_fetch_one!(
+				[self, (Uuid,)]
+				"
+				WITH
+					claim AS (
+						INSERT INTO db_workflow.signal_idempotency_keys (
+							target, signal_name, idempotency_key, signal_id, create_ts, expire_ts
+						)
+						VALUES ($2::JSONB::TEXT, $3, $7, $1, $6, $6 + $8)
+						ON CONFLICT (target, signal_name, idempotency_key) DO UPDATE
+						SET
+							signal_id = excluded.signal_id,
+							create_ts = excluded.create_ts,
+							expire_ts = excluded.expire_ts
+						WHERE signal_idempotency_keys.expire_ts <= excluded.create_ts
+						RETURNING signal_id
+					),
+					insert_signal AS (
+						INSERT INTO db_workflow.tagged_signals (
+							signal_id, tags, signal_name, body, ray_id, create_ts
+						)
+						SELECT $1, $2, $3, $4, $5, $6
+						WHERE EXISTS(SELECT 1 FROM claim)
+						RETURNING 1
+					)
+				SELECT COALESCE(
+					(SELECT signal_id FROM claim),
+					(
+						SELECT signal_id
+						FROM db_workflow.signal_idempotency_keys
+						WHERE
+							target = $2::JSONB::TEXT AND
+							signal_name = $3 AND
+							idempotency_key = $7
+					)
+				)
+				",
+				signal_id,
+				tags,
+				signal_name,
+				sqlx::types::Json(body),
+				ray_id,
+				rivet_util::timestamp::now(),
+				&idempotency_key.key,
+				idempotency_key.window_ms,
+			)
+			.await?;
+
+			signal_id
+		} else {
+			sql_execute!(
+				[self]
+				"
+				INSERT INTO db_workflow.tagged_signals (
+					signal_id, tags, signal_name, body, ray_id, create_ts
+				)			
+				VALUES ($1, $2, $3, $4, $5, $6)
+				",
+				signal_id,
+				tags,
+				signal_name,
+				sqlx::types::Json(body),
+				ray_id,
+				rivet_util::timestamp::now(),
+			)
+			.await?;
+
+			signal_id
+		};
 
 		self.wake_worker();
 
-		Ok(())
+		Ok(signal_id)
 	}
 
 	#[tracing::instrument(skip_all)]
@@ -1406,6 +1777,7 @@ impl Database for DatabaseCrdbNats {
 		event_id: &EventId,
 		create_ts: i64,
 		input: &serde_json::value::RawValue,
+		retry_policy: &serde_json::value::RawValue,
 		res: Result<&serde_json::value::RawValue, &str>,
 		loop_location: Option<&Location>,
 	) -> WorkflowResult<()> {
@@ -1423,9 +1795,10 @@ impl Database for DatabaseCrdbNats {
 						input,
 						output,
 						create_ts,
-						loop_location2
+						loop_location2,
+						retry_policy
 					)
-					VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+					VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
 					ON CONFLICT (workflow_id, location2_hash) DO UPDATE
 					SET output = EXCLUDED.output
 					",
@@ -1438,6 +1811,7 @@ impl Database for DatabaseCrdbNats {
 					sqlx::types::Json(output),
 					create_ts,
 					loop_location,
+					sqlx::types::Json(retry_policy),
 				)
 				.await?;
 			}
@@ -1455,9 +1829,10 @@ impl Database for DatabaseCrdbNats {
 								input_hash,
 								input,
 								create_ts,
-								loop_location2
+								loop_location2,
+								retry_policy
 							)
-							VALUES ($1, $2, $3, $4, $5, $6, $8, $9)
+							VALUES ($1, $2, $3, $4, $5, $6, $8, $9, $11)
 							ON CONFLICT (workflow_id, location2_hash) DO NOTHING
 							RETURNING 1
 						),
@@ -1480,6 +1855,7 @@ impl Database for DatabaseCrdbNats {
 					create_ts,
 					loop_location,
 					rivet_util::timestamp::now(),
+					sqlx::types::Json(retry_policy),
 				)
 				.await?;
 			}
@@ -1680,6 +2056,161 @@ impl Database for DatabaseCrdbNats {
 		Ok(())
 	}
 
+	#[tracing::instrument(skip_all)]
+	async fn commit_workflow_snapshot_event(
+		&self,
+		workflow_id: Uuid,
+		location: &Location,
+		version: usize,
+		output: &serde_json::value::RawValue,
+		loop_location: Option<&Location>,
+	) -> WorkflowResult<()> {
+		self.txn(|| async {
+			let mut conn = self.conn().await?;
+			let mut tx = conn.begin().await.map_err(WorkflowError::Sqlx)?;
+
+			// Snapshots are stored as a loop event with a single completed iteration
+			sql_execute!(
+				[self, @tx &mut tx]
+				"
+				INSERT INTO db_workflow.workflow_loop_events (
+					workflow_id,
+					location2,
+					version,
+					iteration,
+					state,
+					output,
+					loop_location2
+				)
+				VALUES ($1, $2, $3, 1, 'null'::JSONB, $4, $5)
+				ON CONFLICT (workflow_id, location2_hash) DO UPDATE
+				SET
+					iteration = 1,
+					output = $4
+				RETURNING 1
+				",
+				workflow_id,
+				location,
+				version as i64,
+				sqlx::types::Json(output),
+				loop_location,
+			)
+			.await?;
+
+			// Truncate all history recorded within the snapshot
+			sql_execute!(
+				[self, @tx &mut tx]
+				"
+				WITH
+					delete_activity_errors AS (
+						DELETE FROM db_workflow.workflow_activity_errors
+						WHERE
+							workflow_id = $1 AND
+							location2_hash IN (
+								SELECT location2_hash
+								FROM db_workflow.workflow_activity_events
+								WHERE
+									workflow_id = $1 AND
+									loop_location2_hash = $2
+							)
+						RETURNING 1
+					),
+					delete_activity_events AS (
+						DELETE FROM db_workflow.workflow_activity_events@workflow_activity_events_workflow_id_loop_location2_hash_idx
+						WHERE
+							workflow_id = $1 AND
+							loop_location2_hash = $2 AND
+							forgotten = FALSE
+						RETURNING 1
+					),
+					delete_signal_events AS (
+						DELETE FROM db_workflow.workflow_signal_events@workflow_signal_events_workflow_id_loop_location2_hash_idx
+						WHERE
+							workflow_id = $1 AND
+							loop_location2_hash = $2 AND
+							forgotten = FALSE
+						RETURNING 1
+					),
+					delete_sub_workflow_events AS (
+						DELETE FROM db_workflow.workflow_sub_workflow_events@workflow_sub_workflow_events_workflow_id_loop_location2_hash_idx
+						WHERE
+							workflow_id = $1 AND
+							loop_location2_hash = $2 AND
+							forgotten = FALSE
+						RETURNING 1
+					),
+					delete_signal_send_events AS (
+						DELETE FROM db_workflow.workflow_signal_send_events@workflow_signal_send_events_workflow_id_loop_location2_hash_idx
+						WHERE
+							workflow_id = $1 AND
+							loop_location2_hash = $2 AND
+							forgotten = FALSE
+						RETURNING 1
+					),
+					delete_message_send_events AS (
+						DELETE FROM db_workflow.workflow_message_send_events@workflow_message_send_events_workflow_id_loop_location2_hash_idx
+						WHERE
+							workflow_id = $1 AND
+							loop_location2_hash = $2 AND
+							forgotten = FALSE
+						RETURNING 1
+					),
+					delete_loop_events AS (
+						DELETE FROM db_workflow.workflow_loop_events@workflow_loop_events_workflow_id_loop_location2_hash_idx
+						WHERE
+							workflow_id = $1 AND
+							loop_location2_hash = $2 AND
+							forgotten = FALSE
+						RETURNING 1
+					),
+					delete_sleep_events AS (
+						DELETE FROM db_workflow.workflow_sleep_events@workflow_sleep_events_workflow_id_loop_location2_hash_idx
+						WHERE
+							workflow_id = $1 AND
+							loop_location2_hash = $2 AND
+							forgotten = FALSE
+						RETURNING 1
+					),
+					delete_branch_events AS (
+						DELETE FROM db_workflow.workflow_branch_events@workflow_branch_events_workflow_id_loop_location_hash_idx
+						WHERE
+							workflow_id = $1 AND
+							loop_location_hash = $2 AND
+							forgotten = FALSE
+						RETURNING 1
+					),
+					delete_removed_events AS (
+						DELETE FROM db_workflow.workflow_removed_events@workflow_removed_events_workflow_id_loop_location_hash_idx
+						WHERE
+							workflow_id = $1 AND
+							loop_location_hash = $2 AND
+							forgotten = FALSE
+						RETURNING 1
+					),
+					delete_version_check_events AS (
+						DELETE FROM db_workflow.workflow_version_check_events@workflow_version_check_events_workflow_id_loop_location_hash_idx
+						WHERE
+							workflow_id = $1 AND
+							loop_location_hash = $2 AND
+							forgotten = FALSE
+						RETURNING 1
+					)
+				SELECT 1
+				",
+				workflow_id,
+				hash_location(location),
+			)
+			.await?;
+
+			tx.commit().await.map_err(WorkflowError::Sqlx)?;
+
+			Ok(())
+		})
+		.await?;
+
+		Ok(())
+	}
+
 	#[tracing::instrument(skip_all)]
 	async fn commit_workflow_sleep_event(
 		&self,
@@ -22,6 +22,9 @@ pub struct WorkflowRow {
 	input: RawJson,
 	output: Option<RawJson>,
 	has_wake_condition: bool,
+	is_active: bool,
+	cancel_requested: bool,
+	error: Option<String>,
 }
 
 impl From<WorkflowRow> for WorkflowData {
@@ -31,6 +34,9 @@ impl From<WorkflowRow> for WorkflowData {
 			input: value.input.0,
 			output: value.output.map(|x| x.0),
 			has_wake_condition: value.has_wake_condition,
+			is_active: value.is_active,
+			cancel_requested: value.cancel_requested,
+			error: value.error,
 		}
 	}
 }
@@ -43,6 +49,7 @@ pub struct PulledWorkflowRow {
 	ray_id: Uuid,
 	input: RawJson,
 	wake_deadline_ts: Option<i64>,
+	cancel_ts: Option<i64>,
 }
 
 #[derive(sqlx::FromRow)]
@@ -146,6 +153,8 @@ impl TryFrom<AmalgamEventRow> for ActivityEvent {
 				.ok_or(WorkflowError::MissingEventData)?
 				.try_into()
 				.map_err(|_| WorkflowError::IntegerConversion)?,
+			// NOTE: The retry policy is selected as the input column
+			retry_policy: value.input.map(|x| x.0),
 		})
 	}
 }
@@ -405,6 +414,7 @@ pub fn build_histories(
 				input: row.input.0,
 				events: events_by_location,
 				wake_deadline_ts: row.wake_deadline_ts,
+				cancel_ts: row.cancel_ts,
 			}
 		})
 		.collect();
@@ -22,12 +22,18 @@ pub trait DatabaseDebug: Database {
 
 	async fn wake_workflows(&self, workflow_ids: Vec<Uuid>) -> Result<()>;
 
+	async fn cancel_workflows(&self, workflow_ids: Vec<Uuid>) -> Result<()>;
+
 	async fn get_workflow_history(
 		&self,
 		workflow_id: Uuid,
 		include_forgotten: bool,
 	) -> Result<Option<HistoryData>>;
 
+	/// Returns how much history is stored for each of the given workflows.
+	async fn get_workflow_history_sizes(&self, workflow_ids: Vec<Uuid>)
+		-> Result<Vec<HistorySize>>;
+
 	async fn get_signals(&self, signal_ids: Vec<Uuid>) -> Result<Vec<SignalData>>;
 
 	async fn find_signals(
@@ -68,6 +74,17 @@ pub struct HistoryData {
 	pub events: Vec<Event>,
 }
 
+#[derive(Debug)]
+pub struct HistorySize {
+	pub workflow_id: Uuid,
+	/// Events that are replayed every time the workflow runs.
+	pub events: usize,
+	/// Events from previous loop iterations that are still stored.
+	pub forgotten_events: usize,
+	/// Total size of the JSON stored in history (inputs, outputs, bodies, loop state).
+	pub bytes: usize,
+}
+
 #[derive(Debug)]
 pub struct Event {
 	pub location: Location,
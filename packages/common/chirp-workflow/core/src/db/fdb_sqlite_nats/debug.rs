@@ -17,10 +17,13 @@ use uuid::Uuid;
 
 use super::{keys, sqlite::SqlStub, DatabaseFdbSqliteNats};
 use crate::{
-	db::debug::{
-		ActivityError, ActivityEvent, DatabaseDebug, Event, EventData, HistoryData, LoopEvent,
-		MessageSendEvent, SignalData, SignalEvent, SignalSendEvent, SignalState, SubWorkflowEvent,
-		WorkflowData, WorkflowState,
+	db::{
+		debug::{
+			ActivityError, ActivityEvent, DatabaseDebug, Event, EventData, HistoryData,
+			HistorySize, LoopEvent, MessageSendEvent, SignalData, SignalEvent, SignalSendEvent,
+			SignalState, SubWorkflowEvent, WorkflowData, WorkflowState,
+		},
+		Database,
 	},
 	history::{
 		event::{EventType, RemovedEvent, SleepEvent, SleepState},
@@ -31,6 +34,9 @@ use crate::{
 // HACK: We alias global error here because its hardcoded into the sql macros
 type GlobalError = anyhow::Error;
 
+/// How many workflow databases are read at once when calculating history sizes.
+const HISTORY_SIZE_CONCURRENCY: usize = 32;
+
 impl DatabaseFdbSqliteNats {
 	#[tracing::instrument(skip_all)]
 	async fn get_workflows_inner(
@@ -729,6 +735,15 @@ impl DatabaseDebug for DatabaseFdbSqliteNats {
 		Ok(())
 	}
 
+	#[tracing::instrument(skip_all)]
+	async fn cancel_workflows(&self, workflow_ids: Vec<Uuid>) -> Result<()> {
+		for workflow_id in workflow_ids {
+			Database::cancel_workflow(self, workflow_id).await?;
+		}
+
+		Ok(())
+	}
+
 	#[tracing::instrument(skip_all)]
 	async fn get_workflow_history(
 		&self,
@@ -978,6 +993,75 @@ impl DatabaseDebug for DatabaseFdbSqliteNats {
 		}))
 	}
 
+	#[tracing::instrument(skip_all)]
+	async fn get_workflow_history_sizes(
+		&self,
+		workflow_ids: Vec<Uuid>,
+	) -> Result<Vec<HistorySize>> {
+		futures_util::stream::iter(workflow_ids.into_iter().map(|workflow_id| async move {
+			let pool = &self
+				.pools
+				.sqlite(crate::db::sqlite_db_name_internal(workflow_id), true)
+				.await?;
+
+			let rows = sql_fetch_all!(
+				[SqlStub {}, (bool, i64, i64), pool]
+				"
+				SELECT forgotten, COUNT(*), COALESCE(SUM(size), 0)
+				FROM (
+					SELECT forgotten, length(input) + COALESCE(length(output), 0) AS size
+					FROM workflow_activity_events
+					UNION ALL
+					SELECT forgotten, length(body) FROM workflow_signal_events
+					UNION ALL
+					SELECT forgotten, length(input) + COALESCE(length(tags), 0)
+					FROM workflow_sub_workflow_events
+					UNION ALL
+					SELECT forgotten, length(body) + COALESCE(length(tags), 0)
+					FROM workflow_signal_send_events
+					UNION ALL
+					SELECT forgotten, length(body) + length(tags) FROM workflow_message_send_events
+					UNION ALL
+					SELECT forgotten, length(state) + COALESCE(length(output), 0)
+					FROM workflow_loop_events
+					UNION ALL
+					SELECT forgotten, 0 FROM workflow_sleep_events
+					UNION ALL
+					SELECT forgotten, 0 FROM workflow_branch_events
+					UNION ALL
+					SELECT forgotten, 0 FROM workflow_removed_events
+					UNION ALL
+					SELECT forgotten, 0 FROM workflow_version_check_events
+				)
+				GROUP BY forgotten
+				",
+			)
+			.await?;
+
+			let mut size = HistorySize {
+				workflow_id,
+				events: 0,
+				forgotten_events: 0,
+				bytes: 0,
+			};
+
+			for (forgotten, count, bytes) in rows {
+				if forgotten {
+					size.forgotten_events += usize::try_from(count)?;
+				} else {
+					size.events += usize::try_from(count)?;
+				}
+
+				size.bytes += usize::try_from(bytes)?;
+			}
+
+			Result::<_>::Ok(size)
+		}))
+		.buffer_unordered(HISTORY_SIZE_CONCURRENCY)
+		.try_collect()
+		.await
+	}
+
 	#[tracing::instrument(skip_all)]
 	async fn get_signals(&self, signal_ids: Vec<Uuid>) -> Result<Vec<SignalData>> {
 		self.pools
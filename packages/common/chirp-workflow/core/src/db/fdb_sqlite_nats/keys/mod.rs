@@ -1,3 +1,4 @@
+pub mod schedule;
 pub mod signal;
 pub mod wake;
 pub mod worker_instance;
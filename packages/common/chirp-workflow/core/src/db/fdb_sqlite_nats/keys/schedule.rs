@@ -0,0 +1,49 @@
+use std::result::Result::Ok;
+
+use anyhow::*;
+use fdb_util::prelude::*;
+
+#[derive(Debug)]
+pub struct LastRunTsKey {
+	schedule_name: String,
+}
+
+impl LastRunTsKey {
+	pub fn new(schedule_name: String) -> Self {
+		LastRunTsKey { schedule_name }
+	}
+}
+
+impl FormalKey for LastRunTsKey {
+	// Timestamp.
+	type Value = i64;
+
+	fn deserialize(&self, raw: &[u8]) -> Result<Self::Value> {
+		Ok(i64::from_be_bytes(raw.try_into()?))
+	}
+
+	fn serialize(&self, value: Self::Value) -> Result<Vec<u8>> {
+		Ok(value.to_be_bytes().to_vec())
+	}
+}
+
+impl TuplePack for LastRunTsKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (SCHEDULE, DATA, &self.schedule_name, LAST_RUN_TS);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for LastRunTsKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _, schedule_name, _)) =
+			<(usize, usize, String, usize)>::unpack(input, tuple_depth)?;
+		let v = LastRunTsKey { schedule_name };
+
+		Ok((input, v))
+	}
+}
@@ -395,3 +395,179 @@ impl<'de> TupleUnpack<'de> for SilenceTsKey {
 		Ok((input, v))
 	}
 }
+
+#[derive(Debug)]
+pub struct IdempotencyKeyKey {
+	workflow_id: Uuid,
+	signal_name: String,
+	idempotency_key: String,
+}
+
+impl IdempotencyKeyKey {
+	pub fn new(workflow_id: Uuid, signal_name: String, idempotency_key: String) -> Self {
+		IdempotencyKeyKey {
+			workflow_id,
+			signal_name,
+			idempotency_key,
+		}
+	}
+}
+
+impl FormalKey for IdempotencyKeyKey {
+	// Signal id and expire timestamp.
+	type Value = (Uuid, i64);
+
+	fn deserialize(&self, raw: &[u8]) -> Result<Self::Value> {
+		ensure!(raw.len() == 24, "invalid idempotency key value length");
+
+		let signal_id = Uuid::from_slice(&raw[..16])?;
+		let expire_ts = i64::from_be_bytes(raw[16..].try_into()?);
+
+		Ok((signal_id, expire_ts))
+	}
+
+	fn serialize(&self, (signal_id, expire_ts): Self::Value) -> Result<Vec<u8>> {
+		Ok(signal_id
+			.as_bytes()
+			.iter()
+			.copied()
+			.chain(expire_ts.to_be_bytes())
+			.collect())
+	}
+}
+
+impl TuplePack for IdempotencyKeyKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (
+			SIGNAL,
+			IDEMPOTENCY_KEY,
+			self.workflow_id,
+			&self.signal_name,
+			&self.idempotency_key,
+		);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for IdempotencyKeyKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _, workflow_id, signal_name, idempotency_key)) =
+			<(usize, usize, Uuid, String, String)>::unpack(input, tuple_depth)?;
+		let v = IdempotencyKeyKey {
+			workflow_id,
+			signal_name,
+			idempotency_key,
+		};
+
+		Ok((input, v))
+	}
+}
+
+/// Index of idempotency keys by when they expire, used to garbage collect them.
+#[derive(Debug)]
+pub struct IdempotencyKeyExpireTsKey {
+	pub expire_ts: i64,
+	pub workflow_id: Uuid,
+	pub signal_name: String,
+	pub idempotency_key: String,
+}
+
+impl IdempotencyKeyExpireTsKey {
+	pub fn new(
+		expire_ts: i64,
+		workflow_id: Uuid,
+		signal_name: String,
+		idempotency_key: String,
+	) -> Self {
+		IdempotencyKeyExpireTsKey {
+			expire_ts,
+			workflow_id,
+			signal_name,
+			idempotency_key,
+		}
+	}
+
+	pub fn subspace(expire_ts: Option<i64>) -> IdempotencyKeyExpireTsSubspaceKey {
+		IdempotencyKeyExpireTsSubspaceKey::new(expire_ts)
+	}
+}
+
+impl FormalKey for IdempotencyKeyExpireTsKey {
+	type Value = ();
+
+	fn deserialize(&self, _raw: &[u8]) -> Result<Self::Value> {
+		Ok(())
+	}
+
+	fn serialize(&self, _value: Self::Value) -> Result<Vec<u8>> {
+		Ok(Vec::new())
+	}
+}
+
+impl TuplePack for IdempotencyKeyExpireTsKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (
+			SIGNAL,
+			IDEMPOTENCY_KEY,
+			EXPIRE_TS,
+			self.expire_ts,
+			self.workflow_id,
+			&self.signal_name,
+			&self.idempotency_key,
+		);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for IdempotencyKeyExpireTsKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _, _, expire_ts, workflow_id, signal_name, idempotency_key)) =
+			<(usize, usize, usize, i64, Uuid, String, String)>::unpack(input, tuple_depth)?;
+		let v = IdempotencyKeyExpireTsKey {
+			expire_ts,
+			workflow_id,
+			signal_name,
+			idempotency_key,
+		};
+
+		Ok((input, v))
+	}
+}
+
+// Structure should match `IdempotencyKeyExpireTsKey`
+pub struct IdempotencyKeyExpireTsSubspaceKey {
+	expire_ts: Option<i64>,
+}
+
+impl IdempotencyKeyExpireTsSubspaceKey {
+	pub fn new(expire_ts: Option<i64>) -> Self {
+		IdempotencyKeyExpireTsSubspaceKey { expire_ts }
+	}
+}
+
+impl TuplePack for IdempotencyKeyExpireTsSubspaceKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let mut offset = VersionstampOffset::None { size: 0 };
+
+		let t = (SIGNAL, IDEMPOTENCY_KEY, EXPIRE_TS);
+		offset += t.pack(w, tuple_depth)?;
+
+		if let Some(expire_ts) = &self.expire_ts {
+			offset += expire_ts.pack(w, tuple_depth)?;
+		}
+
+		Ok(offset)
+	}
+}
@@ -1095,3 +1095,101 @@ impl<'de> TupleUnpack<'de> for SilenceTsKey {
 		Ok((input, v))
 	}
 }
+
+#[derive(Debug)]
+pub struct CancelTsKey {
+	workflow_id: Uuid,
+}
+
+impl CancelTsKey {
+	pub fn new(workflow_id: Uuid) -> Self {
+		CancelTsKey { workflow_id }
+	}
+}
+
+impl FormalKey for CancelTsKey {
+	// Timestamp.
+	type Value = i64;
+
+	fn deserialize(&self, raw: &[u8]) -> Result<Self::Value> {
+		Ok(i64::from_be_bytes(raw.try_into()?))
+	}
+
+	fn serialize(&self, value: Self::Value) -> Result<Vec<u8>> {
+		Ok(value.to_be_bytes().to_vec())
+	}
+}
+
+impl TuplePack for CancelTsKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (WORKFLOW, DATA, self.workflow_id, CANCEL_TS);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for CancelTsKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _, workflow_id, data)) =
+			<(usize, usize, Uuid, usize)>::unpack(input, tuple_depth)?;
+		if data != CANCEL_TS {
+			return Err(PackError::Message("expected CANCEL_TS data".into()));
+		}
+
+		let v = CancelTsKey { workflow_id };
+
+		Ok((input, v))
+	}
+}
+
+/// Set when a workflow is cancelled while leased. Cleared when the workflow commits.
+#[derive(Debug)]
+pub struct PendingCancelKey {
+	workflow_id: Uuid,
+}
+
+impl PendingCancelKey {
+	pub fn new(workflow_id: Uuid) -> Self {
+		PendingCancelKey { workflow_id }
+	}
+}
+
+impl FormalKey for PendingCancelKey {
+	type Value = ();
+
+	fn deserialize(&self, _raw: &[u8]) -> Result<Self::Value> {
+		Ok(())
+	}
+
+	fn serialize(&self, _value: Self::Value) -> Result<Vec<u8>> {
+		Ok(Vec::new())
+	}
+}
+
+impl TuplePack for PendingCancelKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (WORKFLOW, DATA, self.workflow_id, PENDING_CANCEL);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for PendingCancelKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _, workflow_id, data)) =
+			<(usize, usize, Uuid, usize)>::unpack(input, tuple_depth)?;
+		if data != PENDING_CANCEL {
+			return Err(PackError::Message("expected PENDING_CANCEL data".into()));
+		}
+
+		let v = PendingCancelKey { workflow_id };
+
+		Ok((input, v))
+	}
+}
@@ -25,7 +25,9 @@ use tokio::sync::mpsc;
 use tracing::Instrument;
 use uuid::Uuid;
 
-use super::{Database, PulledWorkflowData, SignalData, WorkflowData};
+use super::{
+	Database, IdempotencyKey, PulledWorkflowData, ScheduleDispatch, SignalData, WorkflowData,
+};
 use crate::{
 	error::{WorkflowError, WorkflowResult},
 	history::{
@@ -51,6 +53,8 @@ const MAX_QUERY_RETRIES: usize = 4;
 const WORKER_INSTANCE_LOST_THRESHOLD_MS: i64 = rivet_util::duration::seconds(30);
 /// How long before overwriting an existing metrics lock.
 const METRICS_LOCK_TIMEOUT_MS: i64 = rivet_util::duration::seconds(30);
+/// Max amount of expired idempotency keys cleared in a single transaction.
+const IDEMPOTENCY_KEY_GC_BATCH: usize = 1000;
 /// For SQL macros.
 const CONTEXT_NAME: &str = "chirp_workflow_fdb_sqlite_nats_engine";
 /// For NATS wake mechanism.
@@ -68,6 +72,129 @@ impl DatabaseFdbSqliteNats {
 		CONTEXT_NAME
 	}
 
+	/// Deletes all history events with the given loop location.
+	async fn delete_loop_history(
+		&self,
+		tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+		loop_location: &Location,
+	) -> WorkflowResult<()> {
+		sql_execute!(
+			[self, @tx tx]
+			"
+			DELETE FROM workflow_activity_errors
+			WHERE location IN (
+				SELECT location
+				FROM workflow_activity_events
+				WHERE loop_location = jsonb(?1)
+			)
+			",
+			loop_location,
+		)
+		.await?;
+
+		sql_execute!(
+			[self, @tx tx]
+			"
+			DELETE FROM workflow_activity_events
+			WHERE loop_location = jsonb(?1) AND NOT forgotten
+			",
+			loop_location,
+		)
+		.await?;
+
+		sql_execute!(
+			[self, @tx tx]
+			"
+			DELETE FROM workflow_signal_events
+			WHERE loop_location = jsonb(?1) AND NOT forgotten
+			",
+			loop_location,
+		)
+		.await?;
+
+		sql_execute!(
+			[self, @tx tx]
+			"
+			DELETE FROM workflow_sub_workflow_events
+			WHERE loop_location = jsonb(?1) AND NOT forgotten
+			",
+			loop_location,
+		)
+		.await?;
+
+		sql_execute!(
+			[self, @tx tx]
+			"
+			DELETE FROM workflow_signal_send_events
+			WHERE loop_location = jsonb(?1) AND NOT forgotten
+			",
+			loop_location,
+		)
+		.await?;
+
+		sql_execute!(
+			[self, @tx tx]
+			"
+			DELETE FROM workflow_message_send_events
+			WHERE loop_location = jsonb(?1) AND NOT forgotten
+			",
+			loop_location,
+		)
+		.await?;
+
+		sql_execute!(
+			[self, @tx tx]
+			"
+			DELETE FROM workflow_loop_events
+			WHERE loop_location = jsonb(?1) AND NOT forgotten
+			",
+			loop_location,
+		)
+		.await?;
+
+		sql_execute!(
+			[self, @tx tx]
+			"
+			DELETE FROM workflow_sleep_events
+			WHERE loop_location = jsonb(?1) AND NOT forgotten
+			",
+			loop_location,
+		)
+		.await?;
+
+		sql_execute!(
+			[self, @tx tx]
+			"
+			DELETE FROM workflow_branch_events
+			WHERE loop_location = jsonb(?1) AND NOT forgotten
+			",
+			loop_location,
+		)
+		.await?;
+
+		sql_execute!(
+			[self, @tx tx]
+			"
+			DELETE FROM workflow_removed_events
+			WHERE loop_location = jsonb(?1) AND NOT forgotten
+			",
+			loop_location,
+		)
+		.await?;
+
+		sql_execute!(
+			[self, @tx tx]
+			"
+			DELETE FROM workflow_version_check_events
+			WHERE loop_location = jsonb(?1) AND NOT forgotten
+			",
+			loop_location,
+		)
+		.await?;
+
+		Ok(())
+	}
+
 	/// Spawns a new thread and publishes a worker wake message to nats.
 	fn wake_worker(&self) {
 		let Ok(nats) = self.pools.nats() else {
@@ -135,6 +262,9 @@ impl DatabaseFdbSqliteNats {
 	///
 	/// This must be done before releasing the lease on the workflow in order to prevent a race
 	/// condition with other workflow workers picking it up.
+	///
+	/// Fails with `WorkflowError::SqliteWriterLeaseLost` if another worker took over the workflow's
+	/// databases, in which case this worker's changes are discarded.
 	#[tracing::instrument(skip_all)]
 	async fn evict_wf_sqlite(&self, workflow_id: Uuid) -> WorkflowResult<()> {
 		tracing::debug!(?workflow_id, "evicting workflow");
@@ -145,7 +275,13 @@ impl DatabaseFdbSqliteNats {
 				crate::db::sqlite_db_name_internal(workflow_id),
 				crate::db::sqlite_db_name_data(workflow_id),
 			])
-			.await?;
+			.await
+			.map_err(|err| match err {
+				rivet_pools::Error::SqliteWriterLeaseLost { .. } => {
+					WorkflowError::SqliteWriterLeaseLost(workflow_id)
+				}
+				err => err.into(),
+			})?;
 
 		Ok(())
 	}
@@ -202,6 +338,190 @@ impl DatabaseFdbSqliteNats {
 
 		Ok(())
 	}
+
+	/// Writes a new workflow. Used by `dispatch_workflow` and when dispatching scheduled workflows.
+	fn write_workflow(
+		&self,
+		ray_id: Uuid,
+		workflow_id: Uuid,
+		workflow_name: &str,
+		tags: Option<&serde_json::Value>,
+		input: &serde_json::value::RawValue,
+		tx: &fdb::RetryableTransaction,
+	) -> Result<(), fdb::FdbBindingError> {
+		// Write create ts
+		let create_ts_key = keys::workflow::CreateTsKey::new(workflow_id);
+		tx.set(
+			&self.subspace.pack(&create_ts_key),
+			&create_ts_key
+				.serialize(rivet_util::timestamp::now())
+				.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
+		);
+
+		// Write name
+		let name_key = keys::workflow::NameKey::new(workflow_id);
+		tx.set(
+			&self.subspace.pack(&name_key),
+			&name_key
+				.serialize(workflow_name.to_string())
+				.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
+		);
+
+		// Write ray id
+		let ray_id_key = keys::workflow::RayIdKey::new(workflow_id);
+		tx.set(
+			&self.subspace.pack(&ray_id_key),
+			&ray_id_key
+				.serialize(ray_id)
+				.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
+		);
+
+		// Write tags
+		let tags = tags
+			.map(|x| {
+				x.as_object()
+					.ok_or_else(|| WorkflowError::InvalidTags("must be an object".to_string()))
+			})
+			.transpose()
+			.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?
+			.into_iter()
+			.flatten()
+			.map(|(k, v)| Ok((k.clone(), value_to_str(v)?)))
+			.collect::<WorkflowResult<Vec<_>>>()
+			.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
+
+		for (k, v) in &tags {
+			// Write tag key
+			let tag_key = keys::workflow::TagKey::new(workflow_id, k.clone(), v.clone());
+			tx.set(
+				&self.subspace.pack(&tag_key),
+				&tag_key
+					.serialize(())
+					.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
+			);
+
+			// Write "by name and first tag" secondary index
+			let by_name_and_tag_key = keys::workflow::ByNameAndTagKey::new(
+				workflow_name.to_string(),
+				k.clone(),
+				v.clone(),
+				workflow_id,
+			);
+			let rest_of_tags = tags
+				.iter()
+				.filter(|(k2, _)| k2 != k)
+				.map(|(k, v)| (k.clone(), v.clone()))
+				.collect();
+			tx.set(
+				&self.subspace.pack(&by_name_and_tag_key),
+				&by_name_and_tag_key
+					.serialize(rest_of_tags)
+					.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
+			);
+		}
+
+		// Write null key for the "by name and first tag" secondary index (all workflows have this)
+		{
+			// Write secondary index by name and first tag
+			let by_name_and_tag_key =
+				keys::workflow::ByNameAndTagKey::null(workflow_name.to_string(), workflow_id);
+			tx.set(
+				&self.subspace.pack(&by_name_and_tag_key),
+				&by_name_and_tag_key
+					.serialize(tags)
+					.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
+			);
+		}
+
+		// Wrote "has wake condition"
+		let has_wake_condition_key = keys::workflow::HasWakeConditionKey::new(workflow_id);
+		tx.set(
+			&self.subspace.pack(&has_wake_condition_key),
+			&has_wake_condition_key
+				.serialize(())
+				.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
+		);
+
+		// Write input
+		let input_key = keys::workflow::InputKey::new(workflow_id);
+
+		for (i, chunk) in input_key
+			.split_ref(input)
+			.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?
+			.into_iter()
+			.enumerate()
+		{
+			let chunk_key = input_key.chunk(i);
+
+			tx.set(&self.subspace.pack(&chunk_key), &chunk);
+		}
+
+		// Write immediate wake condition
+		let wake_condition_key = keys::wake::WorkflowWakeConditionKey::new(
+			workflow_name.to_string(),
+			workflow_id,
+			keys::wake::WakeCondition::Immediate,
+		);
+
+		tx.set(
+			&self.subspace.pack(&wake_condition_key),
+			&wake_condition_key
+				.serialize(())
+				.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
+		);
+
+		Ok(())
+	}
+
+	/// Wakes all workflows waiting on the given sub workflow. Called once the sub workflow reaches a terminal
+	/// state (complete, failed, or cancelled).
+	async fn wake_sub_workflow_waiters(
+		&self,
+		sub_workflow_id: Uuid,
+		tx: &fdb::RetryableTransaction,
+	) -> Result<(), fdb::FdbBindingError> {
+		let sub_workflow_wake_subspace = self
+			.subspace
+			.subspace(&keys::wake::SubWorkflowWakeKey::subspace(sub_workflow_id));
+
+		let mut stream = tx.get_ranges_keyvalues(
+			fdb::RangeOption {
+				mode: StreamingMode::WantAll,
+				..(&sub_workflow_wake_subspace).into()
+			},
+			// NOTE: Must be serializable to conflict with `get_sub_workflow`
+			SERIALIZABLE,
+		);
+
+		while let Some(entry) = stream.try_next().await? {
+			let sub_workflow_wake_key = self
+				.subspace
+				.unpack::<keys::wake::SubWorkflowWakeKey>(&entry.key())
+				.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
+			let workflow_name = sub_workflow_wake_key
+				.deserialize(entry.value())
+				.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
+
+			let wake_condition_key = keys::wake::WorkflowWakeConditionKey::new(
+				workflow_name,
+				sub_workflow_wake_key.workflow_id,
+				keys::wake::WakeCondition::SubWorkflow { sub_workflow_id },
+			);
+
+			// Add wake condition for workflow
+			tx.set(
+				&self.subspace.pack(&wake_condition_key),
+				&wake_condition_key
+					.serialize(())
+					.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
+			);
+
+			// Clear secondary index
+			tx.clear(entry.key());
+		}
+
+		Ok(())
+	}
 }
 
 #[async_trait::async_trait]
@@ -367,6 +687,158 @@ impl Database for DatabaseFdbSqliteNats {
 		Ok(())
 	}
 
+	#[tracing::instrument(skip_all)]
+	async fn clear_expired_idempotency_keys(&self) -> WorkflowResult<()> {
+		loop {
+			let cleared = self
+				.pools
+				.fdb()?
+				.run(|tx, _mc| async move {
+					let now = rivet_util::timestamp::now();
+
+					let expire_ts_subspace = self
+						.subspace
+						.subspace(&keys::signal::IdempotencyKeyExpireTsKey::subspace(None));
+					let expire_ts_subspace_end = self
+						.subspace
+						.subspace(&keys::signal::IdempotencyKeyExpireTsKey::subspace(Some(
+							now,
+						)))
+						.bytes()
+						.to_vec();
+
+					let entries = tx
+						.get_ranges_keyvalues(
+							fdb::RangeOption {
+								mode: StreamingMode::WantAll,
+								limit: Some(IDEMPOTENCY_KEY_GC_BATCH),
+								..(expire_ts_subspace.range().0, expire_ts_subspace_end).into()
+							},
+							SERIALIZABLE,
+						)
+						.try_collect::<Vec<_>>()
+						.await?;
+
+					for entry in &entries {
+						let expire_ts_key = self
+							.subspace
+							.unpack::<keys::signal::IdempotencyKeyExpireTsKey>(entry.key())
+							.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
+						let idempotency_key_key = keys::signal::IdempotencyKeyKey::new(
+							expire_ts_key.workflow_id,
+							expire_ts_key.signal_name,
+							expire_ts_key.idempotency_key,
+						);
+						let idempotency_key_key_buf = self.subspace.pack(&idempotency_key_key);
+
+						// The key may have been claimed again after expiring, in which case it has a newer
+						// expire ts and its own index entry
+						if let Some(idempotency_key_entry) =
+							tx.get(&idempotency_key_key_buf, SERIALIZABLE).await?
+						{
+							let (_, expire_ts) = idempotency_key_key
+								.deserialize(&idempotency_key_entry)
+								.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
+
+							if expire_ts == expire_ts_key.expire_ts {
+								tx.clear(&idempotency_key_key_buf);
+							}
+						}
+
+						tx.clear(entry.key());
+					}
+
+					Ok(entries.len())
+				})
+				.custom_instrument(tracing::info_span!("clear_expired_idempotency_keys_tx"))
+				.await?;
+
+			if cleared < IDEMPOTENCY_KEY_GC_BATCH {
+				break;
+			}
+		}
+
+		Ok(())
+	}
+
+	#[tracing::instrument(skip_all, fields(%schedule_name))]
+	async fn get_schedule_last_run_ts(&self, schedule_name: &str) -> WorkflowResult<Option<i64>> {
+		self.pools
+			.fdb()?
+			.run(|tx, _mc| async move {
+				let last_run_ts_key = keys::schedule::LastRunTsKey::new(schedule_name.to_string());
+
+				tx.get(&self.subspace.pack(&last_run_ts_key), SNAPSHOT)
+					.await?
+					.map(|entry| last_run_ts_key.deserialize(&entry))
+					.transpose()
+					.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))
+			})
+			.custom_instrument(tracing::info_span!("get_schedule_last_run_ts_tx"))
+			.await
+			.map_err(Into::into)
+	}
+
+	#[tracing::instrument(skip_all, fields(%schedule_name))]
+	async fn claim_schedule_run(
+		&self,
+		schedule_name: &str,
+		prev_run_ts: Option<i64>,
+		run_ts: i64,
+		dispatch: Option<ScheduleDispatch<'_>>,
+	) -> WorkflowResult<bool> {
+		let claimed = self
+			.pools
+			.fdb()?
+			.run(|tx, _mc| {
+				async move {
+					let last_run_ts_key =
+						keys::schedule::LastRunTsKey::new(schedule_name.to_string());
+
+					// NOTE: Must be serializable so that only one worker can claim a run
+					let last_run_ts = tx
+						.get(&self.subspace.pack(&last_run_ts_key), SERIALIZABLE)
+						.await?
+						.map(|entry| last_run_ts_key.deserialize(&entry))
+						.transpose()
+						.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
+
+					// Another worker claimed a run first
+					if last_run_ts != prev_run_ts {
+						return Ok(false);
+					}
+
+					tx.set(
+						&self.subspace.pack(&last_run_ts_key),
+						&last_run_ts_key
+							.serialize(run_ts)
+							.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
+					);
+
+					if let Some(dispatch) = dispatch {
+						self.write_workflow(
+							dispatch.ray_id,
+							dispatch.workflow_id,
+							dispatch.workflow_name,
+							Some(dispatch.tags),
+							dispatch.input,
+							&tx,
+						)?;
+					}
+
+					Ok(true)
+				}
+			})
+			.custom_instrument(tracing::info_span!("claim_schedule_run_tx"))
+			.await?;
+
+		if claimed && dispatch.is_some() {
+			self.wake_worker();
+		}
+
+		Ok(claimed)
+	}
+
 	#[tracing::instrument(skip_all)]
 	async fn publish_metrics(&self, _worker_instance_id: Uuid) -> WorkflowResult<()> {
 		// Attempt to be the only worker publishing metrics by writing to the lock key
@@ -633,214 +1105,86 @@ impl Database for DatabaseFdbSqliteNats {
 					.set(count);
 			}
 
-			for (signal_name, count) in pending_signal_count {
-				metrics::SIGNAL_PENDING
-					.with_label_values(&[&signal_name])
-					.set(count);
-			}
-
-			// Clear lock
-			self.pools
-				.fdb()?
-				.run(|tx, _mc| async move {
-					let metrics_lock_key = keys::worker_instance::MetricsLockKey::new();
-					tx.clear(&self.subspace.pack(&metrics_lock_key));
-
-					Ok(())
-				})
-				.custom_instrument(tracing::info_span!("clear_lock_tx"))
-				.await?;
-		}
-
-		Ok(())
-	}
-
-	#[tracing::instrument(skip_all)]
-	async fn update_worker_ping(&self, worker_instance_id: Uuid) -> WorkflowResult<()> {
-		metrics::WORKER_LAST_PING
-			.with_label_values(&[&worker_instance_id.to_string()])
-			.set(rivet_util::timestamp::now());
-
-		self.pools
-			.fdb()?
-			.run(|tx, _mc| {
-				async move {
-					// Update worker instance ping
-					let last_ping_ts_key =
-						keys::worker_instance::LastPingTsKey::new(worker_instance_id);
-					tx.set(
-						&self.subspace.pack(&last_ping_ts_key),
-						&last_ping_ts_key
-							.serialize(rivet_util::timestamp::now())
-							.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
-					);
-
-					Ok(())
-				}
-			})
-			.custom_instrument(tracing::info_span!("update_worker_ping_tx"))
-			.await?;
-
-		Ok(())
-	}
-
-	#[tracing::instrument(skip_all, fields(%workflow_id, %workflow_name, unique))]
-	async fn dispatch_workflow(
-		&self,
-		ray_id: Uuid,
-		workflow_id: Uuid,
-		workflow_name: &str,
-		tags: Option<&serde_json::Value>,
-		input: &serde_json::value::RawValue,
-		unique: bool,
-	) -> WorkflowResult<Uuid> {
-		// TODO: Race condition if two unique dispatch_workflow calls are made at the same time. The txn
-		// inside of find_workflow should be split into find_workflow_inner and run in the same txn in this
-		// function
-		if unique {
-			let empty_tags = json!({});
-
-			if let Some(existing_workflow_id) = self
-				.find_workflow(workflow_name, tags.unwrap_or(&empty_tags))
-				.await?
-			{
-				return Ok(existing_workflow_id);
-			}
-		}
-
-		self.pools
-			.fdb()?
-			.run(|tx, _mc| {
-				async move {
-					// Write create ts
-					let create_ts_key = keys::workflow::CreateTsKey::new(workflow_id);
-					tx.set(
-						&self.subspace.pack(&create_ts_key),
-						&create_ts_key
-							.serialize(rivet_util::timestamp::now())
-							.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
-					);
-
-					// Write name
-					let name_key = keys::workflow::NameKey::new(workflow_id);
-					tx.set(
-						&self.subspace.pack(&name_key),
-						&name_key
-							.serialize(workflow_name.to_string())
-							.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
-					);
-
-					// Write ray id
-					let ray_id_key = keys::workflow::RayIdKey::new(workflow_id);
-					tx.set(
-						&self.subspace.pack(&ray_id_key),
-						&ray_id_key
-							.serialize(ray_id)
-							.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
-					);
-
-					// Write tags
-					let tags = tags
-						.map(|x| {
-							x.as_object().ok_or_else(|| {
-								WorkflowError::InvalidTags("must be an object".to_string())
-							})
-						})
-						.transpose()
-						.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?
-						.into_iter()
-						.flatten()
-						.map(|(k, v)| Ok((k.clone(), value_to_str(v)?)))
-						.collect::<WorkflowResult<Vec<_>>>()
-						.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
-
-					for (k, v) in &tags {
-						// Write tag key
-						let tag_key =
-							keys::workflow::TagKey::new(workflow_id, k.clone(), v.clone());
-						tx.set(
-							&self.subspace.pack(&tag_key),
-							&tag_key
-								.serialize(())
-								.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
-						);
-
-						// Write "by name and first tag" secondary index
-						let by_name_and_tag_key = keys::workflow::ByNameAndTagKey::new(
-							workflow_name.to_string(),
-							k.clone(),
-							v.clone(),
-							workflow_id,
-						);
-						let rest_of_tags = tags
-							.iter()
-							.filter(|(k2, _)| k2 != k)
-							.map(|(k, v)| (k.clone(), v.clone()))
-							.collect();
-						tx.set(
-							&self.subspace.pack(&by_name_and_tag_key),
-							&by_name_and_tag_key
-								.serialize(rest_of_tags)
-								.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
-						);
-					}
-
-					// Write null key for the "by name and first tag" secondary index (all workflows have this)
-					{
-						// Write secondary index by name and first tag
-						let by_name_and_tag_key = keys::workflow::ByNameAndTagKey::null(
-							workflow_name.to_string(),
-							workflow_id,
-						);
-						tx.set(
-							&self.subspace.pack(&by_name_and_tag_key),
-							&by_name_and_tag_key
-								.serialize(tags)
-								.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
-						);
-					}
-
-					// Wrote "has wake condition"
-					let has_wake_condition_key =
-						keys::workflow::HasWakeConditionKey::new(workflow_id);
-					tx.set(
-						&self.subspace.pack(&has_wake_condition_key),
-						&has_wake_condition_key
-							.serialize(())
-							.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
-					);
+			for (signal_name, count) in pending_signal_count {
+				metrics::SIGNAL_PENDING
+					.with_label_values(&[&signal_name])
+					.set(count);
+			}
 
-					// Write input
-					let input_key = keys::workflow::InputKey::new(workflow_id);
+			// Clear lock
+			self.pools
+				.fdb()?
+				.run(|tx, _mc| async move {
+					let metrics_lock_key = keys::worker_instance::MetricsLockKey::new();
+					tx.clear(&self.subspace.pack(&metrics_lock_key));
 
-					for (i, chunk) in input_key
-						.split_ref(input)
-						.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?
-						.into_iter()
-						.enumerate()
-					{
-						let chunk_key = input_key.chunk(i);
+					Ok(())
+				})
+				.custom_instrument(tracing::info_span!("clear_lock_tx"))
+				.await?;
+		}
 
-						tx.set(&self.subspace.pack(&chunk_key), &chunk);
-					}
+		Ok(())
+	}
 
-					// Write immediate wake condition
-					let wake_condition_key = keys::wake::WorkflowWakeConditionKey::new(
-						workflow_name.to_string(),
-						workflow_id,
-						keys::wake::WakeCondition::Immediate,
-					);
+	#[tracing::instrument(skip_all)]
+	async fn update_worker_ping(&self, worker_instance_id: Uuid) -> WorkflowResult<()> {
+		metrics::WORKER_LAST_PING
+			.with_label_values(&[&worker_instance_id.to_string()])
+			.set(rivet_util::timestamp::now());
 
+		self.pools
+			.fdb()?
+			.run(|tx, _mc| {
+				async move {
+					// Update worker instance ping
+					let last_ping_ts_key =
+						keys::worker_instance::LastPingTsKey::new(worker_instance_id);
 					tx.set(
-						&self.subspace.pack(&wake_condition_key),
-						&wake_condition_key
-							.serialize(())
+						&self.subspace.pack(&last_ping_ts_key),
+						&last_ping_ts_key
+							.serialize(rivet_util::timestamp::now())
 							.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
 					);
 
 					Ok(())
 				}
 			})
+			.custom_instrument(tracing::info_span!("update_worker_ping_tx"))
+			.await?;
+
+		Ok(())
+	}
+
+	#[tracing::instrument(skip_all, fields(%workflow_id, %workflow_name, unique))]
+	async fn dispatch_workflow(
+		&self,
+		ray_id: Uuid,
+		workflow_id: Uuid,
+		workflow_name: &str,
+		tags: Option<&serde_json::Value>,
+		input: &serde_json::value::RawValue,
+		unique: bool,
+	) -> WorkflowResult<Uuid> {
+		// TODO: Race condition if two unique dispatch_workflow calls are made at the same time. The txn
+		// inside of find_workflow should be split into find_workflow_inner and run in the same txn in this
+		// function
+		if unique {
+			let empty_tags = json!({});
+
+			if let Some(existing_workflow_id) = self
+				.find_workflow(workflow_name, tags.unwrap_or(&empty_tags))
+				.await?
+			{
+				return Ok(existing_workflow_id);
+			}
+		}
+
+		self.pools
+			.fdb()?
+			.run(|tx, _mc| async move {
+				self.write_workflow(ray_id, workflow_id, workflow_name, tags, input, &tx)
+			})
 			.custom_instrument(tracing::info_span!("dispatch_workflow_tx"))
 			.await?;
 
@@ -861,9 +1205,20 @@ impl Database for DatabaseFdbSqliteNats {
 					let output_subspace = self.subspace.subspace(&output_key);
 					let has_wake_condition_key =
 						keys::workflow::HasWakeConditionKey::new(workflow_id);
+					let worker_instance_id_key =
+						keys::workflow::WorkerInstanceIdKey::new(workflow_id);
+					let cancel_ts_key = keys::workflow::CancelTsKey::new(workflow_id);
+					let error_key = keys::workflow::ErrorKey::new(workflow_id);
 
 					// Read input and output
-					let (input_chunks, output_chunks, has_wake_condition_entry) = tokio::try_join!(
+					let (
+						input_chunks,
+						output_chunks,
+						has_wake_condition_entry,
+						worker_instance_id_entry,
+						cancel_ts_entry,
+						error_entry,
+					) = tokio::try_join!(
 						tx.get_ranges_keyvalues(
 							fdb::RangeOption {
 								mode: StreamingMode::WantAll,
@@ -881,6 +1236,9 @@ impl Database for DatabaseFdbSqliteNats {
 						)
 						.try_collect::<Vec<_>>(),
 						tx.get(&self.subspace.pack(&has_wake_condition_key), SERIALIZABLE),
+						tx.get(&self.subspace.pack(&worker_instance_id_key), SERIALIZABLE),
+						tx.get(&self.subspace.pack(&cancel_ts_key), SERIALIZABLE),
+						tx.get(&self.subspace.pack(&error_key), SERIALIZABLE),
 					)?;
 
 					if input_chunks.is_empty() {
@@ -905,6 +1263,12 @@ impl Database for DatabaseFdbSqliteNats {
 							input,
 							output,
 							has_wake_condition: has_wake_condition_entry.is_some(),
+							is_active: worker_instance_id_entry.is_some(),
+							cancel_requested: cancel_ts_entry.is_some(),
+							error: error_entry
+								.map(|raw| error_key.deserialize(&raw))
+								.transpose()
+								.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
 						}))
 					}
 				}
@@ -914,6 +1278,63 @@ impl Database for DatabaseFdbSqliteNats {
 			.map_err(Into::into)
 	}
 
+	#[tracing::instrument(skip_all, fields(%workflow_id))]
+	async fn get_workflow_loop_state(
+		&self,
+		workflow_id: Uuid,
+		workflow_name: &str,
+	) -> WorkflowResult<Option<Box<serde_json::value::RawValue>>> {
+		let actual_workflow_name = self
+			.pools
+			.fdb()?
+			.run(|tx, _mc| async move {
+				let workflow_name_key = keys::workflow::NameKey::new(workflow_id);
+
+				// NOTE: This does not have to be serializable because wf name doesn't change
+				tx.get(&self.subspace.pack(&workflow_name_key), SNAPSHOT)
+					.await?
+					.map(|entry| workflow_name_key.deserialize(&entry))
+					.transpose()
+					.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))
+			})
+			.custom_instrument(tracing::info_span!("get_workflow_name_tx"))
+			.await?
+			.ok_or(WorkflowError::WorkflowNotFound)?;
+
+		if actual_workflow_name != workflow_name {
+			return Err(WorkflowError::QueryWorkflowMismatch(
+				actual_workflow_name,
+				workflow_name.to_string(),
+			));
+		}
+
+		let pool = &self
+			.pools
+			.sqlite(crate::db::sqlite_db_name_internal(workflow_id), true)
+			.await?;
+
+		let loops = sql_fetch_all!(
+			[self, (Location, String), pool]
+			"
+			SELECT json(location), json(state)
+			FROM workflow_loop_events
+			WHERE
+				output IS NULL AND
+				loop_location IS NULL AND
+				NOT forgotten
+			",
+		)
+		.await?;
+
+		// Ordered in code instead of in SQL so both drivers pick the same loop
+		loops
+			.into_iter()
+			.max_by(|(a, _), (b, _)| a.cmp(b))
+			.map(|(_, state)| serde_json::value::RawValue::from_string(state))
+			.transpose()
+			.map_err(WorkflowError::DeserializeLoopState)
+	}
+
 	/// Returns the first incomplete workflow with the given name and tags, first meaning the one with the
 	/// lowest uuid value (interpreted as u128) because its in a KV store. There is no way to get any other
 	/// workflow besides the first.
@@ -1202,21 +1623,24 @@ impl Database for DatabaseFdbSqliteNats {
 							async move {
 								let create_ts_key = keys::workflow::CreateTsKey::new(workflow_id);
 								let ray_id_key = keys::workflow::RayIdKey::new(workflow_id);
+								let cancel_ts_key = keys::workflow::CancelTsKey::new(workflow_id);
 								let input_key = keys::workflow::InputKey::new(workflow_id);
 								let input_subspace = self.subspace.subspace(&input_key);
 
-								let (create_ts_entry, ray_id_entry, input_chunks) = tokio::try_join!(
-									tx.get(&self.subspace.pack(&create_ts_key), SERIALIZABLE),
-									tx.get(&self.subspace.pack(&ray_id_key), SERIALIZABLE),
-									tx.get_ranges_keyvalues(
-										fdb::RangeOption {
-											mode: StreamingMode::WantAll,
-											..(&input_subspace).into()
-										},
-										SERIALIZABLE,
-									)
-									.try_collect::<Vec<_>>(),
-								)?;
+								let (create_ts_entry, ray_id_entry, cancel_ts_entry, input_chunks) =
+									tokio::try_join!(
+										tx.get(&self.subspace.pack(&create_ts_key), SERIALIZABLE),
+										tx.get(&self.subspace.pack(&ray_id_key), SERIALIZABLE),
+										tx.get(&self.subspace.pack(&cancel_ts_key), SERIALIZABLE),
+										tx.get_ranges_keyvalues(
+											fdb::RangeOption {
+												mode: StreamingMode::WantAll,
+												..(&input_subspace).into()
+											},
+											SERIALIZABLE,
+										)
+										.try_collect::<Vec<_>>(),
+									)?;
 
 								let create_ts = create_ts_key
 									.deserialize(&create_ts_entry.ok_or(
@@ -1232,6 +1656,10 @@ impl Database for DatabaseFdbSqliteNats {
 										),
 									)?)
 									.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
+								let cancel_ts = cancel_ts_entry
+									.map(|x| cancel_ts_key.deserialize(&x))
+									.transpose()
+									.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
 								let input = input_key
 									.combine(input_chunks)
 									.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
@@ -1243,6 +1671,7 @@ impl Database for DatabaseFdbSqliteNats {
 									ray_id,
 									input,
 									wake_deadline_ts,
+									cancel_ts,
 								})
 							}
 						})
@@ -1291,7 +1720,7 @@ impl Database for DatabaseFdbSqliteNats {
 							false,
 							None,
 							&[],
-							None,
+							&[],
 							&err.to_string(),
 						)
 						.await?;
@@ -1311,7 +1740,7 @@ impl Database for DatabaseFdbSqliteNats {
 							activity_name AS name,
 							NULL AS auxiliary_id,
 							input_hash AS hash,
-							NULL AS input,
+							json(retry_policy) AS input,
 							json(output) AS output,
 							create_ts AS create_ts,
 							(
@@ -1508,6 +1937,7 @@ impl Database for DatabaseFdbSqliteNats {
 						ray_id: partial.ray_id,
 						input: partial.input,
 						wake_deadline_ts: partial.wake_deadline_ts,
+						cancel_ts: partial.cancel_ts,
 						events: sqlite::build_history(events)?,
 					}))
 				}
@@ -1552,57 +1982,14 @@ impl Database for DatabaseFdbSqliteNats {
 			.fdb()?
 			.run(|tx, _mc| {
 				async move {
-					let sub_workflow_wake_subspace = self
-						.subspace
-						.subspace(&keys::wake::SubWorkflowWakeKey::subspace(workflow_id));
 					let tags_subspace = self
 						.subspace
 						.subspace(&keys::workflow::TagKey::subspace(workflow_id));
 					let wake_deadline_key = keys::workflow::WakeDeadlineKey::new(workflow_id);
 
-					let mut stream = tx.get_ranges_keyvalues(
-						fdb::RangeOption {
-							mode: StreamingMode::WantAll,
-							..(&sub_workflow_wake_subspace).into()
-						},
-						// NOTE: Must be serializable to conflict with `get_sub_workflow`
-						SERIALIZABLE,
-					);
-
 					let (_, tag_keys, wake_deadline_entry) = tokio::try_join!(
 						// Check for other workflows waiting on this one, wake all
-						async {
-							while let Some(entry) = stream.try_next().await? {
-								let sub_workflow_wake_key = self
-									.subspace
-									.unpack::<keys::wake::SubWorkflowWakeKey>(&entry.key())
-									.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
-								let workflow_name = sub_workflow_wake_key
-									.deserialize(entry.value())
-									.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
-
-								let wake_condition_key = keys::wake::WorkflowWakeConditionKey::new(
-									workflow_name,
-									sub_workflow_wake_key.workflow_id,
-									keys::wake::WakeCondition::SubWorkflow {
-										sub_workflow_id: workflow_id,
-									},
-								);
-
-								// Add wake condition for workflow
-								tx.set(
-									&self.subspace.pack(&wake_condition_key),
-									&wake_condition_key
-										.serialize(())
-										.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
-								);
-
-								// Clear secondary index
-								tx.clear(entry.key());
-							}
-
-							Result::<_, fdb::FdbBindingError>::Ok(())
-						},
+						self.wake_sub_workflow_waiters(workflow_id, &tx),
 						// Read tags
 						async {
 							tx.get_ranges_keyvalues(
@@ -1674,6 +2061,10 @@ impl Database for DatabaseFdbSqliteNats {
 						keys::workflow::HasWakeConditionKey::new(workflow_id);
 					tx.clear(&self.subspace.pack(&has_wake_condition_key));
 
+					// A cancel received while running has no effect on a completed workflow
+					let pending_cancel_key = keys::workflow::PendingCancelKey::new(workflow_id);
+					tx.clear(&self.subspace.pack(&pending_cancel_key));
+
 					// Write output
 					let output_key = keys::workflow::OutputKey::new(workflow_id);
 
@@ -1719,7 +2110,7 @@ impl Database for DatabaseFdbSqliteNats {
 		wake_immediate: bool,
 		wake_deadline_ts: Option<i64>,
 		wake_signals: &[&str],
-		wake_sub_workflow_id: Option<Uuid>,
+		wake_sub_workflows: &[Uuid],
 		error: &str,
 	) -> WorkflowResult<()> {
 		let start_instant = Instant::now();
@@ -1727,15 +2118,30 @@ impl Database for DatabaseFdbSqliteNats {
 		// Evict databases before releasing lease
 		self.evict_wf_sqlite(workflow_id).await?;
 
-		self.pools
+		let (has_wake_condition, pending_cancel) = self
+			.pools
 			.fdb()?
 			.run(|tx, _mc| {
 				async move {
 					let wake_deadline_key = keys::workflow::WakeDeadlineKey::new(workflow_id);
+					let pending_cancel_key = keys::workflow::PendingCancelKey::new(workflow_id);
 
-					let wake_deadline_entry = tx
-						.get(&self.subspace.pack(&wake_deadline_key), SERIALIZABLE)
-						.await?;
+					let (wake_deadline_entry, pending_cancel_entry) = tokio::try_join!(
+						tx.get(&self.subspace.pack(&wake_deadline_key), SERIALIZABLE),
+						tx.get(&self.subspace.pack(&pending_cancel_key), SERIALIZABLE),
+					)?;
+
+					// Keep immediate wake if the workflow was cancelled while running
+					let pending_cancel = pending_cancel_entry.is_some();
+					let wake_immediate = wake_immediate || pending_cancel;
+					let has_wake_condition = wake_immediate
+						|| wake_deadline_ts.is_some()
+						|| !wake_signals.is_empty()
+						|| !wake_sub_workflows.is_empty();
+
+					if pending_cancel {
+						tx.clear(&self.subspace.pack(&pending_cancel_key));
+					}
 
 					// Add immediate wake for workflow
 					if wake_immediate {
@@ -1788,81 +2194,197 @@ impl Database for DatabaseFdbSqliteNats {
 								.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
 						);
 
-						// Write to wake deadline
-						tx.set(
-							&self.subspace.pack(&wake_deadline_key),
-							&wake_deadline_key
-								.serialize(deadline_ts)
-								.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
-						);
+						// Write to wake deadline
+						tx.set(
+							&self.subspace.pack(&wake_deadline_key),
+							&wake_deadline_key
+								.serialize(deadline_ts)
+								.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
+						);
+					}
+
+					self.write_signal_wake_idxs(workflow_id, wake_signals, &tx)?;
+
+					// Write sub workflow wake indexes
+					for sub_workflow_id in wake_sub_workflows {
+						self.write_sub_workflow_wake_idx(
+							workflow_id,
+							workflow_name,
+							*sub_workflow_id,
+							&tx,
+						)?;
+					}
+
+					// Update "has wake condition"
+					let has_wake_condition_key =
+						keys::workflow::HasWakeConditionKey::new(workflow_id);
+					if has_wake_condition {
+						tx.set(
+							&self.subspace.pack(&has_wake_condition_key),
+							&has_wake_condition_key
+								.serialize(())
+								.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
+						);
+					} else {
+						tx.clear(&self.subspace.pack(&has_wake_condition_key));
+
+						// Nothing will wake this workflow again (it failed or was cancelled), wake workflows
+						// waiting on it so they don't wait forever
+						self.wake_sub_workflow_waiters(workflow_id, &tx).await?;
+					}
+
+					// Write error
+					let error_key = keys::workflow::ErrorKey::new(workflow_id);
+					tx.set(
+						&self.subspace.pack(&error_key),
+						&error_key
+							.serialize(error.to_string())
+							.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
+					);
+
+					// Clear lease
+					let lease_key = keys::workflow::LeaseKey::new(workflow_id);
+					tx.clear(&self.subspace.pack(&lease_key));
+					let worker_instance_id_key =
+						keys::workflow::WorkerInstanceIdKey::new(workflow_id);
+					tx.clear(&self.subspace.pack(&worker_instance_id_key));
+
+					Ok((has_wake_condition, pending_cancel))
+				}
+			})
+			.custom_instrument(tracing::info_span!("commit_workflow_tx"))
+			.await?;
+
+		if !has_wake_condition || pending_cancel {
+			self.wake_worker();
+		}
+
+		// Wake worker again if the deadline is before the next tick
+		if let Some(deadline_ts) = wake_deadline_ts {
+			if deadline_ts
+				<= rivet_util::timestamp::now() + self.worker_poll_interval().as_millis() as i64
+			{
+				self.wake_worker();
+			}
+		}
+
+		let dt = start_instant.elapsed().as_secs_f64();
+		metrics::COMMIT_WORKFLOW_DURATION
+			.with_label_values(&[workflow_name])
+			.observe(dt);
+
+		Ok(())
+	}
+
+	#[tracing::instrument(skip_all, fields(%workflow_id))]
+	async fn cancel_workflow(&self, workflow_id: Uuid) -> WorkflowResult<()> {
+		let cancelled = self
+			.pools
+			.fdb()?
+			.run(|tx, _mc| {
+				async move {
+					let workflow_name_key = keys::workflow::NameKey::new(workflow_id);
+					let output_key = keys::workflow::OutputKey::new(workflow_id);
+					let output_subspace = self.subspace.subspace(&output_key);
+					let cancel_ts_key = keys::workflow::CancelTsKey::new(workflow_id);
+					let lease_key = keys::workflow::LeaseKey::new(workflow_id);
+					let has_wake_condition_key =
+						keys::workflow::HasWakeConditionKey::new(workflow_id);
+
+					let (
+						workflow_name_entry,
+						output_chunk,
+						cancel_ts_entry,
+						lease_entry,
+						has_wake_condition_entry,
+					) = tokio::try_join!(
+						// NOTE: This does not have to be serializable because wf name doesn't change
+						tx.get(&self.subspace.pack(&workflow_name_key), SNAPSHOT),
+						async {
+							tx.get_ranges_keyvalues(
+								fdb::RangeOption {
+									mode: StreamingMode::WantAll,
+									limit: Some(1),
+									..(&output_subspace).into()
+								},
+								SERIALIZABLE,
+							)
+							.try_next()
+							.await
+						},
+						tx.get(&self.subspace.pack(&cancel_ts_key), SERIALIZABLE),
+						tx.get(&self.subspace.pack(&lease_key), SERIALIZABLE),
+						tx.get(&self.subspace.pack(&has_wake_condition_key), SERIALIZABLE),
+					)?;
+
+					// Check if the workflow exists
+					let Some(workflow_name_entry) = workflow_name_entry else {
+						return Err(fdb::FdbBindingError::CustomError(
+							WorkflowError::WorkflowNotFound.into(),
+						));
+					};
+
+					// Already complete or already cancelled
+					if output_chunk.is_some() || cancel_ts_entry.is_some() {
+						return Ok(false);
 					}
 
-					self.write_signal_wake_idxs(workflow_id, wake_signals, &tx)?;
-
-					// Write sub workflow wake index
-					if let Some(sub_workflow_id) = wake_sub_workflow_id {
-						self.write_sub_workflow_wake_idx(
-							workflow_id,
-							workflow_name,
-							sub_workflow_id,
-							&tx,
-						)?;
+					// Nothing will wake a dead workflow, cancelling it would revive it
+					if lease_entry.is_none() && has_wake_condition_entry.is_none() {
+						return Err(fdb::FdbBindingError::CustomError(
+							WorkflowError::WorkflowDead(workflow_id).into(),
+						));
 					}
 
-					// Update "has wake condition"
-					let has_wake_condition_key =
-						keys::workflow::HasWakeConditionKey::new(workflow_id);
-					if wake_immediate
-						|| wake_deadline_ts.is_some()
-						|| !wake_signals.is_empty()
-						|| wake_sub_workflow_id.is_some()
-					{
+					// Write cancel ts
+					tx.set(
+						&self.subspace.pack(&cancel_ts_key),
+						&cancel_ts_key
+							.serialize(rivet_util::timestamp::now())
+							.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
+					);
+
+					// The wake conditions of a leased workflow are overwritten when it commits. Mark the cancel
+					// as pending instead so `commit_workflow` wakes it immediately.
+					if lease_entry.is_some() {
+						let pending_cancel_key = keys::workflow::PendingCancelKey::new(workflow_id);
 						tx.set(
-							&self.subspace.pack(&has_wake_condition_key),
-							&has_wake_condition_key
+							&self.subspace.pack(&pending_cancel_key),
+							&pending_cancel_key
 								.serialize(())
 								.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
 						);
-					} else {
-						tx.clear(&self.subspace.pack(&has_wake_condition_key));
+
+						return Ok(false);
 					}
 
-					// Write error
-					let error_key = keys::workflow::ErrorKey::new(workflow_id);
+					let workflow_name = workflow_name_key
+						.deserialize(&workflow_name_entry)
+						.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
+
+					// Add immediate wake for workflow
+					let wake_condition_key = keys::wake::WorkflowWakeConditionKey::new(
+						workflow_name,
+						workflow_id,
+						keys::wake::WakeCondition::Immediate,
+					);
 					tx.set(
-						&self.subspace.pack(&error_key),
-						&error_key
-							.serialize(error.to_string())
+						&self.subspace.pack(&wake_condition_key),
+						&wake_condition_key
+							.serialize(())
 							.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
 					);
 
-					// Clear lease
-					let lease_key = keys::workflow::LeaseKey::new(workflow_id);
-					tx.clear(&self.subspace.pack(&lease_key));
-					let worker_instance_id_key =
-						keys::workflow::WorkerInstanceIdKey::new(workflow_id);
-					tx.clear(&self.subspace.pack(&worker_instance_id_key));
-
-					Ok(())
+					Ok(true)
 				}
 			})
-			.custom_instrument(tracing::info_span!("commit_workflow_tx"))
+			.custom_instrument(tracing::info_span!("cancel_workflow_tx"))
 			.await?;
 
-		// Wake worker again if the deadline is before the next tick
-		if let Some(deadline_ts) = wake_deadline_ts {
-			if deadline_ts
-				<= rivet_util::timestamp::now() + self.worker_poll_interval().as_millis() as i64
-			{
-				self.wake_worker();
-			}
+		if cancelled {
+			self.wake_worker();
 		}
 
-		let dt = start_instant.elapsed().as_secs_f64();
-		metrics::COMMIT_WORKFLOW_DURATION
-			.with_label_values(&[workflow_name])
-			.observe(dt);
-
 		Ok(())
 	}
 
@@ -2101,9 +2623,20 @@ impl Database for DatabaseFdbSqliteNats {
 					let output_subspace = self.subspace.subspace(&output_key);
 					let has_wake_condition_key =
 						keys::workflow::HasWakeConditionKey::new(sub_workflow_id);
+					let worker_instance_id_key =
+						keys::workflow::WorkerInstanceIdKey::new(sub_workflow_id);
+					let cancel_ts_key = keys::workflow::CancelTsKey::new(sub_workflow_id);
+					let error_key = keys::workflow::ErrorKey::new(sub_workflow_id);
 
 					// Read input and output
-					let (input_chunks, output_chunks, has_wake_condition_entry) = tokio::try_join!(
+					let (
+						input_chunks,
+						output_chunks,
+						has_wake_condition_entry,
+						worker_instance_id_entry,
+						cancel_ts_entry,
+						error_entry,
+					) = tokio::try_join!(
 						tx.get_ranges_keyvalues(
 							fdb::RangeOption {
 								mode: StreamingMode::WantAll,
@@ -2121,6 +2654,9 @@ impl Database for DatabaseFdbSqliteNats {
 						)
 						.try_collect::<Vec<_>>(),
 						tx.get(&self.subspace.pack(&has_wake_condition_key), SERIALIZABLE),
+						tx.get(&self.subspace.pack(&worker_instance_id_key), SERIALIZABLE),
+						tx.get(&self.subspace.pack(&cancel_ts_key), SERIALIZABLE),
+						tx.get(&self.subspace.pack(&error_key), SERIALIZABLE),
 					)?;
 
 					if input_chunks.is_empty() {
@@ -2160,6 +2696,12 @@ impl Database for DatabaseFdbSqliteNats {
 							input,
 							output,
 							has_wake_condition: has_wake_condition_entry.is_some(),
+							is_active: worker_instance_id_entry.is_some(),
+							cancel_requested: cancel_ts_entry.is_some(),
+							error: error_entry
+								.map(|raw| error_key.deserialize(&raw))
+								.transpose()
+								.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
 						}))
 					}
 				}
@@ -2177,8 +2719,10 @@ impl Database for DatabaseFdbSqliteNats {
 		signal_id: Uuid,
 		signal_name: &str,
 		body: &serde_json::value::RawValue,
-	) -> WorkflowResult<()> {
-		self.pools
+		idempotency_key: Option<&IdempotencyKey>,
+	) -> WorkflowResult<Uuid> {
+		let signal_id = self
+			.pools
 			.fdb()?
 			.run(|tx, _mc| {
 				async move {
@@ -2199,6 +2743,57 @@ impl Database for DatabaseFdbSqliteNats {
 						.deserialize(&workflow_name_entry)
 						.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
 
+					if let Some(idempotency_key) = idempotency_key {
+						let now = rivet_util::timestamp::now();
+						let idempotency_key_key = keys::signal::IdempotencyKeyKey::new(
+							workflow_id,
+							signal_name.to_string(),
+							idempotency_key.key.clone(),
+						);
+
+						// Check if a signal with the same idempotency key was already published within the
+						// window
+						if let Some(entry) = tx
+							.get(&self.subspace.pack(&idempotency_key_key), SERIALIZABLE)
+							.await?
+						{
+							let (original_signal_id, expire_ts) = idempotency_key_key
+								.deserialize(&entry)
+								.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
+
+							if now < expire_ts {
+								tracing::debug!(
+									?original_signal_id,
+									"signal with idempotency key already published"
+								);
+
+								return Ok(original_signal_id);
+							}
+						}
+
+						let expire_ts = now + idempotency_key.window_ms;
+						tx.set(
+							&self.subspace.pack(&idempotency_key_key),
+							&idempotency_key_key
+								.serialize((signal_id, expire_ts))
+								.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
+						);
+
+						// Index for gc
+						let expire_ts_key = keys::signal::IdempotencyKeyExpireTsKey::new(
+							expire_ts,
+							workflow_id,
+							signal_name.to_string(),
+							idempotency_key.key.clone(),
+						);
+						tx.set(
+							&self.subspace.pack(&expire_ts_key),
+							&expire_ts_key
+								.serialize(())
+								.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
+						);
+					}
+
 					// Write name
 					let name_key = keys::signal::NameKey::new(signal_id);
 					tx.set(
@@ -2288,7 +2883,7 @@ impl Database for DatabaseFdbSqliteNats {
 						);
 					}
 
-					Ok(())
+					Ok(signal_id)
 				}
 			})
 			.custom_instrument(tracing::info_span!("publish_signal_tx"))
@@ -2296,7 +2891,7 @@ impl Database for DatabaseFdbSqliteNats {
 
 		self.wake_worker();
 
-		Ok(())
+		Ok(signal_id)
 	}
 
 	#[tracing::instrument(skip_all)]
@@ -2307,7 +2902,8 @@ impl Database for DatabaseFdbSqliteNats {
 		_signal_id: Uuid,
 		_signal_name: &str,
 		_body: &serde_json::value::RawValue,
-	) -> WorkflowResult<()> {
+		_idempotency_key: Option<&IdempotencyKey>,
+	) -> WorkflowResult<Uuid> {
 		Err(WorkflowError::TaggedSignalsDisabled)
 	}
 
@@ -2362,7 +2958,7 @@ impl Database for DatabaseFdbSqliteNats {
 			.await?;
 
 		if let Err(err) = self
-			.publish_signal(ray_id, to_workflow_id, signal_id, signal_name, body)
+			.publish_signal(ray_id, to_workflow_id, signal_id, signal_name, body, None)
 			.await
 		{
 			// Undo history if FDB failed
@@ -2594,6 +3190,7 @@ impl Database for DatabaseFdbSqliteNats {
 		event_id: &EventId,
 		create_ts: i64,
 		input: &serde_json::value::RawValue,
+		retry_policy: &serde_json::value::RawValue,
 		res: Result<&serde_json::value::RawValue, &str>,
 		loop_location: Option<&Location>,
 	) -> WorkflowResult<()> {
@@ -2616,9 +3213,10 @@ impl Database for DatabaseFdbSqliteNats {
 						input,
 						output,
 						create_ts,
-						loop_location
+						loop_location,
+						retry_policy
 					)
-					VALUES (jsonb(?1), ?2, ?3, ?4, jsonb(?5), jsonb(?6), ?7, jsonb(?8))
+					VALUES (jsonb(?1), ?2, ?3, ?4, jsonb(?5), jsonb(?6), ?7, jsonb(?8), jsonb(?9))
 					ON CONFLICT (location) DO UPDATE
 					SET output = EXCLUDED.output
 					",
@@ -2630,6 +3228,7 @@ impl Database for DatabaseFdbSqliteNats {
 					sqlx::types::Json(output),
 					create_ts,
 					loop_location,
+					sqlx::types::Json(retry_policy),
 				)
 				.await?;
 			}
@@ -2648,9 +3247,10 @@ impl Database for DatabaseFdbSqliteNats {
 							input_hash,
 							input,
 							create_ts,
-							loop_location
+							loop_location,
+							retry_policy
 						)
-						VALUES (jsonb(?1), ?2, ?3, ?4, jsonb(?5), ?6, jsonb(?7))
+						VALUES (jsonb(?1), ?2, ?3, ?4, jsonb(?5), ?6, jsonb(?7), jsonb(?8))
 						ON CONFLICT (location) DO NOTHING
 						",
 						location,
@@ -2660,6 +3260,7 @@ impl Database for DatabaseFdbSqliteNats {
 						sqlx::types::Json(input),
 						create_ts,
 						loop_location,
+						sqlx::types::Json(retry_policy),
 					)
 					.await?;
 
@@ -2797,119 +3398,7 @@ impl Database for DatabaseFdbSqliteNats {
 					workflow_name == "pegboard_client" || workflow_name == "pegboard_actor";
 
 				if delete_instead_of_forget {
-					sql_execute!(
-						[self, @tx &mut tx]
-						"
-						DELETE FROM workflow_activity_errors
-						WHERE location IN (
-							SELECT location
-							FROM workflow_activity_events
-							WHERE loop_location = jsonb(?1)
-						)
-						",
-						location,
-					)
-					.await?;
-
-					sql_execute!(
-						[self, @tx &mut tx]
-						"
-						DELETE FROM workflow_activity_events
-						WHERE loop_location = jsonb(?1) AND NOT forgotten
-						",
-						location,
-					)
-					.await?;
-
-					sql_execute!(
-						[self, @tx &mut tx]
-						"
-						DELETE FROM workflow_signal_events
-						WHERE loop_location = jsonb(?1) AND NOT forgotten
-						",
-						location,
-					)
-					.await?;
-
-					sql_execute!(
-						[self, @tx &mut tx]
-						"
-						DELETE FROM workflow_sub_workflow_events
-						WHERE loop_location = jsonb(?1) AND NOT forgotten
-						",
-						location,
-					)
-					.await?;
-
-					sql_execute!(
-						[self, @tx &mut tx]
-						"
-						DELETE FROM workflow_signal_send_events
-						WHERE loop_location = jsonb(?1) AND NOT forgotten
-						",
-						location,
-					)
-					.await?;
-
-					sql_execute!(
-						[self, @tx &mut tx]
-						"
-						DELETE FROM workflow_message_send_events
-						WHERE loop_location = jsonb(?1) AND NOT forgotten
-						",
-						location,
-					)
-					.await?;
-
-					sql_execute!(
-						[self, @tx &mut tx]
-						"
-						DELETE FROM workflow_loop_events
-						WHERE loop_location = jsonb(?1) AND NOT forgotten
-						",
-						location,
-					)
-					.await?;
-
-					sql_execute!(
-						[self, @tx &mut tx]
-						"
-						DELETE FROM workflow_sleep_events
-						WHERE loop_location = jsonb(?1) AND NOT forgotten
-						",
-						location,
-					)
-					.await?;
-
-					sql_execute!(
-						[self, @tx &mut tx]
-						"
-						DELETE FROM workflow_branch_events
-						WHERE loop_location = jsonb(?1) AND NOT forgotten
-						",
-						location,
-					)
-					.await?;
-
-					sql_execute!(
-						[self, @tx &mut tx]
-						"
-						DELETE FROM workflow_removed_events
-						WHERE loop_location = jsonb(?1) AND NOT forgotten
-						",
-						location,
-					)
-					.await?;
-
-					sql_execute!(
-						[self, @tx &mut tx]
-						"
-						DELETE FROM workflow_version_check_events
-						WHERE loop_location = jsonb(?1) AND NOT forgotten
-						",
-						location,
-					)
-					.await?;
+					self.delete_loop_history(&mut tx, location).await?;
 				} else {
 					sql_execute!(
 						[self, @tx &mut tx]
@@ -3032,6 +3521,63 @@ impl Database for DatabaseFdbSqliteNats {
 		Ok(())
 	}
 
+	#[tracing::instrument(skip_all)]
+	async fn commit_workflow_snapshot_event(
+		&self,
+		workflow_id: Uuid,
+		location: &Location,
+		version: usize,
+		output: &serde_json::value::RawValue,
+		loop_location: Option<&Location>,
+	) -> WorkflowResult<()> {
+		let pool = &self
+			.pools
+			.sqlite(crate::db::sqlite_db_name_internal(workflow_id), false)
+			.await?;
+
+		self.txn(|| async {
+			let mut conn = pool.conn().await?;
+			let mut tx = conn.begin().await?;
+
+			// Snapshots are stored as a loop event with a single completed iteration
+			sql_execute!(
+				[self, @tx &mut tx]
+				"
+				INSERT INTO workflow_loop_events (
+					location,
+					version,
+					iteration,
+					state,
+					output,
+					create_ts,
+					loop_location
+				)
+				VALUES (jsonb(?1), ?2, 1, jsonb('null'), jsonb(?3), ?4, jsonb(?5))
+				ON CONFLICT (location) DO UPDATE
+				SET
+					iteration = 1,
+					output = jsonb(?3)
+				",
+				location,
+				version as i64,
+				sqlx::types::Json(output),
+				rivet_util::timestamp::now(),
+				loop_location,
+			)
+			.await?;
+
+			// Truncate all history recorded within the snapshot
+			self.delete_loop_history(&mut tx, location).await?;
+
+			tx.commit().await.map_err(WorkflowError::Sqlx)?;
+
+			Ok(())
+		})
+		.await?;
+
+		Ok(())
+	}
+
 	#[tracing::instrument(skip_all)]
 	async fn commit_workflow_sleep_event(
 		&self,
@@ -3196,6 +3742,7 @@ struct PartialWorkflow {
 	pub ray_id: Uuid,
 	pub input: Box<serde_json::value::RawValue>,
 	pub wake_deadline_ts: Option<i64>,
+	pub cancel_ts: Option<i64>,
 }
 
 #[derive(Debug, Default)]
@@ -3230,8 +3777,16 @@ async fn flush_handler(pools: rivet_pools::Pools, mut flush_rx: mpsc::UnboundedR
 			)
 			.await
 		{
-			// TODO: Somehow forward the error to the workflow so it can die
-			tracing::error!(?workflow_id, ?err, "failed to flush workflow databases");
+			if let rivet_pools::Error::SqliteWriterLeaseLost { .. } = err {
+				// The workflow will fail when it is evicted
+				tracing::error!(
+					?workflow_id,
+					"another worker took over this workflow's databases, changes will not be flushed"
+				);
+			} else {
+				// TODO: Somehow forward the error to the workflow so it can die
+				tracing::error!(?workflow_id, ?err, "failed to flush workflow databases");
+			}
 		}
 	}
 
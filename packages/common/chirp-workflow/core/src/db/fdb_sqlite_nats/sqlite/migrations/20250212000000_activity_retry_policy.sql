@@ -0,0 +1,2 @@
+-- Retry policy recorded when the activity first ran
+ALTER TABLE workflow_activity_events ADD COLUMN retry_policy BLOB; -- JSONB
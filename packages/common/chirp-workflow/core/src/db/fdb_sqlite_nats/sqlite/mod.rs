@@ -278,6 +278,8 @@ impl TryFrom<AmalgamEventRow> for ActivityEvent {
 				.ok_or(WorkflowError::MissingEventData)?
 				.try_into()
 				.map_err(|_| WorkflowError::IntegerConversion)?,
+			// NOTE: The retry policy is selected as the input column
+			retry_policy: value.input.map(|x| x.0),
 		})
 	}
 }
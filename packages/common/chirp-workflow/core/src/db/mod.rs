@@ -70,6 +70,25 @@ pub trait Database: Send {
 	/// the expired threshold), making them eligible to be run again. Called periodically.
 	async fn clear_expired_leases(&self, worker_instance_id: Uuid) -> WorkflowResult<()>;
 
+	/// Deletes signal idempotency keys whose window has passed. Called periodically.
+	async fn clear_expired_idempotency_keys(&self) -> WorkflowResult<()>;
+
+	/// Retrieves the timestamp of the last claimed run of the given schedule.
+	async fn get_schedule_last_run_ts(&self, schedule_name: &str) -> WorkflowResult<Option<i64>>;
+
+	/// Sets the last run timestamp of the given schedule to `run_ts` if it is still `prev_run_ts`. Returns
+	/// false if another worker claimed a run first.
+	///
+	/// If `dispatch` is set, the run's workflow is dispatched in the same transaction as the claim so a run
+	/// is never lost or dispatched twice if the worker fails in between.
+	async fn claim_schedule_run(
+		&self,
+		schedule_name: &str,
+		prev_run_ts: Option<i64>,
+		run_ts: i64,
+		dispatch: Option<ScheduleDispatch<'_>>,
+	) -> WorkflowResult<bool>;
+
 	/// Function to publish metrics. Called periodically.
 	async fn publish_metrics(&self, worker_instance_id: Uuid) -> WorkflowResult<()>;
 
@@ -88,6 +107,15 @@ pub trait Database: Send {
 	/// Retrieves a workflow with the given ID.
 	async fn get_workflow(&self, workflow_id: Uuid) -> WorkflowResult<Option<WorkflowData>>;
 
+	/// Retrieves the state of the workflow's current top level loop (a loop not nested in another loop). If
+	/// multiple top level loops are running (in separate branches), the one with the greatest location is used.
+	/// Fails if the workflow does not exist or does not have the given name.
+	async fn get_workflow_loop_state(
+		&self,
+		workflow_id: Uuid,
+		workflow_name: &str,
+	) -> WorkflowResult<Option<Box<serde_json::value::RawValue>>>;
+
 	/// Retrieves the first incomplete workflow with the given name and tags.
 	async fn find_workflow(
 		&self,
@@ -119,10 +147,14 @@ pub trait Database: Send {
 		wake_immediate: bool,
 		wake_deadline_ts: Option<i64>,
 		wake_signals: &[&str],
-		wake_sub_workflow: Option<Uuid>,
+		wake_sub_workflows: &[Uuid],
 		error: &str,
 	) -> WorkflowResult<()>;
 
+	/// Marks a workflow as cancelled and wakes it. The workflow will run its compensation handlers on its
+	/// next run and complete with a cancellation error. Does nothing if the workflow is already complete.
+	async fn cancel_workflow(&self, workflow_id: Uuid) -> WorkflowResult<()>;
+
 	/// Pulls the oldest signal with the given filter. Pulls from regular and tagged signals.
 	async fn pull_next_signal(
 		&self,
@@ -143,7 +175,9 @@ pub trait Database: Send {
 		sub_workflow_id: Uuid,
 	) -> WorkflowResult<Option<WorkflowData>>;
 
-	/// Write a new signal to the database.
+	/// Write a new signal to the database. If an idempotency key is given and a signal with the same name and
+	/// key was published to the same workflow within its window, nothing is written. Returns the ID of the
+	/// published signal (the original signal's ID on duplicates).
 	async fn publish_signal(
 		&self,
 		ray_id: Uuid,
@@ -151,9 +185,11 @@ pub trait Database: Send {
 		signal_id: Uuid,
 		signal_name: &str,
 		body: &serde_json::value::RawValue,
-	) -> WorkflowResult<()>;
+		idempotency_key: Option<&IdempotencyKey>,
+	) -> WorkflowResult<Uuid>;
 
-	/// Write a new tagged signal to the database.
+	/// Write a new tagged signal to the database. Deduplicates the same way as `publish_signal`, against
+	/// signals published with the same tags.
 	async fn publish_tagged_signal(
 		&self,
 		ray_id: Uuid,
@@ -161,7 +197,8 @@ pub trait Database: Send {
 		signal_id: Uuid,
 		signal_name: &str,
 		body: &serde_json::value::RawValue,
-	) -> WorkflowResult<()>;
+		idempotency_key: Option<&IdempotencyKey>,
+	) -> WorkflowResult<Uuid>;
 
 	/// Write a new signal to the database. Contains extra info used to populate the history.
 	async fn publish_signal_from_workflow(
@@ -223,6 +260,7 @@ pub trait Database: Send {
 		event_id: &EventId,
 		create_ts: i64,
 		input: &serde_json::value::RawValue,
+		retry_policy: &serde_json::value::RawValue,
 		output: Result<&serde_json::value::RawValue, &str>,
 		loop_location: Option<&Location>,
 	) -> WorkflowResult<()>;
@@ -252,6 +290,17 @@ pub trait Database: Send {
 		loop_location: Option<&Location>,
 	) -> WorkflowResult<()>;
 
+	/// Writes a completed snapshot event to history and deletes all history events recorded within the
+	/// snapshot.
+	async fn commit_workflow_snapshot_event(
+		&self,
+		workflow_id: Uuid,
+		location: &Location,
+		version: usize,
+		output: &serde_json::value::RawValue,
+		loop_location: Option<&Location>,
+	) -> WorkflowResult<()>;
+
 	/// Writes a workflow sleep event to history.
 	async fn commit_workflow_sleep_event(
 		&self,
@@ -305,9 +354,27 @@ pub struct WorkflowData {
 	input: Box<serde_json::value::RawValue>,
 	output: Option<Box<serde_json::value::RawValue>>,
 	pub has_wake_condition: bool,
+	/// Whether or not the workflow is currently being run by a worker.
+	pub is_active: bool,
+	/// Whether or not the workflow was cancelled. Its compensation handlers may still be running.
+	pub cancel_requested: bool,
+	/// Error from the last run of the workflow, if it did not complete.
+	pub error: Option<String>,
 }
 
 impl WorkflowData {
+	/// A workflow is dead when it is incomplete, not running, and has nothing that will wake it (it failed or
+	/// was cancelled).
+	pub fn is_dead(&self) -> bool {
+		self.output.is_none() && !self.is_active && !self.has_wake_condition
+	}
+
+	/// A workflow is cancelled when it was cancelled and has finished running its compensation handlers.
+	/// Cancelled workflows never have an output.
+	pub fn is_cancelled(&self) -> bool {
+		self.is_dead() && self.cancel_requested
+	}
+
 	pub fn parse_input<W: Workflow>(self) -> WorkflowResult<W::Input> {
 		serde_json::from_str(self.input.get()).map_err(WorkflowError::DeserializeWorkflowInput)
 	}
@@ -328,6 +395,7 @@ pub struct PulledWorkflowData {
 	pub ray_id: Uuid,
 	pub input: Box<serde_json::value::RawValue>,
 	pub wake_deadline_ts: Option<i64>,
+	pub cancel_ts: Option<i64>,
 
 	pub events: HashMap<Location, Vec<Event>>,
 }
@@ -339,6 +407,24 @@ pub struct SignalData {
 	pub create_ts: i64,
 }
 
+/// Deduplicates signals published to the same target with the same name and key.
+#[derive(Debug, Clone)]
+pub struct IdempotencyKey {
+	pub key: String,
+	/// How long the key is kept after the first signal is published, in milliseconds.
+	pub window_ms: i64,
+}
+
+/// Workflow dispatched along with a claimed schedule run, see `Database::claim_schedule_run`.
+#[derive(Debug, Clone, Copy)]
+pub struct ScheduleDispatch<'a> {
+	pub ray_id: Uuid,
+	pub workflow_id: Uuid,
+	pub workflow_name: &'a str,
+	pub tags: &'a serde_json::Value,
+	pub input: &'a serde_json::value::RawValue,
+}
+
 /// Database name for the local SQLite database for a workflow.
 pub fn sqlite_db_name_data(workflow_id: Uuid) -> (usize, Uuid, usize) {
 	(WORKFLOW, workflow_id, DATA)
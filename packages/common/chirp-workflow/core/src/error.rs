@@ -1,11 +1,8 @@
-use std::time::{SystemTime, UNIX_EPOCH};
-
 use foundationdb as fdb;
 use global_error::GlobalError;
-use tokio::time::Instant;
 use uuid::Uuid;
 
-use crate::ctx::common::RETRY_TIMEOUT_MS;
+use crate::activity::ActivityBackoff;
 
 pub type WorkflowResult<T> = Result<T, WorkflowError>;
 
@@ -14,16 +11,25 @@ pub enum WorkflowError {
 	#[error("workflow failure: {0:?}")]
 	WorkflowFailure(GlobalError),
 
-	// Includes error count
+	// Includes error count and backoff
 	#[error("activity failure: {0:?}")]
-	ActivityFailure(GlobalError, usize),
+	ActivityFailure(GlobalError, usize, ActivityBackoff),
 
 	#[error("activity failure, max retries reached: {0:?}")]
 	ActivityMaxFailuresReached(GlobalError),
 
+	#[error("activity failure, not retryable: {0:?}")]
+	ActivityNotRetryable(GlobalError),
+
 	#[error("operation failure: {0:?}")]
 	OperationFailure(GlobalError),
 
+	#[error("query failure: {0:?}")]
+	QueryFailure(GlobalError),
+
+	#[error("cannot query workflow {0}, expected workflow {1}")]
+	QueryWorkflowMismatch(String, String),
+
 	#[error("workflow missing from registry: {0}")]
 	WorkflowMissingFromRegistry(String),
 
@@ -33,6 +39,18 @@ pub enum WorkflowError {
 	#[error("workflow stopped")]
 	WorkflowStopped,
 
+	#[error("workflow cancelled")]
+	WorkflowCancelled,
+
+	#[error("workflow {0} is dead and cannot be cancelled")]
+	WorkflowDead(Uuid),
+
+	#[error("workflow {0} was cancelled before completing")]
+	CancelledWorkflowOutput(Uuid),
+
+	#[error("workflow {0} failed: {1}")]
+	FailedWorkflowOutput(Uuid, String),
+
 	#[error("history diverged: {0}")]
 	HistoryDiverged(String),
 
@@ -60,6 +78,12 @@ pub enum WorkflowError {
 	#[error("deserialize activity output: {0}")]
 	DeserializeActivityOutput(serde_json::Error),
 
+	#[error("serialize activity retry policy: {0}")]
+	SerializeActivityRetryPolicy(serde_json::Error),
+
+	#[error("deserialize activity retry policy: {0}")]
+	DeserializeActivityRetryPolicy(serde_json::Error),
+
 	#[error("serialize signal body: {0}")]
 	SerializeSignalBody(serde_json::Error),
 
@@ -132,6 +156,12 @@ pub enum WorkflowError {
 	#[error("sub workflow incomplete: {0:?}")]
 	SubWorkflowIncomplete(Uuid),
 
+	#[error("sub workflows incomplete: {0:?}")]
+	SubWorkflowsIncomplete(Vec<Uuid>),
+
+	#[error("cannot select from an empty list of sub workflows")]
+	EmptySubWorkflowSelect,
+
 	#[error("integer conversion failed")]
 	IntegerConversion,
 
@@ -150,15 +180,26 @@ pub enum WorkflowError {
 	#[error("pools error: {0}")]
 	Pools(#[from] rivet_pools::Error),
 
+	#[error("lost sqlite writer lease for workflow {0}, another worker took over")]
+	SqliteWriterLeaseLost(Uuid),
+
+	// Includes error count and backoff
 	#[error("activity timed out")]
-	ActivityTimeout(usize),
+	ActivityTimeout(usize, ActivityBackoff),
 
+	// Includes error count and backoff
 	#[error("operation timed out")]
-	OperationTimeout(usize),
+	OperationTimeout(usize, ActivityBackoff),
 
 	#[error("duplicate registered workflow: {0}")]
 	DuplicateRegisteredWorkflow(String),
 
+	#[error("duplicate registered schedule: {0}")]
+	DuplicateRegisteredSchedule(String),
+
+	#[error("invalid schedule: {0}")]
+	InvalidSchedule(String),
+
 	#[error("sleeping until {0}")]
 	Sleep(i64),
 
@@ -198,24 +239,10 @@ impl WorkflowError {
 	/// Returns the next deadline for a workflow to be woken up again based on the error.
 	pub(crate) fn deadline_ts(&self) -> Option<i64> {
 		match self {
-			WorkflowError::ActivityFailure(_, error_count)
-			| WorkflowError::ActivityTimeout(error_count)
-			| WorkflowError::OperationTimeout(error_count) => {
-				// NOTE: Max retry is handled in `WorkflowCtx::activity`
-				let mut backoff =
-					rivet_util::Backoff::new_at(8, None, RETRY_TIMEOUT_MS, 500, *error_count);
-				let next = backoff.step().expect("should not have max retry");
-
-				// Calculate timestamp based on the backoff
-				let duration_until = next.duration_since(Instant::now());
-				let deadline_ts = (SystemTime::now() + duration_until)
-					.duration_since(UNIX_EPOCH)
-					.unwrap_or_else(|err| unreachable!("time is broken: {}", err))
-					.as_millis()
-					.try_into()
-					.expect("doesn't fit in i64");
-
-				Some(deadline_ts)
+			WorkflowError::ActivityFailure(_, error_count, backoff)
+			| WorkflowError::ActivityTimeout(error_count, backoff)
+			| WorkflowError::OperationTimeout(error_count, backoff) => {
+				Some(backoff.deadline_ts(*error_count))
 			}
 			WorkflowError::Sleep(ts) | WorkflowError::NoSignalFoundAndSleep(_, ts) => Some(*ts),
 			_ => None,
@@ -225,12 +252,13 @@ impl WorkflowError {
 	/// Any error that the workflow can continue on with its execution from.
 	pub fn is_recoverable(&self) -> bool {
 		match self {
-			WorkflowError::ActivityFailure(_, _)
-			| WorkflowError::ActivityTimeout(_)
-			| WorkflowError::OperationTimeout(_)
+			WorkflowError::ActivityFailure(_, _, _)
+			| WorkflowError::ActivityTimeout(_, _)
+			| WorkflowError::OperationTimeout(_, _)
 			| WorkflowError::NoSignalFound(_)
 			| WorkflowError::NoSignalFoundAndSleep(_, _)
 			| WorkflowError::SubWorkflowIncomplete(_)
+			| WorkflowError::SubWorkflowsIncomplete(_)
 			| WorkflowError::Sleep(_)
 			| WorkflowError::WorkflowStopped => true,
 			_ => false,
@@ -240,9 +268,9 @@ impl WorkflowError {
 	/// Any error that the workflow can try again on a fixed number of times. Only used for printing.
 	pub(crate) fn is_retryable(&self) -> bool {
 		match self {
-			WorkflowError::ActivityFailure(_, _)
-			| WorkflowError::ActivityTimeout(_)
-			| WorkflowError::OperationTimeout(_) => true,
+			WorkflowError::ActivityFailure(_, _, _)
+			| WorkflowError::ActivityTimeout(_, _)
+			| WorkflowError::OperationTimeout(_, _) => true,
 			_ => false,
 		}
 	}
@@ -255,11 +283,13 @@ impl WorkflowError {
 		}
 	}
 
-	pub(crate) fn sub_workflow(&self) -> Option<Uuid> {
-		if let WorkflowError::SubWorkflowIncomplete(sub_workflow_id) = self {
-			Some(*sub_workflow_id)
-		} else {
-			None
+	pub(crate) fn sub_workflows(&self) -> &[Uuid] {
+		match self {
+			WorkflowError::SubWorkflowIncomplete(sub_workflow_id) => {
+				std::slice::from_ref(sub_workflow_id)
+			}
+			WorkflowError::SubWorkflowsIncomplete(sub_workflow_ids) => sub_workflow_ids,
+			_ => &[],
 		}
 	}
 
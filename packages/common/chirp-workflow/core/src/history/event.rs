@@ -8,7 +8,10 @@ use strum::FromRepr;
 use uuid::Uuid;
 
 use super::location::Coordinate;
-use crate::error::{WorkflowError, WorkflowResult};
+use crate::{
+	activity::RetryPolicy,
+	error::{WorkflowError, WorkflowResult},
+};
 
 /// An event that happened in the workflow run.
 ///
@@ -126,6 +129,9 @@ pub struct ActivityEvent {
 	/// If the activity succeeds, this will be some.
 	pub(crate) output: Option<Box<serde_json::value::RawValue>>,
 	pub error_count: usize,
+	/// Retry policy recorded when the activity first ran. None for activities recorded before retry
+	/// policies were persisted.
+	pub(crate) retry_policy: Option<Box<serde_json::value::RawValue>>,
 }
 
 impl ActivityEvent {
@@ -136,6 +142,14 @@ impl ActivityEvent {
 			.transpose()
 			.map_err(WorkflowError::DeserializeActivityOutput)
 	}
+
+	pub fn parse_retry_policy(&self) -> WorkflowResult<Option<RetryPolicy>> {
+		self.retry_policy
+			.as_ref()
+			.map(|x| serde_json::from_str(x.get()))
+			.transpose()
+			.map_err(WorkflowError::DeserializeActivityRetryPolicy)
+	}
 }
 
 #[derive(Debug)]
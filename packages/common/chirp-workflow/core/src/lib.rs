@@ -11,7 +11,9 @@ pub mod message;
 pub mod metrics;
 pub mod operation;
 pub mod prelude;
+pub mod query;
 pub mod registry;
+pub mod schedule;
 pub mod signal;
 mod stub;
 pub mod utils;
@@ -15,7 +15,7 @@ pub mod util {
 
 pub use crate::{
 	activity::Activity as ActivityTrait,
-	ctx::workflow::Loop,
+	ctx::workflow::{Loop, SubWorkflowResult},
 	ctx::*,
 	db::{self, Database},
 	error::{WorkflowError, WorkflowResult},
@@ -24,7 +24,9 @@ pub use crate::{
 	listen::{CustomListener, Listen},
 	message::Message as MessageTrait,
 	operation::Operation as OperationTrait,
+	query::Query as QueryTrait,
 	registry::Registry,
+	schedule::{MissedRunPolicy, Schedule},
 	signal::{join_signal, Signal as SignalTrait},
 	stub::{activity, closure, removed, v},
 	utils::GlobalErrorExt,
@@ -0,0 +1,20 @@
+use std::fmt::Debug;
+
+use global_error::GlobalResult;
+use serde::de::DeserializeOwned;
+
+use crate::workflow::Workflow;
+
+/// A read-only question that can be asked of a running workflow. Queries are answered from the state of the
+/// workflow's current top level loop (see `WorkflowCtx::loope`) and never append events to its history.
+pub trait Query {
+	/// The workflow this query can be asked of.
+	type Workflow: Workflow;
+	/// The state of the loop this query reads.
+	type State: DeserializeOwned;
+	type Output: Debug + Send;
+
+	const NAME: &'static str;
+
+	fn handle(state: &Self::State) -> GlobalResult<Self::Output>;
+}
@@ -7,6 +7,7 @@ use global_error::GlobalError;
 use crate::{
 	ctx::WorkflowCtx,
 	error::{WorkflowError, WorkflowResult},
+	schedule::Schedule,
 	workflow::Workflow,
 };
 
@@ -15,6 +16,7 @@ pub type RegistryHandle = Arc<Registry>;
 /// Contains a lookup map for workflow run handlers by workflow name.
 pub struct Registry {
 	pub(crate) workflows: HashMap<String, Arc<RegistryWorkflow>>,
+	pub(crate) schedules: HashMap<String, Arc<Schedule>>,
 }
 
 impl Default for Registry {
@@ -27,6 +29,7 @@ impl Registry {
 	pub fn new() -> Self {
 		Registry {
 			workflows: HashMap::new(),
+			schedules: HashMap::new(),
 		}
 	}
 
@@ -44,7 +47,16 @@ impl Registry {
 			}
 		}
 
+		for schedule_name in registry.schedules.keys() {
+			if self.schedules.contains_key(schedule_name.as_str()) {
+				return Err(WorkflowError::DuplicateRegisteredSchedule(
+					schedule_name.clone(),
+				));
+			}
+		}
+
 		self.workflows.extend(registry.workflows);
+		self.schedules.extend(registry.schedules);
 
 		Ok(self)
 	}
@@ -100,6 +112,22 @@ impl Registry {
 		Ok(())
 	}
 
+	/// Registers a schedule which will periodically dispatch a workflow. Schedules are ticked by every worker
+	/// using this registry.
+	pub fn register_schedule(&mut self, schedule: Schedule) -> WorkflowResult<()> {
+		// Check for duplicates
+		if self.schedules.contains_key(schedule.name()) {
+			return Err(WorkflowError::DuplicateRegisteredSchedule(
+				schedule.name().to_string(),
+			));
+		}
+
+		self.schedules
+			.insert(schedule.name().to_string(), Arc::new(schedule));
+
+		Ok(())
+	}
+
 	pub fn get_workflow(&self, name: &str) -> WorkflowResult<&Arc<RegistryWorkflow>> {
 		self.workflows
 			.get(name)
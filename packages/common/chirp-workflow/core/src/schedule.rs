@@ -0,0 +1,377 @@
+use std::{str::FromStr, time::Duration};
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+	db::{DatabaseHandle, ScheduleDispatch},
+	error::{WorkflowError, WorkflowResult},
+	workflow::{Workflow, WorkflowInput},
+};
+
+/// Most runs of a single schedule dispatched in one tick when catching up on missed runs. Any remaining runs
+/// are dispatched on the next tick.
+const MAX_CATCH_UP_RUNS: usize = 100;
+/// How far back cron schedules with `MissedRunPolicy::Skip` first look for the latest missed run, so that
+/// runs missed during a long downtime are not all iterated over.
+const SKIP_LOOKBACK_MS: i64 = 60 * 60 * 1000;
+/// Most cron runs iterated over in one tick when looking for the latest missed run.
+const MAX_SKIP_RUNS: usize = 10_000;
+
+/// How often a schedule runs.
+#[derive(Debug, Clone)]
+pub enum ScheduleInterval {
+	/// A cron expression evaluated in UTC. Uses the syntax of the `cron` crate, which has a leading seconds
+	/// field (`sec min hour day_of_month month day_of_week [year]`).
+	Cron(cron::Schedule),
+	/// A fixed amount of time between runs.
+	Fixed(Duration),
+}
+
+/// What to do with runs that were missed while no worker was running (or during long ticks).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MissedRunPolicy {
+	/// Only dispatch the most recent missed run.
+	Skip,
+	/// Dispatch every missed run in order.
+	CatchUp,
+}
+
+/// Periodically dispatches a workflow. Schedules are registered with `Registry::register_schedule` and are
+/// ticked by every worker, but each run is claimed in the database as its workflow is dispatched so it is
+/// only dispatched once.
+///
+/// The first run happens one interval after the schedule is first seen by a worker.
+#[derive(Debug)]
+pub struct Schedule {
+	pub(crate) name: String,
+	pub(crate) workflow_name: &'static str,
+	pub(crate) input: Box<serde_json::value::RawValue>,
+	pub(crate) interval: ScheduleInterval,
+	pub(crate) missed_run_policy: MissedRunPolicy,
+}
+
+impl Schedule {
+	/// Creates a schedule that dispatches a workflow with the given input on a cron expression.
+	pub fn cron<I>(name: impl ToString, expr: &str, input: I) -> WorkflowResult<Self>
+	where
+		I: WorkflowInput,
+		<I as WorkflowInput>::Workflow: Workflow<Input = I>,
+	{
+		let schedule = cron::Schedule::from_str(expr)
+			.map_err(|err| WorkflowError::InvalidSchedule(err.to_string()))?;
+
+		Self::new(name, ScheduleInterval::Cron(schedule), input)
+	}
+
+	/// Creates a schedule that dispatches a workflow with the given input at a fixed interval.
+	pub fn interval<I>(name: impl ToString, interval: Duration, input: I) -> WorkflowResult<Self>
+	where
+		I: WorkflowInput,
+		<I as WorkflowInput>::Workflow: Workflow<Input = I>,
+	{
+		if interval.as_millis() == 0 {
+			return Err(WorkflowError::InvalidSchedule(
+				"interval must be at least 1ms".to_string(),
+			));
+		}
+
+		Self::new(name, ScheduleInterval::Fixed(interval), input)
+	}
+
+	fn new<I>(name: impl ToString, interval: ScheduleInterval, input: I) -> WorkflowResult<Self>
+	where
+		I: WorkflowInput,
+		<I as WorkflowInput>::Workflow: Workflow<Input = I>,
+	{
+		let input = serde_json::value::to_raw_value(&input)
+			.map_err(WorkflowError::SerializeWorkflowInput)?;
+
+		Ok(Schedule {
+			name: name.to_string(),
+			workflow_name: I::Workflow::NAME,
+			input,
+			interval,
+			missed_run_policy: MissedRunPolicy::Skip,
+		})
+	}
+
+	/// Sets what to do with missed runs. Defaults to `MissedRunPolicy::Skip`.
+	pub fn missed_run_policy(mut self, missed_run_policy: MissedRunPolicy) -> Self {
+		self.missed_run_policy = missed_run_policy;
+		self
+	}
+
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	/// Returns the timestamp of the first run after the given timestamp.
+	fn next_run_ts(&self, ts: i64) -> Option<i64> {
+		match &self.interval {
+			ScheduleInterval::Cron(schedule) => {
+				let dt = DateTime::<Utc>::from_timestamp_millis(ts)?;
+
+				schedule.after(&dt).next().map(|dt| dt.timestamp_millis())
+			}
+			ScheduleInterval::Fixed(interval) => Some(ts + interval.as_millis() as i64),
+		}
+	}
+
+	/// Returns the timestamps of all runs after `last_run_ts` that are due at `now`, in order.
+	fn due_runs(&self, last_run_ts: i64, now: i64) -> Vec<i64> {
+		match (self.missed_run_policy, &self.interval) {
+			// Skip straight to the latest run instead of iterating over every missed run
+			(MissedRunPolicy::Skip, ScheduleInterval::Fixed(interval)) => {
+				let interval = interval.as_millis() as i64;
+				let missed = (now - last_run_ts) / interval;
+
+				if missed > 0 {
+					vec![last_run_ts + missed * interval]
+				} else {
+					Vec::new()
+				}
+			}
+			(MissedRunPolicy::Skip, ScheduleInterval::Cron(_)) => {
+				// Look for the latest run within the lookback first. Only schedules that run less often than
+				// the lookback iterate over the runs before it.
+				let lookback_ts = (now - SKIP_LOOKBACK_MS).max(last_run_ts);
+
+				self.latest_run(lookback_ts, now)
+					.or_else(|| {
+						if lookback_ts > last_run_ts {
+							self.latest_run(last_run_ts, lookback_ts)
+						} else {
+							None
+						}
+					})
+					.into_iter()
+					.collect()
+			}
+			(MissedRunPolicy::CatchUp, _) => {
+				let mut runs = Vec::new();
+				let mut ts = last_run_ts;
+
+				while let Some(next_ts) = self.next_run_ts(ts) {
+					if next_ts > now || runs.len() >= MAX_CATCH_UP_RUNS {
+						break;
+					}
+
+					ts = next_ts;
+					runs.push(ts);
+				}
+
+				runs
+			}
+		}
+	}
+
+	/// Returns the latest run after `after_ts` that is due at `now`. Gives up after `MAX_SKIP_RUNS` runs and
+	/// returns the latest run seen, later runs are found on the next tick.
+	fn latest_run(&self, after_ts: i64, now: i64) -> Option<i64> {
+		let mut latest = None;
+		let mut ts = after_ts;
+
+		for _ in 0..MAX_SKIP_RUNS {
+			let Some(next_ts) = self.next_run_ts(ts) else {
+				break;
+			};
+			if next_ts > now {
+				break;
+			}
+
+			ts = next_ts;
+			latest = Some(ts);
+		}
+
+		latest
+	}
+}
+
+/// Dispatches all due runs of the given schedule. Each run's workflow is dispatched in the same transaction
+/// that claims the run so that concurrent workers never dispatch the same run twice.
+#[tracing::instrument(skip_all, fields(schedule_name=%schedule.name))]
+pub(crate) async fn tick(db: &DatabaseHandle, schedule: &Schedule) -> WorkflowResult<()> {
+	let now = rivet_util::timestamp::now();
+
+	let Some(mut last_run_ts) = db.get_schedule_last_run_ts(&schedule.name).await? else {
+		// First time seeing this schedule, runs are counted from now
+		db.claim_schedule_run(&schedule.name, None, now, None)
+			.await?;
+
+		return Ok(());
+	};
+
+	let tags = serde_json::json!({ "schedule": schedule.name });
+
+	for run_ts in schedule.due_runs(last_run_ts, now) {
+		let workflow_id = Uuid::new_v4();
+
+		tracing::debug!(workflow_name=%schedule.workflow_name, %workflow_id, ?run_ts, "dispatching scheduled workflow");
+
+		let claimed = db
+			.claim_schedule_run(
+				&schedule.name,
+				Some(last_run_ts),
+				run_ts,
+				Some(ScheduleDispatch {
+					ray_id: Uuid::new_v4(),
+					workflow_id,
+					workflow_name: schedule.workflow_name,
+					tags: &tags,
+					input: &schedule.input,
+				}),
+			)
+			.await?;
+
+		if !claimed {
+			tracing::debug!(?run_ts, "schedule run already claimed");
+			break;
+		}
+
+		last_run_ts = run_ts;
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn ts(rfc3339: &str) -> i64 {
+		DateTime::parse_from_rfc3339(rfc3339)
+			.unwrap()
+			.timestamp_millis()
+	}
+
+	fn schedule(interval: ScheduleInterval, missed_run_policy: MissedRunPolicy) -> Schedule {
+		Schedule {
+			name: "test".to_string(),
+			workflow_name: "test",
+			input: serde_json::value::to_raw_value(&()).unwrap(),
+			interval,
+			missed_run_policy,
+		}
+	}
+
+	fn cron(expr: &str, missed_run_policy: MissedRunPolicy) -> Schedule {
+		schedule(
+			ScheduleInterval::Cron(cron::Schedule::from_str(expr).unwrap()),
+			missed_run_policy,
+		)
+	}
+
+	fn fixed(interval: Duration, missed_run_policy: MissedRunPolicy) -> Schedule {
+		schedule(ScheduleInterval::Fixed(interval), missed_run_policy)
+	}
+
+	#[test]
+	fn next_run_ts_fixed() {
+		let schedule = fixed(Duration::from_secs(60), MissedRunPolicy::Skip);
+
+		assert_eq!(Some(61_000), schedule.next_run_ts(1_000));
+	}
+
+	#[test]
+	fn next_run_ts_cron() {
+		let schedule = cron("0 0 * * * *", MissedRunPolicy::Skip);
+
+		assert_eq!(
+			Some(ts("2024-01-01T01:00:00Z")),
+			schedule.next_run_ts(ts("2024-01-01T00:30:00Z"))
+		);
+		// Runs are strictly after the given timestamp
+		assert_eq!(
+			Some(ts("2024-01-01T02:00:00Z")),
+			schedule.next_run_ts(ts("2024-01-01T01:00:00Z"))
+		);
+	}
+
+	#[test]
+	fn due_runs_none_before_next_run() {
+		let last_run_ts = ts("2024-01-01T00:00:00Z");
+		let now = ts("2024-01-01T00:59:59Z");
+
+		for policy in [MissedRunPolicy::Skip, MissedRunPolicy::CatchUp] {
+			assert!(cron("0 0 * * * *", policy)
+				.due_runs(last_run_ts, now)
+				.is_empty());
+			assert!(fixed(Duration::from_secs(60 * 60), policy)
+				.due_runs(last_run_ts, now)
+				.is_empty());
+		}
+	}
+
+	#[test]
+	fn due_runs_skip_returns_latest_run() {
+		let last_run_ts = ts("2024-01-01T00:00:00Z");
+		let now = ts("2024-01-01T03:30:00Z");
+
+		assert_eq!(
+			vec![ts("2024-01-01T03:00:00Z")],
+			cron("0 0 * * * *", MissedRunPolicy::Skip).due_runs(last_run_ts, now)
+		);
+		assert_eq!(
+			vec![ts("2024-01-01T03:00:00Z")],
+			fixed(Duration::from_secs(60 * 60), MissedRunPolicy::Skip).due_runs(last_run_ts, now)
+		);
+	}
+
+	#[test]
+	fn due_runs_skip_cron_after_long_downtime() {
+		// Runs every second, a month of downtime would be millions of runs to iterate over
+		let schedule = cron("* * * * * *", MissedRunPolicy::Skip);
+		let last_run_ts = ts("2024-01-01T00:00:00Z");
+		let now = ts("2024-02-01T00:00:00.500Z");
+
+		assert_eq!(
+			vec![ts("2024-02-01T00:00:00Z")],
+			schedule.due_runs(last_run_ts, now)
+		);
+	}
+
+	#[test]
+	fn due_runs_skip_cron_before_lookback() {
+		// Runs daily, the latest run is further back than the lookback
+		let schedule = cron("0 0 0 * * *", MissedRunPolicy::Skip);
+		let last_run_ts = ts("2024-01-01T00:00:00Z");
+		let now = ts("2024-01-04T12:00:00Z");
+
+		assert_eq!(
+			vec![ts("2024-01-04T00:00:00Z")],
+			schedule.due_runs(last_run_ts, now)
+		);
+	}
+
+	#[test]
+	fn due_runs_catch_up_returns_every_run() {
+		let last_run_ts = ts("2024-01-01T00:00:00Z");
+		let now = ts("2024-01-01T03:30:00Z");
+		let expected = vec![
+			ts("2024-01-01T01:00:00Z"),
+			ts("2024-01-01T02:00:00Z"),
+			ts("2024-01-01T03:00:00Z"),
+		];
+
+		assert_eq!(
+			expected,
+			cron("0 0 * * * *", MissedRunPolicy::CatchUp).due_runs(last_run_ts, now)
+		);
+		assert_eq!(
+			expected,
+			fixed(Duration::from_secs(60 * 60), MissedRunPolicy::CatchUp)
+				.due_runs(last_run_ts, now)
+		);
+	}
+
+	#[test]
+	fn due_runs_catch_up_is_capped() {
+		let schedule = fixed(Duration::from_secs(1), MissedRunPolicy::CatchUp);
+		let runs = schedule.due_runs(0, 1_000_000);
+
+		assert_eq!(MAX_CATCH_UP_RUNS, runs.len());
+		assert_eq!(Some(&1_000), runs.first());
+		assert_eq!(Some(&(MAX_CATCH_UP_RUNS as i64 * 1_000)), runs.last());
+	}
+}
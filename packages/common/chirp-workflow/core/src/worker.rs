@@ -20,13 +20,15 @@ use uuid::Uuid;
 
 use crate::{
 	ctx::WorkflowCtx, db::DatabaseHandle, error::WorkflowError, metrics, registry::RegistryHandle,
-	utils,
+	schedule, utils,
 };
 
 /// How often to run gc and update ping.
 const PING_INTERVAL: Duration = Duration::from_secs(20);
 /// How often to publish metrics.
 const METRICS_INTERVAL: Duration = Duration::from_secs(20);
+/// How often to check schedules for due runs.
+const SCHEDULE_INTERVAL: Duration = Duration::from_secs(5);
 /// Time to allow running workflows to shutdown after receiving a SIGINT or SIGTERM.
 const SHUTDOWN_DURATION: Duration = Duration::from_secs(30);
 
@@ -79,6 +81,7 @@ impl Worker {
 
 		let mut gc_handle = self.gc();
 		let mut metrics_handle = self.publish_metrics();
+		let mut schedule_handle = self.tick_schedules();
 
 		loop {
 			tokio::select! {
@@ -91,6 +94,10 @@ impl Worker {
 					tracing::error!(?res, "metrics task unexpectedly stopped");
 					break;
 				},
+				res = &mut schedule_handle => {
+					tracing::error!(?res, "schedule task unexpectedly stopped");
+					break;
+				},
 				res = wake_sub.next() => {
 					if res.is_none() {
 						return Err(WorkflowError::SubscriptionUnsubscribed.into());
@@ -282,6 +289,10 @@ impl Worker {
 					if let Err(err) = db.clear_expired_leases(worker_instance_id).await {
 						tracing::error!(?err, "unhandled gc error");
 					}
+
+					if let Err(err) = db.clear_expired_idempotency_keys().await {
+						tracing::error!(?err, "unhandled idempotency key gc error");
+					}
 				}
 			}
 			.instrument(tracing::info_span!("worker_gc_task")),
@@ -308,6 +319,29 @@ impl Worker {
 			.instrument(tracing::info_span!("worker_metrics_task")),
 		)
 	}
+
+	fn tick_schedules(&self) -> JoinHandle<()> {
+		let db = self.db.clone();
+		let registry = self.registry.clone();
+
+		tokio::task::spawn(
+			async move {
+				let mut schedule_interval = tokio::time::interval(SCHEDULE_INTERVAL);
+				schedule_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+				loop {
+					schedule_interval.tick().await;
+
+					for schedule in registry.schedules.values() {
+						if let Err(err) = schedule::tick(&db, schedule).await {
+							tracing::error!(?err, schedule_name=%schedule.name(), "unhandled schedule error");
+						}
+					}
+				}
+			}
+			.instrument(tracing::info_span!("worker_schedule_task")),
+		)
+	}
 }
 
 struct WorkflowHandle {
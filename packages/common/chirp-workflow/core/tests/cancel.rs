@@ -0,0 +1,154 @@
+use std::{sync::atomic::Ordering, time::Duration};
+
+use chirp_workflow::prelude::*;
+
+mod common;
+use common::*;
+
+/// Starts a worker for the test workflows and returns a database handle to inspect them with.
+async fn start_worker(ctx: &TestCtx) -> db::DatabaseHandle {
+	let mut reg = Registry::new();
+	reg.register_workflow::<def::Failing>().unwrap();
+	reg.register_workflow::<def::Slow>().unwrap();
+	let reg = reg.handle();
+
+	let db = db::DatabaseFdbSqliteNats::from_pools(ctx.pools().clone()).unwrap();
+
+	let worker = Worker::new(reg, db.clone());
+	let config = ctx.config().clone();
+	let pools = ctx.pools().clone();
+	tokio::spawn(async move { worker.start(config, pools).await.unwrap() });
+
+	db
+}
+
+/// Polls until the given workflow is dead.
+async fn wait_for_dead(db: &db::DatabaseHandle, workflow_id: Uuid) -> db::WorkflowData {
+	tokio::time::timeout(Duration::from_secs(30), async {
+		loop {
+			let workflow = db.get_workflow(workflow_id).await.unwrap().unwrap();
+			if workflow.is_dead() {
+				break workflow;
+			}
+
+			tokio::time::sleep(Duration::from_millis(250)).await;
+		}
+	})
+	.await
+	.expect("workflow never finished")
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn cancel_dead_workflow() {
+	setup_tracing();
+
+	let ctx = TestCtx::from_env::<db::DatabaseFdbSqliteNats>("cancel_dead_workflow", true).await;
+	let db = start_worker(&ctx).await;
+
+	let workflow_id = ctx.workflow(def::FailingInput {}).dispatch().await.unwrap();
+	wait_for_dead(&db, workflow_id).await;
+
+	let err = ctx
+		.workflow::<def::FailingInput>(workflow_id)
+		.cancel()
+		.await
+		.unwrap_err();
+	assert!(
+		format!("{err:?}").contains("WorkflowDead"),
+		"expected workflow to be dead, got {err:?}"
+	);
+
+	// The workflow was not revived
+	tokio::time::sleep(Duration::from_secs(1)).await;
+	let workflow = db.get_workflow(workflow_id).await.unwrap().unwrap();
+	assert!(workflow.is_dead());
+	assert!(!workflow.is_cancelled());
+	assert!(!workflow.has_wake_condition);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn cancel_leased_workflow() {
+	setup_tracing();
+
+	let ctx = TestCtx::from_env::<db::DatabaseFdbSqliteNats>("cancel_leased_workflow", true).await;
+	let db = start_worker(&ctx).await;
+
+	let workflow_id = ctx.workflow(def::SlowInput {}).dispatch().await.unwrap();
+
+	// Wait until the workflow is running its activity
+	tokio::time::timeout(Duration::from_secs(30), async {
+		while !def::STARTED.load(Ordering::SeqCst) {
+			tokio::time::sleep(Duration::from_millis(50)).await;
+		}
+	})
+	.await
+	.expect("activity never started");
+
+	ctx.workflow::<def::SlowInput>(workflow_id)
+		.cancel()
+		.await
+		.unwrap();
+
+	// The workflow commits waiting on a signal but is woken by the cancel it received while running
+	let workflow = wait_for_dead(&db, workflow_id).await;
+	assert!(workflow.is_cancelled());
+	assert!(!workflow.has_wake_condition);
+}
+
+mod def {
+	use std::{
+		sync::atomic::{AtomicBool, Ordering},
+		time::Duration,
+	};
+
+	use chirp_workflow::prelude::*;
+
+	/// Set once the slow activity started running.
+	pub static STARTED: AtomicBool = AtomicBool::new(false);
+
+	#[derive(Debug, Serialize, Deserialize)]
+	pub struct FailingInput {}
+
+	#[workflow(Failing)]
+	pub async fn failing(ctx: &mut WorkflowCtx, _input: &FailingInput) -> GlobalResult<()> {
+		ctx.activity(FailInput {}).await?;
+
+		Ok(())
+	}
+
+	#[derive(Debug, Serialize, Deserialize, Hash)]
+	struct FailInput {}
+
+	#[activity(Fail)]
+	#[non_retryable = |_: &GlobalError| true]
+	async fn fail(_ctx: &ActivityCtx, _input: &FailInput) -> GlobalResult<()> {
+		bail!("failed");
+	}
+
+	#[derive(Debug, Serialize, Deserialize)]
+	pub struct SlowInput {}
+
+	#[workflow(Slow)]
+	pub async fn slow(ctx: &mut WorkflowCtx, _input: &SlowInput) -> GlobalResult<()> {
+		ctx.activity(SleepInput {}).await?;
+
+		ctx.listen::<Never>().await?;
+
+		Ok(())
+	}
+
+	#[derive(Debug, Serialize, Deserialize, Hash)]
+	struct SleepInput {}
+
+	#[activity(Sleep)]
+	async fn sleep(_ctx: &ActivityCtx, _input: &SleepInput) -> GlobalResult<()> {
+		STARTED.store(true, Ordering::SeqCst);
+		tokio::time::sleep(Duration::from_secs(2)).await;
+
+		Ok(())
+	}
+
+	#[signal("test_cancel_never")]
+	#[derive(Debug)]
+	pub struct Never {}
+}
@@ -0,0 +1,172 @@
+use std::{sync::Arc, time::Duration};
+
+use chirp_workflow::prelude::*;
+
+mod common;
+use common::*;
+
+async fn setup(name: &'static str) -> (TestCtx, Arc<db::DatabaseFdbSqliteNats>) {
+	setup_tracing();
+
+	let ctx = TestCtx::from_env::<db::DatabaseFdbSqliteNats>(name, true).await;
+
+	let mut reg = Registry::new();
+	reg.register_workflow::<def::Counter>().unwrap();
+	let reg = reg.handle();
+
+	let db = db::DatabaseFdbSqliteNats::from_pools(ctx.pools().clone()).unwrap();
+
+	let worker = Worker::new(reg, db.clone());
+	let config = ctx.config().clone();
+	let pools = ctx.pools().clone();
+	tokio::spawn(async move { worker.start(config, pools).await.unwrap() });
+
+	(ctx, db)
+}
+
+async fn send_bump(ctx: &TestCtx, workflow_id: Uuid, stop: bool) {
+	ctx.signal(def::Bump { stop })
+		.to_workflow_id(workflow_id)
+		.send()
+		.await
+		.unwrap();
+}
+
+/// Polls the count query until it matches the expected value.
+async fn wait_for_count(ctx: &TestCtx, workflow_id: Uuid, expected: Option<usize>) {
+	tokio::time::timeout(Duration::from_secs(30), async {
+		loop {
+			if ctx.query::<def::Count>(workflow_id).await.unwrap() == expected {
+				break;
+			}
+
+			tokio::time::sleep(Duration::from_millis(250)).await;
+		}
+	})
+	.await
+	.expect("query never matched")
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn query_reads_loop_state() {
+	let (ctx, db) = setup("query_reads_loop_state").await;
+
+	let workflow_id = ctx.workflow(def::CounterInput {}).dispatch().await.unwrap();
+
+	wait_for_count(&ctx, workflow_id, Some(0)).await;
+
+	send_bump(&ctx, workflow_id, false).await;
+	send_bump(&ctx, workflow_id, false).await;
+
+	wait_for_count(&ctx, workflow_id, Some(2)).await;
+
+	// Queries don't append events
+	let events_before = db
+		.get_workflow_history(workflow_id, true)
+		.await
+		.unwrap()
+		.unwrap()
+		.events
+		.len();
+	for _ in 0..3 {
+		assert_eq!(Some(2), ctx.query::<def::Count>(workflow_id).await.unwrap());
+	}
+	let events_after = db
+		.get_workflow_history(workflow_id, true)
+		.await
+		.unwrap()
+		.unwrap()
+		.events
+		.len();
+	assert_eq!(events_before, events_after);
+
+	// No loop is running once the workflow completes
+	send_bump(&ctx, workflow_id, true).await;
+	wait_for_count(&ctx, workflow_id, None).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn query_wrong_workflow() {
+	let (ctx, _db) = setup("query_wrong_workflow").await;
+
+	let workflow_id = ctx.workflow(def::CounterInput {}).dispatch().await.unwrap();
+
+	let err = ctx.query::<def::OtherCount>(workflow_id).await.unwrap_err();
+	assert!(
+		matches!(
+			err.as_workflow_error(),
+			Some(WorkflowError::QueryWorkflowMismatch(..))
+		),
+		"expected workflow mismatch, got {err:?}"
+	);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn query_workflow_not_found() {
+	let (ctx, _db) = setup("query_workflow_not_found").await;
+
+	let err = ctx.query::<def::Count>(Uuid::new_v4()).await.unwrap_err();
+	assert!(
+		matches!(
+			err.as_workflow_error(),
+			Some(WorkflowError::WorkflowNotFound)
+		),
+		"expected workflow not found, got {err:?}"
+	);
+}
+
+mod def {
+	use chirp_workflow::prelude::*;
+	use futures_util::FutureExt;
+
+	#[derive(Debug, Serialize, Deserialize)]
+	pub struct CounterInput {}
+
+	#[derive(Debug, Serialize, Deserialize)]
+	pub struct CounterState {
+		count: usize,
+	}
+
+	#[workflow(Counter)]
+	pub async fn counter(ctx: &mut WorkflowCtx, _input: &CounterInput) -> GlobalResult<usize> {
+		ctx.loope(CounterState { count: 0 }, |ctx, state| {
+			async move {
+				let bump = ctx.listen::<Bump>().await?;
+
+				if bump.stop {
+					return Ok(Loop::Break(state.count));
+				}
+
+				state.count += 1;
+
+				Ok(Loop::Continue)
+			}
+			.boxed()
+		})
+		.await
+	}
+
+	#[query(Count, Counter)]
+	pub fn count(state: &CounterState) -> GlobalResult<usize> {
+		Ok(state.count)
+	}
+
+	#[derive(Debug, Serialize, Deserialize)]
+	pub struct OtherInput {}
+
+	#[workflow(Other)]
+	pub async fn other(_ctx: &mut WorkflowCtx, _input: &OtherInput) -> GlobalResult<()> {
+		Ok(())
+	}
+
+	#[query(OtherCount, Other)]
+	pub fn other_count(state: &CounterState) -> GlobalResult<usize> {
+		Ok(state.count)
+	}
+
+	#[signal("test_query_bump")]
+	#[derive(Debug)]
+	pub struct Bump {
+		pub stop: bool,
+	}
+}
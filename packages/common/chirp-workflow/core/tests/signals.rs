@@ -0,0 +1,121 @@
+use std::time::Duration;
+
+use chirp_workflow::prelude::*;
+
+mod common;
+use common::*;
+
+/// Dispatches a workflow without running it so signals can be published to it.
+async fn dispatch_workflow(ctx: &TestCtx) -> Uuid {
+	ctx.workflow(def::TestInput {}).dispatch().await.unwrap()
+}
+
+async fn send_ping(ctx: &TestCtx, workflow_id: Uuid, key: &str, window: Duration) -> Uuid {
+	ctx.signal(def::Ping {})
+		.to_workflow_id(workflow_id)
+		.idempotency_key(key)
+		.idempotency_window(window)
+		.send()
+		.await
+		.unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn signal_idempotency_key_dedup() {
+	setup_tracing();
+
+	let ctx =
+		TestCtx::from_env::<db::DatabaseFdbSqliteNats>("signal_idempotency_key_dedup", true).await;
+	let workflow_id = dispatch_workflow(&ctx).await;
+	let key = Uuid::new_v4().to_string();
+
+	let signal_id = send_ping(&ctx, workflow_id, &key, Duration::from_secs(60)).await;
+
+	// Duplicates return the original signal
+	assert_eq!(
+		signal_id,
+		send_ping(&ctx, workflow_id, &key, Duration::from_secs(60)).await
+	);
+
+	// Other keys are not deduplicated
+	assert_ne!(
+		signal_id,
+		send_ping(
+			&ctx,
+			workflow_id,
+			&Uuid::new_v4().to_string(),
+			Duration::from_secs(60)
+		)
+		.await
+	);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn signal_idempotency_key_scoped_to_workflow() {
+	setup_tracing();
+
+	let ctx = TestCtx::from_env::<db::DatabaseFdbSqliteNats>(
+		"signal_idempotency_key_scoped_to_workflow",
+		true,
+	)
+	.await;
+	let workflow_id_a = dispatch_workflow(&ctx).await;
+	let workflow_id_b = dispatch_workflow(&ctx).await;
+	let key = Uuid::new_v4().to_string();
+
+	let signal_id_a = send_ping(&ctx, workflow_id_a, &key, Duration::from_secs(60)).await;
+	let signal_id_b = send_ping(&ctx, workflow_id_b, &key, Duration::from_secs(60)).await;
+
+	// The same key sent to a different workflow is a different signal
+	assert_ne!(signal_id_a, signal_id_b);
+	assert_eq!(
+		signal_id_b,
+		send_ping(&ctx, workflow_id_b, &key, Duration::from_secs(60)).await
+	);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn signal_idempotency_key_expires() {
+	setup_tracing();
+
+	let ctx =
+		TestCtx::from_env::<db::DatabaseFdbSqliteNats>("signal_idempotency_key_expires", true)
+			.await;
+	let db = db::DatabaseFdbSqliteNats::from_pools(ctx.pools().clone()).unwrap();
+	let workflow_id = dispatch_workflow(&ctx).await;
+	let expired_key = Uuid::new_v4().to_string();
+	let active_key = Uuid::new_v4().to_string();
+
+	let expired_signal_id =
+		send_ping(&ctx, workflow_id, &expired_key, Duration::from_millis(1)).await;
+	let active_signal_id = send_ping(&ctx, workflow_id, &active_key, Duration::from_secs(60)).await;
+
+	tokio::time::sleep(Duration::from_millis(50)).await;
+	db.clear_expired_idempotency_keys().await.unwrap();
+
+	// Only the expired key was cleared
+	assert_ne!(
+		expired_signal_id,
+		send_ping(&ctx, workflow_id, &expired_key, Duration::from_secs(60)).await
+	);
+	assert_eq!(
+		active_signal_id,
+		send_ping(&ctx, workflow_id, &active_key, Duration::from_secs(60)).await
+	);
+}
+
+mod def {
+	use chirp_workflow::prelude::*;
+
+	#[derive(Debug, Serialize, Deserialize)]
+	pub struct TestInput {}
+
+	#[workflow(Test)]
+	pub async fn test(_ctx: &mut WorkflowCtx, _input: &TestInput) -> GlobalResult<()> {
+		Ok(())
+	}
+
+	#[signal("test_ping")]
+	#[derive(Debug)]
+	pub struct Ping {}
+}
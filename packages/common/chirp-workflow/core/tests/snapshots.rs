@@ -0,0 +1,156 @@
+use std::{
+	sync::{atomic::Ordering, Arc},
+	time::Duration,
+};
+
+use chirp_workflow::{
+	db::debug::{DatabaseDebug, EventData, HistoryData},
+	prelude::*,
+};
+
+mod common;
+use common::*;
+
+/// Polls until the history of the workflow matches the predicate.
+async fn wait_for_history(
+	db: &Arc<db::DatabaseFdbSqliteNats>,
+	workflow_id: Uuid,
+	f: impl Fn(&HistoryData) -> bool,
+) -> HistoryData {
+	tokio::time::timeout(Duration::from_secs(30), async {
+		loop {
+			if let Some(history) = db.get_workflow_history(workflow_id, true).await.unwrap() {
+				if f(&history) {
+					break history;
+				}
+			}
+
+			tokio::time::sleep(Duration::from_millis(250)).await;
+		}
+	})
+	.await
+	.expect("history never matched")
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn snapshot_truncates_history() {
+	setup_tracing();
+
+	let ctx =
+		TestCtx::from_env::<db::DatabaseFdbSqliteNats>("snapshot_truncates_history", true).await;
+
+	let mut reg = Registry::new();
+	reg.register_workflow::<def::Snapshot>().unwrap();
+	let reg = reg.handle();
+
+	let db = db::DatabaseFdbSqliteNats::from_pools(ctx.pools().clone()).unwrap();
+
+	let worker = Worker::new(reg, db.clone());
+	let config = ctx.config().clone();
+	let pools = ctx.pools().clone();
+	tokio::spawn(async move { worker.start(config, pools).await.unwrap() });
+
+	let workflow_id = ctx
+		.workflow(def::SnapshotInput {})
+		.dispatch()
+		.await
+		.unwrap();
+
+	// Wait for the snapshot to complete
+	let history = wait_for_history(&db, workflow_id, |history| {
+		history.events.iter().any(
+			|event| matches!(&event.data, EventData::Loop(loop_event) if loop_event.output.is_some()),
+		)
+	})
+	.await;
+
+	// The events recorded within the snapshot were deleted
+	assert!(
+		history
+			.events
+			.iter()
+			.all(|event| !matches!(event.data, EventData::Activity(_))),
+		"unexpected events left in history: {:?}",
+		history.events
+	);
+
+	let sizes = db
+		.get_workflow_history_sizes(vec![workflow_id])
+		.await
+		.unwrap();
+	assert_eq!(1, sizes.len());
+	assert_eq!(history.events.len(), sizes[0].events);
+	assert_eq!(0, sizes[0].forgotten_events);
+
+	// Replay the workflow after the snapshot
+	ctx.signal(def::Wake {})
+		.to_workflow_id(workflow_id)
+		.send()
+		.await
+		.unwrap();
+
+	let output = tokio::time::timeout(Duration::from_secs(30), async {
+		loop {
+			let workflow = db.get_workflow(workflow_id).await.unwrap().unwrap();
+			if let Some(output) = workflow.parse_output::<def::Snapshot>().unwrap() {
+				break output;
+			}
+
+			tokio::time::sleep(Duration::from_millis(250)).await;
+		}
+	})
+	.await
+	.expect("workflow never completed");
+
+	// The replay used the checkpointed output instead of running the activities again
+	assert_eq!(3, output);
+	assert_eq!(3, def::RUNS.load(Ordering::SeqCst));
+}
+
+mod def {
+	use std::sync::atomic::{AtomicUsize, Ordering};
+
+	use chirp_workflow::prelude::*;
+	use futures_util::FutureExt;
+
+	/// How many times the activity ran.
+	pub static RUNS: AtomicUsize = AtomicUsize::new(0);
+
+	#[derive(Debug, Serialize, Deserialize)]
+	pub struct SnapshotInput {}
+
+	#[workflow(Snapshot)]
+	pub async fn snapshot(ctx: &mut WorkflowCtx, _input: &SnapshotInput) -> GlobalResult<usize> {
+		let count = ctx
+			.snapshot(|ctx| {
+				async move {
+					let mut count = 0;
+					for _ in 0..3 {
+						count += ctx.activity(CountInput {}).await?;
+					}
+
+					Ok(count)
+				}
+				.boxed()
+			})
+			.await?;
+
+		ctx.listen::<Wake>().await?;
+
+		Ok(count)
+	}
+
+	#[derive(Debug, Serialize, Deserialize, Hash)]
+	struct CountInput {}
+
+	#[activity(Count)]
+	async fn count(_ctx: &ActivityCtx, _input: &CountInput) -> GlobalResult<usize> {
+		RUNS.fetch_add(1, Ordering::SeqCst);
+
+		Ok(1)
+	}
+
+	#[signal("test_snapshot_wake")]
+	#[derive(Debug)]
+	pub struct Wake {}
+}
@@ -0,0 +1,254 @@
+use std::time::Duration;
+
+use chirp_workflow::prelude::*;
+use serde_json::json;
+
+mod common;
+use common::*;
+
+/// Starts a worker for the test workflows and returns a database handle to inspect them with.
+async fn start_worker(ctx: &TestCtx) -> db::DatabaseHandle {
+	let mut reg = Registry::new();
+	reg.register_workflow::<def::Parent>().unwrap();
+	reg.register_workflow::<def::JoinParent>().unwrap();
+	reg.register_workflow::<def::Child>().unwrap();
+	let reg = reg.handle();
+
+	let db = db::DatabaseFdbSqliteNats::from_pools(ctx.pools().clone()).unwrap();
+
+	let worker = Worker::new(reg, db.clone());
+	let config = ctx.config().clone();
+	let pools = ctx.pools().clone();
+	tokio::spawn(async move { worker.start(config, pools).await.unwrap() });
+
+	db
+}
+
+/// Polls until the sub workflow dispatched by the parent with the given test id exists.
+async fn wait_for_child(db: &db::DatabaseHandle, test_id: Uuid) -> Uuid {
+	tokio::time::timeout(Duration::from_secs(30), async {
+		loop {
+			if let Some(child_id) = db
+				.find_workflow(
+					<def::Child as WorkflowTrait>::NAME,
+					&json!({ "test_id": test_id }),
+				)
+				.await
+				.unwrap()
+			{
+				break child_id;
+			}
+
+			tokio::time::sleep(Duration::from_millis(250)).await;
+		}
+	})
+	.await
+	.expect("child was never dispatched")
+}
+
+/// Polls until the given workflow is dead.
+async fn wait_for_dead(db: &db::DatabaseHandle, workflow_id: Uuid) -> db::WorkflowData {
+	tokio::time::timeout(Duration::from_secs(30), async {
+		loop {
+			let workflow = db.get_workflow(workflow_id).await.unwrap().unwrap();
+			if workflow.is_dead() {
+				break workflow;
+			}
+
+			tokio::time::sleep(Duration::from_millis(250)).await;
+		}
+	})
+	.await
+	.expect("workflow never finished")
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn output_of_cancelled_sub_workflow() {
+	setup_tracing();
+
+	let ctx =
+		TestCtx::from_env::<db::DatabaseFdbSqliteNats>("output_of_cancelled_sub_workflow", true)
+			.await;
+	let db = start_worker(&ctx).await;
+
+	let test_id = Uuid::new_v4();
+	let parent_id = ctx
+		.workflow(def::ParentInput {
+			test_id,
+			fail_child: false,
+			propagate_failure: false,
+		})
+		.dispatch()
+		.await
+		.unwrap();
+
+	let child_id = wait_for_child(&db, test_id).await;
+	ctx.workflow::<def::ChildInput>(child_id)
+		.cancel()
+		.await
+		.unwrap();
+
+	// The child reaches its terminal state after cancelling
+	let child = wait_for_dead(&db, child_id).await;
+	assert!(child.is_cancelled());
+
+	// The parent is woken and fails instead of waiting on the child forever
+	let parent = wait_for_dead(&db, parent_id).await;
+	assert!(!parent.is_cancelled());
+	let error = parent.error.unwrap_or_default();
+	assert!(error.contains("was cancelled"), "unexpected error: {error}");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn output_of_failed_sub_workflow() {
+	setup_tracing();
+
+	let ctx =
+		TestCtx::from_env::<db::DatabaseFdbSqliteNats>("output_of_failed_sub_workflow", true).await;
+	let db = start_worker(&ctx).await;
+
+	let test_id = Uuid::new_v4();
+	let parent_id = ctx
+		.workflow(def::ParentInput {
+			test_id,
+			fail_child: true,
+			propagate_failure: false,
+		})
+		.dispatch()
+		.await
+		.unwrap();
+
+	let child_id = wait_for_child(&db, test_id).await;
+	let child = wait_for_dead(&db, child_id).await;
+	assert!(!child.is_cancelled());
+
+	// The parent keeps waiting since the child can still be woken and complete
+	tokio::time::sleep(Duration::from_secs(3)).await;
+	let parent = db.get_workflow(parent_id).await.unwrap().unwrap();
+	assert!(!parent.is_dead());
+	assert!(parent.has_wake_condition);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn output_of_failed_sub_workflow_propagated() {
+	setup_tracing();
+
+	let ctx = TestCtx::from_env::<db::DatabaseFdbSqliteNats>(
+		"output_of_failed_sub_workflow_propagated",
+		true,
+	)
+	.await;
+	let db = start_worker(&ctx).await;
+
+	let test_id = Uuid::new_v4();
+	let parent_id = ctx
+		.workflow(def::ParentInput {
+			test_id,
+			fail_child: true,
+			propagate_failure: true,
+		})
+		.dispatch()
+		.await
+		.unwrap();
+
+	// The parent fails with the error of the child
+	let parent = wait_for_dead(&db, parent_id).await;
+	let error = parent.error.unwrap_or_default();
+	assert!(error.contains("child failed"), "unexpected error: {error}");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn join_failing_sub_workflow() {
+	setup_tracing();
+
+	let ctx =
+		TestCtx::from_env::<db::DatabaseFdbSqliteNats>("join_failing_sub_workflow", true).await;
+	start_worker(&ctx).await;
+
+	let res = tokio::time::timeout(
+		Duration::from_secs(30),
+		ctx.workflow(def::JoinParentInput {}).output(),
+	)
+	.await
+	.expect("join never resolved")
+	.unwrap();
+
+	assert_eq!(1, res.len());
+	let SubWorkflowResult::Failed(error) = &res[0] else {
+		panic!("expected child to fail, got {:?}", res[0]);
+	};
+	assert!(error.contains("child failed"), "unexpected error: {error}");
+}
+
+mod def {
+	use chirp_workflow::prelude::*;
+
+	#[derive(Debug, Serialize, Deserialize)]
+	pub struct ParentInput {
+		pub test_id: Uuid,
+		pub fail_child: bool,
+		pub propagate_failure: bool,
+	}
+
+	#[workflow(Parent)]
+	pub async fn parent(ctx: &mut WorkflowCtx, input: &ParentInput) -> GlobalResult<()> {
+		let child_id = ctx
+			.workflow(ChildInput {
+				fail: input.fail_child,
+			})
+			.tag("test_id", input.test_id)
+			.dispatch()
+			.await?;
+
+		let mut child = ctx.workflow::<ChildInput>(child_id);
+		if input.propagate_failure {
+			child = child.propagate_failure();
+		}
+		child.output().await?;
+
+		Ok(())
+	}
+
+	#[derive(Debug, Serialize, Deserialize)]
+	pub struct JoinParentInput {}
+
+	#[workflow(JoinParent)]
+	pub async fn join_parent(
+		ctx: &mut WorkflowCtx,
+		_input: &JoinParentInput,
+	) -> GlobalResult<Vec<SubWorkflowResult<()>>> {
+		let child_id = ctx.workflow(ChildInput { fail: true }).dispatch().await?;
+
+		ctx.join_all_sub_workflows::<Child>(&[child_id]).await
+	}
+
+	#[derive(Debug, Serialize, Deserialize)]
+	pub struct ChildInput {
+		pub fail: bool,
+	}
+
+	#[workflow(Child)]
+	pub async fn child(ctx: &mut WorkflowCtx, input: &ChildInput) -> GlobalResult<()> {
+		if input.fail {
+			ctx.activity(FailInput {}).await?;
+		}
+
+		// Wait until cancelled
+		ctx.listen::<Never>().await?;
+
+		Ok(())
+	}
+
+	#[derive(Debug, Serialize, Deserialize, Hash)]
+	struct FailInput {}
+
+	#[activity(Fail)]
+	#[non_retryable = |_: &GlobalError| true]
+	async fn fail(_ctx: &ActivityCtx, _input: &FailInput) -> GlobalResult<()> {
+		bail!("child failed");
+	}
+
+	#[signal("test_never")]
+	#[derive(Debug)]
+	pub struct Never {}
+}
@@ -10,6 +10,10 @@ use syn::{
 struct Config {
 	max_retries: usize,
 	timeout: u64,
+	backoff_wait: Option<usize>,
+	backoff_max_exponent: Option<usize>,
+	backoff_randomness: Option<usize>,
+	non_retryable: Vec<syn::Expr>,
 }
 
 impl Default for Config {
@@ -17,6 +21,10 @@ impl Default for Config {
 		Config {
 			max_retries: 5,
 			timeout: 30,
+			backoff_wait: None,
+			backoff_max_exponent: None,
+			backoff_randomness: None,
+			non_retryable: Vec::new(),
 		}
 	}
 }
@@ -113,6 +121,49 @@ pub fn activity(attr: TokenStream, item: TokenStream) -> TokenStream {
 	let max_retries = config.max_retries;
 	let timeout = config.timeout;
 
+	// Only override the default backoff if any of its properties were set
+	let backoff = if config.backoff_wait.is_some()
+		|| config.backoff_max_exponent.is_some()
+		|| config.backoff_randomness.is_some()
+	{
+		let wait_ms = config.backoff_wait.map(|x| quote! { wait_ms: #x, });
+		let max_exponent = config
+			.backoff_max_exponent
+			.map(|x| quote! { max_exponent: #x, });
+		let randomness_ms = config
+			.backoff_randomness
+			.map(|x| quote! { randomness_ms: #x, });
+
+		quote! {
+			const BACKOFF: chirp_workflow::activity::ActivityBackoff = chirp_workflow::activity::ActivityBackoff {
+				#wait_ms
+				#max_exponent
+				#randomness_ms
+				..chirp_workflow::activity::ActivityBackoff::DEFAULT
+			};
+		}
+	} else {
+		quote! {}
+	};
+
+	let is_retryable = if config.non_retryable.is_empty() {
+		quote! {}
+	} else {
+		let classifiers = config.non_retryable;
+
+		quote! {
+			fn is_retryable(err: &GlobalError) -> bool {
+				#(
+					if (#classifiers)(err) {
+						return false;
+					}
+				)*
+
+				true
+			}
+		}
+	};
+
 	let expanded = quote! {
 		#vis struct #struct_ident;
 
@@ -128,6 +179,9 @@ pub fn activity(attr: TokenStream, item: TokenStream) -> TokenStream {
 			const NAME: &'static str = #fn_name;
 			const MAX_RETRIES: usize = #max_retries;
 			const TIMEOUT: std::time::Duration = std::time::Duration::from_secs(#timeout);
+			#backoff
+
+			#is_retryable
 
 			async fn run(#ctx_ident: #ctx_ty, #input_ident: &Self::Input) -> GlobalResult<Self::Output> {
 				#fn_body
@@ -140,6 +194,67 @@ pub fn activity(attr: TokenStream, item: TokenStream) -> TokenStream {
 	TokenStream::from(expanded)
 }
 
+#[proc_macro_attribute]
+pub fn query(attr: TokenStream, item: TokenStream) -> TokenStream {
+	let QueryArgs { name, workflow } = parse_macro_input!(attr as QueryArgs);
+	let item_fn = parse_macro_input!(item as ItemFn);
+
+	if let Err(err) = parse_empty_config(&item_fn.attrs) {
+		return err.into_compile_error().into();
+	}
+
+	// Queries are answered without a context, directly from workflow state
+	if item_fn.sig.asyncness.is_some() {
+		return error(item_fn.sig.span(), "query function must not be async");
+	}
+
+	let (state_ident, state_type) = match item_fn.sig.inputs.first() {
+		Some(syn::FnArg::Typed(arg)) if item_fn.sig.inputs.len() == 1 => {
+			let syn::Pat::Ident(ident) = arg.pat.as_ref() else {
+				return error(arg.pat.span(), "unsupported input parameter pattern");
+			};
+			let syn::Type::Reference(syn::TypeReference { elem, .. }) = arg.ty.as_ref() else {
+				return error(arg.ty.span(), "state type must be a reference");
+			};
+
+			(ident.ident.clone(), elem.clone())
+		}
+		_ => {
+			return error(
+				item_fn.sig.span(),
+				"Query function must have exactly one parameter: state: &YourStateType",
+			)
+		}
+	};
+
+	let output_type = match parse_output_type("Query", &item_fn.sig.output) {
+		Ok(x) => x,
+		Err(err) => return err,
+	};
+
+	let fn_name = item_fn.sig.ident.to_string();
+	let fn_body = item_fn.block;
+	let vis = item_fn.vis;
+
+	let expanded = quote! {
+		#vis struct #name;
+
+		impl chirp_workflow::query::Query for #name {
+			type Workflow = #workflow;
+			type State = #state_type;
+			type Output = #output_type;
+
+			const NAME: &'static str = #fn_name;
+
+			fn handle(#state_ident: &Self::State) -> GlobalResult<Self::Output> {
+				#fn_body
+			}
+		}
+	};
+
+	TokenStream::from(expanded)
+}
+
 #[proc_macro_attribute]
 pub fn operation(attr: TokenStream, item: TokenStream) -> TokenStream {
 	let name = parse_macro_input!(attr as OptionalIdent)
@@ -251,7 +366,19 @@ fn parse_trait_fn(
 		return Err(error(arg_types[1].span(), "input type must be a reference"));
 	};
 
-	let output_type = match &item_fn.sig.output {
+	let output_type = parse_output_type(trait_name, &item_fn.sig.output)?;
+
+	Ok(TraitFnOutput {
+		ctx_ident: Ident::new(&arg_names[0], proc_macro2::Span::call_site()),
+		input_ident: Ident::new(&arg_names[1], proc_macro2::Span::call_site()),
+		input_type,
+		output_type,
+	})
+}
+
+/// Parses the inner type of a `GlobalResult` return type.
+fn parse_output_type(trait_name: &str, output: &ReturnType) -> Result<syn::Type, TokenStream> {
+	match output {
 		ReturnType::Type(_, ty) => match ty.as_ref() {
 			Type::Path(path) => {
 				let segment = path.path.segments.last().unwrap();
@@ -259,38 +386,27 @@ fn parse_trait_fn(
 					match &segment.arguments {
 						PathArguments::AngleBracketed(args) => {
 							if let Some(GenericArgument::Type(ty)) = args.args.first() {
-								ty.clone()
+								Ok(ty.clone())
 							} else {
-								return Err(error(args.span(), "unsupported Result type"));
+								Err(error(args.span(), "unsupported Result type"))
 							}
 						}
-						_ => {
-							return Err(error(segment.arguments.span(), "unsupported Result type"))
-						}
+						_ => Err(error(segment.arguments.span(), "unsupported Result type")),
 					}
 				} else {
-					return Err(error(
+					Err(error(
 						path.span(),
 						&format!("{} function must return a GlobalResult type", trait_name),
-					));
+					))
 				}
 			}
-			_ => return Err(error(ty.span(), "unsupported output type")),
+			_ => Err(error(ty.span(), "unsupported output type")),
 		},
-		_ => {
-			return Err(error(
-				item_fn.sig.output.span(),
-				&format!("{} function must have a return type", trait_name),
-			));
-		}
-	};
-
-	Ok(TraitFnOutput {
-		ctx_ident: Ident::new(&arg_names[0], proc_macro2::Span::call_site()),
-		input_ident: Ident::new(&arg_names[1], proc_macro2::Span::call_site()),
-		input_type,
-		output_type,
-	})
+		_ => Err(error(
+			output.span(),
+			&format!("{} function must have a return type", trait_name),
+		)),
+	}
 }
 
 #[proc_macro_attribute]
@@ -477,6 +593,24 @@ fn parse_config(attrs: &[syn::Attribute]) -> syn::Result<Config> {
 		} else if ident == "timeout" {
 			config.timeout = syn::parse::<syn::LitInt>(name_value.value.to_token_stream().into())?
 				.base10_parse()?;
+		} else if ident == "backoff_wait" {
+			config.backoff_wait = Some(
+				syn::parse::<syn::LitInt>(name_value.value.to_token_stream().into())?
+					.base10_parse()?,
+			);
+		} else if ident == "backoff_max_exponent" {
+			config.backoff_max_exponent = Some(
+				syn::parse::<syn::LitInt>(name_value.value.to_token_stream().into())?
+					.base10_parse()?,
+			);
+		} else if ident == "backoff_randomness" {
+			config.backoff_randomness = Some(
+				syn::parse::<syn::LitInt>(name_value.value.to_token_stream().into())?
+					.base10_parse()?,
+			);
+		} else if ident == "non_retryable" {
+			// Can be specified multiple times
+			config.non_retryable.push(name_value.value.clone());
 		} else if ident != "doc" {
 			return Err(syn::Error::new(
 				name_value.span(),
@@ -532,6 +666,22 @@ fn parse_empty_config(attrs: &[syn::Attribute]) -> syn::Result<()> {
 	Ok(())
 }
 
+/// `#[query(Name, WorkflowType)]`
+struct QueryArgs {
+	name: Ident,
+	workflow: syn::Path,
+}
+
+impl Parse for QueryArgs {
+	fn parse(input: ParseStream) -> syn::Result<Self> {
+		let name = input.parse()?;
+		input.parse::<syn::Token![,]>()?;
+		let workflow = input.parse()?;
+
+		Ok(QueryArgs { name, workflow })
+	}
+}
+
 struct OptionalIdent {
 	ident: Option<Ident>,
 }
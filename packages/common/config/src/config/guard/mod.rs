@@ -9,6 +9,23 @@ use std::path::PathBuf;
 pub struct Guard {
 	pub http_port: u16,       // Port for HTTP traffic
 	pub https: Option<Https>, // Optional HTTPS configuration
+	/// Ports to proxy raw TCP (and TCP+TLS) GameGuard traffic on. A listener is bound on every port.
+	pub tcp: Option<PortRange>,
+	/// Ports to proxy UDP GameGuard traffic on. A socket is bound on every port.
+	pub udp: Option<PortRange>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PortRange {
+	pub min: u16,
+	pub max: u16,
+}
+
+impl PortRange {
+	pub fn ports(&self) -> std::ops::RangeInclusive<u16> {
+		self.min..=self.max
+	}
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
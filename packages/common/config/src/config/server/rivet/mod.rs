@@ -720,6 +720,9 @@ impl Default for Cache {
 pub enum CacheDriver {
 	Redis,
 	InMemory,
+	/// In-memory cache in front of Redis. Purges are broadcast over NATS to invalidate the in-memory cache
+	/// of every process.
+	Layered,
 }
 
 /// Configuration for the UI service.
@@ -46,12 +46,29 @@ pub const SQLITE: usize = 44;
 pub const INTERNAL: usize = 45;
 pub const METADATA: usize = 46;
 pub const COMPRESSED_DATA: usize = 47;
+pub const CANCEL_TS: usize = 48;
+pub const SCHEDULE: usize = 49;
+pub const LAST_RUN_TS: usize = 50;
+pub const IDEMPOTENCY_KEY: usize = 51;
+pub const VERSION: usize = 52;
+pub const LAST_REQUEST_TS: usize = 53;
+pub const FS_SNAPSHOT: usize = 54;
+pub const REMAINING_DISK: usize = 55;
+pub const TOTAL_DISK: usize = 56;
+pub const LABELS: usize = 57;
+pub const SPREAD_GROUP: usize = 58;
+pub const PENDING_ALLOCATION: usize = 59;
+pub const PAGE: usize = 60;
+pub const PAGE_VERSION: usize = 61;
+pub const EXPIRE_TS: usize = 62;
+pub const PENDING_CANCEL: usize = 63;
 
 // Directories with fdbrs must use string paths instead of tuples
 pub mod dir {
 	pub const PEGBOARD: &str = "p";
 	pub const ACTOR: &str = "a";
 	pub const KV: &str = "k";
+	pub const KV_EXPIRE: &str = "ke";
 }
 
 pub fn key_from_str(key: &str) -> Option<usize> {
@@ -103,6 +120,22 @@ pub fn key_from_str(key: &str) -> Option<usize> {
 		"sqlite" => Some(SQLITE),
 		"internal" => Some(INTERNAL),
 		"metadata" => Some(METADATA),
+		"cancel_ts" => Some(CANCEL_TS),
+		"schedule" => Some(SCHEDULE),
+		"last_run_ts" => Some(LAST_RUN_TS),
+		"idempotency_key" => Some(IDEMPOTENCY_KEY),
+		"version" => Some(VERSION),
+		"last_request_ts" => Some(LAST_REQUEST_TS),
+		"fs_snapshot" => Some(FS_SNAPSHOT),
+		"remaining_disk" => Some(REMAINING_DISK),
+		"total_disk" => Some(TOTAL_DISK),
+		"labels" => Some(LABELS),
+		"spread_group" => Some(SPREAD_GROUP),
+		"pending_allocation" => Some(PENDING_ALLOCATION),
+		"page" => Some(PAGE),
+		"page_version" => Some(PAGE_VERSION),
+		"expire_ts" => Some(EXPIRE_TS),
+		"pending_cancel" => Some(PENDING_CANCEL),
 		_ => None,
 	}
 }
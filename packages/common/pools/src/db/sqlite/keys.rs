@@ -1,6 +1,8 @@
 use fdb_util::prelude::*;
 use std::{result::Result::Ok, sync::Arc};
 
+use uuid::Uuid;
+
 pub struct DbDataKey {
 	db_name_segment: Arc<Vec<u8>>,
 }
@@ -91,3 +93,250 @@ impl<'de> TupleUnpack<'de> for CompressedDbDataChunkKey {
 		Ok((input, v))
 	}
 }
+
+/// Metadata for databases stored as individual pages.
+pub struct DbPagesMetadataKey {
+	db_name_segment: Arc<Vec<u8>>,
+}
+
+impl DbPagesMetadataKey {
+	pub fn new(db_name_segment: Arc<Vec<u8>>) -> Self {
+		DbPagesMetadataKey { db_name_segment }
+	}
+}
+
+impl FormalKey for DbPagesMetadataKey {
+	type Value = DbPagesMetadata;
+
+	fn deserialize(&self, raw: &[u8]) -> anyhow::Result<Self::Value> {
+		let (page_size, page_count, version) = foundationdb::tuple::unpack::<(u32, u32, u64)>(raw)?;
+
+		Ok(DbPagesMetadata {
+			page_size,
+			page_count,
+			version,
+		})
+	}
+
+	fn serialize(&self, value: Self::Value) -> anyhow::Result<Vec<u8>> {
+		Ok((value.page_size, value.page_count, value.version).pack_to_vec())
+	}
+}
+
+impl TuplePack for DbPagesMetadataKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (DBS, &*self.db_name_segment, METADATA);
+		t.pack(w, tuple_depth)
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DbPagesMetadata {
+	pub page_size: u32,
+	/// Size of the database in pages.
+	pub page_count: u32,
+	/// Database version the pages were last written at, see `DbVersionKey`.
+	pub version: u64,
+}
+
+/// Latest version of a database, kept across writes in either format. Snapshot writes clear the pages
+/// metadata, so this keeps page versions monotonic when a database goes back to being stored as pages.
+pub struct DbVersionKey {
+	db_name_segment: Arc<Vec<u8>>,
+}
+
+impl DbVersionKey {
+	pub fn new(db_name_segment: Arc<Vec<u8>>) -> Self {
+		DbVersionKey { db_name_segment }
+	}
+}
+
+impl FormalKey for DbVersionKey {
+	type Value = u64;
+
+	fn deserialize(&self, raw: &[u8]) -> anyhow::Result<Self::Value> {
+		Ok(u64::from_le_bytes(raw.try_into()?))
+	}
+
+	fn serialize(&self, value: Self::Value) -> anyhow::Result<Vec<u8>> {
+		Ok(value.to_le_bytes().to_vec())
+	}
+}
+
+impl TuplePack for DbVersionKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (DBS, &*self.db_name_segment, VERSION);
+		t.pack(w, tuple_depth)
+	}
+}
+
+pub struct DbPageKey {
+	pub db_name_segment: Arc<Vec<u8>>,
+	/// 1-based, same as SQLite.
+	pub pgno: u32,
+}
+
+impl TuplePack for DbPageKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (DBS, &*self.db_name_segment, PAGE, self.pgno);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for DbPageKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, db_name_segment, _, pgno)) =
+			<(usize, Vec<u8>, usize, u32)>::unpack(input, tuple_depth)?;
+		let v = DbPageKey {
+			db_name_segment: Arc::new(db_name_segment),
+			pgno,
+		};
+
+		Ok((input, v))
+	}
+}
+
+pub struct DbPageSubspaceKey {
+	db_name_segment: Arc<Vec<u8>>,
+}
+
+impl DbPageSubspaceKey {
+	pub fn new(db_name_segment: Arc<Vec<u8>>) -> Self {
+		DbPageSubspaceKey { db_name_segment }
+	}
+}
+
+impl TuplePack for DbPageSubspaceKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (DBS, &*self.db_name_segment, PAGE);
+		t.pack(w, tuple_depth)
+	}
+}
+
+/// Version of the metadata at the time the page was last written. Used by readers to only download pages
+/// that changed since they last read the database.
+pub struct DbPageVersionKey {
+	pub db_name_segment: Arc<Vec<u8>>,
+	pub pgno: u32,
+}
+
+impl FormalKey for DbPageVersionKey {
+	type Value = u64;
+
+	fn deserialize(&self, raw: &[u8]) -> anyhow::Result<Self::Value> {
+		Ok(u64::from_le_bytes(raw.try_into()?))
+	}
+
+	fn serialize(&self, value: Self::Value) -> anyhow::Result<Vec<u8>> {
+		Ok(value.to_le_bytes().to_vec())
+	}
+}
+
+impl TuplePack for DbPageVersionKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (DBS, &*self.db_name_segment, PAGE_VERSION, self.pgno);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for DbPageVersionKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, db_name_segment, _, pgno)) =
+			<(usize, Vec<u8>, usize, u32)>::unpack(input, tuple_depth)?;
+		let v = DbPageVersionKey {
+			db_name_segment: Arc::new(db_name_segment),
+			pgno,
+		};
+
+		Ok((input, v))
+	}
+}
+
+pub struct DbPageVersionSubspaceKey {
+	db_name_segment: Arc<Vec<u8>>,
+}
+
+impl DbPageVersionSubspaceKey {
+	pub fn new(db_name_segment: Arc<Vec<u8>>) -> Self {
+		DbPageVersionSubspaceKey { db_name_segment }
+	}
+}
+
+impl TuplePack for DbPageVersionSubspaceKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (DBS, &*self.db_name_segment, PAGE_VERSION);
+		t.pack(w, tuple_depth)
+	}
+}
+
+pub struct DbWriterLeaseKey {
+	db_name_segment: Arc<Vec<u8>>,
+}
+
+impl DbWriterLeaseKey {
+	pub fn new(db_name_segment: Arc<Vec<u8>>) -> Self {
+		DbWriterLeaseKey { db_name_segment }
+	}
+}
+
+impl FormalKey for DbWriterLeaseKey {
+	type Value = DbWriterLease;
+
+	fn deserialize(&self, raw: &[u8]) -> anyhow::Result<Self::Value> {
+		let (holder, token, expire_ts) = foundationdb::tuple::unpack::<(Uuid, u64, i64)>(raw)?;
+
+		Ok(DbWriterLease {
+			holder,
+			token,
+			expire_ts,
+		})
+	}
+
+	fn serialize(&self, value: Self::Value) -> anyhow::Result<Vec<u8>> {
+		Ok((value.holder, value.token, value.expire_ts).pack_to_vec())
+	}
+}
+
+impl TuplePack for DbWriterLeaseKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (DBS, &*self.db_name_segment, LEASE);
+		t.pack(w, tuple_depth)
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DbWriterLease {
+	/// Id of the `SqlitePoolManager` holding the lease.
+	pub holder: Uuid,
+	/// Fencing token, incremented every time the lease is acquired.
+	pub token: u64,
+	pub expire_ts: i64,
+}
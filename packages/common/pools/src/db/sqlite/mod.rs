@@ -1,9 +1,13 @@
 use std::{
+	collections::BTreeSet,
 	fmt::Debug,
-	io::{self, Read, Write},
+	io::{self, Read, SeekFrom, Write},
 	path::{Path, PathBuf},
-	sync::{Arc, Weak},
-	time::Duration,
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc, Weak,
+	},
+	time::{Duration, SystemTime},
 };
 
 use dirs;
@@ -21,8 +25,12 @@ use sqlx::{
 	},
 	Sqlite,
 };
-use tokio::sync::{oneshot, OnceCell, RwLock};
-use tokio::{io::AsyncReadExt, sync::broadcast, time::Instant};
+use tokio::sync::{oneshot, Mutex, OnceCell, RwLock};
+use tokio::{
+	io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt},
+	sync::broadcast,
+	time::Instant,
+};
 use tracing::Instrument;
 use uuid::Uuid;
 
@@ -36,6 +44,15 @@ mod tests;
 const GC_INTERVAL: Duration = Duration::from_secs(5);
 const POOL_TTL: Duration = Duration::from_secs(15);
 const CHUNK_SIZE: usize = 10_000; // 10 KB, not KiB, see https://apple.github.io/foundationdb/blob.html
+/// How long a reader's local copy of a database is kept after it was last read.
+const READER_CACHE_TTL: Duration = Duration::from_secs(60);
+/// See https://www.sqlite.org/fileformat2.html#walformat
+const WAL_HEADER_SIZE: u64 = 32;
+const WAL_FRAME_HEADER_SIZE: u64 = 24;
+/// How long a writer lease is valid for after the pool was last accessed. Must be shorter than how long it
+/// takes for a workflow worker to be considered lost so that leases held by dead workers expire before their
+/// workflows are picked up by other workers.
+const WRITER_LEASE_TTL: Duration = Duration::from_secs(20);
 
 #[derive(Debug, Clone)]
 pub enum SqliteConnType {
@@ -76,10 +93,24 @@ impl std::fmt::Display for SqliteConnType {
 	}
 }
 
+/// How writer databases are persisted to FDB.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SqliteReplication {
+	/// The entire database file is compressed and written on every snapshot.
+	Snapshot,
+	/// Only pages that were written since the last snapshot are written. Readers only download pages that
+	/// changed since they last read the database.
+	Pages,
+}
+
 #[derive(Debug, thiserror::Error)]
 enum SqliteFdbError {
 	#[error("mismatched chunk {key_idx}, expected {chunk_count}")]
 	MismatchedChunk { chunk_count: usize, key_idx: usize },
+	#[error("missing page {pgno}, expected {page_count} pages")]
+	MissingPage { pgno: u32, page_count: u32 },
+	#[error("mismatched page version, expected {expected} but found {actual:?}")]
+	VersionMismatch { expected: u64, actual: Option<u64> },
 }
 
 /// SQLite pool that's loaded on this machine.
@@ -153,17 +184,82 @@ pub type SqlitePoolManagerHandleWeak = Weak<SqlitePoolManager>;
 
 #[derive(Clone)]
 enum SqliteStorage {
-	Local { path: PathBuf },
-	FoundationDb { path: PathBuf },
+	Local {
+		path: PathBuf,
+	},
+	FoundationDb {
+		path: PathBuf,
+		replication: SqliteReplication,
+	},
+}
+
+/// Where a database was loaded from in FDB.
+enum DbSource {
+	Snapshot,
+	Pages(keys::DbPagesMetadata),
+}
+
+/// Data written to FDB for a single database when snapshotting.
+enum SnapshotData {
+	/// Entire compressed database file.
+	Compressed(Vec<u8>),
+	Pages(DirtyPages),
+}
+
+impl SnapshotData {
+	fn len(&self) -> usize {
+		match self {
+			SnapshotData::Compressed(data) => data.len(),
+			SnapshotData::Pages(pages) => pages.pages.len() * pages.page_size as usize,
+		}
+	}
+}
+
+struct DirtyPages {
+	page_size: u32,
+	page_count: u32,
+	/// Version of the pages in FDB these pages apply on top of. If `None`, all pages are written and
+	/// existing data is cleared.
+	base_version: Option<u64>,
+	pages: Vec<(u32, Vec<u8>)>,
+	file_len: u64,
+	file_modified: SystemTime,
+}
+
+/// State of a writer database as of the last page snapshot.
+#[derive(Debug, Clone, Copy)]
+struct PageState {
+	metadata: keys::DbPagesMetadata,
+	/// Length and modification time of the database file after the last checkpoint. SQLite only
+	/// writes to the database file when checkpointing in WAL mode, so if these change before the next
+	/// snapshot the WAL was checkpointed somewhere else (i.e. when the connection was closed) and the
+	/// dirty pages can no longer be determined from the WAL.
+	file_len: u64,
+	file_modified: SystemTime,
+}
+
+/// Local copy of a database stored as pages that readers copy from.
+struct ReaderCacheEntry {
+	path: PathBuf,
+	/// Metadata of the pages written to the local copy. `None` if the file is not valid.
+	cached: Mutex<Option<keys::DbPagesMetadata>>,
+	last_access: RwLock<Instant>,
 }
 
 pub struct SqlitePoolManager {
 	/// Writer pools are kept in memory. Reader pools are one-off temporary SQLite databases.
 	writer_pools: papaya::HashMap<KeyPacked, SqliteWriterEntry>,
+	/// Local copies of databases stored as pages that readers are created from.
+	reader_cache: papaya::HashMap<KeyPacked, Arc<ReaderCacheEntry>>,
 	shutdown: broadcast::Sender<()>,
 	fdb: Option<FdbPool>,
 	storage: SqliteStorage,
 	subspace: fdb_util::Subspace,
+	/// Identifies this manager as the holder of writer leases.
+	instance_id: Uuid,
+	/// Defaults to `WRITER_LEASE_TTL`. Can be lowered with `_RIVET_POOL_SQLITE_WRITER_LEASE_TTL_MS` to test
+	/// lease expiry.
+	writer_lease_ttl: Duration,
 }
 
 // MARK: Public methods
@@ -192,15 +288,32 @@ impl SqlitePoolManager {
 		{
 			SqliteStorage::Local { path }
 		} else {
-			SqliteStorage::FoundationDb { path }
+			// Whole file snapshots are kept as a fallback
+			let replication = if std::env::var("_RIVET_POOL_SQLITE_REPLICATION")
+				.map_or(false, |x| x == "snapshot")
+			{
+				SqliteReplication::Snapshot
+			} else {
+				SqliteReplication::Pages
+			};
+
+			SqliteStorage::FoundationDb { path, replication }
 		};
 
+		let writer_lease_ttl = std::env::var("_RIVET_POOL_SQLITE_WRITER_LEASE_TTL_MS")
+			.ok()
+			.and_then(|x| x.parse().ok())
+			.map_or(WRITER_LEASE_TTL, Duration::from_millis);
+
 		let manager = Arc::new(SqlitePoolManager {
 			writer_pools: papaya::HashMap::new(),
+			reader_cache: papaya::HashMap::new(),
 			shutdown,
 			fdb: fdb.clone(),
 			storage,
 			subspace: fdb_util::Subspace::new(&(RIVET, SQLITE)),
+			instance_id: Uuid::new_v4(),
+			writer_lease_ttl,
 		});
 
 		tokio::task::spawn(manager.clone().manager_gc_loop(shutdown_rx));
@@ -263,9 +376,7 @@ impl SqlitePoolManager {
 			.map(|key| Arc::new(key.pack_to_vec()))
 			.collect();
 
-		self.evict_with_key(&keys_packed)
-			.await
-			.map_err(Error::Global)?;
+		self.evict_with_key(&keys_packed).await?;
 
 		Ok(())
 	}
@@ -283,9 +394,7 @@ impl SqlitePoolManager {
 			.map(|key| Arc::new(key.pack_to_vec()))
 			.collect();
 
-		self.snapshot_with_key(&keys_packed, vacuum, false)
-			.await
-			.map_err(Error::Global)?;
+		self.snapshot_with_key(&keys_packed, vacuum, false).await?;
 
 		Ok(())
 	}
@@ -300,15 +409,32 @@ impl SqlitePoolManager {
 			// Determine the persistent location of this database
 			SqliteStorage::Local { path } => path.join(format!("{hex_key_str}.db")),
 			// Generate temporary file location so multiple readers don't clobber each other
-			SqliteStorage::FoundationDb { path } => {
+			SqliteStorage::FoundationDb { path, .. } => {
 				path.join(format!("rivet-sqlite-{hex_key_str}-{}.db", Uuid::new_v4()))
 			}
 		}
 	}
 
+	fn reader_cache_path(&self, key_packed: &KeyPacked) -> PathBuf {
+		let hex_key_str = hex::encode(&**key_packed);
+
+		match &self.storage {
+			SqliteStorage::Local { path } | SqliteStorage::FoundationDb { path, .. } => {
+				path.join(format!("rivet-sqlite-{hex_key_str}-reader.db"))
+			}
+		}
+	}
+
+	fn replication(&self) -> Option<SqliteReplication> {
+		match &self.storage {
+			SqliteStorage::Local { .. } => None,
+			SqliteStorage::FoundationDb { replication, .. } => Some(*replication),
+		}
+	}
+
 	/// Inner implementation of database eviction that handles the actual removal from the pool
 	#[tracing::instrument(name = "sqlite_evict_with_key", skip_all)]
-	async fn evict_with_key(&self, keys_packed: &[KeyPacked]) -> GlobalResult<()> {
+	async fn evict_with_key(&self, keys_packed: &[KeyPacked]) -> Result<(), Error> {
 		if keys_packed.is_empty() {
 			return Ok(());
 		}
@@ -318,13 +444,32 @@ impl SqlitePoolManager {
 		}
 
 		// Attempt to snapshot all databases in a single call
-		self.snapshot_with_key(keys_packed, true, false).await?;
+		let res = self.snapshot_with_key(keys_packed, true, false).await;
 
 		// Remove all databases from the pools map
 		// Do this after snapshotting since we don't want to remove the db if the snapshot failed.
-		// If the snapshot failed, it will attempt to snapshot again on GC.
+		// If the snapshot failed, it will attempt to snapshot again on GC. Databases that lost their
+		// writer lease are removed regardless since they can never be snapshotted.
+		let mut released_leases = Vec::new();
 		for key_packed in keys_packed {
+			let pool = self
+				.writer_pools
+				.pin()
+				.get(key_packed)
+				.and_then(|entry| entry.pool_once.get().cloned());
+			let lease_lost = pool.as_ref().map_or(false, |pool| pool.lease_lost());
+
+			if res.is_err() && !lease_lost {
+				continue;
+			}
+
 			if let Some(entry) = self.writer_pools.pin_owned().remove(key_packed) {
+				if let Some(token) = pool.and_then(|pool| pool.lease_token) {
+					if !lease_lost {
+						released_leases.push((key_packed.clone(), token));
+					}
+				}
+
 				// NOTE: papaya does not immediately release memory of entries when you call `.remove`.
 				// This means the pool will stick around for some time, so we close the pool and delete files
 				// after removing to ensure we don't have too many open connections.
@@ -342,20 +487,25 @@ impl SqlitePoolManager {
 			}
 		}
 
-		Ok(())
+		// Allow other writers to acquire the databases immediately instead of waiting for the leases to
+		// expire
+		if let Err(err) = self.release_writer_leases(&released_leases).await {
+			tracing::warn!(?err, "failed to release sqlite writer leases");
+		}
+
+		res.map(|_| ())
 	}
 
 	/// Snapshots the current state of SQLite databases to FDB.
 	///
 	/// This will acquire an exclusive lock on the database to ensure consistency.
 	///
-	/// We can do this because we don't use WAL (since we don't need concurrent readers/writers).
-	///
-	/// We don't use `VACUUM FULL` because it requires significant overhead to execute frequently,
-	/// which we don't need since we don't use a WAL.
+	/// With `SqliteReplication::Snapshot`, the entire database file is compressed and written. We don't
+	/// use `VACUUM FULL` because it requires significant overhead to execute frequently. We don't use the
+	/// `.backup` command (or `sqlite3_backup_*`) because it still has some overhead.
 	///
-	/// We don't use the `.backup` command (or `sqlite3_backup_*`) because it still has some
-	/// overhead.
+	/// With `SqliteReplication::Pages`, only pages written to the WAL since the last snapshot are written.
+	/// See `SqlitePoolInner::read_dirty_pages`.
 	///
 	/// Returns `true` if wrote at least one snapshot.
 	#[tracing::instrument(name = "sqlite_snapshot_with_key", skip_all)]
@@ -364,13 +514,13 @@ impl SqlitePoolManager {
 		keys_packed: &[KeyPacked],
 		vacuum: bool,
 		ensure_exists: bool,
-	) -> GlobalResult<bool> {
+	) -> Result<bool, Error> {
 		if keys_packed.is_empty() {
 			return Ok(false);
 		}
 
 		// Only run if snapshotting required
-		let SqliteStorage::FoundationDb { .. } = self.storage else {
+		let SqliteStorage::FoundationDb { replication, .. } = self.storage else {
 			return Ok(false);
 		};
 
@@ -416,46 +566,20 @@ impl SqlitePoolManager {
 							.await?;
 					}
 
-					// Flush WAL journal
-					sqlx::query("PRAGMA wal_checkpoint(TRUNCATE);")
-						.execute(&mut *conn)
-						.instrument(tracing::info_span!("flush_wal"))
-						.await?;
-
-					// Stream the database file and compress it
-					let mut compressed_data = Vec::new();
-					let file = tokio::fs::File::open(&pool.db_path)
-						.await
-						.map_err(Error::Io)?;
-					let mut reader = tokio::io::BufReader::new(file);
-					let mut encoder = lz4_flex::frame::FrameEncoder::new(&mut compressed_data);
-
-					async {
-						let mut buffer = [0u8; 16 * 1024]; // 16 KiB
-						loop {
-							let bytes_read = reader.read(&mut buffer).await.map_err(Error::Io)?;
-							if bytes_read == 0 {
-								break;
-							}
-							encoder
-								.write_all(&buffer[..bytes_read])
-								.map_err(Error::Io)?;
+					let data = match replication {
+						SqliteReplication::Snapshot => {
+							SnapshotData::Compressed(compress_db(&pool, &mut conn, &hex_key).await?)
 						}
-						encoder.finish().map_err(Error::Lz4)?;
-
-						Result::<_, Error>::Ok(())
-					}
-					.instrument(tracing::info_span!("compress"))
-					.await?;
-
-					// 3 MiB
-					if compressed_data.len() > 3 * 1024 * 1024 {
-						metrics::SQLITE_LARGE_DB
-							.with_label_values(&[&hex_key])
-							.set(compressed_data.len().try_into().unwrap_or(i64::MAX));
-					}
+						SqliteReplication::Pages => match pool.read_dirty_pages(&mut conn).await? {
+							Some(pages) => SnapshotData::Pages(pages),
+							None => {
+								tracing::debug!(key=?hex_key, "skipping snapshot, no dirty pages");
+								return Ok(None);
+							}
+						},
+					};
 
-					Ok(Some((key_packed, Arc::new(compressed_data))))
+					Ok(Some((key_packed, pool.clone(), Arc::new(data))))
 				}
 			})
 			.buffer_unordered(32)
@@ -470,47 +594,238 @@ impl SqlitePoolManager {
 		}
 
 		// Write to FDB in a single transaction
-		let fdb = unwrap!(self.fdb.as_ref());
-		fdb.run(|tx, _mc| {
-			let db_data_to_snapshot = db_data_to_snapshot.clone();
-			let subspace = self.subspace.clone();
-			async move {
-				for (key_packed, data) in &db_data_to_snapshot {
-					// Clear previous data
-					let db_data_subspace =
-						subspace.subspace(&keys::DbDataKey::new(key_packed.clone()));
-					tx.clear_subspace_range(&db_data_subspace);
-					let compressed_db_data_subspace =
-						subspace.subspace(&keys::CompressedDbDataKey::new(key_packed.clone()));
-					tx.clear_subspace_range(&compressed_db_data_subspace);
-
-					// Write chunks
-					for (idx, chunk) in data.chunks(CHUNK_SIZE).enumerate() {
-						let chunk_key = keys::CompressedDbDataChunkKey {
-							db_name_segment: key_packed.clone(),
-							chunk: idx,
+		let fdb = self.fdb.as_ref().ok_or(Error::MissingFdbPool)?;
+		let res = fdb
+			.run(|tx, _mc| {
+				let db_data_to_snapshot = db_data_to_snapshot.clone();
+				let subspace = self.subspace.clone();
+				async move {
+					// Validate fencing tokens before writing anything. If another writer acquired the lease,
+					// this writer's data is stale and must not overwrite the other writer's data.
+					let mut lost_leases = Vec::new();
+					for (key_packed, pool, _) in &db_data_to_snapshot {
+						let Some(token) = pool.lease_token else {
+							continue;
 						};
 
-						tx.set(&subspace.pack(&chunk_key), chunk);
+						let lease_key = keys::DbWriterLeaseKey::new(key_packed.clone());
+						let lease = tx
+							.get(&subspace.pack(&lease_key), SERIALIZABLE)
+							.await?
+							.map(|raw| lease_key.deserialize(&raw))
+							.transpose()
+							.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
+
+						if !lease.map_or(false, |lease| {
+							lease.holder == self.instance_id && lease.token == token
+						}) {
+							lost_leases.push(key_packed.clone());
+						}
+					}
+					if !lost_leases.is_empty() {
+						return Ok(Err(lost_leases));
+					}
+
+					let mut written_metadata = Vec::new();
+
+					for (key_packed, _, data) in &db_data_to_snapshot {
+						let db_data_subspace =
+							subspace.subspace(&keys::DbDataKey::new(key_packed.clone()));
+						let compressed_db_data_subspace =
+							subspace.subspace(&keys::CompressedDbDataKey::new(key_packed.clone()));
+						let page_subspace =
+							subspace.subspace(&keys::DbPageSubspaceKey::new(key_packed.clone()));
+						let page_version_subspace = subspace
+							.subspace(&keys::DbPageVersionSubspaceKey::new(key_packed.clone()));
+						let metadata_key = keys::DbPagesMetadataKey::new(key_packed.clone());
+						let metadata_key_packed = subspace.pack(&metadata_key);
+						let version_key = keys::DbVersionKey::new(key_packed.clone());
+						let version_key_packed = subspace.pack(&version_key);
+
+						let current_metadata = tx
+							.get(&metadata_key_packed, SERIALIZABLE)
+							.await?
+							.map(|raw| metadata_key.deserialize(&raw))
+							.transpose()
+							.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
+
+						// Bump the version on every write so readers caching pages never see the same
+						// version twice, even if the database was written as a snapshot in between. Databases
+						// written before the version key existed only have it in their pages metadata.
+						let version = tx
+							.get(&version_key_packed, SERIALIZABLE)
+							.await?
+							.map(|raw| version_key.deserialize(&raw))
+							.transpose()
+							.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?
+							.or(current_metadata.map(|x| x.version))
+							.unwrap_or(0) + 1;
+						tx.set(
+							&version_key_packed,
+							&version_key
+								.serialize(version)
+								.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
+						);
+
+						match &**data {
+							SnapshotData::Compressed(data) => {
+								// Clear previous data
+								tx.clear_subspace_range(&db_data_subspace);
+								tx.clear_subspace_range(&compressed_db_data_subspace);
+								tx.clear(&metadata_key_packed);
+								tx.clear_subspace_range(&page_subspace);
+								tx.clear_subspace_range(&page_version_subspace);
+
+								// Write chunks
+								for (idx, chunk) in data.chunks(CHUNK_SIZE).enumerate() {
+									let chunk_key = keys::CompressedDbDataChunkKey {
+										db_name_segment: key_packed.clone(),
+										chunk: idx,
+									};
+
+									tx.set(&subspace.pack(&chunk_key), chunk);
+								}
+							}
+							SnapshotData::Pages(pages) => {
+								// Make sure the dirty pages apply on top of the pages in FDB
+								if let Some(base_version) = pages.base_version {
+									let current_version = current_metadata.map(|x| x.version);
+									if current_version != Some(base_version) {
+										return Err(FdbBindingError::CustomError(
+											SqliteFdbError::VersionMismatch {
+												expected: base_version,
+												actual: current_version,
+											}
+											.into(),
+										));
+									}
+								} else {
+									// Clear previous data
+									tx.clear_subspace_range(&db_data_subspace);
+									tx.clear_subspace_range(&compressed_db_data_subspace);
+									tx.clear_subspace_range(&page_subspace);
+									tx.clear_subspace_range(&page_version_subspace);
+								}
+
+								// Clear pages past the end of the database
+								let (_, page_end) = page_subspace.range();
+								tx.clear_range(
+									&subspace.pack(&keys::DbPageKey {
+										db_name_segment: key_packed.clone(),
+										pgno: pages.page_count + 1,
+									}),
+									&page_end,
+								);
+								let (_, page_version_end) = page_version_subspace.range();
+								tx.clear_range(
+									&subspace.pack(&keys::DbPageVersionKey {
+										db_name_segment: key_packed.clone(),
+										pgno: pages.page_count + 1,
+									}),
+									&page_version_end,
+								);
+
+								let metadata = keys::DbPagesMetadata {
+									page_size: pages.page_size,
+									page_count: pages.page_count,
+									version,
+								};
+
+								// Write pages
+								for (pgno, page) in &pages.pages {
+									tx.set(
+										&subspace.pack(&keys::DbPageKey {
+											db_name_segment: key_packed.clone(),
+											pgno: *pgno,
+										}),
+										page,
+									);
+
+									let page_version_key = keys::DbPageVersionKey {
+										db_name_segment: key_packed.clone(),
+										pgno: *pgno,
+									};
+									tx.set(
+										&subspace.pack(&page_version_key),
+										&page_version_key.serialize(metadata.version).map_err(
+											|x| fdb::FdbBindingError::CustomError(x.into()),
+										)?,
+									);
+								}
+
+								tx.set(
+									&metadata_key_packed,
+									&metadata_key
+										.serialize(metadata)
+										.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
+								);
+
+								written_metadata.push((key_packed.clone(), metadata));
+							}
+						}
 					}
+
+					Ok(Ok(written_metadata))
 				}
+			})
+			.custom_instrument(tracing::info_span!("snapshot_sqlite_write_tx"))
+			.await;
 
-				Ok(())
+		let written_metadata = match res {
+			Ok(Ok(x)) => x,
+			Ok(Err(lost_leases)) => {
+				for (key_packed, pool, _) in &db_data_to_snapshot {
+					if lost_leases.contains(key_packed) {
+						tracing::warn!(key=?hex::encode(&**key_packed), "lost sqlite writer lease");
+						pool.lease_lost.store(true, Ordering::Release);
+					}
+				}
+
+				return Err(Error::SqliteWriterLeaseLost {
+					keys: lost_leases
+						.iter()
+						.map(|key_packed| hex::encode(&**key_packed))
+						.collect(),
+				});
 			}
-		})
-		.custom_instrument(tracing::info_span!("snapshot_sqlite_write_tx"))
-		.await?;
+			Err(err) => {
+				// The dirty pages were already checkpointed in to the database file, the next snapshot has
+				// to write all pages
+				for (_, pool, data) in &db_data_to_snapshot {
+					if let SnapshotData::Pages(_) = &**data {
+						*pool.page_state.lock().await = None;
+					}
+				}
+
+				return Err(Error::Global(err.into()));
+			}
+		};
 
 		let dt = start_instant.elapsed().as_secs_f64();
 		let total_data_size = db_data_to_snapshot
 			.iter()
-			.map(|(_, data)| data.len())
+			.map(|(_, _, data)| data.len())
 			.sum::<usize>() as f64;
 
 		// Update state if write was successful
-		for (key_packed, data) in db_data_to_snapshot {
+		for (key_packed, pool, data) in db_data_to_snapshot {
 			let hex_key = hex::encode(&**key_packed);
 
+			if let SnapshotData::Pages(pages) = &*data {
+				if let Some((_, metadata)) = written_metadata.iter().find(|(k, _)| *k == key_packed)
+				{
+					*pool.page_state.lock().await = Some(PageState {
+						metadata: *metadata,
+						file_len: pages.file_len,
+						file_modified: pages.file_modified,
+					});
+				}
+
+				metrics::SQLITE_UPLOAD_PAGES
+					.with_label_values(&[&(pages.base_version.is_none()).to_string()])
+					.inc_by(pages.pages.len().try_into().unwrap_or_default());
+			}
+
 			// Because this was batch processed we don't know the rate for each individual key, just estimate
 			// by calculating the size ratio
 			let ratio = data.len() as f64 / total_data_size;
@@ -536,6 +851,10 @@ impl SqlitePoolManager {
 				}
 			}
 
+			if let Err(err) = self.renew_writer_leases().await {
+				tracing::error!(?err, "failed to renew sqlite writer leases");
+			}
+
 			// Anything last used before this instant will be removed
 			let expire_ts = Instant::now() - POOL_TTL;
 
@@ -586,24 +905,114 @@ impl SqlitePoolManager {
 			}
 
 			tracing::debug!(?removed, total=?total_count, "gc sqlite pools");
+
+			// Remove reader caches that have not been read recently
+			let expire_ts = Instant::now() - READER_CACHE_TTL;
+			let mut expired = Vec::new();
+			for (k, v) in self.reader_cache.pin_owned().iter() {
+				if *v.last_access.read().await <= expire_ts {
+					expired.push(k.clone());
+				}
+			}
+
+			for key in expired {
+				let Some(entry) = self.reader_cache.pin_owned().remove(&key).cloned() else {
+					continue;
+				};
+
+				// Readers that already hold this entry will download the entire database again
+				let mut cached = entry.cached.lock().await;
+				*cached = None;
+
+				if let Err(err) = tokio::fs::remove_file(&entry.path).await {
+					tracing::debug!(?err, path=?entry.path, "failed to remove sqlite reader cache file");
+				}
+			}
 		}
 	}
 }
 
 // MARK: FDB Helpers
 impl SqlitePoolManager {
-	/// Returns true if db exists, false if not.
+	/// Returns where the db was loaded from, or `None` if it does not exist.
 	#[tracing::instrument(name = "sqlite_read_from_fdb", skip_all)]
-	async fn read_from_fdb(&self, key_packed: KeyPacked, db_path: &Path) -> GlobalResult<bool> {
+	async fn read_from_fdb(
+		&self,
+		key_packed: KeyPacked,
+		db_path: &Path,
+	) -> GlobalResult<Option<DbSource>> {
 		let hex_key = hex::encode(&*key_packed);
 		let fdb = unwrap!(self.fdb.as_ref());
 
 		let start_instant = Instant::now();
 
-		let (data, chunks) = fdb
+		let (data, chunks, pages_metadata) = fdb
 			.run(|tx, _mc| {
 				let key_packed = key_packed.clone();
 				async move {
+					// Read pages if the database was written with `SqliteReplication::Pages`
+					let metadata_key = keys::DbPagesMetadataKey::new(key_packed.clone());
+					if let Some(raw) = tx
+						.get(&self.subspace.pack(&metadata_key), SERIALIZABLE)
+						.await?
+					{
+						let metadata = metadata_key
+							.deserialize(&raw)
+							.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
+
+						let page_subspace = self
+							.subspace
+							.subspace(&keys::DbPageSubspaceKey::new(key_packed.clone()));
+						let mut page_stream = tx.get_ranges_keyvalues(
+							fdb::RangeOption {
+								mode: StreamingMode::WantAll,
+								..(&page_subspace).into()
+							},
+							SERIALIZABLE,
+						);
+
+						let mut buf = Vec::with_capacity(
+							metadata.page_count as usize * metadata.page_size as usize,
+						);
+						let mut next_pgno = 1;
+						while let Some(entry) = page_stream.try_next().await? {
+							// Parse key
+							let key = self
+								.subspace
+								.unpack::<keys::DbPageKey>(entry.key())
+								.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
+
+							// Validate page
+							if key.pgno != next_pgno
+								|| entry.value().len() != metadata.page_size as usize
+							{
+								return Err(FdbBindingError::CustomError(
+									SqliteFdbError::MissingPage {
+										pgno: next_pgno,
+										page_count: metadata.page_count,
+									}
+									.into(),
+								));
+							}
+							next_pgno += 1;
+
+							// Write to buffer
+							buf.extend(entry.value());
+						}
+
+						if next_pgno != metadata.page_count + 1 {
+							return Err(FdbBindingError::CustomError(
+								SqliteFdbError::MissingPage {
+									pgno: next_pgno,
+									page_count: metadata.page_count,
+								}
+								.into(),
+							));
+						}
+
+						return Ok((buf, metadata.page_count as usize, Some(metadata)));
+					}
+
 					let compressed_db_data_subspace = self
 						.subspace
 						.subspace(&keys::CompressedDbDataKey::new(key_packed.clone()));
@@ -693,7 +1102,7 @@ impl SqlitePoolManager {
 						}
 					}
 
-					Ok((buf, chunk_count))
+					Ok((buf, chunk_count, None))
 				}
 			})
 			.custom_instrument(tracing::info_span!("read_from_fdb_tx"))
@@ -710,13 +1119,356 @@ impl SqlitePoolManager {
 				.with_label_values(&[&hex_key])
 				.set(data_len as f64 / dt.as_secs_f64());
 
-			Ok(true)
+			Ok(Some(match pages_metadata {
+				Some(metadata) => DbSource::Pages(metadata),
+				None => DbSource::Snapshot,
+			}))
 		} else {
 			tracing::debug!(key=?hex_key, "db not found in fdb");
 
-			Ok(false)
+			Ok(None)
 		}
 	}
+
+	/// Loads a point-in-time copy of a database stored as pages in to `db_path` for a reader.
+	///
+	/// A local copy of the database is kept between reads so only pages that changed since the last read
+	/// on this machine are downloaded.
+	///
+	/// Returns false if the database is not stored as pages.
+	#[tracing::instrument(name = "sqlite_read_pages_from_fdb", skip_all)]
+	async fn read_pages_from_fdb(
+		&self,
+		key_packed: KeyPacked,
+		db_path: &Path,
+	) -> GlobalResult<bool> {
+		let hex_key = hex::encode(&*key_packed);
+		let fdb = unwrap!(self.fdb.as_ref());
+
+		let start_instant = Instant::now();
+
+		let entry = self
+			.reader_cache
+			.pin()
+			.get_or_insert_with(key_packed.clone(), || {
+				Arc::new(ReaderCacheEntry {
+					path: self.reader_cache_path(&key_packed),
+					cached: Mutex::new(None),
+					last_access: RwLock::new(Instant::now()),
+				})
+			})
+			.clone();
+
+		{
+			*entry.last_access.write().await = Instant::now();
+		}
+
+		// Held for the duration of the read so concurrent readers don't write to the same file
+		let mut cached = entry.cached.lock().await;
+		let cached_metadata = *cached;
+
+		let res = fdb
+			.run(|tx, _mc| {
+				let key_packed = key_packed.clone();
+				async move {
+					let metadata_key = keys::DbPagesMetadataKey::new(key_packed.clone());
+					let Some(raw) = tx
+						.get(&self.subspace.pack(&metadata_key), SERIALIZABLE)
+						.await?
+					else {
+						return Ok(None);
+					};
+					let metadata = metadata_key
+						.deserialize(&raw)
+						.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
+
+					// Pages written after this version need to be downloaded. Versions are monotonic across
+					// snapshot writes, so a full rewrite of the pages always has a newer version.
+					let since_version = cached_metadata
+						.filter(|x| {
+							x.page_size == metadata.page_size && x.version <= metadata.version
+						})
+						.map(|x| x.version);
+
+					// Determine which pages changed
+					let mut pgnos = Vec::new();
+					if since_version != Some(metadata.version) {
+						let page_version_subspace = self
+							.subspace
+							.subspace(&keys::DbPageVersionSubspaceKey::new(key_packed.clone()));
+						let mut page_version_stream = tx.get_ranges_keyvalues(
+							fdb::RangeOption {
+								mode: StreamingMode::WantAll,
+								..(&page_version_subspace).into()
+							},
+							SERIALIZABLE,
+						);
+
+						while let Some(entry) = page_version_stream.try_next().await? {
+							let key = self
+								.subspace
+								.unpack::<keys::DbPageVersionKey>(entry.key())
+								.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
+							let version = key
+								.deserialize(entry.value())
+								.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
+
+							if since_version.map_or(true, |since_version| version > since_version) {
+								pgnos.push(key.pgno);
+							}
+						}
+					}
+
+					// Fetch changed pages. All reads happen in the same transaction so the pages are
+					// consistent with the metadata.
+					let pages = futures_util::future::try_join_all(pgnos.into_iter().map(|pgno| {
+						let page_key = self.subspace.pack(&keys::DbPageKey {
+							db_name_segment: key_packed.clone(),
+							pgno,
+						});
+
+						async move {
+							let Some(page) = tx.get(&page_key, SERIALIZABLE).await? else {
+								return Err(FdbBindingError::CustomError(
+									SqliteFdbError::MissingPage {
+										pgno,
+										page_count: metadata.page_count,
+									}
+									.into(),
+								));
+							};
+
+							Ok((pgno, page.to_vec()))
+						}
+					}))
+					.await?;
+
+					Ok(Some((metadata, since_version.is_none(), pages)))
+				}
+			})
+			.custom_instrument(tracing::info_span!("read_pages_from_fdb_tx"))
+			.await?;
+
+		let Some((metadata, full, pages)) = res else {
+			return Ok(false);
+		};
+
+		// Reset state in case writing fails
+		*cached = None;
+
+		// Apply changed pages to the local copy
+		let mut file = tokio::fs::OpenOptions::new()
+			.create(true)
+			.write(true)
+			.truncate(full)
+			.open(&entry.path)
+			.await
+			.map_err(Error::Io)?;
+		let mut data_len = 0;
+		for (pgno, page) in &pages {
+			file.seek(SeekFrom::Start(
+				(*pgno as u64 - 1) * metadata.page_size as u64,
+			))
+			.await
+			.map_err(Error::Io)?;
+			file.write_all(page).await.map_err(Error::Io)?;
+			data_len += page.len();
+		}
+		file.set_len(metadata.page_count as u64 * metadata.page_size as u64)
+			.await
+			.map_err(Error::Io)?;
+		file.flush().await.map_err(Error::Io)?;
+		drop(file);
+
+		*cached = Some(metadata);
+
+		// Copy so the reader has its own file
+		tokio::fs::copy(&entry.path, db_path)
+			.await
+			.map_err(Error::Io)?;
+
+		tracing::debug!(key=?hex_key, pages=?pages.len(), ?full, "loaded database pages from fdb");
+
+		let dt = start_instant.elapsed();
+		metrics::SQLITE_DOWNLOAD_DB_RATE
+			.with_label_values(&[&hex_key])
+			.set(data_len as f64 / dt.as_secs_f64());
+
+		Ok(true)
+	}
+}
+
+// MARK: Writer leases
+impl SqlitePoolManager {
+	/// Acquires the writer lease for a database. Fails if another writer holds a lease that has not expired.
+	///
+	/// Returns the fencing token that is validated every time the database is snapshotted.
+	#[tracing::instrument(name = "sqlite_acquire_writer_lease", skip_all)]
+	async fn acquire_writer_lease(&self, key_packed: &KeyPacked) -> Result<u64, Error> {
+		let fdb = self.fdb.as_ref().ok_or(Error::MissingFdbPool)?;
+
+		let token = fdb
+			.run(|tx, _mc| {
+				let key_packed = key_packed.clone();
+				async move {
+					let now = rivet_util::timestamp::now();
+					let lease_key = keys::DbWriterLeaseKey::new(key_packed);
+					let lease_key_packed = self.subspace.pack(&lease_key);
+
+					let lease = tx
+						.get(&lease_key_packed, SERIALIZABLE)
+						.await?
+						.map(|raw| lease_key.deserialize(&raw))
+						.transpose()
+						.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
+
+					if let Some(lease) = &lease {
+						if lease.holder != self.instance_id && lease.expire_ts > now {
+							return Ok(None);
+						}
+					}
+
+					let token = lease.map_or(0, |lease| lease.token) + 1;
+					tx.set(
+						&lease_key_packed,
+						&lease_key
+							.serialize(keys::DbWriterLease {
+								holder: self.instance_id,
+								token,
+								expire_ts: now + self.writer_lease_ttl.as_millis() as i64,
+							})
+							.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
+					);
+
+					Ok(Some(token))
+				}
+			})
+			.custom_instrument(tracing::info_span!("acquire_writer_lease_tx"))
+			.await
+			.map_err(|x| Error::Global(x.into()))?;
+
+		token.ok_or_else(|| Error::SqliteWriterLeaseHeld {
+			key: hex::encode(&**key_packed),
+		})
+	}
+
+	/// Extends the writer leases of all loaded writers to the writer lease TTL after they were last
+	/// accessed. Leases that were acquired by another writer are marked as lost.
+	#[tracing::instrument(name = "sqlite_renew_writer_leases", skip_all)]
+	async fn renew_writer_leases(&self) -> Result<(), Error> {
+		let now = rivet_util::timestamp::now();
+
+		let mut leases = Vec::new();
+		for (key_packed, entry) in self.writer_pools.pin_owned().iter() {
+			let Some(pool) = entry.pool_once.get() else {
+				continue;
+			};
+			let Some(token) = pool.lease_token else {
+				continue;
+			};
+			if pool.lease_lost() {
+				continue;
+			}
+
+			let last_access = { *entry.last_access.read().await };
+			let expire_ts = now - last_access.elapsed().as_millis() as i64
+				+ self.writer_lease_ttl.as_millis() as i64;
+
+			leases.push((key_packed.clone(), pool.clone(), token, expire_ts));
+		}
+
+		if leases.is_empty() {
+			return Ok(());
+		}
+
+		let fdb = self.fdb.as_ref().ok_or(Error::MissingFdbPool)?;
+		let leases_ref = &leases;
+		let lost_leases = fdb
+			.run(|tx, _mc| async move {
+				let mut lost_leases = Vec::new();
+
+				for (key_packed, _, token, expire_ts) in leases_ref {
+					let lease_key = keys::DbWriterLeaseKey::new(key_packed.clone());
+					let lease_key_packed = self.subspace.pack(&lease_key);
+
+					let lease = tx
+						.get(&lease_key_packed, SERIALIZABLE)
+						.await?
+						.map(|raw| lease_key.deserialize(&raw))
+						.transpose()
+						.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
+
+					match lease {
+						Some(lease)
+							if lease.holder == self.instance_id && lease.token == *token =>
+						{
+							if *expire_ts > lease.expire_ts {
+								tx.set(
+									&lease_key_packed,
+									&lease_key
+										.serialize(keys::DbWriterLease {
+											expire_ts: *expire_ts,
+											..lease
+										})
+										.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
+								);
+							}
+						}
+						_ => lost_leases.push(key_packed.clone()),
+					}
+				}
+
+				Ok(lost_leases)
+			})
+			.custom_instrument(tracing::info_span!("renew_writer_leases_tx"))
+			.await
+			.map_err(|x| Error::Global(x.into()))?;
+
+		for (key_packed, pool, _, _) in &leases {
+			if lost_leases.contains(key_packed) {
+				tracing::warn!(key=?hex::encode(&**key_packed), "lost sqlite writer lease");
+				pool.lease_lost.store(true, Ordering::Release);
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Releases writer leases that are still held by this manager.
+	#[tracing::instrument(name = "sqlite_release_writer_leases", skip_all)]
+	async fn release_writer_leases(&self, leases: &[(KeyPacked, u64)]) -> Result<(), Error> {
+		if leases.is_empty() {
+			return Ok(());
+		}
+
+		let fdb = self.fdb.as_ref().ok_or(Error::MissingFdbPool)?;
+		fdb.run(|tx, _mc| async move {
+			for (key_packed, token) in leases {
+				let lease_key = keys::DbWriterLeaseKey::new(key_packed.clone());
+				let lease_key_packed = self.subspace.pack(&lease_key);
+
+				let lease = tx
+					.get(&lease_key_packed, SERIALIZABLE)
+					.await?
+					.map(|raw| lease_key.deserialize(&raw))
+					.transpose()
+					.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
+
+				if lease.map_or(false, |lease| {
+					lease.holder == self.instance_id && lease.token == *token
+				}) {
+					tx.clear(&lease_key_packed);
+				}
+			}
+
+			Ok(())
+		})
+		.custom_instrument(tracing::info_span!("release_writer_leases_tx"))
+		.await
+		.map_err(|x| Error::Global(x.into()))?;
+
+		Ok(())
+	}
 }
 
 impl Drop for SqlitePoolManager {
@@ -734,6 +1486,15 @@ pub struct SqlitePoolInner {
 	db_path: PathBuf,
 	manager: SqlitePoolManagerHandle,
 
+	/// Only used for writers with `SqliteReplication::Pages`. `None` if all pages need to be written on the
+	/// next snapshot.
+	page_state: Mutex<Option<PageState>>,
+
+	/// Fencing token of the writer lease. Only set for writers stored in FDB.
+	lease_token: Option<u64>,
+	/// Set once another writer acquired the lease for this database.
+	lease_lost: AtomicBool,
+
 	/// Used to notify future when this is dropped.
 	_drop_task: oneshot::Sender<()>,
 }
@@ -748,6 +1509,8 @@ impl SqlitePoolInner {
 		let db_url = format!("sqlite://{}", db_path.display());
 
 		// Load database
+		let mut loaded_pages = None;
+		let mut lease_token = None;
 		match &manager.storage {
 			SqliteStorage::Local { .. } => {
 				if !Sqlite::database_exists(&db_url)
@@ -769,12 +1532,46 @@ impl SqlitePoolInner {
 					tracing::debug!(?db_url, "sqlite database already exists");
 				}
 			}
-			SqliteStorage::FoundationDb { .. } => {
+			SqliteStorage::FoundationDb { replication, .. } => {
+				// Acquire the lease before reading so no other writer can write to FDB in between
+				if conn_type.is_writer() {
+					lease_token = Some(manager.acquire_writer_lease(&key_packed).await?);
+				}
+
 				// Read db from FDB
-				let db_exists = manager
-					.read_from_fdb(key_packed.clone(), &db_path)
-					.await
-					.map_err(Error::Global)?;
+				let read_pages =
+					if conn_type.is_reader() && *replication == SqliteReplication::Pages {
+						manager
+							.read_pages_from_fdb(key_packed.clone(), &db_path)
+							.await
+							.map_err(Error::Global)?
+					} else {
+						false
+					};
+
+				let db_exists = if read_pages {
+					true
+				} else {
+					match manager
+						.read_from_fdb(key_packed.clone(), &db_path)
+						.await
+						.map_err(Error::Global)?
+					{
+						Some(DbSource::Pages(metadata)) => {
+							let file_metadata =
+								tokio::fs::metadata(&db_path).await.map_err(Error::Io)?;
+							loaded_pages = Some(PageState {
+								metadata,
+								file_len: file_metadata.len(),
+								file_modified: file_metadata.modified().map_err(Error::Io)?,
+							});
+
+							true
+						}
+						Some(DbSource::Snapshot) => true,
+						None => false,
+					}
+				};
 
 				// Create database if needed
 				if !db_exists {
@@ -839,6 +1636,15 @@ impl SqlitePoolInner {
 			)
 		};
 
+		// Dirty pages are read from the WAL when snapshotting, so the WAL must only be checkpointed when
+		// snapshotting
+		let opts =
+			if conn_type.is_writer() && manager.replication() == Some(SqliteReplication::Pages) {
+				opts.pragma("wal_autocheckpoint", "0")
+			} else {
+				opts
+			};
+
 		// Create pool
 		let res = pool_opts
 			.connect_with(opts)
@@ -860,6 +1666,23 @@ impl SqlitePoolInner {
 
 		tracing::debug!(?db_url, "sqlite connected");
 
+		// Connecting can modify the database file (i.e. changing the journal mode), in which case all pages
+		// are written on the next snapshot
+		let page_state = if conn_type.is_writer() {
+			if let Some(loaded_pages) = loaded_pages {
+				let file_metadata = tokio::fs::metadata(&db_path).await.map_err(Error::Io)?;
+				let file_modified = file_metadata.modified().map_err(Error::Io)?;
+
+				(loaded_pages.file_len == file_metadata.len()
+					&& loaded_pages.file_modified == file_modified)
+					.then_some(loaded_pages)
+			} else {
+				None
+			}
+		} else {
+			None
+		};
+
 		// Create drop handle
 		let (drop_tx, drop_rx) = oneshot::channel();
 		tokio::spawn({
@@ -880,6 +1703,9 @@ impl SqlitePoolInner {
 			inner: pool,
 			db_path,
 			manager,
+			page_state: Mutex::new(page_state),
+			lease_token,
+			lease_lost: AtomicBool::new(false),
 			_drop_task: drop_tx,
 		}))
 	}
@@ -895,6 +1721,7 @@ impl SqlitePoolInner {
 			.await
 		{
 			Ok(x) => Ok(x),
+			Err(err @ Error::SqliteWriterLeaseLost { .. }) => Err(err.into()),
 			Err(err) => {
 				tracing::error!(
 					?err,
@@ -909,7 +1736,9 @@ impl SqlitePoolInner {
 	pub async fn evict(&self) -> GlobalResult<()> {
 		self.manager
 			.evict_with_key(&[self.key_packed.clone()])
-			.await
+			.await?;
+
+		Ok(())
 	}
 }
 
@@ -918,6 +1747,12 @@ impl SqlitePoolInner {
 		&self.db_path
 	}
 
+	/// Whether another writer acquired the lease for this database. Once lost, this pool can no longer be
+	/// snapshotted.
+	pub fn lease_lost(&self) -> bool {
+		self.lease_lost.load(Ordering::Acquire)
+	}
+
 	#[tracing::instrument(skip_all)]
 	pub async fn conn(&self) -> Result<PoolConnection<Sqlite>, sqlx::Error> {
 		// Attempt to use an existing connection
@@ -930,6 +1765,110 @@ impl SqlitePoolInner {
 	}
 }
 
+impl SqlitePoolInner {
+	/// Checkpoints the WAL and reads the pages modified since the last snapshot.
+	///
+	/// Page numbers are read from the WAL frame headers before checkpointing and the page contents are
+	/// read from the database file after checkpointing. If the database file was modified outside of a
+	/// snapshot since the last snapshot, all pages are read.
+	///
+	/// Returns `None` if nothing changed since the last snapshot.
+	#[tracing::instrument(skip_all)]
+	async fn read_dirty_pages(
+		&self,
+		conn: &mut PoolConnection<Sqlite>,
+	) -> GlobalResult<Option<DirtyPages>> {
+		let mut page_state = self.page_state.lock().await;
+
+		let file_metadata = tokio::fs::metadata(&self.db_path)
+			.await
+			.map_err(Error::Io)?;
+		let mut full = match &*page_state {
+			Some(state) => {
+				state.file_len != file_metadata.len()
+					|| state.file_modified != file_metadata.modified().map_err(Error::Io)?
+			}
+			None => true,
+		};
+
+		let wal_path = PathBuf::from(format!("{}-wal", self.db_path.display()));
+		let dirty_pgnos = read_wal_page_numbers(&wal_path).await?;
+
+		// Flush WAL journal
+		sqlx::query("PRAGMA wal_checkpoint(TRUNCATE);")
+			.execute(&mut **conn)
+			.instrument(tracing::info_span!("flush_wal"))
+			.await?;
+
+		let page_size = sqlx::query_scalar::<_, i64>("PRAGMA page_size;")
+			.fetch_one(&mut **conn)
+			.await?;
+		let page_size = u32::try_from(page_size)?;
+
+		let file_metadata = tokio::fs::metadata(&self.db_path)
+			.await
+			.map_err(Error::Io)?;
+		let file_len = file_metadata.len();
+		let file_modified = file_metadata.modified().map_err(Error::Io)?;
+		let page_count = u32::try_from(file_len / page_size as u64)?;
+
+		if let Some(state) = &mut *page_state {
+			if state.metadata.page_size != page_size {
+				full = true;
+			} else if !full && dirty_pgnos.is_empty() && state.metadata.page_count == page_count {
+				// Checkpointing an empty WAL may still touch the database file
+				state.file_len = file_len;
+				state.file_modified = file_modified;
+
+				return Ok(None);
+			}
+		}
+
+		let pgnos = if full {
+			(1..=page_count).collect::<Vec<_>>()
+		} else {
+			dirty_pgnos
+				.into_iter()
+				.filter(|pgno| *pgno <= page_count)
+				.collect()
+		};
+
+		let mut file = tokio::fs::File::open(&self.db_path)
+			.await
+			.map_err(Error::Io)?;
+		let mut pages = Vec::with_capacity(pgnos.len());
+
+		async {
+			for pgno in pgnos {
+				let mut page = vec![0u8; page_size as usize];
+				file.seek(SeekFrom::Start((pgno as u64 - 1) * page_size as u64))
+					.await
+					.map_err(Error::Io)?;
+				file.read_exact(&mut page).await.map_err(Error::Io)?;
+
+				pages.push((pgno, page));
+			}
+
+			Result::<_, Error>::Ok(())
+		}
+		.instrument(tracing::info_span!("read_pages"))
+		.await?;
+
+		Ok(Some(DirtyPages {
+			page_size,
+			page_count,
+			base_version: if full {
+				None
+			} else {
+				page_state.as_ref().map(|state| state.metadata.version)
+			},
+			pages,
+			file_len,
+			file_modified,
+		}))
+	}
+}
+
 impl std::ops::Deref for SqlitePoolInner {
 	type Target = sqlx::SqlitePool;
 
@@ -980,3 +1919,92 @@ async fn clear_db_files(storage: &SqliteStorage, db_path: PathBuf) {
 		}
 	}
 }
+
+/// Checkpoints the WAL and compresses the entire database file.
+async fn compress_db(
+	pool: &SqlitePoolInner,
+	conn: &mut PoolConnection<Sqlite>,
+	hex_key: &str,
+) -> GlobalResult<Vec<u8>> {
+	// Flush WAL journal
+	sqlx::query("PRAGMA wal_checkpoint(TRUNCATE);")
+		.execute(&mut **conn)
+		.instrument(tracing::info_span!("flush_wal"))
+		.await?;
+
+	// Stream the database file and compress it
+	let mut compressed_data = Vec::new();
+	let file = tokio::fs::File::open(&pool.db_path)
+		.await
+		.map_err(Error::Io)?;
+	let mut reader = tokio::io::BufReader::new(file);
+	let mut encoder = lz4_flex::frame::FrameEncoder::new(&mut compressed_data);
+
+	async {
+		let mut buffer = [0u8; 16 * 1024]; // 16 KiB
+		loop {
+			let bytes_read = reader.read(&mut buffer).await.map_err(Error::Io)?;
+			if bytes_read == 0 {
+				break;
+			}
+			encoder
+				.write_all(&buffer[..bytes_read])
+				.map_err(Error::Io)?;
+		}
+		encoder.finish().map_err(Error::Lz4)?;
+
+		Result::<_, Error>::Ok(())
+	}
+	.instrument(tracing::info_span!("compress"))
+	.await?;
+
+	// 3 MiB
+	if compressed_data.len() > 3 * 1024 * 1024 {
+		metrics::SQLITE_LARGE_DB
+			.with_label_values(&[hex_key])
+			.set(compressed_data.len().try_into().unwrap_or(i64::MAX));
+	}
+
+	Ok(compressed_data)
+}
+
+/// Reads the page numbers of all frames in a WAL file. This may include frames from uncommitted
+/// transactions, which is fine since the pages are read from the database file after checkpointing.
+async fn read_wal_page_numbers(path: &Path) -> GlobalResult<BTreeSet<u32>> {
+	let mut file = match tokio::fs::File::open(path).await {
+		Ok(file) => file,
+		Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(BTreeSet::new()),
+		Err(err) => return Err(Error::Io(err).into()),
+	};
+
+	let wal_len = file.metadata().await.map_err(Error::Io)?.len();
+	if wal_len < WAL_HEADER_SIZE {
+		return Ok(BTreeSet::new());
+	}
+
+	let mut header = [0u8; WAL_HEADER_SIZE as usize];
+	file.read_exact(&mut header).await.map_err(Error::Io)?;
+	let page_size = match u32::from_be_bytes([header[8], header[9], header[10], header[11]]) {
+		// The max page size doesn't fit in the 16 bit field of the database header and is stored as 1 in
+		// the WAL header as well
+		1 => 65_536,
+		page_size => page_size as u64,
+	};
+
+	let frame_size = WAL_FRAME_HEADER_SIZE + page_size;
+	let mut pgnos = BTreeSet::new();
+	let mut offset = WAL_HEADER_SIZE;
+	let mut pgno = [0u8; 4];
+
+	while offset + frame_size <= wal_len {
+		file.seek(SeekFrom::Start(offset))
+			.await
+			.map_err(Error::Io)?;
+		file.read_exact(&mut pgno).await.map_err(Error::Io)?;
+		pgnos.insert(u32::from_be_bytes(pgno));
+
+		offset += frame_size;
+	}
+
+	Ok(pgnos)
+}
@@ -71,6 +71,12 @@ pub enum Error {
 
 	#[error("lz4: {0}")]
 	Lz4(lz4_flex::frame::Error),
+
+	#[error("sqlite writer lease for {key} is held by another writer")]
+	SqliteWriterLeaseHeld { key: String },
+
+	#[error("lost sqlite writer lease, another writer took over: {}", .keys.join(", "))]
+	SqliteWriterLeaseLost { keys: Vec<String> },
 }
 
 impl From<global_error::GlobalError> for Error {
@@ -78,6 +78,13 @@ lazy_static::lazy_static! {
 		*REGISTRY,
 	)
 	.unwrap();
+	pub static ref SQLITE_UPLOAD_PAGES: IntCounterVec = register_int_counter_vec_with_registry!(
+		"sqlite_upload_pages",
+		"Pages written to FDB when snapshotting.",
+		&["full"],
+		*REGISTRY,
+	)
+	.unwrap();
 	pub static ref SQLITE_DOWNLOAD_DB_RATE: GaugeVec = register_gauge_vec_with_registry!(
 		"sqlite_download_db_rate",
 		"Rate to download and write to file an sqlite DB from FDB in bytes/s.",
@@ -0,0 +1,145 @@
+use rivet_pools::{
+	db::sqlite::{SqliteConnType, SqlitePoolManager, SqlitePoolManagerHandle},
+	Pools,
+};
+use uuid::Uuid;
+
+/// Verifies a reader with a cached copy of the database sees writes made after the database switched from
+/// pages to a whole-file snapshot and back.
+#[tokio::test(flavor = "multi_thread")]
+async fn sqlite_alternating_replication() {
+	let mut root = rivet_config::config::Root::default();
+	root.server.as_mut().unwrap().foundationdb = Some(Default::default());
+	let config = rivet_config::Config::from_root(root);
+	let pools = Pools::test(config).await.unwrap();
+
+	// Replicates with pages and holds the reader cache
+	let pages_manager = pools.sqlite_manager().clone();
+
+	// Replicates with whole-file snapshots, only read on creation
+	std::env::set_var("_RIVET_POOL_SQLITE_REPLICATION", "snapshot");
+	let snapshot_manager = SqlitePoolManager::new(Some(pools.fdb().unwrap()))
+		.await
+		.unwrap();
+	std::env::remove_var("_RIVET_POOL_SQLITE_REPLICATION");
+
+	let db_name = format!("test_db_{}", Uuid::new_v4());
+
+	write_row(&pages_manager, &db_name, true).await;
+	assert_eq!(1, read_count(&pages_manager, &db_name).await);
+
+	write_row(&snapshot_manager, &db_name, false).await;
+	assert_eq!(2, read_count(&pages_manager, &db_name).await);
+
+	// Written as a full set of pages again, the reader must not mistake it for its cached copy
+	write_row(&pages_manager, &db_name, false).await;
+	assert_eq!(3, read_count(&pages_manager, &db_name).await);
+
+	write_row(&snapshot_manager, &db_name, false).await;
+	write_row(&pages_manager, &db_name, false).await;
+	write_row(&pages_manager, &db_name, false).await;
+	assert_eq!(6, read_count(&pages_manager, &db_name).await);
+}
+
+/// Verifies dirty pages are read from the WAL of a database using the max page size, which the WAL header
+/// stores as 1.
+#[tokio::test(flavor = "multi_thread")]
+async fn sqlite_replication_max_page_size() {
+	let mut root = rivet_config::config::Root::default();
+	root.server.as_mut().unwrap().foundationdb = Some(Default::default());
+	let config = rivet_config::Config::from_root(root);
+	let pools = Pools::test(config).await.unwrap();
+	let manager = pools.sqlite_manager().clone();
+
+	let db_name = format!("test_db_{}", Uuid::new_v4());
+
+	write_row(&manager, &db_name, true).await;
+
+	// The page size can only be changed outside of WAL mode
+	{
+		let db = manager
+			.get(
+				db_name.as_str(),
+				SqliteConnType::Writer {
+					auto_snapshot: false,
+				},
+			)
+			.await
+			.unwrap();
+
+		{
+			let mut conn = db.conn().await.unwrap();
+
+			for query in [
+				"PRAGMA journal_mode = DELETE",
+				"PRAGMA page_size = 65536",
+				"VACUUM",
+				"PRAGMA journal_mode = WAL",
+			] {
+				sqlx::query(query).execute(&mut *conn).await.unwrap();
+			}
+		}
+
+		drop(db);
+		manager.evict(vec![db_name.as_str()]).await.unwrap();
+	}
+
+	write_row(&manager, &db_name, false).await;
+	assert_eq!(2, read_count(&manager, &db_name).await);
+
+	write_row(&manager, &db_name, false).await;
+	assert_eq!(3, read_count(&manager, &db_name).await);
+
+	let db = manager
+		.get(db_name.as_str(), SqliteConnType::Reader)
+		.await
+		.unwrap();
+	let mut conn = db.conn().await.unwrap();
+	let page_size = sqlx::query_scalar::<_, i64>("PRAGMA page_size")
+		.fetch_one(&mut *conn)
+		.await
+		.unwrap();
+	assert_eq!(65_536, page_size);
+}
+
+/// Loads the database as a writer, inserts a row and writes it to FDB.
+async fn write_row(manager: &SqlitePoolManagerHandle, db_name: &str, create_table: bool) {
+	let db = manager
+		.get(
+			db_name,
+			SqliteConnType::Writer {
+				auto_snapshot: false,
+			},
+		)
+		.await
+		.unwrap();
+
+	{
+		let mut conn = db.conn().await.unwrap();
+
+		if create_table {
+			sqlx::query("CREATE TABLE test (value INT)")
+				.execute(&mut *conn)
+				.await
+				.unwrap();
+		}
+
+		sqlx::query("INSERT INTO test (value) VALUES (1)")
+			.execute(&mut *conn)
+			.await
+			.unwrap();
+	}
+
+	drop(db);
+	manager.evict(vec![db_name]).await.unwrap();
+}
+
+async fn read_count(manager: &SqlitePoolManagerHandle, db_name: &str) -> i64 {
+	let db = manager.get(db_name, SqliteConnType::Reader).await.unwrap();
+	let mut conn = db.conn().await.unwrap();
+
+	sqlx::query_scalar("SELECT COUNT(*) FROM test")
+		.fetch_one(&mut *conn)
+		.await
+		.unwrap()
+}
@@ -0,0 +1,137 @@
+use std::time::Duration;
+
+use rivet_pools::{
+	db::sqlite::{SqliteConnType, SqlitePoolManager, SqlitePoolManagerHandle},
+	Error, Pools,
+};
+use uuid::Uuid;
+
+const WRITER: SqliteConnType = SqliteConnType::Writer {
+	auto_snapshot: false,
+};
+
+/// Verifies a database can't be loaded as a writer by a second manager while the first one holds the
+/// lease.
+#[tokio::test(flavor = "multi_thread")]
+async fn sqlite_writer_lease_held() {
+	let pools = setup().await;
+	let manager = pools.sqlite_manager().clone();
+	let other_manager = SqlitePoolManager::new(Some(pools.fdb().unwrap()))
+		.await
+		.unwrap();
+
+	let db_name = format!("test_db_{}", Uuid::new_v4());
+
+	let _db = manager.get(db_name.as_str(), WRITER).await.unwrap();
+
+	let res = other_manager.get(db_name.as_str(), WRITER).await;
+	assert!(
+		matches!(res, Err(Error::SqliteWriterLeaseHeld { .. })),
+		"expected lease to be held, got {:?}",
+		res.map(|_| ())
+	);
+}
+
+/// Verifies a writer whose lease expired and was acquired by another manager can no longer snapshot, so it
+/// can't overwrite the new writer's data.
+#[tokio::test(flavor = "multi_thread")]
+async fn sqlite_writer_lease_lost() {
+	let pools = setup().await;
+	let manager = pools.sqlite_manager().clone();
+
+	// Holds leases that expire shortly after the database was last accessed
+	std::env::set_var("_RIVET_POOL_SQLITE_WRITER_LEASE_TTL_MS", "1000");
+	let stale_manager = SqlitePoolManager::new(Some(pools.fdb().unwrap()))
+		.await
+		.unwrap();
+	std::env::remove_var("_RIVET_POOL_SQLITE_WRITER_LEASE_TTL_MS");
+
+	let db_name = format!("test_db_{}", Uuid::new_v4());
+
+	write_row(&manager, &db_name, true).await;
+
+	let stale_db = stale_manager.get(db_name.as_str(), WRITER).await.unwrap();
+	{
+		let mut conn = stale_db.conn().await.unwrap();
+		sqlx::query("INSERT INTO test (value) VALUES (1)")
+			.execute(&mut *conn)
+			.await
+			.unwrap();
+	}
+
+	// Let the stale writer's lease expire
+	tokio::time::sleep(Duration::from_secs(2)).await;
+
+	write_row(&manager, &db_name, false).await;
+
+	let err = stale_db.snapshot(false).await.unwrap_err();
+	assert!(
+		format!("{err:?}").contains("SqliteWriterLeaseLost"),
+		"expected lease to be lost, got {err:?}"
+	);
+	assert!(stale_db.lease_lost());
+
+	// Only the rows of the current writer were written
+	assert_eq!(2, read_count(&manager, &db_name).await);
+}
+
+/// Verifies evicting a writer releases its lease so another manager can acquire it without waiting for it
+/// to expire.
+#[tokio::test(flavor = "multi_thread")]
+async fn sqlite_writer_lease_released_on_evict() {
+	let pools = setup().await;
+	let manager = pools.sqlite_manager().clone();
+	let other_manager = SqlitePoolManager::new(Some(pools.fdb().unwrap()))
+		.await
+		.unwrap();
+
+	let db_name = format!("test_db_{}", Uuid::new_v4());
+
+	write_row(&manager, &db_name, true).await;
+	write_row(&other_manager, &db_name, false).await;
+	write_row(&manager, &db_name, false).await;
+
+	assert_eq!(3, read_count(&other_manager, &db_name).await);
+}
+
+async fn setup() -> Pools {
+	let mut root = rivet_config::config::Root::default();
+	root.server.as_mut().unwrap().foundationdb = Some(Default::default());
+	let config = rivet_config::Config::from_root(root);
+
+	Pools::test(config).await.unwrap()
+}
+
+/// Loads the database as a writer, inserts a row and writes it to FDB.
+async fn write_row(manager: &SqlitePoolManagerHandle, db_name: &str, create_table: bool) {
+	let db = manager.get(db_name, WRITER).await.unwrap();
+
+	{
+		let mut conn = db.conn().await.unwrap();
+
+		if create_table {
+			sqlx::query("CREATE TABLE test (value INT)")
+				.execute(&mut *conn)
+				.await
+				.unwrap();
+		}
+
+		sqlx::query("INSERT INTO test (value) VALUES (1)")
+			.execute(&mut *conn)
+			.await
+			.unwrap();
+	}
+
+	drop(db);
+	manager.evict(vec![db_name]).await.unwrap();
+}
+
+async fn read_count(manager: &SqlitePoolManagerHandle, db_name: &str) -> i64 {
+	let db = manager.get(db_name, SqliteConnType::Reader).await.unwrap();
+	let mut conn = db.conn().await.unwrap();
+
+	sqlx::query_scalar("SELECT COUNT(*) FROM test")
+		.fetch_one(&mut *conn)
+		.await
+		.unwrap()
+}
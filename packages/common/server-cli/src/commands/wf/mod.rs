@@ -33,6 +33,8 @@ pub enum SubCommand {
 	Silence { workflow_ids: Vec<Uuid> },
 	/// Sets the wake immediate property of a workflow to true.
 	Wake { workflow_ids: Vec<Uuid> },
+	/// Cancels a workflow, running its compensation handlers and cancelling its sub workflows.
+	Cancel { workflow_ids: Vec<Uuid> },
 	/// Lists the entire event history of a workflow.
 	History {
 		#[clap(index = 1)]
@@ -50,6 +52,19 @@ pub enum SubCommand {
 		#[clap(short = 't', action = clap::ArgAction::Count, long)]
 		print_ts: u8,
 	},
+	/// Lists the workflows with the largest event histories. Useful for finding workflows that should be
+	/// snapshotted.
+	HistorySizes {
+		tags: Vec<KvPair>,
+		/// Workflow name.
+		#[clap(long, short = 'n')]
+		name: Option<String>,
+		#[clap(long, short = 's')]
+		state: Option<WorkflowState>,
+		/// Max amount of workflows to print.
+		#[clap(long, default_value_t = 25)]
+		limit: usize,
+	},
 	Signal {
 		#[clap(subcommand)]
 		command: signal::SubCommand,
@@ -98,6 +113,7 @@ impl SubCommand {
 			}
 			Self::Silence { workflow_ids } => db.silence_workflows(workflow_ids).await,
 			Self::Wake { workflow_ids } => db.wake_workflows(workflow_ids).await,
+			Self::Cancel { workflow_ids } => db.cancel_workflows(workflow_ids).await,
 			Self::History {
 				workflow_id,
 				exclude_json,
@@ -110,6 +126,27 @@ impl SubCommand {
 					.await?;
 				util::wf::print_history(history, exclude_json, print_location, print_ts).await
 			}
+			Self::HistorySizes {
+				tags,
+				name,
+				state,
+				limit,
+			} => {
+				let workflows = db
+					.find_workflows(
+						&tags
+							.into_iter()
+							.map(|kv| (kv.key, kv.value))
+							.collect::<Vec<_>>(),
+						name.as_deref(),
+						state.map(Into::into),
+					)
+					.await?;
+				let sizes = db
+					.get_workflow_history_sizes(workflows.iter().map(|wf| wf.workflow_id).collect())
+					.await?;
+				util::wf::print_history_sizes(workflows, sizes, limit)
+			}
 			Self::Signal { command } => command.execute(db).await,
 		}
 	}
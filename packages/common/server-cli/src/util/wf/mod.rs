@@ -1,5 +1,7 @@
 use anyhow::*;
-use chirp_workflow::db::debug::{Event, EventData, HistoryData, WorkflowState};
+use chirp_workflow::db::debug::{
+	Event, EventData, HistoryData, HistorySize, WorkflowData, WorkflowState,
+};
 use chirp_workflow::history::event::SleepState;
 use chrono::{TimeZone, Utc};
 use rivet_term::console::{style, Style};
@@ -101,6 +103,25 @@ pub async fn print_workflows(
 	Ok(())
 }
 
+pub fn print_history_sizes(
+	workflows: Vec<WorkflowData>,
+	mut sizes: Vec<HistorySize>,
+	limit: usize,
+) -> Result<()> {
+	if sizes.is_empty() {
+		rivet_term::status::success("No workflows found", "");
+		return Ok(());
+	}
+
+	// Largest histories first
+	sizes.sort_by_key(|size| std::cmp::Reverse((size.events + size.forgotten_events, size.bytes)));
+	sizes.truncate(limit);
+
+	rivet_term::status::success("Workflows", sizes.len());
+
+	table::history_sizes(workflows, sizes)
+}
+
 pub async fn print_history(
 	history: Option<HistoryData>,
 	exclude_json: bool,
@@ -590,7 +611,7 @@ fn display_state(state: &WorkflowState) -> String {
 
 mod table {
 	use anyhow::*;
-	use chirp_workflow::db::debug::{WorkflowData, WorkflowState};
+	use chirp_workflow::db::debug::{HistorySize, WorkflowData, WorkflowState};
 	use tabled::Tabled;
 	use uuid::Uuid;
 
@@ -606,6 +627,15 @@ mod table {
 		pub tags: String,
 	}
 
+	#[derive(Tabled)]
+	struct HistorySizeTableRow {
+		pub workflow_id: Uuid,
+		pub workflow_name: String,
+		pub events: usize,
+		pub forgotten_events: usize,
+		pub bytes: usize,
+	}
+
 	pub fn workflows(workflows: Vec<WorkflowData>) -> Result<()> {
 		let mut rows = workflows
 			.iter()
@@ -625,4 +655,25 @@ mod table {
 
 		Ok(())
 	}
+
+	pub fn history_sizes(workflows: Vec<WorkflowData>, sizes: Vec<HistorySize>) -> Result<()> {
+		let rows = sizes
+			.into_iter()
+			.map(|size| HistorySizeTableRow {
+				workflow_name: workflows
+					.iter()
+					.find(|w| w.workflow_id == size.workflow_id)
+					.map(|w| w.workflow_name.clone())
+					.unwrap_or_default(),
+				workflow_id: size.workflow_id,
+				events: size.events,
+				forgotten_events: size.forgotten_events,
+				bytes: size.bytes,
+			})
+			.collect::<Vec<_>>();
+
+		rivet_term::format::table(rows);
+
+		Ok(())
+	}
 }
@@ -65,7 +65,7 @@ pub struct Backoff {
 	/// Base wait time in ms.
 	wait: usize,
 
-	/// Maximum randomness.
+	/// Maximum randomness. No jitter is added if 0.
 	randomness: usize,
 
 	/// Iteration of the backoff.
@@ -123,7 +123,7 @@ impl Backoff {
 
 		tokio::time::sleep_until(self.sleep_until).await;
 
-		let next_wait = self.current_duration() + rand::thread_rng().gen_range(0..self.randomness);
+		let next_wait = self.current_duration() + self.jitter();
 		self.sleep_until += Duration::from_millis(next_wait as u64);
 
 		self.i += 1;
@@ -139,8 +139,8 @@ impl Backoff {
 			return None;
 		}
 
-		let next_wait = self.wait * 2usize.pow(self.i.min(self.max_exponent) as u32)
-			+ rand::thread_rng().gen_range(0..self.randomness);
+		let next_wait =
+			self.wait * 2usize.pow(self.i.min(self.max_exponent) as u32) + self.jitter();
 		self.sleep_until += Duration::from_millis(next_wait as u64);
 
 		self.i += 1;
@@ -152,6 +152,15 @@ impl Backoff {
 		self.wait * 2usize.pow(self.i.min(self.max_exponent) as u32)
 	}
 
+	fn jitter(&self) -> usize {
+		// `gen_range` panics on an empty range
+		if self.randomness == 0 {
+			0
+		} else {
+			rand::thread_rng().gen_range(0..self.randomness)
+		}
+	}
+
 	pub fn default_infinite() -> Backoff {
 		Backoff::new(8, None, 1_000, 1_000)
 	}
@@ -190,6 +199,18 @@ mod tests {
 			}
 		}
 	}
+
+	#[test]
+	fn test_backoff_no_randomness() {
+		let mut backoff = super::Backoff::new(5, Some(8), 100, 0);
+		let first = backoff.step().unwrap();
+		let second = backoff.step().unwrap();
+
+		assert_eq!(
+			std::time::Duration::from_millis(200),
+			second.duration_since(first)
+		);
+	}
 }
 
 /// Slices a string without panicking on char boundaries. Defaults to the left side of the char if a slice
@@ -275,6 +275,7 @@ pub async fn create_deprecated(
 				Box::new(models::ActorsLifecycle {
 					kill_timeout: l.kill_timeout,
 					durable: Some(false),
+					idle_timeout: None,
 				})
 			}),
 			network: Some(Box::new(models::ActorsCreateActorNetworkRequest {
@@ -336,6 +337,7 @@ pub async fn create_deprecated(
 			build: Some(body.runtime.build),
 			build_tags: None,
 			tags: body.tags,
+			placement: None,
 		},
 		GlobalEndpointTypeQuery {
 			global,
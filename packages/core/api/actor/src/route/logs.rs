@@ -2,10 +2,15 @@ use api_helper::{
 	anchor::{WatchIndexQuery, WatchResponse},
 	ctx::Ctx,
 };
+use chirp_workflow::prelude::ApiCtx;
+use futures_util::{Stream, StreamExt};
+use http::response::Builder;
+use hyper::Body;
 use rivet_api::models;
 use rivet_operation::prelude::*;
-use serde::Deserialize;
-use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, time::Duration};
+use tracing::Instrument;
 
 use crate::{
 	assert,
@@ -48,32 +53,8 @@ pub async fn get_logs(
 		)
 		.await?;
 
-	// Parse actor IDs from the JSON string
-	let actor_ids: Vec<Uuid> = unwrap_with!(
-		serde_json::from_str(&query.actor_ids_json).ok(),
-		ACTOR_LOGS_INVALID_ACTOR_IDS
-	);
-
-	ensure_with!(!actor_ids.is_empty(), ACTOR_LOGS_NO_ACTOR_IDS);
-
-	// Filter to only valid actors for this game/env
-	let valid_actor_ids = assert::actor_for_env(&ctx, &actor_ids, game_id, env_id, None).await?;
-
-	// Exit early if no valid actors
-	ensure_with!(!valid_actor_ids.is_empty(), ACTOR_LOGS_NO_VALID_ACTOR_IDS);
-
-	// Use only the valid actor IDs from now on
-	let actor_ids = valid_actor_ids;
-
-	// Determine stream type(s)
-	let stream_types = match query.stream {
-		models::ActorsQueryLogStream::StdOut => vec![pegboard::types::LogsStreamType::StdOut],
-		models::ActorsQueryLogStream::StdErr => vec![pegboard::types::LogsStreamType::StdErr],
-		models::ActorsQueryLogStream::All => vec![
-			pegboard::types::LogsStreamType::StdOut,
-			pegboard::types::LogsStreamType::StdErr,
-		],
-	};
+	let actor_ids = valid_actor_ids(&ctx, &query.actor_ids_json, game_id, env_id).await?;
+	let stream_types = stream_types(query.stream);
 
 	// Timestamp to start the query at
 	let before_nts = util::timestamp::now() * 1_000_000;
@@ -202,6 +183,358 @@ pub async fn get_logs(
 	})
 }
 
+// MARK: GET /actors/logs/stream
+/// Number of past log lines read at a time when resuming from a cursor.
+const STREAM_BACKFILL_PAGE_COUNT: i64 = 1024;
+
+/// Max number of pages of past log lines sent when resuming from a cursor. Lines past this are skipped and a
+/// `truncated` event is sent instead.
+const STREAM_BACKFILL_MAX_PAGES: usize = 64;
+
+/// Interval at which comments are sent to keep the connection alive and detect disconnects.
+const STREAM_KEEPALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// How far before subscribing a backfilled line's timestamp can be and still be received from the live
+/// subscription. Accounts for clock skew between the clients and the API.
+const STREAM_LIVE_OVERLAP: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Deserialize)]
+pub struct StreamActorLogsQuery {
+	#[serde(flatten)]
+	pub global: GlobalQuery,
+	pub stream: models::ActorsQueryLogStream,
+	pub actor_ids_json: String,
+	#[serde(default)]
+	pub search_text: Option<String>,
+	#[serde(default)]
+	pub search_case_sensitive: Option<bool>,
+	#[serde(default)]
+	pub search_enable_regex: Option<bool>,
+	/// Id of the last received event, see `LogCursor`. Lines after the cursor are sent before new lines. If
+	/// not provided, only new lines are sent.
+	#[serde(default)]
+	pub cursor: Option<String>,
+}
+
+/// Data of a `log` server-sent event.
+#[derive(Serialize)]
+struct LogStreamEvent {
+	actor_id: String,
+	stream: i32,
+	timestamp: String,
+	/// Base64 encoded.
+	line: String,
+}
+
+/// Position in the log stream, used as the id of `log` events.
+///
+/// Formatted as `{ts}:{count}`, where `ts` is the timestamp of the last sent line in nanoseconds and `count`
+/// is the number of lines with that timestamp that were sent. A cursor of only `{ts}` resumes after all lines
+/// with that timestamp. The cursor never moves backwards.
+///
+/// Resuming is best-effort: live lines from different actors can arrive out of timestamp order and past
+/// lines are read from ClickHouse, which lags behind the live subscription. Lines close to the cursor may
+/// be repeated or skipped when resuming.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct LogCursor {
+	ts: i64,
+	/// None if all lines with `ts` were sent.
+	count: Option<usize>,
+}
+
+impl LogCursor {
+	fn parse(s: &str) -> GlobalResult<Self> {
+		let (ts, count) = match s.split_once(':') {
+			Some((ts, count)) => (ts, Some(count)),
+			None => (s, None),
+		};
+
+		let ts = unwrap_with!(ts.parse().ok(), ACTOR_LOGS_INVALID_CURSOR);
+		let count = match count {
+			Some(count) => Some(unwrap_with!(count.parse().ok(), ACTOR_LOGS_INVALID_CURSOR)),
+			None => None,
+		};
+
+		Ok(LogCursor { ts, count })
+	}
+
+	/// Moves the cursor past the given line. Lines older than the cursor don't move it.
+	fn advance(&mut self, ts: i64) {
+		if ts == self.ts {
+			self.count = self.count.map(|count| count + 1);
+		} else if ts > self.ts {
+			self.ts = ts;
+			self.count = Some(1);
+		}
+	}
+}
+
+impl std::fmt::Display for LogCursor {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self.count {
+			Some(count) => write!(f, "{}:{count}", self.ts),
+			None => write!(f, "{}", self.ts),
+		}
+	}
+}
+
+/// Tracks the lines sent from the backfill that can also be received from the live subscription, since it
+/// is subscribed to before the backfill is read.
+#[derive(Debug)]
+struct BackfillSent {
+	/// Backfilled lines older than this were shipped before subscribing and are not received live.
+	since_ts: i64,
+	/// Number of times each line was sent, identical lines can be logged more than once.
+	lines: HashMap<(Uuid, u8, i64, Vec<u8>), usize>,
+}
+
+impl BackfillSent {
+	fn new(since_ts: i64) -> Self {
+		BackfillSent {
+			since_ts,
+			lines: HashMap::new(),
+		}
+	}
+
+	fn push(&mut self, entry: &pegboard::ops::actor::log::read::LogEntry) {
+		if entry.ts >= self.since_ts {
+			*self.lines.entry(Self::key(entry)).or_default() += 1;
+		}
+	}
+
+	/// Returns true if the live line was already sent from the backfill.
+	fn take(&mut self, entry: &pegboard::ops::actor::log::read::LogEntry) -> bool {
+		if self.lines.is_empty() {
+			return false;
+		}
+
+		let key = Self::key(entry);
+		let Some(count) = self.lines.get_mut(&key) else {
+			return false;
+		};
+
+		*count -= 1;
+		if *count == 0 {
+			self.lines.remove(&key);
+		}
+
+		true
+	}
+
+	fn key(entry: &pegboard::ops::actor::log::read::LogEntry) -> (Uuid, u8, i64, Vec<u8>) {
+		(
+			entry.actor_id,
+			entry.stream_type,
+			entry.ts,
+			entry.message.clone(),
+		)
+	}
+}
+
+/// Streams log lines as server-sent events as they are shipped from the actors.
+#[tracing::instrument(skip_all)]
+pub async fn stream_logs(
+	ctx: Ctx<Auth>,
+	response: &mut Builder,
+	_watch_index: WatchIndexQuery,
+	query: StreamActorLogsQuery,
+) -> GlobalResult<Body> {
+	let CheckOutput { game_id, env_id } = ctx
+		.auth()
+		.check(
+			ctx.op_ctx(),
+			CheckOpts {
+				query: &query.global,
+				allow_service_token: false,
+				opt_auth: false,
+			},
+		)
+		.await?;
+
+	let actor_ids = valid_actor_ids(&ctx, &query.actor_ids_json, game_id, env_id).await?;
+	let stream_types = stream_types(query.stream);
+	let cursor = query.cursor.as_deref().map(LogCursor::parse).transpose()?;
+
+	let filter = pegboard::ops::actor::log::stream::Filter::new(
+		stream_types.clone(),
+		query.search_text.clone(),
+		query.search_case_sensitive,
+		query.search_enable_regex,
+	)?;
+
+	// Subscribe before reading past lines so no lines are missed in between
+	let subscribe_ts = util::timestamp::now() * 1_000_000;
+	let nats = ctx.op_ctx().conn().nats().await?;
+	let live = pegboard::ops::actor::log::stream::subscribe(&nats, &actor_ids).await?;
+
+	let backfill_input = pegboard::ops::actor::log::read::Input {
+		actor_ids,
+		stream_types,
+		count: STREAM_BACKFILL_PAGE_COUNT,
+		order_by: pegboard::ops::actor::log::read::Order::Asc,
+		query: pegboard::ops::actor::log::read::Query::All,
+		search_text: query.search_text,
+		search_case_sensitive: query.search_case_sensitive,
+		search_enable_regex: query.search_enable_regex,
+	};
+
+	let (tx, body) = Body::channel();
+	tokio::task::Builder::new()
+		.name("api_core_actor::stream_logs")
+		.spawn(
+			send_log_events(
+				(*ctx).clone(),
+				tx,
+				cursor,
+				backfill_input,
+				BackfillSent::new(subscribe_ts - STREAM_LIVE_OVERLAP.as_nanos() as i64),
+				live,
+				filter,
+			)
+			.in_current_span(),
+		)?;
+
+	if let Some(headers) = response.headers_mut() {
+		headers.insert(
+			http::header::CONTENT_TYPE,
+			http::HeaderValue::from_static("text/event-stream"),
+		);
+		headers.insert(
+			http::header::CACHE_CONTROL,
+			http::HeaderValue::from_static("no-cache"),
+		);
+	}
+
+	Ok(body)
+}
+
+/// Writes log lines to the response body until the client disconnects.
+async fn send_log_events(
+	ctx: ApiCtx,
+	mut tx: hyper::body::Sender,
+	mut cursor: Option<LogCursor>,
+	mut backfill_input: pegboard::ops::actor::log::read::Input,
+	mut backfill_sent: BackfillSent,
+	live: impl Stream<Item = pegboard::ops::actor::log::read::LogEntry>,
+	filter: pegboard::ops::actor::log::stream::Filter,
+) {
+	// Page through lines after the cursor until caught up with the live subscription
+	if let Some(cursor) = &mut cursor {
+		let mut pages = 0;
+		loop {
+			if pages == STREAM_BACKFILL_MAX_PAGES {
+				tracing::warn!(?cursor, "actor log backfill truncated");
+
+				if tx
+					.send_data("event: truncated\ndata: {}\n\n".into())
+					.await
+					.is_err()
+				{
+					return;
+				}
+
+				break;
+			}
+			pages += 1;
+
+			// Read from the cursor's timestamp to include lines with the same timestamp that were not sent
+			// yet, the ones that were are skipped below
+			let (after_nts, skip) = match cursor.count {
+				Some(count) => (cursor.ts - 1, count),
+				None => (cursor.ts, 0),
+			};
+			backfill_input.query = pegboard::ops::actor::log::read::Query::AfterNts(after_nts);
+			// Always make progress even if more lines than a page have the same timestamp
+			backfill_input.count = STREAM_BACKFILL_PAGE_COUNT + skip as i64;
+
+			let entries = match ctx.op(backfill_input.clone()).await {
+				Ok(res) => res.entries,
+				Err(err) => {
+					tracing::error!(?err, ?cursor, "failed to read actor log backfill");
+					return;
+				}
+			};
+			let caught_up = (entries.len() as i64) < backfill_input.count;
+
+			let skip_ts = cursor.ts;
+			let mut skipped = 0;
+			for entry in entries {
+				if entry.ts == skip_ts && skipped < skip {
+					skipped += 1;
+					continue;
+				}
+
+				cursor.advance(entry.ts);
+				backfill_sent.push(&entry);
+
+				if !send_log_event(&mut tx, &entry, cursor).await {
+					return;
+				}
+			}
+
+			if caught_up {
+				break;
+			}
+		}
+	}
+
+	let mut live = std::pin::pin!(live);
+	let mut keepalive = tokio::time::interval(STREAM_KEEPALIVE_INTERVAL);
+	loop {
+		tokio::select! {
+			entry = live.next() => {
+				let Some(entry) = entry else {
+					tracing::warn!("actor log subscription closed");
+					break;
+				};
+
+				if backfill_sent.take(&entry) || !filter.matches(&entry) {
+					continue;
+				}
+
+				let cursor = cursor.get_or_insert(LogCursor { ts: entry.ts, count: Some(0) });
+				cursor.advance(entry.ts);
+
+				if !send_log_event(&mut tx, &entry, cursor).await {
+					break;
+				}
+			}
+			_ = keepalive.tick() => {
+				if tx.send_data(": keepalive\n\n".into()).await.is_err() {
+					break;
+				}
+			}
+		}
+	}
+}
+
+/// Returns false if the client disconnected.
+async fn send_log_event(
+	tx: &mut hyper::body::Sender,
+	entry: &pegboard::ops::actor::log::read::LogEntry,
+	cursor: &LogCursor,
+) -> bool {
+	let event = util::timestamp::to_string(entry.ts / 1_000_000).and_then(|timestamp| {
+		Ok(serde_json::to_string(&LogStreamEvent {
+			actor_id: entry.actor_id.to_string(),
+			stream: entry.stream_type as i32,
+			timestamp,
+			line: base64::encode(&entry.message),
+		})?)
+	});
+	let data = match event {
+		Ok(data) => data,
+		Err(err) => {
+			tracing::error!(?err, "failed to serialize log event");
+			return true;
+		}
+	};
+
+	tx.send_data(format!("id: {cursor}\nevent: log\ndata: {data}\n\n").into())
+		.await
+		.is_ok()
+}
+
 pub async fn get_logs_deprecated(
 	ctx: Ctx<Auth>,
 	game_id: Uuid,
@@ -235,3 +568,134 @@ pub async fn get_logs_deprecated(
 		watch: logs_res.watch,
 	})
 }
+
+/// Parses the actor IDs of a logs query and filters them to actors in the given environment.
+async fn valid_actor_ids(
+	ctx: &Ctx<Auth>,
+	actor_ids_json: &str,
+	game_id: Uuid,
+	env_id: Uuid,
+) -> GlobalResult<Vec<Uuid>> {
+	// Parse actor IDs from the JSON string
+	let actor_ids: Vec<Uuid> = unwrap_with!(
+		serde_json::from_str(actor_ids_json).ok(),
+		ACTOR_LOGS_INVALID_ACTOR_IDS
+	);
+
+	ensure_with!(!actor_ids.is_empty(), ACTOR_LOGS_NO_ACTOR_IDS);
+
+	// Filter to only valid actors for this game/env
+	let valid_actor_ids = assert::actor_for_env(ctx, &actor_ids, game_id, env_id, None).await?;
+
+	// Exit early if no valid actors
+	ensure_with!(!valid_actor_ids.is_empty(), ACTOR_LOGS_NO_VALID_ACTOR_IDS);
+
+	Ok(valid_actor_ids)
+}
+
+fn stream_types(stream: models::ActorsQueryLogStream) -> Vec<pegboard::types::LogsStreamType> {
+	match stream {
+		models::ActorsQueryLogStream::StdOut => vec![pegboard::types::LogsStreamType::StdOut],
+		models::ActorsQueryLogStream::StdErr => vec![pegboard::types::LogsStreamType::StdErr],
+		models::ActorsQueryLogStream::All => vec![
+			pegboard::types::LogsStreamType::StdOut,
+			pegboard::types::LogsStreamType::StdErr,
+		],
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use pegboard::ops::actor::log::read::LogEntry;
+	use rivet_operation::prelude::Uuid;
+
+	use super::{BackfillSent, LogCursor};
+
+	fn entry(actor_id: Uuid, ts: i64, message: &str) -> LogEntry {
+		LogEntry {
+			ts,
+			message: message.as_bytes().to_vec(),
+			stream_type: 0,
+			actor_id,
+		}
+	}
+
+	#[test]
+	fn log_cursor_roundtrip() {
+		for s in ["123", "123:4"] {
+			assert_eq!(s, LogCursor::parse(s).unwrap().to_string());
+		}
+
+		for s in ["", "foo", "123:", "123:foo", "123:-1"] {
+			assert!(LogCursor::parse(s).is_err(), "{s}");
+		}
+	}
+
+	#[test]
+	fn log_cursor_counts_equal_timestamps() {
+		let mut cursor = LogCursor::parse("100:1").unwrap();
+
+		cursor.advance(100);
+		assert_eq!("100:2", cursor.to_string());
+
+		cursor.advance(101);
+		assert_eq!("101:1", cursor.to_string());
+
+		// All lines with the timestamp of the cursor were already sent
+		let mut cursor = LogCursor::parse("100").unwrap();
+		cursor.advance(100);
+		assert_eq!("100", cursor.to_string());
+	}
+
+	#[test]
+	fn log_cursor_never_moves_backwards() {
+		let mut cursor = LogCursor::parse("100:2").unwrap();
+
+		cursor.advance(99);
+		assert_eq!("100:2", cursor.to_string());
+
+		cursor.advance(100);
+		assert_eq!("100:3", cursor.to_string());
+	}
+
+	#[test]
+	fn backfill_sent_skips_backfilled_live_lines() {
+		let actor_id = Uuid::new_v4();
+		let mut sent = BackfillSent::new(100);
+
+		// Nothing was backfilled
+		assert!(!sent.take(&entry(actor_id, 100, "a")));
+
+		sent.push(&entry(actor_id, 100, "a"));
+		sent.push(&entry(actor_id, 101, "b"));
+		sent.push(&entry(actor_id, 101, "b"));
+
+		assert!(sent.take(&entry(actor_id, 100, "a")));
+
+		// Identical lines are each received once
+		assert!(sent.take(&entry(actor_id, 101, "b")));
+		assert!(sent.take(&entry(actor_id, 101, "b")));
+		assert!(!sent.take(&entry(actor_id, 101, "b")));
+
+		// Lines that were not backfilled
+		assert!(!sent.take(&entry(actor_id, 101, "c")));
+		assert!(!sent.take(&entry(Uuid::new_v4(), 101, "b")));
+		assert!(!sent.take(&entry(actor_id, 102, "d")));
+	}
+
+	#[test]
+	fn backfill_sent_keeps_older_live_lines() {
+		let actor_id = Uuid::new_v4();
+		let mut sent = BackfillSent::new(100);
+
+		sent.push(&entry(actor_id, 99, "a"));
+		sent.push(&entry(actor_id, 105, "b"));
+
+		// Lines from another actor that arrive late are still sent
+		assert!(!sent.take(&entry(Uuid::new_v4(), 101, "c")));
+
+		// Lines shipped before subscribing are not tracked
+		assert!(!sent.take(&entry(actor_id, 99, "a")));
+		assert!(sent.take(&entry(actor_id, 105, "b")));
+	}
+}
@@ -124,6 +124,15 @@ define_router! {
 			),
 		},
 
+		"actors" / "logs" / "stream": {
+			GET: logs::stream_logs(
+				query: logs::StreamActorLogsQuery,
+				with_response: true,
+				returns_body: true,
+				opt_auth: true,
+			),
+		},
+
 
 		"builds": {
 			GET: builds::list(
@@ -205,6 +214,28 @@ define_router! {
 			),
 		},
 
+		"middleware": {
+			GET: routes::get_middleware(
+				query: GlobalQuery,
+				opt_auth: true,
+				rate_limit: {
+					buckets: [
+						{ count: 60_000, bucket: duration::minutes(1) },
+					],
+				},
+			),
+			PUT: routes::update_middleware(
+				query: GlobalQuery,
+				body: models::RoutesMiddlewareUpdateMiddlewareBody,
+				opt_auth: true,
+				rate_limit: {
+					buckets: [
+						{ count: 10_000, bucket: duration::minutes(1) },
+					],
+				},
+			),
+		},
+
 		"routes" / String: {
 			PUT: routes::update(
 				query: GlobalQuery,
@@ -1,7 +1,7 @@
 use api_helper::{anchor::WatchIndexQuery, ctx::Ctx};
 use rivet_api::models;
 use rivet_operation::prelude::*;
-use route::{ops::delete, ops::get, ops::list_for_env, ops::upsert};
+use route::{ops::delete, ops::get, ops::list_for_env, ops::middleware, ops::upsert};
 use serde::Deserialize;
 use serde_json::json;
 use util::timestamp;
@@ -51,15 +51,6 @@ pub async fn list(
 		.routes
 		.iter()
 		.map(|route| {
-			// Get the route target (which will be an Actors target)
-			let target = match &route.target {
-				route::types::RouteTarget::Actors { selector_tags } => models::RoutesRouteTarget {
-					actors: Some(Box::new(models::RoutesRouteTargetActors {
-						selector_tags: selector_tags.clone(),
-					})),
-				},
-			};
-
 			GlobalResult::Ok(models::RoutesRoute {
 				id: route.name_id.clone(),
 				created_at: timestamp::to_string(route.create_ts)?,
@@ -68,7 +59,10 @@ pub async fn list(
 				path: route.path.clone(),
 				route_subpaths: route.route_subpaths,
 				strip_prefix: route.strip_prefix,
-				target: Box::new(target),
+				request_match: Box::new(request_match_to_api(&route.request_match)),
+				target: Box::new(target_to_api(&route.target)),
+				load_balancing: Box::new(load_balancing_to_api(&route.load_balancing)),
+				middleware: Box::new(middleware_to_api(&route.middleware)),
 			})
 		})
 		.collect::<Result<Vec<_>, _>>()?;
@@ -97,19 +91,18 @@ pub async fn update(
 		)
 		.await?;
 
-	// Extract actors selector tags from request
-	let actors_selector_tags = body
-		.target
-		.actors
-		.as_ref()
-		.ok_or_else(|| {
-			err_code!(
-				ROUTE_INVALID_TARGET,
-				msg = "actors target configuration is required"
-			)
-		})?
-		.selector_tags
-		.clone();
+	let target = target_from_api(*body.target)?;
+	let request_match = body
+		.request_match
+		.map(|request_match| request_match_from_api(*request_match));
+	let load_balancing = body
+		.load_balancing
+		.map(|load_balancing| load_balancing_from_api(*load_balancing))
+		.transpose()?;
+	let middleware = body
+		.middleware
+		.map(|middleware| middleware_from_api(*middleware))
+		.transpose()?;
 
 	// Call the upsert operation
 	let _res = ctx
@@ -120,7 +113,10 @@ pub async fn update(
 			path: body.path.clone(),
 			route_subpaths: body.route_subpaths,
 			strip_prefix: body.strip_prefix,
-			actors_selector_tags,
+			target,
+			request_match,
+			load_balancing,
+			middleware,
 		})
 		.await?;
 
@@ -159,3 +155,320 @@ pub async fn delete(
 
 	Ok(json!({}))
 }
+
+// MARK: GET /middleware
+pub async fn get_middleware(
+	ctx: Ctx<Auth>,
+	_watch_index: WatchIndexQuery,
+	query: GlobalQuery,
+) -> GlobalResult<models::RoutesMiddlewareGetMiddlewareResponse> {
+	let CheckOutput {
+		env_id: namespace_id,
+		..
+	} = ctx.auth()
+		.check(
+			ctx.op_ctx(),
+			CheckOpts {
+				query: &query,
+				allow_service_token: true,
+				opt_auth: false,
+			},
+		)
+		.await?;
+
+	let middleware_res = ctx.op(middleware::get::Input { namespace_id }).await?;
+
+	Ok(models::RoutesMiddlewareGetMiddlewareResponse {
+		middleware: Box::new(middleware_to_api(&middleware_res.middleware)),
+	})
+}
+
+// MARK: PUT /middleware
+pub async fn update_middleware(
+	ctx: Ctx<Auth>,
+	body: models::RoutesMiddlewareUpdateMiddlewareBody,
+	query: GlobalQuery,
+) -> GlobalResult<serde_json::Value> {
+	let CheckOutput {
+		env_id: namespace_id,
+		..
+	} = ctx.auth()
+		.check(
+			ctx.op_ctx(),
+			CheckOpts {
+				query: &query,
+				allow_service_token: true,
+				opt_auth: false,
+			},
+		)
+		.await?;
+
+	ctx.op(middleware::upsert::Input {
+		namespace_id,
+		middleware: middleware_from_api(*body.middleware)?,
+	})
+	.await?;
+
+	Ok(json!({}))
+}
+
+fn target_to_api(target: &route::types::RouteTarget) -> models::RoutesRouteTarget {
+	let mut res = models::RoutesRouteTarget::new();
+
+	match target {
+		route::types::RouteTarget::Actors { selector_tags } => {
+			res.actors = Some(Box::new(models::RoutesRouteTargetActors {
+				selector_tags: selector_tags.clone(),
+			}));
+		}
+		route::types::RouteTarget::Split {
+			selector_tags,
+			canary_selector_tags,
+			canary_weight,
+		} => {
+			res.split = Some(Box::new(models::RoutesRouteTargetSplit {
+				selector_tags: selector_tags.clone(),
+				canary_selector_tags: canary_selector_tags.clone(),
+				canary_weight: (*canary_weight).into(),
+			}));
+		}
+		route::types::RouteTarget::Redirect {
+			location,
+			status,
+			preserve_path,
+		} => {
+			res.redirect = Some(Box::new(models::RoutesRouteTargetRedirect {
+				location: location.clone(),
+				status: (*status).into(),
+				preserve_path: *preserve_path,
+			}));
+		}
+		route::types::RouteTarget::Response {
+			status,
+			content_type,
+			body,
+		} => {
+			res.response = Some(Box::new(models::RoutesRouteTargetResponse {
+				status: (*status).into(),
+				content_type: content_type.clone(),
+				body: body.clone(),
+			}));
+		}
+	}
+
+	res
+}
+
+fn target_from_api(target: models::RoutesRouteTarget) -> GlobalResult<route::types::RouteTarget> {
+	let target = match (
+		target.actors,
+		target.split,
+		target.redirect,
+		target.response,
+	) {
+		(Some(actors), None, None, None) => route::types::RouteTarget::Actors {
+			selector_tags: actors.selector_tags,
+		},
+		(None, Some(split), None, None) => route::types::RouteTarget::Split {
+			selector_tags: split.selector_tags,
+			canary_selector_tags: split.canary_selector_tags,
+			canary_weight: split.canary_weight.try_into().map_err(|_| {
+				err_code!(
+					ROUTE_INVALID_TARGET,
+					msg = "canary_weight must be between 0 and 100"
+				)
+			})?,
+		},
+		(None, None, Some(redirect), None) => route::types::RouteTarget::Redirect {
+			location: redirect.location,
+			status: status_from_api(redirect.status)?,
+			preserve_path: redirect.preserve_path,
+		},
+		(None, None, None, Some(response)) => route::types::RouteTarget::Response {
+			status: status_from_api(response.status)?,
+			content_type: response.content_type,
+			body: response.body,
+		},
+		_ => {
+			bail_with!(
+				ROUTE_INVALID_TARGET,
+				msg = "exactly one of actors, split, redirect or response is required"
+			);
+		}
+	};
+
+	Ok(target)
+}
+
+fn status_from_api(status: i32) -> GlobalResult<u16> {
+	// The range is validated in the route service
+	status
+		.try_into()
+		.map_err(|_| err_code!(ROUTE_INVALID_TARGET, msg = "invalid status"))
+}
+
+fn request_match_to_api(request_match: &route::types::RouteMatch) -> models::RoutesRouteMatch {
+	models::RoutesRouteMatch {
+		methods: Some(request_match.methods.clone()),
+		headers: Some(
+			request_match
+				.headers
+				.iter()
+				.map(|header| models::RoutesRouteHeaderMatch {
+					name: header.name.clone(),
+					value: header.value.clone(),
+				})
+				.collect(),
+		),
+	}
+}
+
+fn request_match_from_api(request_match: models::RoutesRouteMatch) -> route::types::RouteMatch {
+	route::types::RouteMatch {
+		methods: request_match
+			.methods
+			.unwrap_or_default()
+			.into_iter()
+			.map(|method| method.to_uppercase())
+			.collect(),
+		headers: request_match
+			.headers
+			.unwrap_or_default()
+			.into_iter()
+			.map(|header| route::types::HeaderMatch {
+				name: header.name.to_lowercase(),
+				value: header.value,
+			})
+			.collect(),
+	}
+}
+
+fn load_balancing_to_api(
+	load_balancing: &route::types::LoadBalancing,
+) -> models::RoutesLoadBalancing {
+	use route::types::{HashKey, LoadBalancing};
+
+	let (strategy, hash_key) = match load_balancing {
+		LoadBalancing::Random => (models::RoutesLoadBalancingStrategy::Random, None),
+		LoadBalancing::RoundRobin => (models::RoutesLoadBalancingStrategy::RoundRobin, None),
+		LoadBalancing::LeastInFlight => (models::RoutesLoadBalancingStrategy::LeastInFlight, None),
+		LoadBalancing::ConsistentHash { key } => {
+			let mut hash_key = models::RoutesHashKey::new();
+			match key {
+				HashKey::Header { name } => hash_key.header = Some(name.clone()),
+				HashKey::Cookie { name } => hash_key.cookie = Some(name.clone()),
+				HashKey::Query { name } => hash_key.query = Some(name.clone()),
+			}
+
+			(
+				models::RoutesLoadBalancingStrategy::ConsistentHash,
+				Some(Box::new(hash_key)),
+			)
+		}
+	};
+
+	models::RoutesLoadBalancing { strategy, hash_key }
+}
+
+fn load_balancing_from_api(
+	load_balancing: models::RoutesLoadBalancing,
+) -> GlobalResult<route::types::LoadBalancing> {
+	use route::types::{HashKey, LoadBalancing};
+
+	let load_balancing = match load_balancing.strategy {
+		models::RoutesLoadBalancingStrategy::Random => LoadBalancing::Random,
+		models::RoutesLoadBalancingStrategy::RoundRobin => LoadBalancing::RoundRobin,
+		models::RoutesLoadBalancingStrategy::LeastInFlight => LoadBalancing::LeastInFlight,
+		models::RoutesLoadBalancingStrategy::ConsistentHash => {
+			let hash_key = load_balancing.hash_key.unwrap_or_default();
+			let key = match (hash_key.header, hash_key.cookie, hash_key.query) {
+				(Some(name), None, None) => HashKey::Header { name },
+				(None, Some(name), None) => HashKey::Cookie { name },
+				(None, None, Some(name)) => HashKey::Query { name },
+				_ => {
+					bail_with!(
+						ROUTE_INVALID_LOAD_BALANCING,
+						msg = "consistent_hash requires exactly one of header, cookie or query in hash_key"
+					);
+				}
+			};
+
+			LoadBalancing::ConsistentHash { key }
+		}
+	};
+
+	Ok(load_balancing)
+}
+
+fn middleware_to_api(
+	middleware: &route::types::MiddlewarePolicy,
+) -> models::RoutesMiddlewarePolicy {
+	models::RoutesMiddlewarePolicy {
+		rate_limit: middleware.rate_limit.as_ref().map(|rate_limit| {
+			Box::new(models::RoutesRateLimitPolicy {
+				requests: rate_limit.requests.try_into().unwrap_or(i32::MAX),
+				period: rate_limit.period.try_into().unwrap_or(i32::MAX),
+				distributed: Some(rate_limit.distributed),
+			})
+		}),
+		max_in_flight: middleware
+			.max_in_flight
+			.map(|x| x.try_into().unwrap_or(i32::MAX)),
+		retry: middleware.retry.as_ref().map(|retry| {
+			Box::new(models::RoutesRetryPolicy {
+				max_attempts: retry.max_attempts.try_into().unwrap_or(i32::MAX),
+				initial_interval: retry.initial_interval.try_into().unwrap_or(i32::MAX),
+			})
+		}),
+		request_timeout: middleware
+			.request_timeout
+			.map(|x| x.try_into().unwrap_or(i32::MAX)),
+	}
+}
+
+fn middleware_from_api(
+	middleware: models::RoutesMiddlewarePolicy,
+) -> GlobalResult<route::types::MiddlewarePolicy> {
+	// Negative values are rejected here, the rest of the validation happens in the route service
+	Ok(route::types::MiddlewarePolicy {
+		rate_limit: middleware
+			.rate_limit
+			.map(|rate_limit| {
+				GlobalResult::Ok(route::types::RateLimitPolicy {
+					requests: non_negative(rate_limit.requests, "rate_limit.requests")?,
+					period: non_negative(rate_limit.period, "rate_limit.period")?,
+					distributed: rate_limit.distributed.unwrap_or_default(),
+				})
+			})
+			.transpose()?,
+		max_in_flight: middleware
+			.max_in_flight
+			.map(|x| non_negative(x, "max_in_flight"))
+			.transpose()?,
+		retry: middleware
+			.retry
+			.map(|retry| {
+				GlobalResult::Ok(route::types::RetryPolicy {
+					max_attempts: non_negative(retry.max_attempts, "retry.max_attempts")?,
+					initial_interval: non_negative(
+						retry.initial_interval,
+						"retry.initial_interval",
+					)?,
+				})
+			})
+			.transpose()?,
+		request_timeout: middleware
+			.request_timeout
+			.map(|x| non_negative(x, "request_timeout"))
+			.transpose()?,
+	})
+}
+
+fn non_negative<T: TryFrom<i32>>(value: i32, field: &str) -> GlobalResult<T> {
+	T::try_from(value).map_err(|_| {
+		err_code!(
+			ROUTE_INVALID_MIDDLEWARE,
+			msg = format!("{field} must not be negative")
+		)
+	})
+}
@@ -261,3 +261,196 @@ async fn list_builds_with_tags() -> GlobalResult<()> {
 
 	Ok(())
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn stream_logs() -> GlobalResult<()> {
+	let ctx = Ctx::init().await?;
+
+	let ctx_config = ctx.config(ctx.service_token.clone())?;
+
+	let res = servers_api::servers_create(
+		&ctx_config,
+		&ctx.game_id_str,
+		&ctx.env_id_str,
+		models::ServersCreateServerRequest {
+			datacenter: ctx.datacenter_id,
+			tags: None,
+			runtime: Box::new(models::ServersCreateServerRuntimeRequest {
+				build: ctx.image_id,
+				arguments: None,
+				environment: Some(HashMap::new()),
+			}),
+			lifecycle: Some(Box::new(models::ServersLifecycle {
+				kill_timeout: Some(0),
+			})),
+			network: Box::new(models::ServersCreateServerNetworkRequest {
+				mode: Some(models::ServersNetworkMode::Bridge),
+				ports: HashMap::new(),
+			}),
+			resources: Box::new(models::ServersResources {
+				cpu: 100,
+				memory: 200,
+			}),
+		},
+	)
+	.await?;
+
+	let (game_res, ns_res) = tokio::try_join!(
+		op!([ctx] game_get {
+			game_ids: vec![ctx.game_id.into()],
+		}),
+		op!([ctx] game_namespace_get {
+			namespace_ids: vec![ctx.env_id.into()],
+		}),
+	)?;
+	let project = unwrap!(game_res.games.first()).name_id.clone();
+	let environment = unwrap!(ns_res.namespaces.first()).name_id.clone();
+	let actor_id = res.server.id;
+	let actor_ids_json = serde_json::to_string(&vec![actor_id])?;
+
+	// Service tokens are not allowed to read logs
+	let token_res = op!([ctx] cloud_game_token_create {
+		game_id: Some(ctx.game_id.into()),
+	})
+	.await?;
+	let cloud_token = token_res.token;
+
+	let stream = |cursor: String| {
+		ctx_config
+			.client
+			.get(format!("{}/actors/logs/stream", ctx_config.base_path))
+			.bearer_auth(&cloud_token)
+			.query(&[
+				("project", project.as_str()),
+				("environment", environment.as_str()),
+				("stream", "all"),
+				("actor_ids_json", actor_ids_json.as_str()),
+				("cursor", cursor.as_str()),
+			])
+			.send()
+	};
+
+	let res = stream("foo".to_string()).await?;
+	assert_eq!(reqwest::StatusCode::BAD_REQUEST, res.status());
+
+	// Past lines, as shipped to ClickHouse
+	let now_ts = util::timestamp::now() * 1_000_000;
+	for (ts, message) in [
+		(now_ts - 2_000_000, "past a"),
+		(now_ts - 1_000_000, "past b"),
+	] {
+		ctx.op_ctx()
+			.clickhouse()
+			.await?
+			.query(indoc!(
+				"
+				INSERT INTO db_pegboard_actor_log.actor_logs2 (namespace, actor_id, ts, stream_type, message)
+				VALUES (?, ?, fromUnixTimestamp64Nano(?), ?, ?)
+				"
+			))
+			.bind("test")
+			.bind(actor_id.to_string())
+			.bind(ts)
+			.bind(pegboard::types::LogsStreamType::StdOut as u8)
+			.bind(message)
+			.execute()
+			.await?;
+	}
+
+	// Resuming after the first past line sends the rest before new lines
+	let mut res = stream(format!("{}:1", now_ts - 2_000_000)).await?;
+	assert!(res.status().is_success(), "{}", res.status());
+	assert_eq!(
+		Some("text/event-stream"),
+		res.headers()
+			.get(reqwest::header::CONTENT_TYPE)
+			.and_then(|x| x.to_str().ok())
+	);
+
+	// The stream is subscribed to before the response is returned
+	let nats = ctx.op_ctx().conn().nats().await?;
+	nats.publish(
+		pegboard::ops::actor::log::stream::subject(actor_id),
+		serde_json::to_vec(&serde_json::json!({
+			"actor_id": actor_id,
+			"stream_type": pegboard::types::LogsStreamType::StdErr as u8,
+			"ts": util::timestamp::now() * 1_000_000,
+			"message": "live",
+		}))?
+		.into(),
+	)
+	.await?;
+
+	let events = read_log_events(&mut res, 2).await?;
+	let lines = events
+		.iter()
+		.map(|(_, data)| {
+			Ok((
+				unwrap!(data["stream"].as_i64()),
+				String::from_utf8(base64::decode(unwrap!(data["line"].as_str()))?)?,
+			))
+		})
+		.collect::<GlobalResult<Vec<_>>>()?;
+	assert_eq!(
+		vec![(0, "past b".to_string()), (1, "live".to_string())],
+		lines
+	);
+	assert_eq!(format!("{}:1", now_ts - 1_000_000), events[0].0);
+
+	// Resuming from the last event id does not repeat past lines
+	let mut res = stream(events[0].0.clone()).await?;
+	assert!(res.status().is_success(), "{}", res.status());
+	assert!(
+		tokio::time::timeout(
+			std::time::Duration::from_secs(2),
+			read_log_events(&mut res, 1)
+		)
+		.await
+		.is_err(),
+		"past line was repeated"
+	);
+
+	Ok(())
+}
+
+/// Reads `log` server-sent events until `count` were received, returning their ids and data.
+async fn read_log_events(
+	res: &mut reqwest::Response,
+	count: usize,
+) -> GlobalResult<Vec<(String, serde_json::Value)>> {
+	let mut buf = String::new();
+	let mut events = Vec::new();
+
+	while events.len() < count {
+		let chunk = tokio::time::timeout(std::time::Duration::from_secs(15), res.chunk()).await?;
+		let Some(chunk) = chunk? else {
+			bail!("log stream closed");
+		};
+		buf.push_str(std::str::from_utf8(&chunk)?);
+
+		while let Some(idx) = buf.find("\n\n") {
+			let event = buf[..idx].to_string();
+			buf.drain(..idx + 2);
+
+			let mut id = None;
+			let mut kind = None;
+			let mut data = None;
+			for line in event.lines() {
+				if let Some(x) = line.strip_prefix("id: ") {
+					id = Some(x.to_string());
+				} else if let Some(x) = line.strip_prefix("event: ") {
+					kind = Some(x.to_string());
+				} else if let Some(x) = line.strip_prefix("data: ") {
+					data = Some(serde_json::from_str(x)?);
+				}
+			}
+
+			// Skip keepalive comments
+			if kind.as_deref() == Some("log") {
+				events.push((unwrap!(id), unwrap!(data)));
+			}
+		}
+	}
+
+	Ok(events)
+}
@@ -50,9 +50,6 @@ pub fn config(rivet_config: rivet_config::Config) -> Result<RunConfigData> {
 			ServiceKind::Singleton,
 			|config, pools| Box::pin(cluster_metrics_publish::start(config, pools)),
 		),
-		Service::new("cluster_gc", ServiceKind::Singleton, |config, pools| {
-			Box::pin(cluster_gc::start(config, pools))
-		}),
 		Service::new(
 			"cluster_default_update",
 			ServiceKind::Oneshot,
@@ -305,6 +302,9 @@ pub fn config(rivet_config: rivet_config::Config) -> Result<RunConfigData> {
 		S3Bucket {
 			name: "bucket-actor-log-export",
 		},
+		S3Bucket {
+			name: "bucket-actor-snapshot",
+		},
 		S3Bucket { name: "bucket-log" },
 		S3Bucket {
 			name: "bucket-svc-build",
@@ -0,0 +1,12 @@
+CREATE TABLE autoscale_decisions (
+	decision_id UUID PRIMARY KEY,
+	datacenter_id UUID NOT NULL REFERENCES datacenters (datacenter_id),
+	pool_type INT NOT NULL, -- cluster::types::PoolType
+	old_desired_count INT NOT NULL,
+	new_desired_count INT NOT NULL,
+	reason TEXT NOT NULL,
+	usage JSONB NOT NULL, -- rivet_api::models::EdgeIntercomPegboardFlavorUsage
+	create_ts INT NOT NULL,
+
+	INDEX (datacenter_id, pool_type, create_ts DESC)
+);
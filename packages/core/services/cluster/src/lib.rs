@@ -2,6 +2,7 @@ use chirp_workflow::prelude::*;
 
 pub mod metrics;
 pub mod ops;
+pub mod provider;
 pub mod types;
 pub mod util;
 pub mod workflows;
@@ -11,9 +12,12 @@ pub fn registry() -> WorkflowResult<Registry> {
 
 	let mut registry = Registry::new();
 	registry.register_workflow::<cluster::Workflow>()?;
+	registry.register_workflow::<datacenter::autoscale::Workflow>()?;
 	registry.register_workflow::<datacenter::scale::Workflow>()?;
 	registry.register_workflow::<datacenter::tls_issue::Workflow>()?;
 	registry.register_workflow::<datacenter::Workflow>()?;
+	registry.register_workflow::<fake_server::Workflow>()?;
+	registry.register_workflow::<gc::Workflow>()?;
 	registry.register_workflow::<prebake::Workflow>()?;
 	registry.register_workflow::<server::drain::Workflow>()?;
 	registry.register_workflow::<server::gg_dns_create::Workflow>()?;
@@ -25,5 +29,11 @@ pub fn registry() -> WorkflowResult<Registry> {
 	registry.register_workflow::<server::Workflow>()?;
 	registry.register_workflow::<server::Workflow2>()?;
 
+	registry.register_schedule(Schedule::interval(
+		"cluster_gc",
+		gc::GC_INTERVAL,
+		gc::Input {},
+	)?)?;
+
 	Ok(registry)
 }
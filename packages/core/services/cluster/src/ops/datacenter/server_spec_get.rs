@@ -43,7 +43,7 @@ pub async fn datacenter_server_spec_list(
 		.datacenters
 		.iter()
 		.filter(|x| match x.provider {
-			crate::types::Provider::Manual => false,
+			crate::types::Provider::Manual | crate::types::Provider::Fake => false,
 			crate::types::Provider::Linode => true,
 		})
 		.map(|dc| {
@@ -141,6 +141,7 @@ pub async fn datacenter_server_spec_list(
 
 				ServerSpec::from_linode(instance_type)
 			}
+			crate::types::Provider::Fake => crate::provider::fake::server_spec(),
 		};
 
 		datacenters_output.push(Datacenter {
@@ -1,23 +1,12 @@
 use std::{collections::HashSet, convert::TryInto, net::IpAddr};
 
 use chirp_workflow::prelude::*;
-use linode::util::client;
-use reqwest::header;
-use serde_json::json;
 
 use super::get::ServerRow;
-use crate::types::{Filter, Provider, Server};
-
-#[derive(Deserialize)]
-struct GetLinodesResponse {
-	data: Vec<Linode>,
-}
-
-#[derive(Deserialize)]
-struct Linode {
-	created: chrono::NaiveDateTime,
-	label: String,
-}
+use crate::{
+	provider,
+	types::{Filter, Provider, Server},
+};
 
 #[derive(Debug)]
 pub struct Input {
@@ -60,79 +49,24 @@ pub async fn cluster_server_lost_list(ctx: &OperationCtx, input: &Input) -> Glob
 	.chain(std::iter::once(Ok((Provider::Linode, linode_token))))
 	.collect::<GlobalResult<HashSet<_>>>()?;
 
-	// Filter by namespace
-	let filter = json!({
-		"label": {
-			"+contains": format!("{}-", ctx.config().server()?.rivet.namespace),
-		}
-	});
-	let mut headers = header::HeaderMap::new();
-	headers.insert(
-		"X-Filter",
-		header::HeaderValue::from_str(&serde_json::to_string(&filter)?)?,
-	);
-
-	let mut servers = Vec::new();
+	let min_age_minutes = input.min_age.unwrap_or(720) as i64; // Default 720 minutes (12 hours)
+	let mut server_ids = Vec::new();
 	for (provider, api_token) in accounts {
-		match provider {
-			Provider::Manual => {
-				// Noop
-			}
-			Provider::Linode => {
-				servers.extend(
-					run_for_linode_account(ctx, &input.filter, &api_token, &headers, input.min_age)
-						.await?,
-				);
-			}
-		}
+		server_ids.extend(
+			provider::get(provider)
+				.list(ctx, &api_token)
+				.await?
+				.into_iter()
+				// Filter out servers younger than min_age
+				.filter(|server| {
+					server.create_ts < ctx.ts() - util::duration::minutes(min_age_minutes)
+				})
+				.map(|server| server.server_id),
+		);
 	}
 
-	Ok(Output { servers })
-}
-
-async fn run_for_linode_account(
-	ctx: &OperationCtx,
-	filter: &Filter,
-	api_token: &str,
-	headers: &header::HeaderMap,
-	min_age: Option<u64>,
-) -> GlobalResult<Vec<Server>> {
-	// Build HTTP client
-	let client = client::Client::new_with_headers(api_token.to_string(), headers.clone()).await?;
-
-	let req = client
-		.inner()
-		.get("https://api.linode.com/v4/linode/instances")
-		.query(&[("page_size", 500)]);
-
-	let res = client
-		.request(req, None, false)
-		.await?
-		.json::<GetLinodesResponse>()
-		.await?;
-
-	tracing::info!("{} servers in account", res.data.len());
-
-	let server_config = ctx.config().server()?;
-	let min_age_minutes = min_age.unwrap_or(720) as i64; // Default 720 minutes (12 hours)
-	let server_ids = res
-		.data
-		.into_iter()
-		// Filter out servers younger than min_age
-		.filter(|linode| {
-			linode.created.and_utc().timestamp_millis()
-				< ctx.ts() - util::duration::minutes(min_age_minutes)
-		})
-		// Parse server ID from linode label
-		.filter_map(|linode| {
-			linode
-				.label
-				.get(server_config.rivet.namespace.len() + 1..)
-				.map(util::uuid::parse)
-		})
-		.collect::<GlobalResult<Vec<_>>>()?;
-
-	// Select deleted servers that match the linode api call
+	// Select deleted servers that match the provider api calls
+	let filter = &input.filter;
 	let servers = sql_fetch_all!(
 		[ctx, ServerRow]
 		"
@@ -191,5 +125,5 @@ async fn run_for_linode_account(
 	.map(TryInto::try_into)
 	.collect::<GlobalResult<Vec<_>>>()?;
 
-	Ok(servers)
+	Ok(Output { servers })
 }
@@ -1,12 +1,10 @@
 use chirp_workflow::prelude::*;
 use cloudflare::{endpoints as cf, framework as cf_framework};
-use linode::util::{api, client};
-use reqwest::header;
-use serde_json::json;
 use std::{collections::HashSet, convert::TryInto};
 
 use crate::{
-	types::{Filter, Provider, Server},
+	provider,
+	types::{Filter, Provider},
 	util::cf_client,
 };
 
@@ -35,7 +33,7 @@ pub async fn cluster_server_prune_with_filter(
 
 	prune_cloudflare(ctx, &servers_res).await?;
 
-	prune_linode(ctx, &servers_res).await?;
+	prune_providers(ctx, &servers_res).await?;
 
 	Ok(Output {})
 }
@@ -107,7 +105,7 @@ async fn prune_cloudflare(
 }
 
 #[tracing::instrument(skip_all)]
-async fn prune_linode(
+async fn prune_providers(
 	ctx: &OperationCtx,
 	servers_res: &crate::ops::server::lost_list::Output,
 ) -> GlobalResult<()> {
@@ -140,61 +138,10 @@ async fn prune_linode(
 	.chain(std::iter::once(Ok((Provider::Linode, linode_token))))
 	.collect::<GlobalResult<HashSet<_>>>()?;
 
-	// Filter by namespace
-	let filter = json!({
-		"label": {
-			"+contains": format!("{}-", ctx.config().server()?.rivet.namespace),
-		}
-	});
-	let mut headers = header::HeaderMap::new();
-	headers.insert(
-		"X-Filter",
-		header::HeaderValue::from_str(&serde_json::to_string(&filter)?)?,
-	);
-
 	for (provider, api_token) in accounts {
-		match provider {
-			Provider::Manual => {
-				// Noop
-			}
-			Provider::Linode => {
-				run_for_linode_account(&servers_res.servers, &api_token, &headers).await?;
-			}
-		}
-	}
-
-	Ok(())
-}
-
-#[tracing::instrument(skip_all)]
-async fn run_for_linode_account(
-	servers: &[Server],
-	api_token: &str,
-	headers: &header::HeaderMap,
-) -> GlobalResult<()> {
-	// Build HTTP client
-	let client = client::Client::new_with_headers(api_token.to_string(), headers.clone()).await?;
-
-	tracing::info!("pruning {} linode servers", servers.len());
-
-	for server in servers {
-		let Some(linode_id) = &server.provider_server_id else {
-			tracing::warn!(server_id = ?server.server_id, "provider_server_ide is none");
-			continue;
-		};
-		let linode_id = linode_id.parse()?;
-
-		tracing::info!(server_id = ?server.server_id, ?linode_id, "pruning linode");
-
-		let firewalls = api::list_linode_firewalls(&client, linode_id).await?;
-
-		for firewall in firewalls {
-			api::delete_firewall(&client, firewall.id).await?;
-		}
-
-		api::delete_instance(&client, linode_id).await?;
-
-		// NOTE: Does not delete ssh keys
+		provider::get(provider)
+			.prune(ctx, &api_token, &servers_res.servers)
+			.await?;
 	}
 
 	Ok(())
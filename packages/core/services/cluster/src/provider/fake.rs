@@ -0,0 +1,151 @@
+use chirp_workflow::prelude::*;
+use server_spec::{types::ServerSpec, LINODE_CPU_PER_CORE};
+
+use super::{CloudProvider, CreateImageRequest, ProviderServer, ProvisionOutput, ProvisionRequest};
+use crate::{
+	types::Server,
+	workflows::fake_server::{self, FakeServer},
+};
+
+/// Simulates a cloud provider in-process without creating any real servers. Used for testing cluster
+/// workflows.
+///
+/// Provisioning behavior is configured through the pool's hardware, see [`FakeHardware`].
+pub struct FakeProvider;
+
+#[async_trait::async_trait]
+impl CloudProvider for FakeProvider {
+	async fn provision(
+		&self,
+		ctx: &mut WorkflowCtx,
+		req: &ProvisionRequest,
+	) -> GlobalResult<Option<ProvisionOutput>> {
+		let workflow_id = ctx
+			.workflow(fake_server::Input {
+				server_id: req.server_id,
+				hardware: req.hardware.clone(),
+			})
+			.tag("server_id", req.server_id)
+			.dispatch()
+			.await?;
+
+		match ctx.listen::<FakeServer>().await? {
+			FakeServer::ProvisionComplete(sig) => Ok(Some(ProvisionOutput {
+				provider_server_workflow_id: workflow_id,
+				provider_server_id: sig.provider_server_id,
+				provider_boot_disk_id: None,
+				public_ip: sig.public_ip,
+			})),
+			FakeServer::ProvisionFailed(_) => {
+				tracing::error!(
+					provision_workflow_id=%workflow_id,
+					server_id=?req.server_id,
+					"failed to provision fake server"
+				);
+
+				Ok(None)
+			}
+		}
+	}
+
+	async fn destroy(
+		&self,
+		ctx: &mut WorkflowCtx,
+		provider_server_workflow_id: Uuid,
+	) -> GlobalResult<()> {
+		ctx.signal(fake_server::Destroy {})
+			.to_workflow_id(provider_server_workflow_id)
+			.send()
+			.await?;
+
+		ctx.workflow::<fake_server::Input>(provider_server_workflow_id)
+			.output()
+			.await?;
+
+		Ok(())
+	}
+
+	fn prebake_hardware(&self) -> &'static str {
+		"fake-prebake"
+	}
+
+	async fn create_image(
+		&self,
+		_ctx: &mut WorkflowCtx,
+		req: &CreateImageRequest,
+	) -> GlobalResult<String> {
+		Ok(format!("fake-image-{}", req.server.provider_server_id))
+	}
+
+	async fn list(
+		&self,
+		_ctx: &OperationCtx,
+		_api_token: &str,
+	) -> GlobalResult<Vec<ProviderServer>> {
+		// Fake servers only exist as workflows and cannot be lost
+		Ok(Vec::new())
+	}
+
+	async fn prune(
+		&self,
+		_ctx: &OperationCtx,
+		_api_token: &str,
+		_servers: &[Server],
+	) -> GlobalResult<()> {
+		Ok(())
+	}
+
+	fn drain_on_scale_down(&self) -> bool {
+		false
+	}
+
+	fn requires_install(&self) -> bool {
+		// There is nothing to SSH into
+		false
+	}
+}
+
+/// Configuration for a fake server, parsed from the pool's `provider_hardware` which is formatted as a
+/// query string after the hardware name, e.g.
+/// `fake-1?provision_latency=5000&destroy_latency=1000&failure_percent=10`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct FakeHardware {
+	/// How long provisioning takes (ms).
+	pub provision_latency: i64,
+	/// How long destroying takes (ms).
+	pub destroy_latency: i64,
+	/// Chance that provisioning fails (0-100).
+	pub failure_percent: u8,
+}
+
+impl FakeHardware {
+	pub fn parse(hardware: &str) -> GlobalResult<Self> {
+		let Some((_, query)) = hardware.split_once('?') else {
+			return Ok(FakeHardware::default());
+		};
+
+		let fake_hardware = serde_urlencoded::from_str::<FakeHardware>(query)?;
+		ensure!(
+			fake_hardware.failure_percent <= 100,
+			"failure_percent must be between 0 and 100"
+		);
+		ensure!(
+			fake_hardware.provision_latency >= 0 && fake_hardware.destroy_latency >= 0,
+			"latency cannot be negative"
+		);
+
+		Ok(fake_hardware)
+	}
+}
+
+/// Resources reported for every fake server.
+pub fn server_spec() -> ServerSpec {
+	ServerSpec {
+		cpu_cores: 4,
+		cpu: 4 * LINODE_CPU_PER_CORE,
+		memory: 8 * 1024,
+		disk: 160 * 1024,
+		bandwidth: 5000 * 1000,
+	}
+}
@@ -0,0 +1,225 @@
+use ::linode::{
+	types::FirewallPreset,
+	util::{api, client},
+	workflows::{image, server},
+};
+use chirp_workflow::prelude::*;
+use reqwest::header;
+use serde_json::json;
+
+use super::{CloudProvider, CreateImageRequest, ProviderServer, ProvisionOutput, ProvisionRequest};
+use crate::types::{PoolType, Server};
+
+pub struct LinodeProvider;
+
+#[async_trait::async_trait]
+impl CloudProvider for LinodeProvider {
+	async fn provision(
+		&self,
+		ctx: &mut WorkflowCtx,
+		req: &ProvisionRequest,
+	) -> GlobalResult<Option<ProvisionOutput>> {
+		let workflow_id = ctx
+			.workflow(server::Input {
+				server_id: req.server_id,
+				provider_datacenter_id: req.provider_datacenter_id.clone(),
+				custom_image: req.custom_image.clone(),
+				api_token: req.api_token.clone(),
+				hardware: req.hardware.clone(),
+				firewall_preset: firewall_preset(req.pool_type),
+				vlan_ip: req.vlan_ip,
+				vlan_ip_net: req.vlan_ip_net,
+				tags: req.tags.clone(),
+			})
+			.tag("server_id", req.server_id)
+			.dispatch()
+			.await?;
+
+		match ctx.listen::<Linode>().await? {
+			Linode::ProvisionComplete(sig) => Ok(Some(ProvisionOutput {
+				provider_server_workflow_id: workflow_id,
+				provider_server_id: sig.linode_id.to_string(),
+				provider_boot_disk_id: Some(sig.boot_disk_id.to_string()),
+				public_ip: sig.public_ip,
+			})),
+			Linode::ProvisionFailed(_) => {
+				tracing::error!(
+					provision_workflow_id=%workflow_id,
+					server_id=?req.server_id,
+					"failed to provision server"
+				);
+
+				Ok(None)
+			}
+		}
+	}
+
+	async fn destroy(
+		&self,
+		ctx: &mut WorkflowCtx,
+		provider_server_workflow_id: Uuid,
+	) -> GlobalResult<()> {
+		tracing::info!(%provider_server_workflow_id, "destroying linode server");
+
+		ctx.signal(server::Destroy {})
+			.to_workflow_id(provider_server_workflow_id)
+			.send()
+			.await?;
+
+		// Wait for workflow to complete
+		ctx.workflow::<server::Input>(provider_server_workflow_id)
+			.output()
+			.await?;
+
+		Ok(())
+	}
+
+	fn prebake_hardware(&self) -> &'static str {
+		::linode::util::consts::PREBAKE_HARDWARE
+	}
+
+	async fn create_image(
+		&self,
+		ctx: &mut WorkflowCtx,
+		req: &CreateImageRequest,
+	) -> GlobalResult<String> {
+		let linode_id = req.server.provider_server_id.parse::<u64>()?;
+		let boot_disk_id = unwrap_ref!(req.server.provider_boot_disk_id).parse::<u64>()?;
+
+		ctx.workflow(image::Input {
+			prebake_server_id: req.prebake_server_id,
+			api_token: req.api_token.clone(),
+			linode_id,
+			boot_disk_id,
+		})
+		.tag("linode_id", linode_id)
+		.dispatch()
+		.await?;
+
+		// Wait for image creation
+		let image_create_res = ctx.listen::<image::CreateComplete>().await?;
+
+		Ok(image_create_res.image_id)
+	}
+
+	async fn list(&self, ctx: &OperationCtx, api_token: &str) -> GlobalResult<Vec<ProviderServer>> {
+		let client =
+			client::Client::new_with_headers(api_token.to_string(), namespace_filter(ctx)?).await?;
+
+		let req = client
+			.inner()
+			.get("https://api.linode.com/v4/linode/instances")
+			.query(&[("page_size", 500)]);
+
+		let res = client
+			.request(req, None, false)
+			.await?
+			.json::<GetLinodesResponse>()
+			.await?;
+
+		tracing::info!("{} servers in account", res.data.len());
+
+		let namespace = &ctx.config().server()?.rivet.namespace;
+		res.data
+			.into_iter()
+			// Parse server ID from linode label
+			.filter_map(|linode| {
+				let server_id = linode.label.get(namespace.len() + 1..)?;
+
+				Some(
+					util::uuid::parse(server_id).map(|server_id| ProviderServer {
+						server_id,
+						create_ts: linode.created.and_utc().timestamp_millis(),
+					}),
+				)
+			})
+			.collect()
+	}
+
+	async fn prune(
+		&self,
+		ctx: &OperationCtx,
+		api_token: &str,
+		servers: &[Server],
+	) -> GlobalResult<()> {
+		let client =
+			client::Client::new_with_headers(api_token.to_string(), namespace_filter(ctx)?).await?;
+
+		tracing::info!("pruning {} linode servers", servers.len());
+
+		for server in servers {
+			let Some(linode_id) = &server.provider_server_id else {
+				tracing::warn!(server_id = ?server.server_id, "provider_server_ide is none");
+				continue;
+			};
+			let linode_id = linode_id.parse()?;
+
+			tracing::info!(server_id = ?server.server_id, ?linode_id, "pruning linode");
+
+			let firewalls = api::list_linode_firewalls(&client, linode_id).await?;
+
+			for firewall in firewalls {
+				api::delete_firewall(&client, firewall.id).await?;
+			}
+
+			api::delete_instance(&client, linode_id).await?;
+
+			// NOTE: Does not delete ssh keys
+		}
+
+		Ok(())
+	}
+
+	fn drain_on_scale_down(&self) -> bool {
+		// Never destroy servers when scaling down with Linode, always drain.
+		//
+		// See _Provider Billing Internals_ in docs
+		true
+	}
+}
+
+pub(crate) fn firewall_preset(pool_type: PoolType) -> FirewallPreset {
+	match pool_type {
+		PoolType::Job | PoolType::Pegboard | PoolType::PegboardIsolate => FirewallPreset::Job,
+		PoolType::Gg | PoolType::Guard => FirewallPreset::Gg,
+		PoolType::Ats => FirewallPreset::Ats,
+		PoolType::Fdb => FirewallPreset::Fdb,
+		PoolType::Worker => FirewallPreset::Worker,
+		PoolType::Nats => FirewallPreset::Nats,
+	}
+}
+
+/// Filters linode API requests by the current namespace.
+fn namespace_filter(ctx: &OperationCtx) -> GlobalResult<header::HeaderMap> {
+	let filter = json!({
+		"label": {
+			"+contains": format!("{}-", ctx.config().server()?.rivet.namespace),
+		}
+	});
+	let mut headers = header::HeaderMap::new();
+	headers.insert(
+		"X-Filter",
+		header::HeaderValue::from_str(&serde_json::to_string(&filter)?)?,
+	);
+
+	Ok(headers)
+}
+
+#[derive(Deserialize)]
+struct GetLinodesResponse {
+	data: Vec<LinodeInstance>,
+}
+
+#[derive(Deserialize)]
+struct LinodeInstance {
+	created: chrono::NaiveDateTime,
+	label: String,
+}
+
+// Listen for linode provision signals
+type ProvisionComplete = server::ProvisionComplete;
+type ProvisionFailed = server::ProvisionFailed;
+join_signal!(Linode {
+	ProvisionComplete,
+	ProvisionFailed,
+});
@@ -0,0 +1,62 @@
+use chirp_workflow::prelude::*;
+
+use super::{CloudProvider, CreateImageRequest, ProviderServer, ProvisionOutput, ProvisionRequest};
+use crate::types::Server;
+
+/// Servers are manually provisioned and connected, all provider actions are noops.
+pub struct ManualProvider;
+
+#[async_trait::async_trait]
+impl CloudProvider for ManualProvider {
+	async fn provision(
+		&self,
+		_ctx: &mut WorkflowCtx,
+		_req: &ProvisionRequest,
+	) -> GlobalResult<Option<ProvisionOutput>> {
+		// Noop
+		Ok(None)
+	}
+
+	async fn destroy(
+		&self,
+		_ctx: &mut WorkflowCtx,
+		_provider_server_workflow_id: Uuid,
+	) -> GlobalResult<()> {
+		// Noop
+		Ok(())
+	}
+
+	fn prebake_hardware(&self) -> &'static str {
+		""
+	}
+
+	async fn create_image(
+		&self,
+		_ctx: &mut WorkflowCtx,
+		_req: &CreateImageRequest,
+	) -> GlobalResult<String> {
+		bail!("manual provider does not support prebake images");
+	}
+
+	async fn list(
+		&self,
+		_ctx: &OperationCtx,
+		_api_token: &str,
+	) -> GlobalResult<Vec<ProviderServer>> {
+		Ok(Vec::new())
+	}
+
+	async fn prune(
+		&self,
+		_ctx: &OperationCtx,
+		_api_token: &str,
+		_servers: &[Server],
+	) -> GlobalResult<()> {
+		Ok(())
+	}
+
+	fn drain_on_scale_down(&self) -> bool {
+		// Manual clusters are never scaled
+		true
+	}
+}
@@ -0,0 +1,109 @@
+//! Cloud providers that servers can be provisioned on.
+//!
+//! Workflows and operations call providers through [`CloudProvider`] instead of matching on
+//! [`Provider`] so adding a new provider does not require touching the server lifecycle.
+
+use std::net::Ipv4Addr;
+
+use chirp_workflow::prelude::*;
+use ipnet::Ipv4Net;
+
+use crate::types::{PoolType, Provider, Server};
+
+pub mod fake;
+pub mod linode;
+pub mod manual;
+
+/// Returns the implementation for the given provider.
+pub fn get(provider: Provider) -> &'static dyn CloudProvider {
+	match provider {
+		Provider::Linode => &linode::LinodeProvider,
+		Provider::Manual => &manual::ManualProvider,
+		Provider::Fake => &fake::FakeProvider,
+	}
+}
+
+#[async_trait::async_trait]
+pub trait CloudProvider: Send + Sync {
+	/// Provisions a single server with the given hardware. Returns `None` if provisioning failed, in which
+	/// case the next hardware in the pool is attempted.
+	///
+	/// Called from within a workflow, all side effects must be run as workflow events.
+	async fn provision(
+		&self,
+		ctx: &mut WorkflowCtx,
+		req: &ProvisionRequest,
+	) -> GlobalResult<Option<ProvisionOutput>>;
+
+	/// Destroys a server provisioned by `provision` and waits for it to be cleaned up.
+	async fn destroy(
+		&self,
+		ctx: &mut WorkflowCtx,
+		provider_server_workflow_id: Uuid,
+	) -> GlobalResult<()>;
+
+	/// Hardware used for servers that are provisioned to create a prebake image.
+	fn prebake_hardware(&self) -> &'static str;
+
+	/// Creates a prebake image from a provisioned and installed server. Returns the provider image id.
+	async fn create_image(
+		&self,
+		ctx: &mut WorkflowCtx,
+		req: &CreateImageRequest,
+	) -> GlobalResult<String>;
+
+	/// Lists all servers in the given account that belong to this namespace.
+	async fn list(&self, ctx: &OperationCtx, api_token: &str) -> GlobalResult<Vec<ProviderServer>>;
+
+	/// Deletes the given servers and their associated resources directly through the provider's API,
+	/// bypassing workflows.
+	async fn prune(
+		&self,
+		ctx: &OperationCtx,
+		api_token: &str,
+		servers: &[Server],
+	) -> GlobalResult<()>;
+
+	/// Whether servers should always be drained instead of destroyed when scaling down.
+	fn drain_on_scale_down(&self) -> bool;
+
+	/// Whether the install workflow should be run after provisioning a server without a prebake image.
+	fn requires_install(&self) -> bool {
+		true
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct ProvisionRequest {
+	pub server_id: Uuid,
+	pub pool_type: PoolType,
+	pub provider_datacenter_id: String,
+	pub api_token: Option<String>,
+	pub hardware: String,
+	pub custom_image: Option<String>,
+	pub vlan_ip: Option<Ipv4Addr>,
+	pub vlan_ip_net: Option<Ipv4Net>,
+	pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProvisionOutput {
+	/// Id of the workflow managing the server in the provider.
+	pub provider_server_workflow_id: Uuid,
+	pub provider_server_id: String,
+	pub provider_boot_disk_id: Option<String>,
+	pub public_ip: Ipv4Addr,
+}
+
+#[derive(Debug, Clone)]
+pub struct CreateImageRequest {
+	pub prebake_server_id: Uuid,
+	pub api_token: Option<String>,
+	pub server: ProvisionOutput,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProviderServer {
+	pub server_id: Uuid,
+	pub create_ts: i64,
+}
@@ -1,5 +1,6 @@
 use std::{
 	convert::{TryFrom, TryInto},
+	hash::{Hash, Hasher},
 	net::{IpAddr, Ipv4Addr},
 	str::FromStr,
 };
@@ -39,9 +40,11 @@ pub enum Provider {
 	/// Servers are manually provisioned and connected.
 	Manual = 1,
 	Linode = 0,
+	/// Simulated in-process provider for testing. See `provider::fake`.
+	Fake = 2,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Hash)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Pool {
 	pub pool_type: PoolType,
 	/// See docs on failover (/docs/packages/cluster/SERVER_PROVISIONING.md#creating-a-new-server)
@@ -52,6 +55,38 @@ pub struct Pool {
 	pub drain_timeout: u64,
 	#[serde(default)]
 	pub margin: u32,
+	/// Adjusts `desired_count` based on pegboard utilization. Only applies to pegboard pools.
+	#[serde(default)]
+	pub autoscale: Option<PoolAutoscale>,
+}
+
+impl Hash for Pool {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		self.pool_type.hash(state);
+		self.hardware.hash(state);
+		self.desired_count.hash(state);
+		self.min_count.hash(state);
+		self.max_count.hash(state);
+		self.drain_timeout.hash(state);
+		self.margin.hash(state);
+
+		// Pools are part of the `InsertDb` activity input in the datacenter workflow. Pools without autoscaling
+		// hash like they did before it existed so datacenters created earlier still replay
+		if let Some(autoscale) = &self.autoscale {
+			autoscale.hash(state);
+		}
+	}
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Hash, PartialEq, Eq)]
+pub struct PoolAutoscale {
+	pub enabled: bool,
+	/// Percentage of capacity to keep free on top of current usage and pending allocations.
+	pub headroom: u32,
+	/// Minimum time since the last autoscale decision before scaling up again (ms).
+	pub scale_up_cooldown: u64,
+	/// Minimum time since the last autoscale decision before scaling down again (ms).
+	pub scale_down_cooldown: u64,
 }
 
 // Backwards compatibility
@@ -73,6 +108,7 @@ impl TryFrom<backend::cluster::Pool> for Pool {
 			max_count: value.max_count,
 			drain_timeout: value.drain_timeout,
 			margin: 0,
+			autoscale: None,
 		})
 	}
 }
@@ -132,7 +168,7 @@ pub struct Hardware {
 	pub provider_hardware: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Hash)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct PoolUpdate {
 	pub pool_type: PoolType,
 
@@ -143,6 +179,26 @@ pub struct PoolUpdate {
 	pub max_count: Option<u32>,
 	pub drain_timeout: Option<u64>,
 	pub margin: Option<u32>,
+	#[serde(default)]
+	pub autoscale: Option<PoolAutoscale>,
+}
+
+impl Hash for PoolUpdate {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		self.pool_type.hash(state);
+		self.hardware.hash(state);
+		self.desired_count.hash(state);
+		self.min_count.hash(state);
+		self.max_count.hash(state);
+		self.drain_timeout.hash(state);
+		self.margin.hash(state);
+
+		// Updates are part of the `UpdateDb` activity input. Updates without autoscaling hash like they did
+		// before it existed so datacenters that already applied one still replay
+		if let Some(autoscale) = &self.autoscale {
+			autoscale.hash(state);
+		}
+	}
 }
 
 #[derive(Serialize, Deserialize, Hash, Debug, Clone, Copy, PartialEq, Eq, FromRepr)]
@@ -1 +1,2 @@
 pub const LINODE_HARDWARE: &str = "g6-nanode-1";
+pub const FAKE_HARDWARE: &str = "fake-1?provision_latency=1000&destroy_latency=500";
@@ -0,0 +1,484 @@
+use chirp_workflow::prelude::*;
+use futures_util::FutureExt;
+use rivet_api::{
+	apis::{
+		configuration::Configuration, edge_intercom_pegboard_api::edge_intercom_pegboard_get_usage,
+	},
+	models,
+};
+use rivet_operation::prelude::proto::{self, backend::pkg::*};
+
+use crate::types::{Pool, PoolAutoscale, PoolType};
+
+/// How often pegboard utilization is checked.
+const AUTOSCALE_INTERVAL_MS: i64 = util::duration::seconds(30);
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Input {
+	pub datacenter_id: Uuid,
+}
+
+#[workflow]
+pub(crate) async fn cluster_datacenter_autoscale(
+	ctx: &mut WorkflowCtx,
+	input: &Input,
+) -> GlobalResult<()> {
+	ctx.repeat(|ctx| {
+		let datacenter_id = input.datacenter_id;
+
+		async move {
+			let scaled = ctx.activity(AutoscaleInput { datacenter_id }).await?;
+
+			if scaled {
+				ctx.signal(super::Scale {})
+					.tag("datacenter_id", datacenter_id)
+					.send()
+					.await?;
+			}
+
+			ctx.sleep(AUTOSCALE_INTERVAL_MS).await?;
+
+			Ok(Loop::Continue)
+		}
+		.boxed()
+	})
+	.await
+}
+
+#[derive(Debug, Serialize, Deserialize, Hash)]
+struct AutoscaleInput {
+	datacenter_id: Uuid,
+}
+
+/// Returns true if the desired count of any pool changed.
+#[activity(Autoscale)]
+#[max_retries = 5]
+async fn autoscale(ctx: &ActivityCtx, input: &AutoscaleInput) -> GlobalResult<bool> {
+	let datacenters_res = ctx
+		.op(crate::ops::datacenter::get::Input {
+			datacenter_ids: vec![input.datacenter_id],
+		})
+		.await?;
+	let dc = unwrap!(datacenters_res.datacenters.into_iter().next());
+
+	let pools = dc
+		.pools
+		.iter()
+		.filter_map(|pool| {
+			pool.autoscale
+				.as_ref()
+				.filter(|autoscale| autoscale.enabled)
+				.map(|autoscale| (pool, autoscale))
+		})
+		.collect::<Vec<_>>();
+
+	if pools.is_empty() {
+		return Ok(false);
+	}
+
+	let usage = fetch_usage(ctx, &dc.name_id).await?;
+	let now = util::timestamp::now();
+	let mut scaled = false;
+
+	for (pool, autoscale) in pools {
+		let flavor_usage = match pool.pool_type {
+			PoolType::Pegboard => &usage.container,
+			PoolType::PegboardIsolate => &usage.isolate,
+			_ => {
+				tracing::warn!(
+					pool_type=?pool.pool_type,
+					"autoscaling is only supported for pegboard pools"
+				);
+				continue;
+			}
+		};
+
+		let (target, reason) = target_desired_count(pool, autoscale, flavor_usage);
+		if target == pool.desired_count {
+			continue;
+		}
+
+		let last_decision = sql_fetch_optional!(
+			[ctx, (i64,)]
+			"
+			SELECT create_ts
+			FROM db_cluster.autoscale_decisions
+			WHERE
+				datacenter_id = $1 AND
+				pool_type = $2
+			ORDER BY create_ts DESC
+			LIMIT 1
+			",
+			input.datacenter_id,
+			pool.pool_type as i64,
+		)
+		.await?;
+
+		let cooldown = if target > pool.desired_count {
+			autoscale.scale_up_cooldown
+		} else {
+			autoscale.scale_down_cooldown
+		};
+		if let Some((last_decision_ts,)) = last_decision {
+			if now - last_decision_ts < i64::try_from(cooldown)? {
+				tracing::debug!(
+					pool_type=?pool.pool_type,
+					desired_count=%pool.desired_count,
+					%target,
+					"skipping autoscale, in cooldown"
+				);
+				continue;
+			}
+		}
+
+		tracing::info!(
+			pool_type=?pool.pool_type,
+			old_desired_count=%pool.desired_count,
+			new_desired_count=%target,
+			%reason,
+			"autoscaling pool"
+		);
+
+		apply_decision(
+			ctx,
+			input.datacenter_id,
+			pool,
+			target,
+			&reason,
+			serde_json::to_value(flavor_usage)?,
+		)
+		.await?;
+
+		scaled = true;
+	}
+
+	if scaled {
+		ctx.cache()
+			.purge("cluster.datacenters2", [input.datacenter_id])
+			.await?;
+	}
+
+	Ok(scaled)
+}
+
+/// Fetches pegboard utilization from the edge datacenter.
+async fn fetch_usage(
+	ctx: &ActivityCtx,
+	dc_name_id: &str,
+) -> GlobalResult<models::EdgeIntercomPegboardGetUsageResponse> {
+	// Create ephemeral token to authenticate with edge
+	let token_res = op!([ctx] token_create {
+		token_config: Some(token::create::request::TokenConfig {
+			ttl: util::duration::minutes(5),
+		}),
+		refresh_token_config: None,
+		issuer: "cluster_datacenter_autoscale".to_owned(),
+		client: None,
+		kind: Some(token::create::request::Kind::New(
+			token::create::request::KindNew { entitlements: vec![proto::claims::Entitlement {
+				kind: Some(proto::claims::entitlement::Kind::Bypass(
+					proto::claims::entitlement::Bypass { }
+				)),
+			}]},
+		)),
+		label: Some("byp".to_owned()),
+		ephemeral: true,
+	})
+	.await?;
+	let token = unwrap!(token_res.token).token;
+
+	let config = Configuration {
+		client: rivet_pools::reqwest::client().await?,
+		base_path: ctx.config().server()?.rivet.edge_api_url_str(dc_name_id)?,
+		bearer_access_token: Some(token),
+		..Default::default()
+	};
+
+	let usage = edge_intercom_pegboard_get_usage(&config).await?;
+
+	Ok(usage)
+}
+
+/// Calculates the number of servers needed to fit current usage and pending allocations with the
+/// configured headroom. Scaling down is limited to one server at a time since each removed server has
+/// to be drained.
+fn target_desired_count(
+	pool: &Pool,
+	autoscale: &PoolAutoscale,
+	usage: &models::EdgeIntercomPegboardFlavorUsage,
+) -> (u32, String) {
+	let (target, reason) = if usage.clients <= 0 {
+		if usage.pending_allocations > 0 {
+			(
+				pool.desired_count.max(1),
+				format!(
+					"{} pending allocations and no clients",
+					usage.pending_allocations
+				),
+			)
+		} else {
+			(pool.desired_count, "no clients".to_string())
+		}
+	} else {
+		let clients = i64::from(usage.clients);
+		let headroom = i64::from(autoscale.headroom);
+
+		// Resources of an average client
+		let client_cpu = (usage.total_cpu / clients).max(1);
+		let client_memory = (usage.total_memory / clients).max(1);
+
+		let required_cpu =
+			(usage.total_cpu - usage.remaining_cpu + usage.pending_cpu) * (100 + headroom) / 100;
+		let required_memory = (usage.total_memory - usage.remaining_memory + usage.pending_memory)
+			* (100 + headroom)
+			/ 100;
+
+		let mut needed =
+			div_ceil(required_cpu, client_cpu).max(div_ceil(required_memory, client_memory));
+
+		// Pending allocations mean no single client could fit an actor even if the pool as a whole has
+		// enough resources, at least one more client is needed
+		if usage.pending_allocations > 0 {
+			needed = needed.max(clients + 1);
+		}
+
+		let needed = u32::try_from(needed).unwrap_or(u32::MAX);
+		let target = if needed < pool.desired_count {
+			pool.desired_count - 1
+		} else {
+			needed
+		};
+
+		(
+			target,
+			format!(
+				"cpu {}/{} millicores, memory {}/{} MiB, {} pending allocations, {}% headroom requires {needed} servers",
+				usage.total_cpu - usage.remaining_cpu,
+				usage.total_cpu,
+				usage.total_memory - usage.remaining_memory,
+				usage.total_memory,
+				usage.pending_allocations,
+				autoscale.headroom,
+			),
+		)
+	};
+
+	(target.max(pool.min_count).min(pool.max_count), reason)
+}
+
+fn div_ceil(a: i64, b: i64) -> i64 {
+	(a.max(0) + b - 1) / b
+}
+
+/// Updates the pool's desired count and records the decision.
+async fn apply_decision(
+	ctx: &ActivityCtx,
+	datacenter_id: Uuid,
+	pool: &Pool,
+	new_desired_count: u32,
+	reason: &str,
+	usage: serde_json::Value,
+) -> GlobalResult<()> {
+	let pool_type = pool.pool_type;
+	let old_desired_count = pool.desired_count;
+	let reason = reason.to_string();
+
+	rivet_pools::utils::crdb::tx(&ctx.crdb().await?, |tx| {
+		let ctx = ctx.clone();
+		let reason = reason.clone();
+		let usage = usage.clone();
+
+		async move {
+			let (pools,) = sql_fetch_one!(
+				[ctx, (sqlx::types::Json<Vec<Pool>>,), @tx tx]
+				"
+				SELECT pools2 FROM db_cluster.datacenters
+				WHERE datacenter_id = $1
+				FOR UPDATE
+				",
+				datacenter_id,
+			)
+			.await?;
+			let mut pools = pools.0;
+
+			let Some(current_pool) = pools.iter_mut().find(|p| p.pool_type == pool_type) else {
+				tracing::warn!(?pool_type, "pool removed before autoscaling");
+				return Ok(());
+			};
+			current_pool.desired_count = new_desired_count;
+
+			sql_execute!(
+				[ctx, @tx tx]
+				"
+				UPDATE db_cluster.datacenters
+				SET pools2 = $2
+				WHERE datacenter_id = $1
+				",
+				datacenter_id,
+				serde_json::to_string(&pools)?,
+			)
+			.await?;
+
+			sql_execute!(
+				[ctx, @tx tx]
+				"
+				INSERT INTO db_cluster.autoscale_decisions (
+					decision_id,
+					datacenter_id,
+					pool_type,
+					old_desired_count,
+					new_desired_count,
+					reason,
+					usage,
+					create_ts
+				)
+				VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+				",
+				Uuid::new_v4(),
+				datacenter_id,
+				pool_type as i64,
+				old_desired_count as i64,
+				new_desired_count as i64,
+				reason,
+				usage,
+				util::timestamp::now(),
+			)
+			.await?;
+
+			Ok(())
+		}
+		.boxed()
+	})
+	.await?;
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn pool(desired_count: u32, min_count: u32, max_count: u32) -> Pool {
+		Pool {
+			pool_type: PoolType::Pegboard,
+			hardware: Vec::new(),
+			desired_count,
+			min_count,
+			max_count,
+			drain_timeout: 0,
+			margin: 0,
+			autoscale: Some(autoscale(0)),
+		}
+	}
+
+	fn autoscale(headroom: u32) -> PoolAutoscale {
+		PoolAutoscale {
+			enabled: true,
+			headroom,
+			scale_up_cooldown: 0,
+			scale_down_cooldown: 0,
+		}
+	}
+
+	/// Usage of clients with 1000 millicores and 1000 MiB each.
+	fn usage(
+		clients: i32,
+		used_cpu: i64,
+		used_memory: i64,
+	) -> models::EdgeIntercomPegboardFlavorUsage {
+		let total = i64::from(clients) * 1000;
+
+		models::EdgeIntercomPegboardFlavorUsage {
+			clients,
+			total_cpu: total,
+			remaining_cpu: total - used_cpu,
+			total_memory: total,
+			remaining_memory: total - used_memory,
+			pending_allocations: 0,
+			pending_cpu: 0,
+			pending_memory: 0,
+		}
+	}
+
+	#[test]
+	fn div_ceil_rounds_up() {
+		assert_eq!(0, div_ceil(0, 4));
+		assert_eq!(1, div_ceil(1, 4));
+		assert_eq!(1, div_ceil(4, 4));
+		assert_eq!(2, div_ceil(5, 4));
+		// Negative requirements don't need any servers
+		assert_eq!(0, div_ceil(-10, 4));
+	}
+
+	#[test]
+	fn scale_up_with_headroom() {
+		// 3600 millicores used with 20% headroom requires 4320 millicores
+		let (target, _) =
+			target_desired_count(&pool(4, 0, 10), &autoscale(20), &usage(4, 3600, 1000));
+		assert_eq!(5, target);
+
+		// Memory is considered as well
+		let (target, _) =
+			target_desired_count(&pool(4, 0, 10), &autoscale(20), &usage(4, 1000, 3600));
+		assert_eq!(5, target);
+
+		// Exact fit without headroom does not scale
+		let (target, _) =
+			target_desired_count(&pool(4, 0, 10), &autoscale(0), &usage(4, 4000, 4000));
+		assert_eq!(4, target);
+	}
+
+	#[test]
+	fn scale_up_for_pending() {
+		// Pending resources count as used
+		let mut u = usage(4, 3000, 1000);
+		u.pending_allocations = 2;
+		u.pending_cpu = 2000;
+		let (target, _) = target_desired_count(&pool(4, 0, 10), &autoscale(0), &u);
+		assert_eq!(5, target);
+
+		// A pending allocation needs at least one more client even if resources are available in total
+		let mut u = usage(2, 100, 100);
+		u.pending_allocations = 1;
+		let (target, _) = target_desired_count(&pool(2, 0, 10), &autoscale(0), &u);
+		assert_eq!(3, target);
+	}
+
+	#[test]
+	fn scale_up_without_clients() {
+		let mut u = usage(0, 0, 0);
+		u.pending_allocations = 1;
+		let (target, _) = target_desired_count(&pool(0, 0, 10), &autoscale(0), &u);
+		assert_eq!(1, target);
+
+		// Servers are already being provisioned
+		let (target, _) = target_desired_count(&pool(3, 0, 10), &autoscale(0), &u);
+		assert_eq!(3, target);
+
+		// Nothing to do without clients or pending allocations
+		let (target, _) = target_desired_count(&pool(3, 0, 10), &autoscale(0), &usage(0, 0, 0));
+		assert_eq!(3, target);
+	}
+
+	#[test]
+	fn scale_down_one_at_a_time() {
+		let (target, _) =
+			target_desired_count(&pool(10, 0, 10), &autoscale(20), &usage(10, 100, 100));
+		assert_eq!(9, target);
+	}
+
+	#[test]
+	fn clamp_to_pool_limits() {
+		let (target, _) =
+			target_desired_count(&pool(4, 0, 6), &autoscale(0), &usage(4, 4000, 20000));
+		assert_eq!(6, target);
+
+		let (target, _) = target_desired_count(&pool(3, 3, 10), &autoscale(0), &usage(3, 100, 100));
+		assert_eq!(3, target);
+
+		let mut u = usage(0, 0, 0);
+		u.pending_allocations = 1;
+		let (target, _) = target_desired_count(&pool(0, 0, 0), &autoscale(0), &u);
+		assert_eq!(0, target);
+	}
+}
@@ -2,6 +2,7 @@ use chirp_workflow::prelude::*;
 use futures_util::FutureExt;
 use std::ops::Deref;
 
+pub mod autoscale;
 pub mod scale;
 pub mod tls_issue;
 
@@ -88,6 +89,14 @@ pub(crate) async fn cluster_datacenter(ctx: &mut WorkflowCtx, input: &Input) ->
 	.output()
 	.await?;
 
+	// Adjusts pool desired counts based on pegboard utilization
+	ctx.v(2)
+		.workflow(autoscale::Input {
+			datacenter_id: input.datacenter_id,
+		})
+		.dispatch()
+		.await?;
+
 	ctx.repeat(|ctx| {
 		let datacenter_id = input.datacenter_id;
 
@@ -327,6 +336,9 @@ async fn update_db(ctx: &ActivityCtx, input: &UpdateDbInput) -> GlobalResult<()>
 			if let Some(margin) = pool.margin {
 				current_pool.margin = margin;
 			}
+			if let Some(autoscale) = &pool.autoscale {
+				current_pool.autoscale = Some(autoscale.clone());
+			}
 		} else {
 			tracing::info!(pool_type=?pool.pool_type, "creating new pool");
 
@@ -358,6 +370,7 @@ async fn update_db(ctx: &ActivityCtx, input: &UpdateDbInput) -> GlobalResult<()>
 				max_count,
 				drain_timeout,
 				margin: 0,
+				autoscale: pool.autoscale.clone(),
 			});
 		};
 	}
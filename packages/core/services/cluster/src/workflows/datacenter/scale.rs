@@ -17,7 +17,10 @@ use std::{
 use chirp_workflow::prelude::*;
 use futures_util::{FutureExt, StreamExt, TryStreamExt};
 
-use crate::types::{Datacenter, PoolType, Provider};
+use crate::{
+	provider,
+	types::{Datacenter, PoolType, Provider},
+};
 
 #[derive(sqlx::FromRow)]
 struct ServerRow {
@@ -95,6 +98,15 @@ pub async fn cluster_scale(ctx: &mut WorkflowCtx, input: &Input) -> GlobalResult
 		})
 		.await?;
 
+	let destroyed_server_ids = diff
+		.actions
+		.iter()
+		.filter_map(|action| match action {
+			Action::Destroy { server_id } => Some(*server_id),
+			_ => None,
+		})
+		.collect::<Vec<_>>();
+
 	if !diff.actions.is_empty() {
 		tracing::info!(actions=?diff.actions, "dispatching signals");
 
@@ -108,9 +120,75 @@ pub async fn cluster_scale(ctx: &mut WorkflowCtx, input: &Input) -> GlobalResult
 		.await?;
 	}
 
+	// Wait for destroyed servers to be cleaned up so they are gone from the provider once scaling completes
+	if !destroyed_server_ids.is_empty() {
+		let server_workflow_ids = ctx
+			.v(2)
+			.activity(FindServerWorkflowsInput {
+				server_ids: destroyed_server_ids,
+			})
+			.await?;
+
+		if !server_workflow_ids.is_empty() {
+			let results = ctx
+				.v(2)
+				.join_all_sub_workflows::<crate::workflows::server::Workflow2>(&server_workflow_ids)
+				.await?;
+
+			for (server_workflow_id, res) in server_workflow_ids.iter().zip(results) {
+				match res {
+					SubWorkflowResult::Complete(()) => {}
+					SubWorkflowResult::Failed(err) => {
+						tracing::warn!(?server_workflow_id, %err, "failed to destroy server");
+					}
+					SubWorkflowResult::Cancelled => {
+						tracing::warn!(
+							?server_workflow_id,
+							"server workflow cancelled while destroying"
+						);
+					}
+				}
+			}
+		}
+	}
+
 	Ok(())
 }
 
+#[derive(Debug, Serialize, Deserialize, Hash)]
+struct FindServerWorkflowsInput {
+	server_ids: Vec<Uuid>,
+}
+
+/// Finds the workflows of the given servers that have not completed yet.
+#[activity(FindServerWorkflows)]
+async fn find_server_workflows(
+	ctx: &ActivityCtx,
+	input: &FindServerWorkflowsInput,
+) -> GlobalResult<Vec<Uuid>> {
+	let mut server_workflow_ids = Vec::with_capacity(input.server_ids.len());
+
+	for server_id in &input.server_ids {
+		let tags = ("server_id", *server_id);
+
+		// Servers provisioned before `Workflow2` still run the old workflow
+		let server_workflow_id = match ctx
+			.find_workflow::<crate::workflows::server::Workflow2>(tags)
+			.await?
+		{
+			Some(server_workflow_id) => Some(server_workflow_id),
+			None => {
+				ctx.find_workflow::<crate::workflows::server::Workflow>(tags)
+					.await?
+			}
+		};
+
+		server_workflow_ids.extend(server_workflow_id);
+	}
+
+	Ok(server_workflow_ids)
+}
+
 #[derive(Debug, Serialize, Deserialize, Hash)]
 struct CalculateDiffInput {
 	datacenter_id: Uuid,
@@ -443,11 +521,7 @@ async fn scale_down_job_servers(
 
 	let destroy_count = match pctx.provider {
 		Provider::Manual => unreachable!("cannot scale manual cluster"),
-		// Never destroy servers when scaling down with Linode, always drain.
-		//
-		// See _Provider Billing Internals_ in docs
-		Provider::Linode => 0,
-		#[allow(unreachable_patterns)]
+		provider if provider::get(provider).drain_on_scale_down() => 0,
 		_ => diff.min(without_nomad_servers.len()),
 	};
 	let drain_count = diff - destroy_count;
@@ -498,11 +572,7 @@ async fn scale_down_pb_servers(
 
 	let destroy_count = match pctx.provider {
 		Provider::Manual => unreachable!("cannot scale manual cluster"),
-		// Never destroy servers when scaling down with Linode, always drain
-		//
-		// See _Provider Billing Internals_ in docs
-		Provider::Linode => 0,
-		#[allow(unreachable_patterns)]
+		provider if provider::get(provider).drain_on_scale_down() => 0,
 		_ => diff.min(without_pb_servers.len()),
 	};
 	let drain_count = diff - destroy_count;
@@ -0,0 +1,95 @@
+use std::net::Ipv4Addr;
+
+use chirp_workflow::prelude::*;
+use rand::Rng;
+
+use crate::provider::fake::FakeHardware;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Input {
+	pub server_id: Uuid,
+	pub hardware: String,
+}
+
+/// Simulates the lifecycle of a server with a cloud provider. See `FakeProvider`.
+#[workflow]
+pub(crate) async fn cluster_fake_server(ctx: &mut WorkflowCtx, input: &Input) -> GlobalResult<()> {
+	let provision_res = ctx
+		.activity(ProvisionInput {
+			hardware: input.hardware.clone(),
+		})
+		.await?;
+
+	ctx.sleep(provision_res.provision_latency).await?;
+
+	let Some(public_ip) = provision_res.public_ip else {
+		tracing::warn!(server_id=?input.server_id, "simulating provision failure");
+
+		ctx.signal(ProvisionFailed {})
+			.tag("server_id", input.server_id)
+			.send()
+			.await?;
+
+		return Ok(());
+	};
+
+	ctx.signal(ProvisionComplete {
+		provider_server_id: format!("fake-{}", input.server_id),
+		public_ip,
+	})
+	.tag("server_id", input.server_id)
+	.send()
+	.await?;
+
+	// Wait for destroy signal
+	ctx.listen::<Destroy>().await?;
+
+	ctx.sleep(provision_res.destroy_latency).await?;
+
+	Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize, Hash)]
+struct ProvisionInput {
+	hardware: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Hash)]
+struct ProvisionOutput {
+	provision_latency: i64,
+	destroy_latency: i64,
+	/// None if provisioning failed.
+	public_ip: Option<Ipv4Addr>,
+}
+
+#[activity(Provision)]
+async fn provision(_ctx: &ActivityCtx, input: &ProvisionInput) -> GlobalResult<ProvisionOutput> {
+	let hardware = FakeHardware::parse(&input.hardware)?;
+	let mut rng = rand::thread_rng();
+
+	let failed = rng.gen_range(0..100) < hardware.failure_percent;
+
+	Ok(ProvisionOutput {
+		provision_latency: hardware.provision_latency,
+		destroy_latency: hardware.destroy_latency,
+		// TEST-NET-2 address, not routable
+		public_ip: (!failed).then(|| Ipv4Addr::new(198, 51, 100, rng.gen_range(1..255))),
+	})
+}
+
+#[signal("cluster_fake_server_provision_complete")]
+pub struct ProvisionComplete {
+	pub provider_server_id: String,
+	pub public_ip: Ipv4Addr,
+}
+
+#[signal("cluster_fake_server_provision_failed")]
+pub struct ProvisionFailed {}
+
+#[signal("cluster_fake_server_destroy")]
+pub struct Destroy {}
+
+join_signal!(pub(crate) FakeServer {
+	ProvisionComplete,
+	ProvisionFailed,
+});
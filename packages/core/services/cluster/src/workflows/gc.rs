@@ -1,17 +1,34 @@
 use std::convert::TryInto;
 
 use chirp_workflow::prelude::*;
-use cluster::types::PoolType;
 use futures_util::FutureExt;
 
-pub async fn start(config: rivet_config::Config, pools: rivet_pools::Pools) -> GlobalResult<()> {
-	let mut interval = tokio::time::interval(std::time::Duration::from_secs(120));
-	loop {
-		interval.tick().await;
+use crate::types::PoolType;
 
-		let ts = util::timestamp::now();
-		run_from_env(config.clone(), pools.clone(), ts).await?;
+/// How often draining servers are checked for completion.
+pub const GC_INTERVAL: std::time::Duration = std::time::Duration::from_secs(120);
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Input {}
+
+/// Marks draining servers that passed their pool's drain timeout as drained and scales their datacenters.
+/// Dispatched every `GC_INTERVAL` by the `cluster_gc` schedule.
+#[workflow]
+pub async fn cluster_gc(ctx: &mut WorkflowCtx, _input: &Input) -> GlobalResult<()> {
+	let datacenter_ids = ctx
+		.activity(MarkDrainedServersInput {
+			ts: ctx.create_ts(),
+		})
+		.await?;
+
+	for datacenter_id in datacenter_ids {
+		ctx.signal(crate::workflows::datacenter::Scale {})
+			.tag("datacenter_id", datacenter_id)
+			.send()
+			.await?;
 	}
+
+	Ok(())
 }
 
 #[derive(sqlx::FromRow)]
@@ -22,23 +39,20 @@ struct ServerRow {
 	drain_ts: i64,
 }
 
-#[tracing::instrument(skip_all)]
-pub async fn run_from_env(
-	config: rivet_config::Config,
-	pools: rivet_pools::Pools,
+#[derive(Debug, Serialize, Deserialize, Hash)]
+struct MarkDrainedServersInput {
 	ts: i64,
-) -> GlobalResult<()> {
-	let client = chirp_client::SharedClient::from_env(pools.clone())?.wrap_new("cluster-gc");
-	let cache = rivet_cache::CacheInner::from_env(&config, pools.clone())?;
-	let ctx = StandaloneCtx::new(
-		db::DatabaseCrdbNats::from_pools(pools.clone())?,
-		config,
-		rivet_connection::Connection::new(client, pools, cache),
-		"cluster-gc",
-	)
-	.await?;
-
-	let datacenter_ids = rivet_pools::utils::crdb::tx(&ctx.crdb().await?, |tx| {
+}
+
+/// Returns the datacenters of the servers that finished draining.
+#[activity(MarkDrainedServers)]
+async fn mark_drained_servers(
+	ctx: &ActivityCtx,
+	input: &MarkDrainedServersInput,
+) -> GlobalResult<Vec<Uuid>> {
+	let ts = input.ts;
+
+	rivet_pools::utils::crdb::tx(&ctx.crdb().await?, |tx| {
 		let ctx = ctx.clone();
 
 		async move {
@@ -61,7 +75,7 @@ pub async fn run_from_env(
 
 			// Fetch relevant datacenters
 			let datacenters_res = ctx
-				.op(cluster::ops::datacenter::get::Input {
+				.op(crate::ops::datacenter::get::Input {
 					datacenter_ids: servers
 						.iter()
 						.map(|server| server.datacenter_id)
@@ -120,22 +134,16 @@ pub async fn run_from_env(
 			)
 			.await?;
 
-			Ok(drained_servers
+			let mut datacenter_ids = drained_servers
 				.into_iter()
 				.map(|(server, _)| server.datacenter_id)
-				.collect::<Vec<_>>())
+				.collect::<Vec<_>>();
+			datacenter_ids.sort();
+			datacenter_ids.dedup();
+
+			Ok(datacenter_ids)
 		}
 		.boxed()
 	})
-	.await?;
-
-	// Scale
-	for datacenter_id in datacenter_ids {
-		ctx.signal(cluster::workflows::datacenter::Scale {})
-			.tag("datacenter_id", datacenter_id)
-			.send()
-			.await?;
-	}
-
-	Ok(())
+	.await
 }
@@ -1,4 +1,6 @@
 pub mod cluster;
 pub mod datacenter;
+pub mod fake_server;
+pub mod gc;
 pub mod prebake;
 pub mod server;
@@ -2,8 +2,9 @@ use chirp_workflow::prelude::*;
 use serde_json::json;
 
 use crate::{
+	provider::{self, CreateImageRequest, ProvisionRequest},
 	types::{PoolType, Provider},
-	workflows::server::{GetDcInput, Linode},
+	workflows::server::GetDcInput,
 };
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -28,95 +29,64 @@ pub async fn cluster_prebake(ctx: &mut WorkflowCtx, input: &Input) -> GlobalResu
 	let mut tags = input.tags.clone();
 	tags.push("prebake".to_string());
 
-	match input.provider {
-		Provider::Manual => {
-			// Noop
+	let provider = provider::get(input.provider);
+	let provision_res = provider
+		.provision(
+			ctx,
+			&ProvisionRequest {
+				server_id: prebake_server_id,
+				pool_type: input.pool_type,
+				provider_datacenter_id: dc.provider_datacenter_id.clone(),
+				api_token: dc.provider_api_token.clone(),
+				hardware: provider.prebake_hardware().to_string(),
+				custom_image: None,
+				vlan_ip: None,
+				vlan_ip_net: None,
+				tags,
+			},
+		)
+		.await?;
+
+	if let Some(provision_res) = provision_res {
+		// Install server
+		if provider.requires_install() {
+			ctx.workflow(crate::workflows::server::install::Input {
+				datacenter_id: input.datacenter_id,
+				server_id: None,
+				public_ip: provision_res.public_ip,
+				pool_type: input.pool_type,
+				initialize_immediately: false,
+			})
+			.output()
+			.await?;
 		}
-		Provider::Linode => {
-			let workflow_id = ctx
-				.workflow(linode::workflows::server::Input {
-					server_id: prebake_server_id,
-					provider_datacenter_id: dc.provider_datacenter_id.clone(),
-					custom_image: None,
+
+		// Create image
+		let image_id = provider
+			.create_image(
+				ctx,
+				&CreateImageRequest {
+					prebake_server_id,
 					api_token: dc.provider_api_token.clone(),
-					hardware: linode::util::consts::PREBAKE_HARDWARE.to_string(),
-					firewall_preset: match input.pool_type {
-						PoolType::Job | PoolType::Pegboard | PoolType::PegboardIsolate => {
-							linode::types::FirewallPreset::Job
-						}
-						PoolType::Gg | PoolType::Guard => linode::types::FirewallPreset::Gg,
-						PoolType::Ats => linode::types::FirewallPreset::Ats,
-						PoolType::Fdb => linode::types::FirewallPreset::Fdb,
-						PoolType::Worker => linode::types::FirewallPreset::Worker,
-						PoolType::Nats => linode::types::FirewallPreset::Nats,
-					},
-					vlan_ip: None,
-					vlan_ip_net: None,
-					tags,
-				})
-				.tag("server_id", prebake_server_id)
-				.dispatch()
-				.await?;
-
-			match ctx.listen::<Linode>().await? {
-				Linode::ProvisionComplete(sig) => {
-					// Install server
-					ctx.workflow(crate::workflows::server::install::Input {
-						datacenter_id: input.datacenter_id,
-						server_id: None,
-						public_ip: sig.public_ip,
-						pool_type: input.pool_type,
-						initialize_immediately: false,
-					})
-					.output()
-					.await?;
-
-					// Create image
-					let workflow_id = ctx
-						.workflow(linode::workflows::image::Input {
-							prebake_server_id,
-							api_token: dc.provider_api_token.clone(),
-							linode_id: sig.linode_id,
-							boot_disk_id: sig.boot_disk_id,
-						})
-						.tag("linode_id", sig.linode_id)
-						.dispatch()
-						.await?;
-
-					// Wait for image creation
-					let image_create_res = ctx
-						.listen::<linode::workflows::image::CreateComplete>()
-						.await?;
-
-					// Write image id to db
-					ctx.activity(UpdateDbInput {
-						provider: input.provider,
-						datacenter_id: input.datacenter_id,
-						pool_type: input.pool_type,
-						install_script_hash: input.install_script_hash.clone(),
-						image_id: image_create_res.image_id,
-					})
-					.await?;
-
-					// Destroy linode server after the image is complete
-					ctx.signal(linode::workflows::server::Destroy {})
-						.tag("server_id", prebake_server_id)
-						.send()
-						.await?;
-
-					// Wait for image workflow to get cleaned up by linode-gc after the image expires
-					ctx.workflow::<linode::workflows::server::Input>(workflow_id)
-						.output()
-						.await?;
-				}
-				Linode::ProvisionFailed(_) => {
-					tracing::error!(
-						provision_workflow_id=%workflow_id,
-						"failed to provision prebake server"
-					);
-				}
-			}
-		}
+					server: provision_res.clone(),
+				},
+			)
+			.await?;
+
+		// Write image id to db
+		ctx.activity(UpdateDbInput {
+			provider: input.provider,
+			datacenter_id: input.datacenter_id,
+			pool_type: input.pool_type,
+			install_script_hash: input.install_script_hash.clone(),
+			image_id,
+		})
+		.await?;
+
+		// Destroy prebake server after the image is complete
+		provider
+			.destroy(ctx, provision_res.provider_server_workflow_id)
+			.await?;
 	}
 
 	ctx.activity(SetDestroyedInput {
@@ -132,6 +132,14 @@ pub fn configure(config: &rivet_config::Config) -> GlobalResult<String> {
 					api_key_path: PathBuf::from("/etc/rivet-server/tls/api_key.pem"),				
 				}
 			}),
+			tcp: Some(rivet_config::config::guard::PortRange {
+				min: server_config.rivet.guard.min_ingress_port_tcp(),
+				max: server_config.rivet.guard.max_ingress_port_tcp(),
+			}),
+			udp: Some(rivet_config::config::guard::PortRange {
+				min: server_config.rivet.guard.min_ingress_port_udp(),
+				max: server_config.rivet.guard.max_ingress_port_udp(),
+			}),
 		}),
 	};
 	let mut guard_config_json = serde_json::to_value(&guard_config)?;
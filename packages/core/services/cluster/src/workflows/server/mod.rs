@@ -18,6 +18,7 @@ pub(crate) mod undrain;
 
 use crate::{
 	metrics,
+	provider::{self, ProvisionRequest},
 	types::{Pool, PoolType, Provider},
 };
 
@@ -38,7 +39,19 @@ pub(crate) async fn cluster_server2(ctx: &mut WorkflowCtx, input: &Input2) -> Gl
 			let input = input.clone();
 			let dc = dc.clone();
 
-			async move { lifecycle(ctx, &input, &dc, state).await }.boxed()
+			async move {
+				// Delete DNS records if this workflow is cancelled during this iteration. Runs before the
+				// server is destroyed
+				if state.has_dns {
+					let input = input.clone();
+					ctx.on_cancel(closure(move |ctx| {
+						async move { delete_dns(ctx, &input).await }.boxed()
+					}));
+				}
+
+				lifecycle(ctx, &input, &dc, state).await
+			}
+			.boxed()
 		})
 		.await?;
 
@@ -150,7 +163,8 @@ async fn provision_server(
 	} else {
 		None
 	};
-	let already_installed = custom_image.is_some();
+	let already_installed =
+		custom_image.is_some() || !provider::get(dc.provider).requires_install();
 
 	// Iterate through list of hardware and attempt to schedule a server. Goes to the next
 	// hardware if an error happens during provisioning
@@ -166,54 +180,30 @@ async fn provision_server(
 			hardware.provider_hardware,
 		);
 
-		match dc.provider {
-			Provider::Manual => {
-				// Noop
-			}
-			Provider::Linode => {
-				let workflow_id = ctx
-					.workflow(linode::workflows::server::Input {
-						server_id: input.server_id,
-						provider_datacenter_id: dc.provider_datacenter_id.clone(),
-						custom_image: custom_image.clone(),
-						api_token: dc.provider_api_token.clone(),
-						hardware: hardware.provider_hardware.clone(),
-						firewall_preset: match input.pool_type {
-							PoolType::Job | PoolType::Pegboard | PoolType::PegboardIsolate => {
-								linode::types::FirewallPreset::Job
-							}
-							PoolType::Gg | PoolType::Guard => linode::types::FirewallPreset::Gg,
-							PoolType::Ats => linode::types::FirewallPreset::Ats,
-							PoolType::Fdb => linode::types::FirewallPreset::Fdb,
-							PoolType::Worker => linode::types::FirewallPreset::Worker,
-							PoolType::Nats => linode::types::FirewallPreset::Nats,
-						},
-						vlan_ip: Some(vlan_ip.ip()),
-						vlan_ip_net: Some(vlan_ip.ip_net()),
-						tags: input.tags.clone(),
-					})
-					.tag("server_id", input.server_id)
-					.dispatch()
-					.await?;
+		let provision_res = provider::get(dc.provider)
+			.provision(
+				ctx,
+				&ProvisionRequest {
+					server_id: input.server_id,
+					pool_type: input.pool_type,
+					provider_datacenter_id: dc.provider_datacenter_id.clone(),
+					api_token: dc.provider_api_token.clone(),
+					hardware: hardware.provider_hardware.clone(),
+					custom_image: custom_image.clone(),
+					vlan_ip: Some(vlan_ip.ip()),
+					vlan_ip_net: Some(vlan_ip.ip_net()),
+					tags: input.tags.clone(),
+				},
+			)
+			.await?;
 
-				match ctx.listen::<Linode>().await? {
-					Linode::ProvisionComplete(sig) => {
-						break Some(ProvisionResponse {
-							provider_server_workflow_id: workflow_id,
-							provider_server_id: sig.linode_id.to_string(),
-							provider_hardware: hardware.provider_hardware.clone(),
-							public_ip: sig.public_ip,
-						});
-					}
-					Linode::ProvisionFailed(_) => {
-						tracing::error!(
-							provision_workflow_id=%workflow_id,
-							server_id=?input.server_id,
-							"failed to provision server"
-						);
-					}
-				}
-			}
+		if let Some(provision_res) = provision_res {
+			break Some(ProvisionResponse {
+				provider_server_workflow_id: provision_res.provider_server_workflow_id,
+				provider_server_id: provision_res.provider_server_id,
+				provider_hardware: hardware.provider_hardware.clone(),
+				public_ip: provision_res.public_ip,
+			});
 		}
 	};
 
@@ -235,6 +225,28 @@ async fn provision_server(
 		})
 		.await?;
 
+		// Destroy the server if this workflow is cancelled
+		let cleanup_input = input.clone();
+		let provider = dc.provider;
+		ctx.on_cancel(closure(move |ctx| {
+			async move {
+				ctx.activity(MarkDestroyedInput {
+					server_id: cleanup_input.server_id,
+				})
+				.await?;
+
+				cleanup(
+					ctx,
+					&cleanup_input,
+					&provider,
+					provider_server_workflow_id,
+					false,
+				)
+				.await
+			}
+			.boxed()
+		}));
+
 		// Install components on server
 		if !already_installed {
 			let install_res = ctx
@@ -933,44 +945,36 @@ async fn cleanup(
 	cleanup_dns: bool,
 ) -> GlobalResult<()> {
 	if cleanup_dns {
-		// Cleanup DNS
-		match input.pool_type {
-			PoolType::Gg => {
-				ctx.workflow(gg_dns_delete::Input {
-					server_id: input.server_id,
-				})
-				.output()
-				.await?;
-			}
-			PoolType::Guard => {
-				ctx.workflow(guard_dns_delete::Input {
-					server_id: input.server_id,
-				})
-				.output()
-				.await?;
-			}
-			_ => {}
-		}
+		delete_dns(ctx, input).await?;
 	}
 
 	// Cleanup server
-	match provider {
-		Provider::Manual => {
-			// Noop
-		}
-		Provider::Linode => {
-			tracing::info!(server_id=?input.server_id, "destroying linode server");
+	tracing::info!(server_id=?input.server_id, ?provider, "destroying server");
 
-			ctx.signal(linode::workflows::server::Destroy {})
-				.to_workflow_id(provider_server_workflow_id)
-				.send()
-				.await?;
+	provider::get(*provider)
+		.destroy(ctx, provider_server_workflow_id)
+		.await?;
 
-			// Wait for workflow to complete
-			ctx.workflow::<linode::workflows::server::Input>(provider_server_workflow_id)
-				.output()
-				.await?;
+	Ok(())
+}
+
+async fn delete_dns(ctx: &mut WorkflowCtx, input: &Input2) -> GlobalResult<()> {
+	match input.pool_type {
+		PoolType::Gg => {
+			ctx.workflow(gg_dns_delete::Input {
+				server_id: input.server_id,
+			})
+			.output()
+			.await?;
 		}
+		PoolType::Guard => {
+			ctx.workflow(guard_dns_delete::Input {
+				server_id: input.server_id,
+			})
+			.output()
+			.await?;
+		}
+		_ => {}
 	}
 
 	Ok(())
@@ -1077,14 +1081,6 @@ impl Default for State {
 	}
 }
 
-// Listen for linode provision signals
-type ProvisionComplete = linode::workflows::server::ProvisionComplete;
-type ProvisionFailed = linode::workflows::server::ProvisionFailed;
-join_signal!(pub(crate) Linode {
-	ProvisionComplete,
-	ProvisionFailed,
-});
-
 #[signal("cluster_server_drain")]
 pub struct Drain {}
 
@@ -24,6 +24,8 @@ pub async fn setup(ctx: &TestCtx, opts: Setup) -> SetupRes {
 		min_count: 0,
 		max_count: 0,
 		drain_timeout: opts.drain_timeout,
+		margin: 0,
+		autoscale: None,
 	}];
 	let provider = cluster::types::Provider::Linode;
 
@@ -0,0 +1,320 @@
+use std::{net::IpAddr, time::Instant};
+
+use chirp_workflow::prelude::*;
+use cluster::{provider::fake::FakeHardware, util::test::FAKE_HARDWARE};
+use serde_json::json;
+
+/// Always fails provisioning.
+const FAILING_HARDWARE: &str = "fake-1?failure_percent=100";
+
+#[derive(Debug, sqlx::FromRow)]
+struct ServerRow {
+	provider_hardware: Option<String>,
+	public_ip: Option<IpAddr>,
+	install_complete_ts: Option<i64>,
+	drain_ts: Option<i64>,
+	cloud_destroy_ts: Option<i64>,
+}
+
+fn worker_pool(hardware: &[&str], desired_count: u32) -> cluster::types::Pool {
+	cluster::types::Pool {
+		pool_type: cluster::types::PoolType::Worker,
+		hardware: hardware
+			.iter()
+			.map(|provider_hardware| cluster::types::Hardware {
+				provider_hardware: provider_hardware.to_string(),
+			})
+			.collect(),
+		desired_count,
+		min_count: 0,
+		max_count: 3,
+		drain_timeout: 0,
+		margin: 0,
+		autoscale: None,
+	}
+}
+
+/// Creates a cluster with a fake datacenter. Creating the datacenter scales the pool up to the desired
+/// count.
+async fn setup(ctx: &TestCtx, pool: cluster::types::Pool) -> Uuid {
+	let datacenter_id = Uuid::new_v4();
+	let cluster_id = Uuid::new_v4();
+
+	let mut sub = ctx
+		.subscribe::<cluster::workflows::cluster::CreateComplete>(&json!({
+			"cluster_id": cluster_id,
+		}))
+		.await
+		.unwrap();
+
+	ctx.workflow(cluster::workflows::cluster::Input {
+		cluster_id,
+		name_id: util::faker::ident(),
+		owner_team_id: None,
+	})
+	.tag("cluster_id", cluster_id)
+	.dispatch()
+	.await
+	.unwrap();
+
+	sub.next().await.unwrap();
+
+	ctx.signal(cluster::workflows::cluster::DatacenterCreate {
+		datacenter_id,
+		name_id: util::faker::ident(),
+		display_name: util::faker::ident(),
+
+		provider: cluster::types::Provider::Fake,
+		provider_datacenter_id: "fake".to_string(),
+		provider_api_token: None,
+
+		pools: vec![pool],
+
+		build_delivery_method: cluster::types::BuildDeliveryMethod::TrafficServer,
+		prebakes_enabled: false,
+	})
+	.tag("cluster_id", cluster_id)
+	.send()
+	.await
+	.unwrap();
+
+	datacenter_id
+}
+
+async fn set_desired_count(ctx: &TestCtx, datacenter_id: Uuid, desired_count: u32) {
+	ctx.signal(cluster::workflows::datacenter::Update {
+		pools: vec![cluster::types::PoolUpdate {
+			pool_type: cluster::types::PoolType::Worker,
+			hardware: Vec::new(),
+			desired_count: Some(desired_count),
+			min_count: None,
+			max_count: None,
+			drain_timeout: None,
+			margin: None,
+			autoscale: None,
+		}],
+		prebakes_enabled: None,
+		guard_public_hostname: None,
+	})
+	.tag("datacenter_id", datacenter_id)
+	.send()
+	.await
+	.unwrap();
+}
+
+async fn get_servers(ctx: &TestCtx, datacenter_id: Uuid) -> Vec<ServerRow> {
+	sql_fetch_all!(
+		[ctx, ServerRow]
+		"
+		SELECT provider_hardware, public_ip, install_complete_ts, drain_ts, cloud_destroy_ts
+		FROM db_cluster.servers
+		WHERE datacenter_id = $1
+		",
+		datacenter_id,
+	)
+	.await
+	.unwrap()
+}
+
+/// Polls the servers of the datacenter until they match the predicate.
+async fn wait_for_servers(
+	ctx: &TestCtx,
+	datacenter_id: Uuid,
+	msg: &str,
+	f: impl Fn(&[ServerRow]) -> bool,
+) -> Vec<ServerRow> {
+	let mut attempts = 0;
+	loop {
+		tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+
+		let servers = get_servers(ctx, datacenter_id).await;
+		if f(&servers) {
+			break servers;
+		}
+
+		attempts += 1;
+		assert!(attempts < 30, "{msg}: {servers:?}");
+	}
+}
+
+/// Servers that were provisioned and not destroyed.
+fn active(servers: &[ServerRow]) -> impl Iterator<Item = &ServerRow> {
+	servers
+		.iter()
+		.filter(|server| server.public_ip.is_some() && server.cloud_destroy_ts.is_none())
+}
+
+#[workflow_test]
+async fn server_provision_fake(ctx: TestCtx) {
+	let datacenter_id = setup(&ctx, worker_pool(&[FAKE_HARDWARE], 1)).await;
+
+	let servers = wait_for_servers(
+		&ctx,
+		datacenter_id,
+		"server was not provisioned",
+		|servers| active(servers).count() == 1,
+	)
+	.await;
+	let server = active(&servers).next().unwrap();
+
+	let Some(IpAddr::V4(public_ip)) = server.public_ip else {
+		panic!("unexpected ipv6 address");
+	};
+	assert_eq!(
+		&public_ip.octets()[..3],
+		&[198, 51, 100],
+		"public ip not in fake range"
+	);
+	assert!(
+		server.install_complete_ts.is_some(),
+		"fake servers do not require install"
+	);
+}
+
+#[workflow_test]
+async fn server_provision_fake_latency(ctx: TestCtx) {
+	let start = Instant::now();
+	let datacenter_id = setup(&ctx, worker_pool(&["fake-1?provision_latency=5000"], 1)).await;
+
+	// The server is created right away but does not have an ip until the latency passed
+	let servers = wait_for_servers(&ctx, datacenter_id, "server was not created", |servers| {
+		!servers.is_empty()
+	})
+	.await;
+	if start.elapsed().as_millis() < 4000 {
+		assert!(
+			servers.iter().all(|server| server.public_ip.is_none()),
+			"server provisioned before latency passed"
+		);
+	}
+
+	wait_for_servers(
+		&ctx,
+		datacenter_id,
+		"server was not provisioned",
+		|servers| active(servers).count() == 1,
+	)
+	.await;
+	assert!(
+		start.elapsed().as_millis() >= 5000,
+		"server provisioned after {:?}",
+		start.elapsed()
+	);
+}
+
+#[workflow_test]
+async fn server_provision_fake_failure(ctx: TestCtx) {
+	let datacenter_id = setup(&ctx, worker_pool(&[FAILING_HARDWARE], 1)).await;
+
+	// Failed servers are destroyed and replaced with new attempts
+	let servers = wait_for_servers(
+		&ctx,
+		datacenter_id,
+		"failed servers were not replaced",
+		|servers| {
+			servers
+				.iter()
+				.filter(|server| server.cloud_destroy_ts.is_some())
+				.count() >= 2
+		},
+	)
+	.await;
+	assert!(
+		servers.iter().all(|server| server.public_ip.is_none()),
+		"server provisioned with failing hardware"
+	);
+
+	// Stop retrying
+	set_desired_count(&ctx, datacenter_id, 0).await;
+}
+
+#[workflow_test]
+async fn server_provision_fake_failover(ctx: TestCtx) {
+	let datacenter_id = setup(&ctx, worker_pool(&[FAILING_HARDWARE, FAKE_HARDWARE], 1)).await;
+
+	// Provisioning falls back to the next hardware in the pool
+	let servers = wait_for_servers(
+		&ctx,
+		datacenter_id,
+		"server was not provisioned",
+		|servers| active(servers).count() == 1,
+	)
+	.await;
+	assert_eq!(
+		Some(FAKE_HARDWARE),
+		active(&servers)
+			.next()
+			.unwrap()
+			.provider_hardware
+			.as_deref()
+	);
+	assert_eq!(
+		1,
+		servers.len(),
+		"failover should not create another server"
+	);
+}
+
+#[workflow_test]
+async fn server_scale_fake(ctx: TestCtx) {
+	let datacenter_id = setup(&ctx, worker_pool(&[FAKE_HARDWARE], 1)).await;
+
+	wait_for_servers(
+		&ctx,
+		datacenter_id,
+		"server was not provisioned",
+		|servers| active(servers).count() == 1,
+	)
+	.await;
+
+	// Scale up
+	set_desired_count(&ctx, datacenter_id, 3).await;
+	wait_for_servers(&ctx, datacenter_id, "pool was not scaled up", |servers| {
+		active(servers).count() == 3
+	})
+	.await;
+
+	// Scaling down drains servers instead of destroying them right away
+	set_desired_count(&ctx, datacenter_id, 1).await;
+	wait_for_servers(&ctx, datacenter_id, "servers were not drained", |servers| {
+		active(servers)
+			.filter(|server| server.drain_ts.is_some())
+			.count() == 2
+	})
+	.await;
+
+	// Drained servers are destroyed once gc marks them as drained
+	ctx.workflow(cluster::workflows::gc::Input {})
+		.output()
+		.await
+		.unwrap();
+	let servers = wait_for_servers(
+		&ctx,
+		datacenter_id,
+		"drained servers were not destroyed",
+		|servers| active(servers).count() == 1,
+	)
+	.await;
+	assert!(
+		active(&servers).all(|server| server.drain_ts.is_none()),
+		"the remaining server should not be draining"
+	);
+}
+
+#[test]
+fn fake_hardware_parse() {
+	let hardware = FakeHardware::parse("fake-1").unwrap();
+	assert_eq!(0, hardware.provision_latency);
+	assert_eq!(0, hardware.failure_percent);
+
+	let hardware = FakeHardware::parse(
+		"fake-1?provision_latency=5000&destroy_latency=1000&failure_percent=10",
+	)
+	.unwrap();
+	assert_eq!(5000, hardware.provision_latency);
+	assert_eq!(1000, hardware.destroy_latency);
+	assert_eq!(10, hardware.failure_percent);
+
+	assert!(FakeHardware::parse("fake-1?failure_percent=101").is_err());
+	assert!(FakeHardware::parse("fake-1?provision_latency=-1").is_err());
+}
@@ -1,4 +1,4 @@
-use std::{net::Ipv4Addr, str, time::Duration};
+use std::{net::Ipv4Addr, str};
 
 use chirp_workflow::prelude::*;
 use chrono::{DateTime, Utc};
@@ -284,26 +284,20 @@ pub struct LinodeInstanceResponse {
 }
 
 // Helpful: https://www.linode.com/community/questions/11588/linodeerrorsapierror-400-linode-busy
-/// Polls linode API until an instance is available.
-pub async fn wait_instance_ready(client: &Client, linode_id: u64) -> GlobalResult<()> {
-	tracing::info!("waiting for instance to be ready");
-
-	loop {
-		let res = client
-			.get::<LinodeInstanceResponse>(&format!("/linode/instances/{linode_id}"))
-			.await?;
-
-		// Check if ready
-		match res.status.as_str() {
-			"booting" | "rebooting" | "shutting_down" | "provisioning" | "deleting"
-			| "migrating" | "rebuilding" | "cloning" | "restoring" => {}
-			_ => break,
-		}
+/// Checks if an instance is available. Errors if it is not ready yet so that the calling activity is retried.
+pub async fn check_instance_ready(client: &Client, linode_id: u64) -> GlobalResult<()> {
+	let res = client
+		.get::<LinodeInstanceResponse>(&format!("/linode/instances/{linode_id}"))
+		.await?;
 
-		tokio::time::sleep(Duration::from_secs(1)).await;
+	// Check if ready
+	match res.status.as_str() {
+		"booting" | "rebooting" | "shutting_down" | "provisioning" | "deleting" | "migrating"
+		| "rebuilding" | "cloning" | "restoring" => {
+			bail!("instance not ready yet (status: {})", res.status);
+		}
+		_ => Ok(()),
 	}
-
-	Ok(())
 }
 
 #[derive(Deserialize)]
@@ -311,38 +305,32 @@ pub struct LinodeDiskResponse {
 	status: String,
 }
 
-/// Polls linode API until a linode disk is available.
-pub async fn wait_disk_ready(client: &Client, linode_id: u64, disk_id: u64) -> GlobalResult<()> {
-	tracing::info!("waiting for linode disk to be ready");
+/// Checks if a linode disk is available. Errors if it is not ready yet so that the calling activity is
+/// retried.
+pub async fn check_disk_ready(client: &Client, linode_id: u64, disk_id: u64) -> GlobalResult<()> {
+	let res = client
+		.inner()
+		.get(format!(
+			"https://api.linode.com/v4/linode/instances/{linode_id}/disks/{disk_id}"
+		))
+		.send()
+		.await?;
+
+	// Manually handle the disk showing up as not found yet
+	if res.status() == reqwest::StatusCode::NOT_FOUND {
+		bail!("disk not found yet");
+	}
 
-	loop {
-		let res = client
-			.inner()
-			.get(format!(
-				"https://api.linode.com/v4/linode/instances/{linode_id}/disks/{disk_id}"
-			))
-			.send()
-			.await?;
+	if !res.status().is_success() {
+		tracing::info!(status=?res.status(), "api request failed");
+		bail_with!(ERROR, error = res.json::<ApiErrorResponse>().await?);
+	}
 
-		// Manually handle the disk showing up as not found yet
-		if res.status() == reqwest::StatusCode::NOT_FOUND {
-			tracing::info!("disk not found yet");
-		} else {
-			if !res.status().is_success() {
-				tracing::info!(status=?res.status(), "api request failed");
-				bail_with!(ERROR, error = res.json::<ApiErrorResponse>().await?);
-			}
-
-			let res = res.json::<LinodeDiskResponse>().await?;
-
-			// Check if ready
-			match res.status.as_str() {
-				"not ready" => {}
-				_ => break,
-			}
-		}
+	let res = res.json::<LinodeDiskResponse>().await?;
 
-		tokio::time::sleep(Duration::from_secs(3)).await;
+	// Check if ready
+	if res.status == "not ready" {
+		bail!("disk not ready yet");
 	}
 
 	Ok(())
@@ -300,14 +300,17 @@ struct WaitInstanceReadyInput {
 }
 
 #[activity(WaitInstanceReady)]
-#[timeout = 60]
+#[max_retries = 60]
+#[backoff_wait = 1000]
+#[backoff_max_exponent = 0]
+#[backoff_randomness = 250]
 async fn wait_instance_ready(
 	ctx: &ActivityCtx,
 	input: &WaitInstanceReadyInput,
 ) -> GlobalResult<()> {
 	let client = client::Client::new(ctx.config(), input.api_token.clone()).await?;
 
-	api::wait_instance_ready(&client, input.linode_id).await
+	api::check_instance_ready(&client, input.linode_id).await
 }
 
 #[derive(Debug, Serialize, Deserialize, Hash)]
@@ -341,10 +344,14 @@ struct WaitDiskReadyInput {
 }
 
 #[activity(WaitDiskReady)]
+#[max_retries = 60]
+#[backoff_wait = 3000]
+#[backoff_max_exponent = 0]
+#[backoff_randomness = 500]
 async fn wait_disk_ready(ctx: &ActivityCtx, input: &WaitDiskReadyInput) -> GlobalResult<()> {
 	let client = client::Client::new(ctx.config(), input.api_token.clone()).await?;
 
-	api::wait_disk_ready(&client, input.linode_id, input.disk_id).await
+	api::check_disk_ready(&client, input.linode_id, input.disk_id).await
 }
 
 #[derive(Debug, Serialize, Deserialize, Hash)]
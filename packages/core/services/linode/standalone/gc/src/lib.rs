@@ -58,7 +58,7 @@ pub async fn run_from_env(
 		let provider = unwrap!(Provider::from_repr(provider.try_into()?));
 
 		match provider {
-			Provider::Manual => {
+			Provider::Manual | Provider::Fake => {
 				// Noop
 			}
 			Provider::Linode => {
@@ -22,11 +22,13 @@ fn convert_datacenter(
 		provider: match datacenter.provider {
 			cluster::types::Provider::Manual => "manual".to_string(),
 			cluster::types::Provider::Linode => "linode".to_string(),
+			cluster::types::Provider::Fake => "fake".to_string(),
 		},
 		provider_region: datacenter.provider_datacenter_id.clone(),
 		provider_display_name: match datacenter.provider {
 			cluster::types::Provider::Manual => "Manual".to_string(),
 			cluster::types::Provider::Linode => "Linode".to_string(),
+			cluster::types::Provider::Fake => "Fake".to_string(),
 		},
 		region_display_name: datacenter.display_name.clone(),
 		name_id: datacenter.name_id.clone(),
@@ -0,0 +1,2 @@
+ALTER TABLE routes
+    ADD COLUMN load_balancing JSONB; -- route::types::LoadBalancing, NULL uses the default strategy
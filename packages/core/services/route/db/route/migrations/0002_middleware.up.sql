@@ -0,0 +1,8 @@
+ALTER TABLE routes
+    ADD COLUMN middleware JSONB; -- route::types::MiddlewarePolicy, NULL inherits the environment's policy
+
+CREATE TABLE env_middleware (
+    namespace_id UUID PRIMARY KEY,
+    middleware JSONB NOT NULL, -- route::types::MiddlewarePolicy
+    update_ts INT NOT NULL
+);
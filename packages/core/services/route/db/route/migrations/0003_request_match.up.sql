@@ -0,0 +1,3 @@
+ALTER TABLE routes
+    ADD COLUMN request_match JSONB, -- route::types::RouteMatch, NULL matches any request
+    ADD COLUMN target_config JSONB; -- route::types::RouteTarget for targets other than actors
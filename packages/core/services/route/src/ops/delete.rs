@@ -12,17 +12,16 @@ pub struct Output {}
 #[operation]
 pub async fn delete(ctx: &OperationCtx, input: &Input) -> GlobalResult<Output> {
 	// Find the route ID by namespace_id and name_id
-	let route_id = sql_fetch_optional!(
-	[ctx, (Uuid,)]
+	let (route_id, hostname) = sql_fetch_optional!(
+	[ctx, (Uuid, String)]
 		"
-			SELECT route_id FROM db_route.routes 
+			SELECT route_id, hostname FROM db_route.routes 
 			WHERE namespace_id = $1 AND name_id = $2 AND delete_ts IS NULL
 			",
 		input.namespace_id,
 		&input.name_id
 	)
 	.await?
-	.map(|(id,)| id)
 	.ok_or_else(|| err_code!(ROUTE_NOT_FOUND))?;
 
 	// Perform a soft delete by setting delete_ts instead of actually deleting the record
@@ -40,5 +39,7 @@ pub async fn delete(ctx: &OperationCtx, input: &Input) -> GlobalResult<Output> {
 	)
 	.await?;
 
+	crate::utils::purge_route_cache(ctx, input.namespace_id, [hostname]).await?;
+
 	Ok(Output {})
 }
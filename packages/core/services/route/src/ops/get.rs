@@ -26,6 +26,10 @@ pub(crate) struct RouteRow {
 	strip_prefix: bool,
 	route_type: i64,
 	actors_selector_tags: Option<sqlx::types::Json<Box<serde_json::value::RawValue>>>,
+	target_config: Option<sqlx::types::Json<types::RouteTarget>>,
+	request_match: Option<sqlx::types::Json<types::RouteMatch>>,
+	load_balancing: Option<sqlx::types::Json<types::LoadBalancing>>,
+	middleware: Option<sqlx::types::Json<types::MiddlewarePolicy>>,
 	create_ts: i64,
 	update_ts: i64,
 	delete_ts: Option<i64>,
@@ -54,8 +58,25 @@ impl TryInto<types::Route> for RouteRow {
 					selector_tags: selector_tags_map,
 				}
 			}
+			types::RouteTargetType::Split
+			| types::RouteTargetType::Redirect
+			| types::RouteTargetType::Response => unwrap!(self.target_config).0,
 		};
 
+		// Routes without a load balancing strategy use the default
+		let load_balancing = self
+			.load_balancing
+			.map(|load_balancing| load_balancing.0)
+			.unwrap_or_default();
+		let middleware = self
+			.middleware
+			.map(|middleware| middleware.0)
+			.unwrap_or_default();
+		let request_match = self
+			.request_match
+			.map(|request_match| request_match.0)
+			.unwrap_or_default();
+
 		Ok(types::Route {
 			route_id: self.route_id,
 			namespace_id: self.namespace_id,
@@ -64,7 +85,10 @@ impl TryInto<types::Route> for RouteRow {
 			path: self.path,
 			route_subpaths: self.route_subpaths,
 			strip_prefix: self.strip_prefix,
+			request_match,
 			target,
+			load_balancing,
+			middleware,
 			create_ts: self.create_ts,
 			update_ts: self.update_ts,
 			delete_ts: self.delete_ts,
@@ -87,6 +111,10 @@ pub async fn get(ctx: &OperationCtx, input: &Input) -> GlobalResult<Output> {
 			strip_prefix,
 			route_type,
 			actors_selector_tags,
+			target_config,
+			request_match,
+			load_balancing,
+			middleware,
 			create_ts,
 			update_ts,
 			delete_ts
@@ -1,4 +1,4 @@
-use std::convert::TryInto;
+use std::{collections::HashMap, convert::TryInto};
 
 use chirp_workflow::prelude::*;
 
@@ -10,6 +10,10 @@ use crate::utils::MAX_PATH_COMPONENTS;
 pub struct Input {
 	pub hostname: String,
 	pub path: String,
+	/// Uppercase HTTP method.
+	pub method: String,
+	/// Values of the request's headers keyed by lowercase name.
+	pub headers: HashMap<String, Vec<String>>,
 }
 
 #[derive(Debug)]
@@ -19,6 +23,9 @@ pub struct Output {
 	/// If this is a subdomain of the routes domain. If true, this hostname should return a 404
 	/// with a custom message.
 	pub is_route_hostname: bool,
+	/// If any of the candidate routes match on the request's method or headers. If true, the resolved
+	/// route can't be reused for other requests with the same hostname and path.
+	pub depends_on_request: bool,
 }
 
 /// Generate all path prefixes for a given path
@@ -51,19 +58,26 @@ pub async fn get_by_hostname_path(ctx: &OperationCtx, input: &Input) -> GlobalRe
 	// Get domain_job from configuration
 	let domain_job = ctx.config().server()?.rivet.domain_job_for_routes()?;
 
-	// Immediately return None if the hostname doesn't end with domain_job
-	// This prevents unnecessary database queries for hostnames that can't possibly match
-	match input.hostname.split_once('.') {
-		Some((_, x)) if x == domain_job => {
-			// Matches pattern
+	// Immediately return None if the hostname isn't `{subdomain}.{domain_job}` or a direct subdomain of it
+	// (for wildcard routes). This prevents unnecessary database queries for hostnames that can't possibly
+	// match
+	let subdomain = input
+		.hostname
+		.strip_suffix(domain_job)
+		.and_then(|x| x.strip_suffix('.'));
+	let (hostname, is_wildcard) = match subdomain.map(|x| x.split_once('.')) {
+		Some(None) => (input.hostname.clone(), false),
+		Some(Some((_, parent))) if !parent.is_empty() && !parent.contains('.') => {
+			(format!("*.{parent}.{domain_job}"), true)
 		}
-		Some((_, _)) | None => {
+		_ => {
 			return Ok(Output {
 				route: None,
 				is_route_hostname: false,
+				depends_on_request: false,
 			});
 		}
-	}
+	};
 
 	// Normalize path format - first strip any query parameters
 	let path_without_query = match input.path.split_once('?') {
@@ -101,76 +115,109 @@ pub async fn get_by_hostname_path(ctx: &OperationCtx, input: &Input) -> GlobalRe
 	// Generate all possible path prefixes for subpath routing
 	let path_prefixes = generate_path_prefixes(&normalized_path);
 
-	// Direct database query to find routes matching hostname and path
-	// This query handles both exact matches and subpath routing in a simple union
-	let rows = sql_fetch_all!(
-		[ctx, RouteRow]
-		"
-        SELECT 
-            route_id,
-            namespace_id,
-            name_id,
-            hostname,
-            path,
-            route_subpaths,
-            strip_prefix,
-            route_type,
-            actors_selector_tags,
-            create_ts,
-            update_ts,
-            delete_ts,
-            100 AS priority -- Exact match gets highest priority
-        FROM 
-            db_route.routes
-        WHERE 
-            hostname = $1
-            AND path = $2
-            AND route_subpaths = false -- Exact matches only
-            AND delete_ts IS NULL
-            
-        UNION ALL
-        
-        SELECT 
-            route_id,
-            namespace_id,
-            name_id,
-            hostname,
-            path,
-            route_subpaths,
-            strip_prefix,
-            route_type,
-            actors_selector_tags,
-            create_ts,
-            update_ts,
-            delete_ts,
-            LENGTH(path) AS priority -- Longer paths get higher priority for subpaths
-        FROM 
-            db_route.routes
-        WHERE 
-            hostname = $1
-            AND path = ANY($3) -- Use the prefixes we generated in Rust
-            AND route_subpaths = true -- Subpath routes only
-            AND delete_ts IS NULL
-            
-        ORDER BY 
-            priority DESC, -- Highest priority first
-            LENGTH(path) DESC -- Longer paths get precedence within the same priority
-        ",
-		&input.hostname,
-		&normalized_path,
-		&path_prefixes
-	)
-	.await?;
-
-	// Convert the top priority row to a Route, or return None if no matches
-	let route = rows
+	// Find routes matching the path. Exact matches get the highest priority, longer paths get higher
+	// priority for subpath routes.
+	let mut routes = fetch_hostname_routes(ctx, &hostname)
+		.await?
+		.into_iter()
+		.filter_map(|route| {
+			let priority = if !route.route_subpaths && route.path == normalized_path {
+				100
+			} else if route.route_subpaths && path_prefixes.contains(&route.path) {
+				route.path.len()
+			} else {
+				return None;
+			};
+
+			Some((priority, route))
+		})
+		.collect::<Vec<_>>();
+
+	// Longer paths get precedence within the same priority. Routes with request conditions are more
+	// specific.
+	routes.sort_by_key(|(priority, route)| {
+		std::cmp::Reverse((*priority, route.path.len(), !route.request_match.is_empty()))
+	});
+	let routes = routes
+		.into_iter()
+		.map(|(_, route)| route)
+		.collect::<Vec<_>>();
+
+	let depends_on_request = routes.iter().any(|route| !route.request_match.is_empty());
+
+	// Get the top priority route (due to ORDER BY) that matches the request
+	let route = routes
 		.into_iter()
-		.next() // Get only the top matching route (highest priority due to ORDER BY)
-		.map(|row| row.try_into())
-		.transpose()?; // Convert Result<Option<T>> to Option<Result<T>>
+		.find(|route| route.request_match.matches(&input.method, &input.headers));
 
 	Ok(Output {
+		// Hostnames that can only match wildcard routes are not route hostnames unless a route matched,
+		// they may belong to other services
+		is_route_hostname: !is_wildcard || route.is_some(),
 		route,
-		is_route_hostname: true,
+		depends_on_request,
 	})
 }
+
+/// Fetches all routes for the hostname. Purged when a route for the hostname is created, updated, or
+/// deleted.
+async fn fetch_hostname_routes(
+	ctx: &OperationCtx,
+	hostname: &str,
+) -> GlobalResult<Vec<types::Route>> {
+	let routes = ctx
+		.cache()
+		.ttl(util::duration::minutes(5))
+		// Most hostnames don't have routes
+		.negative_ttl(util::duration::minutes(1))
+		.fetch_one_json("route.hostname_routes", hostname.to_string(), {
+			let ctx = ctx.clone();
+			move |mut cache, hostname| {
+				let ctx = ctx.clone();
+				async move {
+					let rows = sql_fetch_all!(
+						[ctx, RouteRow]
+						"
+						SELECT
+							route_id,
+							namespace_id,
+							name_id,
+							hostname,
+							path,
+							route_subpaths,
+							strip_prefix,
+							route_type,
+							actors_selector_tags,
+							target_config,
+							request_match,
+							load_balancing,
+							middleware,
+							create_ts,
+							update_ts,
+							delete_ts
+						FROM db_route.routes
+						WHERE
+							hostname = $1
+							AND delete_ts IS NULL
+						",
+						&hostname,
+					)
+					.await?;
+
+					let routes = rows
+						.into_iter()
+						.map(|row| row.try_into())
+						.collect::<GlobalResult<Vec<types::Route>>>()?;
+
+					if !routes.is_empty() {
+						cache.resolve(&hostname, routes);
+					}
+
+					Ok(cache)
+				}
+			}
+		})
+		.await?;
+
+	Ok(routes.unwrap_or_default())
+}
@@ -12,26 +12,47 @@ pub struct Output {
 
 #[operation]
 pub async fn list_for_env(ctx: &OperationCtx, input: &Input) -> GlobalResult<Output> {
-	let route_ids = sql_fetch_all!(
-		[ctx, (Uuid,)]
-		"
-        SELECT
-            route_id
-        FROM
-            db_route.routes
-        WHERE
-            namespace_id = $1
-        AND
-            delete_ts IS NULL
-        ORDER BY
-            create_ts DESC
-        ",
-		input.namespace_id
-	)
-	.await?
-	.into_iter()
-	.map(|(id,)| id)
-	.collect::<Vec<_>>();
+	// Purged when a route in the environment is created or deleted
+	let route_ids = ctx
+		.cache()
+		.ttl(util::duration::minutes(5))
+		.negative_ttl(util::duration::minutes(1))
+		.fetch_one_json("route.env_routes", input.namespace_id, {
+			let ctx = ctx.clone();
+			move |mut cache, namespace_id| {
+				let ctx = ctx.clone();
+				async move {
+					let route_ids = sql_fetch_all!(
+						[ctx, (Uuid,)]
+						"
+						SELECT
+							route_id
+						FROM
+							db_route.routes
+						WHERE
+							namespace_id = $1
+						AND
+							delete_ts IS NULL
+						ORDER BY
+							create_ts DESC
+						",
+						namespace_id
+					)
+					.await?
+					.into_iter()
+					.map(|(id,)| id)
+					.collect::<Vec<_>>();
+
+					if !route_ids.is_empty() {
+						cache.resolve(&namespace_id, route_ids);
+					}
+
+					Ok(cache)
+				}
+			}
+		})
+		.await?
+		.unwrap_or_default();
 
 	Ok(Output { route_ids })
 }
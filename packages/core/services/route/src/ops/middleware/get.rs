@@ -0,0 +1,53 @@
+use chirp_workflow::prelude::*;
+
+use crate::types;
+
+#[derive(Debug)]
+pub struct Input {
+	pub namespace_id: Uuid,
+}
+
+#[derive(Debug)]
+pub struct Output {
+	/// Environment-wide middleware policy. Empty if the environment does not have one.
+	pub middleware: types::MiddlewarePolicy,
+}
+
+#[operation]
+pub async fn route_middleware_get(ctx: &OperationCtx, input: &Input) -> GlobalResult<Output> {
+	// Purged when the environment's middleware is upserted
+	let middleware = ctx
+		.cache()
+		.ttl(util::duration::minutes(5))
+		// Most environments don't have a middleware policy
+		.negative_ttl(util::duration::minutes(1))
+		.fetch_one_json("route.env_middleware", input.namespace_id, {
+			let ctx = ctx.clone();
+			move |mut cache, namespace_id| {
+				let ctx = ctx.clone();
+				async move {
+					let row = sql_fetch_optional!(
+						[ctx, (sqlx::types::Json<types::MiddlewarePolicy>,)]
+						"
+						SELECT middleware
+						FROM db_route.env_middleware
+						WHERE namespace_id = $1
+						",
+						namespace_id
+					)
+					.await?;
+
+					if let Some((middleware,)) = row {
+						cache.resolve(&namespace_id, middleware.0);
+					}
+
+					Ok(cache)
+				}
+			}
+		})
+		.await?;
+
+	Ok(Output {
+		middleware: middleware.unwrap_or_default(),
+	})
+}
@@ -0,0 +1,35 @@
+use chirp_workflow::prelude::*;
+
+use crate::types;
+
+#[derive(Debug)]
+pub struct Input {
+	pub namespace_id: Uuid,
+	pub middleware: types::MiddlewarePolicy,
+}
+
+#[derive(Debug)]
+pub struct Output {}
+
+#[operation]
+pub async fn route_middleware_upsert(ctx: &OperationCtx, input: &Input) -> GlobalResult<Output> {
+	crate::utils::validate_middleware(&input.middleware)?;
+
+	sql_execute!(
+		[ctx]
+		"
+		UPSERT INTO db_route.env_middleware (namespace_id, middleware, update_ts)
+		VALUES ($1, $2, $3)
+		",
+		input.namespace_id,
+		util::serde::Raw::new(&input.middleware)?,
+		ctx.ts(),
+	)
+	.await?;
+
+	ctx.cache()
+		.purge("route.env_middleware", [input.namespace_id])
+		.await?;
+
+	Ok(Output {})
+}
@@ -2,4 +2,5 @@ pub mod delete;
 pub mod get;
 pub mod get_by_hostname_path;
 pub mod list_for_env;
+pub mod middleware;
 pub mod upsert;
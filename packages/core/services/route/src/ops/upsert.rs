@@ -14,8 +14,12 @@ pub struct Input {
 	pub path: String,
 	pub route_subpaths: bool,
 	pub strip_prefix: bool,
-	pub actors_selector_tags: HashMap<String, String>,
-	// Currently only supports actors routes (route_type = 0)
+	pub target: crate::types::RouteTarget,
+	/// Unset fields below keep their existing value when updating a route and use the default when
+	/// creating one.
+	pub request_match: Option<crate::types::RouteMatch>,
+	pub load_balancing: Option<crate::types::LoadBalancing>,
+	pub middleware: Option<crate::types::MiddlewarePolicy>,
 }
 
 #[derive(Debug)]
@@ -32,20 +36,42 @@ pub async fn upsert(ctx: &OperationCtx, input: &Input) -> GlobalResult<Output> {
 	validate_name_id(&input.name_id)?;
 	validate_hostname(domain_job, &input.hostname)?;
 	validate_path(&input.path)?;
-	validate_actors_selector_tags(&input.actors_selector_tags)?;
+	validate_target(&input.target)?;
+	if let Some(request_match) = &input.request_match {
+		validate_request_match(request_match)?;
+	}
+	if let Some(load_balancing) = &input.load_balancing {
+		validate_load_balancing(load_balancing)?;
+	}
+	if let Some(middleware) = &input.middleware {
+		crate::utils::validate_middleware(middleware)?;
+	}
 
 	let now = ctx.ts();
 
-	// Create the route target (Actors type)
-	let target = crate::types::RouteTarget::Actors {
-		selector_tags: input.actors_selector_tags.clone(),
+	// Actors targets are stored as selector tags, all other targets as JSON
+	let route_type = input.target.target_type().as_i64();
+	let (actors_selector_tags_json, target_config_json) = match &input.target {
+		crate::types::RouteTarget::Actors { selector_tags } => {
+			(Some(util::serde::Raw::new(selector_tags)?), None)
+		}
+		target => (None, Some(util::serde::Raw::new(target)?)),
 	};
-
-	// Serialize the actors selector tags for database storage
-	let actors_selector_tags_json = util::serde::Raw::new(&input.actors_selector_tags)?;
+	let set_request_match = input.request_match.is_some();
+	let request_match_json = match &input.request_match {
+		Some(request_match) if !request_match.is_empty() => {
+			Some(util::serde::Raw::new(request_match)?)
+		}
+		_ => None,
+	};
+	let set_load_balancing = input.load_balancing.is_some();
+	let load_balancing_json =
+		util::serde::Raw::new(&input.load_balancing.clone().unwrap_or_default())?;
+	let set_middleware = input.middleware.is_some();
+	let middleware_json = util::serde::Raw::new(&input.middleware.clone().unwrap_or_default())?;
 
 	// Use transaction to either create or update the route
-	let (route_id, created) = rivet_pools::utils::crdb::tx(&ctx.crdb().await?, |tx| {
+	let (route_id, prev_hostname) = rivet_pools::utils::crdb::tx(&ctx.crdb().await?, |tx| {
 		let ctx = ctx.clone();
 		let input_namespace_id = input.namespace_id;
 		let input_name_id = input.name_id.clone();
@@ -53,20 +79,31 @@ pub async fn upsert(ctx: &OperationCtx, input: &Input) -> GlobalResult<Output> {
 		let input_path = input.path.clone();
 		let input_route_subpaths = input.route_subpaths;
 		let input_strip_prefix = input.strip_prefix;
-		let target = target.clone();
 		let actors_selector_tags_json = actors_selector_tags_json.clone();
+		let target_config_json = target_config_json.clone();
+		let request_match_json = request_match_json.clone();
+		let load_balancing_json = load_balancing_json.clone();
+		let middleware_json = middleware_json.clone();
+		let set_request_match = set_request_match;
+		let set_load_balancing = set_load_balancing;
+		let set_middleware = set_middleware;
 		let now = now;
 
 		async move {
-			// First check if any other namespace is already using this hostname
+			// First check if any other namespace is already using this hostname. A wildcard hostname
+			// belongs to the same namespace as its parent hostname.
+			let conflicting_hostnames = match input_hostname.strip_prefix("*.") {
+				Some(parent) => vec![input_hostname.clone(), parent.to_string()],
+				None => vec![input_hostname.clone(), format!("*.{input_hostname}")],
+			};
 			let hostname_conflict = sql_fetch_optional!(
 				[ctx, (Uuid,), @tx tx]
 				"
 				SELECT route_id FROM db_route.routes 
-				WHERE hostname = $1 AND namespace_id != $2 AND delete_ts IS NULL
+				WHERE hostname = ANY($1) AND namespace_id != $2 AND delete_ts IS NULL
 				FOR UPDATE
 				",
-				&input_hostname,
+				&conflicting_hostnames,
 				input_namespace_id
 			)
 			.await?;
@@ -83,42 +120,52 @@ pub async fn upsert(ctx: &OperationCtx, input: &Input) -> GlobalResult<Output> {
 			}
 
 			// Check if a non-deleted route with this namespace_id and name_id already exists
-			let route_id = sql_fetch_optional!(
-				[ctx, (Uuid,), @tx tx]
+			let existing_route = sql_fetch_optional!(
+				[ctx, (Uuid, String), @tx tx]
 				"
-				SELECT route_id FROM db_route.routes 
+				SELECT route_id, hostname FROM db_route.routes 
 				WHERE namespace_id = $1 AND name_id = $2 AND delete_ts IS NULL
 				FOR UPDATE
 				",
 				input_namespace_id,
 				&input_name_id
 			)
-			.await?
-			.map(|(id,)| id);
+			.await?;
 
-			if let Some(existing_id) = route_id {
+			if let Some((existing_id, prev_hostname)) = existing_route {
 				// If active route exists, update it
 				sql_execute!(
 					[ctx, @tx tx]
 					"
 					UPDATE db_route.routes
 					SET hostname = $1, path = $2, route_subpaths = $3, strip_prefix = $4, 
-					    route_type = $5, actors_selector_tags = $6, update_ts = $7
-					WHERE route_id = $8 AND namespace_id = $9 AND delete_ts IS NULL
+					    route_type = $5, actors_selector_tags = $6, target_config = $7,
+					    request_match = CASE WHEN $14 THEN $8 ELSE request_match END,
+					    load_balancing = CASE WHEN $15 THEN $9 ELSE load_balancing END,
+					    middleware = CASE WHEN $16 THEN $10 ELSE middleware END,
+					    update_ts = $11
+					WHERE route_id = $12 AND namespace_id = $13 AND delete_ts IS NULL
 					",
 					&input_hostname,
 					&input_path,
 					input_route_subpaths,
 					input_strip_prefix,
-					0, // Actors type
+					route_type,
 					actors_selector_tags_json,
+					target_config_json,
+					request_match_json,
+					load_balancing_json,
+					middleware_json,
 					now,
 					existing_id,
-					input_namespace_id
+					input_namespace_id,
+					set_request_match,
+					set_load_balancing,
+					set_middleware,
 				)
 				.await?;
 
-				Ok((existing_id, false))
+				Ok((existing_id, Some(prev_hostname)))
 			} else {
 				// Create a new route
 				let new_route_id = Uuid::new_v4();
@@ -128,9 +175,10 @@ pub async fn upsert(ctx: &OperationCtx, input: &Input) -> GlobalResult<Output> {
 					"
 					INSERT INTO db_route.routes (
 						route_id, namespace_id, name_id, hostname, path, route_subpaths, strip_prefix,
-						route_type, actors_selector_tags, create_ts, update_ts, delete_ts
+						route_type, actors_selector_tags, target_config, request_match, load_balancing,
+						middleware, create_ts, update_ts, delete_ts
 					)
-					VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, NULL)
+					VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, NULL)
 					",
 					new_route_id,
 					input_namespace_id,
@@ -139,20 +187,30 @@ pub async fn upsert(ctx: &OperationCtx, input: &Input) -> GlobalResult<Output> {
 					&input_path,
 					input_route_subpaths,
 					input_strip_prefix,
-					0, // Actors type
+					route_type,
 					actors_selector_tags_json,
+					target_config_json,
+					request_match_json,
+					load_balancing_json,
+					middleware_json,
 					now,
 					now
 				)
 				.await?;
 
-				Ok((new_route_id, true))
+				Ok((new_route_id, None))
 			}
 		}
 		.boxed()
 	})
 	.await?;
 
+	let created = prev_hostname.is_none();
+
+	// The hostname may have changed
+	let hostnames = std::iter::once(input.hostname.clone()).chain(prev_hostname);
+	crate::utils::purge_route_cache(ctx, input.namespace_id, hostnames).await?;
+
 	Ok(Output { route_id, created })
 }
 
@@ -169,10 +227,13 @@ fn validate_name_id(name_id: &str) -> GlobalResult<()> {
 
 /// Validates the hostname for a route
 fn validate_hostname(domain_job: &str, hostname: &str) -> GlobalResult<()> {
+	// Wildcard hostnames match any direct subdomain of the rest of the hostname
+	let hostname = hostname.strip_prefix("*.").unwrap_or(hostname);
+
 	let (subdomain, domain) = hostname.split_once('.').ok_or_else(|| {
 		err_code!(
 			ROUTE_INVALID_HOSTNAME,
-			msg = "hostname must be in format {{xxxx}}.{domain_job}"
+			msg = "hostname must be in format {{xxxx}}.{domain_job} or *.{{xxxx}}.{domain_job}"
 		)
 	})?;
 
@@ -354,3 +415,160 @@ fn validate_actors_selector_tags(selector_tags: &HashMap<String, String>) -> Glo
 
 	Ok(())
 }
+
+/// Validates the target for a route
+fn validate_target(target: &crate::types::RouteTarget) -> GlobalResult<()> {
+	match target {
+		crate::types::RouteTarget::Actors { selector_tags } => {
+			validate_actors_selector_tags(selector_tags)?;
+		}
+		crate::types::RouteTarget::Split {
+			selector_tags,
+			canary_selector_tags,
+			canary_weight,
+		} => {
+			validate_actors_selector_tags(selector_tags)?;
+			validate_actors_selector_tags(canary_selector_tags)?;
+
+			ensure_with!(
+				*canary_weight <= 100,
+				ROUTE_INVALID_TARGET,
+				msg = "canary_weight must be between 0 and 100"
+			);
+		}
+		crate::types::RouteTarget::Redirect {
+			location, status, ..
+		} => {
+			ensure_with!(
+				location.starts_with("https://")
+					|| location.starts_with("http://")
+					|| location.starts_with('/'),
+				ROUTE_INVALID_TARGET,
+				msg = "redirect location must be an absolute URL or start with /"
+			);
+
+			ensure_with!(
+				location.len() <= 2048,
+				ROUTE_INVALID_TARGET,
+				msg = "redirect location is too large (max 2048 bytes)"
+			);
+
+			ensure_with!(
+				matches!(status, 301 | 302 | 303 | 307 | 308),
+				ROUTE_INVALID_TARGET,
+				msg = "redirect status must be 301, 302, 303, 307 or 308"
+			);
+		}
+		crate::types::RouteTarget::Response {
+			status,
+			content_type,
+			body,
+		} => {
+			ensure_with!(
+				(200..=599).contains(status),
+				ROUTE_INVALID_TARGET,
+				msg = "response status must be between 200 and 599"
+			);
+
+			if let Some(content_type) = content_type {
+				ensure_with!(
+					!content_type.is_empty() && content_type.len() <= 256,
+					ROUTE_INVALID_TARGET,
+					msg = "response content_type must be between 1 and 256 bytes"
+				);
+			}
+
+			ensure_with!(
+				body.len() <= 64 * 1024,
+				ROUTE_INVALID_TARGET,
+				msg = "response body is too large (max 64 KiB)"
+			);
+		}
+	}
+
+	Ok(())
+}
+
+/// Validates the request conditions for a route
+fn validate_request_match(request_match: &crate::types::RouteMatch) -> GlobalResult<()> {
+	ensure_with!(
+		request_match.methods.len() <= 16,
+		ROUTE_INVALID_REQUEST_MATCH,
+		msg = "request_match can have at most 16 methods"
+	);
+
+	for method in &request_match.methods {
+		ensure_with!(
+			!method.is_empty()
+				&& method.len() <= 32
+				&& method.chars().all(|c| c.is_ascii_uppercase()),
+			ROUTE_INVALID_REQUEST_MATCH,
+			msg = format!(
+				"method '{}' must be an uppercase HTTP method",
+				safe_slice(method, 0, 32)
+			)
+		);
+	}
+
+	ensure_with!(
+		request_match.headers.len() <= 16,
+		ROUTE_INVALID_REQUEST_MATCH,
+		msg = "request_match can have at most 16 headers"
+	);
+
+	for header in &request_match.headers {
+		ensure_with!(
+			!header.name.is_empty()
+				&& header.name.len() <= 256
+				&& header
+					.name
+					.chars()
+					.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_'),
+			ROUTE_INVALID_REQUEST_MATCH,
+			msg = format!(
+				"header name '{}' must be lowercase alphanumeric, dashes or underscores (max 256 bytes)",
+				safe_slice(&header.name, 0, 256)
+			)
+		);
+
+		if let Some(value) = &header.value {
+			ensure_with!(
+				value.len() <= 1024,
+				ROUTE_INVALID_REQUEST_MATCH,
+				msg = format!(
+					"value for header '{}' is too large (max 1024 bytes)",
+					header.name
+				)
+			);
+		}
+	}
+
+	Ok(())
+}
+
+/// Validates the load balancing strategy for a route
+fn validate_load_balancing(load_balancing: &crate::types::LoadBalancing) -> GlobalResult<()> {
+	let crate::types::LoadBalancing::ConsistentHash { key } = load_balancing else {
+		return Ok(());
+	};
+
+	let name = match key {
+		crate::types::HashKey::Header { name }
+		| crate::types::HashKey::Cookie { name }
+		| crate::types::HashKey::Query { name } => name,
+	};
+
+	ensure_with!(
+		!name.is_empty(),
+		ROUTE_INVALID_LOAD_BALANCING,
+		msg = "consistent hash key name cannot be empty"
+	);
+
+	ensure_with!(
+		name.len() <= 256,
+		ROUTE_INVALID_LOAD_BALANCING,
+		msg = "consistent hash key name is too large (max 256 bytes)"
+	);
+
+	Ok(())
+}
@@ -7,6 +7,9 @@ use strum::FromRepr;
 #[derive(Debug, Clone, Copy, PartialEq, Eq, FromRepr)]
 pub enum RouteTargetType {
 	Actors = 0,
+	Split = 1,
+	Redirect = 2,
+	Response = 3,
 }
 
 impl RouteTargetType {
@@ -25,6 +28,150 @@ pub enum RouteTarget {
 	Actors {
 		selector_tags: HashMap<String, String>,
 	},
+	/// Splits requests between two sets of actors, e.g. for canary rollouts.
+	Split {
+		selector_tags: HashMap<String, String>,
+		canary_selector_tags: HashMap<String, String>,
+		/// Percent of requests sent to the canary actors.
+		canary_weight: u8,
+	},
+	/// Redirects requests to another URL.
+	Redirect {
+		location: String,
+		status: u16,
+		/// Appends the request's path (after stripping the route's prefix) and query to the location.
+		preserve_path: bool,
+	},
+	/// Responds with a fixed response without proxying the request.
+	Response {
+		status: u16,
+		content_type: Option<String>,
+		body: String,
+	},
+}
+
+impl RouteTarget {
+	pub fn target_type(&self) -> RouteTargetType {
+		match self {
+			RouteTarget::Actors { .. } => RouteTargetType::Actors,
+			RouteTarget::Split { .. } => RouteTargetType::Split,
+			RouteTarget::Redirect { .. } => RouteTargetType::Redirect,
+			RouteTarget::Response { .. } => RouteTargetType::Response,
+		}
+	}
+}
+
+/// Request conditions a route matches on in addition to its hostname and path. Routes with conditions take
+/// precedence over routes without for the same hostname and path.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RouteMatch {
+	/// HTTP methods (uppercase) to match. Matches any method if empty.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub methods: Vec<String>,
+	/// Headers that all have to match.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub headers: Vec<HeaderMatch>,
+}
+
+impl RouteMatch {
+	pub fn is_empty(&self) -> bool {
+		self.methods.is_empty() && self.headers.is_empty()
+	}
+
+	/// `headers` is keyed by lowercase header name and holds every value of headers sent multiple times.
+	pub fn matches(&self, method: &str, headers: &HashMap<String, Vec<String>>) -> bool {
+		if !self.methods.is_empty() && !self.methods.iter().any(|m| m == method) {
+			return false;
+		}
+
+		self.headers
+			.iter()
+			.all(|header| match (headers.get(&header.name), &header.value) {
+				(Some(values), Some(expected)) => values.iter().any(|value| value == expected),
+				(Some(values), None) => !values.is_empty(),
+				(None, _) => false,
+			})
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HeaderMatch {
+	/// Lowercase header name.
+	pub name: String,
+	/// Matches if any value of the header is exactly this value. If unset, matches if the header is present.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub value: Option<String>,
+}
+
+/// How requests are distributed between the targets of a route
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoadBalancing {
+	/// Pick a random target for every request
+	#[default]
+	Random,
+	/// Cycle through targets in order
+	RoundRobin,
+	/// Pick the target with the fewest requests in flight
+	LeastInFlight,
+	/// Hash a value from the request to pick a target so that requests with the same value go to the
+	/// same target (session affinity). Falls back to random if the request does not have the value.
+	ConsistentHash { key: HashKey },
+}
+
+/// Request value to hash for consistent hashing
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HashKey {
+	Header { name: String },
+	Cookie { name: String },
+	Query { name: String },
+}
+
+/// Middleware policy for requests proxied by guard. Unset fields fall back to the environment's policy and
+/// then to guard's defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MiddlewarePolicy {
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub rate_limit: Option<RateLimitPolicy>,
+	/// Max concurrent requests per client.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub max_in_flight: Option<u32>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub retry: Option<RetryPolicy>,
+	/// Request timeout in seconds.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub request_timeout: Option<u64>,
+}
+
+impl MiddlewarePolicy {
+	/// Fills in unset fields from `fallback`.
+	pub fn or(self, fallback: MiddlewarePolicy) -> MiddlewarePolicy {
+		MiddlewarePolicy {
+			rate_limit: self.rate_limit.or(fallback.rate_limit),
+			max_in_flight: self.max_in_flight.or(fallback.max_in_flight),
+			retry: self.retry.or(fallback.retry),
+			request_timeout: self.request_timeout.or(fallback.request_timeout),
+		}
+	}
+}
+
+/// Rate limit per client
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimitPolicy {
+	pub requests: u64,
+	/// Period in seconds.
+	pub period: u64,
+	/// Share the rate limit between all guard instances instead of counting per instance.
+	#[serde(default)]
+	pub distributed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+	pub max_attempts: u32,
+	/// Initial backoff interval in milliseconds, doubled after every attempt.
+	pub initial_interval: u64,
 }
 
 /// Database representation of a route
@@ -33,11 +180,16 @@ pub struct Route {
 	pub route_id: Uuid,
 	pub namespace_id: Uuid,
 	pub name_id: String,
+	/// Either `{subdomain}.{domain_job}` or a wildcard `*.{subdomain}.{domain_job}` that matches any direct
+	/// subdomain of `{subdomain}.{domain_job}`.
 	pub hostname: String,
 	pub path: String,
 	pub route_subpaths: bool,
 	pub strip_prefix: bool,
+	pub request_match: RouteMatch,
 	pub target: RouteTarget,
+	pub load_balancing: LoadBalancing,
+	pub middleware: MiddlewarePolicy,
 	pub create_ts: i64,
 	pub update_ts: i64,
 	pub delete_ts: Option<i64>,
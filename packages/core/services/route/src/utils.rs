@@ -1,3 +1,5 @@
+use chirp_workflow::prelude::*;
+
 /// The maximum number of path components allowed in a route path.
 ///
 /// This restricts the depth of paths used in routes, which affects both
@@ -5,3 +7,69 @@
 ///
 /// For example, a path with 8 components would be "/a/b/c/d/e/f/g/h".
 pub const MAX_PATH_COMPONENTS: usize = 8;
+
+/// Purges the cached routes of the environment and the given hostnames. Called after routes are created,
+/// updated, or deleted.
+pub(crate) async fn purge_route_cache(
+	ctx: &OperationCtx,
+	namespace_id: Uuid,
+	hostnames: impl IntoIterator<Item = String>,
+) -> GlobalResult<()> {
+	ctx.cache()
+		.purge("route.hostname_routes", hostnames)
+		.await?;
+	ctx.cache()
+		.purge("route.env_routes", [namespace_id])
+		.await?;
+
+	Ok(())
+}
+
+/// Validates a middleware policy for a route or environment
+pub(crate) fn validate_middleware(middleware: &crate::types::MiddlewarePolicy) -> GlobalResult<()> {
+	if let Some(rate_limit) = &middleware.rate_limit {
+		ensure_with!(
+			rate_limit.requests >= 1 && rate_limit.requests <= 1_000_000,
+			ROUTE_INVALID_MIDDLEWARE,
+			msg = "rate_limit.requests must be between 1 and 1000000"
+		);
+
+		ensure_with!(
+			rate_limit.period >= 1 && rate_limit.period <= 60 * 60,
+			ROUTE_INVALID_MIDDLEWARE,
+			msg = "rate_limit.period must be between 1 and 3600 seconds"
+		);
+	}
+
+	if let Some(max_in_flight) = middleware.max_in_flight {
+		ensure_with!(
+			max_in_flight >= 1 && max_in_flight <= 10_000,
+			ROUTE_INVALID_MIDDLEWARE,
+			msg = "max_in_flight must be between 1 and 10000"
+		);
+	}
+
+	if let Some(retry) = &middleware.retry {
+		ensure_with!(
+			retry.max_attempts >= 1 && retry.max_attempts <= 10,
+			ROUTE_INVALID_MIDDLEWARE,
+			msg = "retry.max_attempts must be between 1 and 10"
+		);
+
+		ensure_with!(
+			retry.initial_interval <= 10_000,
+			ROUTE_INVALID_MIDDLEWARE,
+			msg = "retry.initial_interval must be at most 10000 milliseconds"
+		);
+	}
+
+	if let Some(request_timeout) = middleware.request_timeout {
+		ensure_with!(
+			request_timeout >= 1 && request_timeout <= 10 * 60,
+			ROUTE_INVALID_MIDDLEWARE,
+			msg = "request_timeout must be between 1 and 600 seconds"
+		);
+	}
+
+	Ok(())
+}
@@ -0,0 +1,2 @@
+ALTER TABLE workflows
+	ADD COLUMN cancel_ts INT;
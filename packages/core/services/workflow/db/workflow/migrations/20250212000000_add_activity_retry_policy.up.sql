@@ -0,0 +1,2 @@
+ALTER TABLE workflow_activity_events
+	ADD COLUMN retry_policy JSONB;
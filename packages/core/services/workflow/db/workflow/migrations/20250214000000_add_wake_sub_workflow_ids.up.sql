@@ -0,0 +1,2 @@
+ALTER TABLE workflows
+	ADD COLUMN wake_sub_workflow_ids UUID[];
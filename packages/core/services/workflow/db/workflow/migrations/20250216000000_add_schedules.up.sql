@@ -0,0 +1,4 @@
+CREATE TABLE schedules (
+	schedule_name TEXT PRIMARY KEY,
+	last_run_ts INT NOT NULL
+);
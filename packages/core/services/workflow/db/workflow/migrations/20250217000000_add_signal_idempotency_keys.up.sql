@@ -0,0 +1,12 @@
+CREATE TABLE signal_idempotency_keys (
+  -- Workflow id for signals sent to a workflow, tags (as JSONB text) for tagged signals
+  target TEXT NOT NULL,
+  signal_name TEXT NOT NULL,
+  idempotency_key TEXT NOT NULL,
+  signal_id UUID NOT NULL,
+  create_ts INT NOT NULL,
+  expire_ts INT NOT NULL,
+
+  PRIMARY KEY (target, signal_name, idempotency_key),
+  INDEX (expire_ts)
+);
@@ -16,6 +16,9 @@ use crate::{
 
 use super::GlobalQuery;
 
+/// Guard reports actor activity every 15 seconds, shorter idle timeouts could stop actors that are in use.
+const MIN_IDLE_TIMEOUT_MS: i64 = util::duration::seconds(30);
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct GlobalEndpointTypeQuery {
 	#[serde(flatten)]
@@ -113,7 +116,7 @@ pub async fn create(
 	)?;
 	let game_config = unwrap!(game_configs_res.game_configs.first());
 
-	let tags = unwrap_with!(
+	let tags: util::serde::HashableMap<String, String> = unwrap_with!(
 		serde_json::from_value(body.tags.unwrap_or_default()).ok(),
 		API_BAD_BODY,
 		error = "`tags` must be `Map<String, String>`"
@@ -140,6 +143,38 @@ pub async fn create(
 		}
 	};
 
+	let lifecycle = body.lifecycle.map(|x| (*x).api_into()).unwrap_or_else(|| {
+		pegboard::types::ActorLifecycle {
+			kill_timeout_ms: 0,
+			durable: false,
+			idle_timeout_ms: None,
+		}
+	});
+	if let Some(idle_timeout_ms) = lifecycle.idle_timeout_ms {
+		ensure_with!(
+			lifecycle.durable,
+			API_BAD_BODY,
+			error = "`lifecycle.idle_timeout` requires `lifecycle.durable`"
+		);
+		ensure_with!(
+			idle_timeout_ms >= MIN_IDLE_TIMEOUT_MS,
+			API_BAD_BODY,
+			error = format!("`lifecycle.idle_timeout` must be at least {MIN_IDLE_TIMEOUT_MS}ms")
+		);
+	}
+
+	let placement = body
+		.placement
+		.map(|x| (*x).api_into())
+		.unwrap_or_else(pegboard::types::ActorPlacement::default);
+	for k in &placement.spread_by_tags {
+		ensure_with!(
+			tags.contains_key(k),
+			API_BAD_BODY,
+			error = format!("`placement.spread_by_tags` contains {k:?} which is not in `tags`")
+		);
+	}
+
 	let actor_id = Uuid::new_v4();
 	let network = body.network.unwrap_or_default();
 	let endpoint_type = body
@@ -175,12 +210,7 @@ pub async fn create(
 		env_id,
 		tags,
 		resources,
-		lifecycle: body.lifecycle.map(|x| (*x).api_into()).unwrap_or_else(|| {
-			pegboard::types::ActorLifecycle {
-				kill_timeout_ms: 0,
-				durable: false,
-			}
-		}),
+		lifecycle,
 		image_id: build.build_id,
 		root_user_enabled: game_config.root_user_enabled,
 		// args: body.runtime.arguments.unwrap_or_default(),
@@ -234,6 +264,7 @@ pub async fn create(
 			)))
 			.collect::<GlobalResult<HashMap<_, _>>>()?.as_hashable(),
 		endpoint_type,
+		placement,
 	})
 	.tag("actor_id", actor_id)
 	.dispatch()
@@ -331,6 +362,8 @@ pub async fn destroy(
 	})
 	.to_workflow::<pegboard::workflows::actor::Workflow>()
 	.tag("actor_id", actor_id)
+	// Deduplicate retried destroy requests
+	.idempotency_key(actor_id)
 	.send()
 	.await?;
 
@@ -22,6 +22,13 @@ define_router! {
 				opt_auth: true,
 				body: models::EdgeIntercomPegboardToggleClientDrainRequest,
 			),
-		}
+		},
+
+		"pegboard" / "usage": {
+			GET: pegboard::usage(
+				internal_endpoint: true,
+				opt_auth: true,
+			),
+		},
 	},
 }
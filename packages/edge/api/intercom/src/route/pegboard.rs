@@ -1,7 +1,7 @@
-use api_helper::ctx::Ctx;
+use api_helper::{anchor::WatchIndexQuery, ctx::Ctx};
 use chirp_workflow::prelude::*;
 use cluster::types::BuildDeliveryMethod;
-use fdb_util::SERIALIZABLE;
+use fdb_util::{FormalKey, SERIALIZABLE, SNAPSHOT};
 use foundationdb::{self as fdb, options::StreamingMode};
 use futures_util::TryStreamExt;
 use pegboard::protocol;
@@ -170,6 +170,129 @@ pub async fn toggle_drain_client(
 	Ok(json!({}))
 }
 
+// MARK: GET /pegboard/usage
+pub async fn usage(
+	ctx: Ctx<Auth>,
+	_watch_index: WatchIndexQuery,
+) -> GlobalResult<models::EdgeIntercomPegboardGetUsageResponse> {
+	ctx.auth().bypass()?;
+
+	let (container, isolate) = tokio::try_join!(
+		flavor_usage(&ctx, protocol::ClientFlavor::Container),
+		flavor_usage(&ctx, protocol::ClientFlavor::Isolate),
+	)?;
+
+	Ok(models::EdgeIntercomPegboardGetUsageResponse {
+		container: Box::new(container),
+		isolate: Box::new(isolate),
+	})
+}
+
+/// Sums the resources of all responsive, non-draining clients of the given flavor and the resources
+/// requested by actors waiting to be allocated.
+async fn flavor_usage(
+	ctx: &Ctx<Auth>,
+	flavor: protocol::ClientFlavor,
+) -> GlobalResult<models::EdgeIntercomPegboardFlavorUsage> {
+	let usage = ctx
+		.fdb()
+		.await?
+		.run(|tx, _mc| async move {
+			let mut usage = models::EdgeIntercomPegboardFlavorUsage::default();
+
+			let alloc_idx_subspace = pegboard::keys::subspace()
+				.subspace(&pegboard::keys::datacenter::ClientsByRemainingMemKey::subspace(flavor));
+			let ping_threshold_ts =
+				util::timestamp::now() - pegboard::workflows::client::CLIENT_ELIGIBLE_THRESHOLD_MS;
+
+			let mut stream = tx.get_ranges_keyvalues(
+				fdb::RangeOption {
+					mode: StreamingMode::WantAll,
+					..(&alloc_idx_subspace).into()
+				},
+				SNAPSHOT,
+			);
+
+			while let Some(entry) = stream.try_next().await? {
+				let key = pegboard::keys::subspace()
+					.unpack::<pegboard::keys::datacenter::ClientsByRemainingMemKey>(entry.key())
+					.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
+
+				// Scan by last ping
+				if key.last_ping_ts < ping_threshold_ts {
+					continue;
+				}
+
+				let remaining_cpu_key = pegboard::keys::client::RemainingCpuKey::new(key.client_id);
+				let total_cpu_key = pegboard::keys::client::TotalCpuKey::new(key.client_id);
+				let total_mem_key = pegboard::keys::client::TotalMemoryKey::new(key.client_id);
+
+				let (remaining_cpu_entry, total_cpu_entry, total_mem_entry) = tokio::try_join!(
+					tx.get(
+						&pegboard::keys::subspace().pack(&remaining_cpu_key),
+						SNAPSHOT
+					),
+					tx.get(&pegboard::keys::subspace().pack(&total_cpu_key), SNAPSHOT),
+					tx.get(&pegboard::keys::subspace().pack(&total_mem_key), SNAPSHOT),
+				)?;
+
+				// Client is still registering
+				let (Some(remaining_cpu_entry), Some(total_cpu_entry), Some(total_mem_entry)) =
+					(remaining_cpu_entry, total_cpu_entry, total_mem_entry)
+				else {
+					continue;
+				};
+
+				let remaining_cpu = remaining_cpu_key
+					.deserialize(&remaining_cpu_entry)
+					.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
+				let total_cpu = total_cpu_key
+					.deserialize(&total_cpu_entry)
+					.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
+				let total_mem = total_mem_key
+					.deserialize(&total_mem_entry)
+					.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
+
+				usage.clients += 1;
+				usage.total_cpu += total_cpu as i64;
+				usage.remaining_cpu += remaining_cpu as i64;
+				usage.total_memory += total_mem as i64;
+				usage.remaining_memory += key.remaining_mem as i64;
+			}
+
+			let pending_subspace = pegboard::keys::subspace().subspace(
+				&pegboard::keys::datacenter::PendingAllocationKey::subspace(flavor),
+			);
+
+			let mut stream = tx.get_ranges_keyvalues(
+				fdb::RangeOption {
+					mode: StreamingMode::WantAll,
+					..(&pending_subspace).into()
+				},
+				SNAPSHOT,
+			);
+
+			while let Some(entry) = stream.try_next().await? {
+				let key = pegboard::keys::subspace()
+					.unpack::<pegboard::keys::datacenter::PendingAllocationKey>(entry.key())
+					.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
+				let pending = key
+					.deserialize(entry.value())
+					.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
+
+				usage.pending_allocations += 1;
+				usage.pending_cpu += pending.cpu as i64;
+				usage.pending_memory += pending.memory as i64;
+			}
+
+			Ok(usage)
+		})
+		.custom_instrument(tracing::info_span!("usage_fetch_tx", ?flavor))
+		.await?;
+
+	Ok(usage)
+}
+
 async fn resolve_image_fallback_artifact_url(
 	ctx: &Ctx<Auth>,
 	dc_build_delivery_method: BuildDeliveryMethod,
@@ -8,6 +8,7 @@ use crate::{key::Key, metadata::Metadata};
 #[derive(Default)]
 pub(crate) struct EntryBuilder {
 	metadata: Option<Metadata>,
+	version: Option<Vec<u8>>,
 	value: Vec<u8>,
 	next_idx: usize,
 }
@@ -22,6 +23,11 @@ impl EntryBuilder {
 					self.metadata = Some(Metadata::decode(value.value())?);
 				}
 			}
+			SubKey::Version(value) => {
+				if self.version.is_none() {
+					self.version = Some(value.value().to_vec());
+				}
+			}
 			SubKey::Chunk(idx, value) => {
 				// We don't perform deduplication on the input keys for `ActorKv::get` so we might have
 				// duplicate data chunks. This idx check ignores chunks that were already passed and ensures
@@ -43,6 +49,7 @@ impl EntryBuilder {
 			metadata: self
 				.metadata
 				.with_context(|| format!("no metadata for key {key:?}"))?,
+			version: self.version,
 			value: self.value,
 		})
 	}
@@ -52,11 +59,15 @@ impl EntryBuilder {
 #[derive(Serialize)]
 pub struct Entry {
 	pub metadata: Metadata,
+	/// Versionstamp of the last write to this key. Changes every time the key is written. Keys written
+	/// before versions were introduced have no version.
+	pub version: Option<Vec<u8>>,
 	pub value: Vec<u8>,
 }
 
 /// Represents FDB keys within a Rivet KV key.
 pub(crate) enum SubKey {
 	Metadata(fdb::future::FdbValue),
+	Version(fdb::future::FdbValue),
 	Chunk(usize, fdb::future::FdbValue),
 }
@@ -74,24 +74,29 @@ impl TuplePack for Key {
 		w: &mut W,
 		tuple_depth: TupleDepth,
 	) -> std::io::Result<VersionstampOffset> {
-		match self {
-			Key::JsInKey(tuple) => {
-				let mut offset = VersionstampOffset::None { size: 0 };
+		let mut offset = VersionstampOffset::None { size: 0 };
 
-				w.write_all(&[NESTED])?;
-				offset += 1;
+		w.write_all(&[NESTED])?;
+		offset += 1;
 
+		match self {
+			Key::JsInKey(tuple) => {
 				for v in tuple.iter() {
 					offset += v.as_ref().pack(w, tuple_depth.increment())?;
 				}
-
-				w.write_all(&[NIL])?;
-				offset += 1;
-
-				Ok(offset)
 			}
-			Key::JsOutKey(_) => unreachable!("should not be packing out keys"),
+			// Only packed when creating list cursors
+			Key::JsOutKey(tuple) => {
+				for v in tuple.iter() {
+					offset += v.as_slice().pack(w, tuple_depth.increment())?;
+				}
+			}
 		}
+
+		w.write_all(&[NIL])?;
+		offset += 1;
+
+		Ok(offset)
 	}
 }
 
@@ -9,14 +9,15 @@ use deno_core::JsBuffer;
 pub use entry::Entry;
 use entry::{EntryBuilder, SubKey};
 use fdb_util::keys::*;
-use foundationdb::{self as fdb, directory::Directory, tuple::Subspace};
+use foundationdb::{self as fdb, directory::Directory, options::MutationType, tuple::Subspace};
 use futures_util::{StreamExt, TryStreamExt};
 use indexmap::IndexMap;
 use key::Key;
 use list_query::ListLimitReached;
-pub use list_query::ListQuery;
+pub use list_query::{ListBound, ListQuery};
 pub use metadata::Metadata;
 use prost::Message;
+pub use transact::{Check, Mutation};
 use utils::{validate_entries, validate_keys, TransactionExt};
 use uuid::Uuid;
 
@@ -24,6 +25,7 @@ mod entry;
 pub mod key;
 mod list_query;
 mod metadata;
+mod transact;
 mod utils;
 
 const MAX_KEY_SIZE: usize = 2 * 1024;
@@ -32,6 +34,8 @@ const MAX_KEYS: usize = 128;
 const MAX_PUT_PAYLOAD_SIZE: usize = 976 * 1024;
 const MAX_STORAGE_SIZE: usize = 1024 * 1024 * 1024; // 1 GiB
 const VALUE_CHUNK_SIZE: usize = 10_000; // 10 KB, not KiB, see https://apple.github.io/foundationdb/blob.html
+/// Max expired keys deleted per transaction when sweeping.
+const SWEEP_BATCH_SIZE: usize = 128;
 
 // Currently designed largely around the Deno runtime. More abstractions can be made later.
 pub struct ActorKv {
@@ -39,6 +43,8 @@ pub struct ActorKv {
 	db: Arc<fdb::Database>,
 	actor_id: Uuid,
 	subspace: Option<Subspace>,
+	/// Index of keys with a TTL, ordered by expiration.
+	expire_subspace: Option<Subspace>,
 }
 
 impl ActorKv {
@@ -48,6 +54,7 @@ impl ActorKv {
 			db,
 			actor_id,
 			subspace: None,
+			expire_subspace: None,
 		}
 	}
 
@@ -78,6 +85,10 @@ impl ActorKv {
 			.create_or_open(&tx, &[dir::KV.into()], None, None)
 			.await
 			.map_err(|err| anyhow!("failed to create/open fdb actor kv dir: {err:?}"))?;
+		let kv_expire_dir = actor_dir
+			.create_or_open(&tx, &[dir::KV_EXPIRE.into()], None, None)
+			.await
+			.map_err(|err| anyhow!("failed to create/open fdb actor kv expire dir: {err:?}"))?;
 		tx.commit()
 			.await
 			.map_err(|err| anyhow!("failed to commit actor kv txn: {err:?}"))?;
@@ -85,6 +96,9 @@ impl ActorKv {
 		self.subspace = Some(Subspace::from_bytes(
 			kv_dir.bytes().map_err(|err| anyhow!("{err:?}"))?,
 		));
+		self.expire_subspace = Some(Subspace::from_bytes(
+			kv_expire_dir.bytes().map_err(|err| anyhow!("{err:?}"))?,
+		));
 
 		tracing::info!("successfully initialized KV");
 
@@ -111,6 +125,8 @@ impl ActorKv {
 
 		validate_keys(&keys)?;
 
+		let now = utils::now();
+
 		self.db
 			.run(|tx, _mc| {
 				let keys = keys.clone();
@@ -137,11 +153,15 @@ impl ActorKv {
 											if let Ok(sub_key) =
 												key_subspace.unpack::<usize>(value.key())
 											{
-												if sub_key != METADATA {
-													bail!("unexpected sub key: {sub_key:?}");
+												match sub_key {
+													METADATA => {
+														Ok((key.clone(), SubKey::Metadata(value)))
+													}
+													VERSION => {
+														Ok((key.clone(), SubKey::Version(value)))
+													}
+													_ => bail!("unexpected sub key: {sub_key:?}"),
 												}
-
-												Ok((key.clone(), SubKey::Metadata(value)))
 											} else {
 												// Parse sub key as idx
 												let (_, idx) = key_subspace
@@ -177,16 +197,19 @@ impl ActorKv {
 
 				Ok((key, entry))
 			})
+			.filter(|res| is_not_expired(res, now))
 			.collect()
 	}
 
-	/// Gets keys from the KV store.
+	/// Gets keys from the KV store. If the limit is reached, also returns a cursor which can be passed to
+	/// subsequent calls to continue listing from the last returned key.
 	pub async fn list(
 		&self,
 		query: ListQuery,
 		reverse: bool,
 		limit: Option<usize>,
-	) -> Result<IndexMap<Key, Entry>> {
+		cursor: Option<&[u8]>,
+	) -> Result<(IndexMap<Key, Entry>, Option<Vec<u8>>)> {
 		let subspace = self
 			.subspace
 			.as_ref()
@@ -194,7 +217,16 @@ impl ActorKv {
 
 		query.validate()?;
 
-		let list_range = query.range(&subspace);
+		let mut list_range = query.range(&subspace);
+
+		if let Some(cursor) = cursor {
+			list_range = list_query::apply_cursor(subspace, list_range, cursor, reverse)?;
+		}
+
+		// Nothing to list, FDB errors on inverted ranges
+		if list_range.0 >= list_range.1 {
+			return Ok((IndexMap::new(), None));
+		}
 
 		let res = self
 			.db
@@ -223,11 +255,11 @@ impl ActorKv {
 								if let Ok((key, sub_key)) =
 									subspace.unpack::<(Key, usize)>(value.key())
 								{
-									if sub_key != METADATA {
-										bail!("unexpected sub key: {sub_key:?}");
+									match sub_key {
+										METADATA => Ok((key, SubKey::Metadata(value))),
+										VERSION => Ok((key, SubKey::Version(value))),
+										_ => bail!("unexpected sub key: {sub_key:?}"),
 									}
-
-									Ok((key, SubKey::Metadata(value)))
 								} else {
 									// Parse sub key as idx
 									let (key, _, idx) =
@@ -282,37 +314,57 @@ impl ActorKv {
 			})
 			.await;
 
-		let values = match res {
-			Ok(values) => values,
+		let (values, limit_reached) = match res {
+			Ok(values) => (values, false),
 			Err(fdb::FdbBindingError::CustomError(err)) => {
 				let ListLimitReached(values) = *err
 					.downcast::<ListLimitReached>()
 					.map_err(fdb::FdbBindingError::CustomError)?;
 
-				values
+				(values, true)
 			}
 			Err(err) => return Err(err.into()),
 		};
 
-		values
+		// The cursor is the last key returned, relative to the KV subspace
+		let cursor = limit_reached
+			.then(|| values.last().map(|(key, _)| fdb::tuple::pack(key)))
+			.flatten();
+
+		// Expired keys are filtered out after the limit is applied, so pages can be smaller than the limit
+		let now = utils::now();
+		let entries = values
 			.into_iter()
 			.map(|(key, builder)| {
 				let entry = builder.build(&key)?;
 
 				Ok((key, entry))
 			})
-			.collect()
+			.filter(|res| is_not_expired(res, now))
+			.collect::<Result<_>>()?;
+
+		Ok((entries, cursor))
 	}
 
-	/// Puts keys into the KV store.
-	pub async fn put(&self, entries: HashMap<Key, JsBuffer>) -> Result<()> {
+	/// Puts keys into the KV store. Keys put with a TTL (in milliseconds) are hidden once expired and
+	/// eventually deleted by `ActorKv::sweep_expired`.
+	pub async fn put(&self, entries: HashMap<Key, JsBuffer>, ttl: Option<i64>) -> Result<()> {
 		let subspace = self
 			.subspace
 			.as_ref()
 			.context("must call `ActorKv::init` before using KV operations")?;
+		let expire_subspace = self
+			.expire_subspace
+			.as_ref()
+			.context("must call `ActorKv::init` before using KV operations")?;
 		let total_size = self.get_subspace_size(subspace).await? as usize;
 
 		validate_entries(&entries, total_size)?;
+		if let Some(ttl) = ttl {
+			ensure!(ttl > 0, "ttl must be positive");
+		}
+
+		let expire_ts = ttl.map(|ttl| utils::now() + ttl);
 
 		self.db
 			.run(|tx, _mc| {
@@ -327,32 +379,13 @@ impl ActorKv {
 							let key_subspace = subspace.subspace(&key);
 
 							async move {
-								// Clear previous before setting
-								tx.clear_subspace_range(&key_subspace);
-
-								let metadata = Metadata {
-									kv_version: self.version.as_bytes().to_vec(),
-									create_ts: utils::now(),
-								};
-								let mut buf = Vec::new();
-								metadata
-									.encode(&mut buf)
+								self.set_entry(&tx, &key_subspace, &value, expire_ts)
 									.map_err(|err| fdb::FdbBindingError::CustomError(err.into()))?;
 
-								// Set metadata
-								tx.set(&key_subspace.pack(&METADATA), &buf);
-
-								// Set data
-								for start in (0..value.len()).step_by(VALUE_CHUNK_SIZE) {
-									let idx = start / VALUE_CHUNK_SIZE;
-									let end = (start + VALUE_CHUNK_SIZE).min(value.len());
-
-									tx.set(
-										&key_subspace.pack(&(DATA, idx)),
-										&value.get(start..end).context("bad slice").map_err(
-											|err| fdb::FdbBindingError::CustomError(err.into()),
-										)?,
-									);
+								// Add to expiration index. Index entries are not removed when the key is
+								// overwritten, the sweeper skips them if the key's expiration changed
+								if let Some(expire_ts) = expire_ts {
+									tx.set(&expire_subspace.pack(&(expire_ts, &key)), &[]);
 								}
 
 								Ok(())
@@ -367,6 +400,102 @@ impl ActorKv {
 			.map_err(Into::into)
 	}
 
+	/// Atomically checks and mutates keys in the KV store. If any of the checks fail, no mutations are applied
+	/// and `false` is returned.
+	pub async fn transact(&self, checks: Vec<Check>, mutations: Vec<Mutation>) -> Result<bool> {
+		let subspace = self
+			.subspace
+			.as_ref()
+			.context("must call `ActorKv::init` before using KV operations")?;
+		let expire_subspace = self
+			.expire_subspace
+			.as_ref()
+			.context("must call `ActorKv::init` before using KV operations")?;
+		let total_size = self.get_subspace_size(subspace).await? as usize;
+
+		transact::validate(&checks, &mutations, total_size)?;
+
+		let now = utils::now();
+
+		self.db
+			.run(|tx, _mc| {
+				let checks = &checks;
+				let mutations = &mutations;
+
+				async move {
+					// Reading the checked keys adds them to the read conflict range, so any concurrent write
+					// to them will cause this transaction to retry
+					for check in checks {
+						let key_subspace = subspace.subspace(check.key());
+
+						// Expired keys are treated as absent
+						let exists = if let Some(metadata) =
+							tx.get(&key_subspace.pack(&METADATA), false).await?
+						{
+							!Metadata::decode(&*metadata)
+								.map_err(|err| fdb::FdbBindingError::CustomError(err.into()))?
+								.is_expired(now)
+						} else {
+							false
+						};
+
+						let passed = match check {
+							Check::Absent(_) => !exists,
+							Check::Version(_, version) => {
+								exists
+									&& tx
+										.get(&key_subspace.pack(&VERSION), false)
+										.await?
+										.map_or(false, |current| &*current == version.as_ref())
+							}
+						};
+
+						if !passed {
+							return Ok(false);
+						}
+					}
+
+					for mutation in mutations {
+						let key_subspace = subspace.subspace(mutation.key());
+
+						match mutation {
+							Mutation::Put(key, value, ttl) => {
+								let expire_ts = ttl.map(|ttl| now + ttl);
+
+								// Add to expiration index, see `ActorKv::put`
+								if let Some(expire_ts) = expire_ts {
+									tx.set(&expire_subspace.pack(&(expire_ts, key)), &[]);
+								}
+
+								self.set_entry(&tx, &key_subspace, value, expire_ts)
+							}
+							Mutation::Delete(_) => {
+								tx.clear_subspace_range(&key_subspace);
+								Ok(())
+							}
+							Mutation::Add(_, param) => {
+								self.atomic_op(&tx, &key_subspace, param, MutationType::Add, now)
+									.await
+							}
+							Mutation::Min(_, param) => {
+								self.atomic_op(&tx, &key_subspace, param, MutationType::Min, now)
+									.await
+							}
+							Mutation::Max(_, param) => {
+								self.atomic_op(&tx, &key_subspace, param, MutationType::Max, now)
+									.await
+							}
+						}
+						.map_err(|err| fdb::FdbBindingError::CustomError(err.into()))?;
+					}
+
+					Ok(true)
+				}
+			})
+			.await
+			.map_err(Into::into)
+	}
+
 	/// Deletes keys from the KV store.
 	pub async fn delete(&self, keys: Vec<Key>) -> Result<()> {
 		let subspace = self
@@ -399,16 +528,184 @@ impl ActorKv {
 			.subspace
 			.as_ref()
 			.context("must call `ActorKv::init` before using KV operations")?;
+		let expire_subspace = self
+			.expire_subspace
+			.as_ref()
+			.context("must call `ActorKv::init` before using KV operations")?;
 
 		self.db
 			.run(|tx, _mc| async move {
 				tx.clear_subspace_range(&subspace);
+				tx.clear_subspace_range(&expire_subspace);
 				Ok(())
 			})
 			.await
 			.map_err(Into::into)
 	}
 
+	/// Deletes expired keys from the KV store, freeing their storage. Returns the amount of keys deleted.
+	pub async fn sweep_expired(&self) -> Result<usize> {
+		let subspace = self
+			.subspace
+			.as_ref()
+			.context("must call `ActorKv::init` before using KV operations")?;
+		let expire_subspace = self
+			.expire_subspace
+			.as_ref()
+			.context("must call `ActorKv::init` before using KV operations")?;
+
+		let mut total_deleted = 0;
+
+		loop {
+			let now = utils::now();
+
+			let (index_entries, deleted) = self
+				.db
+				.run(|tx, _mc| async move {
+					// All index entries that expired before now
+					let index_entries = tx
+						.get_range(
+							&fdb::RangeOption {
+								mode: fdb::options::StreamingMode::WantAll,
+								limit: Some(SWEEP_BATCH_SIZE),
+								..(
+									expire_subspace.range().0,
+									expire_subspace.subspace(&now).range().1,
+								)
+									.into()
+							},
+							1,
+							false,
+						)
+						.await?;
+
+					let mut deleted = 0;
+
+					for index_entry in index_entries.iter() {
+						let (expire_ts, key) = expire_subspace
+							.unpack::<(i64, Key)>(index_entry.key())
+							.map_err(|err| fdb::FdbBindingError::CustomError(err.into()))?;
+						let key_subspace = subspace.subspace(&key);
+
+						tx.clear(index_entry.key());
+
+						let Some(metadata) = tx.get(&key_subspace.pack(&METADATA), false).await?
+						else {
+							continue;
+						};
+						let metadata = Metadata::decode(&*metadata)
+							.map_err(|err| fdb::FdbBindingError::CustomError(err.into()))?;
+
+						// Key was overwritten since this index entry was written
+						if metadata.expire_ts != Some(expire_ts) {
+							continue;
+						}
+
+						tx.clear_subspace_range(&key_subspace);
+						deleted += 1;
+					}
+
+					Ok((index_entries.len(), deleted))
+				})
+				.await?;
+
+			total_deleted += deleted;
+
+			if index_entries < SWEEP_BATCH_SIZE {
+				break;
+			}
+		}
+
+		Ok(total_deleted)
+	}
+
+	/// Replaces the value of a key.
+	fn set_entry(
+		&self,
+		tx: &fdb::Transaction,
+		key_subspace: &Subspace,
+		value: &[u8],
+		expire_ts: Option<i64>,
+	) -> Result<()> {
+		// Clear previous before setting
+		tx.clear_subspace_range(key_subspace);
+
+		self.set_metadata(tx, key_subspace, expire_ts)?;
+
+		// Set data
+		for start in (0..value.len()).step_by(VALUE_CHUNK_SIZE) {
+			let idx = start / VALUE_CHUNK_SIZE;
+			let end = (start + VALUE_CHUNK_SIZE).min(value.len());
+
+			tx.set(
+				&key_subspace.pack(&(DATA, idx)),
+				value.get(start..end).context("bad slice")?,
+			);
+		}
+
+		Ok(())
+	}
+
+	/// Applies an atomic operation to a key's value, treating it as a little-endian integer. Missing and
+	/// expired values are treated as 0. Removes the key's TTL.
+	async fn atomic_op(
+		&self,
+		tx: &fdb::Transaction,
+		key_subspace: &Subspace,
+		param: &[u8],
+		op: MutationType,
+		now: i64,
+	) -> Result<()> {
+		// Snapshot read to not conflict with other atomic ops
+		if let Some(metadata) = tx.get(&key_subspace.pack(&METADATA), true).await? {
+			if Metadata::decode(&*metadata)?.is_expired(now) {
+				tx.clear_subspace_range(key_subspace);
+			}
+		}
+
+		// Atomic values are always a single chunk. Clear any other chunks left by a previous larger value
+		let data_subspace = key_subspace.subspace(&DATA);
+		tx.clear_range(&data_subspace.pack(&1), &data_subspace.range().1);
+
+		self.set_metadata(tx, key_subspace, None)?;
+
+		tx.atomic_op(&key_subspace.pack(&(DATA, 0)), param, op);
+
+		Ok(())
+	}
+
+	/// Writes the metadata of a key and sets its version to the versionstamp of the transaction.
+	fn set_metadata(
+		&self,
+		tx: &fdb::Transaction,
+		key_subspace: &Subspace,
+		expire_ts: Option<i64>,
+	) -> Result<()> {
+		let metadata = Metadata {
+			kv_version: self.version.as_bytes().to_vec(),
+			create_ts: utils::now(),
+			expire_ts,
+		};
+		let mut buf = Vec::new();
+		metadata.encode(&mut buf)?;
+
+		// Set metadata
+		tx.set(&key_subspace.pack(&METADATA), &buf);
+
+		// Placeholder for the versionstamp followed by its offset in the value
+		let mut version = vec![0; 10];
+		version.extend(0u32.to_le_bytes());
+
+		// Set version
+		tx.atomic_op(
+			&key_subspace.pack(&VERSION),
+			&version,
+			MutationType::SetVersionstampedValue,
+		);
+
+		Ok(())
+	}
+
 	/// **Destroys entire actor's KV. Cannot be undone.**
 	pub async fn destroy(self) -> Result<()> {
 		let root = fdb::directory::DirectoryLayer::default();
@@ -429,3 +726,10 @@ impl ActorKv {
 		Ok(())
 	}
 }
+
+fn is_not_expired(res: &Result<(Key, Entry)>, now: i64) -> bool {
+	match res {
+		Ok((_, entry)) => !entry.metadata.is_expired(now),
+		Err(_) => true,
+	}
+}
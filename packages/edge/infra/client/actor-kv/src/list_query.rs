@@ -13,23 +13,45 @@ use crate::{
 #[serde(rename_all = "camelCase")]
 pub enum ListQuery {
 	All,
-	RangeInclusive(ListKey, Key),
-	RangeExclusive(ListKey, Key),
+	/// Lists all keys between the given bounds. A missing bound is unbounded.
+	Range {
+		start: Option<ListBound>,
+		end: Option<ListBound>,
+	},
 	Prefix(ListKey),
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ListBound {
+	Inclusive(Key),
+	Exclusive(Key),
+}
+
 impl ListQuery {
 	pub(crate) fn range(&self, subspace: &Subspace) -> (Vec<u8>, Vec<u8>) {
 		match self {
 			ListQuery::All => subspace.range(),
-			ListQuery::RangeInclusive(start, end) => (
-				subspace.subspace(&start).range().0,
-				subspace.subspace(&end).range().1,
-			),
-			ListQuery::RangeExclusive(start, end) => (
-				subspace.subspace(&start).range().0,
-				subspace.subspace(&end).range().1,
-			),
+			ListQuery::Range { start, end } => {
+				let (all_start, all_end) = subspace.range();
+
+				let start = match start {
+					// Start of the key's subspace
+					Some(ListBound::Inclusive(key)) => subspace.subspace(key).range().0,
+					// Skip all of the key's sub keys
+					Some(ListBound::Exclusive(key)) => subspace.subspace(key).range().1,
+					None => all_start,
+				};
+				let end = match end {
+					// Include all of the key's sub keys
+					Some(ListBound::Inclusive(key)) => subspace.subspace(key).range().1,
+					// Stop before the key's first sub key
+					Some(ListBound::Exclusive(key)) => subspace.subspace(key).range().0,
+					None => all_end,
+				};
+
+				(start, end)
+			}
 			ListQuery::Prefix(prefix) => subspace.subspace(&prefix).range(),
 		}
 	}
@@ -37,25 +59,19 @@ impl ListQuery {
 	pub(crate) fn validate(&self) -> Result<()> {
 		match self {
 			ListQuery::All => {}
-			ListQuery::RangeInclusive(start, end) => {
-				ensure!(
-					start.len() <= MAX_KEY_SIZE,
-					"start key is too long (max 2048 bytes)"
-				);
-				ensure!(
-					end.len() <= MAX_KEY_SIZE,
-					"end key is too long (max 2048 bytes)"
-				);
-			}
-			ListQuery::RangeExclusive(start, end) => {
-				ensure!(
-					start.len() <= MAX_KEY_SIZE,
-					"startAfter key is too long (max 2048 bytes)"
-				);
-				ensure!(
-					end.len() <= MAX_KEY_SIZE,
-					"end key is too long (max 2048 bytes)"
-				);
+			ListQuery::Range { start, end } => {
+				if let Some(start) = start {
+					ensure!(
+						start.key().len() <= MAX_KEY_SIZE,
+						"start key is too long (max 2048 bytes)"
+					);
+				}
+				if let Some(end) = end {
+					ensure!(
+						end.key().len() <= MAX_KEY_SIZE,
+						"end key is too long (max 2048 bytes)"
+					);
+				}
 			}
 			ListQuery::Prefix(prefix) => {
 				ensure!(
@@ -69,6 +85,37 @@ impl ListQuery {
 	}
 }
 
+impl ListBound {
+	fn key(&self) -> &Key {
+		match self {
+			ListBound::Inclusive(key) | ListBound::Exclusive(key) => key,
+		}
+	}
+}
+
+/// Narrows a list range so that it resumes from the key in the cursor. Listing forwards resumes after the
+/// cursor's key and listing in reverse resumes before it, so the same cursor can be used in either
+/// direction.
+pub(crate) fn apply_cursor(
+	subspace: &Subspace,
+	(start, end): (Vec<u8>, Vec<u8>),
+	cursor: &[u8],
+	reverse: bool,
+) -> Result<(Vec<u8>, Vec<u8>)> {
+	// Validate cursor
+	foundationdb::tuple::unpack::<Key>(cursor).context("invalid list cursor")?;
+
+	let mut cursor_key = subspace.bytes().to_vec();
+	cursor_key.extend(cursor);
+	let (cursor_start, cursor_end) = Subspace::from_bytes(cursor_key).range();
+
+	if reverse {
+		Ok((start, end.min(cursor_start)))
+	} else {
+		Ok((start.max(cursor_end), end))
+	}
+}
+
 // Used to short circuit after the
 pub struct ListLimitReached(pub IndexMap<Key, EntryBuilder>);
 
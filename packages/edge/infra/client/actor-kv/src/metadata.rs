@@ -6,4 +6,14 @@ pub struct Metadata {
 	pub kv_version: Vec<u8>,
 	#[prost(int64, tag = "2")]
 	pub create_ts: i64,
+	/// When the key expires. Expired keys are hidden from reads and eventually deleted by
+	/// `ActorKv::sweep_expired`.
+	#[prost(int64, optional, tag = "3")]
+	pub expire_ts: Option<i64>,
+}
+
+impl Metadata {
+	pub fn is_expired(&self, now: i64) -> bool {
+		self.expire_ts.map_or(false, |expire_ts| expire_ts <= now)
+	}
 }
@@ -0,0 +1,115 @@
+use anyhow::*;
+use deno_core::JsBuffer;
+use serde::Deserialize;
+
+use crate::{
+	key::Key, MAX_KEYS, MAX_KEY_SIZE, MAX_PUT_PAYLOAD_SIZE, MAX_STORAGE_SIZE, MAX_VALUE_SIZE,
+};
+
+/// Max size of an atomic operation's operand. Atomic values are little-endian integers.
+const MAX_ATOMIC_PARAM_SIZE: usize = 8;
+
+/// A condition that must hold for a transaction to be applied.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Check {
+	/// The key must not exist.
+	Absent(Key),
+	/// The key must exist and its version must match the given version.
+	Version(Key, JsBuffer),
+}
+
+impl Check {
+	pub(crate) fn key(&self) -> &Key {
+		match self {
+			Check::Absent(key) | Check::Version(key, _) => key,
+		}
+	}
+}
+
+/// A write applied by a transaction.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Mutation {
+	/// Replaces the key's value. Includes an optional TTL in milliseconds, see `ActorKv::put`.
+	Put(Key, JsBuffer, Option<i64>),
+	Delete(Key),
+	/// Adds to the key's value as a little-endian integer.
+	Add(Key, JsBuffer),
+	/// Sets the key's value to the smaller of its current value and the operand, compared as little-endian
+	/// unsigned integers.
+	Min(Key, JsBuffer),
+	/// Sets the key's value to the larger of its current value and the operand, compared as little-endian
+	/// unsigned integers.
+	Max(Key, JsBuffer),
+}
+
+impl Mutation {
+	pub(crate) fn key(&self) -> &Key {
+		match self {
+			Mutation::Put(key, _, _)
+			| Mutation::Delete(key)
+			| Mutation::Add(key, _)
+			| Mutation::Min(key, _)
+			| Mutation::Max(key, _) => key,
+		}
+	}
+}
+
+pub(crate) fn validate(checks: &[Check], mutations: &[Mutation], total_size: usize) -> Result<()> {
+	ensure!(
+		checks.len() + mutations.len() <= MAX_KEYS,
+		"a maximum of 128 checks and mutations is allowed"
+	);
+
+	for key in checks
+		.iter()
+		.map(Check::key)
+		.chain(mutations.iter().map(Mutation::key))
+	{
+		ensure!(
+			key.len() <= MAX_KEY_SIZE,
+			"key is too long (max 2048 bytes)"
+		);
+	}
+
+	let mut payload_size = 0;
+	for mutation in mutations {
+		match mutation {
+			Mutation::Put(key, value, ttl) => {
+				ensure!(
+					value.len() <= MAX_VALUE_SIZE,
+					"value is too large (max 128 KiB)"
+				);
+				if let Some(ttl) = ttl {
+					ensure!(*ttl > 0, "ttl must be positive");
+				}
+
+				payload_size += key.len() + value.len();
+			}
+			Mutation::Delete(_) => {}
+			Mutation::Add(key, param) | Mutation::Min(key, param) | Mutation::Max(key, param) => {
+				ensure!(!param.is_empty(), "atomic operand cannot be empty");
+				ensure!(
+					param.len() <= MAX_ATOMIC_PARAM_SIZE,
+					"atomic operand is too large (max 8 bytes)"
+				);
+
+				payload_size += key.len() + param.len();
+			}
+		}
+	}
+
+	ensure!(
+		payload_size <= MAX_PUT_PAYLOAD_SIZE,
+		"total payload is too large (max 976 KiB)"
+	);
+
+	let storage_remaining = MAX_STORAGE_SIZE.saturating_sub(total_size);
+	ensure!(
+		payload_size <= storage_remaining,
+		"not enough space left in storage ({storage_remaining} bytes remaining, current payload is {payload_size} bytes)"
+	);
+
+	Ok(())
+}
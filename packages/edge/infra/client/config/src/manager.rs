@@ -1,5 +1,6 @@
 use std::{
 	borrow::Cow,
+	collections::BTreeMap,
 	net::{IpAddr, Ipv4Addr},
 	path::{Path, PathBuf},
 	time::Duration,
@@ -37,7 +38,9 @@ impl Config {
 			reserved_resources: pegboard::client_config::ReservedResources {
 				cpu: self.client.reserved_resources.cpu(),
 				memory: self.client.reserved_resources.memory(),
+				disk: self.client.reserved_resources.disk(),
 			},
+			labels: self.client.labels.clone(),
 		}
 	}
 }
@@ -62,6 +65,9 @@ pub struct Client {
 	pub foundationdb: FoundationDb,
 	#[serde(default)]
 	pub vector: Option<Vector>,
+	/// Arbitrary key-value pairs that actors can require with placement constraints.
+	#[serde(default)]
+	pub labels: BTreeMap<String, String>,
 }
 
 impl Client {
@@ -230,6 +236,8 @@ pub struct ReservedResources {
 	pub cpu: Option<u64>,
 	// MiB
 	pub memory: Option<u64>,
+	// MiB
+	pub disk: Option<u64>,
 }
 
 impl ReservedResources {
@@ -240,6 +248,10 @@ impl ReservedResources {
 	pub fn memory(&self) -> u64 {
 		self.memory.unwrap_or(0)
 	}
+
+	pub fn disk(&self) -> u64 {
+		self.disk.unwrap_or(0)
+	}
 }
 
 #[derive(Clone, Deserialize, Default, JsonSchema)]
@@ -3,7 +3,7 @@
 // Generated with scripts/sdk_actor/compile_bridge.ts
 
 import { core } from "ext:core/mod.js";
-import { op_rivet_kv_delete, op_rivet_kv_delete_all, op_rivet_kv_delete_batch, op_rivet_kv_get, op_rivet_kv_get_batch, op_rivet_kv_list, op_rivet_kv_put, op_rivet_kv_put_batch, } from "ext:core/ops";
+import { op_rivet_kv_delete, op_rivet_kv_delete_all, op_rivet_kv_delete_batch, op_rivet_kv_get, op_rivet_kv_get_batch, op_rivet_kv_list, op_rivet_kv_put, op_rivet_kv_put_batch, op_rivet_kv_transact, } from "ext:core/ops";
 import { deepEqual } from "./lib/fast-equals/index.js";
 /**
  * Retrieves a value from the key-value store.
@@ -14,6 +14,19 @@ export async function get(key, options) {
         return null;
     return deserializeValue(key, entry.value, options?.format);
 }
+/**
+ * Retrieves a value and its version from the key-value store. The version changes every time the key is
+ * written and can be used with `transact` to only write if the key has not changed since it was read.
+ */
+export async function getWithVersion(key, options) {
+    const entry = await op_rivet_kv_get(serializeKey(key));
+    if (entry == null)
+        return null;
+    return {
+        value: deserializeValue(key, entry.value, options?.format),
+        version: entry.version ?? null,
+    };
+}
 /**
  * Retrieves a batch of key-value pairs.
  */
@@ -31,8 +44,11 @@ export async function getBatch(keys, options) {
  * Retrieves all key-value pairs in the KV store. When using any of the options, the keys lexicographic order
  * is used for filtering.
  *
+ * If `options.limit` is reached, the returned map has a `cursor` which can be passed as `options.cursor` to
+ * continue listing after the last returned key (or before it when listing in reverse).
+ *
  * @param {ListOptions} [options] - Options.
- * @returns {Promise<HashMap<Key, Entry>>} The retrieved values.
+ * @returns {Promise<ListResult>} The retrieved values.
  */
 export async function list(options) {
     // Build query
@@ -42,45 +58,45 @@ export async function list(options) {
             prefix: serializeListKey(options.prefix),
         };
     }
-    else if (options?.start) {
-        if (!options.end) {
-            throw new Error("must set options.end with options.start");
+    else if (options?.start ||
+        options?.startAfter ||
+        options?.end ||
+        options?.endBefore) {
+        if (options.start && options.startAfter) {
+            throw new Error("cannot set both options.start and options.startAfter");
         }
-        query = {
-            rangeInclusive: [
-                serializeListKey(options.start),
-                serializeKey(options.end),
-            ],
-        };
-    }
-    else if (options?.startAfter) {
-        if (!options.end) {
-            throw new Error("must set options.end with options.startAfter");
+        if (options.end && options.endBefore) {
+            throw new Error("cannot set both options.end and options.endBefore");
         }
+        let start = null;
+        if (options.start)
+            start = { inclusive: serializeKey(options.start) };
+        else if (options.startAfter)
+            start = { exclusive: serializeKey(options.startAfter) };
+        let end = null;
+        if (options.end)
+            end = { inclusive: serializeKey(options.end) };
+        else if (options.endBefore)
+            end = { exclusive: serializeKey(options.endBefore) };
         query = {
-            rangeExclusive: [
-                serializeListKey(options.startAfter),
-                serializeKey(options.end),
-            ],
+            range: { start, end },
         };
     }
-    else if (options?.end) {
-        throw new Error("must set options.start or options.startAfter with options.end");
-    }
     else {
         query = { all: {} };
     }
-    const entries = await op_rivet_kv_list(query, options?.reverse ?? false, options?.limit);
-    return new HashMap(entries.map(([key, entry]) => {
+    const { entries, cursor } = await op_rivet_kv_list(query, options?.reverse ?? false, options?.limit, options?.cursor ?? null);
+    return new ListResult(entries.map(([key, entry]) => {
         const jsKey = deserializeKey(key);
         return [
             jsKey,
             deserializeValue(jsKey, entry.value, options?.format),
         ];
-    }));
+    }), cursor ?? undefined);
 }
 /**
- * Stores a key-value pair in the key-value store.
+ * Stores a key-value pair in the key-value store. If `options.ttl` (in milliseconds) is set, the key expires
+ * after the TTL and is no longer returned by reads. Writing to the key again without a TTL removes the TTL.
  *
  * @param {Key} key - The key under which the value will be stored.
  * @param {Entry | ArrayBuffer} value - The value to be stored, which will be serialized.
@@ -105,10 +121,10 @@ export async function put(key, value, options) {
         // Handled by validateType
         throw new Error(`unreachable format: \`${format}\``);
     }
-    await op_rivet_kv_put(serializeKey(key), serializedValue);
+    await op_rivet_kv_put(serializeKey(key), serializedValue, serializeTtl(options?.ttl));
 }
 /**
- * Stores a batch of key-value pairs.
+ * Stores a batch of key-value pairs. `options.ttl` applies to all of the keys, see `put`.
  *
  * @param {Map<Key, Entry | ArrayBuffer>} obj - An object containing key-value pairs to be stored.
  * @param {PutBatchOptions} [options] - Options.
@@ -136,7 +152,7 @@ export async function putBatch(obj, options) {
         }
         serializedObj.set(serializeKey(key), serializedValue);
     }
-    await op_rivet_kv_put_batch(serializedObj);
+    await op_rivet_kv_put_batch(serializedObj, serializeTtl(options?.ttl));
 }
 /**
  * Deletes a key-value pair from the key-value store.
@@ -156,6 +172,60 @@ export async function delete_(key) {
 export async function deleteBatch(keys) {
     return await op_rivet_kv_delete_batch(keys.map((x) => serializeKey(x)));
 }
+/**
+ * Atomically applies all mutations if all checks pass. Atomic operations (`add`, `min`, `max`) treat values
+ * as 64 bit little-endian unsigned integers and take either a `bigint` or an `ArrayBuffer` as the operand.
+ * `bigint` operands must fit in 64 bits unsigned, except for `add` which also accepts negative operands down
+ * to -2^63 to subtract. Read their values with `options.format = "arrayBuffer"`. Puts take an optional third element with a `ttl`, see
+ * `put`.
+ *
+ * @param {Transaction} tx - Checks and mutations to apply.
+ * @param {TransactOptions} [options] - Options.
+ * @returns {Promise<boolean>} Whether the checks passed and the mutations were applied.
+ */
+export async function transact(tx, options) {
+    const checks = (tx.checks ?? []).map((check) => {
+        if ("absent" in check) {
+            return { absent: serializeKey(check.absent) };
+        }
+        else if ("version" in check) {
+            const [key, version] = check.version;
+            if (version == null) {
+                throw new Error("version check requires a version, use an absent check instead");
+            }
+            return { version: [serializeKey(key), version] };
+        }
+        throw new Error("invalid transaction check, expected `absent` or `version`");
+    });
+    const format = options?.format ?? "value";
+    const mutations = (tx.mutations ?? []).map((mutation) => {
+        if ("put" in mutation) {
+            const [key, value, putOptions] = mutation.put;
+            validateType(value, key, format);
+            let serializedValue;
+            if (format === "value") {
+                serializedValue = core.serialize(value, { forStorage: true });
+            }
+            else {
+                serializedValue = new Uint8Array(value);
+            }
+            return {
+                put: [serializeKey(key), serializedValue, serializeTtl(putOptions?.ttl)],
+            };
+        }
+        else if ("delete" in mutation) {
+            return { delete: serializeKey(mutation.delete) };
+        }
+        for (const op of ["add", "min", "max"]) {
+            if (op in mutation) {
+                const [key, operand] = mutation[op];
+                return { [op]: [serializeKey(key), serializeAtomicOperand(op, operand)] };
+            }
+        }
+        throw new Error("invalid transaction mutation, expected `put`, `delete`, `add`, `min`, or `max`");
+    });
+    return await op_rivet_kv_transact(checks, mutations);
+}
 /**
  * Deletes all data from the key-value store. **This CANNOT be undone.**
  *
@@ -195,6 +265,31 @@ function serializeKey(key) {
     }
     return { jsInKey: [core.serialize(key)] };
 }
+function serializeTtl(ttl) {
+    if (ttl === undefined || ttl === null)
+        return null;
+    if (!Number.isInteger(ttl) || ttl <= 0) {
+        throw new Error("options.ttl must be a positive integer");
+    }
+    return ttl;
+}
+function serializeAtomicOperand(op, operand) {
+    if (typeof operand === "bigint") {
+        // Values are compared as unsigned by `min` and `max`. Negative operands are only meaningful for `add`,
+        // where they wrap around like two's complement
+        const min = op === "add" ? -(2n ** 63n) : 0n;
+        if (operand < min || operand >= 2n ** 64n) {
+            throw new Error(`atomic operand for \`${op}\` must be between ${min} and 2^64 - 1`);
+        }
+        const buf = new Uint8Array(8);
+        new DataView(buf.buffer).setBigUint64(0, BigInt.asUintN(64, operand), true);
+        return buf;
+    }
+    else if (operand instanceof ArrayBuffer) {
+        return new Uint8Array(operand);
+    }
+    throw new Error("atomic operand must be a `bigint` or an `ArrayBuffer`");
+}
 function serializeListKey(key) {
     if (Array.isArray(key)) {
         return key.map((x) => core.serialize(x));
@@ -258,8 +353,19 @@ class HashMap {
         return this.#internal[Symbol.iterator]();
     }
 }
+class ListResult extends HashMap {
+    /**
+     * Opaque cursor to continue listing from. Only set if the list limit was reached.
+     */
+    cursor;
+    constructor(internal, cursor) {
+        super(internal);
+        this.cursor = cursor;
+    }
+}
 export const KV_NAMESPACE = {
     get,
+    getWithVersion,
     getBatch,
     list,
     put,
@@ -267,4 +373,5 @@ export const KV_NAMESPACE = {
     delete: delete_,
     deleteBatch,
     deleteAll,
+    transact,
 };
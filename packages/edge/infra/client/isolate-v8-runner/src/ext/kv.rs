@@ -17,6 +17,7 @@ deno_core::extension!(
 		op_rivet_kv_delete,
 		op_rivet_kv_delete_batch,
 		op_rivet_kv_delete_all,
+		op_rivet_kv_transact,
 	],
 	esm = [
 		dir "js",
@@ -30,10 +31,10 @@ deno_core::extension!(
 		"40_rivet_kv.js",
 	],
 	options = {
-		kv: actor_kv::ActorKv,
+		kv: Arc<actor_kv::ActorKv>,
 	},
 	state = |state, options| {
-		state.put::<Arc<actor_kv::ActorKv>>(Arc::new(options.kv));
+		state.put::<Arc<actor_kv::ActorKv>>(options.kv);
 	},
 );
 
@@ -59,6 +60,7 @@ impl From<actor_kv::key::Key> for Key {
 #[serde(rename_all = "camelCase")]
 struct Entry {
 	metadata: Metadata,
+	version: Option<ToJsBuffer>,
 	value: ToJsBuffer,
 }
 
@@ -66,6 +68,7 @@ impl From<actor_kv::Entry> for Entry {
 	fn from(value: actor_kv::Entry) -> Self {
 		Entry {
 			metadata: value.metadata.into(),
+			version: value.version.map(Into::into),
 			value: value.value.into(),
 		}
 	}
@@ -76,6 +79,7 @@ impl From<actor_kv::Entry> for Entry {
 pub struct Metadata {
 	pub kv_version: ToJsBuffer,
 	pub create_ts: i64,
+	pub expire_ts: Option<i64>,
 }
 
 impl From<actor_kv::Metadata> for Metadata {
@@ -83,6 +87,7 @@ impl From<actor_kv::Metadata> for Metadata {
 		Metadata {
 			kv_version: value.kv_version.into(),
 			create_ts: value.create_ts,
+			expire_ts: value.expire_ts,
 		}
 	}
 }
@@ -122,6 +127,13 @@ pub fn op_rivet_kv_get_batch(
 	})
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListOutput {
+	entries: FakeMap<Key, Entry>,
+	cursor: Option<ToJsBuffer>,
+}
+
 #[op2(async)]
 #[serde]
 pub fn op_rivet_kv_list(
@@ -129,18 +141,27 @@ pub fn op_rivet_kv_list(
 	#[serde] query: actor_kv::ListQuery,
 	reverse: bool,
 	limit: Option<u32>,
-) -> Result<impl Future<Output = Result<FakeMap<Key, Entry>, AnyError>>, AnyError> {
+	#[serde] cursor: Option<JsBuffer>,
+) -> Result<impl Future<Output = Result<ListOutput, AnyError>>, AnyError> {
 	let kv = state.borrow::<Arc<actor_kv::ActorKv>>().clone();
 
 	Ok(async move {
-		let res = kv
-			.list(query.into(), reverse, limit.map(|x| x as usize))
-			.await?
-			.into_iter()
-			.map(|(k, v)| (k.into(), v.into()))
-			.collect();
-
-		Ok(res)
+		let (entries, cursor) = kv
+			.list(
+				query.into(),
+				reverse,
+				limit.map(|x| x as usize),
+				cursor.as_deref(),
+			)
+			.await?;
+
+		Ok(ListOutput {
+			entries: entries
+				.into_iter()
+				.map(|(k, v)| (k.into(), v.into()))
+				.collect(),
+			cursor: cursor.map(Into::into),
+		})
 	})
 }
 
@@ -149,20 +170,22 @@ pub fn op_rivet_kv_put(
 	state: &mut OpState,
 	#[serde] key: actor_kv::key::Key,
 	#[buffer] value: JsBuffer,
+	#[serde] ttl: Option<i64>,
 ) -> Result<impl Future<Output = Result<(), AnyError>>, AnyError> {
 	let kv = state.borrow::<Arc<actor_kv::ActorKv>>().clone();
 
-	Ok(async move { kv.put([(key, value)].into()).await })
+	Ok(async move { kv.put([(key, value)].into(), ttl).await })
 }
 
 #[op2(async)]
 pub fn op_rivet_kv_put_batch(
 	state: &mut OpState,
 	#[serde] obj: HashMap<actor_kv::key::Key, JsBuffer>,
+	#[serde] ttl: Option<i64>,
 ) -> Result<impl Future<Output = Result<(), AnyError>>, AnyError> {
 	let kv = state.borrow::<Arc<actor_kv::ActorKv>>().clone();
 
-	Ok(async move { kv.put(obj).await })
+	Ok(async move { kv.put(obj, ttl).await })
 }
 
 #[op2(async)]
@@ -194,3 +217,14 @@ pub fn op_rivet_kv_delete_all(
 
 	Ok(async move { kv.delete_all().await })
 }
+
+#[op2(async)]
+pub fn op_rivet_kv_transact(
+	state: &mut OpState,
+	#[serde] checks: Vec<actor_kv::Check>,
+	#[serde] mutations: Vec<actor_kv::Mutation>,
+) -> Result<impl Future<Output = Result<bool, AnyError>>, AnyError> {
+	let kv = state.borrow::<Arc<actor_kv::ActorKv>>().clone();
+
+	Ok(async move { kv.transact(checks, mutations).await })
+}
@@ -8,6 +8,7 @@ use std::{
 	result::Result::{Err, Ok},
 	sync::{mpsc as smpsc, Arc},
 	thread::JoinHandle,
+	time::Duration,
 };
 
 use anyhow::*;
@@ -33,6 +34,9 @@ use uuid::Uuid;
 
 use crate::{ext, log_shipper, metadata::JsMetadata, utils};
 
+/// How often expired KV keys are deleted.
+const KV_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
 pub fn run(
 	config: config::Config,
 	fdb_pool: FdbPool,
@@ -149,9 +153,17 @@ pub async fn run_inner(
 	// Init KV store (create or open)
 	let mut kv = ActorKv::new((&*fdb_pool).clone(), actor_id);
 	kv.init().await?;
+	let kv = Arc::new(kv);
 
 	tracing::info!(?actor_id, ?generation, "isolate kv initialized");
 
+	// Delete the keys that expired while the actor was stopped before starting so their storage is not
+	// counted towards the storage limit
+	sweep_expired_kv(&kv, actor_id).await;
+
+	// Delete expired KV keys in the background. Stops when the isolate's runtime is dropped
+	tokio::task::spawn(sweep_kv(kv.clone(), actor_id));
+
 	// Should match the path from `Actor::make_fs` in manager/src/actor/setup.rs
 	let index = actor_path.join("fs").join("upper").join("index.js");
 
@@ -401,6 +413,30 @@ pub async fn run_inner(
 	Ok(exit_code)
 }
 
+async fn sweep_kv(kv: Arc<ActorKv>, actor_id: Uuid) {
+	// Already swept on start
+	let mut interval = tokio::time::interval_at(
+		tokio::time::Instant::now() + KV_SWEEP_INTERVAL,
+		KV_SWEEP_INTERVAL,
+	);
+	interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+	loop {
+		interval.tick().await;
+
+		sweep_expired_kv(&kv, actor_id).await;
+	}
+}
+
+/// Deletes expired KV keys. Errors are logged since keys are swept again later.
+pub async fn sweep_expired_kv(kv: &ActorKv, actor_id: Uuid) {
+	match kv.sweep_expired().await {
+		Ok(0) => {}
+		Ok(deleted) => tracing::debug!(?actor_id, ?deleted, "deleted expired kv keys"),
+		Err(err) => tracing::error!(?actor_id, "Failed to sweep expired kv keys: {err:?}"),
+	}
+}
+
 // Reads the `start` function from the default export of index.js and calls it.
 fn handle_entrypoint(
 	actor_config: config::actor::Config,
@@ -532,7 +568,6 @@ mod tests {
 
 	use anyhow::*;
 	use deno_runtime::worker::MainWorkerTerminateHandle;
-	use foundationdb as fdb;
 	use pegboard::protocol;
 	use pegboard_config::isolate_runner as config;
 	use tracing_subscriber::prelude::*;
@@ -544,18 +579,58 @@ mod tests {
 	// TODO: Currently requires an fdb container to be running already
 	#[tokio::test]
 	async fn test_isolate() -> Result<()> {
-		tracing_subscriber::registry()
+		let exit_code = run_test_actor("index.js").await?;
+
+		// See `Deno.exit` in the script
+		ensure!(exit_code == Some(2));
+
+		Ok(())
+	}
+
+	/// Runs `tests/kv_ttl.js`, which exits with a non-zero code if expired keys are still readable.
+	// TODO: Currently requires an fdb container to be running already
+	#[tokio::test]
+	async fn test_kv_ttl() -> Result<()> {
+		let exit_code = run_test_actor("kv_ttl.js").await?;
+
+		ensure!(
+			exit_code == Some(0),
+			"kv ttl test failed with {exit_code:?}"
+		);
+
+		Ok(())
+	}
+
+	/// Runs `tests/kv_atomic.js`, which exits with a non-zero code if atomic operands are not encoded as
+	/// unsigned integers.
+	// TODO: Currently requires an fdb container to be running already
+	#[tokio::test]
+	async fn test_kv_atomic() -> Result<()> {
+		let exit_code = run_test_actor("kv_atomic.js").await?;
+
+		ensure!(
+			exit_code == Some(0),
+			"kv atomic test failed with {exit_code:?}"
+		);
+
+		Ok(())
+	}
+
+	/// Runs the given script from the tests folder as a new actor and returns its exit code.
+	async fn run_test_actor(script: &str) -> Result<Option<i32>> {
+		let _ = tracing_subscriber::registry()
 			.with(
 				tracing_logfmt::builder()
 					.with_ansi_color(true)
 					.layer()
 					.with_filter(tracing_subscriber::filter::LevelFilter::INFO),
 			)
-			.init();
+			.try_init();
 
 		let tmp_dir = tempfile::TempDir::new().unwrap();
 		let actors_path = tmp_dir.path().join("actors");
-		let actor_id = Uuid::nil();
+		// Each run uses a new actor so its KV is empty
+		let actor_id = Uuid::new_v4();
 		let generation = 0;
 
 		let fs_path = actors_path
@@ -565,15 +640,21 @@ mod tests {
 		std::fs::create_dir_all(&fs_path)?;
 
 		std::fs::copy(
-			Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/index.js"),
+			Path::new(env!("CARGO_MANIFEST_DIR"))
+				.join("tests")
+				.join(script),
 			fs_path.join("index.js"),
 		)?;
-		std::fs::write(tmp_dir.path().join("fdb.cluster"), "fdb:fdb@127.0.0.1:4500")?;
 
 		let config = config::Config {
 			// Not important
 			actors_path: Path::new("").to_path_buf(),
 			manager_ws_addr: SocketAddr::from(([0, 0, 0, 0], 0)),
+			foundationdb: pegboard_config::FoundationDb {
+				cluster_description: "fdb".to_string(),
+				cluster_id: "fdb".to_string(),
+				addresses: pegboard_config::Addresses::Static(vec!["127.0.0.1:4500".to_string()]),
+			},
 		};
 
 		deno_core::v8_set_flags(vec![
@@ -584,7 +665,7 @@ mod tests {
 		]);
 
 		// Start FDB network thread
-		fdb_util::init(&tmp_dir.path().join("fdb.cluster"));
+		let fdb_pool = utils::setup_fdb_pool(&config).await?;
 
 		// For receiving the terminate handle
 		let (terminate_tx, _terminate_rx) =
@@ -599,12 +680,13 @@ mod tests {
 			env: Default::default(),
 			metadata: protocol::Raw::new(&protocol::ActorMetadata {
 				actor: protocol::ActorMetadataActor {
-					actor_id: Uuid::nil(),
+					actor_id,
 					tags: [("foo".to_string(), "bar".to_string())]
 						.into_iter()
 						.collect(),
 					create_ts: 0,
 				},
+				network: None,
 				project: protocol::ActorMetadataProject {
 					project_id: Uuid::nil(),
 					slug: "foo".into(),
@@ -628,19 +710,15 @@ mod tests {
 			vector_socket_addr: Default::default(),
 		};
 
-		let exit_code = run_inner(
-			config,
-			actors_path.join(actor_id.to_string()).to_path_buf(),
+		run_inner(
+			fdb_pool,
+			actors_path.join(format!("{actor_id}-{generation}")),
 			actor_id,
 			generation,
 			terminate_tx,
 			None,
 			actor_config,
 		)
-		.await?;
-
-		ensure!(exit_code == 0);
-
-		Ok(())
+		.await
 	}
 }
@@ -300,6 +300,10 @@ async fn watch_thread(
 			fatal_tx.send(()).expect("receiver cannot be dropped");
 			return;
 		};
+	} else {
+		// Expired keys are only swept while the isolate is running, delete the ones that expired so far
+		// instead of keeping them until the actor is started again
+		tokio::spawn(sweep_stopped_kv(fdb_pool, actor_id));
 	}
 
 	// Cleanup thread
@@ -307,6 +311,17 @@ async fn watch_thread(
 	cleanup_thread(actor_id, generation, handle, &fatal_tx);
 }
 
+async fn sweep_stopped_kv(fdb_pool: FdbPool, actor_id: Uuid) {
+	let mut kv = ActorKv::new((&*fdb_pool).clone(), actor_id);
+
+	if let Err(err) = kv.init().await {
+		tracing::error!(?actor_id, "Failed to sweep expired kv keys: {err:?}");
+		return;
+	}
+
+	isolate::sweep_expired_kv(&kv, actor_id).await;
+}
+
 async fn poll_thread(handle: &JoinHandle<Result<()>>) {
 	loop {
 		if handle.is_finished() {
@@ -0,0 +1,49 @@
+// Used by `test_kv_atomic` in isolate.rs
+
+function assert(condition, message) {
+	if (!condition) {
+		console.error(`assertion failed: ${message}`);
+		Deno.exit(1);
+	}
+}
+
+async function read(ctx, key) {
+	const buf = await ctx.kv.get(key, { format: "arrayBuffer" });
+	return new DataView(buf).getBigUint64(0, true);
+}
+
+async function rejects(ctx, mutation) {
+	try {
+		await ctx.kv.transact({ mutations: [mutation] });
+	} catch {
+		return true;
+	}
+
+	return false;
+}
+
+export default {
+	async start(ctx) {
+		// Negative operands subtract
+		await ctx.kv.transact({ mutations: [{ add: [["counter"], 5n] }] });
+		await ctx.kv.transact({ mutations: [{ add: [["counter"], -2n] }] });
+		assert((await read(ctx, ["counter"])) === 3n, "add with a negative operand");
+
+		// Values are compared as unsigned, values above 2^63 are larger than small values
+		await ctx.kv.transact({ mutations: [{ max: [["max"], 1n] }] });
+		await ctx.kv.transact({ mutations: [{ max: [["max"], 2n ** 63n] }] });
+		assert((await read(ctx, ["max"])) === 2n ** 63n, "max above 2^63");
+
+		await ctx.kv.transact({ mutations: [{ min: [["min"], 2n ** 64n - 1n] }] });
+		await ctx.kv.transact({ mutations: [{ min: [["min"], 7n] }] });
+		assert((await read(ctx, ["min"])) === 7n, "min below 2^64 - 1");
+
+		// Operands out of range are rejected instead of wrapping
+		assert(await rejects(ctx, { min: [["min"], -1n] }), "negative min operand");
+		assert(await rejects(ctx, { max: [["max"], 2n ** 64n] }), "max operand above 2^64 - 1");
+		assert(await rejects(ctx, { add: [["counter"], -(2n ** 63n) - 1n] }), "add operand below -2^63");
+		assert((await read(ctx, ["min"])) === 7n, "rejected operand was applied");
+
+		Deno.exit(0);
+	},
+};
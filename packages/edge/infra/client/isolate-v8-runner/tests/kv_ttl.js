@@ -0,0 +1,45 @@
+// Used by `test_kv_ttl` in isolate.rs
+
+function assert(condition, message) {
+	if (!condition) {
+		console.error(`assertion failed: ${message}`);
+		Deno.exit(1);
+	}
+}
+
+export default {
+	async start(ctx) {
+		await ctx.kv.put(["ttl", "put"], 1, { ttl: 500 });
+		await ctx.kv.putBatch(new Map([[["ttl", "batch"], 2]]), { ttl: 500 });
+		await ctx.kv.transact({
+			mutations: [{ put: [["ttl", "transact"], 3, { ttl: 500 }] }],
+		});
+		await ctx.kv.put(["ttl", "persistent"], 4);
+		await ctx.kv.put(["ttl", "overwritten"], 5, { ttl: 500 });
+		await ctx.kv.put(["ttl", "overwritten"], 6);
+
+		assert((await ctx.kv.get(["ttl", "put"])) === 1, "put key missing before expiring");
+		assert((await ctx.kv.get(["ttl", "batch"])) === 2, "batch key missing before expiring");
+		assert(
+			(await ctx.kv.get(["ttl", "transact"])) === 3,
+			"transact key missing before expiring",
+		);
+
+		await new Promise((resolve) => setTimeout(resolve, 1000));
+
+		assert((await ctx.kv.get(["ttl", "put"])) === null, "put key not expired");
+		assert((await ctx.kv.get(["ttl", "batch"])) === null, "batch key not expired");
+		assert((await ctx.kv.get(["ttl", "transact"])) === null, "transact key not expired");
+		assert(
+			(await ctx.kv.get(["ttl", "overwritten"])) === 6,
+			"writing a key without a ttl did not remove its ttl",
+		);
+
+		// Only the keys without a ttl are listed
+		const res = await ctx.kv.list({ prefix: ["ttl"] });
+		assert(res.array().length === 2, `expected 2 listed keys, got ${res.array().length}`);
+		assert(res.get(["ttl", "persistent"]) === 4, "persistent key not listed");
+
+		Deno.exit(0);
+	},
+};
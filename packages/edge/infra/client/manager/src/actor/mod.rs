@@ -19,6 +19,7 @@ mod oci_config;
 mod partial_oci_config;
 mod seccomp;
 mod setup;
+mod snapshot;
 
 /// How often to check for a PID when one is not present and a stop command was received.
 const STOP_PID_INTERVAL: Duration = std::time::Duration::from_millis(250);
@@ -32,6 +33,10 @@ pub struct Actor {
 
 	runner: Mutex<Option<runner::Handle>>,
 	exited: Mutex<bool>,
+	/// Set by the last signal sent to the actor. Storage is kept unless the actor is being destroyed.
+	persist_storage: Mutex<bool>,
+	/// Set when the overlay was unmounted to snapshot the upper layer.
+	overlay_unmounted: Mutex<bool>,
 }
 
 impl Actor {
@@ -43,6 +48,8 @@ impl Actor {
 
 			runner: Mutex::new(None),
 			exited: Mutex::new(false),
+			persist_storage: Mutex::new(true),
+			overlay_unmounted: Mutex::new(false),
 		})
 	}
 
@@ -59,6 +66,8 @@ impl Actor {
 
 			runner: Mutex::new(Some(runner)),
 			exited: Mutex::new(false),
+			persist_storage: Mutex::new(true),
+			overlay_unmounted: Mutex::new(false),
 		})
 	}
 
@@ -342,6 +351,9 @@ impl Actor {
 			}
 		};
 
+		// Uploaded before the exit is reported so the next generation restores from it
+		self.upload_snapshot(ctx).await;
+
 		self.set_exit_code(ctx, exit_code).await?;
 
 		tracing::info!(actor_id=?self.actor_id, generation=?self.generation, "complete");
@@ -357,6 +369,8 @@ impl Actor {
 	) -> Result<()> {
 		tracing::info!(actor_id=?self.actor_id, generation=?self.generation, ?signal, "sending signal");
 
+		*self.persist_storage.lock().await = persist_storage;
+
 		let self2 = self.clone();
 		let ctx2 = ctx.clone();
 		tokio::spawn(async move {
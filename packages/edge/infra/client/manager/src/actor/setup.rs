@@ -88,6 +88,8 @@ impl Actor {
 				.await
 				.context("failed to create actor fs work dir")?;
 
+			self.restore_snapshot(&fs_upper_path).await?;
+
 			tracing::info!(actor_id=?self.actor_id, generation=?self.generation, "mounting overlay");
 
 			ensure!(
@@ -134,6 +136,8 @@ impl Actor {
 			utils::copy_dir_all(image_path, &fs_upper_path)
 				.await
 				.context("failed to copy image contents to fs upper dir")?;
+
+			self.restore_snapshot(&fs_upper_path).await?;
 		}
 
 		let duration = timer.elapsed().as_secs_f64();
@@ -555,31 +559,34 @@ impl Actor {
 
 		// Clean up fs mounts
 		if ctx.config().runner.use_mounts() {
-			match Command::new("umount")
-				.arg("-dl")
-				.arg(actor_path.join("fs").join("upper"))
-				.output()
-				.await
-			{
-				Result::Ok(cmd_out) => {
-					if !cmd_out.status.success() {
+			// Already unmounted if a snapshot was taken
+			if !*self.overlay_unmounted.lock().await {
+				match Command::new("umount")
+					.arg("-dl")
+					.arg(actor_path.join("fs").join("upper"))
+					.output()
+					.await
+				{
+					Result::Ok(cmd_out) => {
+						if !cmd_out.status.success() {
+							tracing::error!(
+								actor_id=?self.actor_id,
+								generation=?self.generation,
+								stdout=%std::str::from_utf8(&cmd_out.stdout).unwrap_or("<failed to parse stdout>"),
+								stderr=%std::str::from_utf8(&cmd_out.stderr).unwrap_or("<failed to parse stderr>"),
+								"failed overlay `umount` command",
+							);
+						}
+					}
+					Err(err) => {
 						tracing::error!(
 							actor_id=?self.actor_id,
 							generation=?self.generation,
-							stdout=%std::str::from_utf8(&cmd_out.stdout).unwrap_or("<failed to parse stdout>"),
-							stderr=%std::str::from_utf8(&cmd_out.stderr).unwrap_or("<failed to parse stderr>"),
-							"failed overlay `umount` command",
+							?err,
+							"failed to run overlay `umount` command",
 						);
 					}
 				}
-				Err(err) => {
-					tracing::error!(
-						actor_id=?self.actor_id,
-						generation=?self.generation,
-						?err,
-						"failed to run overlay `umount` command",
-					);
-				}
 			}
 
 			match Command::new("umount")
@@ -0,0 +1,171 @@
+use std::{
+	path::Path,
+	result::Result::{Err, Ok},
+	time::{Duration, Instant},
+};
+
+use anyhow::*;
+use pegboard::protocol;
+use tokio::{fs, process::Command};
+
+use super::Actor;
+use crate::{ctx::Ctx, utils};
+
+/// How long to wait for the snapshot upload before reporting the actor's exit without one. Must be less than
+/// `ACTOR_SNAPSHOT_EXIT_THRESHOLD_MS` in the actor workflow or the actor is considered lost while uploading.
+const UPLOAD_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+impl Actor {
+	/// Extracts the latest snapshot of the actor's file system upper layer into the given dir.
+	pub async fn restore_snapshot(&self, fs_upper_path: &Path) -> Result<()> {
+		let Some(restore_url) = self
+			.config
+			.snapshot
+			.as_ref()
+			.and_then(|snapshot| snapshot.restore_url.as_ref())
+		else {
+			return Ok(());
+		};
+
+		let timer = Instant::now();
+		tracing::info!(actor_id=?self.actor_id, generation=?self.generation, "restoring snapshot");
+
+		utils::archive::extract_from_url(restore_url, fs_upper_path)
+			.await
+			.context("failed to restore snapshot")?;
+
+		let duration = timer.elapsed().as_secs_f64();
+		crate::metrics::SETUP_RESTORE_SNAPSHOT_DURATION.observe(duration);
+		tracing::info!(
+			actor_id=?self.actor_id,
+			generation=?self.generation,
+			duration_seconds=duration,
+			"snapshot restore completed",
+		);
+
+		Ok(())
+	}
+
+	/// Uploads the actor's file system upper layer after it exited and reports the snapshot to the server.
+	/// Failures are logged instead of returned so the exit is always reported.
+	#[tracing::instrument(skip_all)]
+	pub async fn upload_snapshot(&self, ctx: &Ctx) {
+		let Some(snapshot) = &self.config.snapshot else {
+			return;
+		};
+
+		// Storage is not kept when the actor is destroyed
+		if !*self.persist_storage.lock().await {
+			return;
+		}
+
+		match tokio::time::timeout(UPLOAD_TIMEOUT, self.upload_snapshot_inner(ctx, snapshot)).await
+		{
+			Ok(Ok(())) => {}
+			Ok(Err(err)) => {
+				tracing::error!(
+					actor_id=?self.actor_id,
+					generation=?self.generation,
+					?err,
+					"failed to upload snapshot",
+				);
+			}
+			Err(_) => {
+				tracing::error!(
+					actor_id=?self.actor_id,
+					generation=?self.generation,
+					"timed out uploading snapshot",
+				);
+			}
+		}
+	}
+
+	async fn upload_snapshot_inner(
+		&self,
+		ctx: &Ctx,
+		snapshot: &protocol::ActorSnapshot,
+	) -> Result<()> {
+		let timer = Instant::now();
+		tracing::info!(actor_id=?self.actor_id, generation=?self.generation, "uploading snapshot");
+
+		let actor_path = ctx.actor_path(self.actor_id, self.generation);
+		let fs_upper_path = actor_path.join("fs").join("upper");
+		let snapshot_path = actor_path.join("snapshot.tar.lz4");
+
+		// The upper layer is hidden by the overlay mounted on top of it
+		if ctx.config().runner.use_mounts() {
+			let cmd_out = Command::new("umount")
+				.arg(&fs_upper_path)
+				.output()
+				.await
+				.context("failed to run overlay `umount`")?;
+
+			ensure!(
+				cmd_out.status.success(),
+				"failed overlay `umount` command\n{}",
+				std::str::from_utf8(&cmd_out.stderr)?
+			);
+
+			*self.overlay_unmounted.lock().await = true;
+		}
+
+		utils::archive::create(&fs_upper_path, &snapshot_path)
+			.await
+			.context("failed to archive snapshot")?;
+
+		let cmd_out = Command::new("sha256sum")
+			.arg(&snapshot_path)
+			.output()
+			.await
+			.context("failed to run `sha256sum`")?;
+
+		ensure!(
+			cmd_out.status.success(),
+			"failed `sha256sum` command\n{}",
+			std::str::from_utf8(&cmd_out.stderr)?
+		);
+
+		let hash = std::str::from_utf8(&cmd_out.stdout)?
+			.split_whitespace()
+			.next()
+			.context("empty `sha256sum` output")?
+			.to_string();
+		let size_bytes = fs::metadata(&snapshot_path).await?.len();
+
+		let cmd_out = Command::new("curl")
+			.arg("-sSf")
+			// Uploads with PUT
+			.arg("-T")
+			.arg(&snapshot_path)
+			.arg(&snapshot.upload_url)
+			.output()
+			.await
+			.context("failed to run snapshot upload `curl`")?;
+
+		ensure!(
+			cmd_out.status.success(),
+			"failed snapshot upload `curl` command\n{}",
+			std::str::from_utf8(&cmd_out.stderr)?
+		);
+
+		ctx.event(protocol::Event::ActorSnapshotCreated {
+			actor_id: self.actor_id,
+			generation: self.generation,
+			hash,
+			size_bytes,
+		})
+		.await?;
+
+		let duration = timer.elapsed().as_secs_f64();
+		crate::metrics::SNAPSHOT_UPLOAD_DURATION.observe(duration);
+		tracing::info!(
+			actor_id=?self.actor_id,
+			generation=?self.generation,
+			duration_seconds=duration,
+			size_bytes,
+			"snapshot upload completed",
+		);
+
+		Ok(())
+	}
+}
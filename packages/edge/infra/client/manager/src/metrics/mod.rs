@@ -80,6 +80,13 @@ lazy_static::lazy_static! {
 		*REGISTRY,
 	).unwrap();
 
+	pub static ref SETUP_RESTORE_SNAPSHOT_DURATION: Histogram = register_histogram_with_registry!(
+		"actor_setup_restore_snapshot_duration",
+		"Duration of fs snapshot restore step",
+		BUCKETS.to_vec(),
+		*REGISTRY,
+	).unwrap();
+
 	pub static ref SETUP_BIND_PORTS_DURATION: Histogram = register_histogram_with_registry!(
 		"actor_setup_bind_ports_duration",
 		"Duration of port binding step",
@@ -115,6 +122,13 @@ lazy_static::lazy_static! {
 		*REGISTRY,
 	).unwrap();
 
+	pub static ref SNAPSHOT_UPLOAD_DURATION: Histogram = register_histogram_with_registry!(
+		"actor_snapshot_upload_duration",
+		"Duration of fs snapshot archive and upload after the actor exits",
+		BUCKETS.to_vec(),
+		*REGISTRY,
+	).unwrap();
+
 	pub static ref IMAGE_DOWNLOAD_REQUEST_TOTAL: IntCounter = register_int_counter_with_registry!(
 		"image_download_request_total",
 		"Total number of download requests.",
@@ -0,0 +1,117 @@
+use std::{
+	path::Path,
+	process::Stdio,
+	result::Result::{Err, Ok},
+};
+
+use anyhow::*;
+use tokio::{fs, process::Command};
+
+// Keep overlay whiteouts and opaque dir markers as well as file ownership
+const TAR_FLAGS: &[&str] = &["--xattrs", "--xattrs-include=*", "--numeric-owner"];
+// Generated on every start by `setup_oci_bundle`
+const TAR_EXCLUDE: &[&str] = &["--exclude=./config.json", "--exclude=./hosts"];
+
+/// Archives the contents of `src_path` into an lz4 compressed tarball at `dst_path`.
+pub async fn create(src_path: &Path, dst_path: &Path) -> Result<()> {
+	let mut tar = Command::new("tar");
+	tar.arg("-c")
+		.args(TAR_FLAGS)
+		.args(TAR_EXCLUDE)
+		.arg("-C")
+		.arg(src_path)
+		.arg(".");
+
+	let dst = fs::File::create(dst_path)
+		.await
+		.with_context(|| format!("failed to create `{}`", dst_path.display()))?;
+	let mut lz4 = Command::new("lz4");
+	lz4.arg("-c").stdout(dst.into_std().await);
+
+	run_pipeline(vec![tar, lz4]).await
+}
+
+/// Downloads an lz4 compressed tarball from `url` and extracts it into `dst_path`.
+pub async fn extract_from_url(url: &str, dst_path: &Path) -> Result<()> {
+	let mut curl = Command::new("curl");
+	curl.arg("-sSfL").arg(url);
+
+	let mut lz4 = Command::new("lz4");
+	lz4.arg("-dc");
+
+	let mut tar = Command::new("tar");
+	tar.arg("-x").args(TAR_FLAGS).arg("-C").arg(dst_path);
+
+	run_pipeline(vec![curl, lz4, tar]).await
+}
+
+/// Runs the commands with the stdout of each one piped into the stdin of the next. Unlike a shell pipeline
+/// without `pipefail`, this fails if any of the commands fail instead of only the last one.
+async fn run_pipeline(cmds: Vec<Command>) -> Result<()> {
+	let len = cmds.len();
+	let mut children = Vec::with_capacity(len);
+	let mut stdin = None::<Stdio>;
+
+	for (i, mut cmd) in cmds.into_iter().enumerate() {
+		let program = cmd.as_std().get_program().to_string_lossy().to_string();
+		let is_last = i + 1 == len;
+
+		if let Some(stdin) = stdin.take() {
+			cmd.stdin(stdin);
+		}
+		if !is_last {
+			cmd.stdout(Stdio::piped());
+		}
+
+		let mut child = cmd
+			.stderr(Stdio::piped())
+			// Don't leave the rest of the pipeline running if a later command fails to spawn
+			.kill_on_drop(true)
+			.spawn()
+			.with_context(|| format!("failed to spawn `{program}`"))?;
+
+		if !is_last {
+			stdin = Some(
+				child
+					.stdout
+					.take()
+					.context("missing stdout")?
+					.try_into()
+					.with_context(|| format!("failed to pipe `{program}` stdout"))?,
+			);
+		}
+
+		// NOTE: `cmd` is dropped here, closing the parent's copy of the pipe so commands receive EOF and
+		// SIGPIPE as they would in a shell
+		children.push((program, child));
+	}
+
+	let outputs = futures_util::future::try_join_all(children.into_iter().map(
+		|(program, child)| async move {
+			let output = child
+				.wait_with_output()
+				.await
+				.with_context(|| format!("failed to wait for `{program}`"))?;
+
+			Ok::<_, Error>((program, output))
+		},
+	))
+	.await?;
+
+	// A failing command usually makes its neighbors fail too, report all of them
+	let errors = outputs
+		.iter()
+		.filter(|(_, output)| !output.status.success())
+		.map(|(program, output)| {
+			format!(
+				"`{program}` exited with {}\n{}",
+				output.status,
+				String::from_utf8_lossy(&output.stderr).trim(),
+			)
+		})
+		.collect::<Vec<_>>();
+
+	ensure!(errors.is_empty(), "failed pipeline\n{}", errors.join("\n"));
+
+	Ok(())
+}
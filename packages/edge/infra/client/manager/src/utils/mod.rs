@@ -23,6 +23,7 @@ use tokio::{
 	sync::mpsc::{channel, Receiver},
 };
 
+pub mod archive;
 pub mod libc;
 pub mod sql;
 
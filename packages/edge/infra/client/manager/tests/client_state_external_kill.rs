@@ -121,7 +121,10 @@ async fn handle_connection(
 								tracing::info!(?event, "received event");
 
 								let protocol::Event::ActorStateUpdate { state, .. } =
-									event.inner.deserialize().unwrap();
+									event.inner.deserialize().unwrap()
+								else {
+									continue;
+								};
 
 								match state {
 									// Wait for actor to start running
@@ -130,6 +130,7 @@ pub async fn start_echo_actor(
 				network: None,
 			})
 			.unwrap(),
+			snapshot: None,
 		}),
 	};
 
@@ -199,6 +200,7 @@ pub async fn start_js_echo_actor(
 				network: None,
 			})
 			.unwrap(),
+			snapshot: None,
 		}),
 	};
 
@@ -300,6 +302,7 @@ pub async fn init_client(gen_path: &Path, working_path: &Path) -> Config {
 			vector: Some(Vector {
 				address: "127.0.0.1:5021".into(),
 			}),
+			labels: Default::default(),
 		},
 	};
 
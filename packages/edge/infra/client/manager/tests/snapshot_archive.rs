@@ -0,0 +1,111 @@
+// NOTE: Requires installing tar, lz4 and curl on the machine running this test
+
+use pegboard_manager::utils::archive;
+use tokio::fs;
+use url::Url;
+
+mod common;
+use common::*;
+
+/// Verifies a snapshot survives archiving and restoring, without the files generated on every start.
+#[tokio::test(flavor = "multi_thread")]
+async fn snapshot_archive_roundtrip() {
+	setup_tracing();
+
+	let tmp_dir = tempfile::TempDir::new().unwrap();
+	let src_path = tmp_dir.path().join("src");
+	let dst_path = tmp_dir.path().join("dst");
+	let snapshot_path = tmp_dir.path().join("snapshot.tar.lz4");
+
+	fs::create_dir_all(src_path.join("data")).await.unwrap();
+	fs::create_dir(&dst_path).await.unwrap();
+	fs::write(src_path.join("data").join("state"), "foo")
+		.await
+		.unwrap();
+	fs::write(src_path.join("config.json"), "{}").await.unwrap();
+
+	archive::create(&src_path, &snapshot_path).await.unwrap();
+	archive::extract_from_url(file_url(&snapshot_path).as_str(), &dst_path)
+		.await
+		.unwrap();
+
+	assert_eq!(
+		"foo",
+		fs::read_to_string(dst_path.join("data").join("state"))
+			.await
+			.unwrap()
+	);
+	assert!(fs::metadata(dst_path.join("config.json")).await.is_err());
+}
+
+/// The archive command must fail even though `lz4` succeeds on `tar`'s empty output.
+#[tokio::test(flavor = "multi_thread")]
+async fn snapshot_archive_missing_dir() {
+	setup_tracing();
+
+	let tmp_dir = tempfile::TempDir::new().unwrap();
+
+	let err = archive::create(
+		&tmp_dir.path().join("missing"),
+		&tmp_dir.path().join("snapshot.tar.lz4"),
+	)
+	.await
+	.unwrap_err();
+
+	assert!(format!("{err:?}").contains("`tar` exited"), "{err:?}");
+}
+
+/// A failed download must fail the restore instead of extracting an empty archive.
+#[tokio::test(flavor = "multi_thread")]
+async fn snapshot_restore_failed_download() {
+	setup_tracing();
+
+	let tmp_dir = tempfile::TempDir::new().unwrap();
+
+	let err = archive::extract_from_url(
+		file_url(&tmp_dir.path().join("missing.tar.lz4")).as_str(),
+		tmp_dir.path(),
+	)
+	.await
+	.unwrap_err();
+
+	assert!(format!("{err:?}").contains("`curl` exited"), "{err:?}");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn snapshot_restore_corrupt_archive() {
+	setup_tracing();
+
+	let tmp_dir = tempfile::TempDir::new().unwrap();
+	let snapshot_path = tmp_dir.path().join("snapshot.tar.lz4");
+	fs::write(&snapshot_path, "not an archive").await.unwrap();
+
+	let err = archive::extract_from_url(file_url(&snapshot_path).as_str(), tmp_dir.path())
+		.await
+		.unwrap_err();
+
+	assert!(format!("{err:?}").contains("`lz4` exited"), "{err:?}");
+}
+
+/// URLs are passed to `curl` as is, quotes in them must not break out of the command.
+#[tokio::test(flavor = "multi_thread")]
+async fn snapshot_restore_quoted_url() {
+	setup_tracing();
+
+	let tmp_dir = tempfile::TempDir::new().unwrap();
+	let marker_path = tmp_dir.path().join("marker");
+
+	let err = archive::extract_from_url(
+		&format!("file:///missing'; touch '{}", marker_path.display()),
+		tmp_dir.path(),
+	)
+	.await
+	.unwrap_err();
+
+	assert!(format!("{err:?}").contains("`curl` exited"), "{err:?}");
+	assert!(fs::metadata(&marker_path).await.is_err());
+}
+
+fn file_url(path: &std::path::Path) -> Url {
+	Url::from_file_path(path).unwrap()
+}
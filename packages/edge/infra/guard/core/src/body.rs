@@ -0,0 +1,161 @@
+use std::{
+	pin::Pin,
+	sync::{
+		atomic::{AtomicBool, AtomicU64, Ordering},
+		Arc, Mutex,
+	},
+	task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use http_body_util::{combinators::UnsyncBoxBody, BodyExt, Empty, Full};
+use hyper::body::{Body, Frame, SizeHint};
+
+/// Body of responses returned by guard. Proxied bodies are streamed through to the client instead of being
+/// buffered in memory.
+pub type ResponseBody = UnsyncBoxBody<Bytes, hyper::Error>;
+
+/// Creates a response body from a buffer.
+pub fn full(bytes: impl Into<Bytes>) -> ResponseBody {
+	Full::new(bytes.into())
+		.map_err(|never| match never {})
+		.boxed_unsync()
+}
+
+/// Creates an empty response body.
+pub fn empty() -> ResponseBody {
+	Empty::new().map_err(|never| match never {}).boxed_unsync()
+}
+
+/// Wraps a body and counts the bytes of all data frames polled from it. Backpressure is preserved since
+/// frames are only polled from the inner body when the wrapper is polled.
+pub struct CountingBody<B> {
+	inner: B,
+	bytes: Arc<AtomicU64>,
+	on_drop: Option<Box<dyn FnOnce(u64) + Send>>,
+}
+
+impl<B> CountingBody<B> {
+	pub fn new(inner: B) -> Self {
+		Self::with_counter(inner, Arc::new(AtomicU64::new(0)))
+	}
+
+	/// Counts bytes into an existing counter so that it can be read while the body is being streamed.
+	pub fn with_counter(inner: B, bytes: Arc<AtomicU64>) -> Self {
+		CountingBody {
+			inner,
+			bytes,
+			on_drop: None,
+		}
+	}
+
+	/// Sets a callback to run with the total byte count once the body is dropped (either because it was
+	/// fully sent or because the connection was closed).
+	pub fn on_drop(mut self, f: impl FnOnce(u64) + Send + 'static) -> Self {
+		self.on_drop = Some(Box::new(f));
+		self
+	}
+}
+
+impl<B> Body for CountingBody<B>
+where
+	B: Body<Data = Bytes> + Unpin,
+{
+	type Data = Bytes;
+	type Error = B::Error;
+
+	fn poll_frame(
+		mut self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+	) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+		let res = Pin::new(&mut self.inner).poll_frame(cx);
+
+		if let Poll::Ready(Some(Ok(frame))) = &res {
+			if let Some(data) = frame.data_ref() {
+				self.bytes.fetch_add(data.len() as u64, Ordering::Relaxed);
+			}
+		}
+
+		res
+	}
+
+	fn is_end_stream(&self) -> bool {
+		self.inner.is_end_stream()
+	}
+
+	fn size_hint(&self) -> SizeHint {
+		self.inner.size_hint()
+	}
+}
+
+impl<B> Drop for CountingBody<B> {
+	fn drop(&mut self) {
+		if let Some(f) = self.on_drop.take() {
+			f(self.bytes.load(Ordering::Relaxed));
+		}
+	}
+}
+
+/// A body that can be handed to multiple request attempts. The inner body is marked as consumed once any
+/// attempt polls a frame from it, so an attempt that failed before sending its body (i.e. a connection error)
+/// leaves the body intact for the next attempt.
+pub struct SharedBody<B> {
+	inner: Arc<Mutex<B>>,
+	consumed: Arc<AtomicBool>,
+}
+
+impl<B> SharedBody<B> {
+	pub fn new(inner: B) -> Self {
+		SharedBody {
+			inner: Arc::new(Mutex::new(inner)),
+			consumed: Arc::new(AtomicBool::new(false)),
+		}
+	}
+
+	/// Whether or not any attempt has started reading the body. Requests with consumed bodies cannot be
+	/// retried.
+	pub fn is_consumed(&self) -> bool {
+		self.consumed.load(Ordering::Acquire)
+	}
+}
+
+impl<B> Clone for SharedBody<B> {
+	fn clone(&self) -> Self {
+		SharedBody {
+			inner: self.inner.clone(),
+			consumed: self.consumed.clone(),
+		}
+	}
+}
+
+impl<B> Body for SharedBody<B>
+where
+	B: Body + Unpin,
+{
+	type Data = B::Data;
+	type Error = B::Error;
+
+	fn poll_frame(
+		self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+	) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+		self.consumed.store(true, Ordering::Release);
+
+		let mut inner = self.inner.lock().unwrap_or_else(|err| err.into_inner());
+		Pin::new(&mut *inner).poll_frame(cx)
+	}
+
+	fn is_end_stream(&self) -> bool {
+		self.inner
+			.lock()
+			.unwrap_or_else(|err| err.into_inner())
+			.is_end_stream()
+	}
+
+	fn size_hint(&self) -> SizeHint {
+		self.inner
+			.lock()
+			.unwrap_or_else(|err| err.into_inner())
+			.size_hint()
+	}
+}
@@ -0,0 +1,124 @@
+use std::{net::SocketAddr, sync::Arc};
+
+use global_error::*;
+use tokio::{
+	net::{TcpListener, UdpSocket},
+	sync::watch,
+	task::JoinHandle,
+};
+use tokio_rustls::TlsAcceptor;
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::proxy_service::{ActorActivityReporter, RouteTarget, ACTOR_ACTIVITY_INTERVAL};
+
+mod sni;
+mod tcp;
+mod udp;
+
+pub use tcp::run_tcp_listener;
+pub use udp::run_udp_listener;
+
+/// Transport protocol of an L4 connection
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum L4Protocol {
+	Tcp,
+	Udp,
+}
+
+impl L4Protocol {
+	fn as_str(&self) -> &'static str {
+		match self {
+			L4Protocol::Tcp => "tcp",
+			L4Protocol::Udp => "udp",
+		}
+	}
+}
+
+/// Where to proxy an L4 connection to
+#[derive(Clone, Debug)]
+pub struct L4Target {
+	pub target: RouteTarget,
+	/// Terminate TLS in guard and forward the decrypted stream to the target. Only applies to TCP.
+	pub terminate_tls: bool,
+}
+
+/// Resolves the target of an L4 connection from its protocol, the port it came in on and the SNI of the
+/// TLS client hello (only for TCP connections that start with one).
+pub type L4RoutingFn = Arc<
+	dyn for<'a> Fn(
+			L4Protocol,
+			u16,
+			Option<&'a str>,
+		) -> futures::future::BoxFuture<'a, GlobalResult<Option<L4Target>>>
+		+ Send
+		+ Sync,
+>;
+
+/// Binds a listener on every configured TCP and UDP port and starts proxying them. The listeners stop and
+/// drain their connections once `shutdown` is set.
+#[tracing::instrument(skip_all)]
+pub(crate) async fn start_listeners(
+	guard_config: &rivet_config::config::guard::Guard,
+	routing_fn: L4RoutingFn,
+	actor_activity: Option<ActorActivityReporter>,
+	tls_acceptor: Option<TlsAcceptor>,
+	shutdown: watch::Receiver<bool>,
+) -> GlobalResult<Vec<JoinHandle<()>>> {
+	let mut handles = Vec::new();
+
+	if let Some(tcp) = &guard_config.tcp {
+		for port in tcp.ports() {
+			let addr: SocketAddr = ([0, 0, 0, 0], port).into();
+			let listener = TcpListener::bind(addr).await?;
+
+			handles.push(tokio::spawn(
+				run_tcp_listener(
+					listener,
+					routing_fn.clone(),
+					actor_activity.clone(),
+					tls_acceptor.clone(),
+					shutdown.clone(),
+				)
+				.instrument(tracing::info_span!(parent: None, "tcp_listener", ?port)),
+			));
+		}
+
+		tracing::info!(min=?tcp.min, max=?tcp.max, "TCP listeners started");
+	}
+
+	if let Some(udp) = &guard_config.udp {
+		for port in udp.ports() {
+			let addr: SocketAddr = ([0, 0, 0, 0], port).into();
+			let socket = UdpSocket::bind(addr).await?;
+
+			handles.push(tokio::spawn(
+				run_udp_listener(
+					socket,
+					routing_fn.clone(),
+					actor_activity.clone(),
+					shutdown.clone(),
+				)
+				.instrument(tracing::info_span!(parent: None, "udp_listener", ?port)),
+			));
+		}
+
+		tracing::info!(min=?udp.min, max=?udp.max, "UDP listeners started");
+	}
+
+	Ok(handles)
+}
+
+/// Reports activity for the actor every `ACTOR_ACTIVITY_INTERVAL` so that it is not stopped for being idle
+/// while a connection to it is open. Never completes, meant to be raced against the connection.
+async fn report_activity(actor_activity: Option<ActorActivityReporter>, actor_id: Option<Uuid>) {
+	let (Some(actor_activity), Some(actor_id)) = (actor_activity, actor_id) else {
+		return std::future::pending().await;
+	};
+
+	loop {
+		actor_activity.report(actor_id).await;
+
+		tokio::time::sleep(ACTOR_ACTIVITY_INTERVAL).await;
+	}
+}
@@ -0,0 +1,124 @@
+const CONTENT_TYPE_HANDSHAKE: u8 = 0x16;
+const HANDSHAKE_TYPE_CLIENT_HELLO: u8 = 0x01;
+const EXTENSION_SERVER_NAME: u16 = 0x0000;
+const SERVER_NAME_TYPE_HOST_NAME: u8 = 0x00;
+
+/// Result of parsing the start of a TCP stream as a TLS client hello.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum ClientHello {
+	/// The stream does not start with a TLS handshake.
+	NotTls,
+	/// More data is needed to parse the client hello.
+	Incomplete,
+	/// The stream starts with a TLS handshake. Contains the SNI host name, if any.
+	Tls(Option<String>),
+}
+
+/// Parses the SNI host name out of the first TLS record of a stream without consuming it. Only the first
+/// record is read, client hellos fragmented over multiple records are treated as having no SNI.
+pub(crate) fn parse_client_hello(buf: &[u8]) -> ClientHello {
+	let Some(content_type) = buf.first() else {
+		return ClientHello::Incomplete;
+	};
+	if *content_type != CONTENT_TYPE_HANDSHAKE {
+		return ClientHello::NotTls;
+	}
+
+	let Some(header) = buf.get(..5) else {
+		return ClientHello::Incomplete;
+	};
+	// Major version is always 3 (SSL 3.0 through TLS 1.3)
+	if header[1] != 0x03 {
+		return ClientHello::NotTls;
+	}
+
+	let record_len = u16::from_be_bytes([header[3], header[4]]) as usize;
+	let Some(record) = buf.get(5..5 + record_len) else {
+		return ClientHello::Incomplete;
+	};
+
+	ClientHello::Tls(parse_sni(record))
+}
+
+fn parse_sni(record: &[u8]) -> Option<String> {
+	let mut r = Reader(record);
+
+	if r.u8()? != HANDSHAKE_TYPE_CLIENT_HELLO {
+		return None;
+	}
+	let len = r.u24()?;
+	let mut r = Reader(r.take(len)?);
+
+	// Legacy version and random
+	r.take(2 + 32)?;
+	// Session ID
+	let len = r.u8()? as usize;
+	r.take(len)?;
+	// Cipher suites
+	let len = r.u16()? as usize;
+	r.take(len)?;
+	// Compression methods
+	let len = r.u8()? as usize;
+	r.take(len)?;
+
+	let len = r.u16()? as usize;
+	let mut extensions = Reader(r.take(len)?);
+
+	while !extensions.0.is_empty() {
+		let ty = extensions.u16()?;
+		let len = extensions.u16()? as usize;
+		let data = extensions.take(len)?;
+
+		if ty != EXTENSION_SERVER_NAME {
+			continue;
+		}
+
+		let mut r = Reader(data);
+		let len = r.u16()? as usize;
+		let mut names = Reader(r.take(len)?);
+
+		while !names.0.is_empty() {
+			let ty = names.u8()?;
+			let len = names.u16()? as usize;
+			let name = names.take(len)?;
+
+			if ty == SERVER_NAME_TYPE_HOST_NAME {
+				return std::str::from_utf8(name)
+					.ok()
+					.map(|name| name.to_ascii_lowercase());
+			}
+		}
+
+		return None;
+	}
+
+	None
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+	fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+		if self.0.len() < len {
+			return None;
+		}
+
+		let (head, tail) = self.0.split_at(len);
+		self.0 = tail;
+
+		Some(head)
+	}
+
+	fn u8(&mut self) -> Option<u8> {
+		self.take(1).map(|b| b[0])
+	}
+
+	fn u16(&mut self) -> Option<u16> {
+		self.take(2).map(|b| u16::from_be_bytes([b[0], b[1]]))
+	}
+
+	fn u24(&mut self) -> Option<usize> {
+		self.take(3)
+			.map(|b| u32::from_be_bytes([0, b[0], b[1], b[2]]) as usize)
+	}
+}
@@ -0,0 +1,179 @@
+use std::time::Duration;
+
+use global_error::*;
+use tokio::{
+	net::{TcpListener, TcpStream},
+	sync::watch,
+	task::JoinSet,
+};
+use tokio_rustls::TlsAcceptor;
+use tracing::Instrument;
+
+use super::{
+	report_activity,
+	sni::{parse_client_hello, ClientHello},
+	L4Protocol, L4RoutingFn,
+};
+use crate::{metrics, proxy_service::ActorActivityReporter};
+
+/// How long to wait for the client to send a TLS client hello before routing the connection as plain TCP.
+/// Protocols where the server speaks first are delayed by this much.
+const CLIENT_HELLO_TIMEOUT: Duration = Duration::from_millis(500);
+/// Max size of a TLS record (plus header).
+const MAX_CLIENT_HELLO_SIZE: usize = 16 * 1024 + 5;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Accepts connections on a TCP listener and proxies them to the targets resolved by the routing function.
+/// Once `shutdown` is set, stops accepting connections and returns after the open ones close. Open
+/// connections are closed if the task is aborted.
+pub async fn run_tcp_listener(
+	listener: TcpListener,
+	routing_fn: L4RoutingFn,
+	actor_activity: Option<ActorActivityReporter>,
+	tls_acceptor: Option<TlsAcceptor>,
+	mut shutdown: watch::Receiver<bool>,
+) {
+	let port = match listener.local_addr() {
+		Ok(addr) => addr.port(),
+		Err(err) => {
+			tracing::error!(?err, "failed to get TCP listener address");
+			return;
+		}
+	};
+	let protocol = L4Protocol::Tcp.as_str();
+	let mut connections = JoinSet::new();
+
+	loop {
+		tokio::select! {
+			res = listener.accept() => match res {
+				Ok((stream, remote_addr)) => {
+					let routing_fn = routing_fn.clone();
+					let actor_activity = actor_activity.clone();
+					let tls_acceptor = tls_acceptor.clone();
+
+					connections.spawn(
+						async move {
+							metrics::L4_CONNECTION_TOTAL
+								.with_label_values(&[protocol])
+								.inc();
+							metrics::L4_CONNECTION_PENDING
+								.with_label_values(&[protocol])
+								.inc();
+
+							if let Err(err) = handle_connection(
+								stream,
+								port,
+								routing_fn,
+								actor_activity,
+								tls_acceptor,
+							)
+							.await
+							{
+								tracing::debug!(?err, "TCP connection error");
+								metrics::L4_CONNECTION_ERROR
+									.with_label_values(&[protocol])
+									.inc();
+							}
+
+							metrics::L4_CONNECTION_PENDING
+								.with_label_values(&[protocol])
+								.dec();
+						}
+						.instrument(tracing::info_span!("tcp_connection", ?remote_addr)),
+					);
+				}
+				Err(err) => {
+					tracing::debug!(?err, "accept error on TCP port");
+					metrics::L4_LISTENER_ERROR
+						.with_label_values(&[protocol])
+						.inc();
+					tokio::time::sleep(Duration::from_secs(1)).await;
+				}
+			},
+			// Clean up finished connections
+			Some(_) = connections.join_next(), if !connections.is_empty() => {}
+			_ = shutdown.wait_for(|shutdown| *shutdown) => break,
+		}
+	}
+
+	drop(listener);
+
+	tracing::debug!(count=?connections.len(), "draining TCP connections");
+	while connections.join_next().await.is_some() {}
+}
+
+async fn handle_connection(
+	mut stream: TcpStream,
+	port: u16,
+	routing_fn: L4RoutingFn,
+	actor_activity: Option<ActorActivityReporter>,
+	tls_acceptor: Option<TlsAcceptor>,
+) -> GlobalResult<()> {
+	let sni = peek_sni(&stream).await?;
+
+	let Some(target) = routing_fn(L4Protocol::Tcp, port, sni.as_deref()).await? else {
+		tracing::debug!(?port, ?sni, "no route found for TCP connection");
+		return Ok(());
+	};
+
+	let addr = format!("{}:{}", target.target.host, target.target.port);
+	let mut upstream = tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect(&addr)).await??;
+
+	stream.set_nodelay(true)?;
+	upstream.set_nodelay(true)?;
+
+	tracing::debug!(%addr, terminate_tls=?target.terminate_tls, "proxying TCP connection");
+
+	let report_activity = report_activity(actor_activity, target.target.actor_id);
+
+	if target.terminate_tls {
+		let Some(tls_acceptor) = tls_acceptor else {
+			bail!("cannot terminate TLS, no certificate resolver configured");
+		};
+
+		let mut stream =
+			tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, tls_acceptor.accept(stream)).await??;
+		tokio::select! {
+			res = tokio::io::copy_bidirectional(&mut stream, &mut upstream) => {
+				res?;
+			}
+			_ = report_activity => {}
+		}
+	} else {
+		tokio::select! {
+			res = tokio::io::copy_bidirectional(&mut stream, &mut upstream) => {
+				res?;
+			}
+			_ = report_activity => {}
+		}
+	}
+
+	Ok(())
+}
+
+/// Reads the SNI of the TLS client hello at the start of the stream without consuming any data. Returns
+/// `None` if the stream does not start with a client hello.
+async fn peek_sni(stream: &TcpStream) -> GlobalResult<Option<String>> {
+	let mut buf = vec![0u8; MAX_CLIENT_HELLO_SIZE];
+	let deadline = tokio::time::Instant::now() + CLIENT_HELLO_TIMEOUT;
+
+	loop {
+		let n = match tokio::time::timeout_at(deadline, stream.peek(&mut buf)).await {
+			Ok(res) => res?,
+			// Client did not send anything
+			Err(_) => return Ok(None),
+		};
+
+		match parse_client_hello(&buf[..n]) {
+			ClientHello::Tls(sni) => return Ok(sni),
+			ClientHello::NotTls => return Ok(None),
+			ClientHello::Incomplete if n == 0 || n == buf.len() => return Ok(None),
+			ClientHello::Incomplete => {
+				// Peeking returns immediately while there is any data in the socket buffer, give the rest of
+				// the client hello time to arrive before peeking again
+				tokio::time::sleep(Duration::from_millis(5)).await;
+			}
+		}
+	}
+}
@@ -0,0 +1,254 @@
+use std::{
+	cell::RefCell,
+	collections::HashMap,
+	io,
+	net::{Ipv4Addr, Ipv6Addr, SocketAddr},
+	sync::Arc,
+	time::Duration,
+};
+
+use bytes::Bytes;
+use global_error::*;
+use tokio::{
+	net::UdpSocket,
+	sync::{mpsc, watch, Mutex},
+	task::JoinSet,
+};
+use tracing::Instrument;
+
+use super::{report_activity, L4Protocol, L4RoutingFn};
+use crate::{metrics, proxy_service::ActorActivityReporter};
+
+/// Sessions are closed after not sending or receiving any datagrams for this long.
+const SESSION_IDLE_TIMEOUT: Duration = Duration::from_secs(15);
+/// How many datagrams are buffered per session while it is being routed or while the upstream is slow.
+/// Datagrams are dropped when the buffer is full.
+const SESSION_BUFFER_SIZE: usize = 128;
+const MAX_DATAGRAM_SIZE: usize = 65_535;
+/// How long to wait before receiving again after the socket returns an error, so a persistent error does
+/// not spin the listener.
+const RECV_ERROR_BACKOFF: Duration = Duration::from_millis(100);
+
+type Sessions = Arc<Mutex<HashMap<SocketAddr, mpsc::Sender<Bytes>>>>;
+
+thread_local! {
+	/// Receive buffer shared by every UDP socket polled on this thread. Datagrams are copied out of it
+	/// right away, so idle ports and sessions don't each hold a max size buffer.
+	static RECV_BUF: RefCell<Box<[u8]>> = RefCell::new(vec![0u8; MAX_DATAGRAM_SIZE].into_boxed_slice());
+}
+
+/// Receives a datagram from a socket that was reported readable. Returns `None` if the readiness was
+/// spurious.
+fn try_recv_from(socket: &UdpSocket) -> io::Result<Option<(Bytes, SocketAddr)>> {
+	RECV_BUF.with(|buf| {
+		let mut buf = buf.borrow_mut();
+
+		match socket.try_recv_from(&mut buf) {
+			Ok((n, addr)) => Ok(Some((Bytes::copy_from_slice(&buf[..n]), addr))),
+			Err(err) if err.kind() == io::ErrorKind::WouldBlock => Ok(None),
+			Err(err) => Err(err),
+		}
+	})
+}
+
+/// Receives datagrams on a UDP socket and proxies them to the targets resolved by the routing function.
+/// Each client address gets its own session with a dedicated upstream socket, so all datagrams from a
+/// client go to the same target and responses are sent back from the port the client sent to. Once
+/// `shutdown` is set, stops opening new sessions and returns after the open ones close. Open sessions
+/// are closed if the task is aborted.
+pub async fn run_udp_listener(
+	socket: UdpSocket,
+	routing_fn: L4RoutingFn,
+	actor_activity: Option<ActorActivityReporter>,
+	mut shutdown: watch::Receiver<bool>,
+) {
+	let port = match socket.local_addr() {
+		Ok(addr) => addr.port(),
+		Err(err) => {
+			tracing::error!(?err, "failed to get UDP socket address");
+			return;
+		}
+	};
+	let socket = Arc::new(socket);
+	let sessions = Sessions::default();
+	let mut session_tasks = JoinSet::new();
+	let mut draining = false;
+
+	loop {
+		let (datagram, client_addr) = tokio::select! {
+			res = socket.readable() => match res.and_then(|_| try_recv_from(&socket)) {
+				Ok(Some(res)) => res,
+				Ok(None) => continue,
+				Err(err) => {
+					tracing::debug!(?err, "receive error on UDP port");
+					metrics::L4_LISTENER_ERROR
+						.with_label_values(&[L4Protocol::Udp.as_str()])
+						.inc();
+					tokio::time::sleep(RECV_ERROR_BACKOFF).await;
+					continue;
+				}
+			},
+			// Clean up finished sessions
+			Some(_) = session_tasks.join_next(), if !session_tasks.is_empty() => {
+				if draining && session_tasks.is_empty() {
+					break;
+				}
+
+				continue;
+			}
+			_ = shutdown.wait_for(|shutdown| *shutdown), if !draining => {
+				tracing::debug!(count=?session_tasks.len(), "draining UDP sessions");
+				draining = true;
+
+				if session_tasks.is_empty() {
+					break;
+				}
+
+				continue;
+			}
+		};
+
+		let tx = {
+			let mut sessions_guard = sessions.lock().await;
+
+			match sessions_guard.get(&client_addr) {
+				Some(tx) if !tx.is_closed() => tx.clone(),
+				_ if draining => {
+					tracing::debug!(?client_addr, "UDP listener draining, dropping datagram");
+					continue;
+				}
+				_ => {
+					let (tx, rx) = mpsc::channel(SESSION_BUFFER_SIZE);
+					sessions_guard.insert(client_addr, tx.clone());
+
+					session_tasks.spawn(
+						run_session(
+							socket.clone(),
+							sessions.clone(),
+							client_addr,
+							port,
+							routing_fn.clone(),
+							actor_activity.clone(),
+							rx,
+						)
+						.instrument(tracing::info_span!("udp_session", ?client_addr)),
+					);
+
+					tx
+				}
+			}
+		};
+
+		if let Err(mpsc::error::TrySendError::Full(_)) = tx.try_send(datagram) {
+			tracing::debug!(?client_addr, "UDP session buffer full, dropping datagram");
+		}
+	}
+}
+
+async fn run_session(
+	socket: Arc<UdpSocket>,
+	sessions: Sessions,
+	client_addr: SocketAddr,
+	port: u16,
+	routing_fn: L4RoutingFn,
+	actor_activity: Option<ActorActivityReporter>,
+	mut rx: mpsc::Receiver<Bytes>,
+) {
+	let protocol = L4Protocol::Udp.as_str();
+	metrics::L4_CONNECTION_TOTAL
+		.with_label_values(&[protocol])
+		.inc();
+	metrics::L4_CONNECTION_PENDING
+		.with_label_values(&[protocol])
+		.inc();
+
+	if let Err(err) = proxy_session(
+		&socket,
+		client_addr,
+		port,
+		routing_fn,
+		actor_activity,
+		&mut rx,
+	)
+	.await
+	{
+		tracing::debug!(?err, "UDP session error");
+		metrics::L4_CONNECTION_ERROR
+			.with_label_values(&[protocol])
+			.inc();
+	}
+
+	metrics::L4_CONNECTION_PENDING
+		.with_label_values(&[protocol])
+		.dec();
+
+	// Remove the session unless it has already been replaced by a new one
+	let mut sessions_guard = sessions.lock().await;
+	drop(rx);
+
+	if sessions_guard
+		.get(&client_addr)
+		.map_or(false, |tx| tx.is_closed())
+	{
+		sessions_guard.remove(&client_addr);
+	}
+}
+
+async fn proxy_session(
+	socket: &UdpSocket,
+	client_addr: SocketAddr,
+	port: u16,
+	routing_fn: L4RoutingFn,
+	actor_activity: Option<ActorActivityReporter>,
+	rx: &mut mpsc::Receiver<Bytes>,
+) -> GlobalResult<()> {
+	let Some(target) = routing_fn(L4Protocol::Udp, port, None).await? else {
+		tracing::debug!(?port, "no route found for UDP session");
+		return Ok(());
+	};
+
+	let addr = unwrap!(
+		tokio::net::lookup_host((target.target.host.as_str(), target.target.port))
+			.await?
+			.next(),
+		"failed to resolve UDP target"
+	);
+	let bind_addr: SocketAddr = if addr.is_ipv4() {
+		(Ipv4Addr::UNSPECIFIED, 0).into()
+	} else {
+		(Ipv6Addr::UNSPECIFIED, 0).into()
+	};
+	let upstream = UdpSocket::bind(bind_addr).await?;
+	upstream.connect(addr).await?;
+
+	tracing::debug!(%addr, "proxying UDP session");
+
+	let report_activity = report_activity(actor_activity, target.target.actor_id);
+	tokio::pin!(report_activity);
+
+	loop {
+		tokio::select! {
+			datagram = rx.recv() => {
+				let Some(datagram) = datagram else {
+					break;
+				};
+
+				upstream.send(&datagram).await?;
+			}
+			res = upstream.readable() => {
+				res?;
+
+				if let Some((datagram, _)) = try_recv_from(&upstream)? {
+					socket.send_to(&datagram, client_addr).await?;
+				}
+			}
+			_ = tokio::time::sleep(SESSION_IDLE_TIMEOUT) => {
+				tracing::debug!("UDP session idle, closing");
+				break;
+			}
+			_ = &mut report_activity => {}
+		}
+	}
+
+	Ok(())
+}
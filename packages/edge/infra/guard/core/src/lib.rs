@@ -1,5 +1,8 @@
 pub mod analytics;
+pub mod body;
 pub mod cert_resolver;
+pub mod l4;
+pub mod load_balancer;
 pub mod metrics;
 pub mod proxy_service;
 pub mod request_context;
@@ -8,11 +11,21 @@ pub mod types;
 pub mod util;
 
 pub use cert_resolver::CertResolverFn;
-pub use proxy_service::{MiddlewareFn, ProxyService, ProxyState, RouteTarget, RoutingFn};
+pub use l4::{L4Protocol, L4RoutingFn, L4Target};
+pub use load_balancer::{HashKey, LoadBalancingStrategy};
+pub use proxy_service::{
+	ActorActivityFn, ActorActivityReporter, DistributedRateLimitFn, MiddlewareFn, ProxyService,
+	ProxyState, RouteTarget, RoutingFn,
+};
 
 // Re-export hyper StatusCode for use in other crates
 pub mod status {
 	pub use hyper::StatusCode;
 }
+
+// Re-export hyper request types passed to the routing function
+pub mod request {
+	pub use hyper::{HeaderMap, Method};
+}
 pub use server::{run_server, GlobalErrorWrapper};
 pub use types::{EndpointType, GameGuardProtocol};
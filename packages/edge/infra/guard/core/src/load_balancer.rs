@@ -0,0 +1,303 @@
+use std::{
+	borrow::Cow,
+	hash::Hasher,
+	sync::{
+		atomic::{AtomicU32, AtomicUsize, Ordering},
+		Arc,
+	},
+	time::Duration,
+};
+
+use hyper::HeaderMap;
+use moka::future::Cache;
+use tokio::sync::Mutex;
+use twox_hash::XxHash64;
+
+use crate::metrics;
+use crate::proxy_service::{InFlightCounter, RouteConfig, RouteTarget};
+
+/// How many connect failures in a row eject a target.
+const OUTLIER_CONSECUTIVE_FAILURES: u32 = 3;
+/// How long an ejected target is skipped for.
+const OUTLIER_EJECTION_DURATION: Duration = Duration::from_secs(30);
+/// Connect failure counts are forgotten after this long without another failure.
+const OUTLIER_FAILURE_TTL: Duration = Duration::from_secs(60);
+const LOAD_BALANCER_STATE_TTL: Duration = Duration::from_secs(60 * 60); // 1 hour
+
+/// How requests are distributed between the targets of a route
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum LoadBalancingStrategy {
+	/// Pick a random target for every request
+	#[default]
+	Random,
+	/// Cycle through targets in order
+	RoundRobin,
+	/// Pick the target with the fewest requests in flight through this guard
+	LeastInFlight,
+	/// Pick a target by hashing a value from the request so that requests with the same value go to
+	/// the same target. Falls back to random if the request does not have the value.
+	ConsistentHash(HashKey),
+}
+
+/// Request value to hash for consistent hashing
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HashKey {
+	Header(String),
+	Cookie(String),
+	Query(String),
+}
+
+impl HashKey {
+	/// Reads the value to hash from the request. `path` includes the query string.
+	fn extract<'a>(&self, headers: &'a HeaderMap, path: &'a str) -> Option<Cow<'a, str>> {
+		match self {
+			HashKey::Header(name) => headers
+				.get(name.as_str())
+				.and_then(|value| value.to_str().ok())
+				.map(Cow::Borrowed),
+			HashKey::Cookie(name) => headers
+				.get_all(hyper::header::COOKIE)
+				.iter()
+				.filter_map(|value| value.to_str().ok())
+				.flat_map(|value| value.split(';'))
+				.filter_map(|cookie| cookie.trim().split_once('='))
+				.find(|(key, _)| key == name)
+				.map(|(_, value)| Cow::Borrowed(value)),
+			HashKey::Query(name) => path
+				.split_once('?')
+				.into_iter()
+				.flat_map(|(_, query)| query.split('&'))
+				.filter_map(|param| param.split_once('='))
+				.find(|(key, _)| key == name)
+				.map(|(_, value)| Cow::Borrowed(value)),
+		}
+	}
+}
+
+type TargetKey = (String, u16);
+
+fn target_key(target: &RouteTarget) -> TargetKey {
+	(target.host.clone(), target.port)
+}
+
+/// Picks targets for requests according to the route's load balancing strategy. Targets that keep failing
+/// to connect are passively ejected for a while, regardless of the strategy.
+pub struct LoadBalancer {
+	/// Round robin position per hostname.
+	round_robin: Cache<String, Arc<AtomicUsize>>,
+	/// Requests in flight per target.
+	in_flight: Cache<TargetKey, Arc<Mutex<InFlightCounter>>>,
+	/// Connect failures in a row per target.
+	connect_failures: Cache<TargetKey, Arc<AtomicU32>>,
+	/// Targets that are currently ejected. Entries expire when the ejection ends.
+	ejected: Cache<TargetKey, ()>,
+}
+
+impl LoadBalancer {
+	pub fn new() -> Self {
+		Self {
+			round_robin: Cache::builder()
+				.max_capacity(10_000)
+				.time_to_live(LOAD_BALANCER_STATE_TTL)
+				.build(),
+			in_flight: Cache::builder()
+				.max_capacity(10_000)
+				.time_to_live(LOAD_BALANCER_STATE_TTL)
+				.build(),
+			connect_failures: Cache::builder()
+				.max_capacity(10_000)
+				.time_to_live(OUTLIER_FAILURE_TTL)
+				.build(),
+			ejected: Cache::builder()
+				.max_capacity(10_000)
+				.time_to_live(OUTLIER_EJECTION_DURATION)
+				.build(),
+		}
+	}
+
+	/// Chooses a target for a request to `hostname`. `path` includes the query string.
+	#[tracing::instrument(skip_all)]
+	pub async fn choose_target<'a>(
+		&self,
+		hostname: &str,
+		path: &str,
+		headers: &HeaderMap,
+		route: &'a RouteConfig,
+	) -> Option<&'a RouteTarget> {
+		let hash_value = match &route.load_balancing {
+			LoadBalancingStrategy::ConsistentHash(key) => key.extract(headers, path),
+			_ => None,
+		};
+
+		let route_targets = choose_split_group(route, hash_value.as_deref());
+		if route_targets.is_empty() {
+			return None;
+		}
+
+		// Skip ejected targets. If every target is ejected, use all of them instead of failing the request.
+		let mut targets = Vec::with_capacity(route_targets.len());
+		for target in route_targets {
+			if !self.ejected.contains_key(&target_key(target)) {
+				targets.push(target);
+			}
+		}
+		if targets.is_empty() {
+			tracing::debug!("all targets ejected, ignoring ejections");
+			targets = route_targets.iter().collect();
+		}
+
+		match &route.load_balancing {
+			LoadBalancingStrategy::Random => choose_random_target(&targets),
+			LoadBalancingStrategy::RoundRobin => {
+				let counter = self
+					.round_robin
+					.get_with(hostname.to_owned(), async { Arc::new(AtomicUsize::new(0)) })
+					.await;
+				let idx = counter.fetch_add(1, Ordering::Relaxed) % targets.len();
+
+				targets.get(idx).copied()
+			}
+			LoadBalancingStrategy::LeastInFlight => {
+				// Start at a random offset so ties don't always go to the first target
+				let offset = rand::random::<usize>() % targets.len();
+
+				let mut least = None;
+				for i in 0..targets.len() {
+					let target = targets[(offset + i) % targets.len()];
+					let count = match self.in_flight.get(&target_key(target)).await {
+						Some(counter) => counter.lock().await.count(),
+						None => 0,
+					};
+
+					if least.map_or(true, |(_, least_count)| count < least_count) {
+						least = Some((target, count));
+					}
+				}
+
+				least.map(|(target, _)| target)
+			}
+			LoadBalancingStrategy::ConsistentHash(_) => match &hash_value {
+				Some(value) => choose_hashed_target(&targets, value),
+				None => choose_random_target(&targets),
+			},
+		}
+	}
+
+	/// Counts a request to the target as in flight until `release` is called.
+	#[tracing::instrument(skip_all)]
+	pub async fn acquire(&self, target: &RouteTarget) {
+		let counter = self
+			.in_flight
+			.get_with(target_key(target), async {
+				Arc::new(Mutex::new(InFlightCounter::new(usize::MAX)))
+			})
+			.await;
+
+		counter.lock().await.try_acquire();
+	}
+
+	#[tracing::instrument(skip_all)]
+	pub async fn release(&self, target: &RouteTarget) {
+		if let Some(counter) = self.in_flight.get(&target_key(target)).await {
+			counter.lock().await.release();
+		}
+	}
+
+	/// Records a failed connection to the target. Ejects the target after too many failures in a row.
+	#[tracing::instrument(skip_all)]
+	pub async fn record_connect_failure(&self, target: &RouteTarget) {
+		let key = target_key(target);
+		let failures = self
+			.connect_failures
+			.get_with(key.clone(), async { Arc::new(AtomicU32::new(0)) })
+			.await;
+
+		if failures.fetch_add(1, Ordering::Relaxed) + 1 >= OUTLIER_CONSECUTIVE_FAILURES {
+			tracing::debug!(host=%target.host, port=%target.port, "ejecting target");
+
+			self.connect_failures.invalidate(&key).await;
+			self.ejected.insert(key, ()).await;
+
+			metrics::LOAD_BALANCER_EJECTION_TOTAL.inc();
+			metrics::LOAD_BALANCER_EJECTED_COUNT.set(self.ejected.entry_count() as i64);
+		}
+	}
+
+	/// Records a successful connection to the target, resetting its failure count.
+	#[tracing::instrument(skip_all)]
+	pub async fn record_connect_success(&self, target: &RouteTarget) {
+		let key = target_key(target);
+
+		self.connect_failures.invalidate(&key).await;
+
+		if self.ejected.contains_key(&key) {
+			self.ejected.invalidate(&key).await;
+			metrics::LOAD_BALANCER_EJECTED_COUNT.set(self.ejected.entry_count() as i64);
+		}
+	}
+}
+
+impl Default for LoadBalancer {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// Picks the group of targets for a request to a route with a traffic split. With consistent hashing the
+/// group is picked from the hashed value so that requests with the same value stay in the same group.
+fn choose_split_group<'a>(route: &'a RouteConfig, hash_value: Option<&str>) -> &'a [RouteTarget] {
+	let Some(split) = &route.split else {
+		return &route.targets;
+	};
+
+	// Fall back to whichever group has targets
+	if split.targets.is_empty() {
+		return &route.targets;
+	}
+	if route.targets.is_empty() {
+		return &split.targets;
+	}
+
+	let percent = match hash_value {
+		Some(value) => {
+			let mut hasher = XxHash64::default();
+			hasher.write(value.as_bytes());
+			hasher.finish() % 100
+		}
+		None => rand::random::<u64>() % 100,
+	};
+
+	if percent < split.weight as u64 {
+		&split.targets
+	} else {
+		&route.targets
+	}
+}
+
+fn choose_random_target<'a>(targets: &[&'a RouteTarget]) -> Option<&'a RouteTarget> {
+	if targets.is_empty() {
+		return None;
+	}
+
+	targets
+		.get(rand::random::<usize>() % targets.len())
+		.copied()
+}
+
+/// Rendezvous hashing: every target is scored by hashing it together with the value and the highest score
+/// wins. Adding or removing a target only moves the values that hash to that target.
+///
+/// Uses xxHash rather than `DefaultHasher`, whose algorithm may change between Rust versions, so that every
+/// guard instance picks the same target for the same value.
+fn choose_hashed_target<'a>(targets: &[&'a RouteTarget], value: &str) -> Option<&'a RouteTarget> {
+	targets.iter().copied().max_by_key(|target| {
+		let mut hasher = XxHash64::default();
+		// Lengths prevent ambiguity between where one field ends and the next starts
+		hasher.write_u64(value.len() as u64);
+		hasher.write(value.as_bytes());
+		hasher.write_u64(target.host.len() as u64);
+		hasher.write(target.host.as_bytes());
+		hasher.write_u16(target.port);
+		hasher.finish()
+	})
+}
@@ -41,6 +41,36 @@ lazy_static! {
 	)
 	.unwrap();
 
+	// MARK: L4
+	pub static ref L4_CONNECTION_TOTAL: IntCounterVec = register_int_counter_vec_with_registry!(
+		"guard_l4_connection_total",
+		"Total number of proxied L4 connections (or UDP sessions) ever",
+		&["protocol"],
+		*REGISTRY,
+	)
+	.unwrap();
+	pub static ref L4_CONNECTION_PENDING: IntGaugeVec = register_int_gauge_vec_with_registry!(
+		"guard_l4_connection_pending",
+		"Number of open proxied L4 connections (or UDP sessions)",
+		&["protocol"],
+		*REGISTRY,
+	)
+	.unwrap();
+	pub static ref L4_CONNECTION_ERROR: IntCounterVec = register_int_counter_vec_with_registry!(
+		"guard_l4_connection_errors_total",
+		"Total number of errors when proxying L4 connections to actor",
+		&["protocol"],
+		*REGISTRY,
+	)
+	.unwrap();
+	pub static ref L4_LISTENER_ERROR: IntCounterVec = register_int_counter_vec_with_registry!(
+		"guard_l4_listener_errors_total",
+		"Total number of errors when accepting L4 connections or receiving UDP datagrams",
+		&["protocol"],
+		*REGISTRY,
+	)
+	.unwrap();
+
 	// MARK: Pre-proxy
 	pub static ref RESOLVE_ROUTE_DURATION: Histogram = register_histogram_with_registry!(
 		"guard_resolve_route_duration",
@@ -50,6 +80,20 @@ lazy_static! {
 	)
 	.unwrap();
 
+	// MARK: Load balancing
+	pub static ref LOAD_BALANCER_EJECTION_TOTAL: IntCounter = register_int_counter_with_registry!(
+		"guard_load_balancer_ejection_total",
+		"Total number of targets ejected for failing to connect",
+		*REGISTRY,
+	)
+	.unwrap();
+	pub static ref LOAD_BALANCER_EJECTED_COUNT: IntGauge = register_int_gauge_with_registry!(
+		"guard_load_balancer_ejected_count",
+		"Number of currently ejected targets",
+		*REGISTRY,
+	)
+	.unwrap();
+
 	// MARK: Proxy requests
 	pub static ref PROXY_REQUEST_TOTAL: IntCounter = register_int_counter_with_registry!(
 		"guard_proxy_request_total",
@@ -4,7 +4,10 @@ use std::{
 	borrow::Cow,
 	collections::HashMap as StdHashMap,
 	net::SocketAddr,
-	sync::Arc,
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Arc,
+	},
 	time::{Duration, Instant},
 };
 
@@ -13,7 +16,7 @@ use tokio::sync::Mutex;
 use bytes::Bytes;
 use futures_util::{SinkExt, StreamExt};
 use global_error::*;
-use http_body_util::Full;
+use http_body_util::BodyExt;
 use hyper::body::Incoming as BodyIncoming;
 use hyper::header::HeaderName;
 use hyper::{Request, Response, StatusCode};
@@ -21,18 +24,21 @@ use hyper_tungstenite;
 use hyper_util::client::legacy::Client;
 use hyper_util::rt::TokioExecutor;
 use moka::future::Cache;
-use rand;
 use serde_json;
 use tokio::time::timeout;
 use tracing::Instrument;
 use uuid::Uuid;
 
+use crate::body::{self, CountingBody, ResponseBody, SharedBody};
+use crate::load_balancer::{LoadBalancer, LoadBalancingStrategy};
 use crate::metrics;
 use crate::request_context::RequestContext;
 
 const X_FORWARDED_FOR: HeaderName = HeaderName::from_static("x-forwarded-for");
 const ROUTE_CACHE_TTL: Duration = Duration::from_secs(60 * 10); // 10 minutes
 const PROXY_STATE_CACHE_TTL: Duration = Duration::from_secs(60 * 60); // 1 hour
+/// How often activity is reported per actor while it receives requests or has open WebSockets.
+pub(crate) const ACTOR_ACTIVITY_INTERVAL: Duration = Duration::from_secs(15);
 
 // Routing types
 #[derive(Clone, Debug)]
@@ -53,6 +59,21 @@ pub struct RoutingTimeout {
 pub struct RouteConfig {
 	pub targets: Vec<RouteTarget>,
 	pub timeout: RoutingTimeout,
+	pub load_balancing: LoadBalancingStrategy,
+	/// Middleware for requests to this route. Takes precedence over the middleware of the target's actor.
+	pub middleware: Option<MiddlewareConfig>,
+	/// Sends a share of the requests to a second group of targets, e.g. for canary rollouts.
+	pub split: Option<TrafficSplit>,
+	/// Set if the routing function's result depends on the request's method or headers. These routes are not
+	/// cached since the cache is keyed by hostname and path only.
+	pub depends_on_request: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct TrafficSplit {
+	pub targets: Vec<RouteTarget>,
+	/// Percent of requests sent to `targets` instead of the route's targets.
+	pub weight: u8,
 }
 
 #[derive(Clone, Debug)]
@@ -61,6 +82,8 @@ pub enum RoutingOutput {
 	Route(RouteConfig),
 	/// Return a custom response.
 	Response(StructuredResponse),
+	/// Return a response configured by the route, e.g. a redirect.
+	CustomResponse(CustomResponse),
 }
 
 #[derive(Clone, Debug)]
@@ -71,7 +94,7 @@ pub struct StructuredResponse {
 }
 
 impl StructuredResponse {
-	pub fn build_response(&self) -> GlobalResult<Response<Full<Bytes>>> {
+	pub fn build_response(&self) -> GlobalResult<Response<ResponseBody>> {
 		let mut body = StdHashMap::new();
 		body.insert("message", self.message.clone().into_owned());
 
@@ -85,16 +108,38 @@ impl StructuredResponse {
 		let response = Response::builder()
 			.status(self.status)
 			.header(hyper::header::CONTENT_TYPE, "application/json")
-			.body(Full::new(bytes))?;
+			.body(body::full(bytes))?;
 
 		Ok(response)
 	}
 }
 
+#[derive(Clone, Debug)]
+pub struct CustomResponse {
+	pub status: StatusCode,
+	pub headers: Vec<(String, String)>,
+	pub body: String,
+}
+
+impl CustomResponse {
+	pub fn build_response(&self) -> GlobalResult<Response<ResponseBody>> {
+		let mut response = Response::builder().status(self.status);
+		for (name, value) in &self.headers {
+			response = response.header(name, value);
+		}
+
+		Ok(response.body(body::full(Bytes::from(self.body.clone())))?)
+	}
+}
+
 #[derive(Clone, Debug)]
 enum ResolveRouteOutput {
-	Target(RouteTarget),
+	Target {
+		target: RouteTarget,
+		middleware: Option<MiddlewareConfig>,
+	},
 	Response(StructuredResponse),
+	CustomResponse(CustomResponse),
 }
 
 /// Enum defining the type of port the request came in on
@@ -108,6 +153,8 @@ pub type RoutingFn = Arc<
 	dyn for<'a> Fn(
 			&'a str,
 			&'a str,
+			&'a hyper::Method,
+			&'a hyper::HeaderMap,
 			PortType,
 		) -> futures::future::BoxFuture<'a, GlobalResult<RoutingOutput>>
 		+ Send
@@ -122,10 +169,35 @@ pub struct MiddlewareConfig {
 	pub timeout: TimeoutConfig,
 }
 
+/// Used for actors without a middleware policy and for the fields a policy leaves unset.
+impl Default for MiddlewareConfig {
+	fn default() -> Self {
+		Self {
+			rate_limit: RateLimitConfig {
+				requests: 100, // 100 requests
+				period: 60,    // per 60 seconds
+				distributed: false,
+			},
+			max_in_flight: MaxInFlightConfig {
+				amount: 20, // 20 concurrent requests
+			},
+			retry: RetryConfig {
+				max_attempts: 7,       // 7 retry attempts
+				initial_interval: 150, // 150ms initial interval
+			},
+			timeout: TimeoutConfig {
+				request_timeout: 30, // 30 seconds for requests
+			},
+		}
+	}
+}
+
 #[derive(Clone, Debug)]
 pub struct RateLimitConfig {
 	pub requests: u64,
 	pub period: u64, // in seconds
+	/// Count requests with the `DistributedRateLimitFn` so the limit is shared between guard instances.
+	pub distributed: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -156,6 +228,58 @@ pub type MiddlewareFn = Arc<
 		+ Sync,
 >;
 
+/// Counts a request from a client to an actor against a rate limit shared between all guard instances.
+/// Returns false if the request is over the limit. Errors fail the request, so implementations that should
+/// fail open have to handle them.
+pub type DistributedRateLimitFn = Arc<
+	dyn for<'a> Fn(
+			&'a Uuid,
+			std::net::IpAddr,
+			&'a RateLimitConfig,
+		) -> futures::future::BoxFuture<'a, GlobalResult<bool>>
+		+ Send
+		+ Sync,
+>;
+
+/// Records that an actor received a request. Used to stop actors that have been idle for too long.
+pub type ActorActivityFn =
+	Arc<dyn for<'a> Fn(&'a Uuid) -> futures::future::BoxFuture<'a, GlobalResult<()>> + Send + Sync>;
+
+/// Reports actor activity at most once per `ACTOR_ACTIVITY_INTERVAL` per actor. Shared by the HTTP and L4
+/// proxies so that concurrent requests and connections to the same actor are reported once.
+#[derive(Clone)]
+pub struct ActorActivityReporter {
+	actor_activity_fn: ActorActivityFn,
+	/// Actors whose activity was reported within the last `ACTOR_ACTIVITY_INTERVAL`.
+	reported: Cache<Uuid, ()>,
+}
+
+impl ActorActivityReporter {
+	pub fn new(actor_activity_fn: ActorActivityFn) -> Self {
+		ActorActivityReporter {
+			actor_activity_fn,
+			reported: Cache::builder()
+				.max_capacity(10_000)
+				.time_to_live(ACTOR_ACTIVITY_INTERVAL)
+				.build(),
+		}
+	}
+
+	/// Reports activity for the actor unless it was already reported within the last
+	/// `ACTOR_ACTIVITY_INTERVAL`.
+	#[tracing::instrument(skip_all)]
+	pub async fn report(&self, actor_id: Uuid) {
+		if self.reported.contains_key(&actor_id) {
+			return;
+		}
+		self.reported.insert(actor_id, ()).await;
+
+		if let Err(err) = (self.actor_activity_fn)(&actor_id).await {
+			tracing::warn!(?err, ?actor_id, "failed to record actor activity");
+		}
+	}
+}
+
 // Cache for routing results
 struct RouteCache {
 	cache: Cache<(String, String), RouteConfig>,
@@ -233,17 +357,21 @@ impl RateLimiter {
 }
 
 // In-flight requests counter
-struct InFlightCounter {
+pub(crate) struct InFlightCounter {
 	count: usize,
 	max: usize,
 }
 
 impl InFlightCounter {
-	fn new(max: usize) -> Self {
+	pub(crate) fn new(max: usize) -> Self {
 		Self { count: 0, max }
 	}
 
-	fn try_acquire(&mut self) -> bool {
+	pub(crate) fn count(&self) -> usize {
+		self.count
+	}
+
+	pub(crate) fn try_acquire(&mut self) -> bool {
 		if self.count < self.max {
 			self.count += 1;
 			true
@@ -252,19 +380,53 @@ impl InFlightCounter {
 		}
 	}
 
-	fn release(&mut self) {
+	pub(crate) fn release(&mut self) {
 		self.count = self.count.saturating_sub(1);
 	}
 }
 
+/// Counts a proxied request as in flight until dropped.
+struct InFlightGuard {
+	state: Arc<ProxyState>,
+	client_ip: std::net::IpAddr,
+	actor_id: Option<Uuid>,
+	target: RouteTarget,
+}
+
+impl Drop for InFlightGuard {
+	fn drop(&mut self) {
+		metrics::PROXY_REQUEST_PENDING.dec();
+
+		// The response body may be dropped outside of the runtime during shutdown
+		let Ok(handle) = tokio::runtime::Handle::try_current() else {
+			return;
+		};
+
+		let state = self.state.clone();
+		let client_ip = self.client_ip;
+		let actor_id = self.actor_id;
+		let target = self.target.clone();
+		handle.spawn(
+			async move {
+				state.release_in_flight(client_ip, &actor_id).await;
+				state.load_balancer.release(&target).await;
+			}
+			.instrument(tracing::info_span!("release_in_flight_task")),
+		);
+	}
+}
+
 // State shared across all request handlers
 pub struct ProxyState {
 	_config: rivet_config::Config, // Unused but kept for potential future use
 	routing_fn: RoutingFn,
 	middleware_fn: MiddlewareFn,
+	distributed_rate_limit_fn: Option<DistributedRateLimitFn>,
+	actor_activity: Option<ActorActivityReporter>,
 	route_cache: RouteCache,
 	rate_limiters: Cache<(Uuid, std::net::IpAddr), Arc<Mutex<RateLimiter>>>,
 	in_flight_counters: Cache<(Uuid, std::net::IpAddr), Arc<Mutex<InFlightCounter>>>,
+	load_balancer: LoadBalancer,
 	port_type: PortType,
 	clickhouse_inserter: Option<clickhouse_inserter::ClickHouseInserterHandle>,
 }
@@ -274,6 +436,8 @@ impl ProxyState {
 		config: rivet_config::Config,
 		routing_fn: RoutingFn,
 		middleware_fn: MiddlewareFn,
+		distributed_rate_limit_fn: Option<DistributedRateLimitFn>,
+		actor_activity: Option<ActorActivityReporter>,
 		port_type: PortType,
 		clickhouse_inserter: Option<clickhouse_inserter::ClickHouseInserterHandle>,
 	) -> Self {
@@ -281,6 +445,8 @@ impl ProxyState {
 			_config: config,
 			routing_fn,
 			middleware_fn,
+			distributed_rate_limit_fn,
+			actor_activity,
 			route_cache: RouteCache::new(),
 			rate_limiters: Cache::builder()
 				.max_capacity(10_000)
@@ -290,16 +456,19 @@ impl ProxyState {
 				.max_capacity(10_000)
 				.time_to_live(PROXY_STATE_CACHE_TTL)
 				.build(),
+			load_balancer: LoadBalancer::new(),
 			port_type,
 			clickhouse_inserter,
 		}
 	}
 
-	#[tracing::instrument(skip(self))]
+	#[tracing::instrument(skip(self, headers))]
 	async fn resolve_route(
 		&self,
 		hostname: &str,
 		path: &str,
+		method: &hyper::Method,
+		headers: &hyper::HeaderMap,
 		port_type: PortType,
 		ignore_cache: bool,
 	) -> GlobalResult<ResolveRouteOutput> {
@@ -316,9 +485,16 @@ impl ProxyState {
 		// Check cache first
 		if !ignore_cache {
 			if let Some(result) = self.route_cache.get(hostname_only, path).await {
-				// Choose a random target from the cached targets
-				if let Some(target) = choose_random_target(&result.targets) {
-					return Ok(ResolveRouteOutput::Target(target.clone()));
+				// Choose a target from the cached targets
+				if let Some(target) = self
+					.load_balancer
+					.choose_target(hostname_only, path, headers, &result)
+					.await
+				{
+					return Ok(ResolveRouteOutput::Target {
+						target: target.clone(),
+						middleware: result.middleware.clone(),
+					});
 				}
 			}
 		}
@@ -336,7 +512,7 @@ impl ProxyState {
 		);
 		let routing_result = timeout(
 			default_timeout,
-			(self.routing_fn)(hostname_only, path, port_type),
+			(self.routing_fn)(hostname_only, path, method, headers, port_type),
 		)
 		.await;
 
@@ -353,13 +529,19 @@ impl ProxyState {
 					);
 
 					// Cache the result
-					self.route_cache
-						.insert(hostname_only.to_owned(), path.to_owned(), result.clone())
-						.await;
-					tracing::debug!("Added route to cache");
+					if !result.depends_on_request {
+						self.route_cache
+							.insert(hostname_only.to_owned(), path.to_owned(), result.clone())
+							.await;
+						tracing::debug!("Added route to cache");
+					}
 
-					// Choose a random target
-					if let Some(target) = choose_random_target(&result.targets) {
+					// Choose a target according to the route's load balancing strategy
+					if let Some(target) = self
+						.load_balancer
+						.choose_target(hostname_only, path, headers, &result)
+						.await
+					{
 						tracing::debug!(
 							hostname = %hostname_only,
 							path = %path,
@@ -370,7 +552,10 @@ impl ProxyState {
 							server_id = ?target.server_id,
 							"Selected target for request"
 						);
-						Ok(ResolveRouteOutput::Target(target.clone()))
+						Ok(ResolveRouteOutput::Target {
+							target: target.clone(),
+							middleware: result.middleware.clone(),
+						})
 					} else {
 						tracing::warn!(
 							hostname = %hostname_only,
@@ -393,6 +578,15 @@ impl ProxyState {
 					);
 					Ok(ResolveRouteOutput::Response(response))
 				}
+				RoutingOutput::CustomResponse(response) => {
+					tracing::debug!(
+						hostname = %hostname_only,
+						path = %path,
+						status = ?response.status,
+						"Routing returned route response"
+					);
+					Ok(ResolveRouteOutput::CustomResponse(response))
+				}
 			},
 			Err(_) => {
 				tracing::error!(
@@ -425,42 +619,12 @@ impl ProxyState {
 				MiddlewareResponse::Ok(config) => Ok(config),
 				MiddlewareResponse::NotFound => {
 					// Default values if middleware not found for this actor
-					Ok(MiddlewareConfig {
-						rate_limit: RateLimitConfig {
-							requests: 100, // 100 requests
-							period: 60,    // per 60 seconds
-						},
-						max_in_flight: MaxInFlightConfig {
-							amount: 20, // 20 concurrent requests
-						},
-						retry: RetryConfig {
-							max_attempts: 3,       // 3 retry attempts
-							initial_interval: 100, // 100ms initial interval
-						},
-						timeout: TimeoutConfig {
-							request_timeout: 30, // 30 seconds for requests
-						},
-					})
+					Ok(MiddlewareConfig::default())
 				}
 			},
 			Err(_) => {
 				// Default values if middleware times out
-				Ok(MiddlewareConfig {
-					rate_limit: RateLimitConfig {
-						requests: 100, // 100 requests
-						period: 60,    // per 60 seconds
-					},
-					max_in_flight: MaxInFlightConfig {
-						amount: 20, // 20 concurrent requests
-					},
-					retry: RetryConfig {
-						max_attempts: 3,       // 3 retry attempts
-						initial_interval: 100, // 100ms initial interval
-					},
-					timeout: TimeoutConfig {
-						request_timeout: 30, // 30 seconds for requests
-					},
-				})
+				Ok(MiddlewareConfig::default())
 			}
 		}
 	}
@@ -470,14 +634,23 @@ impl ProxyState {
 		&self,
 		ip_addr: std::net::IpAddr,
 		actor_id: &Option<Uuid>,
+		middleware_config: &MiddlewareConfig,
 	) -> GlobalResult<bool> {
 		let Some(actor_id) = *actor_id else {
 			// No rate limiting when actor_id is None
 			return Ok(true);
 		};
 
-		// Get actor-specific middleware config
-		let middleware_config = self.get_middleware_config(&actor_id).await?;
+		if middleware_config.rate_limit.distributed {
+			if let Some(distributed_rate_limit_fn) = &self.distributed_rate_limit_fn {
+				return distributed_rate_limit_fn(
+					&actor_id,
+					ip_addr,
+					&middleware_config.rate_limit,
+				)
+				.await;
+			}
+		}
 
 		let cache_key = (actor_id, ip_addr);
 
@@ -510,15 +683,13 @@ impl ProxyState {
 		&self,
 		ip_addr: std::net::IpAddr,
 		actor_id: &Option<Uuid>,
+		middleware_config: &MiddlewareConfig,
 	) -> GlobalResult<bool> {
 		let Some(actor_id) = *actor_id else {
 			// No in-flight limiting when actor_id is None
 			return Ok(true);
 		};
 
-		// Get actor-specific middleware config
-		let middleware_config = self.get_middleware_config(&actor_id).await?;
-
 		let cache_key = (actor_id, ip_addr);
 
 		// Get existing counter or create a new one
@@ -545,6 +716,21 @@ impl ProxyState {
 		Ok(result)
 	}
 
+	/// Reports activity for the actor, see `ActorActivityReporter`. Does not wait for the report to
+	/// complete.
+	#[tracing::instrument(skip_all)]
+	async fn record_actor_activity(&self, actor_id: Uuid) {
+		let Some(actor_activity) = &self.actor_activity else {
+			return;
+		};
+
+		let actor_activity = actor_activity.clone();
+		tokio::spawn(
+			async move { actor_activity.report(actor_id).await }
+				.instrument(tracing::info_span!("record_actor_activity_task")),
+		);
+	}
+
 	#[tracing::instrument(skip_all)]
 	async fn release_in_flight(&self, ip_addr: std::net::IpAddr, actor_id: &Option<Uuid>) {
 		// Skip if actor_id is None (no in-flight tracking)
@@ -561,16 +747,8 @@ impl ProxyState {
 	}
 }
 
-// Helper function to choose a random target from a list of targets
-fn choose_random_target(targets: &[RouteTarget]) -> Option<&RouteTarget> {
-	if targets.is_empty() {
-		return None;
-	}
-
-	// Use a simple random index selection
-	let random_index = rand::random::<usize>() % targets.len();
-	targets.get(random_index)
-}
+/// Request body sent to the upstream. The client's body is streamed through as it is read.
+type ProxiedRequestBody = SharedBody<CountingBody<BodyIncoming>>;
 
 // Proxy service
 pub struct ProxyService {
@@ -578,7 +756,7 @@ pub struct ProxyService {
 	remote_addr: SocketAddr,
 	// Note: Using the hyper legacy client is the only option currently.
 	// This is what reqwest uses under the hood. Eventually we'll migrate to h3 once it's ready.
-	client: Client<hyper_util::client::legacy::connect::HttpConnector, Full<Bytes>>,
+	client: Client<hyper_util::client::legacy::connect::HttpConnector, ProxiedRequestBody>,
 }
 
 impl ProxyService {
@@ -605,7 +783,7 @@ impl ProxyService {
 		&self,
 		req: Request<BodyIncoming>,
 		request_context: &mut RequestContext,
-	) -> GlobalResult<Response<Full<Bytes>>> {
+	) -> GlobalResult<Response<ResponseBody>> {
 		let host = req
 			.headers()
 			.get(hyper::header::HOST)
@@ -619,29 +797,38 @@ impl ProxyService {
 			.unwrap_or_else(|| req.uri().path().to_string());
 
 		let start_time = Instant::now();
-
-		// Set request body size in analytics (will be updated with actual size later)
+		let request_body_bytes = Arc::new(AtomicU64::new(0));
 
 		let target_res = self
 			.state
-			.resolve_route(host, &path, self.state.port_type.clone(), false)
+			.resolve_route(
+				host,
+				&path,
+				req.method(),
+				req.headers(),
+				self.state.port_type.clone(),
+				false,
+			)
 			.await;
 
 		let duration_secs = start_time.elapsed().as_secs_f64();
 		metrics::RESOLVE_ROUTE_DURATION.observe(duration_secs);
 
 		// Resolve target
-		let target = match target_res {
-			Ok(ResolveRouteOutput::Target(target)) => target,
+		let (target, route_middleware) = match target_res {
+			Ok(ResolveRouteOutput::Target { target, middleware }) => (target, middleware),
 			Ok(ResolveRouteOutput::Response(response)) => {
 				// Return the custom response
 				return response.build_response();
 			}
+			Ok(ResolveRouteOutput::CustomResponse(response)) => {
+				return response.build_response();
+			}
 			Err(err) => {
 				tracing::error!(?err, "Routing error");
 				return Ok(Response::builder()
 					.status(StatusCode::BAD_GATEWAY)
-					.body(Full::<Bytes>::new(Bytes::new()))?);
+					.body(body::empty())?);
 			}
 		};
 
@@ -665,41 +852,83 @@ impl ProxyService {
 		// Extract IP address from remote_addr
 		let client_ip = self.remote_addr.ip();
 
+		// The route's middleware takes precedence over the actor's
+		let middleware_config = match (route_middleware, &actor_id) {
+			(Some(middleware_config), _) => middleware_config,
+			(None, Some(actor_id)) => self.state.get_middleware_config(actor_id).await?,
+			(None, None) => MiddlewareConfig::default(),
+		};
+
 		// Apply rate limiting
-		let res = if !self.state.check_rate_limit(client_ip, &actor_id).await? {
-			Response::builder()
+		let (res, in_flight) = if !self
+			.state
+			.check_rate_limit(client_ip, &actor_id, &middleware_config)
+			.await?
+		{
+			let res = Response::builder()
 				.status(StatusCode::TOO_MANY_REQUESTS)
-				.body(Full::<Bytes>::new(Bytes::new()))
-				.map_err(Into::into)
+				.body(body::empty())
+				.map_err(Into::into);
+
+			(res, None)
 		}
 		// Check in-flight limit
-		else if !self.state.acquire_in_flight(client_ip, &actor_id).await? {
-			Response::builder()
+		else if !self
+			.state
+			.acquire_in_flight(client_ip, &actor_id, &middleware_config)
+			.await?
+		{
+			let res = Response::builder()
 				.status(StatusCode::TOO_MANY_REQUESTS)
-				.body(Full::<Bytes>::new(Bytes::new()))
-				.map_err(Into::into)
+				.body(body::empty())
+				.map_err(Into::into);
+
+			(res, None)
 		} else {
 			// Increment metrics
 			metrics::PROXY_REQUEST_PENDING.inc();
 			metrics::PROXY_REQUEST_TOTAL.inc();
 
-			// Prepare to release in-flight counter when done
-			let state_clone = self.state.clone();
-			crate::defer! {
-				tokio::spawn(async move {
-					state_clone.release_in_flight(client_ip, &actor_id).await;
-				}.instrument(tracing::info_span!("release_in_flight_task")));
+			// Count the request towards the target's load for least-in-flight load balancing
+			self.state.load_balancer.acquire(&target).await;
+
+			if let Some(actor_id) = actor_id {
+				self.state.record_actor_activity(actor_id).await;
 			}
 
+			let in_flight = InFlightGuard {
+				state: self.state.clone(),
+				client_ip,
+				actor_id,
+				target: target.clone(),
+			};
+
 			// Branch for WebSocket vs HTTP handling
 			// Both paths will handle their own metrics and error handling
-			let res = if hyper_tungstenite::is_upgrade_request(&req) {
-				// WebSocket upgrade
-				self.handle_websocket_upgrade(req, target, request_context)
-					.await
+			let (res, in_flight) = if hyper_tungstenite::is_upgrade_request(&req) {
+				// WebSocket upgrade, in flight until the WebSocket closes
+				let res = self
+					.handle_websocket_upgrade(
+						req,
+						target,
+						&middleware_config,
+						request_context,
+						in_flight,
+					)
+					.await;
+
+				(res, None)
 			} else {
-				// Regular HTTP request
-				self.handle_http_request(req, target, request_context).await
+				// Regular HTTP request, count the request body as it is streamed to the upstream
+				let req =
+					req.map(|body| CountingBody::with_counter(body, request_body_bytes.clone()));
+				let res = self
+					.handle_http_request(req, target, &middleware_config, request_context)
+					.await;
+
+				// In flight until the response body is done streaming, or released right away if the
+				// request failed
+				(res, Some(in_flight))
 			};
 
 			let status = match &res {
@@ -713,9 +942,7 @@ impl ProxyService {
 				.with_label_values(&[&status])
 				.observe(duration_secs);
 
-			metrics::PROXY_REQUEST_PENDING.dec();
-
-			res
+			(res, in_flight)
 		};
 
 		if let Err(err) = &res {
@@ -765,48 +992,40 @@ impl ProxyService {
 		request_context.service_response_duration_ms =
 			Some(start_time.elapsed().as_millis() as u32);
 
-		// Insert analytics event asynchronously
-		let mut context_clone = request_context.clone();
-		tokio::spawn(async move {
-			if let Err(error) = context_clone.insert_event().await {
-				tracing::warn!(?error, "failed to insert guard analytics event");
+		match res {
+			Ok(resp) => {
+				// Insert analytics event once the response body is done streaming so the body sizes are known
+				let mut context_clone = request_context.clone();
+				Ok(resp.map(|body| {
+					CountingBody::new(body)
+						.on_drop(move |response_body_bytes| {
+							drop(in_flight);
+
+							context_clone.client_request_body_bytes =
+								Some(request_body_bytes.load(Ordering::Relaxed));
+							context_clone.guard_response_body_bytes = Some(response_body_bytes);
+
+							spawn_insert_event(context_clone);
+						})
+						.boxed_unsync()
+				}))
 			}
-		});
+			Err(err) => {
+				spawn_insert_event(request_context.clone());
 
-		res
+				Err(err)
+			}
+		}
 	}
 
 	#[tracing::instrument(skip_all)]
 	async fn handle_http_request(
 		&self,
-		req: Request<BodyIncoming>,
+		req: Request<CountingBody<BodyIncoming>>,
 		mut target: RouteTarget,
-		request_context: &mut RequestContext,
-	) -> GlobalResult<Response<Full<Bytes>>> {
-		// Get middleware config for this actor if it exists
-		let middleware_config = match &target.actor_id {
-			Some(actor_id) => self.state.get_middleware_config(actor_id).await?,
-			None => {
-				// Default middleware config for targets without actor_id
-				MiddlewareConfig {
-					rate_limit: RateLimitConfig {
-						requests: 100, // 100 requests
-						period: 60,    // per 60 seconds
-					},
-					max_in_flight: MaxInFlightConfig {
-						amount: 20, // 20 concurrent requests
-					},
-					retry: RetryConfig {
-						max_attempts: 3,       // 3 retry attempts
-						initial_interval: 100, // 100ms initial interval
-					},
-					timeout: TimeoutConfig {
-						request_timeout: 30, // 30 seconds for requests
-					},
-				}
-			}
-		};
-
+		middleware_config: &MiddlewareConfig,
+		_request_context: &mut RequestContext,
+	) -> GlobalResult<Response<ResponseBody>> {
 		let host = req
 			.headers()
 			.get(hyper::header::HOST)
@@ -820,18 +1039,10 @@ impl ProxyService {
 			.map(|x| x.to_string())
 			.unwrap_or_else(|| req.uri().path().to_string());
 
-		// Read the request body before proceeding with retries
+		// The request body is streamed to the upstream instead of being buffered. It is shared between
+		// attempts so that the request can be retried as long as no attempt has started reading the body.
 		let (req_parts, body) = req.into_parts();
-		let req_body = match http_body_util::BodyExt::collect(body).await {
-			Ok(collected) => collected.to_bytes(),
-			Err(err) => {
-				tracing::debug!(?err, "Failed to read request body");
-				Bytes::new()
-			}
-		};
-
-		// Set actual request body size in analytics
-		request_context.client_request_body_bytes = Some(req_body.len() as u64);
+		let req_body = SharedBody::new(body);
 
 		// Set up retry with backoff from middleware config
 		let max_attempts = middleware_config.retry.max_attempts;
@@ -875,7 +1086,7 @@ impl ProxyService {
 				};
 
 				// Create the final request with body
-				let proxied_req = match builder.body(Full::<Bytes>::new(req_body.clone())) {
+				let proxied_req = match builder.body(req_body.clone()) {
 					Ok(req) => req,
 					Err(err) => {
 						tracing::warn!(?err, "Failed to build request body");
@@ -893,24 +1104,24 @@ impl ProxyService {
 				match timeout(timeout_duration, self.client.request(proxied_req)).await {
 					Ok(Ok(resp)) => {
 						let response_receive_time = request_send_start.elapsed();
+						self.state
+							.load_balancer
+							.record_connect_success(&target)
+							.await;
 
-						// Convert the hyper::body::Incoming to http_body_util::Full<Bytes>
-						let (parts, body) = resp.into_parts();
-
-						// Read the response body
-						let body_bytes = match http_body_util::BodyExt::collect(body).await {
-							Ok(collected) => collected.to_bytes(),
-							Err(_) => Bytes::new(),
-						};
-
-						// Set actual response body size in analytics
-						request_context.guard_response_body_bytes = Some(body_bytes.len() as u64);
-
-						let full_body = Full::new(body_bytes);
-						return Ok(Response::from_parts(parts, full_body));
+						// Stream the response body back to the client
+						return Ok(resp.map(|body| body.boxed_unsync()));
 					}
 					Ok(Err(err)) => {
-						if !err.is_connect() || attempts >= max_attempts {
+						if err.is_connect() {
+							self.state
+								.load_balancer
+								.record_connect_failure(&target)
+								.await;
+						}
+
+						// Requests can only be retried if their body has not been sent yet
+						if !err.is_connect() || req_body.is_consumed() || attempts >= max_attempts {
 							let error = Some(global_error::ext::AssertionError::Panic {
 								message: format!("Request error"),
 								location: global_error::location!(),
@@ -930,21 +1141,26 @@ impl ProxyService {
 								self.state.resolve_route(
 									&host,
 									&path,
+									&req_parts.method,
+									&req_parts.headers,
 									self.state.port_type.clone(),
 									true,
 								),
 							);
 
 							target = match new_target {
-								Ok(ResolveRouteOutput::Target(target)) => target,
+								Ok(ResolveRouteOutput::Target { target, .. }) => target,
 								Ok(ResolveRouteOutput::Response(response)) => {
 									return response.build_response()
 								}
+								Ok(ResolveRouteOutput::CustomResponse(response)) => {
+									return response.build_response()
+								}
 								Err(err) => {
 									tracing::error!(?err, "Routing error");
 									return Ok(Response::builder()
 										.status(StatusCode::BAD_GATEWAY)
-										.body(Full::<Bytes>::new(Bytes::new()))?);
+										.body(body::empty())?);
 								}
 							};
 
@@ -980,7 +1196,7 @@ impl ProxyService {
 
 		Ok(Response::builder()
 			.status(status_code)
-			.body(Full::<Bytes>::new(Bytes::new()))?)
+			.body(body::empty())?)
 	}
 
 	// Common function to build a request URI and headers
@@ -1032,8 +1248,10 @@ impl ProxyService {
 		&self,
 		req: Request<BodyIncoming>,
 		mut target: RouteTarget,
+		middleware_config: &MiddlewareConfig,
 		_request_context: &mut RequestContext,
-	) -> GlobalResult<Response<Full<Bytes>>> {
+		in_flight: InFlightGuard,
+	) -> GlobalResult<Response<ResponseBody>> {
 		// Get actor and server IDs for metrics and middleware
 		let actor_id = target.actor_id;
 		let server_id = target.server_id;
@@ -1052,36 +1270,13 @@ impl ProxyService {
 			.path_and_query()
 			.map(|x| x.to_string())
 			.unwrap_or_else(|| req.uri().path().to_string());
+		let req_method = req.method().clone();
+		let req_headers = req.headers().clone();
 
 		// Log request details
 		tracing::debug!("WebSocket upgrade request for path: {}, target host: {}:{}, actor_id: {}, server_id: {}",
 			target.path, target.host, target.port, actor_id_str, server_id_str);
 
-		// Get middleware config for this actor if it exists
-		let middleware_config = match &actor_id {
-			Some(actor_id) => self.state.get_middleware_config(actor_id).await?,
-			None => {
-				// Default middleware config for targets without actor_id
-				tracing::debug!("Using default middleware config (no actor_id)");
-				MiddlewareConfig {
-					rate_limit: RateLimitConfig {
-						requests: 100, // 100 requests
-						period: 60,    // per 60 seconds
-					},
-					max_in_flight: MaxInFlightConfig {
-						amount: 20, // 20 concurrent requests
-					},
-					retry: RetryConfig {
-						max_attempts: 3,       // 3 retry attempts
-						initial_interval: 100, // 100ms initial interval
-					},
-					timeout: TimeoutConfig {
-						request_timeout: 30, // 30 seconds for requests
-					},
-				}
-			}
-		};
-
 		// Set up retry with backoff from middleware config
 		let max_attempts = middleware_config.retry.max_attempts;
 		let initial_interval = middleware_config.retry.initial_interval;
@@ -1126,6 +1321,9 @@ impl ProxyService {
 		tracing::debug!("Spawning task to handle WebSocket communication");
 		tokio::spawn(
 			async move {
+				// Released when the task ends
+				let _in_flight = in_flight;
+
 				// Set up a timeout for the entire operation
 				let timeout_duration = Duration::from_secs(30); // 30 seconds timeout
 				tracing::debug!(
@@ -1191,11 +1389,17 @@ impl ProxyService {
 								}
 							}
 
+							state.load_balancer.record_connect_success(&target).await;
+
 							upstream_ws = Some(ws_stream);
 							break;
 						}
 						Ok(Err(err)) => {
 							tracing::debug!(?err, "WebSocket request attempt {} failed", attempts);
+
+							if let tokio_tungstenite::tungstenite::Error::Io(_) = err {
+								state.load_balancer.record_connect_failure(&target).await;
+							}
 						}
 						Err(_) => {
 							tracing::debug!(
@@ -1252,14 +1456,24 @@ impl ProxyService {
 						tokio::time::sleep(backoff),
 						// Resolve target again, this time ignoring cache. This makes sure
 						// we always re-fetch the route on error
-						state.resolve_route(&req_host, &req_path, state.port_type.clone(), true,),
+						state.resolve_route(
+							&req_host,
+							&req_path,
+							&req_method,
+							&req_headers,
+							state.port_type.clone(),
+							true,
+						),
 					);
 
 					match new_target {
-						Ok(ResolveRouteOutput::Target(new_target)) => {
+						Ok(ResolveRouteOutput::Target {
+							target: new_target, ..
+						}) => {
 							target = new_target;
 						}
-						Ok(ResolveRouteOutput::Response(_response)) => {
+						Ok(ResolveRouteOutput::Response(_))
+						| Ok(ResolveRouteOutput::CustomResponse(_)) => {
 							tracing::error!("Expected target, got response")
 						}
 						Err(err) => tracing::error!(?err, "Routing error"),
@@ -1589,9 +1803,25 @@ impl ProxyService {
 					tracing::debug!("Upstream-to-client task completed");
 				};
 
+				// Keep reporting activity while the connection is open so the actor is not stopped for
+				// being idle
+				let report_activity = async {
+					let Some(actor_id) = actor_id else {
+						return std::future::pending::<()>().await;
+					};
+
+					loop {
+						state.record_actor_activity(actor_id).await;
+						tokio::time::sleep(ACTOR_ACTIVITY_INTERVAL).await;
+					}
+				};
+
 				// Run both directions concurrently until either one completes or errors
 				tracing::debug!("Starting bidirectional message forwarding");
-				tokio::join!(client_to_upstream, upstream_to_client);
+				tokio::select! {
+					_ = async { tokio::join!(client_to_upstream, upstream_to_client) } => {}
+					_ = report_activity => {}
+				}
 				tracing::debug!("Bidirectional message forwarding completed");
 			}
 			.instrument(tracing::info_span!("handle_ws_task")),
@@ -1604,17 +1834,17 @@ impl ProxyService {
 		// Extract the parts from the response but preserve all headers and status
 		let (parts, _) = client_response.into_parts();
 		// Create a new response with an empty body - WebSocket upgrades don't need a body
-		Ok(Response::from_parts(
-			parts,
-			Full::<Bytes>::new(Bytes::new()),
-		))
+		Ok(Response::from_parts(parts, body::empty()))
 	}
 }
 
 impl ProxyService {
 	// Process an individual request
 	#[tracing::instrument(skip_all)]
-	pub async fn process(&self, req: Request<BodyIncoming>) -> GlobalResult<Response<Full<Bytes>>> {
+	pub async fn process(
+		&self,
+		req: Request<BodyIncoming>,
+	) -> GlobalResult<Response<ResponseBody>> {
 		// Create request context for analytics tracking
 		let mut request_context = RequestContext::new(self.state.clickhouse_inserter.clone());
 
@@ -1739,6 +1969,19 @@ impl Clone for ProxyService {
 	}
 }
 
+fn spawn_insert_event(mut request_context: RequestContext) {
+	// The response body may be dropped outside of the runtime during shutdown
+	let Ok(handle) = tokio::runtime::Handle::try_current() else {
+		return;
+	};
+
+	handle.spawn(async move {
+		if let Err(error) = request_context.insert_event().await {
+			tracing::warn!(?error, "failed to insert guard analytics event");
+		}
+	});
+}
+
 // Factory for creating proxy services
 pub struct ProxyServiceFactory {
 	state: Arc<ProxyState>,
@@ -1749,6 +1992,8 @@ impl ProxyServiceFactory {
 		config: rivet_config::Config,
 		routing_fn: RoutingFn,
 		middleware_fn: MiddlewareFn,
+		distributed_rate_limit_fn: Option<DistributedRateLimitFn>,
+		actor_activity: Option<ActorActivityReporter>,
 		port_type: PortType,
 		clickhouse_inserter: Option<clickhouse_inserter::ClickHouseInserterHandle>,
 	) -> Self {
@@ -1756,6 +2001,8 @@ impl ProxyServiceFactory {
 			config,
 			routing_fn,
 			middleware_fn,
+			distributed_rate_limit_fn,
+			actor_activity,
 			port_type,
 			clickhouse_inserter,
 		));
@@ -1,6 +1,10 @@
 use crate::cert_resolver::{create_tls_config, CertResolverFn};
+use crate::l4::L4RoutingFn;
 use crate::metrics;
-use crate::proxy_service::{MiddlewareFn, ProxyServiceFactory, RoutingFn};
+use crate::proxy_service::{
+	ActorActivityFn, ActorActivityReporter, DistributedRateLimitFn, MiddlewareFn,
+	ProxyServiceFactory, RoutingFn,
+};
 use global_error::*;
 use hyper::service::service_fn;
 use std::fmt;
@@ -33,18 +37,34 @@ pub async fn run_server(
 	config: rivet_config::Config,
 	routing_fn: RoutingFn,
 	middleware_fn: MiddlewareFn,
+	distributed_rate_limit_fn: Option<DistributedRateLimitFn>,
+	actor_activity_fn: Option<ActorActivityFn>,
+	l4_routing_fn: L4RoutingFn,
 	cert_resolver_fn: Option<CertResolverFn>,
 	clickhouse_inserter: Option<clickhouse_inserter::ClickHouseInserterHandle>,
 ) -> GlobalResult<()> {
 	// Configure servers for different ports
 	let guard_config = config.guard()?;
 
+	// Configure TLS if resolver function is provided. Used by both HTTPS and TCP+TLS ports.
+	let tls_acceptor = cert_resolver_fn.map(|resolver_fn| {
+		// Create a TLS server config using our certificate resolver
+		let server_config = create_tls_config(resolver_fn);
+
+		TlsAcceptor::from(Arc::new(server_config))
+	});
+
+	// Shared by all ports so that activity for the same actor is only reported once
+	let actor_activity = actor_activity_fn.map(ActorActivityReporter::new);
+
 	// Set up HTTP server
 	let http_addr: std::net::SocketAddr = ([0, 0, 0, 0], guard_config.http_port).into();
 	let http_factory = Arc::new(ProxyServiceFactory::new(
 		config.clone(),
 		routing_fn.clone(),
 		middleware_fn.clone(),
+		distributed_rate_limit_fn.clone(),
+		actor_activity.clone(),
 		crate::proxy_service::PortType::Http,
 		clickhouse_inserter.clone(),
 	));
@@ -59,32 +79,38 @@ pub async fn run_server(
 			config.clone(),
 			routing_fn.clone(),
 			middleware_fn.clone(),
+			distributed_rate_limit_fn.clone(),
+			actor_activity.clone(),
 			crate::proxy_service::PortType::Https,
 			clickhouse_inserter.clone(),
 		));
 		let listener = tokio::net::TcpListener::bind(https_addr).await?;
 
-		// Configure TLS if resolver function is provided
-		let acceptor = if let Some(resolver_fn) = cert_resolver_fn {
-			// Create a TLS server config using our certificate resolver
-			let server_config = create_tls_config(resolver_fn);
-
-			Some(TlsAcceptor::from(Arc::new(server_config)))
-		} else {
+		if tls_acceptor.is_none() {
 			tracing::warn!("No TLS certificate resolver provided, HTTPS will not work properly");
-			None
-		};
+		}
 
 		(
 			Some(https_addr),
 			Some(https_factory),
 			Some(listener),
-			acceptor,
+			tls_acceptor.clone(),
 		)
 	} else {
 		(None, None, None, None)
 	};
 
+	// Set up L4 (TCP and UDP) listeners for GameGuard ports
+	let (l4_shutdown_tx, l4_shutdown_rx) = tokio::sync::watch::channel(false);
+	let mut l4_handles = crate::l4::start_listeners(
+		guard_config,
+		l4_routing_fn,
+		actor_activity.clone(),
+		tls_acceptor.clone(),
+		l4_shutdown_rx,
+	)
+	.await?;
+
 	// Set up server builder and graceful shutdown
 	let server = hyper_util::server::conn::auto::Builder::new(hyper_util::rt::TokioExecutor::new());
 	let graceful = hyper_util::server::graceful::GracefulShutdown::new();
@@ -276,9 +302,13 @@ pub async fn run_server(
 		}
 	}
 
+	// Stop L4 listeners and drain their connections alongside the HTTP connections
+	let _ = l4_shutdown_tx.send(true);
+	let l4_drained = futures::future::join_all(l4_handles.iter_mut());
+
 	// Start graceful shutdown with timeout
 	tokio::select! {
-		_ = graceful.shutdown() => {
+		_ = futures::future::join(graceful.shutdown(), l4_drained) => {
 			tracing::info!("Gracefully shutdown completed");
 		},
 		_ = tokio::time::sleep(Duration::from_secs(30)) => {
@@ -286,5 +316,10 @@ pub async fn run_server(
 		}
 	}
 
+	// Closes any L4 connections still open after the timeout
+	for handle in l4_handles {
+		handle.abort();
+	}
+
 	Ok(())
 }
@@ -465,6 +465,8 @@ pub fn create_test_config(
 	let mut guard = rivet_config::config::guard::Guard {
 		http_port: 0, // Use 0 to let the OS choose a port
 		https: None,  // No HTTPS by default in tests
+		tcp: None,
+		udp: None,
 	};
 	mutate(&mut guard);
 	root.guard = Some(guard);
@@ -0,0 +1,386 @@
+mod common;
+
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rivet_guard_core::l4::{run_tcp_listener, run_udp_listener};
+use rivet_guard_core::{ActorActivityReporter, L4Protocol, L4RoutingFn, L4Target, RouteTarget};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use tokio::sync::watch;
+use uuid::Uuid;
+
+use common::init_tracing;
+
+// Routing function that routes every connection to the given address and records the requests it gets
+fn create_l4_routing_fn(
+	addr: SocketAddr,
+	requests: Arc<Mutex<Vec<(L4Protocol, u16, Option<String>)>>>,
+) -> L4RoutingFn {
+	create_l4_actor_routing_fn(addr, None, requests)
+}
+
+fn create_l4_actor_routing_fn(
+	addr: SocketAddr,
+	actor_id: Option<Uuid>,
+	requests: Arc<Mutex<Vec<(L4Protocol, u16, Option<String>)>>>,
+) -> L4RoutingFn {
+	Arc::new(move |protocol: L4Protocol, port: u16, sni: Option<&str>| {
+		requests
+			.lock()
+			.unwrap()
+			.push((protocol, port, sni.map(|sni| sni.to_string())));
+
+		Box::pin(async move {
+			Ok(Some(L4Target {
+				target: RouteTarget {
+					actor_id,
+					server_id: None,
+					host: addr.ip().to_string(),
+					port: addr.port(),
+					path: String::new(),
+				},
+				terminate_tls: false,
+			}))
+		})
+	})
+}
+
+// Activity reporter that records the actors it reports
+fn create_actor_activity(activity: Arc<Mutex<Vec<Uuid>>>) -> ActorActivityReporter {
+	ActorActivityReporter::new(Arc::new(move |actor_id: &Uuid| {
+		activity.lock().unwrap().push(*actor_id);
+
+		Box::pin(async move { Ok(()) })
+	}))
+}
+
+async fn start_tcp_echo_server() -> SocketAddr {
+	let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let addr = listener.local_addr().unwrap();
+
+	tokio::spawn(async move {
+		loop {
+			let (mut stream, _) = listener.accept().await.unwrap();
+
+			tokio::spawn(async move {
+				let (mut read, mut write) = stream.split();
+				let _ = tokio::io::copy(&mut read, &mut write).await;
+			});
+		}
+	});
+
+	addr
+}
+
+#[tokio::test]
+async fn test_tcp_proxy() {
+	init_tracing();
+
+	let echo_addr = start_tcp_echo_server().await;
+	let requests = Arc::new(Mutex::new(Vec::new()));
+
+	let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let guard_addr = listener.local_addr().unwrap();
+	let (_shutdown_tx, shutdown_rx) = watch::channel(false);
+	let handle = tokio::spawn(run_tcp_listener(
+		listener,
+		create_l4_routing_fn(echo_addr, requests.clone()),
+		None,
+		None,
+		shutdown_rx,
+	));
+
+	let mut stream = TcpStream::connect(guard_addr).await.unwrap();
+	stream.write_all(b"hello guard").await.unwrap();
+
+	let mut buf = [0u8; 11];
+	tokio::time::timeout(Duration::from_secs(5), stream.read_exact(&mut buf))
+		.await
+		.unwrap()
+		.unwrap();
+	assert_eq!(&buf, b"hello guard");
+
+	// Plain TCP connections have no SNI
+	let requests = requests.lock().unwrap().clone();
+	assert_eq!(requests, vec![(L4Protocol::Tcp, guard_addr.port(), None)]);
+
+	handle.abort();
+}
+
+#[tokio::test]
+async fn test_tcp_proxy_routes_with_sni() {
+	init_tracing();
+
+	let _ = rustls::crypto::ring::default_provider().install_default();
+
+	let echo_addr = start_tcp_echo_server().await;
+	let requests = Arc::new(Mutex::new(Vec::new()));
+
+	let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let guard_addr = listener.local_addr().unwrap();
+	let (_shutdown_tx, shutdown_rx) = watch::channel(false);
+	let handle = tokio::spawn(run_tcp_listener(
+		listener,
+		create_l4_routing_fn(echo_addr, requests.clone()),
+		None,
+		None,
+		shutdown_rx,
+	));
+
+	// Start a TLS handshake. It never completes since the echo server does not speak TLS, but the client
+	// hello is enough for guard to route the connection.
+	let client_config = rustls::ClientConfig::builder()
+		.with_root_certificates(rustls::RootCertStore::empty())
+		.with_no_client_auth();
+	let connector = tokio_rustls::TlsConnector::from(Arc::new(client_config));
+	let stream = TcpStream::connect(guard_addr).await.unwrap();
+	let server_name = rustls::pki_types::ServerName::try_from("Actor.Example.com").unwrap();
+	let _ = tokio::time::timeout(
+		Duration::from_secs(2),
+		connector.connect(server_name, stream),
+	)
+	.await;
+
+	let requests = requests.lock().unwrap().clone();
+	assert_eq!(
+		requests,
+		vec![(
+			L4Protocol::Tcp,
+			guard_addr.port(),
+			Some("actor.example.com".to_string())
+		)]
+	);
+
+	handle.abort();
+}
+
+#[tokio::test]
+async fn test_udp_proxy_session_affinity() {
+	init_tracing();
+
+	// Set up an upstream that replies with the address each datagram came from
+	let upstream = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+	let upstream_addr = upstream.local_addr().unwrap();
+	tokio::spawn(async move {
+		let mut buf = [0u8; 1024];
+		loop {
+			let (_, addr) = upstream.recv_from(&mut buf).await.unwrap();
+			upstream
+				.send_to(addr.to_string().as_bytes(), addr)
+				.await
+				.unwrap();
+		}
+	});
+
+	let requests = Arc::new(Mutex::new(Vec::new()));
+	let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+	let guard_addr = socket.local_addr().unwrap();
+	let (_shutdown_tx, shutdown_rx) = watch::channel(false);
+	let handle = tokio::spawn(run_udp_listener(
+		socket,
+		create_l4_routing_fn(upstream_addr, requests.clone()),
+		None,
+		shutdown_rx,
+	));
+
+	let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+	client.connect(guard_addr).await.unwrap();
+
+	let mut upstream_sources = Vec::new();
+	for _ in 0..3 {
+		client.send(b"ping").await.unwrap();
+
+		let mut buf = [0u8; 1024];
+		let n = tokio::time::timeout(Duration::from_secs(5), client.recv(&mut buf))
+			.await
+			.unwrap()
+			.unwrap();
+		upstream_sources.push(String::from_utf8_lossy(&buf[..n]).to_string());
+	}
+
+	// All datagrams from the same client go through the same session
+	assert!(upstream_sources.windows(2).all(|w| w[0] == w[1]));
+	assert_eq!(requests.lock().unwrap().len(), 1);
+
+	// A different client gets its own session
+	let other_client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+	other_client.connect(guard_addr).await.unwrap();
+	other_client.send(b"ping").await.unwrap();
+
+	let mut buf = [0u8; 1024];
+	let n = tokio::time::timeout(Duration::from_secs(5), other_client.recv(&mut buf))
+		.await
+		.unwrap()
+		.unwrap();
+	assert_ne!(String::from_utf8_lossy(&buf[..n]), upstream_sources[0]);
+	assert_eq!(requests.lock().unwrap().len(), 2);
+
+	handle.abort();
+}
+
+#[tokio::test]
+async fn test_tcp_proxy_records_actor_activity() {
+	init_tracing();
+
+	let echo_addr = start_tcp_echo_server().await;
+	let actor_id = Uuid::new_v4();
+	let activity = Arc::new(Mutex::new(Vec::new()));
+
+	let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let guard_addr = listener.local_addr().unwrap();
+	let (_shutdown_tx, shutdown_rx) = watch::channel(false);
+	let handle = tokio::spawn(run_tcp_listener(
+		listener,
+		create_l4_actor_routing_fn(echo_addr, Some(actor_id), Default::default()),
+		Some(create_actor_activity(activity.clone())),
+		None,
+		shutdown_rx,
+	));
+
+	let mut stream = TcpStream::connect(guard_addr).await.unwrap();
+	stream.write_all(b"ping").await.unwrap();
+
+	let mut buf = [0u8; 4];
+	tokio::time::timeout(Duration::from_secs(5), stream.read_exact(&mut buf))
+		.await
+		.unwrap()
+		.unwrap();
+
+	// Reported once the connection is proxied so the actor is not stopped for being idle
+	assert_eq!(*activity.lock().unwrap(), vec![actor_id]);
+
+	handle.abort();
+}
+
+#[tokio::test]
+async fn test_tcp_proxy_dedups_actor_activity() {
+	init_tracing();
+
+	let echo_addr = start_tcp_echo_server().await;
+	let actor_id = Uuid::new_v4();
+	let activity = Arc::new(Mutex::new(Vec::new()));
+
+	let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let guard_addr = listener.local_addr().unwrap();
+	let (_shutdown_tx, shutdown_rx) = watch::channel(false);
+	let handle = tokio::spawn(run_tcp_listener(
+		listener,
+		create_l4_actor_routing_fn(echo_addr, Some(actor_id), Default::default()),
+		Some(create_actor_activity(activity.clone())),
+		None,
+		shutdown_rx,
+	));
+
+	// Two concurrent connections to the same actor
+	let mut streams = Vec::new();
+	for _ in 0..2 {
+		let mut stream = TcpStream::connect(guard_addr).await.unwrap();
+		stream.write_all(b"ping").await.unwrap();
+
+		let mut buf = [0u8; 4];
+		tokio::time::timeout(Duration::from_secs(5), stream.read_exact(&mut buf))
+			.await
+			.unwrap()
+			.unwrap();
+
+		streams.push(stream);
+	}
+
+	// Only reported once within the activity interval
+	assert_eq!(*activity.lock().unwrap(), vec![actor_id]);
+
+	handle.abort();
+}
+
+#[tokio::test]
+async fn test_udp_proxy_records_actor_activity() {
+	init_tracing();
+
+	let upstream = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+	let upstream_addr = upstream.local_addr().unwrap();
+	tokio::spawn(async move {
+		let mut buf = [0u8; 1024];
+		loop {
+			let (n, addr) = upstream.recv_from(&mut buf).await.unwrap();
+			upstream.send_to(&buf[..n], addr).await.unwrap();
+		}
+	});
+
+	let actor_id = Uuid::new_v4();
+	let activity = Arc::new(Mutex::new(Vec::new()));
+
+	let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+	let guard_addr = socket.local_addr().unwrap();
+	let (_shutdown_tx, shutdown_rx) = watch::channel(false);
+	let handle = tokio::spawn(run_udp_listener(
+		socket,
+		create_l4_actor_routing_fn(upstream_addr, Some(actor_id), Default::default()),
+		Some(create_actor_activity(activity.clone())),
+		shutdown_rx,
+	));
+
+	let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+	client.connect(guard_addr).await.unwrap();
+	client.send(b"ping").await.unwrap();
+
+	let mut buf = [0u8; 1024];
+	tokio::time::timeout(Duration::from_secs(5), client.recv(&mut buf))
+		.await
+		.unwrap()
+		.unwrap();
+
+	assert_eq!(*activity.lock().unwrap(), vec![actor_id]);
+
+	handle.abort();
+}
+
+#[tokio::test]
+async fn test_tcp_listener_drains_on_shutdown() {
+	init_tracing();
+
+	let echo_addr = start_tcp_echo_server().await;
+
+	let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let guard_addr = listener.local_addr().unwrap();
+	let (shutdown_tx, shutdown_rx) = watch::channel(false);
+	let handle = tokio::spawn(run_tcp_listener(
+		listener,
+		create_l4_routing_fn(echo_addr, Default::default()),
+		None,
+		None,
+		shutdown_rx,
+	));
+
+	let mut stream = TcpStream::connect(guard_addr).await.unwrap();
+	stream.write_all(b"ping").await.unwrap();
+
+	let mut buf = [0u8; 4];
+	tokio::time::timeout(Duration::from_secs(5), stream.read_exact(&mut buf))
+		.await
+		.unwrap()
+		.unwrap();
+
+	shutdown_tx.send(true).unwrap();
+	tokio::time::sleep(Duration::from_millis(100)).await;
+
+	// No longer accepts connections
+	assert!(TcpStream::connect(guard_addr).await.is_err());
+
+	// Open connections are still proxied
+	stream.write_all(b"pong").await.unwrap();
+	tokio::time::timeout(Duration::from_secs(5), stream.read_exact(&mut buf))
+		.await
+		.unwrap()
+		.unwrap();
+	assert_eq!(&buf, b"pong");
+	assert!(!handle.is_finished());
+
+	// Stops once the last connection closes
+	drop(stream);
+	tokio::time::timeout(Duration::from_secs(5), handle)
+		.await
+		.unwrap()
+		.unwrap();
+}
@@ -0,0 +1,264 @@
+use std::collections::HashSet;
+
+use hyper::HeaderMap;
+use rivet_guard_core::load_balancer::LoadBalancer;
+use rivet_guard_core::proxy_service::{RouteConfig, RoutingTimeout, TrafficSplit};
+use rivet_guard_core::{HashKey, LoadBalancingStrategy, RouteTarget};
+
+fn route_config(target_count: u16, load_balancing: LoadBalancingStrategy) -> RouteConfig {
+	RouteConfig {
+		targets: (0..target_count)
+			.map(|i| RouteTarget {
+				actor_id: None,
+				server_id: None,
+				host: "127.0.0.1".to_string(),
+				port: 8000 + i,
+				path: "/".to_string(),
+			})
+			.collect(),
+		timeout: RoutingTimeout {
+			routing_timeout: 10,
+		},
+		load_balancing,
+		middleware: None,
+		split: None,
+		depends_on_request: false,
+	}
+}
+
+fn with_split(mut route: RouteConfig, target_count: u16, weight: u8) -> RouteConfig {
+	route.split = Some(TrafficSplit {
+		targets: (0..target_count)
+			.map(|i| RouteTarget {
+				actor_id: None,
+				server_id: None,
+				host: "127.0.0.1".to_string(),
+				port: 9000 + i,
+				path: "/".to_string(),
+			})
+			.collect(),
+		weight,
+	});
+	route
+}
+
+async fn choose_port(
+	lb: &LoadBalancer,
+	route: &RouteConfig,
+	path: &str,
+	headers: &HeaderMap,
+) -> u16 {
+	lb.choose_target("example.com", path, headers, route)
+		.await
+		.unwrap()
+		.port
+}
+
+#[tokio::test]
+async fn test_round_robin() {
+	let lb = LoadBalancer::new();
+	let route = route_config(3, LoadBalancingStrategy::RoundRobin);
+	let headers = HeaderMap::new();
+
+	let mut ports = Vec::new();
+	for _ in 0..6 {
+		ports.push(choose_port(&lb, &route, "/", &headers).await);
+	}
+
+	// Every target is picked once per cycle, in the same order
+	assert_eq!(ports[..3], ports[3..]);
+	assert_eq!(ports.iter().collect::<HashSet<_>>().len(), 3);
+}
+
+#[tokio::test]
+async fn test_least_in_flight() {
+	let lb = LoadBalancer::new();
+	let route = route_config(3, LoadBalancingStrategy::LeastInFlight);
+	let headers = HeaderMap::new();
+
+	// Load up every target except the last one
+	lb.acquire(&route.targets[0]).await;
+	lb.acquire(&route.targets[1]).await;
+
+	for _ in 0..5 {
+		assert_eq!(choose_port(&lb, &route, "/", &headers).await, 8002);
+	}
+
+	// Once the first target is released it is the least loaded one
+	lb.release(&route.targets[0]).await;
+	lb.acquire(&route.targets[2]).await;
+	lb.acquire(&route.targets[2]).await;
+
+	for _ in 0..5 {
+		assert_eq!(choose_port(&lb, &route, "/", &headers).await, 8000);
+	}
+}
+
+#[tokio::test]
+async fn test_consistent_hash_affinity() {
+	let lb = LoadBalancer::new();
+
+	// Header
+	let route = route_config(
+		8,
+		LoadBalancingStrategy::ConsistentHash(HashKey::Header("x-session-id".to_string())),
+	);
+	let mut headers = HeaderMap::new();
+	headers.insert("x-session-id", "session-a".parse().unwrap());
+	let port = choose_port(&lb, &route, "/", &headers).await;
+	for _ in 0..10 {
+		assert_eq!(choose_port(&lb, &route, "/", &headers).await, port);
+	}
+
+	// Different sessions are spread out over the targets
+	let mut ports = HashSet::new();
+	for i in 0..64 {
+		let mut headers = HeaderMap::new();
+		headers.insert("x-session-id", format!("session-{i}").parse().unwrap());
+		ports.insert(choose_port(&lb, &route, "/", &headers).await);
+	}
+	assert!(ports.len() > 1);
+
+	// Cookie
+	let route = route_config(
+		8,
+		LoadBalancingStrategy::ConsistentHash(HashKey::Cookie("session".to_string())),
+	);
+	let mut headers = HeaderMap::new();
+	headers.insert("cookie", "theme=dark; session=abc".parse().unwrap());
+	let port = choose_port(&lb, &route, "/", &headers).await;
+	let mut headers = HeaderMap::new();
+	headers.insert("cookie", "session=abc; theme=light".parse().unwrap());
+	assert_eq!(choose_port(&lb, &route, "/", &headers).await, port);
+
+	// Query
+	let route = route_config(
+		8,
+		LoadBalancingStrategy::ConsistentHash(HashKey::Query("room".to_string())),
+	);
+	let headers = HeaderMap::new();
+	let port = choose_port(&lb, &route, "/join?room=42", &headers).await;
+	assert_eq!(
+		choose_port(&lb, &route, "/leave?user=1&room=42", &headers).await,
+		port
+	);
+}
+
+#[tokio::test]
+async fn test_consistent_hash_stable_when_targets_removed() {
+	let lb = LoadBalancer::new();
+	let route = route_config(
+		8,
+		LoadBalancingStrategy::ConsistentHash(HashKey::Header("x-session-id".to_string())),
+	);
+
+	let mut assignments = Vec::new();
+	for i in 0..64 {
+		let mut headers = HeaderMap::new();
+		headers.insert("x-session-id", format!("session-{i}").parse().unwrap());
+		assignments.push((
+			headers.clone(),
+			choose_port(&lb, &route, "/", &headers).await,
+		));
+	}
+
+	// Remove one target, only the sessions on that target should move
+	let removed_port = 8003;
+	let mut smaller_route = route.clone();
+	smaller_route.targets.retain(|t| t.port != removed_port);
+
+	for (headers, port) in assignments {
+		let new_port = choose_port(&lb, &smaller_route, "/", &headers).await;
+		if port != removed_port {
+			assert_eq!(new_port, port);
+		}
+	}
+}
+
+#[tokio::test]
+async fn test_outlier_ejection() {
+	let lb = LoadBalancer::new();
+	let route = route_config(2, LoadBalancingStrategy::RoundRobin);
+	let headers = HeaderMap::new();
+
+	// Failures that are not in a row do not eject the target
+	lb.record_connect_failure(&route.targets[0]).await;
+	lb.record_connect_failure(&route.targets[0]).await;
+	lb.record_connect_success(&route.targets[0]).await;
+	lb.record_connect_failure(&route.targets[0]).await;
+
+	let mut ports = HashSet::new();
+	for _ in 0..4 {
+		ports.insert(choose_port(&lb, &route, "/", &headers).await);
+	}
+	assert_eq!(ports.len(), 2);
+
+	// Enough failures in a row eject it
+	lb.record_connect_failure(&route.targets[0]).await;
+	lb.record_connect_failure(&route.targets[0]).await;
+
+	for _ in 0..4 {
+		assert_eq!(choose_port(&lb, &route, "/", &headers).await, 8001);
+	}
+
+	// If every target is ejected, requests still go through to all of them
+	for _ in 0..3 {
+		lb.record_connect_failure(&route.targets[1]).await;
+	}
+
+	let mut ports = HashSet::new();
+	for _ in 0..4 {
+		ports.insert(choose_port(&lb, &route, "/", &headers).await);
+	}
+	assert_eq!(ports.len(), 2);
+}
+
+#[tokio::test]
+async fn test_traffic_split() {
+	let lb = LoadBalancer::new();
+	let headers = HeaderMap::new();
+
+	// Both groups get requests
+	let route = with_split(route_config(2, LoadBalancingStrategy::Random), 2, 50);
+	let mut canary = 0;
+	for _ in 0..200 {
+		if choose_port(&lb, &route, "/", &headers).await >= 9000 {
+			canary += 1;
+		}
+	}
+	assert!(canary > 0 && canary < 200);
+
+	// Weights of 0 and 100 send everything to one group
+	let route = with_split(route_config(2, LoadBalancingStrategy::Random), 2, 0);
+	for _ in 0..20 {
+		assert!(choose_port(&lb, &route, "/", &headers).await < 9000);
+	}
+	let route = with_split(route_config(2, LoadBalancingStrategy::Random), 2, 100);
+	for _ in 0..20 {
+		assert!(choose_port(&lb, &route, "/", &headers).await >= 9000);
+	}
+
+	// Empty groups are skipped
+	let route = with_split(route_config(0, LoadBalancingStrategy::Random), 2, 0);
+	assert!(choose_port(&lb, &route, "/", &headers).await >= 9000);
+	let route = with_split(route_config(2, LoadBalancingStrategy::Random), 0, 100);
+	assert!(choose_port(&lb, &route, "/", &headers).await < 9000);
+
+	// With consistent hashing a session stays in its group
+	let route = with_split(
+		route_config(
+			4,
+			LoadBalancingStrategy::ConsistentHash(HashKey::Header("x-session-id".to_string())),
+		),
+		4,
+		50,
+	);
+	for i in 0..16 {
+		let mut headers = HeaderMap::new();
+		headers.insert("x-session-id", format!("session-{i}").parse().unwrap());
+		let port = choose_port(&lb, &route, "/", &headers).await;
+		for _ in 0..5 {
+			assert_eq!(choose_port(&lb, &route, "/", &headers).await, port);
+		}
+	}
+}
@@ -615,3 +615,86 @@ async fn test_post_requests_with_bodies() {
 	let last_request = test_server.last_request().unwrap();
 	assert_eq!(last_request.method, "POST");
 }
+
+#[tokio::test]
+async fn test_streaming_response_body() {
+	use http_body_util::BodyExt;
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+	init_tracing();
+
+	// Set up an upstream that sends a chunked response and holds back the second chunk until released
+	let upstream = TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let upstream_addr = upstream.local_addr().unwrap();
+	let (release_tx, release_rx) = tokio::sync::oneshot::channel::<()>();
+
+	tokio::spawn(async move {
+		let (mut stream, _) = upstream.accept().await.unwrap();
+
+		// Read the request headers
+		let mut buf = Vec::new();
+		while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+			let mut chunk = [0u8; 1024];
+			let n = stream.read(&mut chunk).await.unwrap();
+			buf.extend_from_slice(&chunk[..n]);
+		}
+
+		stream
+			.write_all(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nfirst\r\n")
+			.await
+			.unwrap();
+		stream.flush().await.unwrap();
+
+		let _ = release_rx.await;
+
+		stream.write_all(b"6\r\nsecond\r\n0\r\n\r\n").await.unwrap();
+		stream.flush().await.unwrap();
+	});
+
+	let routing_fn: rivet_guard_core::proxy_service::RoutingFn = Arc::new(
+		move |_hostname: &str,
+		      path: &str,
+		      _port_type: rivet_guard_core::proxy_service::PortType| {
+			Box::pin(async move {
+				let route_target = RouteTarget {
+					actor_id: Some(Uuid::new_v4()),
+					server_id: Some(Uuid::new_v4()),
+					host: upstream_addr.ip().to_string(),
+					port: upstream_addr.port(),
+					path: path.to_string(),
+				};
+
+				Ok(RoutingResponse::Ok(RouteConfig {
+					targets: vec![route_target],
+					timeout: RoutingTimeout { routing_timeout: 5 },
+				}))
+			})
+		},
+	);
+
+	let config = create_test_config(|_| {});
+	let (guard_addr, _shutdown) = start_guard(config, routing_fn).await;
+
+	let uri = format!("http://{}/stream", guard_addr);
+	let response = make_request(&uri, "example.com", Method::GET)
+		.await
+		.unwrap();
+	assert_eq!(response.status(), StatusCode::OK);
+
+	// The first chunk must arrive before the upstream finishes the response
+	let mut body = response.into_body();
+	let first = tokio::time::timeout(Duration::from_secs(5), body.frame())
+		.await
+		.expect("first chunk was not streamed")
+		.unwrap()
+		.unwrap()
+		.into_data()
+		.unwrap();
+	assert_eq!(first, Bytes::from("first"));
+
+	// Release the rest of the response
+	release_tx.send(()).unwrap();
+
+	let rest = body.collect().await.unwrap().to_bytes();
+	assert_eq!(rest, Bytes::from("second"));
+}
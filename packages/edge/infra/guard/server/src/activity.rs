@@ -0,0 +1,89 @@
+use std::sync::Arc;
+
+use chirp_workflow::prelude::*;
+use fdb_util::{FormalKey, SERIALIZABLE};
+use foundationdb as fdb;
+use rivet_guard_core::proxy_service::ActorActivityFn;
+use uuid::Uuid;
+
+/// Creates a function that records when an actor last received a request. Pegboard stops actors with an
+/// idle timeout once this is older than the timeout. Actors without an idle timeout are skipped.
+pub fn create_actor_activity_function(ctx: StandaloneCtx) -> ActorActivityFn {
+	Arc::new(move |actor_id: &Uuid| {
+		let ctx = ctx.clone();
+		let actor_id = *actor_id;
+
+		Box::pin(async move {
+			if !has_idle_timeout(&ctx, actor_id).await? {
+				return Ok(());
+			}
+
+			let now = util::timestamp::now();
+
+			ctx.fdb()
+				.await?
+				.run(|tx, _mc| async move {
+					// Proxied ports are cleared when the actor is destroyed. Don't write the key for
+					// destroyed actors since nothing would clear it again.
+					let proxied_ports_key = pegboard::keys::actor::ProxiedPortsKey::new(actor_id);
+					if tx
+						.get(
+							&pegboard::keys::subspace().pack(&proxied_ports_key),
+							SERIALIZABLE,
+						)
+						.await?
+						.is_none()
+					{
+						return Ok(());
+					}
+
+					let last_request_ts_key =
+						pegboard::keys::actor::LastRequestTsKey::new(actor_id);
+
+					tx.set(
+						&pegboard::keys::subspace().pack(&last_request_ts_key),
+						&last_request_ts_key
+							.serialize(now)
+							.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
+					);
+
+					Ok(())
+				})
+				.custom_instrument(tracing::info_span!("actor_last_request_ts_tx"))
+				.await?;
+
+			Ok(())
+		})
+	})
+}
+
+/// Whether the actor's lifecycle has an idle timeout. This never changes after the actor is created.
+async fn has_idle_timeout(ctx: &StandaloneCtx, actor_id: Uuid) -> GlobalResult<bool> {
+	let has_idle_timeout = ctx
+		.cache()
+		.ttl(util::duration::hours(1))
+		.fetch_one_json("guard.actor_has_idle_timeout", actor_id, {
+			let ctx = ctx.clone();
+			move |mut cache, actor_id| {
+				let ctx = ctx.clone();
+				async move {
+					let actors_res = ctx
+						.op(pegboard::ops::actor::get::Input {
+							actor_ids: vec![actor_id],
+							endpoint_type: None,
+							allow_errors: false,
+						})
+						.await?;
+
+					if let Some(actor) = actors_res.actors.first() {
+						cache.resolve(&actor_id, actor.lifecycle.idle_timeout_ms.is_some());
+					}
+
+					Ok(cache)
+				}
+			}
+		})
+		.await?;
+
+	Ok(has_idle_timeout.unwrap_or_default())
+}
@@ -1,4 +1,6 @@
+pub mod activity;
 pub mod hyper_imports;
+pub mod middleware;
 pub mod routing;
 
 // Export actor routing function from pegboard for testing
@@ -1,14 +1,12 @@
-use std::{path::PathBuf, sync::Arc, time::Duration};
+use std::{path::PathBuf, time::Duration};
 
 use chirp_workflow::prelude::*;
 use clap::Parser;
 use global_error::GlobalResult;
-use rivet_guard_core::proxy_service::{
-	MaxInFlightConfig, MiddlewareConfig, MiddlewareResponse, RateLimitConfig, RetryConfig,
-	TimeoutConfig,
-};
 use tokio::signal;
 
+mod activity;
+mod middleware;
 mod routing;
 mod tls;
 
@@ -72,8 +70,17 @@ async fn main_inner() -> GlobalResult<()> {
 	// Create a routing function that has access to config and pool
 	let routing_fn = routing::create_routing_function(ctx.clone());
 
+	// Create a routing function for TCP and UDP connections
+	let l4_routing_fn = routing::create_l4_routing_function(ctx.clone());
+
 	// Create a middleware function
-	let middleware_fn = create_middleware_function(ctx.clone());
+	let middleware_fn = middleware::create_middleware_function(ctx.clone());
+
+	// Create a rate limit function for rate limits shared between guard instances
+	let distributed_rate_limit_fn = middleware::create_distributed_rate_limit_function(ctx.clone());
+
+	// Create a function that records actor activity for stopping idle actors
+	let actor_activity_fn = activity::create_actor_activity_function(ctx.clone());
 
 	// Create certificate resolver for TLS
 	let cert_resolver = tls::create_cert_resolver(&ctx).await?;
@@ -91,7 +98,7 @@ async fn main_inner() -> GlobalResult<()> {
 	// Start the server
 	tracing::info!("starting proxy server");
 	tokio::select! {
-		res = rivet_guard_core::run_server(config, routing_fn, middleware_fn, cert_resolver, clickhouse_inserter) => {
+		res = rivet_guard_core::run_server(config, routing_fn, middleware_fn, Some(distributed_rate_limit_fn), Some(actor_activity_fn), l4_routing_fn, cert_resolver, clickhouse_inserter) => {
 			if let Err(err) = res {
 				tracing::error!(?err, "Server error");
 			}
@@ -106,42 +113,3 @@ async fn main_inner() -> GlobalResult<()> {
 
 	Ok(())
 }
-
-/// Creates a middleware function that can use config and pools
-fn create_middleware_function(
-	ctx: StandaloneCtx,
-) -> Arc<
-	dyn for<'a> Fn(
-			&'a uuid::Uuid,
-		) -> futures::future::BoxFuture<'a, GlobalResult<MiddlewareResponse>>
-		+ Send
-		+ Sync,
-> {
-	Arc::new(move |_actor_id: &uuid::Uuid| {
-		let _ctx = ctx.clone();
-
-		Box::pin(async move {
-			// In a real implementation, you would look up actor-specific middleware settings
-			// For now, we'll just return a standard configuration
-
-			// Create middleware config based on the actor ID
-			// This could be fetched from a database in a real implementation
-			Ok(MiddlewareResponse::Ok(MiddlewareConfig {
-				rate_limit: RateLimitConfig {
-					requests: 100, // 100 requests
-					period: 60,    // per 60 seconds
-				},
-				max_in_flight: MaxInFlightConfig {
-					amount: 20, // 20 concurrent requests
-				},
-				retry: RetryConfig {
-					max_attempts: 7,
-					initial_interval: 150,
-				},
-				timeout: TimeoutConfig {
-					request_timeout: 30, // 30 seconds for requests
-				},
-			}))
-		})
-	})
-}
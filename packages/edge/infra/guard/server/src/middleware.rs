@@ -0,0 +1,193 @@
+use std::{net::IpAddr, sync::Arc};
+
+use chirp_workflow::prelude::*;
+use global_error::GlobalResult;
+use rivet_guard_core::proxy_service::{
+	DistributedRateLimitFn, MaxInFlightConfig, MiddlewareConfig, MiddlewareFn, MiddlewareResponse,
+	RateLimitConfig, RetryConfig, TimeoutConfig,
+};
+use uuid::Uuid;
+
+/// Converts a stored middleware policy to guard's middleware config. Unset fields use the defaults.
+pub fn convert_middleware_policy(policy: &route::types::MiddlewarePolicy) -> MiddlewareConfig {
+	let mut config = MiddlewareConfig::default();
+
+	if let Some(rate_limit) = &policy.rate_limit {
+		config.rate_limit = RateLimitConfig {
+			requests: rate_limit.requests,
+			period: rate_limit.period,
+			distributed: rate_limit.distributed,
+		};
+	}
+	if let Some(max_in_flight) = policy.max_in_flight {
+		config.max_in_flight = MaxInFlightConfig {
+			amount: max_in_flight as usize,
+		};
+	}
+	if let Some(retry) = &policy.retry {
+		config.retry = RetryConfig {
+			max_attempts: retry.max_attempts,
+			initial_interval: retry.initial_interval,
+		};
+	}
+	if let Some(request_timeout) = policy.request_timeout {
+		config.timeout = TimeoutConfig { request_timeout };
+	}
+
+	config
+}
+
+/// Creates a middleware function that applies the middleware policy of the actor's environment
+pub fn create_middleware_function(ctx: StandaloneCtx) -> MiddlewareFn {
+	Arc::new(move |actor_id: &Uuid| {
+		let ctx = ctx.clone();
+		let actor_id = *actor_id;
+
+		Box::pin(async move {
+			let policy = ctx
+				.cache()
+				.ttl(util::duration::seconds(15))
+				.fetch_one_json("guard.actor_middleware", actor_id, {
+					let ctx = ctx.clone();
+					move |mut cache, actor_id| {
+						let ctx = ctx.clone();
+						async move {
+							if let Some(policy) =
+								get_actor_middleware_policy(&ctx, actor_id).await?
+							{
+								cache.resolve(&actor_id, policy);
+							}
+
+							Ok(cache)
+						}
+					}
+				})
+				.await?;
+
+			match policy {
+				Some(policy) => Ok(MiddlewareResponse::Ok(convert_middleware_policy(&policy))),
+				None => Ok(MiddlewareResponse::NotFound),
+			}
+		})
+	})
+}
+
+/// Returns the middleware policy of the actor's environment, or `None` if the actor does not exist.
+async fn get_actor_middleware_policy(
+	ctx: &StandaloneCtx,
+	actor_id: Uuid,
+) -> GlobalResult<Option<route::types::MiddlewarePolicy>> {
+	let actors_res = ctx
+		.op(pegboard::ops::actor::get::Input {
+			actor_ids: vec![actor_id],
+			endpoint_type: None,
+			allow_errors: false,
+		})
+		.await?;
+	let Some(actor) = actors_res.actors.first() else {
+		return Ok(None);
+	};
+
+	let middleware_res = ctx
+		.op(route::ops::middleware::get::Input {
+			namespace_id: actor.env_id,
+		})
+		.await?;
+
+	Ok(Some(middleware_res.middleware))
+}
+
+/// Creates a rate limit function that counts requests in the shared cache so the limit applies across all
+/// guard instances
+pub fn create_distributed_rate_limit_function(ctx: StandaloneCtx) -> DistributedRateLimitFn {
+	Arc::new(
+		move |actor_id: &Uuid, ip_addr: IpAddr, rate_limit: &RateLimitConfig| {
+			let ctx = ctx.clone();
+			let actor_id = *actor_id;
+			let requests = rate_limit.requests;
+			let period = rate_limit.period.max(1);
+
+			Box::pin(async move {
+				// The cache's rate limiter takes the count in hits per minute and scales it to the bucket
+				// duration
+				let results = ctx
+					.cache_handle()
+					.rate_limit(
+						&("guard", actor_id),
+						ip_addr.to_string(),
+						rivet_cache::RateLimitConfig {
+							key: "guard".to_string(),
+							buckets: vec![rivet_cache::RateLimitBucketConfig {
+								count: (requests * 60).div_ceil(period),
+								bucket_duration_ms: util::duration::seconds(period as i64),
+							}],
+						},
+					)
+					.await;
+
+				Ok(results.iter().all(|result| result.is_valid))
+			})
+		},
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use route::types::{MiddlewarePolicy, RateLimitPolicy, RetryPolicy};
+
+	use super::*;
+
+	#[test]
+	fn convert_empty_policy_uses_defaults() {
+		let config = convert_middleware_policy(&MiddlewarePolicy::default());
+		let default = MiddlewareConfig::default();
+
+		assert_eq!(config.rate_limit.requests, default.rate_limit.requests);
+		assert_eq!(config.rate_limit.period, default.rate_limit.period);
+		assert_eq!(
+			config.rate_limit.distributed,
+			default.rate_limit.distributed
+		);
+		assert_eq!(config.max_in_flight.amount, default.max_in_flight.amount);
+		assert_eq!(config.retry.max_attempts, default.retry.max_attempts);
+		assert_eq!(
+			config.retry.initial_interval,
+			default.retry.initial_interval
+		);
+		assert_eq!(
+			config.timeout.request_timeout,
+			default.timeout.request_timeout
+		);
+	}
+
+	#[test]
+	fn convert_policy_overrides_set_fields() {
+		let config = convert_middleware_policy(&MiddlewarePolicy {
+			rate_limit: Some(RateLimitPolicy {
+				requests: 5,
+				period: 10,
+				distributed: true,
+			}),
+			max_in_flight: None,
+			retry: Some(RetryPolicy {
+				max_attempts: 2,
+				initial_interval: 25,
+			}),
+			request_timeout: None,
+		});
+		let default = MiddlewareConfig::default();
+
+		assert_eq!(config.rate_limit.requests, 5);
+		assert_eq!(config.rate_limit.period, 10);
+		assert!(config.rate_limit.distributed);
+		assert_eq!(config.retry.max_attempts, 2);
+		assert_eq!(config.retry.initial_interval, 25);
+
+		// Unset fields keep the defaults
+		assert_eq!(config.max_in_flight.amount, default.max_in_flight.amount);
+		assert_eq!(
+			config.timeout.request_timeout,
+			default.timeout.request_timeout
+		);
+	}
+}
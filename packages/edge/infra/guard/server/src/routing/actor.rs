@@ -3,14 +3,19 @@ use std::time::Duration;
 use chirp_workflow::prelude::*;
 use cluster::types::GuardPublicHostname;
 use fdb_util::{FormalKey, SNAPSHOT};
-use foundationdb::{self as fdb};
+use foundationdb::{self as fdb, options::StreamingMode};
+use futures::TryStreamExt;
 use global_error::GlobalResult;
-use pegboard::types::EndpointType;
+use pegboard::types::{EndpointType, GameGuardProtocol};
 use pegboard::util::build_actor_hostname_and_path_regex;
 use rivet_config::config::AccessKind;
 use rivet_guard_core::proxy_service::{RouteConfig, RouteTarget, RoutingOutput, RoutingTimeout};
+use rivet_guard_core::{L4Protocol, L4Target, LoadBalancingStrategy};
 use uuid::Uuid;
 
+/// How long to wait for an actor stopped for being idle to start again.
+const ACTOR_WAKE_TIMEOUT: Duration = Duration::from_secs(60);
+
 /// Route requests to actor services based on hostname and path
 #[tracing::instrument(skip_all)]
 pub async fn route_actor_request(
@@ -164,11 +169,139 @@ async fn try_route_with_endpoint_type(
 			timeout: RoutingTimeout {
 				routing_timeout: 10,
 			},
+			load_balancing: LoadBalancingStrategy::default(),
+			middleware: None,
+			split: None,
+			depends_on_request: false,
 		}))),
 		None => Ok(None),
 	}
 }
 
+/// Route L4 connections to actor services based on the ingress port they came in on. TLS connections
+/// are first routed by SNI since TCP and TCP+TLS ports are allocated from the same port range.
+#[tracing::instrument(skip_all, fields(?protocol, ?port, ?sni))]
+pub async fn route_actor_connection(
+	ctx: &StandaloneCtx,
+	protocol: L4Protocol,
+	port: u16,
+	sni: Option<&str>,
+	dc_id: Uuid,
+) -> GlobalResult<Option<L4Target>> {
+	if let Some(sni) = sni {
+		if let Some(target) = try_route_with_sni(ctx, sni, port, dc_id).await? {
+			return Ok(Some(target));
+		}
+	}
+
+	let protocols: &[GameGuardProtocol] = match protocol {
+		L4Protocol::Tcp if sni.is_some() => &[GameGuardProtocol::TcpTls, GameGuardProtocol::Tcp],
+		L4Protocol::Tcp => &[GameGuardProtocol::Tcp, GameGuardProtocol::TcpTls],
+		L4Protocol::Udp => &[GameGuardProtocol::Udp],
+	};
+
+	for protocol in protocols {
+		let Some(actor_id) = fetch_ingress_port_actor(ctx, *protocol, port).await? else {
+			continue;
+		};
+
+		if let Some(target) = find_actor_by_ingress_port(ctx, &actor_id, *protocol, port).await? {
+			return Ok(Some(target));
+		}
+	}
+
+	// No matching route found
+	Ok(None)
+}
+
+/// Try to route a TCP+TLS connection using an actor hostname in its SNI
+#[tracing::instrument(skip_all)]
+async fn try_route_with_sni(
+	ctx: &StandaloneCtx,
+	sni: &str,
+	port: u16,
+	dc_id: Uuid,
+) -> GlobalResult<Option<L4Target>> {
+	let dc_res = ctx
+		.op(cluster::ops::datacenter::get::Input {
+			datacenter_ids: vec![dc_id],
+		})
+		.await?;
+	let dc = unwrap!(dc_res.datacenters.first());
+
+	let Some((hostname_regex, _)) =
+		build_actor_hostname_and_path_regex(EndpointType::Hostname, &dc.guard_public_hostname)?
+	else {
+		return Ok(None);
+	};
+
+	let Some(captures) = hostname_regex.captures(sni) else {
+		return Ok(None);
+	};
+	let (Some(actor_id), Some(port_name)) = (captures.name("actor_id"), captures.name("port_name"))
+	else {
+		return Ok(None);
+	};
+	let Ok(actor_id) = Uuid::parse_str(actor_id.as_str()) else {
+		return Ok(None);
+	};
+
+	let Some(proxied_ports) = wait_for_proxied_ports(ctx, &actor_id).await? else {
+		return Ok(None);
+	};
+
+	// The port must also be the one the connection came in on
+	let Some(proxied_port) = proxied_ports.iter().find(|pp| {
+		pp.port_name == port_name.as_str()
+			&& pp.protocol == GameGuardProtocol::TcpTls
+			&& pp.ingress_port_number == port
+	}) else {
+		return Ok(None);
+	};
+
+	Ok(Some(L4Target {
+		target: RouteTarget {
+			actor_id: Some(actor_id),
+			server_id: None,
+			host: proxied_port.lan_hostname.parse()?,
+			port: proxied_port.source,
+			path: String::new(),
+		},
+		terminate_tls: true,
+	}))
+}
+
+/// Find an actor by the ingress port allocated to it
+#[tracing::instrument(skip_all, fields(?actor_id, ?protocol, ?port))]
+async fn find_actor_by_ingress_port(
+	ctx: &StandaloneCtx,
+	actor_id: &Uuid,
+	protocol: GameGuardProtocol,
+	port: u16,
+) -> GlobalResult<Option<L4Target>> {
+	let Some(proxied_ports) = wait_for_proxied_ports(ctx, actor_id).await? else {
+		return Ok(None);
+	};
+
+	let Some(proxied_port) = proxied_ports
+		.iter()
+		.find(|pp| pp.protocol == protocol && pp.ingress_port_number == port)
+	else {
+		return Ok(None);
+	};
+
+	Ok(Some(L4Target {
+		target: RouteTarget {
+			actor_id: Some(*actor_id),
+			server_id: None,
+			host: proxied_port.lan_hostname.parse()?,
+			port: proxied_port.source,
+			path: String::new(),
+		},
+		terminate_tls: protocol == GameGuardProtocol::TcpTls,
+	}))
+}
+
 /// Find an actor by actor_id and port_name - this would call into the actor registry
 #[tracing::instrument(skip_all, fields(?actor_id, %port_name, %path_to_forward))]
 async fn find_actor(
@@ -177,6 +310,34 @@ async fn find_actor(
 	port_name: &str,
 	path_to_forward: String,
 ) -> GlobalResult<Option<RouteTarget>> {
+	let Some(proxied_ports) = wait_for_proxied_ports(ctx, actor_id).await? else {
+		return Ok(None);
+	};
+
+	// Find the port
+	let Some(proxied_port) = proxied_ports.iter().find(|pp| pp.port_name == port_name) else {
+		// TODO: Return error port not found
+		return Ok(None);
+	};
+
+	// TODO: Validate protocol based on the incoming port
+
+	Ok(Some(RouteTarget {
+		actor_id: Some(*actor_id),
+		server_id: None,
+		host: proxied_port.lan_hostname.parse()?,
+		port: proxied_port.source,
+		path: path_to_forward,
+	}))
+}
+
+/// Waits for the given actor to become ready and returns its proxied ports. Returns `None` if the actor does
+/// not exist or does not become ready in time.
+#[tracing::instrument(skip_all, fields(?actor_id))]
+pub(super) async fn wait_for_proxied_ports(
+	ctx: &StandaloneCtx,
+	actor_id: &Uuid,
+) -> GlobalResult<Option<Vec<pegboard::keys::actor::ProxiedPort>>> {
 	let actor_exists = tokio::time::timeout(
 		Duration::from_secs(5),
 		ctx.fdb()
@@ -214,6 +375,13 @@ async fn find_actor(
 	{
 		proxied_ports
 	} else {
+		// Actors stopped for being idle have no proxied ports until they are started again
+		let ready_timeout = if wake_actor(ctx, actor_id).await? {
+			ACTOR_WAKE_TIMEOUT
+		} else {
+			Duration::from_secs(15)
+		};
+
 		tracing::info!(?actor_id, "waiting for actor to become ready");
 
 		// Wait for ready, fail, or destroy
@@ -228,7 +396,7 @@ async fn find_actor(
 				bail_with!(ACTOR_FAILED_TO_CREATE, error = "Actor failed before reaching a ready state.");
 			}
 			// Ready timeout
-			_ = tokio::time::sleep(Duration::from_secs(15)) => {
+			_ = tokio::time::sleep(ready_timeout) => {
 				return Ok(None);
 			}
 		}
@@ -246,21 +414,47 @@ async fn find_actor(
 
 	tracing::info!(?actor_id, "actor ready");
 
-	// Find the port
-	let Some(proxied_port) = proxied_ports.iter().find(|pp| pp.port_name == port_name) else {
-		// TODO: Return error port not found
-		return Ok(None);
+	Ok(Some(proxied_ports))
+}
+
+/// Signals the actor to start if it can be stopped for being idle. Returns whether the signal was sent.
+#[tracing::instrument(skip_all, fields(?actor_id))]
+async fn wake_actor(ctx: &StandaloneCtx, actor_id: &Uuid) -> GlobalResult<bool> {
+	let actors_res = ctx
+		.op(pegboard::ops::actor::get::Input {
+			actor_ids: vec![*actor_id],
+			endpoint_type: None,
+			allow_errors: false,
+		})
+		.await?;
+	let Some(actor) = actors_res.actors.first() else {
+		return Ok(false);
 	};
 
-	// TODO: Validate protocol based on the incoming port
+	let Some(idle_timeout_ms) = actor.lifecycle.idle_timeout_ms else {
+		return Ok(false);
+	};
+	if actor.destroy_ts.is_some() {
+		return Ok(false);
+	}
 
-	Ok(Some(RouteTarget {
-		actor_id: Some(*actor_id),
-		server_id: None,
-		host: proxied_port.lan_hostname.parse()?,
-		port: proxied_port.source,
-		path: path_to_forward,
-	}))
+	tracing::info!(?actor_id, "waking actor");
+
+	// Concurrent requests to the same actor only send one signal per wake. The key expires before the actor
+	// can be stopped for being idle again so the next wake is not deduplicated.
+	let idempotency_window =
+		Duration::from_millis(idle_timeout_ms.try_into()?).min(ACTOR_WAKE_TIMEOUT);
+
+	// Ignored by the actor if it is already running
+	ctx.signal(pegboard::workflows::actor::Wake {})
+		.to_workflow::<pegboard::workflows::actor::Workflow>()
+		.tag("actor_id", actor_id)
+		.idempotency_key("wake")
+		.idempotency_window(idempotency_window)
+		.send()
+		.await?;
+
+	Ok(true)
 }
 
 #[tracing::instrument(skip_all, fields(?actor_id))]
@@ -293,3 +487,41 @@ async fn fetch_proxied_ports(
 		.await
 		.map_err(Into::into)
 }
+
+/// Looks up which actor an ingress port is allocated to.
+#[tracing::instrument(skip_all, fields(?protocol, ?port))]
+async fn fetch_ingress_port_actor(
+	ctx: &StandaloneCtx,
+	protocol: GameGuardProtocol,
+	port: u16,
+) -> GlobalResult<Option<Uuid>> {
+	ctx.fdb()
+		.await?
+		.run(|tx, _mc| async move {
+			let ingress_port_subspace = pegboard::keys::subspace()
+				.subspace(&pegboard::keys::port::IngressKey::subspace(protocol, port));
+
+			let mut stream = tx.get_ranges_keyvalues(
+				fdb::RangeOption {
+					mode: StreamingMode::WantAll,
+					limit: Some(1),
+					..(&ingress_port_subspace).into()
+				},
+				SNAPSHOT,
+			);
+
+			let Some(entry) = stream.try_next().await? else {
+				return Ok(None);
+			};
+
+			let ingress_port_key = pegboard::keys::subspace()
+				.unpack::<pegboard::keys::port::IngressKey>(entry.key())
+				.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
+
+			// NOTE: The ingress port key stores the actor ID as `server_id`
+			Ok(Some(ingress_port_key.server_id))
+		})
+		.custom_instrument(tracing::info_span!("fetch_ingress_port_actor_tx"))
+		.await
+		.map_err(Into::into)
+}
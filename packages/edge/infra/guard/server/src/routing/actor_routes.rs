@@ -2,10 +2,11 @@ use chirp_workflow::prelude::*;
 use fdb_util::FormalKey; // Added for deserialize method
 use global_error::GlobalResult;
 use rivet_guard_core::proxy_service::{
-	RouteConfig, RoutingOutput, RoutingTimeout, StructuredResponse,
+	CustomResponse, RouteConfig, RoutingOutput, RoutingTimeout, StructuredResponse, TrafficSplit,
 };
+use rivet_guard_core::request::{HeaderMap, Method};
 use rivet_guard_core::status::StatusCode;
-use rivet_guard_core::RouteTarget;
+use rivet_guard_core::{HashKey, LoadBalancingStrategy, RouteTarget};
 use std::borrow::Cow;
 use std::collections::HashMap;
 use uuid::Uuid;
@@ -16,14 +17,34 @@ pub async fn route_via_route_config(
 	ctx: &StandaloneCtx,
 	host: &str,
 	path: &str,
+	method: &Method,
+	headers: &HeaderMap,
 	dc_id: Uuid,
 ) -> GlobalResult<Option<RoutingOutput>> {
-	// Get route directly using hostname and path
+	// Header names are already lowercase. All values of headers sent multiple times are kept, values that
+	// are not valid strings can't be matched on.
+	let headers = headers
+		.keys()
+		.map(|name| {
+			let values = headers
+				.get_all(name)
+				.iter()
+				.filter_map(|value| value.to_str().ok())
+				.map(ToString::to_string)
+				.collect();
+
+			(name.to_string(), values)
+		})
+		.collect::<HashMap<_, Vec<_>>>();
+
+	// Get route directly using hostname, path and the request's method and headers
 	// The operation handles priority internally and returns the best match
 	let routes_res = ctx
 		.op(route::ops::get_by_hostname_path::Input {
 			hostname: host.to_string(),
 			path: path.to_string(),
+			method: method.as_str().to_string(),
+			headers,
 		})
 		.await?;
 
@@ -43,6 +64,12 @@ pub async fn route_via_route_config(
 
 	let namespace_id = route.namespace_id;
 
+	// The route's middleware policy inherits unset fields from the environment's
+	let env_middleware_res = ctx
+		.op(route::ops::middleware::get::Input { namespace_id })
+		.await?;
+	let middleware = route.middleware.clone().or(env_middleware_res.middleware);
+
 	tracing::debug!(
 		host = host,
 		path = path,
@@ -50,37 +77,7 @@ pub async fn route_via_route_config(
 		"Found matching route"
 	);
 
-	// Convert the selector tags from the route
-	let selector_tags: HashMap<String, String> = match &route.target {
-		route::types::RouteTarget::Actors { selector_tags } => selector_tags.clone(),
-	};
-
-	// Query actors with matching tags in this environment
-	let actors_res = ctx
-		.op(pegboard::ops::actor::list_for_env::Input {
-			env_id: namespace_id,
-			tags: selector_tags,
-			include_destroyed: false,
-			created_before: None,
-			limit: 50, // Reasonable limit for load balancing
-		})
-		.await?;
-
-	if actors_res.actors.is_empty() {
-		tracing::warn!(
-			host = host,
-			path = path,
-			route_id = %route.route_id,
-			"Found matching route but no actors with matching tags"
-		);
-		return Ok(Some(RoutingOutput::Response(StructuredResponse {
-			status: StatusCode::SERVICE_UNAVAILABLE,
-			message: Cow::Borrowed("Found matching route but no actors with matching tags"),
-			docs: None,
-		})));
-	}
-
-	// Process the path once before the loop
+	// Process the path once
 	// First, extract the path and query parts
 	let (path_part, query_part) = match path.split_once('?') {
 		Some((p, q)) => (p, Some(q)),
@@ -115,28 +112,95 @@ pub async fn route_via_route_config(
 		"Path transformation for forwarding"
 	);
 
-	// Fetch each actor's details to get their connection information
-	let mut targets = Vec::new();
+	let (targets, split) = match &route.target {
+		route::types::RouteTarget::Actors { selector_tags } => {
+			let targets = find_route_targets(
+				ctx,
+				namespace_id,
+				selector_tags.clone(),
+				dc_id,
+				&path_to_forward,
+			)
+			.await?;
 
-	for actor_entry in &actors_res.actors {
-		// Find actor's proxied ports
-		if let Some(actor_targets) =
-			find_actor_targets(ctx, &actor_entry.actor_id, dc_id, &path_to_forward).await?
-		{
-			targets.extend(actor_targets);
+			(targets, None)
 		}
-	}
+		route::types::RouteTarget::Split {
+			selector_tags,
+			canary_selector_tags,
+			canary_weight,
+		} => {
+			let (targets, canary_targets) = tokio::try_join!(
+				find_route_targets(
+					ctx,
+					namespace_id,
+					selector_tags.clone(),
+					dc_id,
+					&path_to_forward,
+				),
+				find_route_targets(
+					ctx,
+					namespace_id,
+					canary_selector_tags.clone(),
+					dc_id,
+					&path_to_forward,
+				),
+			)?;
 
-	if targets.is_empty() {
+			(
+				targets,
+				Some(TrafficSplit {
+					targets: canary_targets,
+					weight: *canary_weight,
+				}),
+			)
+		}
+		route::types::RouteTarget::Redirect {
+			location,
+			status,
+			preserve_path,
+		} => {
+			let location = if *preserve_path {
+				format!("{}{}", location.trim_end_matches('/'), path_to_forward)
+			} else {
+				location.clone()
+			};
+
+			return Ok(Some(RoutingOutput::CustomResponse(CustomResponse {
+				status: StatusCode::from_u16(*status)?,
+				headers: vec![("location".to_string(), location)],
+				body: String::new(),
+			})));
+		}
+		route::types::RouteTarget::Response {
+			status,
+			content_type,
+			body,
+		} => {
+			return Ok(Some(RoutingOutput::CustomResponse(CustomResponse {
+				status: StatusCode::from_u16(*status)?,
+				headers: content_type
+					.iter()
+					.map(|content_type| ("content-type".to_string(), content_type.clone()))
+					.collect(),
+				body: body.clone(),
+			})));
+		}
+	};
+
+	let split_is_empty = split
+		.as_ref()
+		.map_or(true, |split| split.targets.is_empty());
+	if targets.is_empty() && split_is_empty {
 		tracing::warn!(
 			host = host,
 			path = path,
 			route_id = %route.route_id,
-			"Found matching actors but no valid targets"
+			"Found matching route but no actor targets"
 		);
 		return Ok(Some(RoutingOutput::Response(StructuredResponse {
-			status: StatusCode::BAD_GATEWAY,
-			message: Cow::Borrowed("Found matching route but no valid actor targets"),
+			status: StatusCode::SERVICE_UNAVAILABLE,
+			message: Cow::Borrowed("Found matching route but no actors with matching tags"),
 			docs: None,
 		})));
 	}
@@ -147,9 +211,91 @@ pub async fn route_via_route_config(
 		timeout: RoutingTimeout {
 			routing_timeout: 10, // 10 seconds timeout
 		},
+		load_balancing: convert_load_balancing(&route.load_balancing),
+		middleware: Some(crate::middleware::convert_middleware_policy(&middleware)),
+		split,
+		depends_on_request: routes_res.depends_on_request,
 	})))
 }
 
+/// Find the targets of all actors in the environment with the given tags
+#[tracing::instrument(skip_all)]
+async fn find_route_targets(
+	ctx: &StandaloneCtx,
+	namespace_id: Uuid,
+	selector_tags: HashMap<String, String>,
+	dc_id: Uuid,
+	path_to_forward: &str,
+) -> GlobalResult<Vec<RouteTarget>> {
+	// Query actors with matching tags in this environment
+	let actors_res = ctx
+		.op(pegboard::ops::actor::list_for_env::Input {
+			env_id: namespace_id,
+			tags: selector_tags,
+			include_destroyed: false,
+			created_before: None,
+			limit: 50, // Reasonable limit for load balancing
+		})
+		.await?;
+
+	// Fetch each actor's details to get their connection information
+	let mut targets = Vec::new();
+
+	for actor_entry in &actors_res.actors {
+		// Find actor's proxied ports
+		if let Some(actor_targets) =
+			find_actor_targets(ctx, &actor_entry.actor_id, dc_id, path_to_forward).await?
+		{
+			targets.extend(actor_targets);
+		}
+	}
+
+	// All actors may be stopped for being idle, start one of them to serve the request
+	if targets.is_empty() && !actors_res.actors.is_empty() {
+		let actors_res = ctx
+			.op(pegboard::ops::actor::get::Input {
+				actor_ids: actors_res.actors.iter().map(|x| x.actor_id).collect(),
+				endpoint_type: None,
+				allow_errors: false,
+			})
+			.await?;
+
+		if let Some(actor) = actors_res
+			.actors
+			.iter()
+			.find(|x| x.lifecycle.idle_timeout_ms.is_some() && x.destroy_ts.is_none())
+		{
+			if super::actor::wait_for_proxied_ports(ctx, &actor.actor_id)
+				.await?
+				.is_some()
+			{
+				if let Some(actor_targets) =
+					find_actor_targets(ctx, &actor.actor_id, dc_id, path_to_forward).await?
+				{
+					targets.extend(actor_targets);
+				}
+			}
+		}
+	}
+
+	Ok(targets)
+}
+
+fn convert_load_balancing(load_balancing: &route::types::LoadBalancing) -> LoadBalancingStrategy {
+	match load_balancing {
+		route::types::LoadBalancing::Random => LoadBalancingStrategy::Random,
+		route::types::LoadBalancing::RoundRobin => LoadBalancingStrategy::RoundRobin,
+		route::types::LoadBalancing::LeastInFlight => LoadBalancingStrategy::LeastInFlight,
+		route::types::LoadBalancing::ConsistentHash { key } => {
+			LoadBalancingStrategy::ConsistentHash(match key {
+				route::types::HashKey::Header { name } => HashKey::Header(name.clone()),
+				route::types::HashKey::Cookie { name } => HashKey::Cookie(name.clone()),
+				route::types::HashKey::Query { name } => HashKey::Query(name.clone()),
+			})
+		}
+	}
+}
+
 /// Find all potential targets for an actor
 #[tracing::instrument(skip_all)]
 async fn find_actor_targets(
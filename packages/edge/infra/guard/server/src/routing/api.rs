@@ -6,6 +6,7 @@ use rivet_guard_core::proxy_service::{
 	RouteConfig, RouteTarget, RoutingOutput, RoutingTimeout, StructuredResponse,
 };
 use rivet_guard_core::status::StatusCode;
+use rivet_guard_core::LoadBalancingStrategy;
 use service_discovery::ServiceDiscovery;
 use url::Url;
 use uuid::Uuid;
@@ -89,5 +90,9 @@ pub async fn route_api_request(
 		timeout: RoutingTimeout {
 			routing_timeout: 10, // 10 seconds for API routing timeout
 		},
+		load_balancing: LoadBalancingStrategy::default(),
+		middleware: None,
+		split: None,
+		depends_on_request: false,
 	})));
 }
@@ -1,7 +1,9 @@
 use chirp_workflow::prelude::*;
 use global_error::GlobalResult;
 use rivet_guard_core::proxy_service::{RoutingOutput, StructuredResponse};
+use rivet_guard_core::request::{HeaderMap, Method};
 use rivet_guard_core::status::StatusCode;
+use rivet_guard_core::{L4Protocol, L4RoutingFn};
 use std::{borrow::Cow, sync::Arc};
 
 pub mod actor;
@@ -15,13 +17,19 @@ pub fn create_routing_function(
 	dyn for<'a> Fn(
 			&'a str,
 			&'a str,
+			&'a Method,
+			&'a HeaderMap,
 			rivet_guard_core::proxy_service::PortType,
 		) -> futures::future::BoxFuture<'a, GlobalResult<RoutingOutput>>
 		+ Send
 		+ Sync,
 > {
 	Arc::new(
-		move |hostname: &str, path: &str, port_type: rivet_guard_core::proxy_service::PortType| {
+		move |hostname: &str,
+		      path: &str,
+		      method: &Method,
+		      headers: &HeaderMap,
+		      port_type: rivet_guard_core::proxy_service::PortType| {
 			let ctx = ctx.clone();
 
 			Box::pin(
@@ -43,15 +51,19 @@ pub fn create_routing_function(
 
 					// Try to route using configured routes first
 					tracing::debug!("Attempting route-based routing for {host} {path}");
-					match actor_routes::route_via_route_config(&ctx, host, path, dc_id).await {
+					match actor_routes::route_via_route_config(
+						&ctx, host, path, method, headers, dc_id,
+					)
+					.await
+					{
 						Ok(Some(RoutingOutput::Route(routing_result))) => {
 							tracing::debug!(
 								"Successfully routed via route config for {host} {path}"
 							);
 							return Ok(RoutingOutput::Route(routing_result));
 						}
-						Ok(Some(RoutingOutput::Response(response))) => {
-							return Ok(RoutingOutput::Response(response));
+						Ok(Some(output)) => {
+							return Ok(output);
 						}
 						Ok(None) => {
 							// Continue to next routing method
@@ -128,3 +140,27 @@ pub fn create_routing_function(
 		},
 	)
 }
+
+/// Creates the routing function that handles all incoming L4 (TCP and UDP) connections
+pub fn create_l4_routing_function(ctx: StandaloneCtx) -> L4RoutingFn {
+	Arc::new(move |protocol: L4Protocol, port: u16, sni: Option<&str>| {
+		let ctx = ctx.clone();
+
+		Box::pin(
+			async move {
+				let dc_id = ctx.config().server()?.rivet.edge()?.datacenter_id;
+
+				match actor::route_actor_connection(&ctx, protocol, port, sni, dc_id).await {
+					Ok(target) => Ok(target),
+					Err(err) => {
+						tracing::error!(?err, "Error in actor::route_actor_connection");
+
+						// Drop the connection
+						Ok(None)
+					}
+				}
+			}
+			.instrument(tracing::info_span!("l4_routing_fn", ?protocol, ?port, ?sni)),
+		)
+	})
+}
@@ -1,12 +1,31 @@
-use std::net::IpAddr;
+use std::{
+	collections::BTreeMap,
+	hash::{Hash, Hasher},
+	net::IpAddr,
+};
 
 use serde::{Deserialize, Serialize};
 
 /// See corresponding documentation in `pegboard_manager::config::Config`
-#[derive(Clone, Debug, Serialize, Deserialize, Hash)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ClientConfig {
 	pub network: Network,
 	pub reserved_resources: ReservedResources,
+	#[serde(default)]
+	pub labels: BTreeMap<String, String>,
+}
+
+impl Hash for ClientConfig {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		self.network.hash(state);
+		self.reserved_resources.hash(state);
+
+		// The config is part of the `InsertFdb` and `ProcessInit` activity inputs in the client workflow.
+		// Clients without labels hash like they did before labels existed so their history still replays
+		if !self.labels.is_empty() {
+			self.labels.hash(state);
+		}
+	}
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Hash)]
@@ -20,10 +39,26 @@ pub struct Network {
 	pub wan_port_range_max: u16,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, Hash)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ReservedResources {
 	// Millicores
 	pub cpu: u64,
 	// Mib
 	pub memory: u64,
+	// Mib
+	#[serde(default)]
+	pub disk: u64,
+}
+
+impl Hash for ReservedResources {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		self.cpu.hash(state);
+		self.memory.hash(state);
+
+		// Clients that don't reserve disk hash like they did before disk was tracked, see the hash impl of
+		// `ClientConfig`
+		if self.disk != 0 {
+			self.disk.hash(state);
+		}
+	}
 }
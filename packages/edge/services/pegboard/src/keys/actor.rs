@@ -51,6 +51,51 @@ impl<'de> TupleUnpack<'de> for CreateTsKey {
 	}
 }
 
+#[derive(Debug)]
+pub struct LastRequestTsKey {
+	actor_id: Uuid,
+}
+
+impl LastRequestTsKey {
+	pub fn new(actor_id: Uuid) -> Self {
+		LastRequestTsKey { actor_id }
+	}
+}
+
+impl FormalKey for LastRequestTsKey {
+	// Timestamp.
+	type Value = i64;
+
+	fn deserialize(&self, raw: &[u8]) -> Result<Self::Value> {
+		Ok(i64::from_be_bytes(raw.try_into()?))
+	}
+
+	fn serialize(&self, value: Self::Value) -> Result<Vec<u8>> {
+		Ok(value.to_be_bytes().to_vec())
+	}
+}
+
+impl TuplePack for LastRequestTsKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (ACTOR, DATA, self.actor_id, LAST_REQUEST_TS);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for LastRequestTsKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _, actor_id, _)) =
+			<(usize, usize, Uuid, usize)>::unpack(input, tuple_depth)?;
+		let v = LastRequestTsKey { actor_id };
+
+		Ok((input, v))
+	}
+}
+
 #[derive(Debug)]
 pub struct WorkflowIdKey {
 	actor_id: Uuid,
@@ -96,6 +141,108 @@ impl<'de> TupleUnpack<'de> for WorkflowIdKey {
 	}
 }
 
+/// Latest uploaded snapshot of the actor's file system upper layer.
+#[derive(Debug)]
+pub struct FsSnapshotKey {
+	actor_id: Uuid,
+}
+
+impl FsSnapshotKey {
+	pub fn new(actor_id: Uuid) -> Self {
+		FsSnapshotKey { actor_id }
+	}
+}
+
+impl FormalKey for FsSnapshotKey {
+	type Value = FsSnapshot;
+
+	fn deserialize(&self, raw: &[u8]) -> Result<Self::Value> {
+		serde_json::from_slice(raw).map_err(Into::into)
+	}
+
+	fn serialize(&self, value: Self::Value) -> Result<Vec<u8>> {
+		serde_json::to_vec(&value).map_err(Into::into)
+	}
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FsSnapshot {
+	/// SHA-256 of the compressed snapshot, used as its key in the snapshot bucket.
+	pub hash: String,
+	pub size_bytes: u64,
+	/// Generation of the actor the snapshot was taken from.
+	pub generation: u32,
+	pub create_ts: i64,
+}
+
+impl TuplePack for FsSnapshotKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (ACTOR, DATA, self.actor_id, FS_SNAPSHOT);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for FsSnapshotKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _, actor_id, _)) =
+			<(usize, usize, Uuid, usize)>::unpack(input, tuple_depth)?;
+
+		let v = FsSnapshotKey { actor_id };
+
+		Ok((input, v))
+	}
+}
+
+/// Spread group the actor was allocated under, see `client::SpreadGroupActorKey`.
+#[derive(Debug)]
+pub struct SpreadGroupKey {
+	actor_id: Uuid,
+}
+
+impl SpreadGroupKey {
+	pub fn new(actor_id: Uuid) -> Self {
+		SpreadGroupKey { actor_id }
+	}
+}
+
+impl FormalKey for SpreadGroupKey {
+	type Value = String;
+
+	fn deserialize(&self, raw: &[u8]) -> Result<Self::Value> {
+		String::from_utf8(raw.to_vec()).map_err(Into::into)
+	}
+
+	fn serialize(&self, value: Self::Value) -> Result<Vec<u8>> {
+		Ok(value.into_bytes())
+	}
+}
+
+impl TuplePack for SpreadGroupKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (ACTOR, DATA, self.actor_id, SPREAD_GROUP);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for SpreadGroupKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _, actor_id, _)) =
+			<(usize, usize, Uuid, usize)>::unpack(input, tuple_depth)?;
+
+		let v = SpreadGroupKey { actor_id };
+
+		Ok((input, v))
+	}
+}
+
 #[derive(Debug)]
 pub struct ProxiedPortsKey {
 	pub actor_id: Uuid,
@@ -1,4 +1,4 @@
-use std::result::Result::Ok;
+use std::{collections::BTreeMap, result::Result::Ok};
 
 use anyhow::*;
 use chirp_workflow::prelude::*;
@@ -229,6 +229,141 @@ impl<'de> TupleUnpack<'de> for TotalCpuKey {
 	}
 }
 
+#[derive(Debug)]
+pub struct RemainingDiskKey {
+	client_id: Uuid,
+}
+
+impl RemainingDiskKey {
+	pub fn new(client_id: Uuid) -> Self {
+		RemainingDiskKey { client_id }
+	}
+}
+
+impl FormalKey for RemainingDiskKey {
+	/// MiB.
+	type Value = u64;
+
+	fn deserialize(&self, raw: &[u8]) -> Result<Self::Value> {
+		Ok(u64::from_be_bytes(raw.try_into()?))
+	}
+
+	fn serialize(&self, value: Self::Value) -> Result<Vec<u8>> {
+		Ok(value.to_be_bytes().to_vec())
+	}
+}
+
+impl TuplePack for RemainingDiskKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (CLIENT, DATA, self.client_id, REMAINING_DISK);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for RemainingDiskKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _, client_id, _)) =
+			<(usize, usize, Uuid, usize)>::unpack(input, tuple_depth)?;
+		let v = RemainingDiskKey { client_id };
+
+		Ok((input, v))
+	}
+}
+
+#[derive(Debug)]
+pub struct TotalDiskKey {
+	client_id: Uuid,
+}
+
+impl TotalDiskKey {
+	pub fn new(client_id: Uuid) -> Self {
+		TotalDiskKey { client_id }
+	}
+}
+
+impl FormalKey for TotalDiskKey {
+	/// MiB.
+	type Value = u64;
+
+	fn deserialize(&self, raw: &[u8]) -> Result<Self::Value> {
+		Ok(u64::from_be_bytes(raw.try_into()?))
+	}
+
+	fn serialize(&self, value: Self::Value) -> Result<Vec<u8>> {
+		Ok(value.to_be_bytes().to_vec())
+	}
+}
+
+impl TuplePack for TotalDiskKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (CLIENT, DATA, self.client_id, TOTAL_DISK);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for TotalDiskKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _, client_id, _)) =
+			<(usize, usize, Uuid, usize)>::unpack(input, tuple_depth)?;
+		let v = TotalDiskKey { client_id };
+
+		Ok((input, v))
+	}
+}
+
+/// Labels from the client's config, matched against actor placement constraints.
+#[derive(Debug)]
+pub struct LabelsKey {
+	client_id: Uuid,
+}
+
+impl LabelsKey {
+	pub fn new(client_id: Uuid) -> Self {
+		LabelsKey { client_id }
+	}
+}
+
+impl FormalKey for LabelsKey {
+	type Value = BTreeMap<String, String>;
+
+	fn deserialize(&self, raw: &[u8]) -> Result<Self::Value> {
+		serde_json::from_slice(raw).map_err(Into::into)
+	}
+
+	fn serialize(&self, value: Self::Value) -> Result<Vec<u8>> {
+		serde_json::to_vec(&value).map_err(Into::into)
+	}
+}
+
+impl TuplePack for LabelsKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (CLIENT, DATA, self.client_id, LABELS);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for LabelsKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _, client_id, _)) =
+			<(usize, usize, Uuid, usize)>::unpack(input, tuple_depth)?;
+		let v = LabelsKey { client_id };
+
+		Ok((input, v))
+	}
+}
+
 #[derive(Debug)]
 pub struct ActorKey {
 	client_id: Uuid,
@@ -372,3 +507,90 @@ impl<'de> TupleUnpack<'de> for WorkflowIdKey {
 		Ok((input, v))
 	}
 }
+
+/// Index of actors allocated to a client by spread group, used for anti-affinity.
+#[derive(Debug)]
+pub struct SpreadGroupActorKey {
+	client_id: Uuid,
+	group: String,
+	pub actor_id: Uuid,
+}
+
+impl SpreadGroupActorKey {
+	pub fn new(client_id: Uuid, group: String, actor_id: Uuid) -> Self {
+		SpreadGroupActorKey {
+			client_id,
+			group,
+			actor_id,
+		}
+	}
+
+	pub fn subspace(client_id: Uuid, group: String) -> SpreadGroupActorSubspaceKey {
+		SpreadGroupActorSubspaceKey::new(client_id, group)
+	}
+}
+
+impl FormalKey for SpreadGroupActorKey {
+	type Value = ();
+
+	fn deserialize(&self, _raw: &[u8]) -> Result<Self::Value> {
+		Ok(())
+	}
+
+	fn serialize(&self, _value: Self::Value) -> Result<Vec<u8>> {
+		Ok(Vec::new())
+	}
+}
+
+impl TuplePack for SpreadGroupActorKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (
+			CLIENT,
+			SPREAD_GROUP,
+			self.client_id,
+			&self.group,
+			self.actor_id,
+		);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for SpreadGroupActorKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _, client_id, group, actor_id)) =
+			<(usize, usize, Uuid, String, Uuid)>::unpack(input, tuple_depth)?;
+		let v = SpreadGroupActorKey {
+			client_id,
+			group,
+			actor_id,
+		};
+
+		Ok((input, v))
+	}
+}
+
+pub struct SpreadGroupActorSubspaceKey {
+	client_id: Uuid,
+	group: String,
+}
+
+impl SpreadGroupActorSubspaceKey {
+	fn new(client_id: Uuid, group: String) -> Self {
+		SpreadGroupActorSubspaceKey { client_id, group }
+	}
+}
+
+impl TuplePack for SpreadGroupActorSubspaceKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (CLIENT, SPREAD_GROUP, self.client_id, &self.group);
+		t.pack(w, tuple_depth)
+	}
+}
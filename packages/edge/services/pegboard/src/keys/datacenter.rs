@@ -146,3 +146,94 @@ impl TuplePack for ClientsByRemainingMemSubspaceKey {
 		Ok(offset)
 	}
 }
+
+/// Actor that could not be allocated because no client had capacity for it. Cleared once the actor is
+/// allocated or destroyed.
+#[derive(Debug)]
+pub struct PendingAllocationKey {
+	pub flavor: protocol::ClientFlavor,
+	pub actor_id: Uuid,
+}
+
+impl PendingAllocationKey {
+	pub fn new(flavor: protocol::ClientFlavor, actor_id: Uuid) -> Self {
+		PendingAllocationKey { flavor, actor_id }
+	}
+
+	pub fn subspace(flavor: protocol::ClientFlavor) -> PendingAllocationSubspaceKey {
+		PendingAllocationSubspaceKey::new(flavor)
+	}
+}
+
+impl FormalKey for PendingAllocationKey {
+	type Value = PendingAllocation;
+
+	fn deserialize(&self, raw: &[u8]) -> Result<Self::Value> {
+		serde_json::from_slice(raw).map_err(Into::into)
+	}
+
+	fn serialize(&self, value: Self::Value) -> Result<Vec<u8>> {
+		serde_json::to_vec(&value).map_err(Into::into)
+	}
+}
+
+impl TuplePack for PendingAllocationKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (
+			DATACENTER,
+			PENDING_ALLOCATION,
+			self.flavor as usize,
+			self.actor_id,
+		);
+		t.pack(w, tuple_depth)
+	}
+}
+
+impl<'de> TupleUnpack<'de> for PendingAllocationKey {
+	fn unpack(input: &[u8], tuple_depth: TupleDepth) -> PackResult<(&[u8], Self)> {
+		let (input, (_, _, flavor, actor_id)) =
+			<(usize, usize, usize, Uuid)>::unpack(input, tuple_depth)?;
+		let flavor = protocol::ClientFlavor::from_repr(flavor).ok_or_else(|| {
+			PackError::Message(format!("invalid flavor `{flavor}` in key").into())
+		})?;
+
+		let v = PendingAllocationKey { flavor, actor_id };
+
+		Ok((input, v))
+	}
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PendingAllocation {
+	/// MiB.
+	pub memory: u64,
+	/// Millicores.
+	pub cpu: u64,
+	/// Timestamp of the most recent failed allocation attempt.
+	pub ts: i64,
+}
+
+pub struct PendingAllocationSubspaceKey {
+	pub flavor: protocol::ClientFlavor,
+}
+
+impl PendingAllocationSubspaceKey {
+	pub fn new(flavor: protocol::ClientFlavor) -> Self {
+		PendingAllocationSubspaceKey { flavor }
+	}
+}
+
+impl TuplePack for PendingAllocationSubspaceKey {
+	fn pack<W: std::io::Write>(
+		&self,
+		w: &mut W,
+		tuple_depth: TupleDepth,
+	) -> std::io::Result<VersionstampOffset> {
+		let t = (DATACENTER, PENDING_ALLOCATION, self.flavor as usize);
+		t.pack(w, tuple_depth)
+	}
+}
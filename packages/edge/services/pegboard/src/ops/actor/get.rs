@@ -23,11 +23,11 @@ struct ActorRow {
 	selected_resources_memory_mib: Option<i64>,
 	lifecycle_kill_timeout_ms: i64,
 	lifecycle_durable: bool,
+	lifecycle_idle_timeout_ms: Option<i64>,
 	create_ts: i64,
 	start_ts: Option<i64>,
 	connectable_ts: Option<i64>,
 	destroy_ts: Option<i64>,
-	client_wan_hostname: Option<String>,
 	image_id: Uuid,
 	args: sqlx::types::Json<Vec<String>>,
 	network_mode: i64,
@@ -58,6 +58,7 @@ pub(crate) struct PortProxied {
 struct ActorData {
 	actor_id: Uuid,
 	row: ActorRow,
+	client_wan_hostname: Option<String>,
 	port_ingress_rows: Vec<PortIngress>,
 	port_host_rows: Vec<PortHost>,
 	port_proxied_rows: Vec<PortProxied>,
@@ -145,11 +146,11 @@ pub async fn pegboard_actor_get(ctx: &OperationCtx, input: &Input) -> GlobalResu
 						selected_resources_memory_mib,
 						lifecycle_kill_timeout_ms,
 						lifecycle_durable,
+						lifecycle_idle_timeout_ms,
 						create_ts,
 						start_ts,
 						connectable_ts,
 						destroy_ts,
-						client_wan_hostname,
 						image_id,
 						json(args) AS args,
 						network_mode,
@@ -189,9 +190,17 @@ pub async fn pegboard_actor_get(ctx: &OperationCtx, input: &Input) -> GlobalResu
 				return Ok(None);
 			};
 
+			// Host ports are only exposed while the actor is connectable
+			let client_wan_hostname = if actor_row.connectable_ts.is_some() {
+				get_client_wan_hostname(ctx, workflow_id).await?
+			} else {
+				None
+			};
+
 			GlobalResult::Ok(Some(ActorData {
 				actor_id,
 				row: actor_row,
+				client_wan_hostname,
 				port_ingress_rows,
 				port_host_rows,
 				port_proxied_rows,
@@ -229,7 +238,7 @@ pub async fn pegboard_actor_get(ctx: &OperationCtx, input: &Input) -> GlobalResu
 			});
 
 			let is_connectable = s.row.connectable_ts.is_some();
-			let wan_hostname = s.row.client_wan_hostname.clone();
+			let wan_hostname = s.client_wan_hostname.clone();
 
 			let ports = s
 				.port_ingress_rows
@@ -286,6 +295,7 @@ pub async fn pegboard_actor_get(ctx: &OperationCtx, input: &Input) -> GlobalResu
 				lifecycle: ActorLifecycle {
 					kill_timeout_ms: s.row.lifecycle_kill_timeout_ms,
 					durable: s.row.lifecycle_durable,
+					idle_timeout_ms: s.row.lifecycle_idle_timeout_ms,
 				},
 				args: s.row.args.0.clone(),
 				environment: s.row.environment.0.clone(),
@@ -303,6 +313,35 @@ pub async fn pegboard_actor_get(ctx: &OperationCtx, input: &Input) -> GlobalResu
 	Ok(Output { actors })
 }
 
+/// Reads the WAN hostname of the client the actor is allocated to from the client's config.
+async fn get_client_wan_hostname(
+	ctx: &OperationCtx,
+	workflow_id: Uuid,
+) -> GlobalResult<Option<String>> {
+	let Some(allocation) = ctx
+		.query::<crate::workflows::actor::Allocation>(workflow_id)
+		.await?
+		.flatten()
+	else {
+		return Ok(None);
+	};
+
+	let client_pool = ctx
+		.sqlite_for_workflow(allocation.client_workflow_id)
+		.await?;
+
+	let (wan_hostname,) = sql_fetch_one!(
+		[ctx, (Option<String>,), client_pool]
+		"
+		SELECT config->'network'->>'wan_hostname' AS wan_hostname
+		FROM state
+		",
+	)
+	.await?;
+
+	Ok(wan_hostname)
+}
+
 pub(crate) fn create_port_ingress(
 	actor_id: Uuid,
 	port: &PortIngress,
@@ -2,7 +2,7 @@ use chirp_workflow::prelude::*;
 
 use crate::types::LogsStreamType;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Input {
 	pub actor_ids: Vec<Uuid>,
 	pub stream_types: Vec<LogsStreamType>,
@@ -139,8 +139,13 @@ pub async fn pegboard_actor_log_read(ctx: &OperationCtx, input: &Input) -> Globa
 					END
 				)
 			)
-		-- Use dynamic direction directly in the ORDER BY clause
-		ORDER BY ts {order_direction}
+		-- Use dynamic direction directly in the ORDER BY clause. Lines with the same timestamp are ordered
+		-- deterministically so they can be paginated
+		ORDER BY
+			ts {order_direction},
+			actor_id_str {order_direction},
+			stream_type {order_direction},
+			message {order_direction}
 		LIMIT
 			?
 		"
@@ -0,0 +1,132 @@
+use chirp_workflow::prelude::*;
+use futures_util::{stream, Stream, StreamExt};
+use regex::{Regex, RegexBuilder};
+
+use super::read::LogEntry;
+use crate::types::LogsStreamType;
+
+/// Max compiled size of user provided search regexes.
+const SEARCH_REGEX_SIZE_LIMIT: usize = 1024 * 1024;
+
+/// NATS subject that Vector publishes the log lines of an actor to as they are shipped. See the
+/// `nats_actor_logs` sink in the Vector server config.
+pub fn subject(actor_id: Uuid) -> String {
+	format!("pegboard.actor.log.{actor_id}")
+}
+
+/// Log line published by Vector, in the format written by the runner log shippers.
+#[derive(Debug, Deserialize)]
+struct LogEvent {
+	actor_id: Uuid,
+	stream_type: u8,
+	/// In nanoseconds.
+	ts: i64,
+	message: String,
+}
+
+/// Subscribes to log lines of the given actors as they are shipped. Past lines are not included, use
+/// `read` for those.
+pub async fn subscribe(
+	nats: &NatsPool,
+	actor_ids: &[Uuid],
+) -> GlobalResult<impl Stream<Item = LogEntry> + Send> {
+	let mut subs = Vec::with_capacity(actor_ids.len());
+	for actor_id in actor_ids {
+		subs.push(nats.subscribe(subject(*actor_id)).await?);
+	}
+
+	Ok(stream::select_all(subs).filter_map(|msg| async move {
+		match serde_json::from_slice::<LogEvent>(&msg.payload) {
+			Ok(event) => Some(LogEntry {
+				ts: event.ts,
+				message: event.message.into_bytes(),
+				stream_type: event.stream_type,
+				actor_id: event.actor_id,
+			}),
+			Err(err) => {
+				tracing::warn!(?err, "failed to decode actor log event");
+				None
+			}
+		}
+	}))
+}
+
+/// Matches log lines with the same stream type and search options as `read`.
+#[derive(Debug)]
+pub struct Filter {
+	stream_types: Vec<LogsStreamType>,
+	search: Option<Search>,
+}
+
+#[derive(Debug)]
+enum Search {
+	Regex(Regex),
+	/// Lowercase if not case sensitive.
+	Text {
+		text: String,
+		case_sensitive: bool,
+	},
+}
+
+impl Filter {
+	pub fn new(
+		stream_types: Vec<LogsStreamType>,
+		search_text: Option<String>,
+		search_case_sensitive: Option<bool>,
+		search_enable_regex: Option<bool>,
+	) -> GlobalResult<Self> {
+		let case_sensitive = search_case_sensitive.unwrap_or(false);
+
+		let search = match search_text.filter(|x| !x.is_empty()) {
+			Some(text) if search_enable_regex.unwrap_or(false) => {
+				let regex = RegexBuilder::new(&text)
+					.case_insensitive(!case_sensitive)
+					.size_limit(SEARCH_REGEX_SIZE_LIMIT)
+					.build();
+
+				match regex {
+					Ok(regex) => Some(Search::Regex(regex)),
+					Err(err) => bail_with!(ACTOR_LOGS_INVALID_SEARCH_REGEX, error = err),
+				}
+			}
+			Some(text) => Some(Search::Text {
+				text: if case_sensitive {
+					text
+				} else {
+					text.to_lowercase()
+				},
+				case_sensitive,
+			}),
+			None => None,
+		};
+
+		Ok(Filter {
+			stream_types,
+			search,
+		})
+	}
+
+	pub fn matches(&self, entry: &LogEntry) -> bool {
+		if !self
+			.stream_types
+			.iter()
+			.any(|stream_type| *stream_type as u8 == entry.stream_type)
+		{
+			return false;
+		}
+
+		let message = String::from_utf8_lossy(&entry.message);
+		match &self.search {
+			Some(Search::Regex(regex)) => regex.is_match(&message),
+			Some(Search::Text {
+				text,
+				case_sensitive: true,
+			}) => message.contains(text.as_str()),
+			Some(Search::Text {
+				text,
+				case_sensitive: false,
+			}) => message.to_lowercase().contains(text.as_str()),
+			None => true,
+		}
+	}
+}
@@ -108,6 +108,18 @@ pub struct ActorConfig {
 	pub ports: HashableMap<String, Port>,
 	pub network_mode: NetworkMode,
 	pub metadata: Raw<ActorMetadata>,
+	/// Set for durable container actors to persist their file system across reschedules.
+	#[serde(default)]
+	pub snapshot: Option<ActorSnapshot>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Hash)]
+pub struct ActorSnapshot {
+	/// Presigned URL of the latest snapshot of the file system upper layer. Restored before the actor
+	/// starts.
+	pub restore_url: Option<String>,
+	/// Presigned URL to upload the new snapshot to once the actor exits.
+	pub upload_url: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Hash)]
@@ -286,6 +298,14 @@ pub enum Event {
 		generation: u32,
 		state: ActorState,
 	},
+	/// Sent after the actor's file system snapshot was uploaded and before the actor's exit.
+	ActorSnapshotCreated {
+		actor_id: Uuid,
+		generation: u32,
+		/// SHA-256 of the compressed snapshot.
+		hash: String,
+		size_bytes: u64,
+	},
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Hash)]
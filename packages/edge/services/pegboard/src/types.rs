@@ -1,4 +1,8 @@
-use std::{collections::HashMap, fmt};
+use std::{
+	collections::HashMap,
+	fmt,
+	hash::{Hash, Hasher},
+};
 
 use chirp_workflow::prelude::*;
 use rivet_api::models;
@@ -42,10 +46,38 @@ impl ActorResources {
 	}
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Hash)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ActorLifecycle {
 	pub kill_timeout_ms: i64,
 	pub durable: bool,
+	/// Stops a durable actor after it has not received requests through guard for this long. The actor is
+	/// started again on its next request.
+	#[serde(default)]
+	pub idle_timeout_ms: Option<i64>,
+}
+
+impl Hash for ActorLifecycle {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		self.kill_timeout_ms.hash(state);
+		self.durable.hash(state);
+
+		// The lifecycle is part of the `InsertDb` activity input in the actor workflow. Skipping the field
+		// when unset keeps the hash of actors created before idle timeouts existed the same so their
+		// history still replays
+		if let Some(idle_timeout_ms) = self.idle_timeout_ms {
+			idle_timeout_ms.hash(state);
+		}
+	}
+}
+
+/// Constraints on which client an actor is allocated to.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Hash)]
+pub struct ActorPlacement {
+	/// Labels the client must have set in its config.
+	pub client_labels: util::serde::HashableMap<String, String>,
+	/// Actor tag keys. Actors in the same environment with equal values for all of these tags are allocated
+	/// to different clients.
+	pub spread_by_tags: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Hash, Debug, Clone, Copy, PartialEq, Eq, FromRepr)]
@@ -211,6 +243,7 @@ impl ApiFrom<models::ActorsLifecycle> for ActorLifecycle {
 		ActorLifecycle {
 			kill_timeout_ms: value.kill_timeout.unwrap_or_default(),
 			durable: value.durable.unwrap_or_default(),
+			idle_timeout_ms: value.idle_timeout,
 		}
 	}
 }
@@ -220,6 +253,16 @@ impl ApiFrom<ActorLifecycle> for models::ActorsLifecycle {
 		models::ActorsLifecycle {
 			kill_timeout: Some(value.kill_timeout_ms),
 			durable: Some(value.durable),
+			idle_timeout: value.idle_timeout_ms,
+		}
+	}
+}
+
+impl ApiFrom<models::ActorsPlacement> for ActorPlacement {
+	fn api_from(value: models::ActorsPlacement) -> ActorPlacement {
+		ActorPlacement {
+			client_labels: value.client_labels.unwrap_or_default().into(),
+			spread_by_tags: value.spread_by_tags.unwrap_or_default(),
 		}
 	}
 }
@@ -4,7 +4,7 @@ use fdb_util::{FormalKey, SERIALIZABLE};
 use foundationdb as fdb;
 use nix::sys::signal::Signal;
 
-use super::{analytics::InsertClickHouseInput, DestroyComplete, DestroyStarted};
+use super::{analytics::InsertClickHouseInput, runtime, DestroyComplete, DestroyStarted};
 use crate::{keys, protocol, types::GameGuardProtocol};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -64,6 +64,18 @@ pub(crate) async fn pegboard_actor_destroy(
 		})
 		.await?;
 
+	// File system snapshots are only taken of containers
+	if matches!(
+		input.build_kind,
+		Some(BuildKind::DockerImage | BuildKind::OciBundle)
+	) {
+		ctx.v(2)
+			.activity(DeleteSnapshotsInput {
+				actor_id: input.actor_id,
+			})
+			.await?;
+	}
+
 	ctx.msg(DestroyComplete {})
 		.tag("actor_id", input.actor_id)
 		.send()
@@ -113,6 +125,91 @@ async fn update_db(
 	.await
 }
 
+#[derive(Debug, Serialize, Deserialize, Hash)]
+struct DeleteSnapshotsInput {
+	actor_id: Uuid,
+}
+
+/// Deletes the file system snapshots of a destroyed durable actor, including uploads that were never
+/// committed.
+#[activity(DeleteSnapshots)]
+async fn delete_snapshots(ctx: &ActivityCtx, input: &DeleteSnapshotsInput) -> GlobalResult<()> {
+	let pool = ctx.sqlite().await?;
+
+	let (durable,) = sql_fetch_one!(
+		[ctx, (bool,), pool]
+		"
+		SELECT lifecycle_durable
+		FROM state
+		",
+	)
+	.await?;
+
+	if !durable {
+		return Ok(());
+	}
+
+	ctx.fdb()
+		.await?
+		.run(|tx, _mc| async move {
+			tx.clear(&keys::subspace().pack(&keys::actor::FsSnapshotKey::new(input.actor_id)));
+
+			Ok(())
+		})
+		.custom_instrument(tracing::info_span!("actor_clear_fs_snapshot_tx"))
+		.await?;
+
+	let s3_client = s3_util::Client::with_bucket(ctx.config(), runtime::SNAPSHOT_BUCKET).await?;
+
+	for prefix in [
+		runtime::snapshot_prefix(input.actor_id),
+		runtime::snapshot_upload_prefix(input.actor_id),
+	] {
+		let mut continuation_token = None;
+
+		loop {
+			let res = s3_client
+				.list_objects_v2()
+				.bucket(s3_client.bucket())
+				.prefix(&prefix)
+				.set_continuation_token(continuation_token)
+				.send()
+				.await?;
+
+			let objects = res
+				.contents()
+				.iter()
+				.filter_map(|obj| obj.key())
+				.map(|key| {
+					s3_util::aws_sdk_s3::types::ObjectIdentifier::builder()
+						.key(key)
+						.build()
+				})
+				.collect::<Result<Vec<_>, _>>()?;
+
+			if !objects.is_empty() {
+				s3_client
+					.delete_objects()
+					.bucket(s3_client.bucket())
+					.delete(
+						s3_util::aws_sdk_s3::types::Delete::builder()
+							.set_objects(Some(objects))
+							.build()?,
+					)
+					.send()
+					.await?;
+			}
+
+			continuation_token = res.next_continuation_token().map(ToString::to_string);
+			if continuation_token.is_none() {
+				break;
+			}
+		}
+	}
+
+	Ok(())
+}
+
 #[derive(Debug, Serialize, Deserialize, Hash)]
 pub struct UpdateFdbInput {
 	actor_id: Uuid,
@@ -124,14 +221,23 @@ pub struct UpdateFdbInput {
 pub async fn update_fdb(ctx: &ActivityCtx, input: &UpdateFdbInput) -> GlobalResult<()> {
 	let pool = ctx.sqlite().await?;
 
-	let ingress_ports = sql_fetch_all!(
-		[ctx, (i64, i64), pool]
-		"
-		SELECT protocol, ingress_port_number
-		FROM ports_ingress
-		",
-	)
-	.await?;
+	let (ingress_ports, selected_resources_disk_mib) = tokio::try_join!(
+		sql_fetch_all!(
+			[ctx, (i64, i64), pool]
+			"
+			SELECT protocol, ingress_port_number
+			FROM ports_ingress
+			",
+		),
+		sql_fetch_one!(
+			[ctx, (Option<i64>,), pool]
+			"
+			SELECT selected_resources_disk_mib
+			FROM state
+			",
+		),
+	)?;
+	let (selected_resources_disk_mib,) = selected_resources_disk_mib;
 
 	ctx.fdb()
 		.await?
@@ -155,6 +261,11 @@ pub async fn update_fdb(ctx: &ActivityCtx, input: &UpdateFdbInput) -> GlobalResu
 						.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
 				);
 
+				// Written by guard when the actor receives a request
+				tx.clear(
+					&keys::subspace().pack(&keys::actor::LastRequestTsKey::new(input.actor_id)),
+				);
+
 				clear_ports_and_resources(
 					input.actor_id,
 					input.build_kind,
@@ -163,6 +274,7 @@ pub async fn update_fdb(ctx: &ActivityCtx, input: &UpdateFdbInput) -> GlobalResu
 					input.actor.client_workflow_id,
 					input.actor.selected_resources_memory_mib,
 					input.actor.selected_resources_cpu_millicores,
+					selected_resources_disk_mib,
 					&tx,
 				)
 				.await
@@ -184,6 +296,7 @@ pub(crate) async fn clear_ports_and_resources(
 	client_workflow_id: Option<Uuid>,
 	selected_resources_memory_mib: Option<i64>,
 	selected_resources_cpu_millicores: Option<i64>,
+	selected_resources_disk_mib: Option<i64>,
 	tx: &fdb::RetryableTransaction,
 ) -> Result<(), fdb::FdbBindingError> {
 	// Remove all allocated ingress ports
@@ -214,6 +327,32 @@ pub(crate) async fn clear_ports_and_resources(
 		// consistency during rescheduling and forced deletion.
 		let actor_key = keys::client::ActorKey::new(client_id, actor_id);
 		tx.clear(&keys::subspace().pack(&actor_key));
+
+		// Remove from the client's spread group index
+		let spread_group_key = keys::actor::SpreadGroupKey::new(actor_id);
+		let spread_group_key_buf = keys::subspace().pack(&spread_group_key);
+		if let Some(spread_group_entry) = tx.get(&spread_group_key_buf, SERIALIZABLE).await? {
+			let group = spread_group_key
+				.deserialize(&spread_group_entry)
+				.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
+
+			let spread_group_actor_key =
+				keys::client::SpreadGroupActorKey::new(client_id, group, actor_id);
+			tx.clear(&keys::subspace().pack(&spread_group_actor_key));
+			tx.clear(&spread_group_key_buf);
+		}
+	}
+
+	// Remove from the pending allocation index, it is set again if the next allocation fails
+	if let Some(build_kind) = build_kind {
+		let client_flavor = match build_kind {
+			BuildKind::DockerImage | BuildKind::OciBundle => protocol::ClientFlavor::Container,
+			BuildKind::JavaScript => protocol::ClientFlavor::Isolate,
+		};
+
+		let pending_allocation_key =
+			keys::datacenter::PendingAllocationKey::new(client_flavor, actor_id);
+		tx.clear(&keys::subspace().pack(&pending_allocation_key));
 	}
 
 	// Release client's resources and update allocation index
@@ -306,6 +445,30 @@ pub(crate) async fn clear_ports_and_resources(
 				.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
 		);
 
+		// Disk is only tracked for clients that reported it
+		if let Some(selected_resources_disk_mib) = selected_resources_disk_mib {
+			let remaining_disk_key = keys::client::RemainingDiskKey::new(client_id);
+			let remaining_disk_key_buf = keys::subspace().pack(&remaining_disk_key);
+
+			if let Some(remaining_disk_entry) =
+				tx.get(&remaining_disk_key_buf, SERIALIZABLE).await?
+			{
+				let remaining_disk = remaining_disk_key
+					.deserialize(&remaining_disk_entry)
+					.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
+				let new_disk = remaining_disk
+					+ u64::try_from(selected_resources_disk_mib)
+						.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
+
+				tx.set(
+					&remaining_disk_key_buf,
+					&remaining_disk_key
+						.serialize(new_disk)
+						.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
+				);
+			}
+		}
+
 		// Only update allocation idx if it existed before
 		if tx
 			.get(&old_allocation_key_buf, SERIALIZABLE)
@@ -4,6 +4,8 @@ use sqlx::Acquire;
 pub async fn run(ctx: &mut WorkflowCtx) -> GlobalResult<()> {
 	ctx.activity(MigrateInitInput {}).await?;
 	ctx.v(2).activity(MigrateExtraMetaInput {}).await?;
+	ctx.v(3).activity(MigrateIdleTimeoutInput {}).await?;
+	ctx.v(4).activity(MigrateSelectedDiskInput {}).await?;
 
 	Ok(())
 }
@@ -102,3 +104,45 @@ async fn migrate_extra_meta(ctx: &ActivityCtx, _input: &MigrateExtraMetaInput) -
 
 	Ok(())
 }
+
+#[derive(Debug, Serialize, Deserialize, Hash)]
+struct MigrateIdleTimeoutInput {}
+
+#[activity(MigrateIdleTimeout)]
+async fn migrate_idle_timeout(
+	ctx: &ActivityCtx,
+	_input: &MigrateIdleTimeoutInput,
+) -> GlobalResult<()> {
+	let pool = ctx.sqlite().await?;
+
+	sql_execute!(
+		[ctx, pool]
+		"
+		ALTER TABLE state ADD lifecycle_idle_timeout_ms INT;
+		",
+	)
+	.await?;
+
+	Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize, Hash)]
+struct MigrateSelectedDiskInput {}
+
+#[activity(MigrateSelectedDisk)]
+async fn migrate_selected_disk(
+	ctx: &ActivityCtx,
+	_input: &MigrateSelectedDiskInput,
+) -> GlobalResult<()> {
+	let pool = ctx.sqlite().await?;
+
+	sql_execute!(
+		[ctx, pool]
+		"
+		ALTER TABLE state ADD selected_resources_disk_mib INT;
+		",
+	)
+	.await?;
+
+	Ok(())
+}
@@ -6,7 +6,7 @@ use rivet_util::serde::HashableMap;
 
 use crate::{
 	protocol,
-	types::{ActorLifecycle, ActorResources, EndpointType, NetworkMode, Routing},
+	types::{ActorLifecycle, ActorPlacement, ActorResources, EndpointType, NetworkMode, Routing},
 };
 
 mod analytics;
@@ -26,6 +26,9 @@ const ACTOR_START_THRESHOLD_MS: i64 = util::duration::seconds(30);
 const ACTOR_STOP_THRESHOLD_MS: i64 = util::duration::seconds(30);
 /// How long to wait after stopped and not receiving an exit state before setting actor as lost.
 const ACTOR_EXIT_THRESHOLD_MS: i64 = util::duration::seconds(5);
+/// Same as `ACTOR_EXIT_THRESHOLD_MS` for actors that upload a snapshot of their file system before the exit
+/// is reported. Must be greater than the client's snapshot upload timeout.
+const ACTOR_SNAPSHOT_EXIT_THRESHOLD_MS: i64 = util::duration::minutes(6);
 /// How long an actor goes without retries before it's retry count is reset to 0, effectively resetting its
 /// backoff to 0.
 const RETRY_RESET_DURATION_MS: i64 = util::duration::minutes(10);
@@ -44,6 +47,8 @@ pub struct Input {
 	pub environment: HashableMap<String, String>,
 	pub network_ports: HashableMap<String, Port>,
 	pub endpoint_type: Option<EndpointType>,
+	#[serde(default)]
+	pub placement: ActorPlacement,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Hash)]
@@ -133,23 +138,42 @@ pub async fn pegboard_actor(ctx: &mut WorkflowCtx, input: &Input) -> GlobalResul
 		.send()
 		.await?;
 
-	let Some(res) = runtime::spawn_actor(ctx, input, &initial_actor_setup, 0).await? else {
-		ctx.msg(Failed {
-			message: "Failed to allocate (no availability).".into(),
-		})
-		.tag("actor_id", input.actor_id)
-		.send()
-		.await?;
+	// Destroy the actor if this workflow is cancelled
+	let actor_id = input.actor_id;
+	let build_kind = initial_actor_setup.meta.build_kind.clone();
+	ctx.on_cancel(closure(move |ctx| {
+		async move {
+			ctx.workflow(destroy::Input {
+				actor_id,
+				build_kind: Some(build_kind),
+				kill: None,
+			})
+			.output()
+			.await
+		}
+		.boxed()
+	}));
 
-		ctx.workflow(destroy::Input {
-			actor_id: input.actor_id,
-			build_kind: Some(initial_actor_setup.meta.build_kind),
-			kill: None,
-		})
-		.output()
-		.await?;
+	let res = match runtime::spawn_actor(ctx, input, &initial_actor_setup, 0).await? {
+		Ok(res) => res,
+		Err(reason) => {
+			ctx.msg(Failed {
+				message: format!("Failed to allocate (no availability): {reason}."),
+			})
+			.tag("actor_id", input.actor_id)
+			.send()
+			.await?;
 
-		return Ok(());
+			ctx.workflow(destroy::Input {
+				actor_id: input.actor_id,
+				build_kind: Some(initial_actor_setup.meta.build_kind),
+				kill: None,
+			})
+			.output()
+			.await?;
+
+			return Ok(());
+		}
 	};
 
 	ctx.v(2)
@@ -162,11 +186,36 @@ pub async fn pegboard_actor(ctx: &mut WorkflowCtx, input: &Input) -> GlobalResul
 
 	let state_res = ctx
 		.loope(
-			runtime::State::new(res.client_id, res.client_workflow_id, input.image_id),
+			runtime::State::new(
+				res.client_id,
+				res.client_workflow_id,
+				input.image_id,
+				runtime::snapshot_enabled(input, &initial_actor_setup.meta.build_kind),
+			),
 			|ctx, state| {
 				let input = input.clone();
 
 				async move {
+					// Kill the current actor if this workflow is cancelled during this iteration. Runs before
+					// the destroy handler registered above
+					let generation = state.generation;
+					let client_workflow_id = state.client_workflow_id;
+					let kill_timeout_ms = input.lifecycle.kill_timeout_ms;
+					ctx.on_cancel(closure(move |ctx| {
+						async move {
+							destroy::kill(
+								ctx,
+								actor_id,
+								generation,
+								client_workflow_id,
+								kill_timeout_ms,
+								false,
+							)
+							.await
+						}
+						.boxed()
+					}));
+
 					let sig = if let Some(drain_timeout_ts) = state.drain_timeout_ts {
 						// Listen for signal with drain timeout
 						if let Some(sig) = ctx.listen_until::<Main>(drain_timeout_ts).await? {
@@ -230,6 +279,26 @@ pub async fn pegboard_actor(ctx: &mut WorkflowCtx, input: &Input) -> GlobalResul
 								state: protocol::ActorState::Lost,
 							})
 						}
+					} else if let Some(idle_check_ts) = state.idle_check_ts {
+						// Listen for signal with idle timeout
+						if let Some(sig) = ctx.listen_until::<Main>(idle_check_ts).await? {
+							sig
+						} else {
+							let next_check_ts = ctx
+								.activity(runtime::CheckIdleInput {
+									actor_id: input.actor_id,
+									idle_timeout_ms: unwrap!(input.lifecycle.idle_timeout_ms),
+								})
+								.await?;
+
+							if next_check_ts.is_some() {
+								state.idle_check_ts = next_check_ts;
+							} else {
+								runtime::sleep_actor(ctx, &input, state).await?;
+							}
+
+							return Ok(Loop::Continue);
+						}
 					} else {
 						// Listen for signal normally
 						ctx.listen::<Main>().await?
@@ -249,6 +318,11 @@ pub async fn pegboard_actor(ctx: &mut WorkflowCtx, input: &Input) -> GlobalResul
 							})
 							.await?;
 
+							// The stopped actor's remaining state updates don't affect the workflow
+							if state.sleeping {
+								return Ok(Loop::Continue);
+							}
+
 							match sig.state {
 								protocol::ActorState::Starting => {
 									state.gc_timeout_ts = None;
@@ -288,14 +362,28 @@ pub async fn pegboard_actor(ctx: &mut WorkflowCtx, input: &Input) -> GlobalResul
 											.send()
 											.await?;
 									}
+
+									// Start checking for inactivity
+									if let Some(idle_timeout_ms) = input.lifecycle.idle_timeout_ms {
+										state.idle_check_ts =
+											Some(util::timestamp::now() + idle_timeout_ms);
+									}
 								}
 								protocol::ActorState::Stopping => {
+									state.idle_check_ts = None;
 									state.gc_timeout_ts =
 										Some(util::timestamp::now() + ACTOR_STOP_THRESHOLD_MS);
 								}
 								protocol::ActorState::Stopped => {
+									// The snapshot is uploaded between stopping and exiting
+									let exit_threshold_ms = if state.snapshot {
+										ACTOR_SNAPSHOT_EXIT_THRESHOLD_MS
+									} else {
+										ACTOR_EXIT_THRESHOLD_MS
+									};
+
 									state.gc_timeout_ts =
-										Some(util::timestamp::now() + ACTOR_EXIT_THRESHOLD_MS);
+										Some(util::timestamp::now() + exit_threshold_ms);
 								}
 								protocol::ActorState::Exited { .. }
 								| protocol::ActorState::Lost => {
@@ -381,6 +469,22 @@ pub async fn pegboard_actor(ctx: &mut WorkflowCtx, input: &Input) -> GlobalResul
 								.send()
 								.await?;
 
+							// A stopped actor starts with the new image when it is woken
+							if state.sleeping {
+								ctx.activity(runtime::UpdateImageInput {
+									image_id: sig.image_id,
+								})
+								.await?;
+								state.image_id = Some(sig.image_id);
+
+								ctx.msg(UpgradeComplete {})
+									.tag("actor_id", input.actor_id)
+									.send()
+									.await?;
+
+								return Ok(Loop::Continue);
+							}
+
 							ctx.activity(runtime::SetConnectableInput { connectable: false })
 								.await?;
 
@@ -426,6 +530,11 @@ pub async fn pegboard_actor(ctx: &mut WorkflowCtx, input: &Input) -> GlobalResul
 								.await?;
 						}
 						Main::Drain(sig) => {
+							// Not allocated to a client
+							if state.sleeping {
+								return Ok(Loop::Continue);
+							}
+
 							state.drain_timeout_ts = Some(
 								sig.drain_timeout_ts
 									- DRAIN_PADDING_MS - input.lifecycle.kill_timeout_ms,
@@ -434,15 +543,37 @@ pub async fn pegboard_actor(ctx: &mut WorkflowCtx, input: &Input) -> GlobalResul
 						Main::Undrain(_) => {
 							state.drain_timeout_ts = None;
 						}
+						Main::Wake(_) => {
+							// Already running or starting otherwise
+							if state.sleeping
+								&& runtime::wake_actor(ctx, &input, state).await?.is_some()
+							{
+								// Destroyed early
+								return Ok(Loop::Break(runtime::StateRes {
+									// Not allocated to a client
+									kill: None,
+								}));
+							}
+						}
+						Main::SnapshotCreated(sig) => {
+							ctx.activity(runtime::CommitSnapshotInput {
+								actor_id: input.actor_id,
+								generation: sig.generation,
+								hash: sig.hash,
+								size_bytes: sig.size_bytes,
+							})
+							.await?;
+						}
 						Main::Destroy(sig) => {
 							return Ok(Loop::Break(runtime::StateRes {
-								kill: Some(KillCtx {
+								// No need to kill if already stopped for being idle
+								kill: (!state.sleeping).then(|| KillCtx {
 									generation: state.generation,
 									kill_timeout_ms: sig
 										.override_kill_timeout_ms
 										.unwrap_or(input.lifecycle.kill_timeout_ms),
 								}),
-							}))
+							}));
 						}
 					}
 
@@ -472,6 +603,28 @@ pub struct Allocated {
 	pub client_id: Uuid,
 }
 
+#[derive(Debug)]
+pub struct AllocationOutput {
+	pub client_id: Uuid,
+	pub client_workflow_id: Uuid,
+	pub generation: u32,
+}
+
+/// Reads the client the actor is currently allocated to from the state of the main loop. Returns `None` while
+/// the actor is sleeping.
+#[query(Allocation, Workflow)]
+pub fn pegboard_actor_allocation(state: &runtime::State) -> GlobalResult<Option<AllocationOutput>> {
+	if state.sleeping {
+		return Ok(None);
+	}
+
+	Ok(Some(AllocationOutput {
+		client_id: state.client_id,
+		client_workflow_id: state.client_workflow_id,
+		generation: state.generation,
+	}))
+}
+
 #[message("pegboard_actor_failed")]
 pub struct Failed {
 	pub message: String,
@@ -511,6 +664,18 @@ pub struct StateUpdate {
 	pub state: protocol::ActorState,
 }
 
+/// Starts an actor that was stopped for being idle. Ignored if the actor is not stopped.
+#[signal("pegboard_actor_wake")]
+pub struct Wake {}
+
+/// Sent by the client once the actor's file system snapshot has been uploaded.
+#[signal("pegboard_actor_snapshot_created")]
+pub struct SnapshotCreated {
+	pub generation: u32,
+	pub hash: String,
+	pub size_bytes: u64,
+}
+
 #[message("pegboard_actor_upgrade_started")]
 pub struct UpgradeStarted {}
 
@@ -523,6 +688,8 @@ join_signal!(Main {
 	Drain,
 	Undrain,
 	Destroy,
+	Wake,
+	SnapshotCreated,
 });
 
 // Stub definition
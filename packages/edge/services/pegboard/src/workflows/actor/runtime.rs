@@ -1,4 +1,4 @@
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use build::types::BuildKind;
 use chirp_workflow::prelude::*;
@@ -24,6 +24,19 @@ use crate::{
 	workflows::client::CLIENT_ELIGIBLE_THRESHOLD_MS,
 };
 
+/// How many eligible clients to score before allocating to the best one.
+const ALLOCATE_MAX_CANDIDATES: usize = 32;
+/// How many clients with enough memory to scan at most, eligible or not. Bounds the reads of the allocation
+/// transaction when most clients are rejected.
+const ALLOCATE_MAX_SCANNED: usize = 512;
+/// Bucket holding snapshots of durable actors' file system upper layers.
+pub(crate) const SNAPSHOT_BUCKET: &str = "bucket-actor-snapshot";
+/// How long the client has to download the snapshot after the actor is allocated.
+const SNAPSHOT_RESTORE_URL_EXPIRE: Duration = Duration::from_secs(15 * 60);
+/// Snapshots are uploaded when the actor exits, so the upload URL has to outlive the actor. This is the
+/// maximum expiration for presigned S3 URLs, actors running for longer are not snapshotted.
+const SNAPSHOT_UPLOAD_URL_EXPIRE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
 #[derive(Deserialize, Serialize)]
 pub struct State {
 	pub generation: u32,
@@ -34,13 +47,22 @@ pub struct State {
 
 	pub drain_timeout_ts: Option<i64>,
 	pub gc_timeout_ts: Option<i64>,
+	/// When to check if the actor has been idle for longer than its idle timeout.
+	#[serde(default)]
+	pub idle_check_ts: Option<i64>,
+	/// Set while the actor is stopped for being idle.
+	#[serde(default)]
+	pub sleeping: bool,
+	/// Whether the current generation uploads a snapshot of its file system when it stops.
+	#[serde(default)]
+	pub snapshot: bool,
 
 	#[serde(default)]
 	reschedule_state: RescheduleState,
 }
 
 impl State {
-	pub fn new(client_id: Uuid, client_workflow_id: Uuid, image_id: Uuid) -> Self {
+	pub fn new(client_id: Uuid, client_workflow_id: Uuid, image_id: Uuid, snapshot: bool) -> Self {
 		State {
 			generation: 0,
 			client_id,
@@ -48,6 +70,9 @@ impl State {
 			image_id: Some(image_id),
 			drain_timeout_ts: None,
 			gc_timeout_ts: Some(util::timestamp::now() + ACTOR_START_THRESHOLD_MS),
+			idle_check_ts: None,
+			sleeping: false,
+			snapshot,
 			reschedule_state: RescheduleState::default(),
 		}
 	}
@@ -391,6 +416,496 @@ async fn allocate_actor_v2(
 	Ok(res)
 }
 
+#[derive(Debug, Serialize, Deserialize, Hash)]
+struct AllocateActorInputV3 {
+	actor_id: Uuid,
+	generation: u32,
+	build_kind: BuildKind,
+	resources: protocol::Resources,
+	client_labels: util::serde::HashableMap<String, String>,
+	spread_group: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum AllocateActorOutputV3 {
+	Allocated(AllocateActorOutputV2),
+	/// Explains why no client was eligible.
+	Unavailable {
+		reason: String,
+	},
+}
+
+/// Client that passed all allocation constraints.
+struct AllocateCandidate {
+	entry_key: Vec<u8>,
+	allocation_key: keys::datacenter::ClientsByRemainingMemKey,
+	client_workflow_id: Uuid,
+	remaining_cpu: u64,
+	remaining_disk: Option<u64>,
+	score: f64,
+}
+
+/// Counts of clients with enough memory that were rejected, used to explain allocation failures.
+#[derive(Default)]
+struct AllocateRejections {
+	unresponsive: usize,
+	insufficient_cpu: usize,
+	insufficient_disk: usize,
+	missing_labels: usize,
+	spread_conflict: usize,
+	/// Stopped at `ALLOCATE_MAX_SCANNED` before scanning all clients with enough memory.
+	scan_limit_reached: bool,
+}
+
+impl AllocateRejections {
+	/// Whether more capacity would let the actor be allocated. Clients lacking labels or conflicting with
+	/// the spread group can't be fixed by provisioning more of the same clients.
+	fn is_capacity(&self) -> bool {
+		let no_client_with_memory = self.unresponsive == 0
+			&& self.insufficient_cpu == 0
+			&& self.insufficient_disk == 0
+			&& self.missing_labels == 0
+			&& self.spread_conflict == 0;
+
+		no_client_with_memory || self.insufficient_cpu != 0 || self.insufficient_disk != 0
+	}
+}
+
+impl std::fmt::Display for AllocateRejections {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let reasons = [
+			(self.unresponsive, "not responding"),
+			(self.insufficient_cpu, "not enough cpu"),
+			(self.insufficient_disk, "not enough disk"),
+			(self.missing_labels, "missing required labels"),
+			(
+				self.spread_conflict,
+				"already running an actor with the same spread tags",
+			),
+		]
+		.into_iter()
+		.filter(|(count, _)| *count != 0)
+		.map(|(count, reason)| format!("{count} {reason}"))
+		.collect::<Vec<_>>();
+
+		if reasons.is_empty() {
+			write!(f, "no client has enough memory")
+		} else if self.scan_limit_reached {
+			write!(
+				f,
+				"no eligible client among the first {ALLOCATE_MAX_SCANNED} with enough memory ({})",
+				reasons.join(", ")
+			)
+		} else {
+			write!(
+				f,
+				"no eligible client among those with enough memory ({})",
+				reasons.join(", ")
+			)
+		}
+	}
+}
+
+/// Fraction of a client's resources that would be in use after allocating the actor, averaged over all
+/// tracked dimensions. Higher scores pack actors tighter.
+fn allocate_score(dims: &[(u64, u64, u64)]) -> f64 {
+	let utilizations = dims
+		.iter()
+		.filter(|(total, _, _)| *total != 0)
+		.map(|(total, remaining, requested)| {
+			(total.saturating_sub(remaining.saturating_sub(*requested))) as f64 / *total as f64
+		})
+		.collect::<Vec<_>>();
+
+	if utilizations.is_empty() {
+		0.0
+	} else {
+		utilizations.iter().sum::<f64>() / utilizations.len() as f64
+	}
+}
+
+#[activity(AllocateActorV3)]
+async fn allocate_actor_v3(
+	ctx: &ActivityCtx,
+	input: &AllocateActorInputV3,
+) -> GlobalResult<AllocateActorOutputV3> {
+	let client_flavor = match input.build_kind {
+		BuildKind::DockerImage | BuildKind::OciBundle => protocol::ClientFlavor::Container,
+		BuildKind::JavaScript => protocol::ClientFlavor::Isolate,
+	};
+	let memory_mib = input.resources.memory / 1024 / 1024;
+	let disk_mib = u64::from(input.resources.disk);
+
+	let start_instant = Instant::now();
+
+	let (res, disk_allocated) = ctx
+		.fdb()
+		.await?
+		.run(|tx, _mc| async move {
+			let ping_threshold_ts = util::timestamp::now() - CLIENT_ELIGIBLE_THRESHOLD_MS;
+
+			// Select a range that only includes clients that have enough remaining mem to allocate this actor
+			let start = keys::subspace().pack(
+				&keys::datacenter::ClientsByRemainingMemKey::subspace_with_mem(
+					client_flavor,
+					memory_mib,
+				),
+			);
+			let client_allocation_subspace =
+				keys::datacenter::ClientsByRemainingMemKey::subspace(client_flavor);
+			let end = keys::subspace()
+				.subspace(&client_allocation_subspace)
+				.range()
+				.1;
+
+			let mut stream = tx.get_ranges_keyvalues(
+				fdb::RangeOption {
+					mode: StreamingMode::Iterator,
+					// Containers bin pack so we reverse the order, this decides which clients are scored
+					// first when there are more than `ALLOCATE_MAX_CANDIDATES`
+					reverse: matches!(client_flavor, protocol::ClientFlavor::Container),
+					..(start, end).into()
+				},
+				// NOTE: This is not SERIALIZABLE because we don't want to conflict with all of the keys, just
+				// the one we choose
+				SNAPSHOT,
+			);
+
+			let mut rejections = AllocateRejections::default();
+			let mut candidates = 0;
+			let mut scanned = 0;
+			let mut best: Option<AllocateCandidate> = None;
+
+			while candidates < ALLOCATE_MAX_CANDIDATES {
+				if scanned == ALLOCATE_MAX_SCANNED {
+					rejections.scan_limit_reached = true;
+					break;
+				}
+
+				let Some(entry) = stream.try_next().await? else {
+					break;
+				};
+				scanned += 1;
+
+				let allocation_key = keys::subspace()
+					.unpack::<keys::datacenter::ClientsByRemainingMemKey>(entry.key())
+					.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
+				let client_id = allocation_key.client_id;
+
+				// Scan by last ping
+				if allocation_key.last_ping_ts < ping_threshold_ts {
+					rejections.unresponsive += 1;
+					continue;
+				}
+
+				let remaining_cpu_key = keys::client::RemainingCpuKey::new(client_id);
+				let total_cpu_key = keys::client::TotalCpuKey::new(client_id);
+				let total_mem_key = keys::client::TotalMemoryKey::new(client_id);
+				let remaining_disk_key = keys::client::RemainingDiskKey::new(client_id);
+				let total_disk_key = keys::client::TotalDiskKey::new(client_id);
+				let labels_key = keys::client::LabelsKey::new(client_id);
+
+				let (
+					remaining_cpu_entry,
+					total_cpu_entry,
+					total_mem_entry,
+					remaining_disk_entry,
+					total_disk_entry,
+					labels_entry,
+				) = tokio::try_join!(
+					tx.get(&keys::subspace().pack(&remaining_cpu_key), SNAPSHOT),
+					tx.get(&keys::subspace().pack(&total_cpu_key), SNAPSHOT),
+					tx.get(&keys::subspace().pack(&total_mem_key), SNAPSHOT),
+					tx.get(&keys::subspace().pack(&remaining_disk_key), SNAPSHOT),
+					tx.get(&keys::subspace().pack(&total_disk_key), SNAPSHOT),
+					tx.get(&keys::subspace().pack(&labels_key), SNAPSHOT),
+				)?;
+
+				let remaining_cpu = remaining_cpu_key
+					.deserialize(
+						&remaining_cpu_entry.ok_or(fdb::FdbBindingError::CustomError(
+							format!("key should exist: {remaining_cpu_key:?}").into(),
+						))?,
+					)
+					.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
+				let total_cpu = total_cpu_key
+					.deserialize(&total_cpu_entry.ok_or(fdb::FdbBindingError::CustomError(
+						format!("key should exist: {total_cpu_key:?}").into(),
+					))?)
+					.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
+				let total_mem = total_mem_key
+					.deserialize(&total_mem_entry.ok_or(fdb::FdbBindingError::CustomError(
+						format!("key should exist: {total_mem_key:?}").into(),
+					))?)
+					.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?;
+				// Clients registered before disk was tracked have no disk keys, disk is not limited for them
+				let disk = match (remaining_disk_entry, total_disk_entry) {
+					(Some(remaining_disk_entry), Some(total_disk_entry)) => Some((
+						remaining_disk_key
+							.deserialize(&remaining_disk_entry)
+							.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
+						total_disk_key
+							.deserialize(&total_disk_entry)
+							.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
+					)),
+					_ => None,
+				};
+				let labels = labels_entry
+					.map(|labels_entry| labels_key.deserialize(&labels_entry))
+					.transpose()
+					.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?
+					.unwrap_or_default();
+
+				if remaining_cpu < input.resources.cpu {
+					rejections.insufficient_cpu += 1;
+					continue;
+				}
+
+				if let Some((remaining_disk, _)) = disk {
+					if remaining_disk < disk_mib {
+						rejections.insufficient_disk += 1;
+						continue;
+					}
+				}
+
+				if !input
+					.client_labels
+					.iter()
+					.all(|(k, v)| labels.get(k) == Some(v))
+				{
+					rejections.missing_labels += 1;
+					continue;
+				}
+
+				if let Some(spread_group) = &input.spread_group {
+					let spread_group_subspace =
+						keys::subspace().subspace(&keys::client::SpreadGroupActorKey::subspace(
+							client_id,
+							spread_group.clone(),
+						));
+
+					let spread_group_entry = tx
+						.get_ranges_keyvalues(
+							fdb::RangeOption {
+								mode: StreamingMode::Iterator,
+								limit: Some(1),
+								..(&spread_group_subspace).into()
+							},
+							SNAPSHOT,
+						)
+						.try_next()
+						.await?;
+
+					if spread_group_entry.is_some() {
+						rejections.spread_conflict += 1;
+						continue;
+					}
+				}
+
+				candidates += 1;
+
+				let mut dims = vec![
+					(total_cpu, remaining_cpu, input.resources.cpu),
+					(total_mem, allocation_key.remaining_mem, memory_mib),
+				];
+				if let Some((remaining_disk, total_disk)) = disk {
+					dims.push((total_disk, remaining_disk, disk_mib));
+				}
+				let score = allocate_score(&dims);
+
+				if best.as_ref().map_or(true, |best| score > best.score) {
+					best = Some(AllocateCandidate {
+						entry_key: entry.key().to_vec(),
+						client_workflow_id: allocation_key
+							.deserialize(entry.value())
+							.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
+						allocation_key,
+						remaining_cpu,
+						remaining_disk: disk.map(|(remaining_disk, _)| remaining_disk),
+						score,
+					});
+				}
+			}
+
+			let pending_allocation_key =
+				keys::datacenter::PendingAllocationKey::new(client_flavor, input.actor_id);
+			let pending_allocation_key_buf = keys::subspace().pack(&pending_allocation_key);
+
+			let Some(best) = best else {
+				if rejections.is_capacity() {
+					// Record the demand so the cluster autoscaler can provision capacity for it
+					tx.set(
+						&pending_allocation_key_buf,
+						&pending_allocation_key
+							.serialize(keys::datacenter::PendingAllocation {
+								memory: memory_mib,
+								cpu: input.resources.cpu,
+								ts: util::timestamp::now(),
+							})
+							.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
+					);
+				} else {
+					// Clear demand recorded by a previous attempt, new clients would not fit this actor either
+					tx.clear(&pending_allocation_key_buf);
+				}
+
+				return Ok((
+					AllocateActorOutputV3::Unavailable {
+						reason: rejections.to_string(),
+					},
+					false,
+				));
+			};
+			let client_id = best.allocation_key.client_id;
+
+			// Add read conflicts only for the keys of the chosen client
+			let remaining_cpu_key = keys::client::RemainingCpuKey::new(client_id);
+			let remaining_cpu_key_buf = keys::subspace().pack(&remaining_cpu_key);
+			let remaining_disk_key = keys::client::RemainingDiskKey::new(client_id);
+			let remaining_disk_key_buf = keys::subspace().pack(&remaining_disk_key);
+			for key in [
+				&best.entry_key,
+				&remaining_cpu_key_buf,
+				&remaining_disk_key_buf,
+			] {
+				tx.add_conflict_range(key, &end_of_key_range(key), ConflictRangeType::Read)?;
+			}
+
+			// Clear old entry
+			tx.clear(&best.entry_key);
+
+			// Update allocated amount
+			let new_remaining_mem = best.allocation_key.remaining_mem - memory_mib;
+			let new_remaining_cpu = best.remaining_cpu - input.resources.cpu;
+			let new_allocation_key = keys::datacenter::ClientsByRemainingMemKey::new(
+				client_flavor,
+				new_remaining_mem,
+				best.allocation_key.last_ping_ts,
+				client_id,
+			);
+			tx.set(
+				&keys::subspace().pack(&new_allocation_key),
+				&new_allocation_key
+					.serialize(best.client_workflow_id)
+					.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
+			);
+
+			tracing::debug!(
+				old_mem=%best.allocation_key.remaining_mem,
+				old_cpu=%best.remaining_cpu,
+				old_disk=?best.remaining_disk,
+				new_mem=%new_remaining_mem,
+				new_cpu=%new_remaining_cpu,
+				score=%best.score,
+				"allocating resources"
+			);
+
+			// Update client record
+			let remaining_mem_key = keys::client::RemainingMemoryKey::new(client_id);
+			tx.set(
+				&keys::subspace().pack(&remaining_mem_key),
+				&remaining_mem_key
+					.serialize(new_remaining_mem)
+					.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
+			);
+
+			tx.set(
+				&remaining_cpu_key_buf,
+				&remaining_cpu_key
+					.serialize(new_remaining_cpu)
+					.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
+			);
+
+			if let Some(remaining_disk) = best.remaining_disk {
+				tx.set(
+					&remaining_disk_key_buf,
+					&remaining_disk_key
+						.serialize(remaining_disk - disk_mib)
+						.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
+				);
+			}
+
+			tx.clear(&pending_allocation_key_buf);
+
+			// Insert actor index key
+			let client_actor_key = keys::client::ActorKey::new(client_id, input.actor_id);
+			tx.set(
+				&keys::subspace().pack(&client_actor_key),
+				&client_actor_key
+					.serialize(input.generation)
+					.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
+			);
+
+			if let Some(spread_group) = &input.spread_group {
+				let spread_group_subspace = keys::subspace().subspace(
+					&keys::client::SpreadGroupActorKey::subspace(client_id, spread_group.clone()),
+				);
+				let (spread_group_start, spread_group_end) = spread_group_subspace.range();
+				tx.add_conflict_range(
+					&spread_group_start,
+					&spread_group_end,
+					ConflictRangeType::Read,
+				)?;
+
+				// Insert spread group index key
+				let spread_group_actor_key = keys::client::SpreadGroupActorKey::new(
+					client_id,
+					spread_group.clone(),
+					input.actor_id,
+				);
+				tx.set(
+					&keys::subspace().pack(&spread_group_actor_key),
+					&spread_group_actor_key
+						.serialize(())
+						.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
+				);
+
+				let spread_group_key = keys::actor::SpreadGroupKey::new(input.actor_id);
+				tx.set(
+					&keys::subspace().pack(&spread_group_key),
+					&spread_group_key
+						.serialize(spread_group.clone())
+						.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
+				);
+			}
+
+			Ok((
+				AllocateActorOutputV3::Allocated(AllocateActorOutputV2 {
+					client_id,
+					client_workflow_id: best.client_workflow_id,
+				}),
+				best.remaining_disk.is_some(),
+			))
+		})
+		.custom_instrument(tracing::info_span!("actor_allocate_tx"))
+		.await?;
+
+	let dt = start_instant.elapsed().as_secs_f64();
+	metrics::ACTOR_ALLOCATE_DURATION
+		.with_label_values(&[&matches!(res, AllocateActorOutputV3::Allocated(_)).to_string()])
+		.observe(dt);
+
+	// Disk is only released if it was allocated, clients registered before disk was tracked don't have it
+	if matches!(res, AllocateActorOutputV3::Allocated(_)) {
+		let pool = ctx.sqlite().await?;
+
+		sql_execute!(
+			[ctx, pool]
+			"
+			UPDATE state
+			SET selected_resources_disk_mib = ?
+			",
+			disk_allocated
+				.then_some(disk_mib)
+				.map(i64::try_from)
+				.transpose()?,
+		)
+		.await?;
+	}
+
+	Ok(res)
+}
+
 #[derive(Debug, Serialize, Deserialize, Hash)]
 pub struct UpdateFdbInput {
 	pub actor_id: Uuid,
@@ -631,36 +1146,79 @@ async fn compare_retry(ctx: &ActivityCtx, input: &CompareRetryInput) -> GlobalRe
 	Ok((now, input.last_retry_ts < now - RETRY_RESET_DURATION_MS))
 }
 
-/// Returns whether or not there was availability to spawn the actor.
+/// Identifies actors that should not be allocated to the same client, see `ActorPlacement::spread_by_tags`.
+fn spread_group(input: &Input) -> GlobalResult<Option<String>> {
+	if input.placement.spread_by_tags.is_empty() {
+		return Ok(None);
+	}
+
+	let mut tags = input
+		.placement
+		.spread_by_tags
+		.iter()
+		.map(|k| (k, input.tags.get(k)))
+		.collect::<Vec<_>>();
+	tags.sort();
+	tags.dedup();
+
+	Ok(Some(serde_json::to_string(&(input.env_id, tags))?))
+}
+
+/// Whether the file system of the actor is persisted across reschedules with snapshots. Only applies to
+/// durable containers.
+pub fn snapshot_enabled(input: &Input, build_kind: &BuildKind) -> bool {
+	input.lifecycle.durable && matches!(build_kind, BuildKind::DockerImage | BuildKind::OciBundle)
+}
+
+/// Returns the allocated client or why there was no availability to spawn the actor.
 pub async fn spawn_actor(
 	ctx: &mut WorkflowCtx,
 	input: &Input,
 	actor_setup: &setup::ActorSetupCtx,
 	generation: u32,
-) -> GlobalResult<Option<AllocateActorOutputV2>> {
-	let res = match ctx.check_version(2).await? {
-		1 => {
-			ctx.activity(AllocateActorInputV1 {
+) -> GlobalResult<Result<AllocateActorOutputV2, String>> {
+	let res = match ctx.check_version(3).await? {
+		1 => ctx
+			.activity(AllocateActorInputV1 {
 				actor_id: input.actor_id,
 				build_kind: actor_setup.meta.build_kind,
 				resources: actor_setup.resources.clone(),
 			})
 			.await?
-		}
+			.ok_or_else(|| "no client has enough memory".to_string()),
+		2 => ctx
+			.v(2)
+			.activity(AllocateActorInputV2 {
+				actor_id: input.actor_id,
+				generation,
+				build_kind: actor_setup.meta.build_kind,
+				resources: actor_setup.resources.clone(),
+			})
+			.await?
+			.ok_or_else(|| "no client has enough memory".to_string()),
 		_ => {
-			ctx.v(2)
-				.activity(AllocateActorInputV2 {
+			let res = ctx
+				.v(3)
+				.activity(AllocateActorInputV3 {
 					actor_id: input.actor_id,
 					generation,
 					build_kind: actor_setup.meta.build_kind,
 					resources: actor_setup.resources.clone(),
+					client_labels: input.placement.client_labels.clone(),
+					spread_group: spread_group(input)?,
 				})
-				.await?
+				.await?;
+
+			match res {
+				AllocateActorOutputV3::Allocated(res) => Ok(res),
+				AllocateActorOutputV3::Unavailable { reason } => Err(reason),
+			}
 		}
 	};
 
-	let Some(res) = res else {
-		return Ok(None);
+	let res = match res {
+		Ok(res) => res,
+		Err(reason) => return Ok(Err(reason)),
 	};
 
 	let (_, ports_res) = ctx
@@ -676,6 +1234,19 @@ pub async fn spawn_actor(
 		))
 		.await?;
 
+	let snapshot = if snapshot_enabled(input, &actor_setup.meta.build_kind) {
+		Some(
+			ctx.v(2)
+				.activity(ResolveSnapshotInput {
+					actor_id: input.actor_id,
+					generation,
+				})
+				.await?,
+		)
+	} else {
+		None
+	};
+
 	let cluster_id = ctx.config().server()?.rivet.edge()?.cluster_id;
 
 	ctx.signal(protocol::Command::StartActor {
@@ -758,13 +1329,14 @@ pub async fn spawn_actor(
 					build_id: input.image_id,
 				},
 			})?,
+			snapshot,
 		}),
 	})
 	.to_workflow_id(res.client_workflow_id)
 	.send()
 	.await?;
 
-	Ok(Some(res))
+	Ok(Ok(res))
 }
 
 pub async fn reschedule_actor(
@@ -783,6 +1355,75 @@ pub async fn reschedule_actor(
 	})
 	.await?;
 
+	reallocate_actor(ctx, input, state, image_id).await
+}
+
+/// Stops an actor that has been idle for longer than its idle timeout and releases its resources. Its
+/// storage and ingress ports are kept so it can be started again with `wake_actor`.
+pub async fn sleep_actor(
+	ctx: &mut WorkflowCtx,
+	input: &Input,
+	state: &mut State,
+) -> GlobalResult<()> {
+	tracing::debug!(actor_id=?input.actor_id, "actor idle, stopping");
+
+	ctx.activity(SetConnectableInput { connectable: false })
+		.await?;
+
+	destroy::kill(
+		ctx,
+		input.actor_id,
+		state.generation,
+		state.client_workflow_id,
+		input.lifecycle.kill_timeout_ms,
+		true,
+	)
+	.await?;
+
+	ctx.activity(ReleaseResourcesInput {
+		actor_id: input.actor_id,
+		image_id: state.image_id.unwrap_or(input.image_id),
+		client_id: state.client_id,
+		client_workflow_id: state.client_workflow_id,
+	})
+	.await?;
+
+	state.sleeping = true;
+	state.idle_check_ts = None;
+	state.gc_timeout_ts = None;
+
+	Ok(())
+}
+
+/// Starts an actor that was stopped by `sleep_actor`. Returns the destroy signal if it was received before
+/// the actor could be allocated.
+pub async fn wake_actor(
+	ctx: &mut WorkflowCtx,
+	input: &Input,
+	state: &mut State,
+) -> GlobalResult<Option<Destroy>> {
+	tracing::debug!(actor_id=?input.actor_id, "waking actor");
+
+	let image_id = state.image_id.unwrap_or(input.image_id);
+	let res = reallocate_actor(ctx, input, state, image_id).await?;
+
+	if res.is_none() {
+		state.sleeping = false;
+	}
+
+	Ok(res)
+}
+
+/// Allocates the actor to a client again after it was stopped.
+async fn reallocate_actor(
+	ctx: &mut WorkflowCtx,
+	input: &Input,
+	state: &mut State,
+	image_id: Uuid,
+) -> GlobalResult<Option<Destroy>> {
+	// Restarted once the new actor is running
+	state.idle_check_ts = None;
+
 	let actor_setup = setup::setup(ctx, &input, setup::SetupCtx::Reschedule { image_id }).await?;
 
 	let next_generation = state.generation + 1;
@@ -823,12 +1464,13 @@ pub async fn reschedule_actor(
 					}
 				}
 
-				if let Some(res) = spawn_actor(ctx, &input, &actor_setup, next_generation).await? {
-					Ok(Loop::Break(Ok((state.clone(), res))))
-				} else {
-					tracing::debug!(actor_id=?input.actor_id, "failed to reschedule actor, retrying");
+				match spawn_actor(ctx, &input, &actor_setup, next_generation).await? {
+					Ok(res) => Ok(Loop::Break(Ok((state.clone(), res)))),
+					Err(reason) => {
+						tracing::debug!(actor_id=?input.actor_id, %reason, "failed to reschedule actor, retrying");
 
-					Ok(Loop::Continue)
+						Ok(Loop::Continue)
+					}
 				}
 			}
 			.boxed()
@@ -841,6 +1483,7 @@ pub async fn reschedule_actor(
 			state.generation = next_generation;
 			state.client_id = res.client_id;
 			state.client_workflow_id = res.client_workflow_id;
+			state.snapshot = snapshot_enabled(input, &actor_setup.meta.build_kind);
 
 			// Save reschedule state in global state
 			state.reschedule_state = reschedule_state;
@@ -872,7 +1515,11 @@ async fn clear_ports_and_resources(
 	let (
 		build_res,
 		ingress_ports,
-		(selected_resources_cpu_millicores, selected_resources_memory_mib),
+		(
+			selected_resources_cpu_millicores,
+			selected_resources_memory_mib,
+			selected_resources_disk_mib,
+		),
 		_,
 	) = tokio::try_join!(
 		ctx.op(build::ops::get::Input {
@@ -886,9 +1533,12 @@ async fn clear_ports_and_resources(
 			",
 		),
 		sql_fetch_one!(
-			[ctx, (Option<i64>, Option<i64>), pool]
+			[ctx, (Option<i64>, Option<i64>, Option<i64>), pool]
 			"
-			SELECT selected_resources_cpu_millicores, selected_resources_memory_mib
+			SELECT
+				selected_resources_cpu_millicores,
+				selected_resources_memory_mib,
+				selected_resources_disk_mib
 			FROM state
 			",
 		),
@@ -915,6 +1565,7 @@ async fn clear_ports_and_resources(
 					Some(input.client_workflow_id),
 					selected_resources_memory_mib,
 					selected_resources_cpu_millicores,
+					selected_resources_disk_mib,
 					&tx,
 				)
 				.await
@@ -926,6 +1577,360 @@ async fn clear_ports_and_resources(
 	Ok(())
 }
 
+#[derive(Debug, Serialize, Deserialize, Hash)]
+struct ReleaseResourcesInput {
+	actor_id: Uuid,
+	image_id: Uuid,
+	client_id: Uuid,
+	client_workflow_id: Uuid,
+}
+
+/// Releases the client resources of a stopped actor without freeing its ingress ports.
+#[activity(ReleaseResources)]
+async fn release_resources(ctx: &ActivityCtx, input: &ReleaseResourcesInput) -> GlobalResult<()> {
+	let pool = &ctx.sqlite().await?;
+
+	let (
+		build_res,
+		(
+			selected_resources_cpu_millicores,
+			selected_resources_memory_mib,
+			selected_resources_disk_mib,
+		),
+		_,
+	) = tokio::try_join!(
+		ctx.op(build::ops::get::Input {
+			build_ids: vec![input.image_id],
+		}),
+		sql_fetch_one!(
+			[ctx, (Option<i64>, Option<i64>, Option<i64>), pool]
+			"
+			SELECT
+				selected_resources_cpu_millicores,
+				selected_resources_memory_mib,
+				selected_resources_disk_mib
+			FROM state
+			",
+		),
+		// Idempotent
+		sql_execute!(
+			[ctx, pool]
+			"
+			DELETE FROM ports_proxied
+			",
+		),
+	)?;
+	let build = unwrap_with!(build_res.builds.first(), BUILD_NOT_FOUND);
+
+	ctx.fdb()
+		.await?
+		.run(|tx, _mc| async move {
+			destroy::clear_ports_and_resources(
+				input.actor_id,
+				Some(build.kind),
+				// Keep ingress ports so the actor can be reached while stopped
+				Vec::new(),
+				Some(input.client_id),
+				Some(input.client_workflow_id),
+				selected_resources_memory_mib,
+				selected_resources_cpu_millicores,
+				selected_resources_disk_mib,
+				&tx,
+			)
+			.await
+		})
+		.custom_instrument(tracing::info_span!("actor_release_resources_tx"))
+		.await?;
+
+	// Not allocated to a client until woken. This also prevents resources from being released again if the
+	// actor is destroyed while stopped.
+	sql_execute!(
+		[ctx, pool]
+		"
+		UPDATE state
+		SET
+			client_id = NULL,
+			client_workflow_id = NULL,
+			client_wan_hostname = NULL
+		",
+	)
+	.await?;
+
+	Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize, Hash)]
+pub struct CheckIdleInput {
+	pub actor_id: Uuid,
+	pub idle_timeout_ms: i64,
+}
+
+/// Returns when to check again, or `None` if the actor has not received a request through guard (or become
+/// connectable) within its idle timeout.
+#[activity(CheckIdle)]
+pub async fn check_idle(ctx: &ActivityCtx, input: &CheckIdleInput) -> GlobalResult<Option<i64>> {
+	let pool = ctx.sqlite().await?;
+
+	let (connectable_ts,) = sql_fetch_one!(
+		[ctx, (Option<i64>,), pool]
+		"
+		SELECT connectable_ts
+		FROM state
+		",
+	)
+	.await?;
+
+	let last_request_ts = ctx
+		.fdb()
+		.await?
+		.run(|tx, _mc| async move {
+			let last_request_ts_key = keys::actor::LastRequestTsKey::new(input.actor_id);
+			let raw = tx
+				.get(&keys::subspace().pack(&last_request_ts_key), SNAPSHOT)
+				.await?;
+
+			raw.map(|raw| {
+				last_request_ts_key
+					.deserialize(&raw)
+					.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))
+			})
+			.transpose()
+		})
+		.custom_instrument(tracing::info_span!("actor_last_request_ts_tx"))
+		.await?;
+
+	let now = util::timestamp::now();
+	let last_active_ts = last_request_ts.max(connectable_ts).unwrap_or(now);
+	let idle_ts = last_active_ts + input.idle_timeout_ms;
+
+	Ok((idle_ts > now).then_some(idle_ts))
+}
+
+#[derive(Debug, Serialize, Deserialize, Hash)]
+struct ResolveSnapshotInput {
+	actor_id: Uuid,
+	generation: u32,
+}
+
+/// Presigns the URLs to restore the actor's latest snapshot from and to upload the new snapshot to.
+#[activity(ResolveSnapshot)]
+async fn resolve_snapshot(
+	ctx: &ActivityCtx,
+	input: &ResolveSnapshotInput,
+) -> GlobalResult<protocol::ActorSnapshot> {
+	let snapshot = ctx
+		.fdb()
+		.await?
+		.run(|tx, _mc| async move {
+			let fs_snapshot_key = keys::actor::FsSnapshotKey::new(input.actor_id);
+			let raw = tx
+				.get(&keys::subspace().pack(&fs_snapshot_key), SERIALIZABLE)
+				.await?;
+
+			raw.map(|raw| {
+				fs_snapshot_key
+					.deserialize(&raw)
+					.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))
+			})
+			.transpose()
+		})
+		.custom_instrument(tracing::info_span!("actor_fetch_fs_snapshot_tx"))
+		.await?;
+
+	// Presigned URLs are used by the client
+	let s3_client = s3_util::Client::with_bucket_and_endpoint(
+		ctx.config(),
+		SNAPSHOT_BUCKET,
+		s3_util::EndpointKind::EdgeInternal,
+	)
+	.await?;
+
+	let restore_url = if let Some(snapshot) = snapshot {
+		let presigned_req = s3_client
+			.get_object()
+			.bucket(s3_client.bucket())
+			.key(snapshot_key(input.actor_id, &snapshot.hash))
+			.presigned(
+				s3_util::aws_sdk_s3::presigning::PresigningConfig::builder()
+					.expires_in(SNAPSHOT_RESTORE_URL_EXPIRE)
+					.build()?,
+			)
+			.await?;
+
+		Some(presigned_req.uri().to_string())
+	} else {
+		None
+	};
+
+	let presigned_req = s3_client
+		.put_object()
+		.bucket(s3_client.bucket())
+		.key(snapshot_upload_key(input.actor_id, input.generation))
+		.presigned(
+			s3_util::aws_sdk_s3::presigning::PresigningConfig::builder()
+				.expires_in(SNAPSHOT_UPLOAD_URL_EXPIRE)
+				.build()?,
+		)
+		.await?;
+
+	Ok(protocol::ActorSnapshot {
+		restore_url,
+		upload_url: presigned_req.uri().to_string(),
+	})
+}
+
+#[derive(Debug, Serialize, Deserialize, Hash)]
+pub struct CommitSnapshotInput {
+	pub actor_id: Uuid,
+	pub generation: u32,
+	pub hash: String,
+	pub size_bytes: u64,
+}
+
+/// Moves an uploaded snapshot to its content addressed key and makes it the actor's latest snapshot.
+#[activity(CommitSnapshot)]
+pub async fn commit_snapshot(ctx: &ActivityCtx, input: &CommitSnapshotInput) -> GlobalResult<()> {
+	// The hash is reported by the client and used in the object key. Invalid snapshots are dropped instead
+	// of failing the actor.
+	if input.hash.len() != 64 || !input.hash.chars().all(|c| c.is_ascii_hexdigit()) {
+		tracing::warn!(actor_id=?input.actor_id, hash=%input.hash, "invalid snapshot hash");
+		return Ok(());
+	}
+
+	let s3_client = s3_util::Client::with_bucket(ctx.config(), SNAPSHOT_BUCKET).await?;
+	let upload_key = snapshot_upload_key(input.actor_id, input.generation);
+	let snapshot_key = snapshot_key(input.actor_id, &input.hash);
+
+	let upload_obj = s3_client
+		.head_object()
+		.bucket(s3_client.bucket())
+		.key(&upload_key)
+		.send()
+		.await?;
+	if upload_obj.content_length != i64::try_from(input.size_bytes).ok() {
+		tracing::warn!(
+			actor_id=?input.actor_id,
+			size_bytes=?input.size_bytes,
+			content_length=?upload_obj.content_length,
+			"snapshot size does not match upload",
+		);
+		return Ok(());
+	}
+
+	// Identical snapshots of the actor share the same object
+	let snapshot_exists = match s3_client
+		.head_object()
+		.bucket(s3_client.bucket())
+		.key(&snapshot_key)
+		.send()
+		.await
+	{
+		Ok(_) => true,
+		Err(err)
+			if err
+				.as_service_error()
+				.map(|err| err.is_not_found())
+				.unwrap_or_default() =>
+		{
+			false
+		}
+		Err(err) => return Err(err.into()),
+	};
+
+	if !snapshot_exists {
+		s3_client
+			.copy_object()
+			.bucket(s3_client.bucket())
+			.copy_source(format!("{}/{upload_key}", s3_client.bucket()))
+			.key(&snapshot_key)
+			.send()
+			.await?;
+	}
+
+	s3_client
+		.delete_object()
+		.bucket(s3_client.bucket())
+		.key(&upload_key)
+		.send()
+		.await?;
+
+	let unused_hash = ctx
+		.fdb()
+		.await?
+		.run(|tx, _mc| async move {
+			let fs_snapshot_key = keys::actor::FsSnapshotKey::new(input.actor_id);
+			let fs_snapshot_key_buf = keys::subspace().pack(&fs_snapshot_key);
+
+			let existing = tx
+				.get(&fs_snapshot_key_buf, SERIALIZABLE)
+				.await?
+				.map(|raw| {
+					fs_snapshot_key
+						.deserialize(&raw)
+						.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))
+				})
+				.transpose()?;
+
+			// Snapshots of previous generations can arrive late, never replace a newer snapshot
+			if let Some(existing) = &existing {
+				if existing.generation > input.generation {
+					return Ok((existing.hash != input.hash).then(|| input.hash.clone()));
+				}
+			}
+
+			tx.set(
+				&fs_snapshot_key_buf,
+				&fs_snapshot_key
+					.serialize(keys::actor::FsSnapshot {
+						hash: input.hash.clone(),
+						size_bytes: input.size_bytes,
+						generation: input.generation,
+						create_ts: util::timestamp::now(),
+					})
+					.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
+			);
+
+			// The replaced snapshot is not referenced by the actor anymore
+			Ok(existing
+				.map(|existing| existing.hash)
+				.filter(|hash| hash != &input.hash))
+		})
+		.custom_instrument(tracing::info_span!("actor_commit_fs_snapshot_tx"))
+		.await?;
+
+	if let Some(unused_hash) = unused_hash {
+		s3_client
+			.delete_object()
+			.bucket(s3_client.bucket())
+			.key(snapshot_key(input.actor_id, &unused_hash))
+			.send()
+			.await?;
+	}
+
+	Ok(())
+}
+
+/// Prefix of snapshots uploaded by the client before they are committed.
+pub(crate) fn snapshot_upload_prefix(actor_id: Uuid) -> String {
+	format!("upload/{actor_id}/")
+}
+
+/// Key of a snapshot uploaded by the client before it is committed.
+fn snapshot_upload_key(actor_id: Uuid, generation: u32) -> String {
+	format!("{}{generation}.tar.lz4", snapshot_upload_prefix(actor_id))
+}
+
+/// Prefix of the actor's committed snapshots.
+pub(crate) fn snapshot_prefix(actor_id: Uuid) -> String {
+	format!("snapshot/{actor_id}/")
+}
+
+/// Content addressed key of a committed snapshot. Snapshots are scoped to the actor so they can be deleted
+/// once the actor stops referencing them.
+fn snapshot_key(actor_id: Uuid, hash: &str) -> String {
+	format!("{}{hash}.tar.lz4", snapshot_prefix(actor_id))
+}
+
 #[derive(Debug, Serialize, Deserialize, Hash)]
 pub struct SetFinishedInput {}
 
@@ -945,3 +1950,118 @@ pub async fn set_finished(ctx: &ActivityCtx, input: &SetFinishedInput) -> Global
 
 	Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::types::{ActorLifecycle, ActorPlacement, ActorResources};
+
+	fn input(env_id: Uuid, tags: &[(&str, &str)], spread_by_tags: &[&str]) -> Input {
+		Input {
+			actor_id: Uuid::new_v4(),
+			env_id,
+			tags: tags
+				.iter()
+				.map(|(k, v)| (k.to_string(), v.to_string()))
+				.collect(),
+			resources: ActorResources::default_isolate(),
+			lifecycle: ActorLifecycle {
+				kill_timeout_ms: 0,
+				durable: false,
+				idle_timeout_ms: None,
+			},
+			image_id: Uuid::new_v4(),
+			root_user_enabled: false,
+			args: Vec::new(),
+			network_mode: NetworkMode::Bridge,
+			environment: Default::default(),
+			network_ports: Default::default(),
+			endpoint_type: None,
+			placement: ActorPlacement {
+				client_labels: Default::default(),
+				spread_by_tags: spread_by_tags.iter().map(|k| k.to_string()).collect(),
+			},
+		}
+	}
+
+	#[test]
+	fn allocate_score_empty() {
+		assert_eq!(0.0, allocate_score(&[]));
+
+		// Dimensions the client doesn't track are ignored
+		assert_eq!(0.0, allocate_score(&[(0, 0, 100)]));
+		assert_eq!(0.5, allocate_score(&[(0, 0, 100), (100, 100, 50)]));
+	}
+
+	#[test]
+	fn allocate_score_averages_dimensions() {
+		// 40 in use + 10 requested out of 100
+		assert_eq!(0.5, allocate_score(&[(100, 60, 10)]));
+
+		assert_eq!(0.75, allocate_score(&[(100, 60, 10), (1000, 0, 0)]));
+	}
+
+	#[test]
+	fn allocate_score_prefers_fuller_clients() {
+		let empty = allocate_score(&[(1000, 1000, 100), (1000, 1000, 100)]);
+		let full = allocate_score(&[(1000, 200, 100), (1000, 500, 100)]);
+
+		assert!(full > empty, "{full} <= {empty}");
+	}
+
+	#[test]
+	fn allocate_score_saturates() {
+		// Requesting more than remaining does not underflow
+		assert_eq!(1.0, allocate_score(&[(100, 10, 50)]));
+		// Remaining above total does not underflow
+		assert_eq!(0.0, allocate_score(&[(100, 200, 50)]));
+	}
+
+	#[test]
+	fn spread_group_none() {
+		let env_id = Uuid::new_v4();
+
+		assert_eq!(
+			None,
+			spread_group(&input(env_id, &[("a", "1")], &[])).unwrap()
+		);
+	}
+
+	#[test]
+	fn spread_group_ignores_order_and_duplicates() {
+		let env_id = Uuid::new_v4();
+		let tags = [("a", "1"), ("b", "2"), ("c", "3")];
+
+		let group = spread_group(&input(env_id, &tags, &["a", "b"])).unwrap();
+		assert!(group.is_some());
+		assert_eq!(
+			group,
+			spread_group(&input(env_id, &tags, &["b", "a", "b"])).unwrap()
+		);
+
+		// Tags that are not spread by don't matter
+		assert_eq!(
+			group,
+			spread_group(&input(env_id, &[("a", "1"), ("b", "2")], &["a", "b"])).unwrap()
+		);
+	}
+
+	#[test]
+	fn spread_group_differs() {
+		let env_id = Uuid::new_v4();
+		let group = spread_group(&input(env_id, &[("a", "1")], &["a"])).unwrap();
+
+		// Different tag value
+		assert_ne!(
+			group,
+			spread_group(&input(env_id, &[("a", "2")], &["a"])).unwrap()
+		);
+		// Missing tag
+		assert_ne!(group, spread_group(&input(env_id, &[], &["a"])).unwrap());
+		// Different environment
+		assert_ne!(
+			group,
+			spread_group(&input(Uuid::new_v4(), &[("a", "1")], &["a"])).unwrap()
+		);
+	}
+}
@@ -282,13 +282,14 @@ async fn insert_db(ctx: &ActivityCtx, input: &InsertDbInput) -> GlobalResult<i64
 			resources_memory_mib,
 			lifecycle_kill_timeout_ms,
 			lifecycle_durable,
+			lifecycle_idle_timeout_ms,
 			create_ts,
 			image_id,
 			args,
 			network_mode,
 			environment
 		)
-		VALUES (?, jsonb(?), ?, ?, ?, ?, ?, ?, jsonb(?), ?, jsonb(?))
+		VALUES (?, jsonb(?), ?, ?, ?, ?, ?, ?, ?, jsonb(?), ?, jsonb(?))
 		",
 		input.env_id,
 		serde_json::to_string(&input.tags)?,
@@ -296,6 +297,7 @@ async fn insert_db(ctx: &ActivityCtx, input: &InsertDbInput) -> GlobalResult<i64
 		input.resources.memory_mib as i32,
 		input.lifecycle.kill_timeout_ms,
 		input.lifecycle.durable,
+		input.lifecycle.idle_timeout_ms,
 		create_ts,
 		input.image_id,
 		serde_json::to_string(&input.args)?,
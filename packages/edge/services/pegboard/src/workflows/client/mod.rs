@@ -33,17 +33,17 @@ pub struct Input {
 
 #[workflow]
 pub async fn pegboard_client(ctx: &mut WorkflowCtx, input: &Input) -> GlobalResult<()> {
-	migrations::run(ctx).await?;
-
-	ctx.activity(InsertDbInput {
-		flavor: input.flavor,
-	})
-	.await?;
-
-	ctx.activity(PublishRegisteredInput {
-		client_id: input.client_id,
-	})
-	.await?;
+	match ctx.check_version(2).await? {
+		1 => register(ctx, input.client_id, input.flavor).await?,
+		_latest => {
+			// Registration only runs once, checkpoint it so it isn't replayed for the lifetime of the client
+			let client_id = input.client_id;
+			let flavor = input.flavor;
+			ctx.v(2)
+				.snapshot(|ctx| async move { register(ctx, client_id, flavor).await }.boxed())
+				.await?
+		}
+	}
 
 	ctx.loope(State::default(), |ctx, state| {
 		let client_id = input.client_id;
@@ -135,33 +135,50 @@ pub async fn pegboard_client(ctx: &mut WorkflowCtx, input: &Input) -> GlobalResu
 							// NOTE: This should not be parallelized because signals should be sent in order
 							// Forward to actor workflows
 							for event in events {
-								#[allow(irrefutable_let_patterns)]
-								if let protocol::Event::ActorStateUpdate {
-									actor_id,
-									generation,
-									state,
-								} = event.inner.deserialize()?
-								{
-									let res = ctx
-										.signal(crate::workflows::actor::StateUpdate {
+								let (actor_id, res) = match event.inner.deserialize()? {
+									protocol::Event::ActorStateUpdate {
+										actor_id,
+										generation,
+										state,
+									} => (
+										actor_id,
+										ctx.signal(crate::workflows::actor::StateUpdate {
 											generation,
 											state,
 										})
 										.to_workflow::<crate::workflows::actor::Workflow>()
 										.tag("actor_id", actor_id)
 										.send()
-										.await;
+										.await,
+									),
+									protocol::Event::ActorSnapshotCreated {
+										actor_id,
+										generation,
+										hash,
+										size_bytes,
+									} => (
+										actor_id,
+										ctx.signal(crate::workflows::actor::SnapshotCreated {
+											generation,
+											hash,
+											size_bytes,
+										})
+										.to_workflow::<crate::workflows::actor::Workflow>()
+										.tag("actor_id", actor_id)
+										.send()
+										.await,
+									),
+								};
 
-									if let Some(WorkflowError::WorkflowNotFound) =
-										res.as_workflow_error()
-									{
-										tracing::warn!(
-											?actor_id,
-											"actor workflow not found, likely already stopped"
-										);
-									} else {
-										res?;
-									}
+								if let Some(WorkflowError::WorkflowNotFound) =
+									res.as_workflow_error()
+								{
+									tracing::warn!(
+										?actor_id,
+										"actor workflow not found, likely already stopped"
+									);
+								} else {
+									res?;
 								}
 							}
 						}
@@ -317,6 +334,20 @@ struct State {
 	drain_timeout_ts: Option<i64>,
 }
 
+async fn register(
+	ctx: &mut WorkflowCtx,
+	client_id: Uuid,
+	flavor: ClientFlavor,
+) -> GlobalResult<()> {
+	migrations::run(ctx).await?;
+
+	ctx.activity(InsertDbInput { flavor }).await?;
+
+	ctx.activity(PublishRegisteredInput { client_id }).await?;
+
+	Ok(())
+}
+
 #[derive(Debug, Serialize, Deserialize, Hash)]
 struct InsertDbInput {
 	flavor: ClientFlavor,
@@ -355,6 +386,15 @@ async fn insert_fdb(ctx: &ActivityCtx, input: &InsertFdbInput) -> GlobalResult<(
 		input.system.memory.total_memory / 1024 / 1024 - input.config.reserved_resources.memory;
 	// Millicores
 	let allocable_cpu = input.system.cpu.physical_core_count * 1000;
+	// MiB, actor file systems are assumed to live on the largest disk
+	let allocable_disk = input
+		.system
+		.storage
+		.disks
+		.iter()
+		.map(|disk| disk.total_space / 1024 / 1024)
+		.max()
+		.map(|total_disk| total_disk.saturating_sub(input.config.reserved_resources.disk));
 
 	ctx.fdb()
 		.await?
@@ -444,6 +484,26 @@ async fn insert_fdb(ctx: &ActivityCtx, input: &InsertFdbInput) -> GlobalResult<(
 						.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
 				);
 
+				if let Some(allocable_disk) = allocable_disk {
+					// Set remaining disk
+					let remaining_disk_key = keys::client::RemainingDiskKey::new(input.client_id);
+					tx.set(
+						&keys::subspace().pack(&remaining_disk_key),
+						&remaining_disk_key
+							.serialize(allocable_disk)
+							.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
+					);
+
+					// Set total disk
+					let total_disk_key = keys::client::TotalDiskKey::new(input.client_id);
+					tx.set(
+						&keys::subspace().pack(&total_disk_key),
+						&total_disk_key
+							.serialize(allocable_disk)
+							.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
+					);
+				}
+
 				// Set last ping
 				let last_ping_ts = util::timestamp::now();
 				tx.set(
@@ -456,6 +516,15 @@ async fn insert_fdb(ctx: &ActivityCtx, input: &InsertFdbInput) -> GlobalResult<(
 				(allocable_memory, last_ping_ts)
 			};
 
+			// Labels can change between registrations
+			let labels_key = keys::client::LabelsKey::new(input.client_id);
+			tx.set(
+				&keys::subspace().pack(&labels_key),
+				&labels_key
+					.serialize(input.config.labels.clone())
+					.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
+			);
+
 			// Insert into index (same as the `update_allocation_idx` op with `AddIdx`)
 			let allocation_key = keys::datacenter::ClientsByRemainingMemKey::new(
 				input.flavor,
@@ -0,0 +1,189 @@
+use std::time::Duration;
+
+use chirp_workflow::prelude::*;
+use fdb_util::FormalKey;
+use foundationdb as fdb;
+use rivet_operation::prelude::proto::backend;
+
+const IDLE_TIMEOUT_MS: i64 = util::duration::seconds(3);
+
+#[workflow_test]
+async fn actor_idle_sleep_and_wake(ctx: TestCtx) {
+	let actor_id = create_actor(&ctx).await;
+
+	// Stopped after not receiving any requests
+	wait_for_connectable(&ctx, actor_id, false).await;
+
+	// Started again on the next request
+	let mut ready_sub = ctx
+		.subscribe::<pegboard::workflows::actor::Ready>(("actor_id", actor_id))
+		.await
+		.unwrap();
+
+	ctx.signal(pegboard::workflows::actor::Wake {})
+		.to_workflow::<pegboard::workflows::actor::Workflow>()
+		.tag("actor_id", actor_id)
+		.send()
+		.await
+		.unwrap();
+
+	tokio::time::timeout(Duration::from_secs(60), ready_sub.next())
+		.await
+		.expect("actor was never woken")
+		.unwrap();
+
+	// Waking a running actor is a noop
+	ctx.signal(pegboard::workflows::actor::Wake {})
+		.to_workflow::<pegboard::workflows::actor::Workflow>()
+		.tag("actor_id", actor_id)
+		.send()
+		.await
+		.unwrap();
+	tokio::time::sleep(Duration::from_millis(500)).await;
+	assert!(get_actor(&ctx, actor_id).await.connectable_ts.is_some());
+
+	// Goes back to sleep
+	wait_for_connectable(&ctx, actor_id, false).await;
+
+	destroy_actor(&ctx, actor_id).await;
+}
+
+#[workflow_test]
+async fn actor_idle_activity_keeps_awake(ctx: TestCtx) {
+	let actor_id = create_actor(&ctx).await;
+
+	// Report activity the same way guard does for several idle timeouts
+	let start = std::time::Instant::now();
+	while start.elapsed() < Duration::from_millis(3 * IDLE_TIMEOUT_MS as u64) {
+		record_activity(&ctx, actor_id).await;
+		tokio::time::sleep(Duration::from_millis(IDLE_TIMEOUT_MS as u64 / 3)).await;
+
+		assert!(
+			get_actor(&ctx, actor_id).await.connectable_ts.is_some(),
+			"actor was stopped while active"
+		);
+	}
+
+	// Stopped once the activity stops
+	wait_for_connectable(&ctx, actor_id, false).await;
+
+	destroy_actor(&ctx, actor_id).await;
+}
+
+/// Creates a durable actor with an idle timeout and waits for it to be ready.
+async fn create_actor(ctx: &TestCtx) -> Uuid {
+	let game_res = op!([ctx] faker_game {
+		..Default::default()
+	})
+	.await
+	.unwrap();
+	let env_id = game_res.prod_env_id.unwrap().as_uuid();
+
+	let build_res = op!([ctx] faker_build {
+		env_id: game_res.prod_env_id,
+		image: backend::faker::Image::DsEcho as i32,
+	})
+	.await
+	.unwrap();
+
+	let actor_id = Uuid::new_v4();
+
+	let mut ready_sub = ctx
+		.subscribe::<pegboard::workflows::actor::Ready>(("actor_id", actor_id))
+		.await
+		.unwrap();
+
+	ctx.workflow(pegboard::workflows::actor::Input {
+		actor_id,
+		env_id,
+		tags: Default::default(),
+		resources: pegboard::types::ActorResources::default_isolate(),
+		lifecycle: pegboard::types::ActorLifecycle {
+			kill_timeout_ms: 0,
+			durable: true,
+			idle_timeout_ms: Some(IDLE_TIMEOUT_MS),
+		},
+		image_id: build_res.build_id.unwrap().as_uuid(),
+		root_user_enabled: false,
+		args: Vec::new(),
+		network_mode: pegboard::types::NetworkMode::Bridge,
+		environment: Default::default(),
+		network_ports: Default::default(),
+		endpoint_type: None,
+		placement: Default::default(),
+	})
+	.tag("actor_id", actor_id)
+	.dispatch()
+	.await
+	.unwrap();
+
+	tokio::time::timeout(Duration::from_secs(60), ready_sub.next())
+		.await
+		.expect("actor never became ready")
+		.unwrap();
+
+	actor_id
+}
+
+async fn destroy_actor(ctx: &TestCtx, actor_id: Uuid) {
+	ctx.signal(pegboard::workflows::actor::Destroy {
+		override_kill_timeout_ms: None,
+	})
+	.to_workflow::<pegboard::workflows::actor::Workflow>()
+	.tag("actor_id", actor_id)
+	.send()
+	.await
+	.unwrap();
+}
+
+async fn get_actor(ctx: &TestCtx, actor_id: Uuid) -> pegboard::types::Actor {
+	ctx.op(pegboard::ops::actor::get::Input {
+		actor_ids: vec![actor_id],
+		endpoint_type: None,
+		allow_errors: false,
+	})
+	.await
+	.unwrap()
+	.actors
+	.into_iter()
+	.next()
+	.unwrap()
+}
+
+/// Polls until the actor is (or is no longer) connectable.
+async fn wait_for_connectable(ctx: &TestCtx, actor_id: Uuid, connectable: bool) {
+	tokio::time::timeout(Duration::from_secs(60), async {
+		loop {
+			if get_actor(ctx, actor_id).await.connectable_ts.is_some() == connectable {
+				break;
+			}
+
+			tokio::time::sleep(Duration::from_millis(250)).await;
+		}
+	})
+	.await
+	.expect("actor connectable state never changed");
+}
+
+/// Records a request to the actor like guard's actor activity function.
+async fn record_activity(ctx: &TestCtx, actor_id: Uuid) {
+	let now = util::timestamp::now();
+
+	ctx.pools()
+		.fdb()
+		.unwrap()
+		.run(|tx, _mc| async move {
+			let last_request_ts_key = pegboard::keys::actor::LastRequestTsKey::new(actor_id);
+
+			tx.set(
+				&pegboard::keys::subspace().pack(&last_request_ts_key),
+				&last_request_ts_key
+					.serialize(now)
+					.map_err(|x| fdb::FdbBindingError::CustomError(x.into()))?,
+			);
+
+			Ok(())
+		})
+		.await
+		.unwrap();
+}
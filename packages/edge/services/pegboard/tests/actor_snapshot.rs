@@ -0,0 +1,109 @@
+use std::time::Duration;
+
+use chirp_workflow::prelude::*;
+use pegboard::protocol;
+use rivet_operation::prelude::proto::backend;
+
+/// A durable container that takes longer than the regular exit threshold to upload its snapshot after
+/// stopping must not be considered lost.
+#[workflow_test]
+async fn actor_snapshot_slow_upload(ctx: TestCtx) {
+	let actor_id = create_actor(&ctx).await;
+
+	// Report the actor as stopped without exiting, like the client does while uploading the snapshot
+	ctx.signal(pegboard::workflows::actor::StateUpdate {
+		generation: 0,
+		state: protocol::ActorState::Stopped,
+	})
+	.to_workflow::<pegboard::workflows::actor::Workflow>()
+	.tag("actor_id", actor_id)
+	.send()
+	.await
+	.unwrap();
+
+	// Longer than `ACTOR_EXIT_THRESHOLD_MS`. A lost durable actor would be made unconnectable and
+	// rescheduled
+	tokio::time::sleep(Duration::from_secs(10)).await;
+	assert!(
+		get_actor(&ctx, actor_id).await.connectable_ts.is_some(),
+		"actor was lost while uploading its snapshot"
+	);
+
+	ctx.signal(pegboard::workflows::actor::Destroy {
+		override_kill_timeout_ms: None,
+	})
+	.to_workflow::<pegboard::workflows::actor::Workflow>()
+	.tag("actor_id", actor_id)
+	.send()
+	.await
+	.unwrap();
+}
+
+/// Creates a durable container actor and waits for it to be ready.
+async fn create_actor(ctx: &TestCtx) -> Uuid {
+	let game_res = op!([ctx] faker_game {
+		..Default::default()
+	})
+	.await
+	.unwrap();
+	let env_id = game_res.prod_env_id.unwrap().as_uuid();
+
+	let build_res = op!([ctx] faker_build {
+		env_id: game_res.prod_env_id,
+		image: backend::faker::Image::DsEcho as i32,
+	})
+	.await
+	.unwrap();
+
+	let actor_id = Uuid::new_v4();
+
+	let mut ready_sub = ctx
+		.subscribe::<pegboard::workflows::actor::Ready>(("actor_id", actor_id))
+		.await
+		.unwrap();
+
+	ctx.workflow(pegboard::workflows::actor::Input {
+		actor_id,
+		env_id,
+		tags: Default::default(),
+		resources: pegboard::types::ActorResources::default_isolate(),
+		lifecycle: pegboard::types::ActorLifecycle {
+			kill_timeout_ms: 0,
+			durable: true,
+			idle_timeout_ms: None,
+		},
+		image_id: build_res.build_id.unwrap().as_uuid(),
+		root_user_enabled: false,
+		args: Vec::new(),
+		network_mode: pegboard::types::NetworkMode::Bridge,
+		environment: Default::default(),
+		network_ports: Default::default(),
+		endpoint_type: None,
+		placement: Default::default(),
+	})
+	.tag("actor_id", actor_id)
+	.dispatch()
+	.await
+	.unwrap();
+
+	tokio::time::timeout(Duration::from_secs(60), ready_sub.next())
+		.await
+		.expect("actor never became ready")
+		.unwrap();
+
+	actor_id
+}
+
+async fn get_actor(ctx: &TestCtx, actor_id: Uuid) -> pegboard::types::Actor {
+	ctx.op(pegboard::ops::actor::get::Input {
+		actor_ids: vec![actor_id],
+		endpoint_type: None,
+		allow_errors: false,
+	})
+	.await
+	.unwrap()
+	.actors
+	.into_iter()
+	.next()
+	.unwrap()
+}
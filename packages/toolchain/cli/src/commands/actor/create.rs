@@ -93,6 +93,19 @@ pub struct Opts {
 	#[clap(long)]
 	durable: bool,
 
+	/// Stop the actor after this many seconds without requests, it is started again on the next request
+	#[clap(long, requires = "durable")]
+	idle_timeout: Option<i64>,
+
+	/// Labels a client must have to run the actor (key=value format)
+	#[clap(long)]
+	client_labels: Option<String>,
+
+	/// Actor tag keys to spread across clients, actors with the same values for these tags are placed on
+	/// different clients
+	#[clap(long = "spread-by-tag")]
+	spread_by_tags: Option<Vec<String>>,
+
 	/// If included, the `current` tag will not be automatically inserted to the build tag
 	#[clap(long)]
 	no_build_current_tag: bool,
@@ -126,6 +139,20 @@ impl Opts {
 			HashMap::new()
 		};
 
+		// Parse placement
+		let placement = if self.client_labels.is_some() || self.spread_by_tags.is_some() {
+			Some(Box::new(models::ActorsPlacement {
+				client_labels: self
+					.client_labels
+					.as_ref()
+					.map(|t| kv_str::from_str::<HashMap<String, String>>(t))
+					.transpose()?,
+				spread_by_tags: self.spread_by_tags.clone(),
+			}))
+		} else {
+			None
+		};
+
 		// Parse build ID
 		let mut build_id = self
 			.build
@@ -284,7 +311,9 @@ impl Opts {
 			lifecycle: Some(Box::new(models::ActorsLifecycle {
 				durable: Some(self.durable),
 				kill_timeout: self.kill_timeout,
+				idle_timeout: self.idle_timeout.map(|x| x * 1000),
 			})),
+			placement,
 		};
 
 		let response = apis::actors_api::actors_create(
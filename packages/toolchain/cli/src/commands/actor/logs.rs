@@ -22,6 +22,10 @@ pub struct Opts {
 	#[clap(long)]
 	no_timestamps: bool,
 
+	/// Stream new logs as they are written (default)
+	#[clap(long, short = 'f', conflicts_with = "no_follow")]
+	follow: bool,
+
 	/// Display logs and exit (do not continue following new logs)
 	#[clap(long)]
 	no_follow: bool,
@@ -50,7 +54,7 @@ impl Opts {
 					.stream
 					.clone()
 					.unwrap_or(toolchain::util::actor::logs::LogStream::All),
-				follow: !self.no_follow,
+				follow: self.follow || !self.no_follow,
 				print_type,
 				exit_on_ctrl_c: true
 			},
@@ -1,11 +1,19 @@
 use anyhow::*;
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use std::collections::HashMap;
 use toolchain::{
 	rivet_api::{apis, models},
 	ToolchainCtx,
 };
 
+#[derive(ValueEnum, Clone)]
+enum LoadBalancingStrategy {
+	Random,
+	RoundRobin,
+	LeastInFlight,
+	ConsistentHash,
+}
+
 /// Create or update a route endpoint
 #[derive(Parser)]
 pub struct Opts {
@@ -35,6 +43,72 @@ pub struct Opts {
 	/// Selector tags in key=value comma-separated format (e.g. type=function,function=my-function)
 	#[clap(long)]
 	selector_tags: Option<String>,
+
+	/// Selector tags of canary actors in key=value comma-separated format, splits requests between the
+	/// selector tags and the canary selector tags
+	#[clap(long, requires = "canary_weight")]
+	canary_selector_tags: Option<String>,
+
+	/// Percent of requests sent to the canary actors
+	#[clap(long)]
+	canary_weight: Option<i32>,
+
+	/// Redirect requests to this URL instead of routing them to actors
+	#[clap(long, conflicts_with_all = ["selector_tags", "canary_selector_tags", "canary_weight", "response_status"])]
+	redirect: Option<String>,
+
+	/// Redirect status code (301, 302, 303, 307 or 308)
+	#[clap(long, requires = "redirect")]
+	redirect_status: Option<i32>,
+
+	/// Append the request's path and query to the redirect location (true/false)
+	#[clap(long, requires = "redirect")]
+	redirect_preserve_path: Option<bool>,
+
+	/// Respond with a fixed response with this status code instead of routing requests to actors
+	#[clap(long, conflicts_with_all = ["selector_tags", "canary_selector_tags", "canary_weight"])]
+	response_status: Option<i32>,
+
+	/// Body of the fixed response
+	#[clap(long, requires = "response_status")]
+	response_body: Option<String>,
+
+	/// Content type of the fixed response
+	#[clap(long, requires = "response_status")]
+	response_content_type: Option<String>,
+
+	/// Only match requests with these HTTP methods, comma-separated (e.g. GET,POST). Pass an empty value to
+	/// match any method
+	#[clap(long)]
+	match_methods: Option<String>,
+
+	/// Only match requests with this header, as name=value or name to only require the header to be
+	/// present. Can be passed multiple times
+	#[clap(long)]
+	match_header: Vec<String>,
+
+	/// How requests are distributed between the route's actors
+	#[clap(long)]
+	load_balancing: Option<LoadBalancingStrategy>,
+
+	/// Header to hash with the consistent-hash strategy
+	#[clap(long, conflicts_with_all = ["hash_cookie", "hash_query"])]
+	hash_header: Option<String>,
+
+	/// Cookie to hash with the consistent-hash strategy
+	#[clap(long, conflicts_with = "hash_query")]
+	hash_cookie: Option<String>,
+
+	/// Query parameter to hash with the consistent-hash strategy
+	#[clap(long)]
+	hash_query: Option<String>,
+
+	/// Remove all request conditions of the route
+	#[clap(long, conflicts_with_all = ["match_methods", "match_header"])]
+	clear_match: bool,
+
+	#[clap(flatten)]
+	middleware: super::middleware::MiddlewareOpts,
 }
 
 impl Opts {
@@ -52,6 +126,12 @@ impl Opts {
 			.map(|tags| kv_str::from_str::<HashMap<String, String>>(tags))
 			.transpose()
 			.context("Failed to parse selector tags")?;
+		let canary_selector_tags = self
+			.canary_selector_tags
+			.as_ref()
+			.map(|tags| kv_str::from_str::<HashMap<String, String>>(tags))
+			.transpose()
+			.context("Failed to parse canary selector tags")?;
 
 		// Build route update body
 		let mut update_route_body = models::RoutesUpdateRouteBody {
@@ -71,20 +151,24 @@ impl Opts {
 			path: route.as_ref().map(|r| r.path.clone()).unwrap_or_else(|| "/".to_string()),
 			route_subpaths: route.as_ref().map(|r| r.route_subpaths).unwrap_or(true),
 			strip_prefix: route.as_ref().map(|r| r.strip_prefix).unwrap_or(true),
-			target: Box::new(models::RoutesRouteTarget {
-				actors: Some(Box::new(models::RoutesRouteTargetActors {
-					selector_tags: route
-						.as_ref()
-						.and_then(|r| r.target.actors.as_ref().map(|a| a.selector_tags.clone()))
-						.unwrap_or_else(|| {
-							// Default selector tags for functions
-							let mut tags = HashMap::new();
-							tags.insert("type".to_string(), "function".to_string());
-							tags.insert("function".to_string(), self.name.clone());
-							tags
-						}),
-				})),
+			// Keep the existing target unless it is changed below
+			target: route.as_ref().map(|r| r.target.clone()).unwrap_or_else(|| {
+				// Default selector tags for functions
+				let mut tags = HashMap::new();
+				tags.insert("type".to_string(), "function".to_string());
+				tags.insert("function".to_string(), self.name.clone());
+
+				Box::new(models::RoutesRouteTarget {
+					actors: Some(Box::new(models::RoutesRouteTargetActors {
+						selector_tags: tags,
+					})),
+					..models::RoutesRouteTarget::new()
+				})
 			}),
+			// Keeps the existing request conditions and load balancing unless changed below
+			request_match: None,
+			load_balancing: None,
+			middleware: route.as_ref().map(|r| r.middleware.clone()),
 		};
 
 		// Override with any provided options
@@ -104,12 +188,50 @@ impl Opts {
 			update_route_body.strip_prefix = strip_prefix;
 		}
 
-		if let Some(tags) = selector_tags {
-			if let Some(actors) = &mut update_route_body.target.actors {
-				actors.selector_tags = tags;
+		self.apply_target(&mut update_route_body.target, selector_tags, canary_selector_tags)?;
+
+		if self.clear_match {
+			update_route_body.request_match = Some(Box::new(models::RoutesRouteMatch::new()));
+		} else if self.match_methods.is_some() || !self.match_header.is_empty() {
+			let mut request_match = route
+				.as_ref()
+				.map(|r| r.request_match.clone())
+				.unwrap_or_default();
+
+			if let Some(methods) = &self.match_methods {
+				request_match.methods = Some(
+					methods
+						.split(',')
+						.map(|method| method.trim().to_uppercase())
+						.filter(|method| !method.is_empty())
+						.collect(),
+				);
 			}
+
+			if !self.match_header.is_empty() {
+				request_match.headers = Some(
+					self.match_header
+						.iter()
+						.map(|header| match header.split_once('=') {
+							Some((name, value)) => models::RoutesRouteHeaderMatch {
+								name: name.trim().to_lowercase(),
+								value: Some(value.to_string()),
+							},
+							None => models::RoutesRouteHeaderMatch::new(header.trim().to_lowercase()),
+						})
+						.collect(),
+				);
+			}
+
+			update_route_body.request_match = Some(request_match);
 		}
 
+		update_route_body.load_balancing = self.load_balancing(route.as_ref())?;
+
+		let mut middleware = update_route_body.middleware.take().unwrap_or_default();
+		self.middleware.apply(&mut middleware)?;
+		update_route_body.middleware = Some(middleware);
+
 		// Create/update route
 		let result = apis::routes_api::routes_update(
 			&ctx.openapi_config_cloud,
@@ -141,6 +263,161 @@ impl Opts {
 	}
 }
 
+impl Opts {
+	/// Changes the route's target according to the target flags, keeping the rest of the existing target.
+	fn apply_target(
+		&self,
+		target: &mut models::RoutesRouteTarget,
+		selector_tags: Option<HashMap<String, String>>,
+		canary_selector_tags: Option<HashMap<String, String>>,
+	) -> Result<()> {
+		if let Some(location) = &self.redirect {
+			let existing = target.redirect.take();
+			*target = models::RoutesRouteTarget {
+				redirect: Some(Box::new(models::RoutesRouteTargetRedirect {
+					location: location.clone(),
+					status: self
+						.redirect_status
+						.or_else(|| existing.as_ref().map(|r| r.status))
+						.unwrap_or(302),
+					preserve_path: self
+						.redirect_preserve_path
+						.or_else(|| existing.as_ref().map(|r| r.preserve_path))
+						.unwrap_or_default(),
+				})),
+				..models::RoutesRouteTarget::new()
+			};
+		} else if let Some(redirect) = &mut target.redirect {
+			if let Some(status) = self.redirect_status {
+				redirect.status = status;
+			}
+			if let Some(preserve_path) = self.redirect_preserve_path {
+				redirect.preserve_path = preserve_path;
+			}
+		} else if self.redirect_status.is_some() || self.redirect_preserve_path.is_some() {
+			bail!("route does not redirect, pass --redirect");
+		}
+
+		if let Some(status) = self.response_status {
+			let existing = target.response.take();
+			*target = models::RoutesRouteTarget {
+				response: Some(Box::new(models::RoutesRouteTargetResponse {
+					status,
+					content_type: self
+						.response_content_type
+						.clone()
+						.or_else(|| existing.as_ref().and_then(|r| r.content_type.clone())),
+					body: self
+						.response_body
+						.clone()
+						.or_else(|| existing.as_ref().map(|r| r.body.clone()))
+						.unwrap_or_default(),
+				})),
+				..models::RoutesRouteTarget::new()
+			};
+		}
+
+		// Selector tags of the actors the route currently targets, if any
+		let existing_selector_tags = target
+			.actors
+			.as_ref()
+			.map(|a| a.selector_tags.clone())
+			.or_else(|| target.split.as_ref().map(|s| s.selector_tags.clone()));
+
+		if let Some(canary_weight) = self.canary_weight {
+			let existing = target.split.take();
+			let Some(canary_selector_tags) = canary_selector_tags
+				.or_else(|| existing.as_ref().map(|s| s.canary_selector_tags.clone()))
+			else {
+				bail!("route has no canary, pass --canary-selector-tags");
+			};
+			let Some(selector_tags) = selector_tags.or(existing_selector_tags) else {
+				bail!("route does not target actors, pass --selector-tags");
+			};
+
+			*target = models::RoutesRouteTarget {
+				split: Some(Box::new(models::RoutesRouteTargetSplit {
+					selector_tags,
+					canary_selector_tags,
+					canary_weight,
+				})),
+				..models::RoutesRouteTarget::new()
+			};
+		} else if let Some(selector_tags) = selector_tags {
+			if let Some(split) = &mut target.split {
+				split.selector_tags = selector_tags;
+			} else {
+				*target = models::RoutesRouteTarget {
+					actors: Some(Box::new(models::RoutesRouteTargetActors { selector_tags })),
+					..models::RoutesRouteTarget::new()
+				};
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Builds the route's load balancing from the load balancing flags. Returns `None` to keep the existing
+	/// load balancing if no flags were passed.
+	fn load_balancing(
+		&self,
+		route: Option<&models::RoutesRoute>,
+	) -> Result<Option<Box<models::RoutesLoadBalancing>>> {
+		let hash_key = match (&self.hash_header, &self.hash_cookie, &self.hash_query) {
+			(None, None, None) => None,
+			(header, cookie, query) => Some(Box::new(models::RoutesHashKey {
+				header: header.clone(),
+				cookie: cookie.clone(),
+				query: query.clone(),
+			})),
+		};
+
+		let has_hash_key = hash_key.is_some();
+		if self.load_balancing.is_none() && !has_hash_key {
+			return Ok(None);
+		}
+
+		let mut load_balancing = route
+			.map(|r| r.load_balancing.clone())
+			.unwrap_or_default();
+
+		if let Some(strategy) = &self.load_balancing {
+			load_balancing.strategy = match strategy {
+				LoadBalancingStrategy::Random => models::RoutesLoadBalancingStrategy::Random,
+				LoadBalancingStrategy::RoundRobin => models::RoutesLoadBalancingStrategy::RoundRobin,
+				LoadBalancingStrategy::LeastInFlight => {
+					models::RoutesLoadBalancingStrategy::LeastInFlight
+				}
+				LoadBalancingStrategy::ConsistentHash => {
+					models::RoutesLoadBalancingStrategy::ConsistentHash
+				}
+			};
+		}
+
+		if let Some(hash_key) = hash_key {
+			load_balancing.hash_key = Some(hash_key);
+		}
+
+		match load_balancing.strategy {
+			models::RoutesLoadBalancingStrategy::ConsistentHash => {
+				ensure!(
+					load_balancing.hash_key.is_some(),
+					"the consistent-hash strategy requires --hash-header, --hash-cookie or --hash-query"
+				);
+			}
+			_ => {
+				ensure!(
+					!has_hash_key,
+					"hash flags require --load-balancing consistent-hash"
+				);
+				load_balancing.hash_key = None;
+			}
+		}
+
+		Ok(Some(load_balancing))
+	}
+}
+
 // Helper function to get route if it exists
 async fn get_route(ctx: &ToolchainCtx, env: &str, route_id: &str) -> Result<Option<models::RoutesRoute>> {
 	let routes_response = apis::routes_api::routes_list(
@@ -0,0 +1,177 @@
+use anyhow::*;
+use clap::Parser;
+use toolchain::rivet_api::{apis, models};
+
+/// Middleware flags shared between commands that edit a middleware policy. Flags that are not passed
+/// leave the existing policy untouched.
+#[derive(Parser)]
+pub struct MiddlewareOpts {
+	/// Max requests per client per rate limit period
+	#[clap(long, requires = "rate_limit_period")]
+	rate_limit_requests: Option<i32>,
+
+	/// Rate limit period in seconds
+	#[clap(long, requires = "rate_limit_requests")]
+	rate_limit_period: Option<i32>,
+
+	/// Share the rate limit between all edge instances (true/false)
+	#[clap(long)]
+	rate_limit_distributed: Option<bool>,
+
+	/// Max concurrent requests per client
+	#[clap(long)]
+	max_in_flight: Option<i32>,
+
+	/// Max attempts when connecting to the actor fails
+	#[clap(long, requires = "retry_initial_interval")]
+	retry_max_attempts: Option<i32>,
+
+	/// Initial retry backoff in milliseconds, doubled after every attempt
+	#[clap(long, requires = "retry_max_attempts")]
+	retry_initial_interval: Option<i32>,
+
+	/// Request timeout in seconds
+	#[clap(long)]
+	request_timeout: Option<i32>,
+}
+
+impl MiddlewareOpts {
+	pub fn apply(&self, policy: &mut models::RoutesMiddlewarePolicy) -> Result<()> {
+		if let (Some(requests), Some(period)) = (self.rate_limit_requests, self.rate_limit_period) {
+			let distributed = policy
+				.rate_limit
+				.as_ref()
+				.and_then(|rate_limit| rate_limit.distributed);
+			policy.rate_limit = Some(Box::new(models::RoutesRateLimitPolicy {
+				requests,
+				period,
+				distributed,
+			}));
+		}
+
+		if let Some(distributed) = self.rate_limit_distributed {
+			let Some(rate_limit) = &mut policy.rate_limit else {
+				bail!("--rate-limit-distributed requires a rate limit, pass --rate-limit-requests and --rate-limit-period");
+			};
+			rate_limit.distributed = Some(distributed);
+		}
+
+		if let Some(max_in_flight) = self.max_in_flight {
+			policy.max_in_flight = Some(max_in_flight);
+		}
+
+		if let (Some(max_attempts), Some(initial_interval)) =
+			(self.retry_max_attempts, self.retry_initial_interval)
+		{
+			policy.retry = Some(Box::new(models::RoutesRetryPolicy {
+				max_attempts,
+				initial_interval,
+			}));
+		}
+
+		if let Some(request_timeout) = self.request_timeout {
+			policy.request_timeout = Some(request_timeout);
+		}
+
+		Ok(())
+	}
+}
+
+/// Show or update the middleware policy applied to all routes of an environment
+#[derive(Parser)]
+pub struct Opts {
+	/// Specify the environment (will prompt if not specified)
+	#[clap(long, alias = "env", short = 'e')]
+	environment: Option<String>,
+
+	#[clap(flatten)]
+	middleware: MiddlewareOpts,
+}
+
+impl Opts {
+	pub async fn execute(&self) -> Result<()> {
+		let ctx = crate::util::login::load_or_login().await?;
+		let env = crate::util::env::get_or_select(&ctx, self.environment.as_ref()).await?;
+
+		let res = apis::routes_middleware_api::routes_middleware_get(
+			&ctx.openapi_config_cloud,
+			Some(&ctx.project.name_id.to_string()),
+			Some(&env),
+		)
+		.await?;
+
+		let mut middleware = *res.middleware;
+		let original = middleware.clone();
+		self.middleware.apply(&mut middleware)?;
+
+		if middleware != original {
+			apis::routes_middleware_api::routes_middleware_update(
+				&ctx.openapi_config_cloud,
+				models::RoutesMiddlewareUpdateMiddlewareBody {
+					middleware: Box::new(middleware.clone()),
+				},
+				Some(&ctx.project.name_id.to_string()),
+				Some(&env),
+			)
+			.await?;
+
+			println!("Updated middleware for environment '{}'", env);
+			println!();
+		}
+
+		print_middleware(&middleware);
+
+		Ok(())
+	}
+}
+
+pub fn print_middleware(middleware: &models::RoutesMiddlewarePolicy) {
+	let unset = || "default".to_string();
+
+	println!(
+		"{:<20} {}",
+		"Rate limit",
+		middleware
+			.rate_limit
+			.as_ref()
+			.map(|rate_limit| format!(
+				"{} requests / {}s{}",
+				rate_limit.requests,
+				rate_limit.period,
+				if rate_limit.distributed.unwrap_or_default() {
+					" (distributed)"
+				} else {
+					""
+				}
+			))
+			.unwrap_or_else(unset)
+	);
+	println!(
+		"{:<20} {}",
+		"Max in flight",
+		middleware
+			.max_in_flight
+			.map(|max_in_flight| max_in_flight.to_string())
+			.unwrap_or_else(unset)
+	);
+	println!(
+		"{:<20} {}",
+		"Retry",
+		middleware
+			.retry
+			.as_ref()
+			.map(|retry| format!(
+				"{} attempts, {}ms initial interval",
+				retry.max_attempts, retry.initial_interval
+			))
+			.unwrap_or_else(unset)
+	);
+	println!(
+		"{:<20} {}",
+		"Request timeout",
+		middleware
+			.request_timeout
+			.map(|request_timeout| format!("{request_timeout}s"))
+			.unwrap_or_else(unset)
+	);
+}
@@ -3,6 +3,7 @@ use clap::Parser;
 
 pub mod endpoint;
 pub mod list;
+pub mod middleware;
 
 /// Commands for managing routes
 #[derive(Parser)]
@@ -12,6 +13,8 @@ pub enum SubCommand {
 	/// Create or update an endpoint (route)
 	#[clap(alias = "ep")]
 	Endpoint(endpoint::Opts),
+	/// Show or update the middleware policy for all routes of an environment
+	Middleware(middleware::Opts),
 }
 
 impl SubCommand {
@@ -19,6 +22,7 @@ impl SubCommand {
 		match self {
 			SubCommand::List(opts) => opts.execute().await,
 			SubCommand::Endpoint(opts) => opts.execute().await,
+			SubCommand::Middleware(opts) => opts.execute().await,
 		}
 	}
 }
\ No newline at end of file
@@ -250,7 +250,10 @@ async fn setup_function_routes(
 							actors: Some(Box::new(models::RoutesRouteTargetActors {
 								selector_tags: route_tags.clone(),
 							})),
+							..models::RoutesRouteTarget::new()
 						}),
+						request_match: None,
+						middleware: Some(matching_route.middleware.clone()),
 					};
 
 					// Only update fields that have changed
@@ -412,7 +415,10 @@ async fn create_function_route(
 			actors: Some(Box::new(models::RoutesRouteTargetActors {
 				selector_tags: route_tags.clone(),
 			})),
+			..models::RoutesRouteTarget::new()
 		}),
+		request_match: None,
+		middleware: None,
 	};
 
 	// Create/update route
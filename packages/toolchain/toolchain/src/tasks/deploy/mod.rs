@@ -291,7 +291,9 @@ async fn create_edge_function_actors(
 					lifecycle: Some(Box::new(models::ActorsLifecycle {
 						durable: Some(true),
 						kill_timeout: None,
+						idle_timeout: None,
 					})),
+					placement: None,
 				};
 
 				apis::actors_api::actors_create(
@@ -2,6 +2,8 @@ use anyhow::*;
 use base64::{engine::general_purpose::STANDARD, Engine};
 use clap::ValueEnum;
 use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use serde::Deserialize;
 use std::time::Duration;
 use tokio::signal;
 use tokio::sync::watch;
@@ -88,9 +90,6 @@ async fn tail_stream(
 	stream: models::ActorsQueryLogStream,
 	log_fetched_tx: watch::Sender<bool>,
 ) -> Result<()> {
-	let mut watch_index: Option<String> = None;
-	let mut first_batch_fetched = false;
-
 	// Check if this stream is intended to be polled. If not, sleep indefinitely so the other
 	// future doesn't exit.
 	match (&opts.stream, stream) {
@@ -106,60 +105,172 @@ async fn tail_stream(
 		}
 	}
 
+	// Print the most recent logs
+	let res = apis::actors_logs_api::actors_logs_get(
+		&ctx.openapi_config_cloud,
+		stream,
+		&serde_json::to_string(&vec![opts.actor_id])?,
+		Some(&ctx.project.name_id),
+		Some(opts.environment),
+		None,
+		None,
+		None,
+		None,
+	)
+	.await
+	.map_err(|err| anyhow!("Failed to fetch logs: {err}"))?;
+
+	log_fetched_tx.send(true).ok();
+
+	for (ts, line) in res.timestamps.iter().zip(res.lines.iter()) {
+		print_line(opts, ts, line);
+	}
+
+	if !opts.follow {
+		return Ok(());
+	}
+
+	// Stream new logs, resuming after the last fetched line
+	follow_stream(ctx, opts, stream, res.watch.index).await
+}
+
+/// Event sent by the actor log stream endpoint.
+#[derive(Deserialize)]
+struct LogStreamEvent {
+	timestamp: String,
+	/// Base64 encoded.
+	line: String,
+}
+
+/// Streams new lines of a specific stream of an actor's log over server-sent events.
+///
+/// Reconnects from the last received event if the connection drops.
+async fn follow_stream(
+	ctx: &ToolchainCtx,
+	opts: &TailOpts<'_>,
+	stream: models::ActorsQueryLogStream,
+	mut cursor: String,
+) -> Result<()> {
+	let config = &ctx.openapi_config_cloud;
+	let actor_ids_json = serde_json::to_string(&vec![opts.actor_id])?;
+
 	loop {
-		let res = apis::actors_logs_api::actors_logs_get(
-			&ctx.openapi_config_cloud,
-			stream,
-			&serde_json::to_string(&vec![opts.actor_id])?,
-			Some(&ctx.project.name_id),
-			Some(opts.environment),
-			None,
-			None,
-			None,
-			watch_index.as_deref(),
-		)
-		.await
-		.map_err(|err| anyhow!("Failed to fetch logs: {err}"))?;
-		watch_index = Some(res.watch.index);
+		let mut req = config
+			.client
+			.get(format!("{}/actors/logs/stream", config.base_path))
+			.query(&[
+				("project", ctx.project.name_id.as_str()),
+				("environment", opts.environment),
+				("stream", stream.to_string().as_str()),
+				("actor_ids_json", actor_ids_json.as_str()),
+				("cursor", cursor.as_str()),
+			]);
+		if let Some(token) = &config.bearer_access_token {
+			req = req.bearer_auth(token);
+		}
 
-		if !first_batch_fetched {
-			log_fetched_tx.send(true).ok();
-			first_batch_fetched = true;
+		let res = req
+			.send()
+			.await
+			.map_err(|err| anyhow!("Failed to stream logs: {err}"))?;
+		if !res.status().is_success() {
+			let status = res.status();
+			let body = res.text().await.unwrap_or_default();
+			bail!("Failed to stream logs ({status}): {body}");
 		}
 
-		for (ts, line) in res.timestamps.iter().zip(res.lines.iter()) {
-			let Result::Ok(ts) = ts.parse::<DateTime<Utc>>() else {
-				eprintln!("Failed to parse timestamp: {ts} for line {line}");
-				continue;
-			};
-			let decoded_line = match STANDARD.decode(line) {
-				Result::Ok(bytes) => String::from_utf8_lossy(&bytes).to_string(),
-				Err(_) => {
-					eprintln!("Failed to decode base64: {line}");
-					continue;
-				}
+		let mut body = res.bytes_stream();
+		let mut buf = Vec::new();
+		while let Some(chunk) = body.next().await {
+			let Result::Ok(chunk) = chunk else {
+				break;
 			};
+			buf.extend_from_slice(&chunk);
 
-
-			match &opts.print_type {
-				PrintType::Custom(callback) => {
-					(callback)(ts, decoded_line);
-				}
-				PrintType::Print => {
-					println!("{decoded_line}");
+			for event in take_events(&mut buf) {
+				if let Some(id) = event.id {
+					cursor = id;
 				}
-				PrintType::PrintWithTime => {
-					println!("{ts} {decoded_line}");
+
+				match (event.event.as_deref(), event.data) {
+					(Some("log"), Some(data)) => {
+						match serde_json::from_str::<LogStreamEvent>(&data) {
+							Result::Ok(event) => print_line(opts, &event.timestamp, &event.line),
+							Err(err) => eprintln!("Failed to parse log event: {err}"),
+						}
+					}
+					(Some("truncated"), _) => eprintln!(
+						"Too many log lines since the last received line, some lines were skipped"
+					),
+					// Comments are used as keepalives
+					_ => {}
 				}
 			}
 		}
 
-		if !opts.follow {
-			break;
+		// Throttle reconnects
+		tokio::time::sleep(Duration::from_secs(1)).await;
+	}
+}
+
+/// Server-sent event, only with the fields sent by the log stream endpoint.
+#[derive(Debug, Default, PartialEq)]
+struct SseEvent {
+	id: Option<String>,
+	event: Option<String>,
+	data: Option<String>,
+}
+
+/// Removes the complete events from the buffer and parses them. Events are separated by blank lines, an
+/// incomplete event at the end is left in the buffer.
+fn take_events(buf: &mut Vec<u8>) -> Vec<SseEvent> {
+	let mut events = Vec::new();
+
+	while let Some(end) = buf.windows(2).position(|x| x == b"\n\n") {
+		let raw = buf.drain(..end + 2).collect::<Vec<_>>();
+
+		let mut event = SseEvent::default();
+		for field in String::from_utf8_lossy(&raw).lines() {
+			if let Some(value) = field.strip_prefix("id: ") {
+				event.id = Some(value.to_string());
+			} else if let Some(value) = field.strip_prefix("event: ") {
+				event.event = Some(value.to_string());
+			} else if let Some(value) = field.strip_prefix("data: ") {
+				event.data = Some(value.to_string());
+			}
 		}
+
+		events.push(event);
 	}
 
-	Ok(())
+	events
+}
+
+/// Decodes and prints a log line.
+fn print_line(opts: &TailOpts<'_>, ts: &str, line: &str) {
+	let Result::Ok(ts) = ts.parse::<DateTime<Utc>>() else {
+		eprintln!("Failed to parse timestamp: {ts} for line {line}");
+		return;
+	};
+	let decoded_line = match STANDARD.decode(line) {
+		Result::Ok(bytes) => String::from_utf8_lossy(&bytes).to_string(),
+		Err(_) => {
+			eprintln!("Failed to decode base64: {line}");
+			return;
+		}
+	};
+
+	match &opts.print_type {
+		PrintType::Custom(callback) => {
+			(callback)(ts, decoded_line);
+		}
+		PrintType::Print => {
+			println!("{decoded_line}");
+		}
+		PrintType::PrintWithTime => {
+			println!("{ts} {decoded_line}");
+		}
+	}
 }
 
 /// Polls the actor state. Exits when finished.
@@ -209,3 +320,43 @@ async fn poll_actor_state(
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_take_events() {
+		let mut buf = b"id: 100:1\nevent: log\ndata: {}\n\n: keepalive\n\nevent: truncated\ndata: {}\n\nid: 10".to_vec();
+
+		assert_eq!(
+			vec![
+				SseEvent {
+					id: Some("100:1".to_string()),
+					event: Some("log".to_string()),
+					data: Some("{}".to_string()),
+				},
+				SseEvent::default(),
+				SseEvent {
+					id: None,
+					event: Some("truncated".to_string()),
+					data: Some("{}".to_string()),
+				},
+			],
+			take_events(&mut buf)
+		);
+
+		// Incomplete event is kept until the rest is received
+		assert_eq!(b"id: 10".to_vec(), buf);
+		buf.extend_from_slice(b"1:1\nevent: log\ndata: {}\n\n");
+		assert_eq!(
+			vec![SseEvent {
+				id: Some("101:1".to_string()),
+				event: Some("log".to_string()),
+				data: Some("{}".to_string()),
+			}],
+			take_events(&mut buf)
+		);
+		assert!(buf.is_empty());
+	}
+}
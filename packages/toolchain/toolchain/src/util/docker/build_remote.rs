@@ -360,7 +360,9 @@ async fn get_or_create_ci_manager_actor(
 			lifecycle: Some(Box::new(models::ActorsLifecycle {
 				kill_timeout: Some(30000),
 				durable: Some(true),
+				idle_timeout: None,
 			})),
+			placement: None,
 		},
 		Some(&ctx.project.name_id),
 		Some(CI_ENVIRONMENT_ID),
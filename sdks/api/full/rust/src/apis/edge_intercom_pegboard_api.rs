@@ -13,6 +13,19 @@ use reqwest;
 use super::{configuration, Error};
 use crate::apis::ResponseContent;
 
+/// struct for typed errors of method [`edge_intercom_pegboard_get_usage`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum EdgeIntercomPegboardGetUsageError {
+	Status400(crate::models::ErrorBody),
+	Status403(crate::models::ErrorBody),
+	Status404(crate::models::ErrorBody),
+	Status408(crate::models::ErrorBody),
+	Status429(crate::models::ErrorBody),
+	Status500(crate::models::ErrorBody),
+	UnknownValue(serde_json::Value),
+}
+
 /// struct for typed errors of method [`edge_intercom_pegboard_prewarm_image`]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
@@ -39,6 +52,48 @@ pub enum EdgeIntercomPegboardToggleClientDrainError {
 	UnknownValue(serde_json::Value),
 }
 
+pub async fn edge_intercom_pegboard_get_usage(
+	configuration: &configuration::Configuration,
+) -> Result<
+	crate::models::EdgeIntercomPegboardGetUsageResponse,
+	Error<EdgeIntercomPegboardGetUsageError>,
+> {
+	let local_var_configuration = configuration;
+
+	let local_var_client = &local_var_configuration.client;
+
+	let local_var_uri_str = format!("{}/pegboard/usage", local_var_configuration.base_path);
+	let mut local_var_req_builder =
+		local_var_client.request(reqwest::Method::GET, local_var_uri_str.as_str());
+
+	if let Some(ref local_var_user_agent) = local_var_configuration.user_agent {
+		local_var_req_builder =
+			local_var_req_builder.header(reqwest::header::USER_AGENT, local_var_user_agent.clone());
+	}
+	if let Some(ref local_var_token) = local_var_configuration.bearer_access_token {
+		local_var_req_builder = local_var_req_builder.bearer_auth(local_var_token.to_owned());
+	};
+
+	let local_var_req = local_var_req_builder.build()?;
+	let local_var_resp = local_var_client.execute(local_var_req).await?;
+
+	let local_var_status = local_var_resp.status();
+	let local_var_content = local_var_resp.text().await?;
+
+	if !local_var_status.is_client_error() && !local_var_status.is_server_error() {
+		serde_json::from_str(&local_var_content).map_err(Error::from)
+	} else {
+		let local_var_entity: Option<EdgeIntercomPegboardGetUsageError> =
+			serde_json::from_str(&local_var_content).ok();
+		let local_var_error = ResponseContent {
+			status: local_var_status,
+			content: local_var_content,
+			entity: local_var_entity,
+		};
+		Err(Error::ResponseError(local_var_error))
+	}
+}
+
 pub async fn edge_intercom_pegboard_prewarm_image(
 	configuration: &configuration::Configuration,
 	image_id: &str,
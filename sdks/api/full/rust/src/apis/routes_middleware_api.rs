@@ -0,0 +1,142 @@
+/*
+ * Rivet API
+ *
+ * No description provided (generated by Openapi Generator https://github.com/openapitools/openapi-generator)
+ *
+ * The version of the OpenAPI document: 0.0.1
+ *
+ * Generated by: https://openapi-generator.tech
+ */
+
+use reqwest;
+
+use super::{configuration, Error};
+use crate::apis::ResponseContent;
+
+/// struct for typed errors of method [`routes_middleware_get`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RoutesMiddlewareGetError {
+	Status400(crate::models::ErrorBody),
+	Status403(crate::models::ErrorBody),
+	Status404(crate::models::ErrorBody),
+	Status408(crate::models::ErrorBody),
+	Status429(crate::models::ErrorBody),
+	Status500(crate::models::ErrorBody),
+	UnknownValue(serde_json::Value),
+}
+
+/// struct for typed errors of method [`routes_middleware_update`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RoutesMiddlewareUpdateError {
+	Status400(crate::models::ErrorBody),
+	Status403(crate::models::ErrorBody),
+	Status404(crate::models::ErrorBody),
+	Status408(crate::models::ErrorBody),
+	Status429(crate::models::ErrorBody),
+	Status500(crate::models::ErrorBody),
+	UnknownValue(serde_json::Value),
+}
+
+/// Returns the middleware policy applied to all routes of the given environment.
+pub async fn routes_middleware_get(
+	configuration: &configuration::Configuration,
+	project: Option<&str>,
+	environment: Option<&str>,
+) -> Result<crate::models::RoutesMiddlewareGetMiddlewareResponse, Error<RoutesMiddlewareGetError>> {
+	let local_var_configuration = configuration;
+
+	let local_var_client = &local_var_configuration.client;
+
+	let local_var_uri_str = format!("{}/middleware", local_var_configuration.base_path);
+	let mut local_var_req_builder =
+		local_var_client.request(reqwest::Method::GET, local_var_uri_str.as_str());
+
+	if let Some(ref local_var_str) = project {
+		local_var_req_builder =
+			local_var_req_builder.query(&[("project", &local_var_str.to_string())]);
+	}
+	if let Some(ref local_var_str) = environment {
+		local_var_req_builder =
+			local_var_req_builder.query(&[("environment", &local_var_str.to_string())]);
+	}
+	if let Some(ref local_var_user_agent) = local_var_configuration.user_agent {
+		local_var_req_builder =
+			local_var_req_builder.header(reqwest::header::USER_AGENT, local_var_user_agent.clone());
+	}
+	if let Some(ref local_var_token) = local_var_configuration.bearer_access_token {
+		local_var_req_builder = local_var_req_builder.bearer_auth(local_var_token.to_owned());
+	};
+
+	let local_var_req = local_var_req_builder.build()?;
+	let local_var_resp = local_var_client.execute(local_var_req).await?;
+
+	let local_var_status = local_var_resp.status();
+	let local_var_content = local_var_resp.text().await?;
+
+	if !local_var_status.is_client_error() && !local_var_status.is_server_error() {
+		serde_json::from_str(&local_var_content).map_err(Error::from)
+	} else {
+		let local_var_entity: Option<RoutesMiddlewareGetError> =
+			serde_json::from_str(&local_var_content).ok();
+		let local_var_error = ResponseContent {
+			status: local_var_status,
+			content: local_var_content,
+			entity: local_var_entity,
+		};
+		Err(Error::ResponseError(local_var_error))
+	}
+}
+
+/// Sets the middleware policy applied to all routes of the given environment.
+pub async fn routes_middleware_update(
+	configuration: &configuration::Configuration,
+	routes_middleware_update_middleware_body: crate::models::RoutesMiddlewareUpdateMiddlewareBody,
+	project: Option<&str>,
+	environment: Option<&str>,
+) -> Result<serde_json::Value, Error<RoutesMiddlewareUpdateError>> {
+	let local_var_configuration = configuration;
+
+	let local_var_client = &local_var_configuration.client;
+
+	let local_var_uri_str = format!("{}/middleware", local_var_configuration.base_path);
+	let mut local_var_req_builder =
+		local_var_client.request(reqwest::Method::PUT, local_var_uri_str.as_str());
+
+	if let Some(ref local_var_str) = project {
+		local_var_req_builder =
+			local_var_req_builder.query(&[("project", &local_var_str.to_string())]);
+	}
+	if let Some(ref local_var_str) = environment {
+		local_var_req_builder =
+			local_var_req_builder.query(&[("environment", &local_var_str.to_string())]);
+	}
+	if let Some(ref local_var_user_agent) = local_var_configuration.user_agent {
+		local_var_req_builder =
+			local_var_req_builder.header(reqwest::header::USER_AGENT, local_var_user_agent.clone());
+	}
+	if let Some(ref local_var_token) = local_var_configuration.bearer_access_token {
+		local_var_req_builder = local_var_req_builder.bearer_auth(local_var_token.to_owned());
+	};
+	local_var_req_builder = local_var_req_builder.json(&routes_middleware_update_middleware_body);
+
+	let local_var_req = local_var_req_builder.build()?;
+	let local_var_resp = local_var_client.execute(local_var_req).await?;
+
+	let local_var_status = local_var_resp.status();
+	let local_var_content = local_var_resp.text().await?;
+
+	if !local_var_status.is_client_error() && !local_var_status.is_server_error() {
+		serde_json::from_str(&local_var_content).map_err(Error::from)
+	} else {
+		let local_var_entity: Option<RoutesMiddlewareUpdateError> =
+			serde_json::from_str(&local_var_content).ok();
+		let local_var_error = ResponseContent {
+			status: local_var_status,
+			content: local_var_content,
+			entity: local_var_entity,
+		};
+		Err(Error::ResponseError(local_var_error))
+	}
+}
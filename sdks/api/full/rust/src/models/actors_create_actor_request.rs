@@ -31,6 +31,8 @@ pub struct ActorsCreateActorRequest {
 	pub resources: Option<Box<crate::models::ActorsResources>>,
 	#[serde(rename = "lifecycle", skip_serializing_if = "Option::is_none")]
 	pub lifecycle: Option<Box<crate::models::ActorsLifecycle>>,
+	#[serde(rename = "placement", skip_serializing_if = "Option::is_none")]
+	pub placement: Option<Box<crate::models::ActorsPlacement>>,
 }
 
 impl ActorsCreateActorRequest {
@@ -44,6 +46,7 @@ impl ActorsCreateActorRequest {
 			network: None,
 			resources: None,
 			lifecycle: None,
+			placement: None,
 		}
 	}
 }
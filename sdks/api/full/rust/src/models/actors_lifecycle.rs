@@ -16,6 +16,9 @@ pub struct ActorsLifecycle {
 	/// If true, the actor will try to reschedule itself automatically in the event of a crash or a datacenter failover. The actor will not reschedule if it exits successfully.
 	#[serde(rename = "durable", skip_serializing_if = "Option::is_none")]
 	pub durable: Option<bool>,
+	/// Stops the actor after it has not received any requests for this many milliseconds. The actor is started again when it receives its next request. Requires `durable`.
+	#[serde(rename = "idle_timeout", skip_serializing_if = "Option::is_none")]
+	pub idle_timeout: Option<i64>,
 }
 
 impl ActorsLifecycle {
@@ -23,6 +26,7 @@ impl ActorsLifecycle {
 		ActorsLifecycle {
 			kill_timeout: None,
 			durable: None,
+			idle_timeout: None,
 		}
 	}
 }
@@ -0,0 +1,28 @@
+/*
+ * Rivet API
+ *
+ * No description provided (generated by Openapi Generator https://github.com/openapitools/openapi-generator)
+ *
+ * The version of the OpenAPI document: 0.0.1
+ *
+ * Generated by: https://openapi-generator.tech
+ */
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct ActorsPlacement {
+	/// Labels that a client must have in its config to run the actor.
+	#[serde(rename = "client_labels", skip_serializing_if = "Option::is_none")]
+	pub client_labels: Option<::std::collections::HashMap<String, String>>,
+	/// Tag keys of the actor. Actors in the same environment with the same values for all of these tags are placed on different clients.
+	#[serde(rename = "spread_by_tags", skip_serializing_if = "Option::is_none")]
+	pub spread_by_tags: Option<Vec<String>>,
+}
+
+impl ActorsPlacement {
+	pub fn new() -> ActorsPlacement {
+		ActorsPlacement {
+			client_labels: None,
+			spread_by_tags: None,
+		}
+	}
+}
@@ -0,0 +1,63 @@
+/*
+ * Rivet API
+ *
+ * No description provided (generated by Openapi Generator https://github.com/openapitools/openapi-generator)
+ *
+ * The version of the OpenAPI document: 0.0.1
+ *
+ * Generated by: https://openapi-generator.tech
+ */
+
+/// EdgeIntercomPegboardFlavorUsage : Resource usage of all responsive clients of a single flavor.
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct EdgeIntercomPegboardFlavorUsage {
+	#[serde(rename = "clients")]
+	pub clients: i32,
+	/// Millicores.
+	#[serde(rename = "total_cpu")]
+	pub total_cpu: i64,
+	/// Millicores.
+	#[serde(rename = "remaining_cpu")]
+	pub remaining_cpu: i64,
+	/// MiB.
+	#[serde(rename = "total_memory")]
+	pub total_memory: i64,
+	/// MiB.
+	#[serde(rename = "remaining_memory")]
+	pub remaining_memory: i64,
+	/// Actors that failed to allocate because no client had capacity for them.
+	#[serde(rename = "pending_allocations")]
+	pub pending_allocations: i32,
+	/// Millicores requested by pending allocations.
+	#[serde(rename = "pending_cpu")]
+	pub pending_cpu: i64,
+	/// MiB requested by pending allocations.
+	#[serde(rename = "pending_memory")]
+	pub pending_memory: i64,
+}
+
+impl EdgeIntercomPegboardFlavorUsage {
+	/// Resource usage of all responsive clients of a single flavor.
+	pub fn new(
+		clients: i32,
+		total_cpu: i64,
+		remaining_cpu: i64,
+		total_memory: i64,
+		remaining_memory: i64,
+		pending_allocations: i32,
+		pending_cpu: i64,
+		pending_memory: i64,
+	) -> EdgeIntercomPegboardFlavorUsage {
+		EdgeIntercomPegboardFlavorUsage {
+			clients,
+			total_cpu,
+			remaining_cpu,
+			total_memory,
+			remaining_memory,
+			pending_allocations,
+			pending_cpu,
+			pending_memory,
+		}
+	}
+}
@@ -0,0 +1,29 @@
+/*
+ * Rivet API
+ *
+ * No description provided (generated by Openapi Generator https://github.com/openapitools/openapi-generator)
+ *
+ * The version of the OpenAPI document: 0.0.1
+ *
+ * Generated by: https://openapi-generator.tech
+ */
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct EdgeIntercomPegboardGetUsageResponse {
+	#[serde(rename = "container")]
+	pub container: Box<crate::models::EdgeIntercomPegboardFlavorUsage>,
+	#[serde(rename = "isolate")]
+	pub isolate: Box<crate::models::EdgeIntercomPegboardFlavorUsage>,
+}
+
+impl EdgeIntercomPegboardGetUsageResponse {
+	pub fn new(
+		container: crate::models::EdgeIntercomPegboardFlavorUsage,
+		isolate: crate::models::EdgeIntercomPegboardFlavorUsage,
+	) -> EdgeIntercomPegboardGetUsageResponse {
+		EdgeIntercomPegboardGetUsageResponse {
+			container: Box::new(container),
+			isolate: Box::new(isolate),
+		}
+	}
+}
@@ -26,6 +26,8 @@ pub mod actors_network;
 pub use self::actors_network::ActorsNetwork;
 pub mod actors_network_mode;
 pub use self::actors_network_mode::ActorsNetworkMode;
+pub mod actors_placement;
+pub use self::actors_placement::ActorsPlacement;
 pub mod actors_port;
 pub use self::actors_port::ActorsPort;
 pub mod actors_port_protocol;
@@ -380,6 +382,10 @@ pub mod cloud_version_summary;
 pub use self::cloud_version_summary::CloudVersionSummary;
 pub mod core_intercom_pegboard_mark_client_registered_request;
 pub use self::core_intercom_pegboard_mark_client_registered_request::CoreIntercomPegboardMarkClientRegisteredRequest;
+pub mod edge_intercom_pegboard_flavor_usage;
+pub use self::edge_intercom_pegboard_flavor_usage::EdgeIntercomPegboardFlavorUsage;
+pub mod edge_intercom_pegboard_get_usage_response;
+pub use self::edge_intercom_pegboard_get_usage_response::EdgeIntercomPegboardGetUsageResponse;
 pub mod edge_intercom_pegboard_toggle_client_drain_request;
 pub use self::edge_intercom_pegboard_toggle_client_drain_request::EdgeIntercomPegboardToggleClientDrainRequest;
 pub mod error_body;
@@ -602,14 +608,40 @@ pub mod regions_recommend_region_response;
 pub use self::regions_recommend_region_response::RegionsRecommendRegionResponse;
 pub mod regions_region;
 pub use self::regions_region::RegionsRegion;
+pub mod routes_hash_key;
+pub use self::routes_hash_key::RoutesHashKey;
 pub mod routes_list_routes_response;
 pub use self::routes_list_routes_response::RoutesListRoutesResponse;
+pub mod routes_load_balancing;
+pub use self::routes_load_balancing::RoutesLoadBalancing;
+pub mod routes_load_balancing_strategy;
+pub use self::routes_load_balancing_strategy::RoutesLoadBalancingStrategy;
+pub mod routes_middleware_get_middleware_response;
+pub use self::routes_middleware_get_middleware_response::RoutesMiddlewareGetMiddlewareResponse;
+pub mod routes_middleware_policy;
+pub use self::routes_middleware_policy::RoutesMiddlewarePolicy;
+pub mod routes_middleware_update_middleware_body;
+pub use self::routes_middleware_update_middleware_body::RoutesMiddlewareUpdateMiddlewareBody;
+pub mod routes_rate_limit_policy;
+pub use self::routes_rate_limit_policy::RoutesRateLimitPolicy;
+pub mod routes_retry_policy;
+pub use self::routes_retry_policy::RoutesRetryPolicy;
 pub mod routes_route;
 pub use self::routes_route::RoutesRoute;
+pub mod routes_route_header_match;
+pub use self::routes_route_header_match::RoutesRouteHeaderMatch;
+pub mod routes_route_match;
+pub use self::routes_route_match::RoutesRouteMatch;
 pub mod routes_route_target;
 pub use self::routes_route_target::RoutesRouteTarget;
 pub mod routes_route_target_actors;
 pub use self::routes_route_target_actors::RoutesRouteTargetActors;
+pub mod routes_route_target_redirect;
+pub use self::routes_route_target_redirect::RoutesRouteTargetRedirect;
+pub mod routes_route_target_response;
+pub use self::routes_route_target_response::RoutesRouteTargetResponse;
+pub mod routes_route_target_split;
+pub use self::routes_route_target_split::RoutesRouteTargetSplit;
 pub mod routes_update_route_body;
 pub use self::routes_update_route_body::RoutesUpdateRouteBody;
 pub mod servers_build;
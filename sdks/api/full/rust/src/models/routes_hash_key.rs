@@ -0,0 +1,35 @@
+/*
+ * Rivet API
+ *
+ * No description provided (generated by Openapi Generator https://github.com/openapitools/openapi-generator)
+ *
+ * The version of the OpenAPI document: 0.0.1
+ *
+ * Generated by: https://openapi-generator.tech
+ */
+
+/// RoutesHashKey : Exactly one of the fields must be set.
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct RoutesHashKey {
+	/// Name of the header to hash.
+	#[serde(rename = "header", skip_serializing_if = "Option::is_none")]
+	pub header: Option<String>,
+	/// Name of the cookie to hash.
+	#[serde(rename = "cookie", skip_serializing_if = "Option::is_none")]
+	pub cookie: Option<String>,
+	/// Name of the query parameter to hash.
+	#[serde(rename = "query", skip_serializing_if = "Option::is_none")]
+	pub query: Option<String>,
+}
+
+impl RoutesHashKey {
+	/// Exactly one of the fields must be set.
+	pub fn new() -> RoutesHashKey {
+		RoutesHashKey {
+			header: None,
+			cookie: None,
+			query: None,
+		}
+	}
+}
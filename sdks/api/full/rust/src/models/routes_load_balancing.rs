@@ -0,0 +1,29 @@
+/*
+ * Rivet API
+ *
+ * No description provided (generated by Openapi Generator https://github.com/openapitools/openapi-generator)
+ *
+ * The version of the OpenAPI document: 0.0.1
+ *
+ * Generated by: https://openapi-generator.tech
+ */
+
+/// RoutesLoadBalancing : How requests are distributed between the actors of a route.
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct RoutesLoadBalancing {
+	#[serde(rename = "strategy")]
+	pub strategy: crate::models::RoutesLoadBalancingStrategy,
+	#[serde(rename = "hash_key", skip_serializing_if = "Option::is_none")]
+	pub hash_key: Option<Box<crate::models::RoutesHashKey>>,
+}
+
+impl RoutesLoadBalancing {
+	/// How requests are distributed between the actors of a route.
+	pub fn new(strategy: crate::models::RoutesLoadBalancingStrategy) -> RoutesLoadBalancing {
+		RoutesLoadBalancing {
+			strategy,
+			hash_key: None,
+		}
+	}
+}
@@ -0,0 +1,41 @@
+/*
+ * Rivet API
+ *
+ * No description provided (generated by Openapi Generator https://github.com/openapitools/openapi-generator)
+ *
+ * The version of the OpenAPI document: 0.0.1
+ *
+ * Generated by: https://openapi-generator.tech
+ */
+
+/// RoutesLoadBalancingStrategy : `consistent_hash` sends requests with the same hashed value to the same actor and falls back to `random` if the request does not have the value.
+
+/// `consistent_hash` sends requests with the same hashed value to the same actor and falls back to `random` if the request does not have the value.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub enum RoutesLoadBalancingStrategy {
+	#[serde(rename = "random")]
+	Random,
+	#[serde(rename = "round_robin")]
+	RoundRobin,
+	#[serde(rename = "least_in_flight")]
+	LeastInFlight,
+	#[serde(rename = "consistent_hash")]
+	ConsistentHash,
+}
+
+impl ToString for RoutesLoadBalancingStrategy {
+	fn to_string(&self) -> String {
+		match self {
+			Self::Random => String::from("random"),
+			Self::RoundRobin => String::from("round_robin"),
+			Self::LeastInFlight => String::from("least_in_flight"),
+			Self::ConsistentHash => String::from("consistent_hash"),
+		}
+	}
+}
+
+impl Default for RoutesLoadBalancingStrategy {
+	fn default() -> RoutesLoadBalancingStrategy {
+		Self::Random
+	}
+}
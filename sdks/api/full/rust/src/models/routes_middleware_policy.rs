@@ -0,0 +1,37 @@
+/*
+ * Rivet API
+ *
+ * No description provided (generated by Openapi Generator https://github.com/openapitools/openapi-generator)
+ *
+ * The version of the OpenAPI document: 0.0.1
+ *
+ * Generated by: https://openapi-generator.tech
+ */
+
+/// RoutesMiddlewarePolicy : Middleware applied by the edge to requests to actors. Unset fields inherit the environment's policy, then the default.
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct RoutesMiddlewarePolicy {
+	#[serde(rename = "rate_limit", skip_serializing_if = "Option::is_none")]
+	pub rate_limit: Option<Box<crate::models::RoutesRateLimitPolicy>>,
+	/// Max concurrent requests per client.
+	#[serde(rename = "max_in_flight", skip_serializing_if = "Option::is_none")]
+	pub max_in_flight: Option<i32>,
+	#[serde(rename = "retry", skip_serializing_if = "Option::is_none")]
+	pub retry: Option<Box<crate::models::RoutesRetryPolicy>>,
+	/// Request timeout in seconds.
+	#[serde(rename = "request_timeout", skip_serializing_if = "Option::is_none")]
+	pub request_timeout: Option<i32>,
+}
+
+impl RoutesMiddlewarePolicy {
+	/// Middleware applied by the edge to requests to actors. Unset fields inherit the environment's policy, then the default.
+	pub fn new() -> RoutesMiddlewarePolicy {
+		RoutesMiddlewarePolicy {
+			rate_limit: None,
+			max_in_flight: None,
+			retry: None,
+			request_timeout: None,
+		}
+	}
+}
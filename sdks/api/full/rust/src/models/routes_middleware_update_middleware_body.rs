@@ -0,0 +1,25 @@
+/*
+ * Rivet API
+ *
+ * No description provided (generated by Openapi Generator https://github.com/openapitools/openapi-generator)
+ *
+ * The version of the OpenAPI document: 0.0.1
+ *
+ * Generated by: https://openapi-generator.tech
+ */
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct RoutesMiddlewareUpdateMiddlewareBody {
+	#[serde(rename = "middleware")]
+	pub middleware: Box<crate::models::RoutesMiddlewarePolicy>,
+}
+
+impl RoutesMiddlewareUpdateMiddlewareBody {
+	pub fn new(
+		middleware: crate::models::RoutesMiddlewarePolicy,
+	) -> RoutesMiddlewareUpdateMiddlewareBody {
+		RoutesMiddlewareUpdateMiddlewareBody {
+			middleware: Box::new(middleware),
+		}
+	}
+}
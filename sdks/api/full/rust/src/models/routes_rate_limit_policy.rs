@@ -0,0 +1,34 @@
+/*
+ * Rivet API
+ *
+ * No description provided (generated by Openapi Generator https://github.com/openapitools/openapi-generator)
+ *
+ * The version of the OpenAPI document: 0.0.1
+ *
+ * Generated by: https://openapi-generator.tech
+ */
+
+/// RoutesRateLimitPolicy : Rate limit per client.
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct RoutesRateLimitPolicy {
+	#[serde(rename = "requests")]
+	pub requests: i32,
+	/// Period in seconds.
+	#[serde(rename = "period")]
+	pub period: i32,
+	/// Share the rate limit between all edge instances instead of counting per instance.
+	#[serde(rename = "distributed", skip_serializing_if = "Option::is_none")]
+	pub distributed: Option<bool>,
+}
+
+impl RoutesRateLimitPolicy {
+	/// Rate limit per client.
+	pub fn new(requests: i32, period: i32) -> RoutesRateLimitPolicy {
+		RoutesRateLimitPolicy {
+			requests,
+			period,
+			distributed: None,
+		}
+	}
+}
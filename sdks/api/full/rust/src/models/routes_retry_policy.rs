@@ -0,0 +1,27 @@
+/*
+ * Rivet API
+ *
+ * No description provided (generated by Openapi Generator https://github.com/openapitools/openapi-generator)
+ *
+ * The version of the OpenAPI document: 0.0.1
+ *
+ * Generated by: https://openapi-generator.tech
+ */
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct RoutesRetryPolicy {
+	#[serde(rename = "max_attempts")]
+	pub max_attempts: i32,
+	/// Initial backoff interval in milliseconds, doubled after every attempt.
+	#[serde(rename = "initial_interval")]
+	pub initial_interval: i32,
+}
+
+impl RoutesRetryPolicy {
+	pub fn new(max_attempts: i32, initial_interval: i32) -> RoutesRetryPolicy {
+		RoutesRetryPolicy {
+			max_attempts,
+			initial_interval,
+		}
+	}
+}
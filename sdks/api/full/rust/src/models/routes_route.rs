@@ -28,8 +28,14 @@ pub struct RoutesRoute {
 	/// Whether to remove the path prefix before sending the request to the target.
 	#[serde(rename = "strip_prefix")]
 	pub strip_prefix: bool,
+	#[serde(rename = "request_match")]
+	pub request_match: Box<crate::models::RoutesRouteMatch>,
 	#[serde(rename = "target")]
 	pub target: Box<crate::models::RoutesRouteTarget>,
+	#[serde(rename = "load_balancing")]
+	pub load_balancing: Box<crate::models::RoutesLoadBalancing>,
+	#[serde(rename = "middleware")]
+	pub middleware: Box<crate::models::RoutesMiddlewarePolicy>,
 }
 
 impl RoutesRoute {
@@ -41,7 +47,10 @@ impl RoutesRoute {
 		path: String,
 		route_subpaths: bool,
 		strip_prefix: bool,
+		request_match: crate::models::RoutesRouteMatch,
 		target: crate::models::RoutesRouteTarget,
+		load_balancing: crate::models::RoutesLoadBalancing,
+		middleware: crate::models::RoutesMiddlewarePolicy,
 	) -> RoutesRoute {
 		RoutesRoute {
 			id,
@@ -51,7 +60,10 @@ impl RoutesRoute {
 			path,
 			route_subpaths,
 			strip_prefix,
+			request_match: Box::new(request_match),
 			target: Box::new(target),
+			load_balancing: Box::new(load_balancing),
+			middleware: Box::new(middleware),
 		}
 	}
 }
@@ -0,0 +1,24 @@
+/*
+ * Rivet API
+ *
+ * No description provided (generated by Openapi Generator https://github.com/openapitools/openapi-generator)
+ *
+ * The version of the OpenAPI document: 0.0.1
+ *
+ * Generated by: https://openapi-generator.tech
+ */
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct RoutesRouteHeaderMatch {
+	#[serde(rename = "name")]
+	pub name: String,
+	/// Matches if any value of the header is exactly this value. If unset, matches if the header is present.
+	#[serde(rename = "value", skip_serializing_if = "Option::is_none")]
+	pub value: Option<String>,
+}
+
+impl RoutesRouteHeaderMatch {
+	pub fn new(name: String) -> RoutesRouteHeaderMatch {
+		RoutesRouteHeaderMatch { name, value: None }
+	}
+}
@@ -0,0 +1,31 @@
+/*
+ * Rivet API
+ *
+ * No description provided (generated by Openapi Generator https://github.com/openapitools/openapi-generator)
+ *
+ * The version of the OpenAPI document: 0.0.1
+ *
+ * Generated by: https://openapi-generator.tech
+ */
+
+/// RoutesRouteMatch : Request conditions a route matches on in addition to its hostname and path. Routes with conditions take precedence over routes without for the same hostname and path.
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct RoutesRouteMatch {
+	/// HTTP methods to match. Matches any method if empty.
+	#[serde(rename = "methods", skip_serializing_if = "Option::is_none")]
+	pub methods: Option<Vec<String>>,
+	/// Headers that all have to match.
+	#[serde(rename = "headers", skip_serializing_if = "Option::is_none")]
+	pub headers: Option<Vec<crate::models::RoutesRouteHeaderMatch>>,
+}
+
+impl RoutesRouteMatch {
+	/// Request conditions a route matches on in addition to its hostname and path. Routes with conditions take precedence over routes without for the same hostname and path.
+	pub fn new() -> RoutesRouteMatch {
+		RoutesRouteMatch {
+			methods: None,
+			headers: None,
+		}
+	}
+}
@@ -8,14 +8,28 @@
  * Generated by: https://openapi-generator.tech
  */
 
+/// RoutesRouteTarget : Exactly one target must be set.
+
 #[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
 pub struct RoutesRouteTarget {
 	#[serde(rename = "actors", skip_serializing_if = "Option::is_none")]
 	pub actors: Option<Box<crate::models::RoutesRouteTargetActors>>,
+	#[serde(rename = "split", skip_serializing_if = "Option::is_none")]
+	pub split: Option<Box<crate::models::RoutesRouteTargetSplit>>,
+	#[serde(rename = "redirect", skip_serializing_if = "Option::is_none")]
+	pub redirect: Option<Box<crate::models::RoutesRouteTargetRedirect>>,
+	#[serde(rename = "response", skip_serializing_if = "Option::is_none")]
+	pub response: Option<Box<crate::models::RoutesRouteTargetResponse>>,
 }
 
 impl RoutesRouteTarget {
+	/// Exactly one target must be set.
 	pub fn new() -> RoutesRouteTarget {
-		RoutesRouteTarget { actors: None }
+		RoutesRouteTarget {
+			actors: None,
+			split: None,
+			redirect: None,
+			response: None,
+		}
 	}
 }
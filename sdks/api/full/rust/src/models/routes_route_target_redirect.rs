@@ -0,0 +1,34 @@
+/*
+ * Rivet API
+ *
+ * No description provided (generated by Openapi Generator https://github.com/openapitools/openapi-generator)
+ *
+ * The version of the OpenAPI document: 0.0.1
+ *
+ * Generated by: https://openapi-generator.tech
+ */
+
+/// RoutesRouteTargetRedirect : Redirects requests to another URL.
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct RoutesRouteTargetRedirect {
+	#[serde(rename = "location")]
+	pub location: String,
+	/// Redirect status code (301, 302, 303, 307 or 308).
+	#[serde(rename = "status")]
+	pub status: i32,
+	/// Whether to append the request's path (after stripping the route's prefix) and query to the location.
+	#[serde(rename = "preserve_path")]
+	pub preserve_path: bool,
+}
+
+impl RoutesRouteTargetRedirect {
+	/// Redirects requests to another URL.
+	pub fn new(location: String, status: i32, preserve_path: bool) -> RoutesRouteTargetRedirect {
+		RoutesRouteTargetRedirect {
+			location,
+			status,
+			preserve_path,
+		}
+	}
+}
@@ -0,0 +1,32 @@
+/*
+ * Rivet API
+ *
+ * No description provided (generated by Openapi Generator https://github.com/openapitools/openapi-generator)
+ *
+ * The version of the OpenAPI document: 0.0.1
+ *
+ * Generated by: https://openapi-generator.tech
+ */
+
+/// RoutesRouteTargetResponse : Responds with a fixed response without proxying the request.
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct RoutesRouteTargetResponse {
+	#[serde(rename = "status")]
+	pub status: i32,
+	#[serde(rename = "content_type", skip_serializing_if = "Option::is_none")]
+	pub content_type: Option<String>,
+	#[serde(rename = "body")]
+	pub body: String,
+}
+
+impl RoutesRouteTargetResponse {
+	/// Responds with a fixed response without proxying the request.
+	pub fn new(status: i32, body: String) -> RoutesRouteTargetResponse {
+		RoutesRouteTargetResponse {
+			status,
+			content_type: None,
+			body,
+		}
+	}
+}
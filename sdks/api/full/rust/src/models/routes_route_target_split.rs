@@ -0,0 +1,39 @@
+/*
+ * Rivet API
+ *
+ * No description provided (generated by Openapi Generator https://github.com/openapitools/openapi-generator)
+ *
+ * The version of the OpenAPI document: 0.0.1
+ *
+ * Generated by: https://openapi-generator.tech
+ */
+
+/// RoutesRouteTargetSplit : Splits requests between two sets of actors, e.g. for canary rollouts.
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct RoutesRouteTargetSplit {
+	/// Tags of actors to route requests to.
+	#[serde(rename = "selector_tags")]
+	pub selector_tags: ::std::collections::HashMap<String, String>,
+	/// Tags of the canary actors to route requests to.
+	#[serde(rename = "canary_selector_tags")]
+	pub canary_selector_tags: ::std::collections::HashMap<String, String>,
+	/// Percent of requests sent to the canary actors.
+	#[serde(rename = "canary_weight")]
+	pub canary_weight: i32,
+}
+
+impl RoutesRouteTargetSplit {
+	/// Splits requests between two sets of actors, e.g. for canary rollouts.
+	pub fn new(
+		selector_tags: ::std::collections::HashMap<String, String>,
+		canary_selector_tags: ::std::collections::HashMap<String, String>,
+		canary_weight: i32,
+	) -> RoutesRouteTargetSplit {
+		RoutesRouteTargetSplit {
+			selector_tags,
+			canary_selector_tags,
+			canary_weight,
+		}
+	}
+}
@@ -22,6 +22,15 @@ pub struct RoutesUpdateRouteBody {
 	pub route_subpaths: bool,
 	#[serde(rename = "target")]
 	pub target: Box<crate::models::RoutesRouteTarget>,
+	/// Request conditions of this route. Keeps the route's existing conditions if unset.
+	#[serde(rename = "request_match", skip_serializing_if = "Option::is_none")]
+	pub request_match: Option<Box<crate::models::RoutesRouteMatch>>,
+	/// Keeps the route's existing load balancing if unset.
+	#[serde(rename = "load_balancing", skip_serializing_if = "Option::is_none")]
+	pub load_balancing: Option<Box<crate::models::RoutesLoadBalancing>>,
+	/// Middleware for requests to this route. Unset fields inherit the environment's policy. Keeps the route's existing middleware if unset.
+	#[serde(rename = "middleware", skip_serializing_if = "Option::is_none")]
+	pub middleware: Option<Box<crate::models::RoutesMiddlewarePolicy>>,
 }
 
 impl RoutesUpdateRouteBody {
@@ -38,6 +47,9 @@ impl RoutesUpdateRouteBody {
 			strip_prefix,
 			route_subpaths,
 			target: Box::new(target),
+			request_match: None,
+			load_balancing: None,
+			middleware: None,
 		}
 	}
 }